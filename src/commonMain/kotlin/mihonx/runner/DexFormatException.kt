@@ -0,0 +1,8 @@
+package mihonx.runner
+
+/**
+ * Thrown by the installs when a dex file of the extension is not one the runner can parse, e.g.
+ * a compact dex ART optimized on a device instead of the dex file shipped in the APK, or a dex
+ * file whose checksum or signature doesn't match its contents.
+ */
+class DexFormatException(message: String) : IllegalArgumentException(message)
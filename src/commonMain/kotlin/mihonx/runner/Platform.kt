@@ -12,7 +12,44 @@ expect object RustBridge {
     fun getDexVersion(): String
 
     fun installExtension(bytes: ByteArray)
+    fun installExtensionFile(path: String)
+    fun installMultiDexExtension(dexes: Array<ByteArray>)
+    fun installExtensions(batch: Array<ByteArray>): String
+    fun setClassFilter(packages: Array<String>)
     fun getName(ctx: ExtensionContext): String
     fun callMethod(method_name: String): String
+    fun callLongMethod(method_name: String): Long
+    fun callDoubleMethod(method_name: String): Double
     fun isUserAgentEqual(): Boolean
+    fun listMethods(): String
+    fun hiddenApiReport(): String
+    fun dependencyGraph(): String
+    fun preloadClosure(className: String?): Int
+    fun unsupportedReport(): String
+    fun missingShims(): String
+    fun exportJson(): String
+    fun kotlinStubs(): String
+    fun callMethodJson(method_name: String, argsJson: String): String
+    fun callMethodGraph(method_name: String, argsJson: String): String
+    fun callMethodWithArgs(method_name: String, signature: String?, args: Array<Any?>): Any?
+    fun fetchPages(method_name: String, startPage: Int, limit: Int): String
+
+    fun checkExtensionUpdates(index: String, installed: String, fingerprint: String?): String
+
+    fun runSelfTest(): String
+
+    fun setLogSink(sink: String, path: String?, maxBytes: Long, maxFiles: Int)
+    fun setSourceDateContext(source: String?, locale: String, timeZone: String)
+    fun setSourceCapability(source: String?, capability: String, granted: Boolean)
+    fun setMaxCallDepth(depth: Int)
+    fun setFixedClock(millis: Long)
+    fun advanceClock(millis: Long)
+    fun getCookies(source: String?, url: String?): String
+    fun clearCookies(source: String?, url: String?): Int
+
+    fun setMaxCacheSize(bytes: Long)
+    fun getCacheUsage(): String
+    fun trimCache(targetBytes: Long): Long
+
+    fun shutdown(timeoutMillis: Long): Boolean
 }
\ No newline at end of file
@@ -0,0 +1,174 @@
+// Extensions are distributed as .apk files, ZIP archives holding classes.dex, classes2.dex and so
+// on next to resources the runner has no use for. Entries are found through the central
+// directory at the end of the archive, whose sizes are also right for entries written with a
+// data descriptor. APKs are far from the 4 GiB ZIP64 needs, so it isn't supported.
+
+use flate2::read::DeflateDecoder;
+use flate2::Crc;
+use std::fmt;
+use std::io::Read;
+
+use crate::parser::bytes::DexBytes;
+use crate::parser::parser::Parser;
+
+const LOCAL_FILE_HEADER: u32 = 0x04034b50;
+const CENTRAL_DIRECTORY_HEADER: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY: u32 = 0x06054b50;
+
+/// End of central directory record without its comment
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApkError {
+    /// The end of central directory record is missing
+    NotAnArchive,
+    /// A record or entry reaches past the end of the archive
+    Truncated,
+    /// The entry `name` is compressed with a method other than stored or deflated
+    UnsupportedCompression { name: String, method: u16 },
+    /// The entry `name` doesn't inflate to its recorded size and checksum
+    CorruptEntry { name: String },
+    /// The archive has no classes.dex
+    NoDexFile,
+}
+
+impl fmt::Display for ApkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApkError::NotAnArchive => write!(f, "not a ZIP archive, the end of central directory record is missing"),
+            ApkError::Truncated => write!(f, "the archive is truncated"),
+            ApkError::UnsupportedCompression { name, method } => {
+                write!(f, "{} is compressed with unsupported method {}", name, method)
+            }
+            ApkError::CorruptEntry { name } => write!(f, "{} is corrupt", name),
+            ApkError::NoDexFile => write!(f, "the archive contains no classes.dex"),
+        }
+    }
+}
+
+impl std::error::Error for ApkError {}
+
+/// Whether `bytes` start like a ZIP archive rather than like a dex file
+pub fn is_archive(bytes: &[u8]) -> bool {
+    bytes.starts_with(&LOCAL_FILE_HEADER.to_le_bytes())
+}
+
+/// Parser for an extension given either as its APK or as a bare dex file
+pub fn extension_parser(bytes: impl Into<DexBytes>, debug_enabled: bool) -> Result<Parser, ApkError> {
+    let bytes = bytes.into();
+    if !is_archive(&bytes) {
+        return Ok(Parser::new(bytes, debug_enabled));
+    }
+    Ok(Parser::new_multi_dex(dex_files(&bytes)?, debug_enabled))
+}
+
+/// Contents of the dex files of the APK `bytes` in the order Android loads them: classes.dex,
+/// then classes2.dex, classes3.dex and on up to the first one missing. Stored dex files are views
+/// into `bytes`, only deflated ones are copied out.
+pub fn dex_files(bytes: &DexBytes) -> Result<Vec<DexBytes>, ApkError> {
+    let entries = central_directory(bytes)?;
+
+    let mut dex_files = Vec::new();
+    for number in 1.. {
+        let name = if number == 1 { "classes.dex".to_string() } else { format!("classes{}.dex", number) };
+        let Some(entry) = entries.iter().find(|entry| entry.name == name) else {
+            break;
+        };
+        dex_files.push(extract(bytes, entry)?);
+    }
+
+    if dex_files.is_empty() {
+        return Err(ApkError::NoDexFile);
+    }
+    Ok(dex_files)
+}
+
+/// An entry as the central directory records it
+struct Entry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: usize,
+    size: usize,
+    /// Offset of the entry's local file header
+    offset: usize,
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Result<u16, ApkError> {
+    let field = bytes.get(offset..offset + 2).ok_or(ApkError::Truncated)?;
+    Ok(u16::from_le_bytes([field[0], field[1]]))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32, ApkError> {
+    let field = bytes.get(offset..offset + 4).ok_or(ApkError::Truncated)?;
+    Ok(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+/// Read the entries of the central directory. The end of central directory record is the last
+/// thing in the archive, only followed by a comment of up to 64 KiB.
+fn central_directory(bytes: &[u8]) -> Result<Vec<Entry>, ApkError> {
+    let last = bytes.len().checked_sub(END_OF_CENTRAL_DIRECTORY_SIZE).ok_or(ApkError::NotAnArchive)?;
+    let end = (last.saturating_sub(u16::MAX as usize)..=last)
+        .rev()
+        .find(|offset| u32_at(bytes, *offset).is_ok_and(|signature| signature == END_OF_CENTRAL_DIRECTORY))
+        .ok_or(ApkError::NotAnArchive)?;
+
+    let count = u16_at(bytes, end + 10)?;
+    let mut cursor = u32_at(bytes, end + 16)? as usize;
+
+    let mut entries = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        if u32_at(bytes, cursor)? != CENTRAL_DIRECTORY_HEADER {
+            return Err(ApkError::Truncated);
+        }
+        let name_length = u16_at(bytes, cursor + 28)? as usize;
+        let extra_length = u16_at(bytes, cursor + 30)? as usize;
+        let comment_length = u16_at(bytes, cursor + 32)? as usize;
+        let name = bytes.get(cursor + 46..cursor + 46 + name_length).ok_or(ApkError::Truncated)?;
+
+        entries.push(Entry {
+            name: String::from_utf8_lossy(name).into_owned(),
+            method: u16_at(bytes, cursor + 10)?,
+            crc: u32_at(bytes, cursor + 16)?,
+            compressed_size: u32_at(bytes, cursor + 20)? as usize,
+            size: u32_at(bytes, cursor + 24)? as usize,
+            offset: u32_at(bytes, cursor + 42)? as usize,
+        });
+        cursor += 46 + name_length + extra_length + comment_length;
+    }
+    Ok(entries)
+}
+
+/// Contents of `entry`, which follow its local file header. The local header's own name and
+/// extra field lengths count, they can differ from the central directory's.
+fn extract(bytes: &DexBytes, entry: &Entry) -> Result<DexBytes, ApkError> {
+    if u32_at(bytes, entry.offset)? != LOCAL_FILE_HEADER {
+        return Err(ApkError::Truncated);
+    }
+    let name_length = u16_at(bytes, entry.offset + 26)? as usize;
+    let extra_length = u16_at(bytes, entry.offset + 28)? as usize;
+    let start = entry.offset + 30 + name_length + extra_length;
+    let compressed = bytes.get(start..start + entry.compressed_size).ok_or(ApkError::Truncated)?;
+
+    let contents = match entry.method {
+        STORED => bytes.slice(start..start + entry.compressed_size),
+        DEFLATED => {
+            let mut contents = Vec::with_capacity(entry.size);
+            DeflateDecoder::new(compressed)
+                .read_to_end(&mut contents)
+                .map_err(|_| ApkError::CorruptEntry { name: entry.name.clone() })?;
+            contents.into()
+        }
+        method => return Err(ApkError::UnsupportedCompression { name: entry.name.clone(), method }),
+    };
+
+    let mut crc = Crc::new();
+    crc.update(&contents);
+    if contents.len() != entry.size || crc.sum() != entry.crc {
+        return Err(ApkError::CorruptEntry { name: entry.name.clone() });
+    }
+    Ok(contents)
+}
@@ -0,0 +1,70 @@
+// Soaks the extension installed in `out/` of the working directory and writes the CSV report
+// to a file or stdout.
+//
+//   soak [--duration <secs>] [--interval <secs>] [--query <text>] [--output <file>]
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use mihon_runner::soak::{self, SoakConfig};
+
+const USAGE: &str = "soak [--duration <secs>] [--interval <secs>] [--query <text>] [--output <file>]";
+
+fn main() -> ExitCode {
+    let mut config = SoakConfig {
+        duration: Duration::from_secs(60 * 60),
+        interval: Duration::from_secs(5),
+        query: "one".to_string(),
+    };
+    let mut output = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let Some(value) = args.next() else {
+            eprintln!("Missing value for {}\n{}", arg, USAGE);
+            return ExitCode::FAILURE;
+        };
+        let seconds = || value.parse().map(Duration::from_secs);
+        match (arg.as_str(), seconds()) {
+            ("--duration", Ok(duration)) => config.duration = duration,
+            ("--interval", Ok(interval)) => config.interval = interval,
+            ("--query", _) => config.query = value,
+            ("--output", _) => output = Some(value),
+            _ => {
+                eprintln!("Invalid argument {} {}\n{}", arg, value, USAGE);
+                return ExitCode::FAILURE;
+            }
+        }
+    }
+
+    let mut report: Box<dyn Write> = match &output {
+        Some(path) => match File::create(path) {
+            Ok(file) => Box::new(BufWriter::new(file)),
+            Err(error) => {
+                eprintln!("Can't create {}: {}", path, error);
+                return ExitCode::FAILURE;
+            }
+        },
+        None => Box::new(io::stdout()),
+    };
+
+    match soak::run(&config, &mut report) {
+        Ok(summary) => {
+            eprintln!(
+                "{} rounds, {} calls, {} failed, at most {} heap objects, slowest call {} ms",
+                summary.rounds,
+                summary.calls,
+                summary.failures,
+                summary.max_heap_objects,
+                summary.max_latency.as_millis(),
+            );
+            if summary.failures == 0 { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+        }
+        Err(error) => {
+            eprintln!("Soak failed: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,259 @@
+// Bookkeeping for everything the runner persists under `out/`, with an optional size budget
+// enforced through least-recently-used eviction.
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use crate::interpreter::native::binary_name;
+use crate::utils::GENERATION_FILE;
+
+pub const CACHE_ROOT: &str = "out";
+/// Directory of the cache directories of the extensions installed in batches, one per source
+pub const EXTENSIONS_DIR: &str = "extensions";
+
+lazy_static! {
+    static ref CACHE: Mutex<CacheManager> = Mutex::new(CacheManager::new(PathBuf::from(CACHE_ROOT)));
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub enum CacheCategory {
+    /// Parsed classes stored as `out/<descriptor>` json files. Loading the extension parses it
+    /// again from its dex files once one of them was evicted.
+    Class,
+    /// Container, data section, string pool, class list, dex files and install generation
+    /// needed to rebuild the parser
+    Metadata,
+    /// Responses stored by the http subsystem under `out/http/`
+    Http,
+    Other,
+}
+
+impl CacheCategory {
+    /// Category of the file at `relative`, a path within the cache root
+    pub fn of(relative: &Path) -> Self {
+        // Files of an extension installed in a batch are laid out like those of the root
+        let nested: PathBuf;
+        let relative = match relative.strip_prefix(EXTENSIONS_DIR) {
+            Ok(in_extensions) => {
+                nested = in_extensions.components().skip(1).collect();
+                nested.as_path()
+            }
+            Err(_) => relative,
+        };
+        let file_name = relative.file_name().and_then(|n| n.to_str()).unwrap_or("");
+
+        if relative.starts_with("http") {
+            CacheCategory::Http
+        } else if file_name.starts_with("extension") || relative == Path::new(GENERATION_FILE) {
+            CacheCategory::Metadata
+        } else if relative.starts_with("L") || relative.starts_with("[") {
+            CacheCategory::Class
+        } else {
+            CacheCategory::Other
+        }
+    }
+
+    /// Metadata can't be rebuilt without reinstalling the extension, so it is never evicted.
+    fn is_evictable(&self) -> bool {
+        *self != CacheCategory::Metadata
+    }
+}
+
+struct CacheEntry {
+    size: u64,
+    category: CacheCategory,
+    last_used: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheUsage {
+    pub total: u64,
+    pub max_size: Option<u64>,
+    pub categories: BTreeMap<CacheCategory, u64>,
+}
+
+pub struct CacheManager {
+    /// Directory of the files, those written elsewhere aren't accounted for
+    root: PathBuf,
+    max_size: Option<u64>,
+    entries: HashMap<PathBuf, CacheEntry>,
+    clock: u64,
+    scanned: bool,
+}
+
+impl CacheManager {
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            max_size: None,
+            entries: HashMap::new(),
+            clock: 0,
+            scanned: false,
+        }
+    }
+
+    /// Picks up files written by earlier runs, oldest modification first.
+    fn scan(&mut self) {
+        if self.scanned {
+            return;
+        }
+        self.scanned = true;
+
+        let mut found: Vec<(PathBuf, u64, SystemTime)> = Vec::new();
+        collect_files(&self.root, &mut found);
+        found.sort_by_key(|(_, _, modified)| *modified);
+
+        for (path, size, _) in found {
+            self.clock += 1;
+            let Some(category) = self.category(&path) else {
+                continue;
+            };
+            self.entries.insert(path, CacheEntry { size, category, last_used: self.clock });
+        }
+    }
+
+    /// Category of the file at `path`, `None` outside of the root
+    fn category(&self, path: &Path) -> Option<CacheCategory> {
+        path.strip_prefix(&self.root).ok().map(CacheCategory::of)
+    }
+
+    pub fn record(&mut self, path: &Path) {
+        let Some(category) = self.category(path) else {
+            return;
+        };
+        self.scan();
+        let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        self.clock += 1;
+        self.entries.insert(path.to_path_buf(), CacheEntry { size, category, last_used: self.clock });
+        self.enforce_budget();
+    }
+
+    pub fn touch(&mut self, path: &Path) {
+        self.scan();
+        self.clock += 1;
+        if let Some(entry) = self.entries.get_mut(path) {
+            entry.last_used = self.clock;
+        }
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.entries.values().map(|e| e.size).sum()
+    }
+
+    pub fn usage(&mut self) -> CacheUsage {
+        self.scan();
+        let mut categories = BTreeMap::new();
+        for entry in self.entries.values() {
+            *categories.entry(entry.category).or_insert(0) += entry.size;
+        }
+
+        CacheUsage {
+            total: self.total_size(),
+            max_size: self.max_size,
+            categories,
+        }
+    }
+
+    /// Forget what is known about the files, they are scanned again on the next use
+    fn forget(&mut self) {
+        self.entries.clear();
+        self.clock = 0;
+        self.scanned = false;
+    }
+
+    pub fn set_max_size(&mut self, max_size: Option<u64>) {
+        self.scan();
+        self.max_size = max_size;
+        self.enforce_budget();
+    }
+
+    fn enforce_budget(&mut self) {
+        if let Some(max_size) = self.max_size {
+            self.trim(max_size);
+        }
+    }
+
+    /// Evicts least recently used files until the cache fits into `target` bytes.
+    /// Returns the amount of bytes freed.
+    pub fn trim(&mut self, target: u64) -> u64 {
+        self.scan();
+        let mut total = self.total_size();
+        if total <= target {
+            return 0;
+        }
+
+        let mut candidates: Vec<(PathBuf, u64, u64)> = self.entries
+            .iter()
+            .filter(|(_, entry)| entry.category.is_evictable())
+            .map(|(path, entry)| (path.clone(), entry.size, entry.last_used))
+            .collect();
+        candidates.sort_by_key(|(_, _, last_used)| *last_used);
+
+        let mut freed = 0;
+        for (path, size, _) in candidates {
+            if total <= target {
+                break;
+            }
+            if fs::remove_file(&path).is_ok() || !path.exists() {
+                self.entries.remove(&path);
+                total -= size;
+                freed += size;
+            }
+        }
+        freed
+    }
+}
+
+fn collect_files(dir: &Path, found: &mut Vec<(PathBuf, u64, SystemTime)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, found);
+        } else if let Ok(metadata) = entry.metadata() {
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            found.push((path, metadata.len(), modified));
+        }
+    }
+}
+
+/// Cache directory under `root` of the extension of `source`, the descriptor of its main class,
+/// when installed in a batch
+pub fn extension_dir(root: &Path, source: &str) -> PathBuf {
+    root.join(EXTENSIONS_DIR).join(binary_name(source))
+}
+
+/// Register a file that was just written into the cache.
+pub fn record(path: &Path) {
+    CACHE.lock().unwrap().record(path);
+}
+
+/// Mark a cached file as recently used.
+pub fn touch(path: &Path) {
+    CACHE.lock().unwrap().touch(path);
+}
+
+pub fn set_max_size(max_size: Option<u64>) {
+    CACHE.lock().unwrap().set_max_size(max_size);
+}
+
+pub fn usage() -> CacheUsage {
+    CACHE.lock().unwrap().usage()
+}
+
+pub fn trim(target: u64) -> u64 {
+    CACHE.lock().unwrap().trim(target)
+}
+
+/// Drop the in-memory bookkeeping, nothing in it is lost as the files themselves are rescanned
+pub fn forget() {
+    CACHE.lock().unwrap().forget();
+}
@@ -0,0 +1,270 @@
+// What an extension uses that the interpreter can't run yet: instructions it doesn't execute,
+// invoke-polymorphic and invoke-custom beyond method handles and lambdas, native methods
+// without a stand-in, and methods of the libraries it doesn't bundle that no shim provides.
+// Hosts check the reports before running an extension, to refuse it or warn, rather than have a
+// fetch fail halfway, and extension authors see which shims their source is missing.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::interpreter::interpreter::{is_implemented, provides_method, LAMBDA_METAFACTORY, METHOD_HANDLE_INVOKERS};
+use crate::interpreter::native::{native_methods, platform_method};
+use crate::model::{resolve_reference, Reference};
+use crate::parser::parser::Parser;
+use crate::types::{method_key, AccessFlags, DexClass, DexMethod, Instruction};
+
+/// Something the interpreter doesn't support
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum Unsupported {
+    /// An instruction `execute` doesn't run, by its name, e.g. `NegInt`. Running it
+    /// throws a VerifyError.
+    Opcode { name: String },
+    /// invoke-polymorphic of something other than MethodHandle.invoke and invokeExact, e.g. a
+    /// VarHandle accessor, as `Lcls;->name`
+    PolymorphicInvoke { method: String },
+    /// invoke-custom bootstrapped by something other than LambdaMetafactory, as `Lcls;->name`
+    CallSiteBootstrap { method: String },
+    /// A native method of the extension without a stand-in, calling it returns nothing
+    NativeMethod,
+    /// Code the parser can't decode, it fails with a VerifyError once it runs
+    UndecodableCode { error: String },
+}
+
+impl Unsupported {
+    /// What the uses of the same thing have in common, e.g. `opcode NegInt`
+    pub fn label(&self) -> String {
+        match self {
+            Unsupported::Opcode { name } => format!("opcode {}", name),
+            Unsupported::PolymorphicInvoke { method } => format!("invoke-polymorphic {}", method),
+            Unsupported::CallSiteBootstrap { method } => format!("invoke-custom {}", method),
+            Unsupported::NativeMethod => "native method".to_string(),
+            Unsupported::UndecodableCode { .. } => "undecodable code".to_string(),
+        }
+    }
+}
+
+/// Where the extension uses something unsupported
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsupportedUse {
+    pub class_name: String,
+    /// Key of the method the use is in, or of the native method
+    pub method: String,
+    /// Position of the instruction, `None` for whole methods
+    pub pc: Option<usize>,
+    pub feature: Unsupported,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UnsupportedReport {
+    /// Every use, in class, method and pc order
+    pub uses: Vec<UnsupportedUse>,
+    /// Number of uses by label, see `Unsupported::label`
+    pub counts: BTreeMap<String, usize>,
+}
+
+impl UnsupportedReport {
+    /// Scan every method of the parsed extension. Code is decoded as needed but not kept.
+    pub fn scan(parser: &Parser) -> Self {
+        let mut report = UnsupportedReport::default();
+        for class in &parser.classes {
+            let mut methods: Vec<(&String, &DexMethod)> = class.methods.iter().collect();
+            methods.sort_by_key(|(key, _)| *key);
+
+            for (key, method) in methods {
+                if method.access_flags.contains(AccessFlags::NATIVE) && !has_stand_in(class, key) {
+                    report.add(class, key, None, Unsupported::NativeMethod);
+                }
+                let instructions = match parser.instructions(class, method) {
+                    Ok(instructions) => instructions,
+                    Err(error) => {
+                        report.add(class, key, None, Unsupported::UndecodableCode { error: error.to_string() });
+                        continue;
+                    }
+                };
+                for (pc, instruction) in instructions.iter().enumerate() {
+                    if let Some(feature) = unsupported(parser, class.dex, instruction) {
+                        report.add(class, key, Some(pc), feature);
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    fn add(&mut self, class: &DexClass, method: &str, pc: Option<usize>, feature: Unsupported) {
+        *self.counts.entry(feature.label()).or_default() += 1;
+        self.uses.push(UnsupportedUse { class_name: class.name.clone(), method: method.to_string(), pc, feature });
+    }
+}
+
+/// Whether the interpreter provides native method `key` of `class` itself
+fn has_stand_in(class: &DexClass, key: &str) -> bool {
+    native_methods(&class.name).contains_key(key) || platform_method(&class.name, key).is_some()
+}
+
+/// What `instruction` of dex file `dex` uses that the interpreter doesn't support
+fn unsupported(parser: &Parser, dex: usize, instruction: &Instruction) -> Option<Unsupported> {
+    let expanded = instruction.expand_range();
+    let instruction = expanded.as_ref().unwrap_or(instruction);
+    if !is_implemented(instruction) {
+        return Some(Unsupported::Opcode { name: opcode_name(instruction) });
+    }
+
+    let tables = parser.tables(dex)?;
+    match instruction {
+        Instruction::InvokePolymorphic { method_idx, .. } => {
+            let (class_name, name) = tables.resolve_method(*method_idx)?;
+            (!METHOD_HANDLE_INVOKERS.contains(&&*name))
+                .then(|| Unsupported::PolymorphicInvoke { method: format!("{}->{}", class_name, name) })
+        }
+        Instruction::InvokeCustom { call_site_idx, .. } => {
+            let call_site = tables.container.call_site(*call_site_idx as usize)?;
+            let bootstrap = tables.container.method_handle(call_site.bootstrap_method_handle as usize)?;
+            let (class_name, name) = tables.resolve_method(bootstrap.method_id()?)?;
+            (&*class_name != LAMBDA_METAFACTORY)
+                .then(|| Unsupported::CallSiteBootstrap { method: format!("{}->{}", class_name, name) })
+        }
+        _ => None,
+    }
+}
+
+/// Name of the variant of `instruction`, e.g. `Goto` for `Goto { signed_branch_off: 4 }`
+fn opcode_name(instruction: &Instruction) -> String {
+    let debug = format!("{:?}", instruction);
+    debug.split([' ', '{', '(']).next().unwrap_or_default().to_string()
+}
+
+/// A method of a class outside the extension that no shim provides
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingMethod {
+    /// The class outside the extension the method is looked up on, the superclass of the class
+    /// the reference names when that one is the extension's
+    pub class_name: String,
+    pub method: String,
+    /// Whether it is called through invoke-interface. Those run on whatever class the receiver
+    /// has, which may be a platform class providing the method after all.
+    pub interface: bool,
+    /// Classes of the extension calling it
+    pub callers: BTreeSet<String>,
+}
+
+/// Methods the extension calls on classes it doesn't define, such as okhttp3 or org.jsoup ones,
+/// that the interpreter has no shim for
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MissingShimReport {
+    /// In class and method order
+    pub methods: Vec<MissingMethod>,
+    /// Classes with at least one missing method
+    pub classes: BTreeSet<String>,
+}
+
+impl MissingShimReport {
+    /// Check every method reference of the parsed extension. Methods whose code can't be decoded
+    /// are skipped, `UnsupportedReport` lists them.
+    pub fn scan(parser: &Parser) -> Self {
+        let mut missing: BTreeMap<(String, String), MissingMethod> = BTreeMap::new();
+        for class in &parser.classes {
+            for method in class.methods.values() {
+                let Ok(instructions) = parser.instructions(class, method) else {
+                    continue;
+                };
+                for instruction in instructions.iter() {
+                    let Some(Reference::Method { class_name, name, descriptor }) = resolve_reference(parser, class.dex, instruction) else {
+                        continue;
+                    };
+                    let key = method_key(&name, &descriptor);
+                    let Some(owner) = external_owner(parser, &class_name, &key) else {
+                        continue;
+                    };
+                    if provides_method(&owner, &key) {
+                        continue;
+                    }
+                    let interface = matches!(
+                        instruction,
+                        Instruction::InvokeInterface { .. } | Instruction::InvokeInterfaceRange { .. }
+                    );
+                    let entry = missing.entry((owner.clone(), key.clone())).or_insert_with(|| MissingMethod {
+                        class_name: owner,
+                        method: key,
+                        interface,
+                        callers: BTreeSet::new(),
+                    });
+                    entry.interface &= interface;
+                    entry.callers.insert(class.name.clone());
+                }
+            }
+        }
+
+        let methods: Vec<MissingMethod> = missing.into_values().collect();
+        let classes = methods.iter().map(|method| method.class_name.clone()).collect();
+        MissingShimReport { methods, classes }
+    }
+}
+
+/// The class outside the extension method `key` of `class_name` is looked up on, `None` if the
+/// extension defines the method itself or in a superclass
+fn external_owner(parser: &Parser, class_name: &str, key: &str) -> Option<String> {
+    let mut current = class_name.to_string();
+    loop {
+        let Some(class) = parser.classes.iter().find(|class| class.name == current) else {
+            return Some(current);
+        };
+        if class.methods.contains_key(key) {
+            return None;
+        }
+        current = class.super_class.clone()?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex, self_test_cases, SelfTestCase};
+    use crate::types::DexValue;
+
+    #[test]
+    fn reports_nothing_of_the_micro_dex() {
+        let parser = parse_micro_dex(&self_test_cases());
+        assert!(UnsupportedReport::scan(&parser).uses.is_empty());
+        // It only calls into itself
+        assert!(MissingShimReport::scan(&parser).methods.is_empty());
+    }
+
+    /// A const-method-type, which the interpreter doesn't run yet, is reported with its method and pc
+    #[test]
+    fn reports_unsupported_opcodes() {
+        let case = SelfTestCase {
+            name: "constMethodType",
+            return_type: "V",
+            registers: 1,
+            // const-method-type v0, proto@0; return-void
+            insns: |_| vec![0xFF, 0x00, 0x00, 0x00, 0x0E, 0x00],
+            expected: || DexValue::Void,
+        };
+        let report = UnsupportedReport::scan(&parse_micro_dex(&[case]));
+        let opcode = Unsupported::Opcode { name: "ConstMethodType".to_string() };
+        let found: Vec<_> = report.uses.iter().map(|found| (found.method.as_str(), found.pc, &found.feature)).collect();
+        assert_eq!(found, [("constMethodType:()V", Some(0), &opcode)]);
+        assert_eq!(report.counts.get("opcode ConstMethodType"), Some(&1));
+    }
+
+    /// Methods with shims, directly, through a builtin superclass or by name, are provided
+    #[test]
+    fn provides_shimmed_methods() {
+        let methods = [
+            ("Ljava/util/ArrayList;", "size:()I", true),
+            ("Ljava/lang/String;", "length:()I", true),
+            ("Lkotlin/jvm/internal/Intrinsics;", "checkNotNullParameter:(Ljava/lang/Object;Ljava/lang/String;)V", true),
+            ("Ljava/lang/IllegalStateException;", "getMessage:()Ljava/lang/String;", true),
+            ("Lokhttp3/Dispatcher;", "cancelAll:()V", false),
+            ("Ljava/lang/String;", "codePointAt:(I)I", false),
+        ];
+        for (class_name, key, provided) in methods {
+            assert_eq!(provides_method(class_name, key), provided, "{}->{}", class_name, key);
+        }
+    }
+}
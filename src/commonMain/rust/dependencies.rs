@@ -0,0 +1,147 @@
+// Which classes of an extension depend on which others: their superclass, interfaces and field
+// types, and the types, fields and methods their code references. Hosts and tools see what an
+// extension pulls in, and the interpreter can decode the code a class reaches before it runs.
+
+use serde::Serialize;
+use std::collections::{BTreeSet, HashSet, VecDeque};
+
+use crate::model::{resolve_reference, Reference};
+use crate::parser::parser::Parser;
+use crate::types::{DexClass, DexMethod, Instruction};
+
+/// What one class references, classes by descriptor and members as `Lcls;->name:Ty` and
+/// `Lcls;->name(args)ret`. References to the class itself are left out.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassDependencies {
+    pub class_name: String,
+    pub types: BTreeSet<String>,
+    pub fields: BTreeSet<String>,
+    pub methods: BTreeSet<String>,
+}
+
+impl ClassDependencies {
+    /// Dependencies of `class` from its declaration and `code`, the instructions of its methods
+    fn collect<'a>(parser: &Parser, class: &DexClass, code: impl IntoIterator<Item = &'a [Instruction]>) -> Self {
+        let mut dependencies = ClassDependencies { class_name: class.name.clone(), ..Default::default() };
+        let declared = class.super_class.iter()
+            .chain(&class.interfaces)
+            .chain(class.static_fields.values().chain(class.instance_fields.values()).map(|field| &field.ty));
+        for ty in declared {
+            dependencies.add_type(ty);
+        }
+
+        for instructions in code {
+            for instruction in instructions {
+                match resolve_reference(parser, class.dex, instruction) {
+                    Some(Reference::Type(ty)) => dependencies.add_type(&ty),
+                    Some(Reference::Field { class_name, name, ty }) => {
+                        dependencies.add_type(&class_name);
+                        dependencies.add_type(&ty);
+                        if class_name != class.name {
+                            dependencies.fields.insert(format!("{}->{}:{}", class_name, name, ty));
+                        }
+                    }
+                    Some(Reference::Method { class_name, name, descriptor }) => {
+                        dependencies.add_type(&class_name);
+                        if class_name != class.name {
+                            dependencies.methods.insert(format!("{}->{}{}", class_name, name, descriptor));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        dependencies
+    }
+
+    /// Add the class of `ty`, the element class of an array, nothing for primitives
+    fn add_type(&mut self, ty: &str) {
+        let ty = ty.trim_start_matches('[');
+        if ty.starts_with('L') && ty.ends_with(';') && ty != self.class_name {
+            self.types.insert(ty.to_string());
+        }
+    }
+}
+
+/// Dependencies of every parsed class, in dex order
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    pub classes: Vec<ClassDependencies>,
+    /// Referenced classes the extension doesn't define, which the runner has to provide
+    pub external_types: BTreeSet<String>,
+}
+
+impl DependencyGraph {
+    /// Graph of the parsed extension. Methods whose code can't be decoded only contribute their
+    /// class's declaration.
+    pub fn build(parser: &Parser) -> Self {
+        let classes: Vec<ClassDependencies> = parser.classes.iter()
+            .map(|class| {
+                let code: Vec<_> = class.methods.values()
+                    .filter_map(|method| parser.instructions(class, method).ok())
+                    .collect();
+                ClassDependencies::collect(parser, class, code.iter().map(|instructions| instructions.as_ref()))
+            })
+            .collect();
+
+        let defined: HashSet<&str> = parser.classes.iter().map(|class| class.name.as_str()).collect();
+        let external_types = classes.iter()
+            .flat_map(|dependencies| &dependencies.types)
+            .filter(|ty| !defined.contains(ty.as_str()))
+            .cloned()
+            .collect();
+        DependencyGraph { classes, external_types }
+    }
+}
+
+/// Decode the code of `class_name` and of every class of the extension it reaches, so none of
+/// it is decoded while running. Only the reached code is decoded, unlike building the whole
+/// graph. Returns the classes, nearest first. Code that can't be decoded is left pending, it
+/// fails with a VerifyError once it runs.
+pub fn preload_closure(parser: &mut Parser, class_name: &str) -> Vec<String> {
+    let mut closure = Vec::new();
+    let mut seen = HashSet::from([class_name.to_string()]);
+    let mut queue = VecDeque::from([class_name.to_string()]);
+    while let Some(name) = queue.pop_front() {
+        let Some(class) = parser.classes.iter().find(|class| class.name == name).cloned() else {
+            continue;
+        };
+        let methods: Vec<DexMethod> = class.methods.keys()
+            .filter_map(|key| parser.decoded_method(&class, key).ok().flatten())
+            .collect();
+        let dependencies = ClassDependencies::collect(parser, &class, methods.iter().map(|method| method.instructions.as_slice()));
+        for ty in dependencies.types {
+            if seen.insert(ty.clone()) {
+                queue.push_back(ty);
+            }
+        }
+        closure.push(name);
+    }
+    closure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex, self_test_cases, OBJECT_CLASS, SELF_TEST_CLASS};
+
+    /// The micro dex's only outside reference is its superclass
+    #[test]
+    fn builds_the_graph_of_the_micro_dex() {
+        let graph = DependencyGraph::build(&parse_micro_dex(&self_test_cases()));
+        assert_eq!(graph.external_types.iter().collect::<Vec<_>>(), [OBJECT_CLASS]);
+        let classes: Vec<_> = graph.classes.iter().map(|dependencies| dependencies.class_name.as_str()).collect();
+        assert_eq!(classes, [SELF_TEST_CLASS]);
+        assert_eq!(graph.classes[0].types.iter().collect::<Vec<_>>(), [OBJECT_CLASS]);
+    }
+
+    /// Preloading the closure of the micro dex class leaves no code pending
+    #[test]
+    fn preloads_the_closure() {
+        let mut parser = parse_micro_dex(&self_test_cases());
+        assert_eq!(preload_closure(&mut parser, SELF_TEST_CLASS), [SELF_TEST_CLASS]);
+        assert!(parser.classes.iter().flat_map(|class| class.methods.values()).all(|method| !method.code_pending()));
+    }
+}
@@ -0,0 +1,192 @@
+// Installing several extensions in one go, e.g. after a repository sync. Parsing is the
+// expensive part and runs on a pool of at most one worker per core, which share the install
+// policy, the class filter and a pool of the strings the extensions have in common. Each
+// extension is persisted into a cache directory of its own, named after its source, afterwards
+// in batch order.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::path::Path;
+use std::panic;
+use std::sync::Mutex;
+use std::thread;
+
+use crate::apk;
+use crate::cache;
+use crate::interpreter::native::binary_name;
+use crate::lifecycle;
+use crate::parser::filter::{class_filter, ClassFilter};
+use crate::parser::parser::Parser;
+use crate::parser::strings::StringPool;
+use crate::policy::{self, InstallPolicy, PolicyViolation};
+use crate::utils::panic_message;
+
+#[derive(Debug, Serialize)]
+pub struct InstallResult {
+    /// Position of the extension in the batch
+    pub index: usize,
+    pub success: bool,
+    /// Name of the extension's main class, which selects it with `rustUseInstalledExtension`
+    pub source: Option<String>,
+    pub classes: usize,
+    /// Problems the parser recovered from, e.g. malformed strings
+    pub warnings: Vec<String>,
+    pub error: Option<String>,
+    /// Why the install policy rejected the extension
+    pub violations: Vec<PolicyViolation>,
+}
+
+impl InstallResult {
+    fn failed(index: usize, source: Option<String>, error: String) -> Self {
+        InstallResult { index, success: false, source, classes: 0, warnings: Vec::new(), error: Some(error), violations: Vec::new() }
+    }
+}
+
+/// An extension of a batch parsed, with the descriptor of its main class, or why it couldn't be
+type Parsed = Result<(String, Parser), String>;
+
+/// What every install of a batch uses, taken once for the whole batch
+struct BatchSetup {
+    policy: InstallPolicy,
+    class_filter: ClassFilter,
+    strings: StringPool,
+}
+
+/// Install every extension of `batch`, each an APK or a bare dex file, into the cache directory
+/// of its source under `root`, see `cache::extension_dir`. The extension the interpreter runs stays the same
+/// until the host picks one with `rustUseInstalledExtension`. Should the batch hold a source
+/// twice, its last extension is the one installed.
+pub fn install_extensions(batch: Vec<Vec<u8>>, root: &Path) -> Vec<InstallResult> {
+    let setup = BatchSetup { policy: policy::install_policy(), class_filter: class_filter(), strings: StringPool::default() };
+    let parsed = parse_batch(batch, &setup);
+
+    // The last extension of a source wins, like installing them one after the other would
+    let mut last_of_source = HashMap::new();
+    for (index, parsed) in parsed.iter().enumerate() {
+        if let Ok(source) = parsed.as_ref().map(|(source, _)| source) {
+            last_of_source.insert(source.clone(), index);
+        }
+    }
+
+    parsed.into_iter()
+        .enumerate()
+        .map(|(index, parsed)| {
+            let (source, mut parser) = match parsed {
+                Ok(parsed) => parsed,
+                Err(error) => return InstallResult::failed(index, None, error),
+            };
+            let name = binary_name(&source);
+            if last_of_source[&source] != index {
+                let error = format!("extension {} of the batch is {} as well", last_of_source[&source], name);
+                return InstallResult::failed(index, Some(name), error);
+            }
+
+            let violations = policy::check_install_with(&setup.policy, &parser);
+            if !violations.is_empty() {
+                return InstallResult {
+                    error: Some(policy::rejection_report(&violations)),
+                    violations,
+                    ..InstallResult::failed(index, Some(name), String::new())
+                };
+            }
+
+            parser.cache_dir = cache::extension_dir(root, &source);
+            if let Err(error) = parser.save() {
+                return InstallResult::failed(index, Some(name), error.to_string());
+            }
+            InstallResult {
+                index,
+                success: true,
+                source: Some(name),
+                classes: parser.classes.len(),
+                warnings: parser.errors.iter().map(|error| error.to_string()).collect(),
+                error: None,
+                violations: Vec::new(),
+            }
+        })
+        .collect()
+}
+
+/// Parse the extensions of `batch` on a pool of workers, in batch order with the descriptor of
+/// their main class
+fn parse_batch(batch: Vec<Vec<u8>>, setup: &BatchSetup) -> Vec<Parsed> {
+    let count = batch.len();
+    let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get).min(count);
+    let queue = Mutex::new(batch.into_iter().enumerate());
+
+    let mut parsed: Vec<(usize, Parsed)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| scope.spawn(|| {
+                let mut parsed = Vec::new();
+                loop {
+                    let Some((index, bytes)) = queue.lock().unwrap().next() else {
+                        break;
+                    };
+                    let Some(_worker) = lifecycle::register_worker() else {
+                        parsed.push((index, Err("The runner is shutting down".to_string())));
+                        continue;
+                    };
+                    parsed.push((index, parse_extension(bytes, setup)));
+                }
+                parsed
+            }))
+            .collect();
+
+        handles.into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    });
+    parsed.sort_by_key(|(index, _)| *index);
+    parsed.into_iter().map(|(_, parsed)| parsed).collect()
+}
+
+fn parse_extension(bytes: Vec<u8>, setup: &BatchSetup) -> Parsed {
+    panic::catch_unwind(panic::AssertUnwindSafe(move || {
+        let mut parser = apk::extension_parser(bytes, false).map_err(|error| error.to_string())?;
+        parser.persist = false;
+        parser.verify_integrity = true;
+        parser.class_filter = setup.class_filter.clone();
+        parser.string_pool = Some(setup.strings.clone());
+        parser.parse().map_err(|error| error.to_string())?;
+        let main_class = parser.main_class().ok_or("The extension has no class extending a Source")?;
+        Ok((parser.classes[main_class].name.clone(), parser))
+    }))
+    .map_err(panic_message)?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::endian::Endian;
+    use crate::selftest::{build_micro_dex, build_micro_dex_extending, self_test_cases, TestCacheDir, SELF_TEST_CLASS};
+
+    const SOURCE_CLASS: &str = "Leu/kanade/tachiyomi/source/online/HttpSource;";
+
+    /// The micro dex has no Source, so it can't be told apart from other extensions and isn't
+    /// installed, while the batch goes on with the others
+    #[test]
+    fn reports_each_extension_of_the_batch() {
+        let root = TestCacheDir::new("install-batch");
+        let dex = build_micro_dex(&self_test_cases(), Endian::Little);
+        let results = install_extensions(vec![dex, b"not a dex".to_vec()], &root.0);
+        let indices: Vec<_> = results.iter().map(|result| result.index).collect();
+        assert_eq!(indices, [0, 1]);
+        assert!(results.iter().all(|result| !result.success && result.error.is_some()));
+        assert_eq!(results[0].error.as_deref(), Some("The extension has no class extending a Source"));
+    }
+
+    #[test]
+    fn installs_the_last_extension_of_a_source() {
+        let root = TestCacheDir::new("install-source");
+        let dex = build_micro_dex_extending(&self_test_cases(), Endian::Little, SOURCE_CLASS);
+        let results = install_extensions(vec![dex.clone(), dex], &root.0);
+        assert!(!results[0].success);
+        assert!(results[1].success, "{:?}", results[1].error);
+        assert_eq!(results[1].source.as_deref(), Some(binary_name(SELF_TEST_CLASS).as_str()));
+
+        let installed = Parser::initialize_from_dir(&cache::extension_dir(&root.0, SELF_TEST_CLASS)).unwrap();
+        assert_eq!(installed.classes.len(), results[1].classes);
+        assert_eq!(installed.classes[0].name, SELF_TEST_CLASS);
+    }
+}
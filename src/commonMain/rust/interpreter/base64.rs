@@ -0,0 +1,225 @@
+// android.util.Base64 and java.util.Base64. The two differ in more than their API: Android's
+// default flags wrap lines at 76 chars and end with a newline, and its decoder skips anything
+// outside the alphabet, while the java.util basic decoder rejects such chars and only the MIME
+// one skips them. Extensions decode image URLs and payloads written against either, so both
+// behaviours are kept.
+
+use crate::interpreter::exception::ILLEGAL_ARGUMENT_EXCEPTION;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{bytes_of, new_byte_array, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const ANDROID_BASE64: &str = "Landroid/util/Base64;";
+pub const BASE64: &str = "Ljava/util/Base64;";
+pub const ENCODER: &str = "Ljava/util/Base64$Encoder;";
+pub const DECODER: &str = "Ljava/util/Base64$Decoder;";
+
+// android.util.Base64 flags
+const NO_PADDING: i32 = 1;
+const NO_WRAP: i32 = 2;
+const CRLF: i32 = 4;
+const URL_SAFE: i32 = 8;
+
+const URL_SAFE_FIELD: &str = "urlSafe";
+const PADDING_FIELD: &str = "padding";
+const MIME_FIELD: &str = "mime";
+
+const STANDARD_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL_SAFE_ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+const LINE_LENGTH: usize = 76;
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (ANDROID_BASE64, "encode:([BI)[B" | "encode:([BIII)[B") => android_encode,
+        (ANDROID_BASE64, "encodeToString:([BI)Ljava/lang/String;" | "encodeToString:([BIII)Ljava/lang/String;") => android_encode_to_string,
+        (ANDROID_BASE64, "decode:(Ljava/lang/String;I)[B" | "decode:([BI)[B" | "decode:([BIII)[B") => android_decode,
+
+        (BASE64, "getEncoder:()Ljava/util/Base64$Encoder;") => |interpreter, _, _| coder(interpreter, ENCODER, false, false),
+        (BASE64, "getUrlEncoder:()Ljava/util/Base64$Encoder;") => |interpreter, _, _| coder(interpreter, ENCODER, true, false),
+        (BASE64, "getMimeEncoder:()Ljava/util/Base64$Encoder;") => |interpreter, _, _| coder(interpreter, ENCODER, false, true),
+        (BASE64, "getDecoder:()Ljava/util/Base64$Decoder;") => |interpreter, _, _| coder(interpreter, DECODER, false, false),
+        (BASE64, "getUrlDecoder:()Ljava/util/Base64$Decoder;") => |interpreter, _, _| coder(interpreter, DECODER, true, false),
+        (BASE64, "getMimeDecoder:()Ljava/util/Base64$Decoder;") => |interpreter, _, _| coder(interpreter, DECODER, false, true),
+        (ENCODER, "encode:([B)[B") => encoder_encode,
+        (ENCODER, "encodeToString:([B)Ljava/lang/String;") => encoder_encode_to_string,
+        (ENCODER, "withoutPadding:()Ljava/util/Base64$Encoder;") => encoder_without_padding,
+        (DECODER, "decode:(Ljava/lang/String;)[B" | "decode:([B)[B") => decoder_decode,
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn alphabet(url_safe: bool) -> &'static [u8; 64] {
+    if url_safe { URL_SAFE_ALPHABET } else { STANDARD_ALPHABET }
+}
+
+fn encode(data: &[u8], alphabet: &[u8; 64], padding: bool) -> String {
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (index, byte)| bits | (*byte as u32) << (16 - 8 * index));
+        // A chunk of n bytes makes n + 1 chars
+        for index in 0..=chunk.len() {
+            encoded.push(alphabet[(bits >> (18 - 6 * index) & 0x3f) as usize] as char);
+        }
+        if padding {
+            encoded.extend(std::iter::repeat_n('=', 3 - chunk.len()));
+        }
+    }
+    encoded
+}
+
+/// Break `encoded` into lines of 76 chars
+fn wrap(encoded: &str, separator: &str, trailing: bool) -> String {
+    let lines: Vec<&str> = encoded.as_bytes().chunks(LINE_LENGTH)
+        .map(|line| std::str::from_utf8(line).unwrap_or_default())
+        .collect();
+    let mut wrapped = lines.join(separator);
+    if trailing && !wrapped.is_empty() {
+        wrapped.push_str(separator);
+    }
+    wrapped
+}
+
+/// Decode up to the first `=`. Chars outside the alphabet are skipped if `lenient`, otherwise
+/// they're an error like a final char that makes no whole byte.
+fn decode(text: &[u8], alphabet: &[u8; 64], lenient: bool) -> Result<Vec<u8>, String> {
+    let mut decoded = Vec::with_capacity(text.len() / 4 * 3);
+    let mut bits = 0u32;
+    let mut count = 0;
+    for &c in text {
+        if c == b'=' {
+            break;
+        }
+        let Some(value) = alphabet.iter().position(|letter| *letter == c) else {
+            if lenient {
+                continue;
+            }
+            return Err(format!("Illegal base64 character {:x}", c));
+        };
+        bits = bits << 6 | value as u32;
+        count += 1;
+        if count == 4 {
+            decoded.extend_from_slice(&bits.to_be_bytes()[1..]);
+            bits = 0;
+            count = 0;
+        }
+    }
+
+    match count {
+        0 => {}
+        2 => decoded.push((bits >> 4) as u8),
+        3 => decoded.extend_from_slice(&((bits >> 2) as u16).to_be_bytes()),
+        _ => return Err("Last unit does not have enough valid bits".to_string()),
+    }
+    Ok(decoded)
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> i32 {
+    args.get(index).and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+fn object_arg(args: &[DexValue]) -> Option<ObjectId> {
+    match args.first() {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+/// The input of an encode or decode call, a String or the bytes of a byte[] and its range
+fn input(interpreter: &Interpreter, args: &[DexValue]) -> Vec<u8> {
+    if let Some(DexValue::String(text)) = args.first() {
+        return text.as_bytes().to_vec();
+    }
+    let bytes = bytes_of(interpreter, object_arg(args));
+    // The (input, offset, len, flags) overloads
+    if args.len() == 4 {
+        let offset = int_arg(args, 1).clamp(0, bytes.len() as i32) as usize;
+        let length = int_arg(args, 2).clamp(0, (bytes.len() - offset) as i32) as usize;
+        return bytes[offset..offset + length].to_vec();
+    }
+    bytes
+}
+
+fn android_encoded(interpreter: &Interpreter, args: &[DexValue]) -> String {
+    let flags = int_arg(args, args.len().saturating_sub(1));
+    let encoded = encode(&input(interpreter, args), alphabet(flags & URL_SAFE != 0), flags & NO_PADDING == 0);
+    if flags & NO_WRAP != 0 {
+        return encoded;
+    }
+    wrap(&encoded, if flags & CRLF != 0 { "\r\n" } else { "\n" }, true)
+}
+
+fn android_encode(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let encoded = android_encoded(interpreter, &args);
+    new_byte_array(interpreter, encoded.into_bytes())
+}
+
+fn android_encode_to_string(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String(android_encoded(interpreter, &args))
+}
+
+fn android_decode(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let flags = int_arg(&args, args.len().saturating_sub(1));
+    match decode(&input(interpreter, &args), alphabet(flags & URL_SAFE != 0), true) {
+        Ok(data) => new_byte_array(interpreter, data),
+        Err(_) => {
+            interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "bad base-64");
+            DexValue::Void
+        }
+    }
+}
+
+/// A java.util.Base64 encoder or decoder object
+fn coder(interpreter: &mut Interpreter, class_name: &str, url_safe: bool, mime: bool) -> DexValue {
+    let id = interpreter.alloc_object(class_name);
+    let coder = interpreter.heap.get_mut(&id).unwrap();
+    coder.fields.insert(URL_SAFE_FIELD.to_string(), DexValue::Boolean(url_safe));
+    coder.fields.insert(MIME_FIELD.to_string(), DexValue::Boolean(mime));
+    coder.fields.insert(PADDING_FIELD.to_string(), DexValue::Boolean(true));
+    DexValue::Object(id)
+}
+
+fn flag(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> bool {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|coder| coder.fields.get(name))
+        .is_some_and(|value| *value == DexValue::Boolean(true))
+}
+
+/// MIME lines are separated by CRLF, without one at the end
+fn encoded(interpreter: &Interpreter, this: Option<ObjectId>, args: &[DexValue]) -> String {
+    let alphabet = alphabet(flag(interpreter, this, URL_SAFE_FIELD));
+    let encoded = encode(&input(interpreter, args), alphabet, flag(interpreter, this, PADDING_FIELD));
+    if flag(interpreter, this, MIME_FIELD) { wrap(&encoded, "\r\n", false) } else { encoded }
+}
+
+fn encoder_encode(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let encoded = encoded(interpreter, this, &args);
+    new_byte_array(interpreter, encoded.into_bytes())
+}
+
+fn encoder_encode_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String(encoded(interpreter, this, &args))
+}
+
+fn encoder_without_padding(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let url_safe = flag(interpreter, this, URL_SAFE_FIELD);
+    let mime = flag(interpreter, this, MIME_FIELD);
+    let encoder = coder(interpreter, ENCODER, url_safe, mime);
+    if let DexValue::Object(id) = encoder {
+        interpreter.heap.get_mut(&id).unwrap().fields.insert(PADDING_FIELD.to_string(), DexValue::Boolean(false));
+    }
+    encoder
+}
+
+fn decoder_decode(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let alphabet = alphabet(flag(interpreter, this, URL_SAFE_FIELD));
+    let lenient = flag(interpreter, this, MIME_FIELD);
+    match decode(&input(interpreter, &args), alphabet, lenient) {
+        Ok(data) => new_byte_array(interpreter, data),
+        Err(message) => {
+            interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &message);
+            DexValue::Void
+        }
+    }
+}
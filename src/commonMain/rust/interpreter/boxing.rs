@@ -0,0 +1,424 @@
+// Boxed primitives, which Kotlin emits for every nullable or generic Int, Long, Boolean, Double
+// and Char. A box is a heap object holding its primitive in a field, a register can't hold the
+// primitive itself because `const/4 vX, #0` is also how null is loaded. The unboxing methods
+// take a bare primitive as well, for values the host or the codec left unboxed.
+
+use std::collections::HashMap;
+
+use crate::interpreter::exception::NUMBER_FORMAT_EXCEPTION;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{parameters, PlatformMethod};
+use crate::interpreter::strings::double_to_string;
+use crate::types::{DexValue, Object, ObjectId};
+
+pub const INTEGER: &str = "Ljava/lang/Integer;";
+pub const LONG: &str = "Ljava/lang/Long;";
+pub const BOOLEAN: &str = "Ljava/lang/Boolean;";
+pub const DOUBLE: &str = "Ljava/lang/Double;";
+pub const CHARACTER: &str = "Ljava/lang/Character;";
+/// The boxing helpers suspend functions use for primitives they return as Object
+pub const COROUTINE_BOXING: &str = "Lkotlin/coroutines/jvm/internal/Boxing;";
+
+const VALUE_FIELD: &str = "value";
+
+pub fn is_box(class_name: &str) -> bool {
+    matches!(class_name, INTEGER | LONG | BOOLEAN | DOUBLE | CHARACTER)
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (INTEGER, "valueOf:(I)Ljava/lang/Integer;") => integer_value_of,
+        (INTEGER, "valueOf:(Ljava/lang/String;)Ljava/lang/Integer;") => integer_value_of_string,
+        (INTEGER, "parseInt:(Ljava/lang/String;)I" | "parseInt:(Ljava/lang/String;I)I") => integer_parse,
+        (INTEGER, "toString:(I)Ljava/lang/String;") => integer_to_string,
+        (INTEGER, "toHexString:(I)Ljava/lang/String;") => integer_to_hex_string,
+        (INTEGER, "compare:(II)I") => integer_compare,
+
+        (LONG, "valueOf:(J)Ljava/lang/Long;") => long_value_of,
+        (LONG, "valueOf:(Ljava/lang/String;)Ljava/lang/Long;") => long_value_of_string,
+        (LONG, "parseLong:(Ljava/lang/String;)J" | "parseLong:(Ljava/lang/String;I)J") => long_parse,
+        (LONG, "toString:(J)Ljava/lang/String;") => long_to_string,
+        (LONG, "toHexString:(J)Ljava/lang/String;") => long_to_hex_string,
+        (LONG, "compare:(JJ)I") => long_compare,
+
+        (BOOLEAN, "valueOf:(Z)Ljava/lang/Boolean;") => boolean_value_of,
+        (BOOLEAN, "valueOf:(Ljava/lang/String;)Ljava/lang/Boolean;") => boolean_value_of_string,
+        (BOOLEAN, "parseBoolean:(Ljava/lang/String;)Z") => boolean_parse,
+        (BOOLEAN, "toString:(Z)Ljava/lang/String;") => boolean_to_string,
+
+        (DOUBLE, "valueOf:(D)Ljava/lang/Double;") => double_value_of,
+        (DOUBLE, "valueOf:(Ljava/lang/String;)Ljava/lang/Double;") => double_value_of_string,
+        (DOUBLE, "parseDouble:(Ljava/lang/String;)D") => double_parse,
+        (DOUBLE, "toString:(D)Ljava/lang/String;") => double_to_string_static,
+        (DOUBLE, "compare:(DD)I") => double_compare,
+        (DOUBLE, "isNaN:(D)Z") => double_is_nan,
+
+        (CHARACTER, "valueOf:(C)Ljava/lang/Character;") => character_value_of,
+        (CHARACTER, "toString:(C)Ljava/lang/String;") => character_to_string,
+        (CHARACTER, "isDigit:(C)Z") => character_is_digit,
+        (CHARACTER, "isLetter:(C)Z") => character_is_letter,
+        (CHARACTER, "isLetterOrDigit:(C)Z") => character_is_letter_or_digit,
+        (CHARACTER, "isWhitespace:(C)Z") => character_is_whitespace,
+        (CHARACTER, "isUpperCase:(C)Z") => character_is_upper_case,
+        (CHARACTER, "isLowerCase:(C)Z") => character_is_lower_case,
+        (CHARACTER, "toUpperCase:(C)C") => character_to_upper_case,
+        (CHARACTER, "toLowerCase:(C)C") => character_to_lower_case,
+
+        (COROUTINE_BOXING, "boxInt:(I)Ljava/lang/Integer;") => integer_value_of,
+        (COROUTINE_BOXING, "boxLong:(J)Ljava/lang/Long;") => long_value_of,
+        (COROUTINE_BOXING, "boxBoolean:(Z)Ljava/lang/Boolean;") => boolean_value_of,
+        (COROUTINE_BOXING, "boxDouble:(D)Ljava/lang/Double;") => double_value_of,
+        (COROUTINE_BOXING, "boxChar:(C)Ljava/lang/Character;") => character_value_of,
+
+        // Number and the unboxing methods, Kotlin calls them on every box
+        (INTEGER | LONG | DOUBLE | CHARACTER | BOOLEAN, key) => match key {
+            "intValue:()I" | "shortValue:()S" | "byteValue:()B" => box_int_value,
+            "longValue:()J" => box_long_value,
+            "doubleValue:()D" => box_double_value,
+            "floatValue:()F" => box_float_value,
+            "booleanValue:()Z" => box_boolean_value,
+            "charValue:()C" => box_char_value,
+            "toString:()Ljava/lang/String;" => box_to_string,
+            "hashCode:()I" => box_hash_code,
+            "equals:(Ljava/lang/Object;)Z" => box_equals,
+            "compareTo:(Ljava/lang/Object;)I"
+            | "compareTo:(Ljava/lang/Integer;)I"
+            | "compareTo:(Ljava/lang/Long;)I"
+            | "compareTo:(Ljava/lang/Double;)I"
+            | "compareTo:(Ljava/lang/Character;)I" => box_compare_to,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// Box `value` in a new instance of `class_name`
+pub fn box_value(interpreter: &mut Interpreter, class_name: &str, value: DexValue) -> ObjectId {
+    let id = interpreter.alloc_object(class_name);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(VALUE_FIELD.to_string(), value);
+    id
+}
+
+/// The primitive in the box `value` refers to, `None` for anything but a box
+pub fn unboxed(heap: &HashMap<ObjectId, Object>, value: &DexValue) -> Option<DexValue> {
+    let DexValue::Object(id) = value else {
+        return None;
+    };
+    let object = heap.get(id).filter(|object| is_box(&object.class_name))?;
+    object.fields.get(VALUE_FIELD).cloned()
+}
+
+/// Primitive of the receiver, which is a box or a primitive the caller boxed on the fly
+fn primitive(interpreter: &Interpreter, this: Option<ObjectId>) -> DexValue {
+    this.and_then(|id| unboxed(&interpreter.heap, &DexValue::Object(id))).unwrap_or(DexValue::Int(0))
+}
+
+fn as_i64(value: &DexValue) -> i64 {
+    match value {
+        DexValue::Long(value) => *value,
+        DexValue::Double(value) => *value as i64,
+        value => value.as_int().unwrap_or(0) as i64,
+    }
+}
+
+fn as_f64(value: &DexValue) -> f64 {
+    match value {
+        DexValue::Double(value) => *value,
+        DexValue::Long(value) => *value as f64,
+        value => value.as_int().unwrap_or(0) as f64,
+    }
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> i32 {
+    args.get(index).and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+fn long_arg(args: &[DexValue], index: usize) -> i64 {
+    args.get(index).and_then(|value| value.as_long()).unwrap_or(0)
+}
+
+fn double_arg(args: &[DexValue], index: usize) -> f64 {
+    args.get(index).and_then(|value| value.as_double()).unwrap_or(0.0)
+}
+
+fn char_arg(args: &[DexValue], index: usize) -> char {
+    char::from_u32(int_arg(args, index) as u32).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+fn string_arg(args: &[DexValue], index: usize) -> Option<String> {
+    match args.get(index) {
+        Some(DexValue::String(text)) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+fn number_format_error(interpreter: &mut Interpreter, text: Option<&str>) -> DexValue {
+    let message = match text {
+        Some(text) => format!("For input string: \"{}\"", text),
+        None => "null".to_string(),
+    };
+    interpreter.throw_new(NUMBER_FORMAT_EXCEPTION, &message);
+    DexValue::Void
+}
+
+/// Integer.parseInt and Long.parseLong, with an optional radix argument
+fn parse_integer(interpreter: &mut Interpreter, args: &[DexValue]) -> Option<i64> {
+    let text = string_arg(args, 0);
+    let radix = args.get(1).and_then(|value| value.as_int()).unwrap_or(10) as u32;
+    let parsed = text.as_deref()
+        .filter(|_| (2..=36).contains(&radix))
+        .and_then(|text| i64::from_str_radix(text, radix).ok());
+    if parsed.is_none() {
+        number_format_error(interpreter, text.as_deref());
+    }
+    parsed
+}
+
+/// Double.parseDouble, which ignores surrounding whitespace and a trailing type suffix
+/// `text` read like Double.parseDouble does
+pub fn java_double(text: &str) -> Option<f64> {
+    let trimmed = text.trim();
+    let number = trimmed.strip_suffix(['d', 'D', 'f', 'F']).unwrap_or(trimmed);
+    match number.trim_start_matches(['+', '-']) {
+        "Infinity" | "NaN" => number.replace("Infinity", "inf").parse().ok(),
+        // Rust also reads inf and infinity, Java doesn't
+        rest if rest.chars().any(|c| c.is_ascii_alphabetic() && !matches!(c, 'e' | 'E')) => None,
+        _ => number.parse().ok(),
+    }
+}
+
+fn parse_double(interpreter: &mut Interpreter, args: &[DexValue]) -> Option<f64> {
+    let text = string_arg(args, 0);
+    let parsed = text.as_deref().and_then(java_double);
+    if parsed.is_none() {
+        number_format_error(interpreter, text.as_deref());
+    }
+    parsed
+}
+
+fn integer_value_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Object(box_value(interpreter, INTEGER, DexValue::Int(int_arg(&args, 0))))
+}
+
+fn integer_value_of_string(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match parse_integer(interpreter, &args) {
+        Some(value) if i32::try_from(value).is_ok() => DexValue::Object(box_value(interpreter, INTEGER, DexValue::Int(value as i32))),
+        Some(_) => number_format_error(interpreter, string_arg(&args, 0).as_deref()),
+        None => DexValue::Void,
+    }
+}
+
+fn integer_parse(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match parse_integer(interpreter, &args) {
+        Some(value) if i32::try_from(value).is_ok() => DexValue::Int(value as i32),
+        Some(_) => number_format_error(interpreter, string_arg(&args, 0).as_deref()),
+        None => DexValue::Void,
+    }
+}
+
+fn integer_to_string(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String(int_arg(&args, 0).to_string())
+}
+
+/// The value as unsigned hex digits, how digest bytes are often printed
+fn integer_to_hex_string(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String(format!("{:x}", int_arg(&args, 0) as u32))
+}
+
+fn integer_compare(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(int_arg(&args, 0).cmp(&int_arg(&args, 1)) as i32)
+}
+
+fn long_value_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Object(box_value(interpreter, LONG, DexValue::Long(long_arg(&args, 0))))
+}
+
+fn long_value_of_string(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match parse_integer(interpreter, &args) {
+        Some(value) => DexValue::Object(box_value(interpreter, LONG, DexValue::Long(value))),
+        None => DexValue::Void,
+    }
+}
+
+fn long_parse(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    parse_integer(interpreter, &args).map_or(DexValue::Void, DexValue::Long)
+}
+
+fn long_to_string(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String(long_arg(&args, 0).to_string())
+}
+
+fn long_to_hex_string(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String(format!("{:x}", long_arg(&args, 0) as u64))
+}
+
+fn long_compare(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let args = parameters(&args);
+    DexValue::Int(long_arg(&args, 0).cmp(&long_arg(&args, 1)) as i32)
+}
+
+fn boolean_value_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Object(box_value(interpreter, BOOLEAN, DexValue::Boolean(int_arg(&args, 0) != 0)))
+}
+
+fn boolean_value_of_string(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = boolean_parse(interpreter, this, args);
+    DexValue::Object(box_value(interpreter, BOOLEAN, value))
+}
+
+/// Only "true" in any case is true, anything else including null is false
+fn boolean_parse(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(string_arg(&args, 0).is_some_and(|text| text.eq_ignore_ascii_case("true")))
+}
+
+fn boolean_to_string(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String((int_arg(&args, 0) != 0).to_string())
+}
+
+fn double_value_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Object(box_value(interpreter, DOUBLE, DexValue::Double(double_arg(&args, 0))))
+}
+
+fn double_value_of_string(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match parse_double(interpreter, &args) {
+        Some(value) => DexValue::Object(box_value(interpreter, DOUBLE, DexValue::Double(value))),
+        None => DexValue::Void,
+    }
+}
+
+fn double_parse(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    parse_double(interpreter, &args).map_or(DexValue::Void, DexValue::Double)
+}
+
+fn double_to_string_static(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String(double_to_string(double_arg(&args, 0)))
+}
+
+/// Double.compare orders -0.0 before 0.0 and NaN after everything, which total_cmp matches
+fn double_compare(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let args = parameters(&args);
+    DexValue::Int(double_arg(&args, 0).total_cmp(&double_arg(&args, 1)) as i32)
+}
+
+fn double_is_nan(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(double_arg(&args, 0).is_nan())
+}
+
+fn character_value_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Object(box_value(interpreter, CHARACTER, DexValue::Char(int_arg(&args, 0) as u16)))
+}
+
+fn character_to_string(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::String(String::from_utf16_lossy(&[int_arg(&args, 0) as u16]))
+}
+
+fn character_is_digit(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(char_arg(&args, 0).is_numeric())
+}
+
+fn character_is_letter(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(char_arg(&args, 0).is_alphabetic())
+}
+
+fn character_is_letter_or_digit(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(char_arg(&args, 0).is_alphanumeric())
+}
+
+fn character_is_whitespace(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    // Java leaves out the no-break spaces
+    let c = char_arg(&args, 0);
+    DexValue::Boolean(c.is_whitespace() && !matches!(c, '\u{00A0}' | '\u{2007}' | '\u{202F}'))
+}
+
+fn character_is_upper_case(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(char_arg(&args, 0).is_uppercase())
+}
+
+fn character_is_lower_case(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(char_arg(&args, 0).is_lowercase())
+}
+
+/// Mappings to more than one char, e.g. ß to SS, leave the char as it is like Java does
+fn map_char(c: char, mapped: impl Iterator<Item = char>) -> DexValue {
+    let mapped: Vec<char> = mapped.collect();
+    let c = if mapped.len() == 1 { mapped[0] } else { c };
+    let mut units = [0; 2];
+    DexValue::Char(c.encode_utf16(&mut units)[0])
+}
+
+fn character_to_upper_case(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let c = char_arg(&args, 0);
+    map_char(c, c.to_uppercase())
+}
+
+fn character_to_lower_case(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let c = char_arg(&args, 0);
+    map_char(c, c.to_lowercase())
+}
+
+fn box_int_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(as_i64(&primitive(interpreter, this)) as i32)
+}
+
+fn box_long_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Long(as_i64(&primitive(interpreter, this)))
+}
+
+fn box_double_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Double(as_f64(&primitive(interpreter, this)))
+}
+
+fn box_float_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Float(as_f64(&primitive(interpreter, this)) as f32)
+}
+
+fn box_boolean_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(as_i64(&primitive(interpreter, this)) != 0)
+}
+
+fn box_char_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Char(as_i64(&primitive(interpreter, this)) as u16)
+}
+
+fn box_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let value = primitive(interpreter, this);
+    DexValue::String(interpreter.string_value_of(&value))
+}
+
+fn box_hash_code(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let hash = match primitive(interpreter, this) {
+        DexValue::Long(value) => (value ^ (value as u64 >> 32) as i64) as i32,
+        DexValue::Double(value) => {
+            let bits = value.to_bits();
+            (bits ^ (bits >> 32)) as i32
+        }
+        DexValue::Boolean(value) => if value { 1231 } else { 1237 },
+        value => value.as_int().unwrap_or(0),
+    };
+    DexValue::Int(hash)
+}
+
+/// Boxes are only equal to boxes of the same class, Integer 1 doesn't equal Long 1
+fn box_equals(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let class_name = this.and_then(|id| interpreter.heap.get(&id)).map(|object| object.class_name.clone());
+    let other = match args.first() {
+        Some(DexValue::Object(id)) => interpreter.heap.get(id)
+            .filter(|other| Some(&other.class_name) == class_name.as_ref())
+            .and_then(|other| other.fields.get(VALUE_FIELD).cloned()),
+        _ => None,
+    };
+    DexValue::Boolean(other == Some(primitive(interpreter, this)))
+}
+
+fn box_compare_to(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = primitive(interpreter, this);
+    let other = args.first()
+        .and_then(|other| unboxed(&interpreter.heap, other).or_else(|| Some(other.clone())))
+        .unwrap_or(DexValue::Null);
+    let ordering = match (&value, &other) {
+        (DexValue::Double(_), _) | (_, DexValue::Double(_)) => as_f64(&value).total_cmp(&as_f64(&other)),
+        _ => as_i64(&value).cmp(&as_i64(&other)),
+    };
+    DexValue::Int(ordering as i32)
+}
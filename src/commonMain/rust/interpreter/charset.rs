@@ -0,0 +1,230 @@
+// java.nio.charset.Charset and the charset constants of StandardCharsets and Kotlin's Charsets,
+// for sources whose sites aren't UTF-8 and that build strings from bytes with an explicit
+// charset. The legacy encodings (GBK, Shift_JIS, EUC-KR, Big5, ...) come from encoding_rs. It
+// follows the web's labels, where ISO-8859-1 and US-ASCII mean windows-1252 and UTF-16 means
+// little-endian, so those and the UTF-16 variants are done here the way Java does them.
+
+use std::collections::HashMap;
+
+use encoding_rs::{EncoderResult, Encoding};
+
+use crate::interpreter::exception::{ILLEGAL_ARGUMENT_EXCEPTION, ILLEGAL_CHARSET_NAME_EXCEPTION, UNSUPPORTED_CHARSET_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::PlatformMethod;
+use crate::types::{DexValue, ObjectId};
+
+pub const CHARSET: &str = "Ljava/nio/charset/Charset;";
+pub const STANDARD_CHARSETS: &str = "Ljava/nio/charset/StandardCharsets;";
+pub const KOTLIN_CHARSETS: &str = "Lkotlin/text/Charsets;";
+pub const CHARSETS_KT: &str = "Lkotlin/text/CharsetsKt;";
+
+/// Field of a Charset object holding its canonical name
+const NAME_FIELD: &str = "name";
+
+/// The static fields of StandardCharsets, which Kotlin's Charsets also has
+const STANDARD_NAMES: [(&str, Charset); 6] = [
+    ("UTF_8", Charset::Utf8),
+    ("UTF_16", Charset::Utf16),
+    ("UTF_16BE", Charset::Utf16Be),
+    ("UTF_16LE", Charset::Utf16Le),
+    ("ISO_8859_1", Charset::Latin1),
+    ("US_ASCII", Charset::Ascii),
+];
+
+#[derive(Clone, Copy)]
+pub enum Charset {
+    Utf8,
+    /// Big-endian unless the bytes start with a byte order mark, encoding writes one
+    Utf16,
+    Utf16Be,
+    Utf16Le,
+    Latin1,
+    Ascii,
+    Other(&'static Encoding),
+}
+
+impl Charset {
+    /// The charset `name` or one of its aliases stands for, in any case
+    pub fn for_name(name: &str) -> Option<Charset> {
+        let charset = match name.to_ascii_uppercase().replace('_', "-").as_str() {
+            "UTF-8" | "UTF8" => Charset::Utf8,
+            "UTF-16" | "UTF16" => Charset::Utf16,
+            "UTF-16BE" | "UNICODEBIGUNMARKED" => Charset::Utf16Be,
+            "UTF-16LE" | "UNICODELITTLEUNMARKED" => Charset::Utf16Le,
+            "ISO-8859-1" | "ISO8859-1" | "ISO-LATIN-1" | "LATIN1" | "L1" | "8859-1" | "CP819" => Charset::Latin1,
+            "US-ASCII" | "ASCII" | "ISO646-US" | "646" => Charset::Ascii,
+            _ => match Encoding::for_label_no_replacement(name.as_bytes()) {
+                // encoding_rs only decodes UTF-16, its other labels ("unicode", ...) are Java's UTF-16 anyway
+                Some(encoding) if encoding == encoding_rs::UTF_16LE || encoding == encoding_rs::UTF_16BE => Charset::Utf16,
+                Some(encoding) => Charset::Other(encoding),
+                _ => return None,
+            },
+        };
+        Some(charset)
+    }
+
+    /// The canonical name, which name() and toString() give
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Utf8 => "UTF-8",
+            Charset::Utf16 => "UTF-16",
+            Charset::Utf16Be => "UTF-16BE",
+            Charset::Utf16Le => "UTF-16LE",
+            Charset::Latin1 => "ISO-8859-1",
+            Charset::Ascii => "US-ASCII",
+            Charset::Other(encoding) if encoding == encoding_rs::GB18030 => "GB18030",
+            Charset::Other(encoding) => encoding.name(),
+        }
+    }
+
+    /// The text of `bytes`, malformed input replaced by U+FFFD like the JVM does
+    pub fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            Charset::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Charset::Utf16 => match bytes {
+                [0xfe, 0xff, rest @ ..] => decode_utf16(rest, u16::from_be_bytes),
+                [0xff, 0xfe, rest @ ..] => decode_utf16(rest, u16::from_le_bytes),
+                _ => decode_utf16(bytes, u16::from_be_bytes),
+            },
+            Charset::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+            Charset::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+            Charset::Latin1 => bytes.iter().map(|byte| *byte as char).collect(),
+            Charset::Ascii => bytes.iter().map(|byte| if byte.is_ascii() { *byte as char } else { '\u{fffd}' }).collect(),
+            Charset::Other(encoding) => encoding.decode_without_bom_handling(bytes).0.into_owned(),
+        }
+    }
+
+    /// The bytes of `text`, characters the charset can't represent written as '?' like the JVM does
+    pub fn encode(self, text: &str) -> Vec<u8> {
+        match self {
+            Charset::Utf8 => text.as_bytes().to_vec(),
+            Charset::Utf16 => [0xfe, 0xff].into_iter().chain(text.encode_utf16().flat_map(u16::to_be_bytes)).collect(),
+            Charset::Utf16Be => text.encode_utf16().flat_map(u16::to_be_bytes).collect(),
+            Charset::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            Charset::Latin1 => text.chars().map(|c| if (c as u32) < 0x100 { c as u8 } else { b'?' }).collect(),
+            Charset::Ascii => text.chars().map(|c| if c.is_ascii() { c as u8 } else { b'?' }).collect(),
+            Charset::Other(encoding) => encode_legacy(encoding, text),
+        }
+    }
+}
+
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> String {
+    let chunks = bytes.chunks_exact(2);
+    let odd = !chunks.remainder().is_empty();
+    let mut text = String::from_utf16_lossy(&chunks.map(|pair| unit([pair[0], pair[1]])).collect::<Vec<u16>>());
+    if odd {
+        text.push('\u{fffd}');
+    }
+    text
+}
+
+fn encode_legacy(encoding: &'static Encoding, text: &str) -> Vec<u8> {
+    let mut encoder = encoding.new_encoder();
+    let mut bytes = Vec::with_capacity(encoder.max_buffer_length_from_utf8_without_replacement(text.len()).unwrap_or(text.len()));
+    let mut rest = text;
+    loop {
+        let (result, read) = encoder.encode_from_utf8_to_vec_without_replacement(rest, &mut bytes, true);
+        rest = &rest[read..];
+        match result {
+            EncoderResult::InputEmpty => return bytes,
+            EncoderResult::OutputFull => bytes.reserve(encoder.max_buffer_length_from_utf8_without_replacement(rest.len()).unwrap_or(rest.len()).max(16)),
+            EncoderResult::Unmappable(_) => bytes.push(b'?'),
+        }
+    }
+}
+
+/// The charset of a `Charset` or charset name argument, UTF-8 for none like the platform
+/// default. An unknown name is given back as the error for the caller to throw the exception
+/// of the method it implements.
+pub fn charset_arg(interpreter: &Interpreter, value: Option<&DexValue>) -> Result<Charset, String> {
+    let name = match value {
+        Some(DexValue::String(name)) => name.clone(),
+        Some(DexValue::Object(id)) => match interpreter.heap.get(id).and_then(|object| object.fields.get(NAME_FIELD)) {
+            Some(DexValue::String(name)) => name.clone(),
+            _ => return Ok(Charset::Utf8),
+        },
+        _ => return Ok(Charset::Utf8),
+    };
+    Charset::for_name(&name).ok_or(name)
+}
+
+/// The Charset object for `charset`
+pub fn new_charset(interpreter: &mut Interpreter, charset: Charset) -> DexValue {
+    let id = interpreter.alloc_object(CHARSET);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(NAME_FIELD.to_string(), DexValue::String(charset.name().to_string()));
+    DexValue::Object(id)
+}
+
+/// The static fields of StandardCharsets and Kotlin's Charsets
+pub fn charset_statics(interpreter: &mut Interpreter) -> HashMap<String, DexValue> {
+    STANDARD_NAMES.iter()
+        .map(|(field, charset)| (field.to_string(), new_charset(interpreter, *charset)))
+        .collect()
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (CHARSET, "forName:(Ljava/lang/String;)Ljava/nio/charset/Charset;")
+        | (CHARSETS_KT, "charset:(Ljava/lang/String;)Ljava/nio/charset/Charset;") => for_name,
+        (CHARSET, "defaultCharset:()Ljava/nio/charset/Charset;") => |interpreter, _, _| new_charset(interpreter, Charset::Utf8),
+        (CHARSET, "isSupported:(Ljava/lang/String;)Z") => |interpreter, _, args| {
+            let Some(DexValue::String(name)) = args.first() else {
+                interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "Null charset name");
+                return DexValue::Void;
+            };
+            if !is_legal_name(name) {
+                interpreter.throw_new(ILLEGAL_CHARSET_NAME_EXCEPTION, name);
+                return DexValue::Void;
+            }
+            DexValue::Boolean(Charset::for_name(name).is_some())
+        },
+        (CHARSET, "name:()Ljava/lang/String;" | "displayName:()Ljava/lang/String;" | "toString:()Ljava/lang/String;") => {
+            |interpreter, this, _| DexValue::String(name_of(interpreter, this))
+        }
+        (CHARSET, "equals:(Ljava/lang/Object;)Z") => |interpreter, this, args| {
+            let other = match args.first() {
+                Some(DexValue::Object(id)) if interpreter.heap.get(id).is_some_and(|object| object.class_name == CHARSET) => Some(*id),
+                _ => None,
+            };
+            DexValue::Boolean(other.is_some() && name_of(interpreter, this) == name_of(interpreter, other))
+        },
+        (CHARSET, "hashCode:()I") => |interpreter, this, _| {
+            let hash = name_of(interpreter, this).encode_utf16().fold(0i32, |hash, unit| hash.wrapping_mul(31).wrapping_add(unit as i32));
+            DexValue::Int(hash)
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn name_of(interpreter: &Interpreter, this: Option<ObjectId>) -> String {
+    match this.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(NAME_FIELD)) {
+        Some(DexValue::String(name)) => name.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Whether `name` is made of the characters Java allows in charset names
+fn is_legal_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphanumeric())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || "-+:_.".contains(c))
+}
+
+/// `Charset.forName(name)` and Kotlin's `charset(name)`
+fn for_name(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(DexValue::String(name)) = args.first() else {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "Null charset name");
+        return DexValue::Void;
+    };
+    if !is_legal_name(name) {
+        interpreter.throw_new(ILLEGAL_CHARSET_NAME_EXCEPTION, name);
+        return DexValue::Void;
+    }
+    match Charset::for_name(name) {
+        Some(charset) => new_charset(interpreter, charset),
+        None => {
+            interpreter.throw_new(UNSUPPORTED_CHARSET_EXCEPTION, name);
+            DexValue::Void
+        }
+    }
+}
@@ -0,0 +1,448 @@
+// javax.crypto.Cipher with AES in CBC or ECB mode, and the SecretKeySpec and IvParameterSpec it
+// is initialized with. Sources decrypt image URLs and API payloads with these, mostly
+// `AES/CBC/PKCS5Padding`. The block cipher comes from the aes crate, chaining and padding are
+// done here so their failures throw what the JDK throws. Like a digest, a cipher buffers what
+// update() is given and processes all of it in doFinal(), after which it is ready for the next
+// message with the same key.
+
+use aes::cipher::consts::U16;
+use aes::cipher::{BlockDecrypt, BlockEncrypt, BlockSizeUser, KeyInit};
+use aes::{Aes128, Aes192, Aes256, Block};
+
+use crate::interpreter::exception::{
+    BAD_PADDING_EXCEPTION, ILLEGAL_ARGUMENT_EXCEPTION, ILLEGAL_BLOCK_SIZE_EXCEPTION, ILLEGAL_STATE_EXCEPTION,
+    INVALID_ALGORITHM_PARAMETER_EXCEPTION, INVALID_KEY_EXCEPTION, NO_SUCH_ALGORITHM_EXCEPTION,
+};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{bytes_of, new_byte_array, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const CIPHER: &str = "Ljavax/crypto/Cipher;";
+pub const SECRET_KEY_SPEC: &str = "Ljavax/crypto/spec/SecretKeySpec;";
+pub const IV_PARAMETER_SPEC: &str = "Ljavax/crypto/spec/IvParameterSpec;";
+
+const ENCRYPT_MODE: i32 = 1;
+const DECRYPT_MODE: i32 = 2;
+
+const BLOCK_SIZE: usize = 16;
+
+/// The transformation as it was asked for
+const TRANSFORMATION_FIELD: &str = "transformation";
+/// The opmode of init(), missing until the cipher is initialized
+const MODE_FIELD: &str = "mode";
+const KEY_FIELD: &str = "key";
+const IV_FIELD: &str = "iv";
+/// Of a SecretKeySpec
+const ALGORITHM_FIELD: &str = "algorithm";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Chaining {
+    Cbc,
+    Ecb,
+}
+
+/// The chaining and whether PKCS#5 padding is used, for `AES`, `AES/CBC/PKCS5Padding` and the
+/// like. PKCS7Padding is the same as PKCS5Padding for 16 byte blocks.
+fn transformation(name: &str) -> Option<(Chaining, bool)> {
+    let parts: Vec<String> = name.split('/').map(|part| part.trim().to_ascii_uppercase()).collect();
+    let (chaining, padding) = match parts.as_slice() {
+        [algorithm] if algorithm == "AES" => return Some((Chaining::Ecb, true)),
+        [algorithm, chaining, padding] if algorithm == "AES" => (chaining.as_str(), padding.as_str()),
+        _ => return None,
+    };
+    let chaining = match chaining {
+        "CBC" => Chaining::Cbc,
+        "ECB" => Chaining::Ecb,
+        _ => return None,
+    };
+    match padding {
+        "PKCS5PADDING" | "PKCS7PADDING" => Some((chaining, true)),
+        "NOPADDING" => Some((chaining, false)),
+        _ => None,
+    }
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (CIPHER, "getInstance:(Ljava/lang/String;)Ljavax/crypto/Cipher;") => get_instance,
+        (CIPHER, "init:(ILjava/security/Key;)V" | "init:(ILjava/security/Key;Ljava/security/spec/AlgorithmParameterSpec;)V") => init,
+        (CIPHER, "update:([B)[B" | "update:([BII)[B") => |interpreter, this, args| {
+            let input = input(interpreter, &args);
+            if initialized(interpreter, this)
+                && let Some(buffer) = this.and_then(|id| interpreter.heap.get_mut(&id)).and_then(|object| object.array.as_mut())
+            {
+                buffer.extend(input.into_iter().map(|byte| DexValue::Byte(byte as i8)));
+            }
+            // Everything is processed by doFinal(), so its output is the whole message
+            new_byte_array(interpreter, Vec::new())
+        },
+        (CIPHER, "doFinal:()[B" | "doFinal:([B)[B" | "doFinal:([BII)[B") => do_final,
+        (CIPHER, "getIV:()[B") => |interpreter, this, _| match field(interpreter, this, IV_FIELD) {
+            Some(DexValue::Array(iv)) => new_byte_array(interpreter, iv.iter().map(|byte| byte.as_int().unwrap_or(0) as u8).collect()),
+            _ => DexValue::Null,
+        },
+        (CIPHER, "getBlockSize:()I") => |_, _, _| DexValue::Int(BLOCK_SIZE as i32),
+        (CIPHER, "getAlgorithm:()Ljava/lang/String;") => |interpreter, this, _| {
+            field(interpreter, this, TRANSFORMATION_FIELD).unwrap_or(DexValue::Null)
+        },
+
+        (SECRET_KEY_SPEC, "<init>:([BLjava/lang/String;)V" | "<init>:([BIILjava/lang/String;)V") => |interpreter, this, args| {
+            let key = input(interpreter, &args);
+            let algorithm = args.last().cloned().unwrap_or(DexValue::Null);
+            if key.is_empty() {
+                interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "Empty key");
+                return DexValue::Void;
+            }
+            if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+                object.fields.insert(ALGORITHM_FIELD.to_string(), algorithm);
+                object.array = Some(key.into_iter().map(|byte| DexValue::Byte(byte as i8)).collect());
+            }
+            DexValue::Void
+        },
+        (SECRET_KEY_SPEC, "getEncoded:()[B") => |interpreter, this, _| {
+            let key = bytes_of(interpreter, this);
+            new_byte_array(interpreter, key)
+        },
+        (SECRET_KEY_SPEC, "getAlgorithm:()Ljava/lang/String;") => |interpreter, this, _| {
+            field(interpreter, this, ALGORITHM_FIELD).unwrap_or(DexValue::Null)
+        },
+        (SECRET_KEY_SPEC, "getFormat:()Ljava/lang/String;") => |_, _, _| DexValue::String("RAW".to_string()),
+
+        (IV_PARAMETER_SPEC, "<init>:([B)V" | "<init>:([BII)V") => |interpreter, this, args| {
+            let iv = input(interpreter, &args);
+            if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+                object.array = Some(iv.into_iter().map(|byte| DexValue::Byte(byte as i8)).collect());
+            }
+            DexValue::Void
+        },
+        (IV_PARAMETER_SPEC, "getIV:()[B") => |interpreter, this, _| {
+            let iv = bytes_of(interpreter, this);
+            new_byte_array(interpreter, iv)
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn object_arg(args: &[DexValue], index: usize) -> Option<ObjectId> {
+    match args.get(index) {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+fn field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> Option<DexValue> {
+    this.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(name)).cloned()
+}
+
+fn initialized(interpreter: &Interpreter, this: Option<ObjectId>) -> bool {
+    field(interpreter, this, MODE_FIELD).is_some()
+}
+
+/// The bytes of the byte[] argument, or the range of it that `(input, offset, len)` overloads
+/// give. Trailing arguments that aren't ints, like the algorithm of a SecretKeySpec, don't count.
+fn input(interpreter: &Interpreter, args: &[DexValue]) -> Vec<u8> {
+    let bytes = bytes_of(interpreter, object_arg(args, 0));
+    match (args.get(1).and_then(DexValue::as_int), args.get(2).and_then(DexValue::as_int)) {
+        (Some(offset), Some(length)) => {
+            let offset = offset.clamp(0, bytes.len() as i32) as usize;
+            let length = length.clamp(0, (bytes.len() - offset) as i32) as usize;
+            bytes[offset..offset + length].to_vec()
+        }
+        _ => bytes,
+    }
+}
+
+fn to_array(bytes: &[u8]) -> DexValue {
+    DexValue::Array(bytes.iter().map(|byte| DexValue::Byte(*byte as i8)).collect())
+}
+
+fn from_array(value: Option<DexValue>) -> Option<Vec<u8>> {
+    match value {
+        Some(DexValue::Array(bytes)) => Some(bytes.iter().map(|byte| byte.as_int().unwrap_or(0) as u8).collect()),
+        _ => None,
+    }
+}
+
+fn get_instance(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = match args.first() {
+        Some(DexValue::String(name)) => name.clone(),
+        _ => String::new(),
+    };
+    if transformation(&name).is_none() {
+        interpreter.throw_new(NO_SUCH_ALGORITHM_EXCEPTION, &format!("Cannot find any provider supporting {}", name));
+        return DexValue::Void;
+    }
+
+    let id = interpreter.alloc_object(CIPHER);
+    let object = interpreter.heap.get_mut(&id).unwrap();
+    object.fields.insert(TRANSFORMATION_FIELD.to_string(), DexValue::String(name));
+    object.array = Some(Vec::new());
+    DexValue::Object(id)
+}
+
+/// `init(opmode, key)` and `init(opmode, key, params)`, the key and IV are copied so changing
+/// their arrays afterwards doesn't affect the cipher
+fn init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mode = args.first().and_then(|value| value.as_int()).unwrap_or(0);
+    let key = bytes_of(interpreter, object_arg(&args, 1));
+    let iv = object_arg(&args, 2).map(|params| bytes_of(interpreter, Some(params)));
+    let chaining = match field(interpreter, this, TRANSFORMATION_FIELD) {
+        Some(DexValue::String(name)) => transformation(&name).map(|(chaining, _)| chaining),
+        _ => None,
+    };
+
+    if mode != ENCRYPT_MODE && mode != DECRYPT_MODE {
+        interpreter.throw_new(INVALID_ALGORITHM_PARAMETER_EXCEPTION, &format!("Unsupported opmode {}", mode));
+        return DexValue::Void;
+    }
+    if ![16, 24, 32].contains(&key.len()) {
+        interpreter.throw_new(INVALID_KEY_EXCEPTION, &format!("Invalid AES key length: {} bytes", key.len()));
+        return DexValue::Void;
+    }
+    match (chaining, &iv) {
+        (Some(Chaining::Cbc), None) => {
+            // The JDK makes up a random IV when encrypting, nothing could decrypt that here
+            interpreter.throw_new(INVALID_KEY_EXCEPTION, "Parameters missing");
+            return DexValue::Void;
+        }
+        (Some(Chaining::Cbc), Some(iv)) if iv.len() != BLOCK_SIZE => {
+            interpreter.throw_new(INVALID_ALGORITHM_PARAMETER_EXCEPTION, "Wrong IV length: must be 16 bytes long");
+            return DexValue::Void;
+        }
+        (Some(Chaining::Ecb), Some(_)) => {
+            interpreter.throw_new(INVALID_ALGORITHM_PARAMETER_EXCEPTION, "ECB mode cannot use IV");
+            return DexValue::Void;
+        }
+        _ => {}
+    }
+
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(MODE_FIELD.to_string(), DexValue::Int(mode));
+        object.fields.insert(KEY_FIELD.to_string(), to_array(&key));
+        match iv {
+            Some(iv) => object.fields.insert(IV_FIELD.to_string(), to_array(&iv)),
+            None => object.fields.remove(IV_FIELD),
+        };
+        object.array = Some(Vec::new());
+    }
+    DexValue::Void
+}
+
+fn do_final(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    if !initialized(interpreter, this) {
+        interpreter.throw_new(ILLEGAL_STATE_EXCEPTION, "Cipher not initialized");
+        return DexValue::Void;
+    }
+    let mut data = bytes_of(interpreter, this);
+    if !args.is_empty() {
+        data.extend(input(interpreter, &args));
+    }
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.array = Some(Vec::new());
+    }
+
+    let encrypt = field(interpreter, this, MODE_FIELD) == Some(DexValue::Int(ENCRYPT_MODE));
+    let key = from_array(field(interpreter, this, KEY_FIELD)).unwrap_or_default();
+    let iv = from_array(field(interpreter, this, IV_FIELD));
+    let (chaining, padding) = match field(interpreter, this, TRANSFORMATION_FIELD) {
+        Some(DexValue::String(name)) => transformation(&name).unwrap_or((Chaining::Ecb, true)),
+        _ => (Chaining::Ecb, true),
+    };
+
+    match process(&key, chaining, iv.as_deref(), padding, encrypt, data) {
+        Ok(output) => new_byte_array(interpreter, output),
+        Err((exception, message)) => {
+            interpreter.throw_new(exception, message);
+            DexValue::Void
+        }
+    }
+}
+
+/// Encrypt or decrypt a whole message, or the exception and message the JDK throws for it
+fn process(key: &[u8], chaining: Chaining, iv: Option<&[u8]>, padding: bool, encrypt: bool, mut data: Vec<u8>) -> Result<Vec<u8>, (&'static str, &'static str)> {
+    if encrypt {
+        if padding {
+            let pad = BLOCK_SIZE - data.len() % BLOCK_SIZE;
+            data.extend(std::iter::repeat_n(pad as u8, pad));
+        } else if !data.len().is_multiple_of(BLOCK_SIZE) {
+            return Err((ILLEGAL_BLOCK_SIZE_EXCEPTION, "Input length not multiple of 16 bytes"));
+        }
+        return Ok(blocks(key, chaining, iv, true, data));
+    }
+
+    if !data.len().is_multiple_of(BLOCK_SIZE) {
+        let message = if padding {
+            "Input length must be multiple of 16 when decrypting with padded cipher"
+        } else {
+            "Input length not multiple of 16 bytes"
+        };
+        return Err((ILLEGAL_BLOCK_SIZE_EXCEPTION, message));
+    }
+    let mut output = blocks(key, chaining, iv, false, data);
+    if padding {
+        let pad = output.last().copied().unwrap_or(0) as usize;
+        if pad == 0 || pad > BLOCK_SIZE || pad > output.len() || output[output.len() - pad..].iter().any(|byte| *byte as usize != pad) {
+            return Err((
+                BAD_PADDING_EXCEPTION,
+                "Given final block not properly padded. Such issues can arise if a bad key is used during decryption.",
+            ));
+        }
+        output.truncate(output.len() - pad);
+    }
+    Ok(output)
+}
+
+/// Run whole blocks through AES with the key's size
+fn blocks(key: &[u8], chaining: Chaining, iv: Option<&[u8]>, encrypt: bool, data: Vec<u8>) -> Vec<u8> {
+    match key.len() {
+        16 => chain(&Aes128::new_from_slice(key).unwrap(), chaining, iv, encrypt, data),
+        24 => chain(&Aes192::new_from_slice(key).unwrap(), chaining, iv, encrypt, data),
+        _ => chain(&Aes256::new_from_slice(key).unwrap(), chaining, iv, encrypt, data),
+    }
+}
+
+fn chain<C: BlockEncrypt + BlockDecrypt + BlockSizeUser<BlockSize = U16>>(cipher: &C, chaining: Chaining, iv: Option<&[u8]>, encrypt: bool, mut data: Vec<u8>) -> Vec<u8> {
+    let mut previous = Block::clone_from_slice(iv.unwrap_or(&[0; BLOCK_SIZE]));
+    for chunk in data.chunks_mut(BLOCK_SIZE) {
+        let mut block = Block::clone_from_slice(chunk);
+        match (chaining, encrypt) {
+            (Chaining::Ecb, true) => cipher.encrypt_block(&mut block),
+            (Chaining::Ecb, false) => cipher.decrypt_block(&mut block),
+            (Chaining::Cbc, true) => {
+                block.iter_mut().zip(previous.iter()).for_each(|(byte, mask)| *byte ^= mask);
+                cipher.encrypt_block(&mut block);
+                previous = block;
+            }
+            (Chaining::Cbc, false) => {
+                let ciphertext = block;
+                cipher.decrypt_block(&mut block);
+                block.iter_mut().zip(previous.iter()).for_each(|(byte, mask)| *byte ^= mask);
+                previous = ciphertext;
+            }
+        }
+        chunk.copy_from_slice(&block);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex, self_test_cases};
+
+    fn hex(digits: &str) -> Vec<u8> {
+        (0..digits.len()).step_by(2).map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn call(interpreter: &mut Interpreter, class_name: &str, method_key: &str, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+        platform_method(class_name, method_key).unwrap()(interpreter, this, args)
+    }
+
+    /// Class of the exception the last call threw, if any
+    fn thrown(interpreter: &mut Interpreter) -> Option<String> {
+        match interpreter.pending_exception.take()?.exception {
+            DexValue::Object(id) => Some(interpreter.heap[&id].class_name.clone()),
+            _ => None,
+        }
+    }
+
+    /// `Cipher.getInstance(transformation)` initialized with `key` and `iv`
+    fn cipher(interpreter: &mut Interpreter, transformation: &str, mode: i32, key: &[u8], iv: Option<&[u8]>) -> ObjectId {
+        let DexValue::Object(cipher) = call(interpreter, CIPHER, "getInstance:(Ljava/lang/String;)Ljavax/crypto/Cipher;", None, vec![DexValue::String(transformation.to_string())]) else {
+            panic!("{} has no cipher", transformation);
+        };
+        let key_spec = interpreter.alloc_object(SECRET_KEY_SPEC);
+        let key = new_byte_array(interpreter, key.to_vec());
+        call(interpreter, SECRET_KEY_SPEC, "<init>:([BLjava/lang/String;)V", Some(key_spec), vec![key, DexValue::String("AES".to_string())]);
+        let mut args = vec![DexValue::Int(mode), DexValue::Object(key_spec)];
+        if let Some(iv) = iv {
+            let iv_spec = interpreter.alloc_object(IV_PARAMETER_SPEC);
+            let iv = new_byte_array(interpreter, iv.to_vec());
+            call(interpreter, IV_PARAMETER_SPEC, "<init>:([B)V", Some(iv_spec), vec![iv]);
+            args.push(DexValue::Object(iv_spec));
+        }
+        call(interpreter, CIPHER, "init:(ILjava/security/Key;Ljava/security/spec/AlgorithmParameterSpec;)V", Some(cipher), args);
+        cipher
+    }
+
+    /// `doFinal(data)`, or the class of the exception it threw
+    fn finish(interpreter: &mut Interpreter, cipher: ObjectId, data: &[u8]) -> Result<Vec<u8>, String> {
+        let data = new_byte_array(interpreter, data.to_vec());
+        let output = call(interpreter, CIPHER, "doFinal:([B)[B", Some(cipher), vec![data]);
+        match (thrown(interpreter), output) {
+            (Some(exception), _) => Err(exception),
+            (None, DexValue::Object(id)) => Ok(bytes_of(interpreter, Some(id))),
+            (None, output) => panic!("doFinal returned {:?}", output),
+        }
+    }
+
+    /// The vectors of FIPS 197 appendix C.1 and SP 800-38A F.2.1
+    #[test]
+    fn encrypts_known_vectors() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let ecb = cipher(&mut interpreter, "AES/ECB/NoPadding", ENCRYPT_MODE, &hex("000102030405060708090a0b0c0d0e0f"), None);
+        assert_eq!(finish(&mut interpreter, ecb, &hex("00112233445566778899aabbccddeeff")), Ok(hex("69c4e0d86a7b0430d8cdb78070b4c55a")));
+
+        let key = hex("2b7e151628aed2a6abf7158809cf4f3c");
+        let iv = hex("000102030405060708090a0b0c0d0e0f");
+        let cbc = cipher(&mut interpreter, "AES/CBC/NoPadding", ENCRYPT_MODE, &key, Some(&iv));
+        let plaintext = hex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e51");
+        assert_eq!(finish(&mut interpreter, cbc, &plaintext), Ok(hex("7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b2")));
+    }
+
+    #[test]
+    fn round_trips_padded_messages() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let key = hex("603deb1015ca71be2b73aef0857d77811f352c073b6108d72d9810a30914dff4");
+        let iv = [7; BLOCK_SIZE];
+        for (transformation, iv) in [("AES/CBC/PKCS5Padding", Some(&iv[..])), ("AES/ECB/PKCS7Padding", None), ("AES", None)] {
+            for length in [0, 5, 16, 33] {
+                let message: Vec<u8> = (0..length as u8).collect();
+                let encrypt = cipher(&mut interpreter, transformation, ENCRYPT_MODE, &key, iv);
+                let encrypted = finish(&mut interpreter, encrypt, &message).unwrap();
+                assert_eq!(encrypted.len(), (length / BLOCK_SIZE + 1) * BLOCK_SIZE, "{} of {} bytes", transformation, length);
+
+                let decrypt = cipher(&mut interpreter, transformation, DECRYPT_MODE, &key, iv);
+                assert_eq!(finish(&mut interpreter, decrypt, &encrypted), Ok(message), "{} of {} bytes", transformation, length);
+            }
+        }
+    }
+
+    #[test]
+    fn throws_bad_padding_for_unpadded_blocks() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let key = [1; 16];
+        // A block ending in 0 never decrypts to valid padding
+        let encrypt = cipher(&mut interpreter, "AES/ECB/NoPadding", ENCRYPT_MODE, &key, None);
+        let encrypted = finish(&mut interpreter, encrypt, &[0; BLOCK_SIZE]).unwrap();
+        let decrypt = cipher(&mut interpreter, "AES/ECB/PKCS5Padding", DECRYPT_MODE, &key, None);
+        assert_eq!(finish(&mut interpreter, decrypt, &encrypted), Err(BAD_PADDING_EXCEPTION.to_string()));
+        assert_eq!(finish(&mut interpreter, decrypt, &encrypted[1..]), Err(ILLEGAL_BLOCK_SIZE_EXCEPTION.to_string()));
+    }
+
+    /// Transformation, key, IV and the exception init() throws for them
+    type InitCase = (&'static str, &'static [u8], Option<&'static [u8]>, &'static str);
+
+    #[test]
+    fn refuses_invalid_keys_and_ivs() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let cases: [InitCase; 4] = [
+            ("AES/CBC/PKCS5Padding", &[0; 15], Some(&[0; 16]), INVALID_KEY_EXCEPTION),
+            ("AES/CBC/PKCS5Padding", &[0; 16], Some(&[0; 8]), INVALID_ALGORITHM_PARAMETER_EXCEPTION),
+            ("AES/CBC/PKCS5Padding", &[0; 16], None, INVALID_KEY_EXCEPTION),
+            ("AES/ECB/PKCS5Padding", &[0; 32], Some(&[0; 16]), INVALID_ALGORITHM_PARAMETER_EXCEPTION),
+        ];
+        for (transformation, key, iv, exception) in cases {
+            let cipher = cipher(&mut interpreter, transformation, ENCRYPT_MODE, key, iv);
+            assert_eq!(thrown(&mut interpreter).as_deref(), Some(exception), "{} with a {} byte key", transformation, key.len());
+            // Left uninitialized
+            assert_eq!(finish(&mut interpreter, cipher, b"message"), Err(ILLEGAL_STATE_EXCEPTION.to_string()));
+        }
+
+        let key_spec = interpreter.alloc_object(SECRET_KEY_SPEC);
+        let key = new_byte_array(&mut interpreter, Vec::new());
+        call(&mut interpreter, SECRET_KEY_SPEC, "<init>:([BLjava/lang/String;)V", Some(key_spec), vec![key, DexValue::String("AES".to_string())]);
+        assert_eq!(thrown(&mut interpreter).as_deref(), Some(ILLEGAL_ARGUMENT_EXCEPTION));
+    }
+}
@@ -0,0 +1,93 @@
+// The time every native reads: System.currentTimeMillis() and nanoTime(), Android's SystemClock,
+// new Date(), cookie expiry and the rate limiter. The host can fix the clock for tests that need
+// the same times on every run: a fixed clock stands still until it is advanced, and sleeping on
+// it advances it rather than blocking, so a rate-limited run takes no real time.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use lazy_static::lazy_static;
+
+use crate::interpreter::date::SYSTEM;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{parameters, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const SYSTEM_CLOCK: &str = "Landroid/os/SystemClock;";
+
+enum Clock {
+    Host,
+    /// Fixed at `millis` since the epoch, then advanced by `nanos`
+    Fixed { millis: i64, nanos: i64 },
+}
+
+lazy_static! {
+    /// Origin of the host's nanoTime(), like the JVM's it is arbitrary
+    static ref ORIGIN: Instant = Instant::now();
+    static ref CLOCK: Mutex<Clock> = Mutex::new(Clock::Host);
+}
+
+/// Stop the clock at `millis` since the epoch, or let it run with the host's again for `None`
+pub fn set_fixed(millis: Option<i64>) {
+    *CLOCK.lock().unwrap() = match millis {
+        Some(millis) => Clock::Fixed { millis, nanos: 0 },
+        None => Clock::Host,
+    };
+}
+
+/// Move a fixed clock forward, the host's can't be
+pub fn advance(duration: Duration) {
+    if let Clock::Fixed { nanos, .. } = &mut *CLOCK.lock().unwrap() {
+        *nanos += duration.as_nanos() as i64;
+    }
+}
+
+/// Milliseconds since the epoch
+pub fn now_millis() -> i64 {
+    match *CLOCK.lock().unwrap() {
+        Clock::Host => SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_millis() as i64).unwrap_or(0),
+        Clock::Fixed { millis, nanos } => millis + nanos / 1_000_000,
+    }
+}
+
+/// Nanoseconds since some fixed point, only ever moving forward
+pub fn nano_time() -> i64 {
+    match *CLOCK.lock().unwrap() {
+        Clock::Host => ORIGIN.elapsed().as_nanos() as i64,
+        Clock::Fixed { nanos, .. } => nanos,
+    }
+}
+
+/// Block for `duration`, or advance a fixed clock by it
+pub fn sleep(duration: Duration) {
+    let fixed = matches!(*CLOCK.lock().unwrap(), Clock::Fixed { .. });
+    if fixed {
+        advance(duration);
+    } else {
+        std::thread::sleep(duration);
+    }
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (SYSTEM, "currentTimeMillis:()J") => |_, _, _| DexValue::Long(now_millis()),
+        (SYSTEM, "nanoTime:()J") => |_, _, _| DexValue::Long(nano_time()),
+
+        // The interpreter's process starts with the device, so boot and uptime are the same
+        (SYSTEM_CLOCK, "elapsedRealtime:()J" | "uptimeMillis:()J") => |_, _, _| DexValue::Long(nano_time() / 1_000_000),
+        (SYSTEM_CLOCK, "elapsedRealtimeNanos:()J") => |_, _, _| DexValue::Long(nano_time()),
+        (SYSTEM_CLOCK, "currentThreadTimeMillis:()J") => |_, _, _| DexValue::Long(nano_time() / 1_000_000),
+        (SYSTEM_CLOCK, "sleep:(J)V") => system_clock_sleep,
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// SystemClock.sleep(ms), which unlike Thread.sleep() can't be interrupted
+fn system_clock_sleep(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let millis = parameters(&args).first().and_then(|value| value.as_long()).unwrap_or(0);
+    if millis > 0 {
+        sleep(Duration::from_millis(millis as u64));
+    }
+    DexValue::Void
+}
@@ -0,0 +1,256 @@
+// Converts between heap object graphs and JSON. Objects are written as their fields plus an
+// `@class` key, arrays and lists as JSON arrays. The source api models go by their short names
+// so the host does not need to know the descriptors of their implementations.
+
+use serde_json::{Map, Number, Value};
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::interpreter::collections::ARRAY_LIST;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::MANGAS_PAGE;
+use crate::types::{DexValue, ObjectId};
+
+/// Key holding the class of an encoded object
+pub const CLASS_KEY: &str = "@class";
+/// Keys of `encode_graph`: the heap id of an object, and an object written out elsewhere
+pub const ID_KEY: &str = "@id";
+pub const REF_KEY: &str = "@ref";
+
+/// Nesting allowed in either direction before giving up, filter groups are the deepest graphs
+/// sources produce and stay far below it
+pub const MAX_DEPTH: usize = 64;
+
+/// Short names of the data classes exchanged with the host
+const KNOWN_CLASSES: &[(&str, &str)] = &[
+    ("SManga", "Leu/kanade/tachiyomi/source/model/SMangaImpl;"),
+    ("SChapter", "Leu/kanade/tachiyomi/source/model/SChapterImpl;"),
+    ("Page", "Leu/kanade/tachiyomi/source/model/Page;"),
+    ("MangasPage", MANGAS_PAGE),
+    ("FilterList", "Leu/kanade/tachiyomi/source/model/FilterList;"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CodecError {
+    /// The object graph refers back to an object that is still being encoded
+    Cycle { class_name: String },
+    DepthExceeded { limit: usize },
+    /// A JSON object without `@class` where the expected type is unknown
+    UnknownClass { json: String },
+    /// The JSON value can't be stored in a field or argument of type `descriptor`
+    TypeMismatch { descriptor: String, json: String },
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Cycle { class_name } => write!(f, "Cycle through an instance of {}", class_name),
+            CodecError::DepthExceeded { limit } => write!(f, "Object graph nested deeper than {} levels", limit),
+            CodecError::UnknownClass { json } => write!(f, "No class known for {}", json),
+            CodecError::TypeMismatch { descriptor, json } => write!(f, "Can't convert {} to {}", json, descriptor),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+/// Descriptor of a class from its short name or its descriptor
+pub fn resolve_class(name: &str) -> String {
+    KNOWN_CLASSES.iter()
+        .find(|(short, _)| *short == name)
+        .map(|(_, descriptor)| descriptor.to_string())
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Short name of a known data class, its descriptor otherwise
+fn class_label(descriptor: &str) -> String {
+    KNOWN_CLASSES.iter()
+        .find(|(_, known)| *known == descriptor)
+        .map(|(short, _)| short.to_string())
+        .unwrap_or_else(|| descriptor.to_string())
+}
+
+pub fn encode(interpreter: &Interpreter, value: &DexValue) -> Result<Value, CodecError> {
+    Encoder { interpreter, path: HashSet::new(), written: None }.encode(value, 0)
+}
+
+/// Encode any value, whatever the shape of its object graph. Objects carry their heap id under
+/// `@id` and are written out once, later references to them are `{"@ref": id}`, so cycles and
+/// shared objects come through. Arrays are written wherever they are referenced, an array
+/// containing itself and graphs deeper than `MAX_DEPTH` end in null.
+pub fn encode_graph(interpreter: &Interpreter, value: &DexValue) -> Value {
+    Encoder { interpreter, path: HashSet::new(), written: Some(HashSet::new()) }
+        .encode(value, 0)
+        .unwrap_or(Value::Null)
+}
+
+/// Build the heap value for `json`, `descriptor` is the type it is stored as and decides how
+/// numbers are read and which class objects without `@class` get
+pub fn decode(interpreter: &mut Interpreter, json: &Value, descriptor: Option<&str>) -> Result<DexValue, CodecError> {
+    decode_value(interpreter, json, descriptor, 0)
+}
+
+struct Encoder<'a> {
+    interpreter: &'a Interpreter,
+    /// Objects between the root and the value being encoded
+    path: HashSet<ObjectId>,
+    /// Objects written so far, `None` unless encoding a graph
+    written: Option<HashSet<ObjectId>>,
+}
+
+impl Encoder<'_> {
+    fn encode(&mut self, value: &DexValue, depth: usize) -> Result<Value, CodecError> {
+        if depth > MAX_DEPTH {
+            if self.written.is_some() {
+                return Ok(Value::Null);
+            }
+            return Err(CodecError::DepthExceeded { limit: MAX_DEPTH });
+        }
+
+        let json = match value {
+            DexValue::Null | DexValue::Void => Value::Null,
+            DexValue::Boolean(value) => Value::from(*value),
+            DexValue::Byte(value) => Value::from(*value),
+            DexValue::Short(value) => Value::from(*value),
+            DexValue::Int(value) => Value::from(*value),
+            DexValue::Long(value) => Value::from(*value),
+            DexValue::Float(value) => Number::from_f64(*value as f64).map(Value::Number).unwrap_or(Value::Null),
+            DexValue::Double(value) => Number::from_f64(*value).map(Value::Number).unwrap_or(Value::Null),
+            DexValue::Char(value) => Value::from(char::from_u32(*value as u32).map(String::from).unwrap_or_default()),
+            DexValue::String(value) | DexValue::Type(value) | DexValue::Enum(value) => Value::from(value.clone()),
+            DexValue::Array(values) => Value::Array(
+                values.iter().map(|value| self.encode(value, depth + 1)).collect::<Result<_, _>>()?,
+            ),
+            DexValue::Object(id) => return self.encode_object(*id, depth),
+            // Method handles and the like only show up when debugging, keep them readable
+            value => Value::from(format!("{:?}", value)),
+        };
+        Ok(json)
+    }
+
+    fn encode_object(&mut self, id: ObjectId, depth: usize) -> Result<Value, CodecError> {
+        let Some(object) = self.interpreter.heap.get(&id) else {
+            return Ok(Value::Null);
+        };
+        if let Some(written) = &mut self.written {
+            match &object.array {
+                Some(_) if self.path.contains(&id) => return Ok(Value::Null),
+                Some(_) => {}
+                None if !written.insert(id) => return Ok(serde_json::json!({ REF_KEY: id })),
+                None => {}
+            }
+        }
+        if !self.path.insert(id) {
+            return Err(CodecError::Cycle { class_name: object.class_name.clone() });
+        }
+
+        let json = match &object.array {
+            Some(elements) => Value::Array(
+                elements.iter().map(|element| self.encode(element, depth + 1)).collect::<Result<_, _>>()?,
+            ),
+            None => {
+                let mut fields = Map::new();
+                fields.insert(CLASS_KEY.to_string(), Value::from(class_label(&object.class_name)));
+                if self.written.is_some() {
+                    fields.insert(ID_KEY.to_string(), Value::from(id));
+                }
+
+                // Sorted so the same object always encodes the same way
+                let mut names: Vec<&String> = object.fields.keys().collect();
+                names.sort();
+                for name in names {
+                    fields.insert(name.clone(), self.encode(&object.fields[name], depth + 1)?);
+                }
+                Value::Object(fields)
+            }
+        };
+
+        self.path.remove(&id);
+        Ok(json)
+    }
+}
+
+fn decode_value(interpreter: &mut Interpreter, json: &Value, descriptor: Option<&str>, depth: usize) -> Result<DexValue, CodecError> {
+    if depth > MAX_DEPTH {
+        return Err(CodecError::DepthExceeded { limit: MAX_DEPTH });
+    }
+    let mismatch = || CodecError::TypeMismatch { descriptor: descriptor.unwrap_or("?").to_string(), json: json.to_string() };
+
+    let value = match (descriptor, json) {
+        (_, Value::Null) => DexValue::default_for(descriptor.unwrap_or("")),
+        (Some("Z"), Value::Bool(value)) => DexValue::Boolean(*value),
+        (Some("B"), Value::Number(number)) => DexValue::Byte(number.as_i64().ok_or_else(mismatch)? as i8),
+        (Some("S"), Value::Number(number)) => DexValue::Short(number.as_i64().ok_or_else(mismatch)? as i16),
+        (Some("C"), Value::String(value)) => DexValue::Char(value.encode_utf16().next().ok_or_else(mismatch)?),
+        (Some("I"), Value::Number(number)) => DexValue::Int(number.as_i64().ok_or_else(mismatch)? as i32),
+        (Some("J"), Value::Number(number)) => DexValue::Long(number.as_i64().ok_or_else(mismatch)?),
+        (Some("F"), Value::Number(number)) => DexValue::Float(number.as_f64().ok_or_else(mismatch)? as f32),
+        (Some("D"), Value::Number(number)) => DexValue::Double(number.as_f64().ok_or_else(mismatch)?),
+        (Some("Z" | "B" | "S" | "C" | "I" | "J" | "F" | "D"), _) => return Err(mismatch()),
+
+        // Untyped, e.g. fields of classes that aren't loaded
+        (_, Value::Bool(value)) => DexValue::Boolean(*value),
+        (_, Value::Number(number)) => match number.as_i64() {
+            Some(value) => i32::try_from(value).map(DexValue::Int).unwrap_or(DexValue::Long(value)),
+            None => DexValue::Double(number.as_f64().ok_or_else(mismatch)?),
+        },
+        (_, Value::String(value)) => DexValue::String(value.clone()),
+
+        (_, Value::Array(elements)) => {
+            let element_type = descriptor.and_then(|descriptor| descriptor.strip_prefix('['));
+            let elements = elements.iter()
+                .map(|element| decode_value(interpreter, element, element_type, depth + 1))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let class_name = descriptor.filter(|descriptor| descriptor.starts_with('[')).unwrap_or(ARRAY_LIST);
+            let id = interpreter.alloc_object(class_name);
+            interpreter.heap.get_mut(&id).unwrap().array = Some(elements);
+            DexValue::Object(id)
+        }
+
+        (_, Value::Object(fields)) => {
+            let class_name = match fields.get(CLASS_KEY) {
+                Some(Value::String(name)) => resolve_class(name),
+                _ => descriptor
+                    .filter(|descriptor| descriptor.starts_with('L') && *descriptor != "Ljava/lang/Object;")
+                    .map(resolve_class)
+                    .ok_or_else(|| CodecError::UnknownClass { json: json.to_string() })?,
+            };
+
+            // Filled in like a deserializer would, without running a constructor
+            let id = interpreter.alloc_object(&class_name);
+            for (name, field) in fields.iter().filter(|(name, _)| *name != CLASS_KEY) {
+                let field_type = interpreter.instance_field_type(&class_name, name);
+                let value = decode_value(interpreter, field, field_type.as_deref(), depth + 1)?;
+                interpreter.heap.get_mut(&id).unwrap().fields.insert(name.clone(), value);
+            }
+            DexValue::Object(id)
+        }
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex, self_test_cases, OBJECT_CLASS};
+
+    /// A graph with a cycle and an object referenced twice, which the plain codec refuses
+    #[test]
+    fn encodes_cycles_by_reference() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let first = interpreter.alloc_object(OBJECT_CLASS);
+        let second = interpreter.alloc_object(OBJECT_CLASS);
+        interpreter.heap.get_mut(&first).unwrap().fields.insert("next".to_string(), DexValue::Object(second));
+        interpreter.heap.get_mut(&second).unwrap().fields.insert("next".to_string(), DexValue::Object(first));
+        let array = interpreter.alloc_array("[Ljava/lang/Object;", 2);
+        interpreter.heap.get_mut(&array).unwrap().array = Some(vec![DexValue::Object(first), DexValue::Object(first)]);
+
+        assert!(matches!(encode(&interpreter, &DexValue::Object(array)), Err(CodecError::Cycle { .. })));
+        let expected = serde_json::json!([
+            { "@class": OBJECT_CLASS, "@id": first, "next": { "@class": OBJECT_CLASS, "@id": second, "next": { "@ref": first } } },
+            { "@ref": first },
+        ]);
+        assert_eq!(encode_graph(&interpreter, &DexValue::Object(array)), expected);
+    }
+}
@@ -0,0 +1,518 @@
+// java.util collections. A list keeps its elements in the `array` of its heap object, just like
+// an array does, so the codec and the host read lists and arrays the same way and either can be
+// passed where the other's elements are copied from. A map keeps its entries there, as entry
+// objects in insertion order, and finds keys through their equals(); the maps extensions build
+// for headers and query parameters are small enough for that. Sets are lists that skip the
+// elements they already hold, which also keeps them in insertion order.
+
+use crate::interpreter::exception::{INDEX_OUT_OF_BOUNDS_EXCEPTION, NO_SUCH_ELEMENT_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::PlatformMethod;
+use crate::types::{DexValue, ObjectId};
+
+pub const ARRAY_LIST: &str = "Ljava/util/ArrayList;";
+pub const LIST_ITERATOR: &str = "Ljava/util/ArrayList$Itr;";
+pub const HASH_MAP: &str = "Ljava/util/HashMap;";
+pub const LINKED_HASH_MAP: &str = "Ljava/util/LinkedHashMap;";
+pub const MAP_ENTRY: &str = "Ljava/util/HashMap$Node;";
+pub const HASH_SET: &str = "Ljava/util/HashSet;";
+pub const LINKED_HASH_SET: &str = "Ljava/util/LinkedHashSet;";
+
+/// Interfaces of the collections the interpreter knows without a class file, for instanceof
+pub fn builtin_interfaces(class_name: &str) -> Option<&'static [&'static str]> {
+    match class_name {
+        ARRAY_LIST => Some(&["Ljava/util/List;", "Ljava/util/RandomAccess;"]),
+        HASH_MAP | LINKED_HASH_MAP => Some(&["Ljava/util/Map;"]),
+        MAP_ENTRY => Some(&["Ljava/util/Map$Entry;"]),
+        HASH_SET | LINKED_HASH_SET => Some(&["Ljava/util/Set;"]),
+        LIST_ITERATOR => Some(&["Ljava/util/Iterator;"]),
+        "Ljava/util/List;" | "Ljava/util/Set;" => Some(&["Ljava/util/Collection;"]),
+        "Ljava/util/Collection;" => Some(&["Ljava/lang/Iterable;"]),
+        _ => None,
+    }
+}
+
+const LIST_FIELD: &str = "list";
+const CURSOR_FIELD: &str = "cursor";
+const KEY_FIELD: &str = "key";
+const VALUE_FIELD: &str = "value";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        // Arrays are objects whose class is their descriptor, clone() is all they add
+        (array, "clone:()Ljava/lang/Object;") if array.starts_with('[') => clone_array,
+        // The capacity is only a hint
+        (ARRAY_LIST, "<init>:()V" | "<init>:(I)V") => list_init,
+        (ARRAY_LIST, "<init>:(Ljava/util/Collection;)V") => list_init_with_elements,
+        (ARRAY_LIST, "add:(Ljava/lang/Object;)Z") => list_add,
+        (ARRAY_LIST, "add:(ILjava/lang/Object;)V") => list_insert,
+        (ARRAY_LIST, "addAll:(Ljava/util/Collection;)Z") => list_add_all,
+        (ARRAY_LIST, "get:(I)Ljava/lang/Object;") => list_get,
+        (ARRAY_LIST, "set:(ILjava/lang/Object;)Ljava/lang/Object;") => list_set,
+        (ARRAY_LIST, "remove:(I)Ljava/lang/Object;") => list_remove_at,
+        (ARRAY_LIST, "remove:(Ljava/lang/Object;)Z") => list_remove,
+        (ARRAY_LIST, "clear:()V") => list_clear,
+        (ARRAY_LIST, "size:()I") => list_size,
+        (ARRAY_LIST, "isEmpty:()Z") => list_is_empty,
+        (ARRAY_LIST, "contains:(Ljava/lang/Object;)Z") => list_contains,
+        (ARRAY_LIST, "indexOf:(Ljava/lang/Object;)I") => list_index_of,
+        (ARRAY_LIST, "iterator:()Ljava/util/Iterator;") => list_iterator,
+        (ARRAY_LIST, "toArray:()[Ljava/lang/Object;") => list_to_array,
+        (ARRAY_LIST, "toString:()Ljava/lang/String;") => list_to_string,
+
+        (LIST_ITERATOR, "hasNext:()Z") => iterator_has_next,
+        (LIST_ITERATOR, "next:()Ljava/lang/Object;") => iterator_next,
+
+        (HASH_MAP | LINKED_HASH_MAP, "<init>:()V" | "<init>:(I)V" | "<init>:(IF)V") => list_init,
+        (HASH_MAP | LINKED_HASH_MAP, "<init>:(Ljava/util/Map;)V") => map_init_with_entries,
+        (HASH_MAP | LINKED_HASH_MAP, "put:(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;") => map_put,
+        (HASH_MAP | LINKED_HASH_MAP, "putAll:(Ljava/util/Map;)V") => map_put_all,
+        (HASH_MAP | LINKED_HASH_MAP, "get:(Ljava/lang/Object;)Ljava/lang/Object;") => map_get,
+        (HASH_MAP | LINKED_HASH_MAP, "getOrDefault:(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;") => map_get_or_default,
+        (HASH_MAP | LINKED_HASH_MAP, "containsKey:(Ljava/lang/Object;)Z") => map_contains_key,
+        (HASH_MAP | LINKED_HASH_MAP, "remove:(Ljava/lang/Object;)Ljava/lang/Object;") => map_remove,
+        (HASH_MAP | LINKED_HASH_MAP, "clear:()V") => list_clear,
+        (HASH_MAP | LINKED_HASH_MAP, "size:()I") => list_size,
+        (HASH_MAP | LINKED_HASH_MAP, "isEmpty:()Z") => list_is_empty,
+        (HASH_MAP | LINKED_HASH_MAP, "keySet:()Ljava/util/Set;") => map_key_set,
+        (HASH_MAP | LINKED_HASH_MAP, "values:()Ljava/util/Collection;") => map_values,
+        (HASH_MAP | LINKED_HASH_MAP, "entrySet:()Ljava/util/Set;") => map_entry_set,
+        (HASH_MAP | LINKED_HASH_MAP, "toString:()Ljava/lang/String;") => map_to_string,
+
+        (HASH_SET | LINKED_HASH_SET, "<init>:()V" | "<init>:(I)V" | "<init>:(IF)V") => list_init,
+        (HASH_SET | LINKED_HASH_SET, "<init>:(Ljava/util/Collection;)V") => set_init_with_elements,
+        (HASH_SET | LINKED_HASH_SET, "add:(Ljava/lang/Object;)Z") => set_add,
+        (HASH_SET | LINKED_HASH_SET, "addAll:(Ljava/util/Collection;)Z") => set_add_all,
+        (HASH_SET | LINKED_HASH_SET, "remove:(Ljava/lang/Object;)Z") => list_remove,
+        (HASH_SET | LINKED_HASH_SET, "contains:(Ljava/lang/Object;)Z") => list_contains,
+        (HASH_SET | LINKED_HASH_SET, "clear:()V") => list_clear,
+        (HASH_SET | LINKED_HASH_SET, "size:()I") => list_size,
+        (HASH_SET | LINKED_HASH_SET, "isEmpty:()Z") => list_is_empty,
+        (HASH_SET | LINKED_HASH_SET, "iterator:()Ljava/util/Iterator;") => list_iterator,
+        (HASH_SET | LINKED_HASH_SET, "toArray:()[Ljava/lang/Object;") => list_to_array,
+        (HASH_SET | LINKED_HASH_SET, "toString:()Ljava/lang/String;") => list_to_string,
+
+        (MAP_ENTRY, "getKey:()Ljava/lang/Object;") => entry_get_key,
+        (MAP_ENTRY, "getValue:()Ljava/lang/Object;") => entry_get_value,
+        (MAP_ENTRY, "setValue:(Ljava/lang/Object;)Ljava/lang/Object;") => entry_set_value,
+        (MAP_ENTRY, "toString:()Ljava/lang/String;") => entry_to_string,
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// Elements of a list or an array, empty for anything else
+pub fn elements_of(interpreter: &Interpreter, value: &DexValue) -> Vec<DexValue> {
+    match value {
+        DexValue::Object(id) => interpreter.heap.get(id)
+            .and_then(|object| object.array.clone())
+            .unwrap_or_default(),
+        DexValue::Array(elements) => elements.clone(),
+        _ => Vec::new(),
+    }
+}
+
+/// Allocate an ArrayList holding `elements`
+pub fn new_list(interpreter: &mut Interpreter, elements: Vec<DexValue>) -> ObjectId {
+    let id = interpreter.alloc_object(ARRAY_LIST);
+    interpreter.heap.get_mut(&id).unwrap().array = Some(elements);
+    id
+}
+
+/// Allocate a LinkedHashMap holding the `entries`, a later entry replaces the value of an
+/// earlier one with an equal key
+pub fn new_map(interpreter: &mut Interpreter, entries: Vec<(DexValue, DexValue)>) -> ObjectId {
+    let id = interpreter.alloc_object(LINKED_HASH_MAP);
+    interpreter.heap.get_mut(&id).unwrap().array = Some(Vec::new());
+    for (key, value) in entries {
+        put(interpreter, Some(id), key, value);
+    }
+    id
+}
+
+/// Keys and values of a map's entries, in insertion order
+pub fn map_entries(interpreter: &Interpreter, map: &DexValue) -> Vec<(DexValue, DexValue)> {
+    elements_of(interpreter, map).iter()
+        .map(|entry| (field_of(interpreter, entry, KEY_FIELD), field_of(interpreter, entry, VALUE_FIELD)))
+        .collect()
+}
+
+/// Value the map `this` holds under `key`
+pub fn map_value(interpreter: &mut Interpreter, this: Option<ObjectId>, key: &DexValue) -> Option<DexValue> {
+    let (_, entry) = find_entry(interpreter, this, key)?;
+    Some(field_of(interpreter, &entry, VALUE_FIELD))
+}
+
+/// Remove the entry of `key` from the map `this`, returning its value
+pub fn remove_key(interpreter: &mut Interpreter, this: Option<ObjectId>, key: &DexValue) -> Option<DexValue> {
+    let (index, entry) = find_entry(interpreter, this, key)?;
+    if let Some(entries) = elements_mut(interpreter, this) {
+        entries.remove(index);
+    }
+    Some(field_of(interpreter, &entry, VALUE_FIELD))
+}
+
+/// Allocate an iterator over the elements of the list `list`
+pub fn new_iterator(interpreter: &mut Interpreter, list: Option<ObjectId>) -> DexValue {
+    let id = interpreter.alloc_object(LIST_ITERATOR);
+    let iterator = interpreter.heap.get_mut(&id).unwrap();
+    iterator.fields.insert(LIST_FIELD.to_string(), list.map_or(DexValue::Null, DexValue::Object));
+    iterator.fields.insert(CURSOR_FIELD.to_string(), DexValue::Int(0));
+    DexValue::Object(id)
+}
+
+/// Allocate a LinkedHashSet holding the distinct `elements`
+pub fn new_set(interpreter: &mut Interpreter, elements: Vec<DexValue>) -> ObjectId {
+    let id = interpreter.alloc_object(LINKED_HASH_SET);
+    interpreter.heap.get_mut(&id).unwrap().array = Some(Vec::new());
+    set_add_all(interpreter, Some(id), vec![DexValue::Array(elements)]);
+    id
+}
+
+fn elements(interpreter: &Interpreter, this: Option<ObjectId>) -> &[DexValue] {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.array.as_deref())
+        .unwrap_or(&[])
+}
+
+fn elements_mut(interpreter: &mut Interpreter, this: Option<ObjectId>) -> Option<&mut Vec<DexValue>> {
+    let object = interpreter.heap.get_mut(&this?)?;
+    Some(object.array.get_or_insert_with(Vec::new))
+}
+
+fn index_arg(args: &[DexValue]) -> i32 {
+    args.first().and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+/// `index` if it's below `size`, otherwise throws an IndexOutOfBoundsException
+fn checked_index(interpreter: &mut Interpreter, index: i32, size: usize) -> Option<usize> {
+    match usize::try_from(index) {
+        Ok(index) if index < size => Some(index),
+        _ => {
+            interpreter.throw_new(INDEX_OUT_OF_BOUNDS_EXCEPTION, &format!("Index {} out of bounds for length {}", index, size));
+            None
+        }
+    }
+}
+
+fn list_init(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    if let Some(elements) = elements_mut(interpreter, this) {
+        elements.clear();
+    }
+    DexValue::Void
+}
+
+fn list_init_with_elements(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let copied = elements_of(interpreter, args.first().unwrap_or(&DexValue::Null));
+    if let Some(elements) = elements_mut(interpreter, this) {
+        *elements = copied;
+    }
+    DexValue::Void
+}
+
+fn list_add(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    if let Some(elements) = elements_mut(interpreter, this) {
+        elements.push(args.into_iter().next().unwrap_or(DexValue::Null));
+    }
+    DexValue::Boolean(true)
+}
+
+fn list_insert(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let size = elements(interpreter, this).len();
+    // Inserting at the end is allowed
+    let Some(index) = checked_index(interpreter, index_arg(&args), size + 1) else {
+        return DexValue::Void;
+    };
+    if let Some(elements) = elements_mut(interpreter, this) {
+        elements.insert(index, args.get(1).cloned().unwrap_or(DexValue::Null));
+    }
+    DexValue::Void
+}
+
+fn list_add_all(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let added = elements_of(interpreter, args.first().unwrap_or(&DexValue::Null));
+    let changed = !added.is_empty();
+    if let Some(elements) = elements_mut(interpreter, this) {
+        elements.extend(added);
+    }
+    DexValue::Boolean(changed)
+}
+
+fn list_get(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let size = elements(interpreter, this).len();
+    match checked_index(interpreter, index_arg(&args), size) {
+        Some(index) => elements(interpreter, this)[index].clone(),
+        None => DexValue::Void,
+    }
+}
+
+fn list_set(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let size = elements(interpreter, this).len();
+    let Some(index) = checked_index(interpreter, index_arg(&args), size) else {
+        return DexValue::Void;
+    };
+    let value = args.get(1).cloned().unwrap_or(DexValue::Null);
+    elements_mut(interpreter, this).map_or(DexValue::Null, |elements| std::mem::replace(&mut elements[index], value))
+}
+
+fn list_remove_at(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let size = elements(interpreter, this).len();
+    let Some(index) = checked_index(interpreter, index_arg(&args), size) else {
+        return DexValue::Void;
+    };
+    elements_mut(interpreter, this).map_or(DexValue::Null, |elements| elements.remove(index))
+}
+
+fn list_remove(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let DexValue::Int(index) = list_index_of(interpreter, this, args) else {
+        return DexValue::Boolean(false);
+    };
+    if index < 0 {
+        return DexValue::Boolean(false);
+    }
+    if let Some(elements) = elements_mut(interpreter, this) {
+        elements.remove(index as usize);
+    }
+    DexValue::Boolean(true)
+}
+
+fn list_clear(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    list_init(interpreter, this, Vec::new())
+}
+
+fn list_size(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(elements(interpreter, this).len() as i32)
+}
+
+fn list_is_empty(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(elements(interpreter, this).is_empty())
+}
+
+fn list_contains(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let found = list_index_of(interpreter, this, args);
+    DexValue::Boolean(matches!(found, DexValue::Int(index) if index >= 0))
+}
+
+/// Elements are compared with their equals(), so data classes match by value
+fn list_index_of(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let target = args.first().cloned().unwrap_or(DexValue::Null);
+    let elements = elements(interpreter, this).to_vec();
+    let index = elements.iter().position(|element| interpreter.values_equal(element, &target));
+    DexValue::Int(index.map_or(-1, |index| index as i32))
+}
+
+fn list_iterator(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    new_iterator(interpreter, this)
+}
+
+/// `array.clone()`, a shallow copy of the same type. An enum's values() is `$VALUES.clone()`.
+fn clone_array(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let Some(array) = this.and_then(|id| interpreter.heap.get(&id)) else {
+        return DexValue::Null;
+    };
+    let (class_name, elements) = (array.class_name.clone(), array.array.clone());
+    let id = interpreter.alloc_object(&class_name);
+    interpreter.heap.get_mut(&id).unwrap().array = elements;
+    DexValue::Object(id)
+}
+
+fn list_to_array(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let elements = elements(interpreter, this).to_vec();
+    let id = interpreter.alloc_object("[Ljava/lang/Object;");
+    interpreter.heap.get_mut(&id).unwrap().array = Some(elements);
+    DexValue::Object(id)
+}
+
+/// `[a, b, c]` with each element's toString()
+fn list_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let elements = elements(interpreter, this).to_vec();
+    let texts: Vec<String> = elements.iter().map(|element| interpreter.string_value_of(element)).collect();
+    DexValue::String(format!("[{}]", texts.join(", ")))
+}
+
+fn set_init_with_elements(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    list_init(interpreter, this, Vec::new());
+    set_add_all(interpreter, this, args);
+    DexValue::Void
+}
+
+fn set_add(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    if let DexValue::Boolean(true) = list_contains(interpreter, this, args.clone()) {
+        return DexValue::Boolean(false);
+    }
+    list_add(interpreter, this, args)
+}
+
+fn set_add_all(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut changed = false;
+    for element in elements_of(interpreter, args.first().unwrap_or(&DexValue::Null)) {
+        changed |= set_add(interpreter, this, vec![element]) == DexValue::Boolean(true);
+    }
+    DexValue::Boolean(changed)
+}
+
+/// The list an iterator walks and the position of its next element
+fn iterator_state(interpreter: &Interpreter, this: Option<ObjectId>) -> (Option<ObjectId>, usize) {
+    let Some(iterator) = this.and_then(|id| interpreter.heap.get(&id)) else {
+        return (None, 0);
+    };
+    let list = match iterator.fields.get(LIST_FIELD) {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    };
+    let cursor = iterator.fields.get(CURSOR_FIELD).and_then(|cursor| cursor.as_int()).unwrap_or(0);
+    (list, cursor as usize)
+}
+
+fn iterator_has_next(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let (list, cursor) = iterator_state(interpreter, this);
+    DexValue::Boolean(cursor < elements(interpreter, list).len())
+}
+
+fn iterator_next(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let (list, cursor) = iterator_state(interpreter, this);
+    let Some(element) = elements(interpreter, list).get(cursor).cloned() else {
+        interpreter.throw_new(NO_SUCH_ELEMENT_EXCEPTION, "");
+        return DexValue::Void;
+    };
+    if let Some(iterator) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        iterator.fields.insert(CURSOR_FIELD.to_string(), DexValue::Int(cursor as i32 + 1));
+    }
+    element
+}
+
+fn field_of(interpreter: &Interpreter, object: &DexValue, field: &str) -> DexValue {
+    match object {
+        DexValue::Object(id) => interpreter.heap.get(id)
+            .and_then(|object| object.fields.get(field).cloned())
+            .unwrap_or(DexValue::Null),
+        _ => DexValue::Null,
+    }
+}
+
+fn new_entry(interpreter: &mut Interpreter, key: DexValue, value: DexValue) -> DexValue {
+    let id = interpreter.alloc_object(MAP_ENTRY);
+    let entry = interpreter.heap.get_mut(&id).unwrap();
+    entry.fields.insert(KEY_FIELD.to_string(), key);
+    entry.fields.insert(VALUE_FIELD.to_string(), value);
+    DexValue::Object(id)
+}
+
+/// The entry of `this` whose key equals `key`
+fn find_entry(interpreter: &mut Interpreter, this: Option<ObjectId>, key: &DexValue) -> Option<(usize, DexValue)> {
+    let entries = elements(interpreter, this).to_vec();
+    entries.into_iter().enumerate().find(|(_, entry)| {
+        let entry_key = field_of(interpreter, entry, KEY_FIELD);
+        interpreter.values_equal(&entry_key, key)
+    })
+}
+
+/// Store `value` under `key`, returning the value it replaces or null
+pub fn put(interpreter: &mut Interpreter, this: Option<ObjectId>, key: DexValue, value: DexValue) -> DexValue {
+    if let Some((_, DexValue::Object(entry))) = find_entry(interpreter, this, &key) {
+        let entry = interpreter.heap.get_mut(&entry).unwrap();
+        return entry.fields.insert(VALUE_FIELD.to_string(), value).unwrap_or(DexValue::Null);
+    }
+
+    let entry = new_entry(interpreter, key, value);
+    if let Some(entries) = elements_mut(interpreter, this) {
+        entries.push(entry);
+    }
+    DexValue::Null
+}
+
+fn map_init_with_entries(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    list_init(interpreter, this, Vec::new());
+    map_put_all(interpreter, this, args)
+}
+
+fn map_put(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut args = args.into_iter();
+    let key = args.next().unwrap_or(DexValue::Null);
+    let value = args.next().unwrap_or(DexValue::Null);
+    put(interpreter, this, key, value)
+}
+
+/// Entries are copied, the maps don't share them
+fn map_put_all(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    for entry in elements_of(interpreter, args.first().unwrap_or(&DexValue::Null)) {
+        let key = field_of(interpreter, &entry, KEY_FIELD);
+        let value = field_of(interpreter, &entry, VALUE_FIELD);
+        put(interpreter, this, key, value);
+    }
+    DexValue::Void
+}
+
+fn map_get(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    map_get_or_default(interpreter, this, vec![args.into_iter().next().unwrap_or(DexValue::Null), DexValue::Null])
+}
+
+fn map_get_or_default(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let key = args.first().cloned().unwrap_or(DexValue::Null);
+    match find_entry(interpreter, this, &key) {
+        Some((_, entry)) => field_of(interpreter, &entry, VALUE_FIELD),
+        None => args.get(1).cloned().unwrap_or(DexValue::Null),
+    }
+}
+
+fn map_contains_key(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let key = args.first().cloned().unwrap_or(DexValue::Null);
+    DexValue::Boolean(find_entry(interpreter, this, &key).is_some())
+}
+
+fn map_remove(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let key = args.first().cloned().unwrap_or(DexValue::Null);
+    remove_key(interpreter, this, &key).unwrap_or(DexValue::Null)
+}
+
+// Key and value collections are snapshots rather than views, extensions read them right away.
+// The entries are the map's own, so setValue() on them writes through.
+
+fn map_field_list(interpreter: &mut Interpreter, this: Option<ObjectId>, field: &str) -> DexValue {
+    let values = elements(interpreter, this).iter()
+        .map(|entry| field_of(interpreter, entry, field))
+        .collect();
+    DexValue::Object(new_list(interpreter, values))
+}
+
+fn map_key_set(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    map_field_list(interpreter, this, KEY_FIELD)
+}
+
+fn map_values(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    map_field_list(interpreter, this, VALUE_FIELD)
+}
+
+fn map_entry_set(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let entries = elements(interpreter, this).to_vec();
+    DexValue::Object(new_list(interpreter, entries))
+}
+
+/// `{a=1, b=2}`
+fn map_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let entries = elements(interpreter, this).to_vec();
+    let texts: Vec<String> = entries.iter().map(|entry| interpreter.string_value_of(entry)).collect();
+    DexValue::String(format!("{{{}}}", texts.join(", ")))
+}
+
+fn entry_get_key(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    field_of(interpreter, &this.map_or(DexValue::Null, DexValue::Object), KEY_FIELD)
+}
+
+fn entry_get_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    field_of(interpreter, &this.map_or(DexValue::Null, DexValue::Object), VALUE_FIELD)
+}
+
+fn entry_set_value(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = args.into_iter().next().unwrap_or(DexValue::Null);
+    this.and_then(|id| interpreter.heap.get_mut(&id))
+        .and_then(|entry| entry.fields.insert(VALUE_FIELD.to_string(), value))
+        .unwrap_or(DexValue::Null)
+}
+
+fn entry_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let entry = this.map_or(DexValue::Null, DexValue::Object);
+    let key = field_of(interpreter, &entry, KEY_FIELD);
+    let value = field_of(interpreter, &entry, VALUE_FIELD);
+    DexValue::String(format!("{}={}", interpreter.string_value_of(&key), interpreter.string_value_of(&value)))
+}
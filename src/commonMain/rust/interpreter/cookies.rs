@@ -0,0 +1,250 @@
+// Cookie store of the HTTP shims, one jar per source like the WebView cookie manager Mihon shares
+// between an extension's clients. Cookies are taken from Set-Cookie headers after RFC 6265 and
+// sent back on every request they match, redirects included. Cookies with an expiry are written
+// to disk so a session survives a reload of the runner, session cookies only live as long as it.
+// There is no public suffix list, a Domain attribute just has to cover the request host.
+
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::interpreter::clock;
+use crate::utils::class_descriptor;
+
+/// Jars live in this directory, one JSON file per source
+const COOKIES_DIR: &str = "out/cookies";
+
+lazy_static! {
+    /// Jars loaded so far, keyed by the descriptor of the source's main class
+    static ref JARS: Mutex<HashMap<String, Vec<Cookie>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    /// Lowercase host or domain, without a leading dot
+    pub domain: String,
+    /// Set without a Domain attribute, so only sent to `domain` itself and not its subdomains
+    pub host_only: bool,
+    pub path: String,
+    /// Milliseconds since the epoch, `None` for a session cookie
+    pub expires_at: Option<i64>,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+impl Cookie {
+    /// Parse the Set-Cookie value `header` received from `host` for a request to `request_path`.
+    /// `None` if it's malformed or sets a domain `host` isn't in.
+    pub fn parse(host: &str, request_path: &str, header: &str, now: i64) -> Option<Cookie> {
+        let mut attributes = header.split(';');
+        let (name, value) = attributes.next()?.split_once('=')?;
+        let name = name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let host = host.to_ascii_lowercase();
+        let mut cookie = Cookie {
+            name: name.to_string(),
+            value: value.trim().trim_matches('"').to_string(),
+            domain: host.clone(),
+            host_only: true,
+            path: default_path(request_path),
+            expires_at: None,
+            secure: false,
+            http_only: false,
+        };
+
+        let mut max_age = None;
+        let mut expires = None;
+        for attribute in attributes {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            let value = value.trim();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "domain" => {
+                    let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                    if domain.is_empty() {
+                        continue;
+                    }
+                    if !domain_matches(&host, &domain) || !domain.contains('.') && domain != host {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+                "path" if value.starts_with('/') => cookie.path = value.to_string(),
+                "max-age" => max_age = value.parse::<i64>().ok(),
+                "expires" => expires = parse_expires(value),
+                "secure" => cookie.secure = true,
+                "httponly" => cookie.http_only = true,
+                _ => {}
+            }
+        }
+        // Max-Age wins over Expires, zero or less means the cookie is to be removed
+        cookie.expires_at = match max_age {
+            Some(seconds) if seconds <= 0 => Some(i64::MIN),
+            Some(seconds) => Some(now.saturating_add(seconds.saturating_mul(1000))),
+            None => expires,
+        };
+        Some(cookie)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    /// Whether the cookie goes with a request to `path` on `host`
+    pub fn matches(&self, host: &str, path: &str, https: bool) -> bool {
+        let host = host.to_ascii_lowercase();
+        let domain_ok = if self.host_only { host == self.domain } else { domain_matches(&host, &self.domain) };
+        domain_ok && path_matches(path, &self.path) && (https || !self.secure)
+    }
+}
+
+/// The request path up to its last `/`, what a cookie without a Path attribute is scoped to
+fn default_path(request_path: &str) -> String {
+    match request_path.rfind('/') {
+        Some(index) if index > 0 && request_path.starts_with('/') => request_path[..index].to_string(),
+        _ => "/".to_string(),
+    }
+}
+
+fn domain_matches(host: &str, domain: &str) -> bool {
+    let is_ip = host.parse::<std::net::IpAddr>().is_ok() || host.starts_with('[');
+    host == domain || !is_ip && host.strip_suffix(domain).is_some_and(|rest| rest.ends_with('.'))
+}
+
+fn path_matches(path: &str, cookie_path: &str) -> bool {
+    path == cookie_path
+        || path.starts_with(cookie_path) && (cookie_path.ends_with('/') || path[cookie_path.len()..].starts_with('/'))
+}
+
+/// An Expires date in any of the formats servers send, picked apart like RFC 6265 section 5.1.1
+/// says: the first time, day, month and year tokens are used whatever their order
+fn parse_expires(text: &str) -> Option<i64> {
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+    let tokens = text.split(|c: char| !c.is_ascii_alphanumeric() && c != ':').filter(|token| !token.is_empty());
+    for token in tokens {
+        if time.is_none() && token.contains(':') {
+            let parts: Vec<u32> = token.split(':').filter_map(|part| part.parse().ok()).collect();
+            if parts.len() == 3 {
+                time = Some((parts[0], parts[1], parts[2]));
+                continue;
+            }
+        }
+        let digits = token.len() - token.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if day.is_none() && (1..=2).contains(&digits) {
+            day = token[..digits].parse::<u32>().ok();
+            continue;
+        }
+        if month.is_none() && token.len() >= 3 {
+            let prefix = token[..3].to_ascii_lowercase();
+            const MONTHS: [&str; 12] = ["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+            if let Some(index) = MONTHS.iter().position(|name| *name == prefix) {
+                month = Some(index as u32 + 1);
+                continue;
+            }
+        }
+        if year.is_none() && (2..=4).contains(&digits) {
+            year = token[..digits].parse::<i32>().ok().map(|year| match year {
+                70..=99 => year + 1900,
+                0..=69 => year + 2000,
+                year => year,
+            });
+        }
+    }
+    let (hour, minute, second) = time?;
+    let date = NaiveDate::from_ymd_opt(year?, month?, day?)?;
+    Some(date.and_hms_opt(hour, minute, second)?.and_utc().timestamp_millis())
+}
+
+fn jar_file(source: &str) -> PathBuf {
+    let namespace = source.trim_start_matches('L').trim_end_matches(';').replace('/', ".");
+    PathBuf::from(COOKIES_DIR).join(format!("{}.json", namespace))
+}
+
+/// Run `action` on the jar of `source`, read from disk the first time. The persistent cookies
+/// are written back if `action` says it changed the jar.
+fn with_jar<T>(source: &str, action: impl FnOnce(&mut Vec<Cookie>) -> (T, bool)) -> T {
+    let source = class_descriptor(source);
+    let mut jars = JARS.lock().unwrap();
+    let jar = jars.entry(source.clone()).or_insert_with(|| {
+        std::fs::read(jar_file(&source)).ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    });
+    let now = clock::now_millis();
+    jar.retain(|cookie| !cookie.is_expired(now));
+    let (result, changed) = action(jar);
+    if changed {
+        let persistent: Vec<&Cookie> = jar.iter().filter(|cookie| cookie.expires_at.is_some()).collect();
+        let path = jar_file(&source);
+        let written = std::fs::create_dir_all(COOKIES_DIR)
+            .and_then(|_| std::fs::write(&path, serde_json::to_vec(&persistent).unwrap()));
+        if let Err(error) = written {
+            crate::logging::log(&format!("Could not save cookies to {}: {}", path.display(), error));
+        }
+    }
+    result
+}
+
+/// Store `cookies`, replacing those with the same name, domain and path. An expired one just
+/// removes the cookie it replaces.
+pub fn save(source: &str, cookies: Vec<Cookie>) {
+    if cookies.is_empty() {
+        return;
+    }
+    let now = clock::now_millis();
+    with_jar(source, |jar| {
+        for cookie in cookies {
+            jar.retain(|stored| !(stored.name == cookie.name && stored.domain == cookie.domain && stored.path == cookie.path));
+            if !cookie.is_expired(now) {
+                jar.push(cookie);
+            }
+        }
+        ((), true)
+    })
+}
+
+/// Cookies of `source` to send with a request to `path` on `host`, in the order they were set
+pub fn load(source: &str, host: &str, path: &str, https: bool) -> Vec<Cookie> {
+    with_jar(source, |jar| {
+        let cookies = jar.iter().filter(|cookie| cookie.matches(host, path, https)).cloned().collect();
+        (cookies, false)
+    })
+}
+
+/// Every cookie of `source`, or those set for `host` and its subdomains
+pub fn list(source: &str, host: Option<&str>) -> Vec<Cookie> {
+    with_jar(source, |jar| {
+        let cookies = jar.iter()
+            .filter(|cookie| host.is_none_or(|host| domain_matches(&cookie.domain, &host.to_ascii_lowercase())))
+            .cloned()
+            .collect();
+        (cookies, false)
+    })
+}
+
+/// Drop every cookie of `source`, or those set for `host` and its subdomains. Returns how many
+/// were removed.
+pub fn clear(source: &str, host: Option<&str>) -> usize {
+    with_jar(source, |jar| {
+        let before = jar.len();
+        jar.retain(|cookie| host.is_some_and(|host| !domain_matches(&cookie.domain, &host.to_ascii_lowercase())));
+        let removed = before - jar.len();
+        (removed, removed > 0)
+    })
+}
+
+/// Value of a Cookie request header carrying `cookies`
+pub fn header_value(cookies: &[Cookie]) -> String {
+    cookies.iter().map(|cookie| format!("{}={}", cookie.name, cookie.value)).collect::<Vec<_>>().join("; ")
+}
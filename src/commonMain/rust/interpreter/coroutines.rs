@@ -0,0 +1,352 @@
+// Kotlin suspend functions. A suspend function compiles to a method taking one more parameter,
+// the Continuation to resume once it is done, and returning either its result or the
+// COROUTINE_SUSPENDED marker. Its body is a state machine kept in a ContinuationImpl subclass the
+// extension declares, whose invokeSuspend() runs it on from the last suspension point.
+//
+// Everything runs on the calling thread. Platform suspend functions that have to wait, e.g. for
+// an HTTP response, do the work and queue their continuation with the outcome before returning
+// the marker. Whoever waits for a coroutine, an entry point the host called or a runBlocking
+// block, then resumes queued continuations until the coroutine's completion has its outcome.
+// kotlinx builders run their block right away, withContext() and friends in their caller's
+// coroutine, so nothing is ever dispatched to another thread.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::interpreter::clock;
+use crate::interpreter::collections::{elements_of, new_list};
+use crate::interpreter::exception::ILLEGAL_STATE_EXCEPTION;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{parameters, PlatformMethod};
+use crate::types::{DexClass, DexValue, ObjectId};
+
+pub const CONTINUATION: &str = "Lkotlin/coroutines/Continuation;";
+pub const BASE_CONTINUATION_IMPL: &str = "Lkotlin/coroutines/jvm/internal/BaseContinuationImpl;";
+pub const CONTINUATION_IMPL: &str = "Lkotlin/coroutines/jvm/internal/ContinuationImpl;";
+pub const RESTRICTED_CONTINUATION_IMPL: &str = "Lkotlin/coroutines/jvm/internal/RestrictedContinuationImpl;";
+pub const SUSPEND_LAMBDA: &str = "Lkotlin/coroutines/jvm/internal/SuspendLambda;";
+pub const RESTRICTED_SUSPEND_LAMBDA: &str = "Lkotlin/coroutines/jvm/internal/RestrictedSuspendLambda;";
+pub const DEBUG_PROBES_KT: &str = "Lkotlin/coroutines/jvm/internal/DebugProbesKt;";
+pub const INTRINSICS_KT: &str = "Lkotlin/coroutines/intrinsics/IntrinsicsKt;";
+pub const COROUTINE_SINGLETONS: &str = "Lkotlin/coroutines/intrinsics/CoroutineSingletons;";
+pub const EMPTY_COROUTINE_CONTEXT: &str = "Lkotlin/coroutines/EmptyCoroutineContext;";
+pub const RESULT: &str = "Lkotlin/Result;";
+pub const RESULT_FAILURE: &str = "Lkotlin/Result$Failure;";
+pub const RESULT_KT: &str = "Lkotlin/ResultKt;";
+
+pub const BUILDERS_KT: &str = "Lkotlinx/coroutines/BuildersKt;";
+pub const COROUTINE_SCOPE_KT: &str = "Lkotlinx/coroutines/CoroutineScopeKt;";
+pub const SUPERVISOR_KT: &str = "Lkotlinx/coroutines/SupervisorKt;";
+pub const AWAIT_KT: &str = "Lkotlinx/coroutines/AwaitKt;";
+pub const DELAY_KT: &str = "Lkotlinx/coroutines/DelayKt;";
+pub const DISPATCHERS: &str = "Lkotlinx/coroutines/Dispatchers;";
+pub const DISPATCHER: &str = "Lkotlinx/coroutines/CoroutineDispatcher;";
+pub const CONTEXT_SCOPE: &str = "Lkotlinx/coroutines/internal/ContextScope;";
+/// The completion of an entry point the host calls or of a runBlocking block
+pub const BLOCKING_COROUTINE: &str = "Lkotlinx/coroutines/BlockingCoroutine;";
+/// What async() returns
+pub const DEFERRED_COROUTINE: &str = "Lkotlinx/coroutines/DeferredCoroutine;";
+/// What launch() returns
+pub const STANDALONE_COROUTINE: &str = "Lkotlinx/coroutines/StandaloneCoroutine;";
+
+const SUSPENDED_FIELD: &str = "COROUTINE_SUSPENDED";
+/// Field of a continuation holding the continuation to resume once it is done
+const COMPLETION_FIELD: &str = "completion";
+/// Field of a BlockingCoroutine, DeferredCoroutine or StandaloneCoroutine holding what the
+/// coroutine completed with, a value or a Result.Failure. Absent while it runs.
+const OUTCOME_FIELD: &str = "outcome";
+/// Field of a Result.Failure holding the exception
+const EXCEPTION_FIELD: &str = "exception";
+
+const INVOKE_SUSPEND: &str = "invokeSuspend:(Ljava/lang/Object;)Ljava/lang/Object;";
+const RESUME_WITH: &str = "resumeWith:(Ljava/lang/Object;)V";
+
+/// Superclasses of the coroutine classes the interpreter knows without a class file
+pub fn builtin_super_class(class_name: &str) -> Option<&'static str> {
+    match class_name {
+        BASE_CONTINUATION_IMPL => Some("Ljava/lang/Object;"),
+        CONTINUATION_IMPL | RESTRICTED_CONTINUATION_IMPL => Some(BASE_CONTINUATION_IMPL),
+        SUSPEND_LAMBDA => Some(CONTINUATION_IMPL),
+        RESTRICTED_SUSPEND_LAMBDA => Some(RESTRICTED_CONTINUATION_IMPL),
+        _ => None,
+    }
+}
+
+/// Whether calling `method_name` of `class` with `argc` arguments calls a suspend function,
+/// which takes a Continuation after them
+pub fn is_suspend_call(class: &DexClass, method_name: &str, argc: usize) -> bool {
+    class.resolve_method_key(method_name, Some(argc))
+        .and_then(|key| class.methods.get(&key))
+        .is_some_and(|method| method.parameters.len() == argc + 1 && method.parameters.last().is_some_and(|last| last == CONTINUATION))
+}
+
+/// The COROUTINE_SUSPENDED marker, compared by identity
+pub fn suspended(interpreter: &mut Interpreter) -> DexValue {
+    interpreter.initialize_class(COROUTINE_SINGLETONS);
+    interpreter.statics.get(COROUTINE_SINGLETONS)
+        .and_then(|statics| statics.get(SUSPENDED_FIELD).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+/// CoroutineSingletons' static fields
+pub fn coroutine_singletons_statics(interpreter: &mut Interpreter) -> HashMap<String, DexValue> {
+    let marker = interpreter.alloc_object(COROUTINE_SINGLETONS);
+    HashMap::from([(SUSPENDED_FIELD.to_string(), DexValue::Object(marker))])
+}
+
+/// A completion for the host or runBlocking to wait on
+pub fn new_completion(interpreter: &mut Interpreter) -> ObjectId {
+    interpreter.alloc_object(BLOCKING_COROUTINE)
+}
+
+/// Queue `continuation` to be resumed with `outcome`, for a platform suspend function that is
+/// about to return COROUTINE_SUSPENDED
+pub fn resume_later(interpreter: &mut Interpreter, continuation: &DexValue, outcome: DexValue) {
+    if let DexValue::Object(id) = continuation {
+        interpreter.resumptions.push_back((*id, outcome));
+    }
+}
+
+/// The outcome of a coroutine that threw `exception`
+pub fn failure(interpreter: &mut Interpreter, exception: DexValue) -> DexValue {
+    let id = interpreter.alloc_object(RESULT_FAILURE);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(EXCEPTION_FIELD.to_string(), exception);
+    DexValue::Object(id)
+}
+
+/// The exception of a Result.Failure outcome
+fn failure_exception(interpreter: &Interpreter, outcome: &DexValue) -> Option<DexValue> {
+    let DexValue::Object(id) = outcome else {
+        return None;
+    };
+    let object = interpreter.heap.get(id).filter(|object| object.class_name == RESULT_FAILURE)?;
+    Some(object.fields.get(EXCEPTION_FIELD).cloned().unwrap_or(DexValue::Null))
+}
+
+/// The result of a suspend call that returned `value` with `completion` as its continuation,
+/// waiting for the completion if the call suspended. `None` with the exception pending when
+/// the coroutine failed.
+pub fn complete(interpreter: &mut Interpreter, completion: ObjectId, value: Option<DexValue>) -> Option<DexValue> {
+    let marker = suspended(interpreter);
+    match value {
+        Some(value) if value == marker => join(interpreter, completion),
+        value => value,
+    }
+}
+
+/// Resume queued continuations until `completion` has its outcome, which is returned
+fn join(interpreter: &mut Interpreter, completion: ObjectId) -> Option<DexValue> {
+    loop {
+        let outcome = interpreter.heap.get(&completion).and_then(|object| object.fields.get(OUTCOME_FIELD)).cloned();
+        if let Some(outcome) = outcome {
+            if let Some(exception) = failure_exception(interpreter, &outcome) {
+                interpreter.throw(exception);
+                return None;
+            }
+            return Some(outcome);
+        }
+
+        let Some((continuation, result)) = interpreter.resumptions.pop_front() else {
+            interpreter.throw_new(ILLEGAL_STATE_EXCEPTION, "Coroutine suspended with nothing left to resume it");
+            return None;
+        };
+        interpreter.invoke_virtual(continuation, RESUME_WITH, vec![result]);
+        if interpreter.pending_exception.is_some() {
+            return None;
+        }
+    }
+}
+
+/// Run the suspend function object `block` with `completion` as its continuation, like
+/// `block.invoke(scope, completion)`. A throw comes back as a pending exception.
+fn start(interpreter: &mut Interpreter, block: &DexValue, completion: DexValue) -> Option<DexValue> {
+    let scope = interpreter.alloc_object(CONTEXT_SCOPE);
+    interpreter.invoke_function(block, vec![DexValue::Object(scope), completion])
+}
+
+/// Start `block` as a coroutine of its own that completes `job`, a DeferredCoroutine or a
+/// StandaloneCoroutine, and return the job
+fn start_job(interpreter: &mut Interpreter, class_name: &str, block: &DexValue) -> DexValue {
+    let job = interpreter.alloc_object(class_name);
+    let value = start(interpreter, block, DexValue::Object(job));
+    let outcome = match interpreter.pending_exception.take() {
+        Some(pending) => Some(failure(interpreter, pending.exception)),
+        None => Some(value.unwrap_or(DexValue::Null)).filter(|value| *value != suspended(interpreter)),
+    };
+    if let Some(outcome) = outcome {
+        set_outcome(interpreter, job, outcome);
+    }
+    DexValue::Object(job)
+}
+
+fn set_outcome(interpreter: &mut Interpreter, job: ObjectId, outcome: DexValue) {
+    if let Some(object) = interpreter.heap.get_mut(&job) {
+        object.fields.insert(OUTCOME_FIELD.to_string(), outcome);
+    }
+}
+
+fn field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> DexValue {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.fields.get(name).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn set_field(interpreter: &mut Interpreter, this: Option<ObjectId>, name: &str, value: DexValue) {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(name.to_string(), value);
+    }
+}
+
+fn arg(args: &[DexValue], index: usize) -> DexValue {
+    args.get(index).cloned().unwrap_or(DexValue::Null)
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (INTRINSICS_KT, "getCOROUTINE_SUSPENDED:()Ljava/lang/Object;") => |interpreter, _, _| suspended(interpreter),
+        (DEBUG_PROBES_KT, "probeCoroutineCreated:(Lkotlin/coroutines/Continuation;)Lkotlin/coroutines/Continuation;") => |_, _, args| arg(&args, 0),
+        (DEBUG_PROBES_KT, "probeCoroutineResumed:(Lkotlin/coroutines/Continuation;)V" | "probeCoroutineSuspended:(Lkotlin/coroutines/Continuation;)V") => |_, _, _| DexValue::Void,
+
+        // The super constructors of the continuations and suspend lambdas the compiler generates
+        (CONTINUATION_IMPL | RESTRICTED_CONTINUATION_IMPL, "<init>:(Lkotlin/coroutines/Continuation;)V" | "<init>:(Lkotlin/coroutines/Continuation;Lkotlin/coroutines/CoroutineContext;)V") => {
+            |interpreter, this, args| {
+                set_field(interpreter, this, COMPLETION_FIELD, arg(&args, 0));
+                DexValue::Void
+            }
+        }
+        (SUSPEND_LAMBDA | RESTRICTED_SUSPEND_LAMBDA, "<init>:(ILkotlin/coroutines/Continuation;)V") => |interpreter, this, args| {
+            set_field(interpreter, this, COMPLETION_FIELD, arg(&args, 1));
+            DexValue::Void
+        },
+        (BASE_CONTINUATION_IMPL, "resumeWith:(Ljava/lang/Object;)V") => continuation_resume_with,
+        (BASE_CONTINUATION_IMPL, "getCompletion:()Lkotlin/coroutines/Continuation;") => |interpreter, this, _| field(interpreter, this, COMPLETION_FIELD),
+        (BASE_CONTINUATION_IMPL | CONTEXT_SCOPE | BLOCKING_COROUTINE | DEFERRED_COROUTINE | STANDALONE_COROUTINE, "getContext:()Lkotlin/coroutines/CoroutineContext;")
+        | (CONTEXT_SCOPE, "getCoroutineContext:()Lkotlin/coroutines/CoroutineContext;") => {
+            |interpreter, _, _| DexValue::Object(interpreter.alloc_object(EMPTY_COROUTINE_CONTEXT))
+        }
+
+        (BLOCKING_COROUTINE | DEFERRED_COROUTINE | STANDALONE_COROUTINE, "resumeWith:(Ljava/lang/Object;)V") => |interpreter, this, args| {
+            set_field(interpreter, this, OUTCOME_FIELD, arg(&args, 0));
+            DexValue::Void
+        },
+        (DEFERRED_COROUTINE, "await:(Lkotlin/coroutines/Continuation;)Ljava/lang/Object;") => |interpreter, this, _| {
+            this.and_then(|job| join(interpreter, job)).unwrap_or(DexValue::Void)
+        },
+        (DEFERRED_COROUTINE | STANDALONE_COROUTINE, "join:(Lkotlin/coroutines/Continuation;)Ljava/lang/Object;") => |interpreter, this, _| {
+            match this.and_then(|job| join(interpreter, job)) {
+                Some(_) => DexValue::Null,
+                None => DexValue::Void,
+            }
+        },
+        (DEFERRED_COROUTINE | STANDALONE_COROUTINE, "isCompleted:()Z") => |interpreter, this, _| {
+            DexValue::Boolean(this.and_then(|id| interpreter.heap.get(&id)).is_some_and(|object| object.fields.contains_key(OUTCOME_FIELD)))
+        },
+
+        (RESULT_KT, "throwOnFailure:(Ljava/lang/Object;)V") => |interpreter, _, args| {
+            if let Some(exception) = failure_exception(interpreter, &arg(&args, 0)) {
+                interpreter.throw(exception);
+            }
+            DexValue::Void
+        },
+        (RESULT_KT, "createFailure:(Ljava/lang/Throwable;)Ljava/lang/Object;") => |interpreter, _, args| failure(interpreter, arg(&args, 0)),
+        (RESULT, "constructor-impl:(Ljava/lang/Object;)Ljava/lang/Object;") => |_, _, args| arg(&args, 0),
+        (RESULT, "isFailure-impl:(Ljava/lang/Object;)Z") => |interpreter, _, args| {
+            DexValue::Boolean(failure_exception(interpreter, &arg(&args, 0)).is_some())
+        },
+        (RESULT, "isSuccess-impl:(Ljava/lang/Object;)Z") => |interpreter, _, args| {
+            DexValue::Boolean(failure_exception(interpreter, &arg(&args, 0)).is_none())
+        },
+        (RESULT, "exceptionOrNull-impl:(Ljava/lang/Object;)Ljava/lang/Throwable;") => |interpreter, _, args| {
+            failure_exception(interpreter, &arg(&args, 0)).unwrap_or(DexValue::Null)
+        },
+
+        // Run in the caller's coroutine, the context only ever picks a thread
+        (BUILDERS_KT, "withContext:(Lkotlin/coroutines/CoroutineContext;Lkotlin/jvm/functions/Function2;Lkotlin/coroutines/Continuation;)Ljava/lang/Object;") => {
+            |interpreter, _, args| start(interpreter, &arg(&args, 1), arg(&args, 2)).unwrap_or(DexValue::Void)
+        }
+        (COROUTINE_SCOPE_KT, "coroutineScope:(Lkotlin/jvm/functions/Function2;Lkotlin/coroutines/Continuation;)Ljava/lang/Object;")
+        | (SUPERVISOR_KT, "supervisorScope:(Lkotlin/jvm/functions/Function2;Lkotlin/coroutines/Continuation;)Ljava/lang/Object;") => {
+            |interpreter, _, args| start(interpreter, &arg(&args, 0), arg(&args, 1)).unwrap_or(DexValue::Void)
+        }
+        (BUILDERS_KT, "runBlocking:(Lkotlin/coroutines/CoroutineContext;Lkotlin/jvm/functions/Function2;)Ljava/lang/Object;"
+            | "runBlocking$default:(Lkotlin/coroutines/CoroutineContext;Lkotlin/jvm/functions/Function2;ILjava/lang/Object;)Ljava/lang/Object;") => {
+            |interpreter, _, args| {
+                let completion = new_completion(interpreter);
+                let value = start(interpreter, &arg(&args, 1), DexValue::Object(completion));
+                if interpreter.pending_exception.is_some() {
+                    return DexValue::Void;
+                }
+                complete(interpreter, completion, value).unwrap_or(DexValue::Void)
+            }
+        }
+        (BUILDERS_KT, "async:(Lkotlinx/coroutines/CoroutineScope;Lkotlin/coroutines/CoroutineContext;Lkotlinx/coroutines/CoroutineStart;Lkotlin/jvm/functions/Function2;)Lkotlinx/coroutines/Deferred;"
+            | "async$default:(Lkotlinx/coroutines/CoroutineScope;Lkotlin/coroutines/CoroutineContext;Lkotlinx/coroutines/CoroutineStart;Lkotlin/jvm/functions/Function2;ILjava/lang/Object;)Lkotlinx/coroutines/Deferred;") => {
+            |interpreter, _, args| start_job(interpreter, DEFERRED_COROUTINE, &arg(&args, 3))
+        }
+        (BUILDERS_KT, "launch:(Lkotlinx/coroutines/CoroutineScope;Lkotlin/coroutines/CoroutineContext;Lkotlinx/coroutines/CoroutineStart;Lkotlin/jvm/functions/Function2;)Lkotlinx/coroutines/Job;"
+            | "launch$default:(Lkotlinx/coroutines/CoroutineScope;Lkotlin/coroutines/CoroutineContext;Lkotlinx/coroutines/CoroutineStart;Lkotlin/jvm/functions/Function2;ILjava/lang/Object;)Lkotlinx/coroutines/Job;") => {
+            |interpreter, _, args| start_job(interpreter, STANDALONE_COROUTINE, &arg(&args, 3))
+        }
+        (AWAIT_KT, "awaitAll:(Ljava/util/Collection;Lkotlin/coroutines/Continuation;)Ljava/lang/Object;") => await_all,
+        (DELAY_KT, "delay:(JLkotlin/coroutines/Continuation;)Ljava/lang/Object;") => |_, _, args| {
+            let millis = parameters(&args).first().and_then(|value| value.as_long()).unwrap_or(0);
+            if millis > 0 {
+                clock::sleep(Duration::from_millis(millis as u64));
+            }
+            DexValue::Null
+        },
+        (DISPATCHERS, "getIO:()Lkotlinx/coroutines/CoroutineDispatcher;"
+            | "getDefault:()Lkotlinx/coroutines/CoroutineDispatcher;"
+            | "getUnconfined:()Lkotlinx/coroutines/CoroutineDispatcher;"
+            | "getMain:()Lkotlinx/coroutines/MainCoroutineDispatcher;") => |interpreter, _, _| DexValue::Object(interpreter.alloc_object(DISPATCHER)),
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// `BaseContinuationImpl.resumeWith(result)`: run the state machine on, and once it is done
+/// resume its completion with the outcome, looping instead of recursing up the chain of
+/// interpreted continuations like the Kotlin runtime does
+fn continuation_resume_with(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut current = this;
+    let mut result = arg(&args, 0);
+    while let Some(continuation) = current {
+        let completion = field(interpreter, current, COMPLETION_FIELD);
+        let value = interpreter.invoke_virtual(continuation, INVOKE_SUSPEND, vec![result]);
+        let outcome = match interpreter.pending_exception.take() {
+            Some(pending) => failure(interpreter, pending.exception),
+            None => value.unwrap_or(DexValue::Null),
+        };
+        if outcome == suspended(interpreter) {
+            break;
+        }
+
+        let DexValue::Object(next) = completion else {
+            break;
+        };
+        let class_name = interpreter.heap.get(&next).map(|object| object.class_name.clone()).unwrap_or_default();
+        if interpreter.is_subclass_of(&class_name, BASE_CONTINUATION_IMPL) {
+            current = Some(next);
+            result = outcome;
+        } else {
+            interpreter.invoke_virtual(next, RESUME_WITH, vec![outcome]);
+            break;
+        }
+    }
+    DexValue::Void
+}
+
+/// `awaitAll(deferreds)`, the values in order or the first failure
+fn await_all(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut values = Vec::new();
+    for deferred in elements_of(interpreter, &arg(&args, 0)) {
+        let DexValue::Object(job) = deferred else {
+            continue;
+        };
+        match join(interpreter, job) {
+            Some(value) => values.push(value),
+            None => return DexValue::Void,
+        }
+    }
+    DexValue::Object(new_list(interpreter, values))
+}
@@ -0,0 +1,1067 @@
+// Date handling for the java.util/java.text shims. Chapter dates are parsed in the source's
+// locale and time zone rather than the device's, so every source carries a DateContext set by
+// the host or derived from its metadata. Time zones are fixed offsets, daylight saving time is
+// not modelled, and the calendar arithmetic is chrono's.
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Timelike};
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::interpreter::{boxing, clock};
+use crate::interpreter::exception::{ILLEGAL_ARGUMENT_EXCEPTION, PARSE_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::PlatformMethod;
+use crate::types::{DexValue, ObjectId};
+use crate::utils::class_descriptor;
+
+pub const LOCALE: &str = "Ljava/util/Locale;";
+pub const TIME_ZONE: &str = "Ljava/util/TimeZone;";
+pub const DATE: &str = "Ljava/util/Date;";
+pub const SIMPLE_DATE_FORMAT: &str = "Ljava/text/SimpleDateFormat;";
+pub const DATE_FORMAT: &str = "Ljava/text/DateFormat;";
+pub const PARSE_POSITION: &str = "Ljava/text/ParsePosition;";
+pub const CALENDAR: &str = "Ljava/util/Calendar;";
+pub const SYSTEM: &str = "Ljava/lang/System;";
+pub const TIME_UNIT: &str = "Ljava/util/concurrent/TimeUnit;";
+
+const LOCALE_TAG_FIELD: &str = "tag";
+const TIME_ZONE_ID_FIELD: &str = "id";
+const TIME_FIELD: &str = "time";
+const PATTERN_FIELD: &str = "pattern";
+const LENIENT_FIELD: &str = "lenient";
+const INDEX_FIELD: &str = "index";
+const ERROR_INDEX_FIELD: &str = "errorIndex";
+const NAME_FIELD: &str = "name";
+const ORDINAL_FIELD: &str = "ordinal";
+
+const MILLIS_PER_MINUTE: i64 = 60_000;
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+lazy_static! {
+    /// Contexts set by the host, keyed by the descriptor of the source's main class
+    static ref SOURCE_CONTEXTS: Mutex<HashMap<String, DateContext>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateContext {
+    /// BCP 47 language tag, e.g. `en-US`
+    pub locale: String,
+    /// Time zone id, e.g. `UTC`, `Asia/Tokyo` or `GMT+09:00`
+    pub time_zone: String,
+}
+
+impl Default for DateContext {
+    fn default() -> Self {
+        Self { locale: "en".to_string(), time_zone: "UTC".to_string() }
+    }
+}
+
+pub fn set_source_context(source: &str, context: DateContext) {
+    SOURCE_CONTEXTS.lock().unwrap().insert(class_descriptor(source), context);
+}
+
+/// Context of the running source. Without one from the host, the locale follows the source's
+/// `lang` and the time zone is UTC.
+pub fn source_context(interpreter: &Interpreter) -> DateContext {
+    if let Some(context) = interpreter.source_name().and_then(|source| SOURCE_CONTEXTS.lock().unwrap().get(&source).cloned()) {
+        return context;
+    }
+
+    let lang = interpreter.main_object
+        .and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| match object.fields.get("lang") {
+            Some(DexValue::String(lang)) if !lang.is_empty() && lang != "all" => Some(lang.clone()),
+            _ => None,
+        });
+    DateContext { locale: lang.unwrap_or_else(|| "en".to_string()), ..DateContext::default() }
+}
+
+/// Offset from UTC in minutes, `None` for ids that aren't known
+pub fn time_zone_offset(id: &str) -> Option<i32> {
+    let named = match id {
+        "UTC" | "GMT" | "Z" | "Etc/UTC" | "Etc/GMT" | "Europe/London" | "Europe/Lisbon" => Some(0),
+        "Europe/Paris" | "Europe/Berlin" | "Europe/Madrid" | "Europe/Rome" | "Europe/Amsterdam" | "Europe/Warsaw" => Some(60),
+        "Europe/Istanbul" | "Europe/Moscow" => Some(180),
+        "Asia/Kolkata" | "Asia/Calcutta" => Some(330),
+        "Asia/Jakarta" | "Asia/Bangkok" | "Asia/Ho_Chi_Minh" | "Asia/Saigon" => Some(420),
+        "Asia/Shanghai" | "Asia/Hong_Kong" | "Asia/Taipei" | "Asia/Singapore" | "Asia/Manila" => Some(480),
+        "Asia/Tokyo" | "Asia/Seoul" => Some(540),
+        "America/Sao_Paulo" | "America/Argentina/Buenos_Aires" => Some(-180),
+        "America/New_York" => Some(-300),
+        "America/Chicago" | "America/Mexico_City" => Some(-360),
+        "America/Los_Angeles" => Some(-480),
+        // Abbreviations seen in dates, with their fixed offsets like the JVM's own
+        "JST" | "KST" => Some(540),
+        "CET" | "BST" => Some(60),
+        "CEST" => Some(120),
+        "EST" | "CDT" => Some(-300),
+        "EDT" => Some(-240),
+        "MST" | "PDT" => Some(-420),
+        "PST" => Some(-480),
+        _ => None,
+    };
+    if named.is_some() {
+        return named;
+    }
+
+    // GMT+9, UTC-05:30, +0900, +09:00
+    let offset = id.strip_prefix("GMT").or_else(|| id.strip_prefix("UTC")).unwrap_or(id);
+    let (sign, digits) = match offset.chars().next()? {
+        '+' => (1, &offset[1..]),
+        '-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() > 2 => digits.split_at(digits.len() - 2),
+        None => (digits, "0"),
+    };
+    let (hours, minutes) = (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?);
+    (hours <= 18 && minutes < 60).then_some(sign * (hours * 60 + minutes))
+}
+
+fn format_offset(offset: i32, separator: &str) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    format!("{}{:02}{}{:02}", sign, offset.abs() / 60, separator, offset.abs() % 60)
+}
+
+/// Local date and time fields of an instant
+#[derive(Debug, Clone, Copy, Default)]
+struct Fields {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+    millisecond: i64,
+    /// 1 for Sunday through 7 for Saturday, like Calendar.DAY_OF_WEEK
+    day_of_week: i64,
+    day_of_year: i64,
+}
+
+impl Fields {
+    /// Instants beyond the years chrono covers read as the epoch
+    fn of(millis: i64, offset: i32) -> Self {
+        let local = DateTime::from_timestamp_millis(millis.saturating_add(offset as i64 * MILLIS_PER_MINUTE))
+            .unwrap_or_default()
+            .naive_utc();
+
+        Fields {
+            year: local.year() as i64,
+            month: local.month() as i64,
+            day: local.day() as i64,
+            hour: local.hour() as i64,
+            minute: local.minute() as i64,
+            second: local.second() as i64,
+            millisecond: local.and_utc().timestamp_subsec_millis() as i64,
+            day_of_week: local.weekday().number_from_sunday() as i64,
+            day_of_year: local.ordinal() as i64,
+        }
+    }
+
+    /// Months and days out of range roll over into the next larger field, like a lenient Calendar
+    fn to_millis(self, offset: i32) -> i64 {
+        let month_index = self.month - 1;
+        let year = self.year + month_index.div_euclid(12);
+        let month = month_index.rem_euclid(12) + 1;
+
+        let first_of_month = first_of_month(year, month).map_or(0, |date| date.and_time(NaiveTime::MIN).and_utc().timestamp_millis());
+        first_of_month + (self.day - 1) * MILLIS_PER_DAY + self.hour * 3_600_000 + self.minute * 60_000 + self.second * 1000 + self.millisecond
+            - offset as i64 * MILLIS_PER_MINUTE
+    }
+
+    /// Whether every field is in range, which a non-lenient format requires
+    fn is_valid(&self) -> bool {
+        first_of_month(self.year, self.month).is_some()
+            && (1..=days_in_month(self.year, self.month)).contains(&self.day)
+            && NaiveTime::from_hms_milli_opt(self.hour as u32, self.minute as u32, self.second as u32, self.millisecond as u32).is_some()
+    }
+}
+
+fn first_of_month(year: i64, month: i64) -> Option<NaiveDate> {
+    NaiveDate::from_ymd_opt(i32::try_from(year).ok()?, u32::try_from(month).ok()?, 1)
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    let next = if month == 12 { first_of_month(year + 1, 1) } else { first_of_month(year, month + 1) };
+    match (first_of_month(year, month), next) {
+        (Some(first), Some(next)) => (next - first).num_days(),
+        _ => 31,
+    }
+}
+
+fn language(locale: &str) -> &str {
+    locale.split(['-', '_']).next().unwrap_or("")
+}
+
+/// Month and weekday names (Sunday first) of the supported languages, English otherwise. Months
+/// are in the form used inside dates, the genitive for Russian.
+fn names(locale: &str) -> ([&'static str; 12], [&'static str; 7]) {
+    match language(locale) {
+        "es" => (
+            ["enero", "febrero", "marzo", "abril", "mayo", "junio", "julio", "agosto", "septiembre", "octubre", "noviembre", "diciembre"],
+            ["domingo", "lunes", "martes", "miércoles", "jueves", "viernes", "sábado"],
+        ),
+        "pt" => (
+            ["janeiro", "fevereiro", "março", "abril", "maio", "junho", "julho", "agosto", "setembro", "outubro", "novembro", "dezembro"],
+            ["domingo", "segunda-feira", "terça-feira", "quarta-feira", "quinta-feira", "sexta-feira", "sábado"],
+        ),
+        "fr" => (
+            ["janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre", "décembre"],
+            ["dimanche", "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi"],
+        ),
+        "de" => (
+            ["Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober", "November", "Dezember"],
+            ["Sonntag", "Montag", "Dienstag", "Mittwoch", "Donnerstag", "Freitag", "Samstag"],
+        ),
+        "it" => (
+            ["gennaio", "febbraio", "marzo", "aprile", "maggio", "giugno", "luglio", "agosto", "settembre", "ottobre", "novembre", "dicembre"],
+            ["domenica", "lunedì", "martedì", "mercoledì", "giovedì", "venerdì", "sabato"],
+        ),
+        "id" | "in" => (
+            ["Januari", "Februari", "Maret", "April", "Mei", "Juni", "Juli", "Agustus", "September", "Oktober", "November", "Desember"],
+            ["Minggu", "Senin", "Selasa", "Rabu", "Kamis", "Jumat", "Sabtu"],
+        ),
+        "ru" => (
+            ["января", "февраля", "марта", "апреля", "мая", "июня", "июля", "августа", "сентября", "октября", "ноября", "декабря"],
+            ["воскресенье", "понедельник", "вторник", "среда", "четверг", "пятница", "суббота"],
+        ),
+        "tr" => (
+            ["Ocak", "Şubat", "Mart", "Nisan", "Mayıs", "Haziran", "Temmuz", "Ağustos", "Eylül", "Ekim", "Kasım", "Aralık"],
+            ["Pazar", "Pazartesi", "Salı", "Çarşamba", "Perşembe", "Cuma", "Cumartesi"],
+        ),
+        _ => (
+            ["January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November", "December"],
+            ["Sunday", "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday"],
+        ),
+    }
+}
+
+/// AM and PM markers of the locale's language
+fn am_pm_markers(locale: &str) -> [&'static str; 2] {
+    match language(locale) {
+        "ja" => ["午前", "午後"],
+        "zh" => ["上午", "下午"],
+        "ko" => ["오전", "오후"],
+        _ => ["AM", "PM"],
+    }
+}
+
+fn abbreviation(name: &str) -> String {
+    name.chars().take(3).collect()
+}
+
+/// Lowercase `c` without its accent, so "MARCO" matches "março" and "fevrier" "février"
+fn fold(c: char) -> char {
+    match c.to_lowercase().next().unwrap_or(c) {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' => 'a',
+        'ç' => 'c',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' | 'ı' => 'i',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ğ' => 'g',
+        'ş' => 's',
+        c => c,
+    }
+}
+
+/// Length in `text` of `prefix` if `text` starts with it, ignoring case and accents
+fn prefix_length(text: &str, prefix: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for expected in prefix.chars() {
+        let (_, c) = chars.next()?;
+        if fold(c) != fold(expected) {
+            return None;
+        }
+    }
+    Some(chars.next().map_or(text.len(), |(index, _)| index))
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Field(char, usize),
+    Literal(String),
+}
+
+fn tokenize(pattern: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            // Quoted text, '' is a single quote
+            let mut literal = String::new();
+            if chars.peek() == Some(&'\'') {
+                chars.next();
+                literal.push('\'');
+            } else {
+                while let Some(c) = chars.next() {
+                    if c == '\'' {
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                            literal.push('\'');
+                        } else {
+                            break;
+                        }
+                    } else {
+                        literal.push(c);
+                    }
+                }
+            }
+            tokens.push(Token::Literal(literal));
+        } else if c.is_ascii_alphabetic() {
+            let mut count = 1;
+            while chars.peek() == Some(&c) {
+                chars.next();
+                count += 1;
+            }
+            tokens.push(Token::Field(c, count));
+        } else {
+            tokens.push(Token::Literal(c.to_string()));
+        }
+    }
+    tokens
+}
+
+fn is_numeric_field(token: Option<&Token>) -> bool {
+    match token {
+        Some(Token::Field('M' | 'L', count)) => *count < 3,
+        Some(Token::Field(c, _)) => "yYdDFHhKkmsSuwW".contains(*c),
+        _ => false,
+    }
+}
+
+/// Parse the start of `text` like SimpleDateFormat.parse, returning the instant and the length
+/// of the text read. A non-lenient format rejects fields out of range instead of rolling them
+/// over.
+pub fn parse(pattern: &str, text: &str, locale: &str, offset: i32, lenient: bool) -> Option<(i64, usize)> {
+    let (months, days) = names(locale);
+    // Sites often write English names whatever their language
+    let (english_months, english_days) = names("en");
+    let tokens = tokenize(pattern);
+    let now = Fields::of(clock::now_millis(), offset);
+
+    let mut fields = Fields { year: 1970, month: 1, day: 1, ..Fields::default() };
+    let mut pm = None;
+    let mut before_christ = false;
+    let mut zone = None;
+    let mut rest = text;
+
+    for (i, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Literal(literal) if literal.trim().is_empty() => rest = rest.trim_start(),
+            Token::Literal(literal) => rest = rest.strip_prefix(literal.as_str())?,
+            Token::Field(c, count) if is_numeric_field(Some(token)) => {
+                // Abutting numeric fields such as yyyyMMdd are read by their width
+                let width = if is_numeric_field(tokens.get(i + 1)) { *count } else { usize::MAX };
+                let sign = if rest.starts_with('-') && *c == 'y' { rest = &rest[1..]; -1 } else { 1 };
+                let digits = rest.chars().take_while(|c| c.is_ascii_digit()).take(width).count();
+                if digits == 0 {
+                    return None;
+                }
+                let value: i64 = rest[..digits].parse().ok()?;
+                rest = &rest[digits..];
+
+                match c {
+                    'y' | 'Y' if *count <= 2 && digits == 2 => {
+                        // Two digit years land within 80 years before and 20 years after now
+                        let century = now.year - now.year.rem_euclid(100);
+                        let year = century + value;
+                        fields.year = if year > now.year + 20 { year - 100 } else { year };
+                    }
+                    'y' | 'Y' => fields.year = sign * value,
+                    'M' | 'L' => fields.month = value,
+                    'd' => fields.day = value,
+                    // The day of the year rolls over from January
+                    'D' => {
+                        fields.month = 1;
+                        fields.day = value;
+                    }
+                    'H' => fields.hour = value,
+                    'k' => fields.hour = value % 24,
+                    'h' => fields.hour = value % 12,
+                    'K' => fields.hour = value,
+                    'm' => fields.minute = value,
+                    's' => fields.second = value,
+                    'S' => fields.millisecond = value,
+                    // Week based fields and the day number of the week don't move the date
+                    _ => {}
+                }
+            }
+            Token::Field('M' | 'L', _) => {
+                let (month, length) = match_name(rest, &months).or_else(|| match_name(rest, &english_months))?;
+                fields.month = month as i64 + 1;
+                rest = &rest[length..];
+            }
+            Token::Field('E', _) => {
+                let (_, length) = match_name(rest, &days).or_else(|| match_name(rest, &english_days))?;
+                rest = &rest[length..];
+            }
+            Token::Field('G', _) => {
+                let (era, length) = match_name(rest, &["BC", "AD"])?;
+                before_christ = era == 0;
+                rest = &rest[length..];
+            }
+            Token::Field('a', _) => {
+                let [am, pm_marker] = am_pm_markers(locale);
+                let (is_pm, length) = [(am, false), (pm_marker, true), ("am", false), ("a.m.", false), ("pm", true), ("p.m.", true)]
+                    .into_iter()
+                    .find_map(|(marker, is_pm)| Some((is_pm, prefix_length(rest, marker)?)))?;
+                pm = Some(is_pm);
+                rest = &rest[length..];
+            }
+            Token::Field('z' | 'Z' | 'X', _) => {
+                let (offset, length) = parse_zone(rest)?;
+                zone = Some(offset);
+                rest = &rest[length..];
+            }
+            Token::Field(..) => return None,
+        }
+    }
+
+    if pm == Some(true) && fields.hour < 12 {
+        fields.hour += 12;
+    }
+    if before_christ {
+        fields.year = 1 - fields.year;
+    }
+    if !lenient && !fields.is_valid() {
+        return None;
+    }
+    Some((fields.to_millis(zone.unwrap_or(offset)), text.len() - rest.len()))
+}
+
+/// Index of the full or abbreviated name at the start of `text` and the length read, ignoring
+/// case and accents. Any start of a name of 3 letters or more is taken for an abbreviation,
+/// with or without a period, e.g. "Sept." or "févr".
+fn match_name(text: &str, names: &[&str]) -> Option<(usize, usize)> {
+    let full = names.iter().enumerate()
+        .filter_map(|(index, name)| Some((index, prefix_length(text, name)?)))
+        .max_by_key(|(_, length)| *length);
+    if full.is_some() {
+        return full;
+    }
+
+    let word = &text[..text.find(|c: char| !c.is_alphabetic()).unwrap_or(text.len())];
+    if word.chars().count() < 3 {
+        return None;
+    }
+    let index = names.iter().position(|name| prefix_length(name, word).is_some())?;
+    let length = if text[word.len()..].starts_with('.') { word.len() + 1 } else { word.len() };
+    Some((index, length))
+}
+
+/// Offset and length of the time zone at the start of `text`: `Z`, `+09:00`, `+0900`, `GMT+9`
+/// or an id such as `UTC`, `JST` or `Asia/Tokyo`
+fn parse_zone(text: &str) -> Option<(i32, usize)> {
+    let length = text.find(|c: char| !(c.is_ascii_alphanumeric() || "+-:/_".contains(c))).unwrap_or(text.len());
+    // The longest known zone, so a zone followed by digits like "+0900 2024" is still read
+    (1..=length).rev().find_map(|end| time_zone_offset(&text[..end]).map(|offset| (offset, end)))
+}
+
+pub fn format(pattern: &str, millis: i64, locale: &str, time_zone: &str) -> String {
+    let (months, days) = names(locale);
+    let [am, pm] = am_pm_markers(locale);
+    let offset = time_zone_offset(time_zone).unwrap_or(0);
+    let fields = Fields::of(millis, offset);
+
+    let number = |value: i64, count: usize| format!("{:0width$}", value, width = count);
+    tokenize(pattern).into_iter()
+        .map(|token| match token {
+            Token::Literal(literal) => literal,
+            Token::Field('G', _) => if fields.year > 0 { "AD" } else { "BC" }.to_string(),
+            Token::Field('y' | 'Y', 2) => number(fields.year.rem_euclid(100), 2),
+            Token::Field('y' | 'Y', count) => number(fields.year, count),
+            Token::Field('M' | 'L', count) if count >= 4 => months[fields.month as usize - 1].to_string(),
+            Token::Field('M' | 'L', 3) => abbreviation(months[fields.month as usize - 1]),
+            Token::Field('M' | 'L', count) => number(fields.month, count),
+            Token::Field('d', count) => number(fields.day, count),
+            Token::Field('D', count) => number(fields.day_of_year, count),
+            Token::Field('F', count) => number((fields.day - 1) / 7 + 1, count),
+            Token::Field('E', count) if count >= 4 => days[fields.day_of_week as usize - 1].to_string(),
+            Token::Field('E', _) => abbreviation(days[fields.day_of_week as usize - 1]),
+            // Day number of the week, 1 for Monday
+            Token::Field('u', count) => number((fields.day_of_week + 5) % 7 + 1, count),
+            Token::Field('H', count) => number(fields.hour, count),
+            Token::Field('k', count) => number(if fields.hour == 0 { 24 } else { fields.hour }, count),
+            Token::Field('h', count) => number(if fields.hour % 12 == 0 { 12 } else { fields.hour % 12 }, count),
+            Token::Field('K', count) => number(fields.hour % 12, count),
+            Token::Field('m', count) => number(fields.minute, count),
+            Token::Field('s', count) => number(fields.second, count),
+            Token::Field('S', count) => number(fields.millisecond, count),
+            Token::Field('a', _) => if fields.hour < 12 { am } else { pm }.to_string(),
+            Token::Field('z', _) if offset == 0 => "UTC".to_string(),
+            Token::Field('z', _) => format!("GMT{}", format_offset(offset, ":")),
+            Token::Field('Z', _) => format_offset(offset, ""),
+            Token::Field('X', _) if offset == 0 => "Z".to_string(),
+            Token::Field('X', count) => format_offset(offset, if count >= 3 { ":" } else { "" }),
+            Token::Field(c, count) => c.to_string().repeat(count),
+        })
+        .collect()
+}
+
+// Platform methods of the date classes. Objects keep their state in plain fields: Locale its
+// language tag, TimeZone its id, Date and Calendar their instant in milliseconds.
+
+fn field(interpreter: &Interpreter, id: Option<ObjectId>, name: &str) -> Option<DexValue> {
+    interpreter.heap.get(&id?)?.fields.get(name).cloned()
+}
+
+fn string_field(interpreter: &Interpreter, id: Option<ObjectId>, name: &str) -> Option<String> {
+    match field(interpreter, id, name)? {
+        DexValue::String(value) => Some(value),
+        _ => None,
+    }
+}
+
+fn set_field(interpreter: &mut Interpreter, id: Option<ObjectId>, name: &str, value: DexValue) {
+    if let Some(object) = id.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(name.to_string(), value);
+    }
+}
+
+fn object_arg(args: &[DexValue], index: usize) -> Option<ObjectId> {
+    match args.get(index) {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+fn string_arg(args: &[DexValue], index: usize) -> String {
+    match args.get(index) {
+        Some(DexValue::String(value)) => value.clone(),
+        _ => String::new(),
+    }
+}
+
+fn long_arg(args: &[DexValue], index: usize) -> i64 {
+    match args.get(index) {
+        Some(value) => value.as_long().or_else(|| value.as_int().map(|value| value as i64)).unwrap_or(0),
+        None => 0,
+    }
+}
+
+fn millis_of(interpreter: &Interpreter, id: Option<ObjectId>) -> i64 {
+    match field(interpreter, id, TIME_FIELD) {
+        Some(DexValue::Long(millis)) => millis,
+        _ => 0,
+    }
+}
+
+pub fn new_locale(interpreter: &mut Interpreter, tag: &str) -> DexValue {
+    let id = interpreter.alloc_object(LOCALE);
+    set_field(interpreter, Some(id), LOCALE_TAG_FIELD, DexValue::String(tag.to_string()));
+    DexValue::Object(id)
+}
+
+fn new_time_zone(interpreter: &mut Interpreter, time_zone: &str) -> DexValue {
+    // Like the JVM, unknown ids end up as GMT
+    let time_zone = if time_zone_offset(time_zone).is_some() { time_zone } else { "GMT" };
+    let id = interpreter.alloc_object(TIME_ZONE);
+    set_field(interpreter, Some(id), TIME_ZONE_ID_FIELD, DexValue::String(time_zone.to_string()));
+    DexValue::Object(id)
+}
+
+fn new_date(interpreter: &mut Interpreter, millis: i64) -> DexValue {
+    let id = interpreter.alloc_object(DATE);
+    set_field(interpreter, Some(id), TIME_FIELD, DexValue::Long(millis));
+    DexValue::Object(id)
+}
+
+/// Static Locale constants, seeded when the class is first used
+pub fn locale_statics(interpreter: &mut Interpreter) -> HashMap<String, DexValue> {
+    [
+        ("ROOT", ""), ("ENGLISH", "en"), ("US", "en-US"), ("UK", "en-GB"), ("CANADA", "en-CA"),
+        ("FRENCH", "fr"), ("FRANCE", "fr-FR"), ("GERMAN", "de"), ("GERMANY", "de-DE"),
+        ("ITALIAN", "it"), ("ITALY", "it-IT"), ("JAPANESE", "ja"), ("JAPAN", "ja-JP"),
+        ("KOREAN", "ko"), ("KOREA", "ko-KR"), ("CHINESE", "zh"), ("CHINA", "zh-CN"),
+        ("SIMPLIFIED_CHINESE", "zh-CN"), ("TRADITIONAL_CHINESE", "zh-TW"),
+    ]
+    .into_iter()
+    .map(|(name, tag)| (name.to_string(), new_locale(interpreter, tag)))
+    .collect()
+}
+
+fn locale_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let language = string_arg(&args, 0);
+    let country = string_arg(&args, 1);
+    let tag = if country.is_empty() { language } else { format!("{}-{}", language, country) };
+    set_field(interpreter, this, LOCALE_TAG_FIELD, DexValue::String(tag));
+    DexValue::Void
+}
+
+fn locale_get_default(interpreter: &mut Interpreter, _this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let locale = source_context(interpreter).locale;
+    new_locale(interpreter, &locale)
+}
+
+fn locale_for_language_tag(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    new_locale(interpreter, &string_arg(&args, 0))
+}
+
+fn locale_get_language(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let tag = string_field(interpreter, this, LOCALE_TAG_FIELD).unwrap_or_default();
+    DexValue::String(tag.split(['-', '_']).next().unwrap_or("").to_string())
+}
+
+fn locale_to_language_tag(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(string_field(interpreter, this, LOCALE_TAG_FIELD).unwrap_or_default())
+}
+
+fn locale_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(string_field(interpreter, this, LOCALE_TAG_FIELD).unwrap_or_default().replace('-', "_"))
+}
+
+fn time_zone_get_time_zone(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    new_time_zone(interpreter, &string_arg(&args, 0))
+}
+
+fn time_zone_get_default(interpreter: &mut Interpreter, _this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let time_zone = source_context(interpreter).time_zone;
+    new_time_zone(interpreter, &time_zone)
+}
+
+fn time_zone_get_id(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(string_field(interpreter, this, TIME_ZONE_ID_FIELD).unwrap_or_else(|| "GMT".to_string()))
+}
+
+fn time_zone_get_raw_offset(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let id = string_field(interpreter, this, TIME_ZONE_ID_FIELD).unwrap_or_default();
+    DexValue::Int(time_zone_offset(&id).unwrap_or(0) * 60_000)
+}
+
+fn date_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let millis = if args.is_empty() { clock::now_millis() } else { long_arg(&args, 0) };
+    set_field(interpreter, this, TIME_FIELD, DexValue::Long(millis));
+    DexValue::Void
+}
+
+fn date_get_time(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Long(millis_of(interpreter, this))
+}
+
+fn date_compare_to(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let ordering = millis_of(interpreter, this).cmp(&millis_of(interpreter, object_arg(&args, 0)));
+    DexValue::Int(ordering as i32)
+}
+
+fn date_before(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(millis_of(interpreter, this) < millis_of(interpreter, object_arg(&args, 0)))
+}
+
+fn date_after(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(millis_of(interpreter, this) > millis_of(interpreter, object_arg(&args, 0)))
+}
+
+fn date_set_time(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    set_field(interpreter, this, TIME_FIELD, DexValue::Long(long_arg(&args, 0)));
+    DexValue::Void
+}
+
+fn date_equals(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let other = object_arg(&args, 0).filter(|id| interpreter.heap.get(id).is_some_and(|object| object.class_name == DATE));
+    DexValue::Boolean(other.is_some() && millis_of(interpreter, this) == millis_of(interpreter, other))
+}
+
+fn date_hash_code(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let millis = millis_of(interpreter, this);
+    DexValue::Int((millis ^ (millis as u64 >> 32) as i64) as i32)
+}
+
+/// Date.toString() in the source's time zone, e.g. "Tue Mar 05 14:30:00 GMT+09:00 2024"
+fn date_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let time_zone = source_context(interpreter).time_zone;
+    DexValue::String(format("EEE MMM dd HH:mm:ss zzz yyyy", millis_of(interpreter, this), "en", &time_zone))
+}
+
+/// SimpleDateFormat(pattern) and SimpleDateFormat(pattern, locale), both start out in the
+/// source's time zone
+fn date_format_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let context = source_context(interpreter);
+    let locale = string_field(interpreter, object_arg(&args, 1), LOCALE_TAG_FIELD).unwrap_or(context.locale);
+
+    set_field(interpreter, this, PATTERN_FIELD, DexValue::String(string_arg(&args, 0)));
+    set_field(interpreter, this, LOCALE_TAG_FIELD, DexValue::String(locale));
+    set_field(interpreter, this, TIME_ZONE_ID_FIELD, DexValue::String(context.time_zone));
+    DexValue::Void
+}
+
+/// Parse `text` with the format `this`
+fn parse_with(interpreter: &Interpreter, this: Option<ObjectId>, text: &str) -> Option<(i64, usize)> {
+    let pattern = string_field(interpreter, this, PATTERN_FIELD).unwrap_or_default();
+    let locale = string_field(interpreter, this, LOCALE_TAG_FIELD).unwrap_or_default();
+    let time_zone = string_field(interpreter, this, TIME_ZONE_ID_FIELD).unwrap_or_default();
+    let lenient = field(interpreter, this, LENIENT_FIELD) != Some(DexValue::Boolean(false));
+    parse(&pattern, text, &locale, time_zone_offset(&time_zone).unwrap_or(0), lenient)
+}
+
+fn date_format_parse(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let text = string_arg(&args, 0);
+    match parse_with(interpreter, this, &text) {
+        Some((millis, _)) => new_date(interpreter, millis),
+        None => {
+            interpreter.throw_new(PARSE_EXCEPTION, &format!("Unparseable date: \"{}\"", text));
+            DexValue::Null
+        }
+    }
+}
+
+/// parse(text, position) reads from the index of the ParsePosition and moves it past the date.
+/// On failure it returns null and sets the error index instead of throwing.
+fn date_format_parse_from(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let text = string_arg(&args, 0);
+    let position = object_arg(&args, 1);
+    let index = field(interpreter, position, INDEX_FIELD).and_then(|index| index.as_int()).unwrap_or(0).max(0) as usize;
+    // ParsePosition indices count UTF-16 code units
+    let start = text.char_indices()
+        .scan(0, |units, (offset, c)| {
+            let start = *units;
+            *units += c.len_utf16();
+            Some((start, offset))
+        })
+        .find(|(units, _)| *units >= index)
+        .map_or(text.len(), |(_, offset)| offset);
+
+    match parse_with(interpreter, this, &text[start..]) {
+        Some((millis, length)) => {
+            let end = text[..start + length].encode_utf16().count();
+            set_field(interpreter, position, INDEX_FIELD, DexValue::Int(end as i32));
+            new_date(interpreter, millis)
+        }
+        None => {
+            set_field(interpreter, position, ERROR_INDEX_FIELD, DexValue::Int(index as i32));
+            DexValue::Null
+        }
+    }
+}
+
+/// format(Date) and format(Object), the latter also taking epoch millis as a Long or Integer
+fn date_format_format(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let date = object_arg(&args, 0).filter(|id| interpreter.heap.get(id).is_some_and(|object| object.class_name == DATE));
+    let millis = match date {
+        Some(id) => millis_of(interpreter, Some(id)),
+        None => match args.first().and_then(|value| boxing::unboxed(&interpreter.heap, value)) {
+            Some(DexValue::Long(millis)) => millis,
+            Some(DexValue::Int(millis)) => millis as i64,
+            _ => {
+                interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "Cannot format given Object as a Date");
+                return DexValue::Void;
+            }
+        },
+    };
+    let pattern = string_field(interpreter, this, PATTERN_FIELD).unwrap_or_default();
+    let locale = string_field(interpreter, this, LOCALE_TAG_FIELD).unwrap_or_default();
+    let time_zone = string_field(interpreter, this, TIME_ZONE_ID_FIELD).unwrap_or_default();
+    DexValue::String(format(&pattern, millis, &locale, &time_zone))
+}
+
+fn date_format_set_lenient(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let lenient = args.first().and_then(|lenient| lenient.as_int()).unwrap_or(1) != 0;
+    set_field(interpreter, this, LENIENT_FIELD, DexValue::Boolean(lenient));
+    DexValue::Void
+}
+
+fn date_format_is_lenient(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(field(interpreter, this, LENIENT_FIELD) != Some(DexValue::Boolean(false)))
+}
+
+fn date_format_apply_pattern(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    set_field(interpreter, this, PATTERN_FIELD, DexValue::String(string_arg(&args, 0)));
+    DexValue::Void
+}
+
+fn date_format_set_time_zone(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let time_zone = string_field(interpreter, object_arg(&args, 0), TIME_ZONE_ID_FIELD).unwrap_or_else(|| "GMT".to_string());
+    set_field(interpreter, this, TIME_ZONE_ID_FIELD, DexValue::String(time_zone));
+    DexValue::Void
+}
+
+fn date_format_get_time_zone(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let time_zone = string_field(interpreter, this, TIME_ZONE_ID_FIELD).unwrap_or_default();
+    new_time_zone(interpreter, &time_zone)
+}
+
+fn date_format_to_pattern(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(string_field(interpreter, this, PATTERN_FIELD).unwrap_or_default())
+}
+
+fn parse_position_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    set_field(interpreter, this, INDEX_FIELD, DexValue::Int(args.first().and_then(|index| index.as_int()).unwrap_or(0)));
+    set_field(interpreter, this, ERROR_INDEX_FIELD, DexValue::Int(-1));
+    DexValue::Void
+}
+
+fn parse_position_get(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> DexValue {
+    field(interpreter, this, name).unwrap_or(DexValue::Int(-1))
+}
+
+fn parse_position_set(interpreter: &mut Interpreter, this: Option<ObjectId>, name: &str, args: &[DexValue]) -> DexValue {
+    set_field(interpreter, this, name, DexValue::Int(args.first().and_then(|index| index.as_int()).unwrap_or(0)));
+    DexValue::Void
+}
+
+/// Calendar.getInstance() and its TimeZone/Locale overloads, set to now
+fn calendar_get_instance(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let time_zone = string_field(interpreter, object_arg(&args, 0), TIME_ZONE_ID_FIELD)
+        .unwrap_or_else(|| source_context(interpreter).time_zone);
+
+    let id = interpreter.alloc_object(CALENDAR);
+    set_field(interpreter, Some(id), TIME_FIELD, DexValue::Long(clock::now_millis()));
+    set_field(interpreter, Some(id), TIME_ZONE_ID_FIELD, DexValue::String(time_zone));
+    DexValue::Object(id)
+}
+
+fn calendar_offset(interpreter: &Interpreter, this: Option<ObjectId>) -> i32 {
+    let time_zone = string_field(interpreter, this, TIME_ZONE_ID_FIELD).unwrap_or_default();
+    time_zone_offset(&time_zone).unwrap_or(0)
+}
+
+fn calendar_get_time(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let millis = millis_of(interpreter, this);
+    new_date(interpreter, millis)
+}
+
+fn calendar_set_time(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let millis = millis_of(interpreter, object_arg(&args, 0));
+    set_field(interpreter, this, TIME_FIELD, DexValue::Long(millis));
+    DexValue::Void
+}
+
+fn calendar_set_time_in_millis(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    set_field(interpreter, this, TIME_FIELD, DexValue::Long(long_arg(&args, 0)));
+    DexValue::Void
+}
+
+/// Calendar field numbers, compilers inline them so only the values matter
+const YEAR: i32 = 1;
+const MONTH: i32 = 2;
+const WEEK_OF_YEAR: i32 = 3;
+const WEEK_OF_MONTH: i32 = 4;
+const DAY_OF_MONTH: i32 = 5;
+const DAY_OF_YEAR: i32 = 6;
+const DAY_OF_WEEK: i32 = 7;
+const HOUR: i32 = 10;
+const HOUR_OF_DAY: i32 = 11;
+const MINUTE: i32 = 12;
+const SECOND: i32 = 13;
+const MILLISECOND: i32 = 14;
+
+fn calendar_get(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let fields = Fields::of(millis_of(interpreter, this), calendar_offset(interpreter, this));
+    let value = match args.first().and_then(|field| field.as_int()).unwrap_or(0) {
+        YEAR => fields.year,
+        // Calendar months start at 0
+        MONTH => fields.month - 1,
+        DAY_OF_MONTH => fields.day,
+        DAY_OF_YEAR => fields.day_of_year,
+        DAY_OF_WEEK => fields.day_of_week,
+        HOUR => fields.hour % 12,
+        HOUR_OF_DAY => fields.hour,
+        MINUTE => fields.minute,
+        SECOND => fields.second,
+        MILLISECOND => fields.millisecond,
+        _ => 0,
+    };
+    DexValue::Int(value as i32)
+}
+
+/// Calendar.add(field, amount), overflowing into larger fields
+fn calendar_add(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let offset = calendar_offset(interpreter, this);
+    let mut fields = Fields::of(millis_of(interpreter, this), offset);
+    let amount = args.get(1).and_then(|amount| amount.as_int()).unwrap_or(0) as i64;
+
+    match args.first().and_then(|field| field.as_int()).unwrap_or(0) {
+        YEAR => fields.year += amount,
+        MONTH => {
+            fields.month += amount;
+            // Jan 31 + 1 month is the last day of February, not March 3rd
+            let month_index = fields.month - 1;
+            let (year, month) = (fields.year + month_index.div_euclid(12), month_index.rem_euclid(12) + 1);
+            fields.day = fields.day.min(days_in_month(year, month));
+        }
+        WEEK_OF_YEAR | WEEK_OF_MONTH => fields.day += amount * 7,
+        DAY_OF_MONTH | DAY_OF_YEAR | DAY_OF_WEEK => fields.day += amount,
+        HOUR | HOUR_OF_DAY => fields.hour += amount,
+        MINUTE => fields.minute += amount,
+        SECOND => fields.second += amount,
+        MILLISECOND => fields.millisecond += amount,
+        _ => {}
+    }
+    set_field(interpreter, this, TIME_FIELD, DexValue::Long(fields.to_millis(offset)));
+    DexValue::Void
+}
+
+/// Calendar.set(field, value) and the set(year, month, day[, hour, minute[, second]]) overloads
+fn calendar_set(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let offset = calendar_offset(interpreter, this);
+    let mut fields = Fields::of(millis_of(interpreter, this), offset);
+    let values: Vec<i64> = args.iter().map(|value| value.as_int().unwrap_or(0) as i64).collect();
+
+    if let [field, value] = values[..] {
+        match field as i32 {
+            YEAR => fields.year = value,
+            MONTH => fields.month = value + 1,
+            DAY_OF_MONTH => fields.day = value,
+            HOUR | HOUR_OF_DAY => fields.hour = value,
+            MINUTE => fields.minute = value,
+            SECOND => fields.second = value,
+            MILLISECOND => fields.millisecond = value,
+            _ => {}
+        }
+    } else {
+        let value = |index: usize, default: i64| values.get(index).copied().unwrap_or(default);
+        fields.year = value(0, fields.year);
+        fields.month = value(1, fields.month - 1) + 1;
+        fields.day = value(2, fields.day);
+        fields.hour = value(3, fields.hour);
+        fields.minute = value(4, fields.minute);
+        fields.second = value(5, fields.second);
+    }
+    set_field(interpreter, this, TIME_FIELD, DexValue::Long(fields.to_millis(offset)));
+    DexValue::Void
+}
+
+/// TimeUnit constants in declaration order with their length in nanoseconds
+const TIME_UNITS: [(&str, i64); 7] = [
+    ("NANOSECONDS", 1),
+    ("MICROSECONDS", 1_000),
+    ("MILLISECONDS", 1_000_000),
+    ("SECONDS", 1_000_000_000),
+    ("MINUTES", 60_000_000_000),
+    ("HOURS", 3_600_000_000_000),
+    ("DAYS", 86_400_000_000_000),
+];
+
+/// The TimeUnit constants, objects holding their name and ordinal like an enum constant
+pub fn time_unit_statics(interpreter: &mut Interpreter) -> HashMap<String, DexValue> {
+    TIME_UNITS.iter()
+        .enumerate()
+        .map(|(ordinal, (name, _))| {
+            let id = interpreter.alloc_object(TIME_UNIT);
+            set_field(interpreter, Some(id), NAME_FIELD, DexValue::String(name.to_string()));
+            set_field(interpreter, Some(id), ORDINAL_FIELD, DexValue::Int(ordinal as i32));
+            (name.to_string(), DexValue::Object(id))
+        })
+        .collect()
+}
+
+/// Nanoseconds in one `unit`, a TimeUnit object
+fn unit_nanos(interpreter: &Interpreter, unit: Option<ObjectId>) -> Option<i64> {
+    let name = string_field(interpreter, unit, NAME_FIELD)?;
+    TIME_UNITS.iter().find(|(unit, _)| *unit == name).map(|(_, nanos)| *nanos)
+}
+
+/// `duration` of the TimeUnit `unit` in milliseconds, `None` if `unit` isn't one
+pub fn time_unit_to_millis(interpreter: &Interpreter, duration: i64, unit: &DexValue) -> Option<i64> {
+    let DexValue::Object(unit) = unit else {
+        return None;
+    };
+    Some(convert_duration(duration, unit_nanos(interpreter, Some(*unit))?, 1_000_000))
+}
+
+/// `duration` in units of `from` nanoseconds as units of `to` nanoseconds, truncated and clamped
+/// to the range of a long like `TimeUnit.convert`
+fn convert_duration(duration: i64, from: i64, to: i64) -> i64 {
+    (duration as i128 * from as i128 / to as i128).clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+fn time_unit_convert_to(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue], to: i64) -> DexValue {
+    let from = unit_nanos(interpreter, this).unwrap_or(1);
+    DexValue::Long(convert_duration(long_arg(args, 0), from, to))
+}
+
+/// `convert(sourceDuration, sourceUnit)`, the duration in the other unit as this one
+fn time_unit_convert(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let from = unit_nanos(interpreter, object_arg(&args, 1)).unwrap_or(1);
+    let to = unit_nanos(interpreter, this).unwrap_or(1);
+    DexValue::Long(convert_duration(long_arg(&args, 0), from, to))
+}
+
+fn time_unit_sleep(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let nanos = convert_duration(long_arg(&args, 0), unit_nanos(interpreter, this).unwrap_or(1), 1);
+    if nanos > 0 {
+        clock::sleep(std::time::Duration::from_nanos(nanos as u64));
+    }
+    DexValue::Void
+}
+
+
+/// Date class methods by class descriptor and method key, static ones are called without `this`
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (LOCALE, "<init>:(Ljava/lang/String;)V" | "<init>:(Ljava/lang/String;Ljava/lang/String;)V") => locale_init,
+        (LOCALE, "getDefault:()Ljava/util/Locale;") => locale_get_default,
+        (LOCALE, "forLanguageTag:(Ljava/lang/String;)Ljava/util/Locale;") => locale_for_language_tag,
+        (LOCALE, "getLanguage:()Ljava/lang/String;") => locale_get_language,
+        (LOCALE, "toLanguageTag:()Ljava/lang/String;") => locale_to_language_tag,
+        (LOCALE, "toString:()Ljava/lang/String;") => locale_to_string,
+
+        (TIME_ZONE, "getTimeZone:(Ljava/lang/String;)Ljava/util/TimeZone;") => time_zone_get_time_zone,
+        (TIME_ZONE, "getDefault:()Ljava/util/TimeZone;") => time_zone_get_default,
+        (TIME_ZONE, "getID:()Ljava/lang/String;") => time_zone_get_id,
+        (TIME_ZONE, "getRawOffset:()I") => time_zone_get_raw_offset,
+
+        (DATE, "<init>:()V" | "<init>:(J)V") => date_init,
+        (DATE, "getTime:()J") => date_get_time,
+        (DATE, "compareTo:(Ljava/util/Date;)I") => date_compare_to,
+        (DATE, "before:(Ljava/util/Date;)Z") => date_before,
+        (DATE, "after:(Ljava/util/Date;)Z") => date_after,
+        (DATE, "setTime:(J)V") => date_set_time,
+        (DATE, "equals:(Ljava/lang/Object;)Z") => date_equals,
+        (DATE, "hashCode:()I") => date_hash_code,
+        (DATE, "toString:()Ljava/lang/String;") => date_to_string,
+
+        // Formats are often held in a DateFormat typed property, which calls the methods on it
+        (SIMPLE_DATE_FORMAT, "<init>:(Ljava/lang/String;)V" | "<init>:(Ljava/lang/String;Ljava/util/Locale;)V") => date_format_init,
+        (SIMPLE_DATE_FORMAT | DATE_FORMAT, "parse:(Ljava/lang/String;)Ljava/util/Date;") => date_format_parse,
+        (SIMPLE_DATE_FORMAT | DATE_FORMAT, "parse:(Ljava/lang/String;Ljava/text/ParsePosition;)Ljava/util/Date;") => date_format_parse_from,
+        (SIMPLE_DATE_FORMAT | DATE_FORMAT, "format:(Ljava/util/Date;)Ljava/lang/String;" | "format:(Ljava/lang/Object;)Ljava/lang/String;") => date_format_format,
+        (SIMPLE_DATE_FORMAT | DATE_FORMAT, "setTimeZone:(Ljava/util/TimeZone;)V") => date_format_set_time_zone,
+        (SIMPLE_DATE_FORMAT | DATE_FORMAT, "getTimeZone:()Ljava/util/TimeZone;") => date_format_get_time_zone,
+        (SIMPLE_DATE_FORMAT | DATE_FORMAT, "setLenient:(Z)V") => date_format_set_lenient,
+        (SIMPLE_DATE_FORMAT | DATE_FORMAT, "isLenient:()Z") => date_format_is_lenient,
+        (SIMPLE_DATE_FORMAT, "toPattern:()Ljava/lang/String;") => date_format_to_pattern,
+        (SIMPLE_DATE_FORMAT, "applyPattern:(Ljava/lang/String;)V") => date_format_apply_pattern,
+
+        (PARSE_POSITION, "<init>:(I)V") => parse_position_init,
+        (PARSE_POSITION, "getIndex:()I") => |interpreter, this, _| parse_position_get(interpreter, this, INDEX_FIELD),
+        (PARSE_POSITION, "getErrorIndex:()I") => |interpreter, this, _| parse_position_get(interpreter, this, ERROR_INDEX_FIELD),
+        (PARSE_POSITION, "setIndex:(I)V") => |interpreter, this, args| parse_position_set(interpreter, this, INDEX_FIELD, &args),
+        (PARSE_POSITION, "setErrorIndex:(I)V") => |interpreter, this, args| parse_position_set(interpreter, this, ERROR_INDEX_FIELD, &args),
+
+        (
+            CALENDAR,
+            "getInstance:()Ljava/util/Calendar;"
+            | "getInstance:(Ljava/util/TimeZone;)Ljava/util/Calendar;"
+            | "getInstance:(Ljava/util/Locale;)Ljava/util/Calendar;",
+        ) => calendar_get_instance,
+        (CALENDAR, "getTime:()Ljava/util/Date;") => calendar_get_time,
+        (CALENDAR, "setTime:(Ljava/util/Date;)V") => calendar_set_time,
+        (CALENDAR, "getTimeInMillis:()J") => date_get_time,
+        (CALENDAR, "setTimeInMillis:(J)V") => calendar_set_time_in_millis,
+        (CALENDAR, "get:(I)I") => calendar_get,
+        (CALENDAR, "add:(II)V") => calendar_add,
+        (CALENDAR, "set:(II)V" | "set:(III)V" | "set:(IIIII)V" | "set:(IIIIII)V") => calendar_set,
+
+        (TIME_UNIT, "toNanos:(J)J") => |interpreter, this, args| time_unit_convert_to(interpreter, this, &args, 1),
+        (TIME_UNIT, "toMicros:(J)J") => |interpreter, this, args| time_unit_convert_to(interpreter, this, &args, 1_000),
+        (TIME_UNIT, "toMillis:(J)J") => |interpreter, this, args| time_unit_convert_to(interpreter, this, &args, 1_000_000),
+        (TIME_UNIT, "toSeconds:(J)J") => |interpreter, this, args| time_unit_convert_to(interpreter, this, &args, 1_000_000_000),
+        (TIME_UNIT, "toMinutes:(J)J") => |interpreter, this, args| time_unit_convert_to(interpreter, this, &args, 60_000_000_000),
+        (TIME_UNIT, "toHours:(J)J") => |interpreter, this, args| time_unit_convert_to(interpreter, this, &args, 3_600_000_000_000),
+        (TIME_UNIT, "toDays:(J)J") => |interpreter, this, args| time_unit_convert_to(interpreter, this, &args, 86_400_000_000_000),
+        (TIME_UNIT, "convert:(JLjava/util/concurrent/TimeUnit;)J") => time_unit_convert,
+        (TIME_UNIT, "sleep:(J)V") => time_unit_sleep,
+        (TIME_UNIT, "name:()Ljava/lang/String;" | "toString:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(string_field(interpreter, this, NAME_FIELD).unwrap_or_default())
+        },
+        (TIME_UNIT, "ordinal:()I") => |interpreter, this, _| field(interpreter, this, ORDINAL_FIELD).unwrap_or(DexValue::Int(0)),
+        _ => return None,
+    };
+    Some(method)
+}
@@ -0,0 +1,228 @@
+// java.security.MessageDigest, for sources that sign their API requests or derive image keys
+// with a hash, and the bits of java.math.BigInteger that turn a digest into a hex string
+// (`BigInteger(1, digest).toString(16)`). A digest buffers its input in the array of its object
+// and hashes it all at once when digest() is called, which also resets it like on the JVM.
+
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha224, Sha256, Sha384, Sha512};
+
+use crate::interpreter::exception::{NO_SUCH_ALGORITHM_EXCEPTION, NUMBER_FORMAT_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{bytes_of, new_byte_array, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const MESSAGE_DIGEST: &str = "Ljava/security/MessageDigest;";
+pub const BIG_INTEGER: &str = "Ljava/math/BigInteger;";
+
+/// The algorithm name as it was asked for, which getAlgorithm() gives back
+const ALGORITHM_FIELD: &str = "algorithm";
+/// -1, 0 or 1, the magnitude is the big-endian bytes in the object's array
+const SIGNUM_FIELD: &str = "signum";
+
+/// The algorithms by their standard name, any of which is accepted in any case
+#[derive(Debug, Clone, Copy)]
+enum Algorithm {
+    Md5,
+    Sha1,
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    fn named(name: &str) -> Option<Algorithm> {
+        let algorithm = match name.to_ascii_uppercase().as_str() {
+            "MD5" => Algorithm::Md5,
+            "SHA-1" | "SHA1" | "SHA" => Algorithm::Sha1,
+            "SHA-224" | "SHA224" => Algorithm::Sha224,
+            "SHA-256" | "SHA256" => Algorithm::Sha256,
+            "SHA-384" | "SHA384" => Algorithm::Sha384,
+            "SHA-512" | "SHA512" => Algorithm::Sha512,
+            _ => return None,
+        };
+        Some(algorithm)
+    }
+
+    fn hash(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Algorithm::Md5 => Md5::digest(data).to_vec(),
+            Algorithm::Sha1 => Sha1::digest(data).to_vec(),
+            Algorithm::Sha224 => Sha224::digest(data).to_vec(),
+            Algorithm::Sha256 => Sha256::digest(data).to_vec(),
+            Algorithm::Sha384 => Sha384::digest(data).to_vec(),
+            Algorithm::Sha512 => Sha512::digest(data).to_vec(),
+        }
+    }
+
+    fn length(self) -> i32 {
+        match self {
+            Algorithm::Md5 => 16,
+            Algorithm::Sha1 => 20,
+            Algorithm::Sha224 => 28,
+            Algorithm::Sha256 => 32,
+            Algorithm::Sha384 => 48,
+            Algorithm::Sha512 => 64,
+        }
+    }
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (MESSAGE_DIGEST, "getInstance:(Ljava/lang/String;)Ljava/security/MessageDigest;") => get_instance,
+        (MESSAGE_DIGEST, "update:(B)V") => update_byte,
+        (MESSAGE_DIGEST, "update:([B)V" | "update:([BII)V") => update,
+        (MESSAGE_DIGEST, "digest:()[B") => digest,
+        (MESSAGE_DIGEST, "digest:([B)[B") => |interpreter, this, args| {
+            update(interpreter, this, args);
+            digest(interpreter, this, Vec::new())
+        },
+        (MESSAGE_DIGEST, "reset:()V") => |interpreter, this, _| {
+            set_input(interpreter, this, Vec::new());
+            DexValue::Void
+        },
+        (MESSAGE_DIGEST, "getAlgorithm:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(algorithm_name(interpreter, this))
+        },
+        (MESSAGE_DIGEST, "getDigestLength:()I") => |interpreter, this, _| {
+            DexValue::Int(Algorithm::named(&algorithm_name(interpreter, this)).map_or(0, Algorithm::length))
+        },
+        (MESSAGE_DIGEST, "isEqual:([B[B)Z") => |interpreter, _, args| {
+            DexValue::Boolean(bytes_of(interpreter, object_arg(&args, 0)) == bytes_of(interpreter, object_arg(&args, 1)))
+        },
+
+        (BIG_INTEGER, "<init>:(I[B)V") => big_integer_init,
+        (BIG_INTEGER, "toString:(I)Ljava/lang/String;") => |interpreter, this, args| {
+            let radix = args.first().and_then(|value| value.as_int()).unwrap_or(10);
+            DexValue::String(big_integer_to_string(interpreter, this, radix))
+        },
+        (BIG_INTEGER, "toString:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(big_integer_to_string(interpreter, this, 10))
+        },
+        (BIG_INTEGER, "signum:()I") => |interpreter, this, _| {
+            field(interpreter, this, SIGNUM_FIELD).unwrap_or(DexValue::Int(0))
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn object_arg(args: &[DexValue], index: usize) -> Option<ObjectId> {
+    match args.get(index) {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+fn field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> Option<DexValue> {
+    this.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(name)).cloned()
+}
+
+fn algorithm_name(interpreter: &Interpreter, this: Option<ObjectId>) -> String {
+    match field(interpreter, this, ALGORITHM_FIELD) {
+        Some(DexValue::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn set_input(interpreter: &mut Interpreter, this: Option<ObjectId>, input: Vec<DexValue>) {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.array = Some(input);
+    }
+}
+
+fn get_instance(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = match args.first() {
+        Some(DexValue::String(name)) => name.clone(),
+        _ => String::new(),
+    };
+    if Algorithm::named(&name).is_none() {
+        interpreter.throw_new(NO_SUCH_ALGORITHM_EXCEPTION, &format!("{} MessageDigest not available", name));
+        return DexValue::Void;
+    }
+
+    let id = interpreter.alloc_object(MESSAGE_DIGEST);
+    let object = interpreter.heap.get_mut(&id).unwrap();
+    object.fields.insert(ALGORITHM_FIELD.to_string(), DexValue::String(name));
+    object.array = Some(Vec::new());
+    DexValue::Object(id)
+}
+
+fn update_byte(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let byte = args.first().and_then(|value| value.as_int()).unwrap_or(0) as i8;
+    if let Some(input) = this.and_then(|id| interpreter.heap.get_mut(&id)).and_then(|object| object.array.as_mut()) {
+        input.push(DexValue::Byte(byte));
+    }
+    DexValue::Void
+}
+
+/// `update(input)` and `update(input, offset, len)`
+fn update(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut bytes = bytes_of(interpreter, object_arg(&args, 0));
+    if args.len() == 3 {
+        let offset = args[1].as_int().unwrap_or(0).clamp(0, bytes.len() as i32) as usize;
+        let length = args[2].as_int().unwrap_or(0).clamp(0, (bytes.len() - offset) as i32) as usize;
+        bytes = bytes[offset..offset + length].to_vec();
+    }
+    if let Some(input) = this.and_then(|id| interpreter.heap.get_mut(&id)).and_then(|object| object.array.as_mut()) {
+        input.extend(bytes.into_iter().map(|byte| DexValue::Byte(byte as i8)));
+    }
+    DexValue::Void
+}
+
+fn digest(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let Some(algorithm) = Algorithm::named(&algorithm_name(interpreter, this)) else {
+        return DexValue::Null;
+    };
+    let hash = algorithm.hash(&bytes_of(interpreter, this));
+    set_input(interpreter, this, Vec::new());
+    new_byte_array(interpreter, hash)
+}
+
+/// `BigInteger(signum, magnitude)`
+fn big_integer_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let signum = args.first().and_then(|value| value.as_int()).unwrap_or(0);
+    let magnitude: Vec<u8> = bytes_of(interpreter, object_arg(&args, 1)).into_iter().skip_while(|byte| *byte == 0).collect();
+    if !(-1..=1).contains(&signum) {
+        interpreter.throw_new(NUMBER_FORMAT_EXCEPTION, "Invalid signum value");
+        return DexValue::Void;
+    }
+    if signum == 0 && !magnitude.is_empty() {
+        interpreter.throw_new(NUMBER_FORMAT_EXCEPTION, "signum-magnitude mismatch");
+        return DexValue::Void;
+    }
+
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        let signum = if magnitude.is_empty() { 0 } else { signum };
+        object.fields.insert(SIGNUM_FIELD.to_string(), DexValue::Int(signum));
+        object.array = Some(magnitude.into_iter().map(|byte| DexValue::Byte(byte as i8)).collect());
+    }
+    DexValue::Void
+}
+
+/// The digits of the number in `radix`, which is 10 outside 2 to 36 like in Java
+fn big_integer_to_string(interpreter: &Interpreter, this: Option<ObjectId>, radix: i32) -> String {
+    let radix = if (2..=36).contains(&radix) { radix as u32 } else { 10 };
+    let negative = field(interpreter, this, SIGNUM_FIELD) == Some(DexValue::Int(-1));
+    let mut magnitude = bytes_of(interpreter, this);
+
+    // Long division of the big-endian magnitude, least significant digit first
+    let mut digits = Vec::new();
+    while magnitude.iter().any(|byte| *byte != 0) {
+        let mut remainder = 0u32;
+        for byte in magnitude.iter_mut() {
+            let value = remainder << 8 | *byte as u32;
+            *byte = (value / radix) as u8;
+            remainder = value % radix;
+        }
+        digits.push(std::char::from_digit(remainder, radix).unwrap_or('0'));
+    }
+    if digits.is_empty() {
+        digits.push('0');
+    }
+    if negative {
+        digits.push('-');
+    }
+    digits.into_iter().rev().collect()
+}
@@ -0,0 +1,160 @@
+// java.lang.Enum, which the enum classes of extensions extend for their filters and sort
+// options. The enum class itself is in the dex and its `<clinit>` builds the constants as it is,
+// each constant's constructor passes its name and ordinal up to Enum's, which keeps them here.
+// values() clones `$VALUES`, see `collections::clone_array`, while valueOf() and
+// getEnumConstants() collect the constants from the class's statics so they don't depend on
+// field names R8 may have changed.
+
+use crate::interpreter::collections::{elements_of, new_list};
+use crate::interpreter::exception::{ILLEGAL_ARGUMENT_EXCEPTION, NULL_POINTER_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{binary_name, PlatformMethod, CLASS_DESCRIPTOR_FIELD, JAVA_LANG_CLASS};
+use crate::types::{DexValue, ObjectId};
+
+pub const ENUM: &str = "Ljava/lang/Enum;";
+pub const ENUM_ENTRIES_KT: &str = "Lkotlin/enums/EnumEntriesKt;";
+
+const NAME_FIELD: &str = "name";
+const ORDINAL_FIELD: &str = "ordinal";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (ENUM, "<init>:(Ljava/lang/String;I)V") => |interpreter, this, args| {
+            if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+                object.fields.insert(NAME_FIELD.to_string(), args.first().cloned().unwrap_or(DexValue::Null));
+                object.fields.insert(ORDINAL_FIELD.to_string(), DexValue::Int(args.get(1).and_then(|value| value.as_int()).unwrap_or(0)));
+            }
+            DexValue::Void
+        },
+        (ENUM, "name:()Ljava/lang/String;" | "toString:()Ljava/lang/String;") => |interpreter, this, _| DexValue::String(name_of(interpreter, this)),
+        (ENUM, "ordinal:()I") => |interpreter, this, _| DexValue::Int(ordinal_of(interpreter, this)),
+        (ENUM, "compareTo:(Ljava/lang/Enum;)I" | "compareTo:(Ljava/lang/Object;)I") => |interpreter, this, args| {
+            let other = match args.first() {
+                Some(DexValue::Object(id)) => Some(*id),
+                _ => None,
+            };
+            DexValue::Int(ordinal_of(interpreter, this) - ordinal_of(interpreter, other))
+        },
+        // Constants are singletons, Enum's equals() and hashCode() are final and by identity
+        (ENUM, "equals:(Ljava/lang/Object;)Z") => |_, this, args| {
+            DexValue::Boolean(this.is_some_and(|id| args.first() == Some(&DexValue::Object(id))))
+        },
+        (ENUM, "hashCode:()I") => |_, this, _| DexValue::Int(this.unwrap_or_default() as i32),
+        (ENUM, "getDeclaringClass:()Ljava/lang/Class;") => |interpreter, this, _| {
+            let class_name = this.and_then(|id| interpreter.heap.get(&id)).map(|object| object.class_name.clone()).unwrap_or_default();
+            let declaring = enum_class_of(interpreter, &class_name);
+            DexValue::Object(interpreter.class_object(&declaring))
+        },
+        (ENUM, "valueOf:(Ljava/lang/Class;Ljava/lang/String;)Ljava/lang/Enum;") => value_of,
+        (ENUM_ENTRIES_KT, "enumEntries:([Ljava/lang/Enum;)Lkotlin/enums/EnumEntries;") => |interpreter, _, args| {
+            let entries = elements_of(interpreter, args.first().unwrap_or(&DexValue::Null));
+            DexValue::Object(new_list(interpreter, entries))
+        },
+        // What getEntries() of a Java enum compiles to, the function returns values()
+        (ENUM_ENTRIES_KT, "enumEntries:(Lkotlin/jvm/functions/Function0;)Lkotlin/enums/EnumEntries;") => |interpreter, _, args| {
+            let values = interpreter.invoke_function(args.first().unwrap_or(&DexValue::Null), Vec::new()).unwrap_or(DexValue::Null);
+            let entries = elements_of(interpreter, &values);
+            DexValue::Object(new_list(interpreter, entries))
+        },
+        (JAVA_LANG_CLASS, "isEnum:()Z") => |interpreter, this, _| {
+            let descriptor = descriptor_of(interpreter, this);
+            DexValue::Boolean(interpreter.super_class_of(&descriptor).as_deref() == Some(ENUM))
+        },
+        (JAVA_LANG_CLASS, "getEnumConstants:()[Ljava/lang/Object;") => |interpreter, this, _| {
+            let descriptor = descriptor_of(interpreter, this);
+            if interpreter.super_class_of(&descriptor).as_deref() != Some(ENUM) {
+                return DexValue::Null;
+            }
+            let Some(constants) = constants(interpreter, &descriptor) else {
+                return DexValue::Void;
+            };
+            let id = interpreter.alloc_object(&format!("[{}", descriptor));
+            interpreter.heap.get_mut(&id).unwrap().array = Some(constants.into_iter().map(DexValue::Object).collect());
+            DexValue::Object(id)
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// The name a constant was declared with
+pub fn name_of(interpreter: &Interpreter, this: Option<ObjectId>) -> String {
+    match this.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(NAME_FIELD)) {
+        Some(DexValue::String(name)) => name.clone(),
+        _ => String::new(),
+    }
+}
+
+fn ordinal_of(interpreter: &Interpreter, this: Option<ObjectId>) -> i32 {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.fields.get(ORDINAL_FIELD))
+        .and_then(|ordinal| ordinal.as_int())
+        .unwrap_or(0)
+}
+
+fn descriptor_of(interpreter: &Interpreter, class_object: Option<ObjectId>) -> String {
+    match class_object.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(CLASS_DESCRIPTOR_FIELD)) {
+        Some(DexValue::String(descriptor)) => descriptor.clone(),
+        _ => String::new(),
+    }
+}
+
+/// The enum class a constant belongs to. Constants with a body are instances of an anonymous
+/// subclass, e.g. `Sort$1`, whose superclass is the enum class.
+fn enum_class_of(interpreter: &Interpreter, class_name: &str) -> String {
+    match interpreter.super_class_of(class_name) {
+        Some(super_class) if super_class != ENUM => super_class,
+        _ => class_name.to_string(),
+    }
+}
+
+/// The constants of the enum class `class_name` in ordinal order, initializing the class first.
+/// `None` if its initialization threw.
+pub fn constants(interpreter: &mut Interpreter, class_name: &str) -> Option<Vec<ObjectId>> {
+    if !interpreter.initialize_class(class_name) {
+        return None;
+    }
+
+    let candidates: Vec<ObjectId> = interpreter.statics.get(class_name)
+        .map(|statics| statics.values().filter_map(|value| match value {
+            DexValue::Object(id) => Some(*id),
+            _ => None,
+        }).collect())
+        .unwrap_or_default();
+    let mut constants: Vec<ObjectId> = candidates.into_iter()
+        .filter(|id| {
+            let Some(object) = interpreter.heap.get(id) else {
+                return false;
+            };
+            object.fields.contains_key(ORDINAL_FIELD) && enum_class_of(interpreter, &object.class_name) == class_name
+        })
+        .collect();
+    constants.sort_by_key(|id| ordinal_of(interpreter, Some(*id)));
+    constants.dedup();
+    Some(constants)
+}
+
+/// `Enum.valueOf(type, name)`, which each enum class's valueOf(String) calls
+fn value_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let class_object = match args.first() {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    };
+    let Some(DexValue::String(name)) = args.get(1) else {
+        interpreter.throw_new(NULL_POINTER_EXCEPTION, "Name is null");
+        return DexValue::Void;
+    };
+    let descriptor = descriptor_of(interpreter, class_object);
+    let Some(constants) = constants(interpreter, &descriptor) else {
+        return DexValue::Void;
+    };
+
+    match constants.into_iter().find(|id| name_of(interpreter, Some(*id)) == *name) {
+        Some(constant) => DexValue::Object(constant),
+        None => {
+            let message = format!("No enum constant {}.{}", binary_name(&descriptor).replace('$', "."), name);
+            interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &message);
+            DexValue::Void
+        }
+    }
+}
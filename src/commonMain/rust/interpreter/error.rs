@@ -0,0 +1,61 @@
+use std::fmt;
+
+use crate::interpreter::codec::CodecError;
+use crate::parser::error::ParseError;
+
+/// Errors surfaced from the interpreter to its host
+#[derive(Debug, Clone, PartialEq)]
+pub enum InterpreterError {
+    /// An exception was thrown and no frame on the stack had a matching handler
+    UncaughtException {
+        class_name: String,
+        message: Option<String>,
+        /// Methods that were on the stack when the exception was thrown, innermost first
+        stack_trace: Vec<String>,
+    },
+    /// Arguments or results that couldn't be converted from or to JSON
+    Marshaling(CodecError),
+    /// A class of install generation `found` showed up in an interpreter running `expected`,
+    /// the extension was reinstalled underneath it
+    GenerationMismatch { class_name: String, expected: u64, found: u64 },
+    /// The installed extension couldn't be loaded from the cache nor parsed again
+    Load(ParseError),
+    /// No class of the extension extends a Source
+    NoMainClass,
+    /// The main class `class_name` has no constructor to create the source with
+    NoMainConstructor { class_name: String },
+}
+
+impl From<CodecError> for InterpreterError {
+    fn from(error: CodecError) -> Self {
+        InterpreterError::Marshaling(error)
+    }
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpreterError::UncaughtException { class_name, message, stack_trace } => {
+                write!(f, "Uncaught exception {}", class_name)?;
+                if let Some(message) = message {
+                    write!(f, ": {}", message)?;
+                }
+                for method in stack_trace {
+                    write!(f, "\n\tat {}", method)?;
+                }
+                Ok(())
+            }
+            InterpreterError::Marshaling(error) => write!(f, "Marshaling failed: {}", error),
+            InterpreterError::GenerationMismatch { class_name, expected, found } => write!(
+                f,
+                "{} belongs to install generation {} but generation {} is loaded, the extension has to be reloaded",
+                class_name, found, expected,
+            ),
+            InterpreterError::Load(error) => write!(f, "Loading the extension failed: {}", error),
+            InterpreterError::NoMainClass => write!(f, "The extension has no class extending a Source"),
+            InterpreterError::NoMainConstructor { class_name } => write!(f, "The main class {} has no constructor", class_name),
+        }
+    }
+}
+
+impl std::error::Error for InterpreterError {}
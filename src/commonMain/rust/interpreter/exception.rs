@@ -0,0 +1,344 @@
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{binary_name, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const THROWABLE: &str = "Ljava/lang/Throwable;";
+pub const STACK_TRACE_ELEMENT: &str = "Ljava/lang/StackTraceElement;";
+pub const EXCEPTIONS_KT: &str = "Lkotlin/ExceptionsKt;";
+
+pub const NULL_POINTER_EXCEPTION: &str = "Ljava/lang/NullPointerException;";
+pub const ARITHMETIC_EXCEPTION: &str = "Ljava/lang/ArithmeticException;";
+pub const ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION: &str = "Ljava/lang/ArrayIndexOutOfBoundsException;";
+pub const NEGATIVE_ARRAY_SIZE_EXCEPTION: &str = "Ljava/lang/NegativeArraySizeException;";
+pub const KOTLIN_NULL_POINTER_EXCEPTION: &str = "Lkotlin/KotlinNullPointerException;";
+pub const UNINITIALIZED_PROPERTY_ACCESS_EXCEPTION: &str = "Lkotlin/UninitializedPropertyAccessException;";
+pub const PARSE_EXCEPTION: &str = "Ljava/text/ParseException;";
+pub const IO_EXCEPTION: &str = "Ljava/io/IOException;";
+pub const FILE_NOT_FOUND_EXCEPTION: &str = "Ljava/io/FileNotFoundException;";
+pub const ILLEGAL_ARGUMENT_EXCEPTION: &str = "Ljava/lang/IllegalArgumentException;";
+pub const ILLEGAL_STATE_EXCEPTION: &str = "Ljava/lang/IllegalStateException;";
+pub const UNSUPPORTED_OPERATION_EXCEPTION: &str = "Ljava/lang/UnsupportedOperationException;";
+pub const NUMBER_FORMAT_EXCEPTION: &str = "Ljava/lang/NumberFormatException;";
+pub const PATTERN_SYNTAX_EXCEPTION: &str = "Ljava/util/regex/PatternSyntaxException;";
+pub const URI_SYNTAX_EXCEPTION: &str = "Ljava/net/URISyntaxException;";
+pub const UNKNOWN_HOST_EXCEPTION: &str = "Ljava/net/UnknownHostException;";
+pub const CONNECT_EXCEPTION: &str = "Ljava/net/ConnectException;";
+pub const SOCKET_TIMEOUT_EXCEPTION: &str = "Ljava/net/SocketTimeoutException;";
+pub const PROTOCOL_EXCEPTION: &str = "Ljava/net/ProtocolException;";
+pub const JSON_EXCEPTION: &str = "Lorg/json/JSONException;";
+pub const SERIALIZATION_EXCEPTION: &str = "Lkotlinx/serialization/SerializationException;";
+pub const MISSING_FIELD_EXCEPTION: &str = "Lkotlinx/serialization/MissingFieldException;";
+pub const UNKNOWN_FIELD_EXCEPTION: &str = "Lkotlinx/serialization/UnknownFieldException;";
+pub const JSON_DECODING_EXCEPTION: &str = "Lkotlinx/serialization/json/internal/JsonDecodingException;";
+pub const NO_SUCH_ELEMENT_EXCEPTION: &str = "Ljava/util/NoSuchElementException;";
+pub const SECURITY_EXCEPTION: &str = "Ljava/lang/SecurityException;";
+pub const SELECTOR_PARSE_EXCEPTION: &str = "Lorg/jsoup/select/Selector$SelectorParseException;";
+pub const NO_SUCH_ALGORITHM_EXCEPTION: &str = "Ljava/security/NoSuchAlgorithmException;";
+pub const INVALID_KEY_EXCEPTION: &str = "Ljava/security/InvalidKeyException;";
+pub const INVALID_ALGORITHM_PARAMETER_EXCEPTION: &str = "Ljava/security/InvalidAlgorithmParameterException;";
+pub const BAD_PADDING_EXCEPTION: &str = "Ljavax/crypto/BadPaddingException;";
+pub const ILLEGAL_BLOCK_SIZE_EXCEPTION: &str = "Ljavax/crypto/IllegalBlockSizeException;";
+pub const UNSUPPORTED_CHARSET_EXCEPTION: &str = "Ljava/nio/charset/UnsupportedCharsetException;";
+pub const ILLEGAL_CHARSET_NAME_EXCEPTION: &str = "Ljava/nio/charset/IllegalCharsetNameException;";
+pub const HTTP_EXCEPTION: &str = "Leu/kanade/tachiyomi/network/HttpException;";
+pub const UNSUPPORTED_ENCODING_EXCEPTION: &str = "Ljava/io/UnsupportedEncodingException;";
+pub const EXECUTION_EXCEPTION: &str = "Ljava/util/concurrent/ExecutionException;";
+pub const ILLEGAL_MONITOR_STATE_EXCEPTION: &str = "Ljava/lang/IllegalMonitorStateException;";
+pub const ASSERTION_ERROR: &str = "Ljava/lang/AssertionError;";
+pub const STACK_OVERFLOW_ERROR: &str = "Ljava/lang/StackOverflowError;";
+pub const EXCEPTION_IN_INITIALIZER_ERROR: &str = "Ljava/lang/ExceptionInInitializerError;";
+pub const NO_CLASS_DEF_FOUND_ERROR: &str = "Ljava/lang/NoClassDefFoundError;";
+pub const ABSTRACT_METHOD_ERROR: &str = "Ljava/lang/AbstractMethodError;";
+pub const NO_SUCH_METHOD_ERROR: &str = "Ljava/lang/NoSuchMethodError;";
+pub const VERIFY_ERROR: &str = "Ljava/lang/VerifyError;";
+pub const INDEX_OUT_OF_BOUNDS_EXCEPTION: &str = "Ljava/lang/IndexOutOfBoundsException;";
+pub const STRING_INDEX_OUT_OF_BOUNDS_EXCEPTION: &str = "Ljava/lang/StringIndexOutOfBoundsException;";
+
+/// Field of an exception object holding its detail message
+pub const MESSAGE_FIELD: &str = "message";
+
+/// Field of a wrapping exception object holding the exception it wraps
+pub const CAUSE_FIELD: &str = "cause";
+
+/// Field of an exception object holding the methods on the stack when it was created, or when
+/// it was first thrown for the ones the interpreter throws itself
+pub const STACK_TRACE_FIELD: &str = "stackTrace";
+
+const CLASS_NAME_FIELD: &str = "declaringClass";
+const METHOD_NAME_FIELD: &str = "methodName";
+const FILE_NAME_FIELD: &str = "fileName";
+const LINE_NUMBER_FIELD: &str = "lineNumber";
+
+/// An exception that was thrown and is looking for a handler
+#[derive(Debug, Clone)]
+pub struct PendingException {
+    pub exception: DexValue,
+    /// Methods that were on the stack at the throw site, innermost first
+    pub stack_trace: Vec<String>,
+}
+
+/// Superclasses of the platform exceptions the interpreter knows without a class file
+pub fn builtin_super_class(class_name: &str) -> Option<&'static str> {
+    match class_name {
+        "Ljava/lang/Throwable;" => Some("Ljava/lang/Object;"),
+        "Ljava/lang/Exception;" | "Ljava/lang/Error;" => Some("Ljava/lang/Throwable;"),
+        "Ljava/lang/RuntimeException;"
+        | "Ljava/io/IOException;"
+        | "Ljava/lang/InterruptedException;"
+        | "Ljava/text/ParseException;"
+        | "Ljava/net/URISyntaxException;"
+        | "Lorg/json/JSONException;"
+        | "Ljava/lang/ReflectiveOperationException;" => Some("Ljava/lang/Exception;"),
+        "Ljava/lang/NullPointerException;"
+        | "Ljava/lang/ArithmeticException;"
+        | "Ljava/lang/IllegalArgumentException;"
+        | "Ljava/lang/IllegalStateException;"
+        | "Ljava/lang/IndexOutOfBoundsException;"
+        | "Ljava/lang/ClassCastException;"
+        | "Ljava/lang/UnsupportedOperationException;"
+        | "Ljava/lang/NegativeArraySizeException;"
+        | "Ljava/lang/ArrayStoreException;"
+        | "Ljava/util/NoSuchElementException;"
+        | "Ljava/util/ConcurrentModificationException;"
+        | "Ljava/lang/SecurityException;"
+        | "Ljava/lang/IllegalMonitorStateException;"
+        | "Lkotlin/UninitializedPropertyAccessException;" => Some("Ljava/lang/RuntimeException;"),
+        "Ljava/lang/ArrayIndexOutOfBoundsException;"
+        | "Ljava/lang/StringIndexOutOfBoundsException;" => Some("Ljava/lang/IndexOutOfBoundsException;"),
+        "Lorg/jsoup/select/Selector$SelectorParseException;"
+        | "Leu/kanade/tachiyomi/network/HttpException;" => Some("Ljava/lang/IllegalStateException;"),
+        "Ljava/lang/NumberFormatException;"
+        | "Ljava/util/regex/PatternSyntaxException;"
+        | "Ljava/nio/charset/UnsupportedCharsetException;"
+        | "Ljava/nio/charset/IllegalCharsetNameException;"
+        | "Lkotlinx/serialization/SerializationException;" => Some("Ljava/lang/IllegalArgumentException;"),
+        "Lkotlinx/serialization/MissingFieldException;"
+        | "Lkotlinx/serialization/UnknownFieldException;"
+        | "Lkotlinx/serialization/json/internal/JsonException;" => Some("Lkotlinx/serialization/SerializationException;"),
+        "Lkotlinx/serialization/json/internal/JsonDecodingException;" => Some("Lkotlinx/serialization/json/internal/JsonException;"),
+        "Lkotlin/KotlinNullPointerException;" => Some("Ljava/lang/NullPointerException;"),
+        "Ljava/io/InterruptedIOException;"
+        | "Ljava/io/FileNotFoundException;"
+        | "Ljava/io/EOFException;"
+        | "Ljava/io/UnsupportedEncodingException;"
+        | "Ljava/net/UnknownHostException;"
+        | "Ljava/net/ProtocolException;"
+        | "Ljava/net/SocketException;" => Some("Ljava/io/IOException;"),
+        "Ljava/net/SocketTimeoutException;" => Some("Ljava/io/InterruptedIOException;"),
+        "Ljava/net/ConnectException;" => Some("Ljava/net/SocketException;"),
+        "Ljava/util/concurrent/TimeoutException;"
+        | "Ljava/util/concurrent/ExecutionException;"
+        | "Ljava/lang/CloneNotSupportedException;"
+        | "Ljava/security/GeneralSecurityException;" => Some("Ljava/lang/Exception;"),
+        "Ljava/security/NoSuchAlgorithmException;"
+        | "Ljava/security/KeyException;"
+        | "Ljava/security/InvalidAlgorithmParameterException;"
+        | "Ljavax/crypto/BadPaddingException;"
+        | "Ljavax/crypto/IllegalBlockSizeException;" => Some("Ljava/security/GeneralSecurityException;"),
+        "Ljava/security/InvalidKeyException;" => Some("Ljava/security/KeyException;"),
+        "Ljava/lang/ClassNotFoundException;" => Some("Ljava/lang/ReflectiveOperationException;"),
+        "Lkotlin/NotImplementedError;" => Some("Ljava/lang/Error;"),
+        "Ljava/lang/VirtualMachineError;" | "Ljava/lang/LinkageError;" | "Ljava/lang/AssertionError;" => Some("Ljava/lang/Error;"),
+        "Ljava/lang/ExceptionInInitializerError;"
+        | "Ljava/lang/NoClassDefFoundError;"
+        | "Ljava/lang/IncompatibleClassChangeError;"
+        | "Ljava/lang/VerifyError;" => Some("Ljava/lang/LinkageError;"),
+        "Ljava/lang/AbstractMethodError;" | "Ljava/lang/NoSuchMethodError;" => Some("Ljava/lang/IncompatibleClassChangeError;"),
+        "Ljava/lang/StackOverflowError;" | "Ljava/lang/OutOfMemoryError;" => Some("Ljava/lang/VirtualMachineError;"),
+        _ => None,
+    }
+}
+
+// Platform methods of Throwable, which every exception inherits, including the ones extensions
+// declare. Messages and causes are plain fields, the stack trace is an array of method names.
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (
+            THROWABLE,
+            "<init>:()V"
+            | "<init>:(Ljava/lang/String;)V"
+            | "<init>:(Ljava/lang/String;Ljava/lang/Throwable;)V"
+            | "<init>:(Ljava/lang/String;Ljava/lang/Throwable;ZZ)V",
+        ) => throwable_init,
+        (THROWABLE, "<init>:(Ljava/lang/Throwable;)V") => throwable_init_with_cause,
+        (THROWABLE, "getMessage:()Ljava/lang/String;" | "getLocalizedMessage:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, MESSAGE_FIELD),
+        (THROWABLE, "getCause:()Ljava/lang/Throwable;") => |interpreter, this, _| field(interpreter, this, CAUSE_FIELD),
+        (THROWABLE, "initCause:(Ljava/lang/Throwable;)Ljava/lang/Throwable;") => throwable_init_cause,
+        (THROWABLE, "toString:()Ljava/lang/String;") => throwable_to_string,
+        (THROWABLE, "fillInStackTrace:()Ljava/lang/Throwable;") => throwable_fill_in_stack_trace,
+        (THROWABLE, "getStackTrace:()[Ljava/lang/StackTraceElement;") => throwable_get_stack_trace,
+        // There is no console to print to and nothing keeps suppressed exceptions
+        (THROWABLE, "printStackTrace:()V" | "addSuppressed:(Ljava/lang/Throwable;)V" | "setStackTrace:([Ljava/lang/StackTraceElement;)V") => |_, _, _| DexValue::Void,
+
+        (EXCEPTIONS_KT, "stackTraceToString:(Ljava/lang/Throwable;)Ljava/lang/String;") => exceptions_stack_trace_to_string,
+        (EXCEPTIONS_KT, "addSuppressed:(Ljava/lang/Throwable;Ljava/lang/Throwable;)V") => |_, _, _| DexValue::Void,
+
+        (STACK_TRACE_ELEMENT, "getClassName:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, CLASS_NAME_FIELD),
+        (STACK_TRACE_ELEMENT, "getMethodName:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, METHOD_NAME_FIELD),
+        (STACK_TRACE_ELEMENT, "getFileName:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, FILE_NAME_FIELD),
+        (STACK_TRACE_ELEMENT, "getLineNumber:()I") => |interpreter, this, _| match field(interpreter, this, LINE_NUMBER_FIELD) {
+            DexValue::Null => DexValue::Int(-1),
+            line => line,
+        },
+        (STACK_TRACE_ELEMENT, "toString:()Ljava/lang/String;") => stack_trace_element_to_string,
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> DexValue {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.fields.get(name).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn set_field(interpreter: &mut Interpreter, this: Option<ObjectId>, name: &str, value: DexValue) {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(name.to_string(), value);
+    }
+}
+
+/// Record the stack of the code creating the exception `this`, without the frames of its own
+/// constructors like the JVM
+fn capture_stack_trace(interpreter: &mut Interpreter, this: Option<ObjectId>) {
+    let constructors = interpreter.frames.iter()
+        .rev()
+        .take_while(|frame| frame.method_key.starts_with("<init>:") && interpreter.is_subclass_of(&frame.class_name, THROWABLE))
+        .count();
+    let trace = interpreter.stack_trace().into_iter().skip(constructors).map(DexValue::String).collect();
+    set_field(interpreter, this, STACK_TRACE_FIELD, DexValue::Array(trace));
+}
+
+/// Throwable(), Throwable(message) and Throwable(message, cause)
+fn throwable_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let message = match args.first() {
+        Some(DexValue::String(message)) => DexValue::String(message.clone()),
+        _ => DexValue::Null,
+    };
+    set_field(interpreter, this, MESSAGE_FIELD, message);
+    if let Some(cause @ DexValue::Object(_)) = args.get(1) {
+        set_field(interpreter, this, CAUSE_FIELD, cause.clone());
+    }
+    capture_stack_trace(interpreter, this);
+    DexValue::Void
+}
+
+/// Throwable(cause) takes the description of the cause as its message
+fn throwable_init_with_cause(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let message = match args.first() {
+        Some(cause @ DexValue::Object(_)) => {
+            set_field(interpreter, this, CAUSE_FIELD, cause.clone());
+            DexValue::String(interpreter.string_value_of(cause))
+        }
+        _ => DexValue::Null,
+    };
+    set_field(interpreter, this, MESSAGE_FIELD, message);
+    capture_stack_trace(interpreter, this);
+    DexValue::Void
+}
+
+fn throwable_init_cause(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    if this.is_some() && args.first() == this.map(DexValue::Object).as_ref() {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "Self-causation not permitted");
+        return DexValue::Void;
+    }
+    set_field(interpreter, this, CAUSE_FIELD, args.into_iter().next().unwrap_or(DexValue::Null));
+    this.map_or(DexValue::Null, DexValue::Object)
+}
+
+/// The class name, followed by the message if there is one, e.g. `java.io.IOException: timeout`
+fn throwable_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let class_name = this.and_then(|id| interpreter.heap.get(&id)).map(|object| object.class_name.clone()).unwrap_or_default();
+    let name = binary_name(&class_name);
+    match this.and_then(|id| interpreter.exception_message(&DexValue::Object(id))) {
+        Some(message) => DexValue::String(format!("{}: {}", name, message)),
+        None => DexValue::String(name),
+    }
+}
+
+fn throwable_fill_in_stack_trace(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    capture_stack_trace(interpreter, this);
+    this.map_or(DexValue::Null, DexValue::Object)
+}
+
+fn stack_trace_of(interpreter: &Interpreter, this: Option<ObjectId>) -> Vec<String> {
+    match field(interpreter, this, STACK_TRACE_FIELD) {
+        DexValue::Array(trace) => trace.into_iter()
+            .filter_map(|method| match method {
+                DexValue::String(method) => Some(method),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// StackTraceElements of the recorded methods, which are written `class.method(descriptor)`,
+/// then ` (file:line)` if known
+fn throwable_get_stack_trace(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let elements = stack_trace_of(interpreter, this).into_iter()
+        .map(|method| {
+            let name = &method[..method.find('(').unwrap_or(method.len())];
+            let (class_name, method_name) = name.rsplit_once('.').unwrap_or(("", name));
+            let location = method.strip_suffix(')')
+                .and_then(|method| method.rsplit_once(" ("))
+                .map(|(_, location)| match location.rsplit_once(':') {
+                    Some((file, line)) => (file, line.parse().ok()),
+                    None => (location, None),
+                });
+            let id = interpreter.alloc_object(STACK_TRACE_ELEMENT);
+            set_field(interpreter, Some(id), CLASS_NAME_FIELD, DexValue::String(class_name.to_string()));
+            set_field(interpreter, Some(id), METHOD_NAME_FIELD, DexValue::String(method_name.to_string()));
+            if let Some((file, line)) = location {
+                if file != "Unknown Source" {
+                    set_field(interpreter, Some(id), FILE_NAME_FIELD, DexValue::String(file.to_string()));
+                }
+                if let Some(line) = line {
+                    set_field(interpreter, Some(id), LINE_NUMBER_FIELD, DexValue::Int(line));
+                }
+            }
+            DexValue::Object(id)
+        })
+        .collect();
+
+    let id = interpreter.alloc_object("[Ljava/lang/StackTraceElement;");
+    interpreter.heap.get_mut(&id).unwrap().array = Some(elements);
+    DexValue::Object(id)
+}
+
+/// What printStackTrace() would print: the exception, its stack and its causes
+fn exceptions_stack_trace_to_string(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut text = String::new();
+    let mut exception = args.into_iter().next().unwrap_or(DexValue::Null);
+    // Causes can form a cycle through initCause
+    let mut seen = Vec::new();
+    while let DexValue::Object(id) = exception {
+        if seen.contains(&id) {
+            break;
+        }
+        seen.push(id);
+        if !text.is_empty() {
+            text.push_str("Caused by: ");
+        }
+        text.push_str(&interpreter.string_value_of(&DexValue::Object(id)));
+        text.push('\n');
+        for method in stack_trace_of(interpreter, Some(id)) {
+            text.push_str(&format!("\tat {}\n", method));
+        }
+        exception = field(interpreter, Some(id), CAUSE_FIELD);
+    }
+    DexValue::String(text)
+}
+
+fn stack_trace_element_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let class_name = interpreter.string_value_of(&field(interpreter, this, CLASS_NAME_FIELD));
+    let method_name = interpreter.string_value_of(&field(interpreter, this, METHOD_NAME_FIELD));
+    let location = match (field(interpreter, this, FILE_NAME_FIELD), field(interpreter, this, LINE_NUMBER_FIELD)) {
+        (DexValue::String(file), DexValue::Int(line)) => format!("{}:{}", file, line),
+        (DexValue::String(file), _) => file,
+        (_, DexValue::Int(line)) => format!("Unknown Source:{}", line),
+        _ => "Unknown Source".to_string(),
+    };
+    DexValue::String(format!("{}.{}({})", class_name, method_name, location))
+}
@@ -0,0 +1,410 @@
+// java.io.File and the kotlin.io file extensions, confined to a scratch directory per source.
+// Sources see their directory as the root of the file system: absolute and relative paths both
+// resolve inside it and paths climbing above it are refused. Access is governed by
+// `Capability::ScratchStorage` and the directory is capped at `MAX_STORAGE_BYTES`.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use crate::interpreter::exception::{FILE_NOT_FOUND_EXCEPTION, IO_EXCEPTION, SECURITY_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{bytes_of, new_byte_array, PlatformMethod};
+use crate::policy::{self, Capability};
+use crate::types::{DexValue, ObjectId};
+
+pub const FILE: &str = "Ljava/io/File;";
+const FILES_KT: &str = "Lkotlin/io/FilesKt;";
+
+const PATH_FIELD: &str = "path";
+
+/// Scratch directories live below this directory, one per source
+const STORAGE_DIR: &str = "out/storage";
+
+/// Bytes a source may keep in its scratch directory
+pub const MAX_STORAGE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Path components below the scratch root, `None` if the path climbs above it
+fn components(path: &str) -> Option<Vec<&str>> {
+    let mut components = Vec::new();
+    for component in path.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => {
+                components.pop()?;
+            }
+            component => components.push(component),
+        }
+    }
+    Some(components)
+}
+
+/// Path like java.io.File keeps it, without repeated or trailing separators
+fn normalize(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let joined = path.split('/').filter(|component| !component.is_empty()).collect::<Vec<_>>().join("/");
+    if absolute { format!("/{}", joined) } else { joined }
+}
+
+/// Scratch directory of the running source, throws SecurityException without the capability
+fn storage_root(interpreter: &mut Interpreter) -> Option<PathBuf> {
+    let source = interpreter.source_name().unwrap_or_default();
+    if !policy::is_granted(&source, Capability::ScratchStorage) {
+        interpreter.throw_new(SECURITY_EXCEPTION, &format!("{} has no access to scratch storage", source));
+        return None;
+    }
+
+    let namespace = source.trim_start_matches('L').trim_end_matches(';').replace('/', ".");
+    Some(Path::new(STORAGE_DIR).join(namespace))
+}
+
+/// The scratch root and the file `path` refers to on disk
+fn resolve(interpreter: &mut Interpreter, path: &str) -> Option<(PathBuf, PathBuf)> {
+    let root = storage_root(interpreter)?;
+    match components(path) {
+        Some(components) => {
+            let target = components.iter().fold(root.clone(), |target, component| target.join(component));
+            Some((root, target))
+        }
+        None => {
+            interpreter.throw_new(SECURITY_EXCEPTION, &format!("{} is outside of the scratch storage", path));
+            None
+        }
+    }
+}
+
+fn directory_size(path: &Path) -> u64 {
+    let Ok(entries) = fs::read_dir(path) else {
+        return 0;
+    };
+    entries.flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(metadata) if metadata.is_dir() => directory_size(&entry.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn read_file(interpreter: &mut Interpreter, path: &str) -> Option<Vec<u8>> {
+    let (_, target) = resolve(interpreter, path)?;
+    match fs::read(&target) {
+        Ok(data) => Some(data),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            interpreter.throw_new(FILE_NOT_FOUND_EXCEPTION, &format!("{} (No such file or directory)", path));
+            None
+        }
+        Err(error) => {
+            interpreter.throw_new(IO_EXCEPTION, &error.to_string());
+            None
+        }
+    }
+}
+
+/// Write or append `data`, throws IOException if the scratch directory would grow past its cap
+fn write_file(interpreter: &mut Interpreter, path: &str, data: &[u8], append: bool) {
+    let Some((root, target)) = resolve(interpreter, path) else {
+        return;
+    };
+
+    let replaced = if append { 0 } else { fs::metadata(&target).map(|metadata| metadata.len()).unwrap_or(0) };
+    if directory_size(&root).saturating_sub(replaced) + data.len() as u64 > MAX_STORAGE_BYTES {
+        interpreter.throw_new(IO_EXCEPTION, &format!("Scratch storage is limited to {} bytes", MAX_STORAGE_BYTES));
+        return;
+    }
+
+    let written = fs::create_dir_all(target.parent().unwrap_or(&root))
+        .and_then(|_| OpenOptions::new().create(true).write(true).append(append).truncate(!append).open(&target))
+        .and_then(|mut file| file.write_all(data));
+    if let Err(error) = written {
+        interpreter.throw_new(IO_EXCEPTION, &error.to_string());
+    }
+}
+
+fn path_of(interpreter: &Interpreter, file: Option<ObjectId>) -> String {
+    match file.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(PATH_FIELD)) {
+        Some(DexValue::String(path)) => path.clone(),
+        _ => String::new(),
+    }
+}
+
+fn string_arg(args: &[DexValue], index: usize) -> Option<String> {
+    match args.get(index) {
+        Some(DexValue::String(value)) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn object_arg(args: &[DexValue], index: usize) -> Option<ObjectId> {
+    match args.get(index) {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+fn new_file(interpreter: &mut Interpreter, path: &str) -> DexValue {
+    let id = interpreter.alloc_object(FILE);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(PATH_FIELD.to_string(), DexValue::String(normalize(path)));
+    DexValue::Object(id)
+}
+
+/// File(path)
+fn file_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let path = normalize(&string_arg(&args, 0).unwrap_or_default());
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(PATH_FIELD.to_string(), DexValue::String(path));
+    }
+    DexValue::Void
+}
+
+/// File(parent, child) with the parent as a String or a File
+fn file_init_child(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let parent = match args.first() {
+        Some(DexValue::String(parent)) => Some(parent.clone()),
+        Some(DexValue::Object(id)) => Some(path_of(interpreter, Some(*id))),
+        _ => None,
+    };
+    let child = string_arg(&args, 1).unwrap_or_default();
+    let path = match parent {
+        Some(parent) => format!("{}/{}", parent, child),
+        None => child,
+    };
+    file_init(interpreter, this, vec![DexValue::String(path)])
+}
+
+fn file_get_path(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(path_of(interpreter, this))
+}
+
+fn file_get_name(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    DexValue::String(path.rsplit('/').next().unwrap_or("").to_string())
+}
+
+fn file_get_parent(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    match path_of(interpreter, this).rsplit_once('/') {
+        Some(("", _)) => DexValue::String("/".to_string()),
+        Some((parent, _)) => DexValue::String(parent.to_string()),
+        None => DexValue::Null,
+    }
+}
+
+fn file_get_parent_file(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match file_get_parent(interpreter, this, args) {
+        DexValue::String(parent) => new_file(interpreter, &parent),
+        _ => DexValue::Null,
+    }
+}
+
+/// Absolute path as the source sees it, rooted at its scratch directory
+fn absolute_path(interpreter: &Interpreter, this: Option<ObjectId>) -> String {
+    let path = path_of(interpreter, this);
+    match components(&path) {
+        Some(components) => format!("/{}", components.join("/")),
+        None => path,
+    }
+}
+
+fn file_get_absolute_path(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(absolute_path(interpreter, this))
+}
+
+fn file_get_absolute_file(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = absolute_path(interpreter, this);
+    new_file(interpreter, &path)
+}
+
+fn file_exists(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    DexValue::Boolean(resolve(interpreter, &path).is_some_and(|(_, target)| target.exists()))
+}
+
+fn file_is_file(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    DexValue::Boolean(resolve(interpreter, &path).is_some_and(|(_, target)| target.is_file()))
+}
+
+fn file_is_directory(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    DexValue::Boolean(resolve(interpreter, &path).is_some_and(|(_, target)| target.is_dir()))
+}
+
+fn file_length(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    let length = resolve(interpreter, &path)
+        .and_then(|(_, target)| fs::metadata(target).ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .unwrap_or(0);
+    DexValue::Long(length as i64)
+}
+
+fn file_last_modified(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    let modified = resolve(interpreter, &path)
+        .and_then(|(_, target)| fs::metadata(target).and_then(|metadata| metadata.modified()).ok())
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or(0);
+    DexValue::Long(modified)
+}
+
+/// Deletes a file or an empty directory, the scratch root itself stays
+fn file_delete(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    let deleted = resolve(interpreter, &path).is_some_and(|(root, target)| {
+        target != root && if target.is_dir() { fs::remove_dir(&target).is_ok() } else { fs::remove_file(&target).is_ok() }
+    });
+    DexValue::Boolean(deleted)
+}
+
+fn file_mkdir(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    let created = resolve(interpreter, &path).is_some_and(|(root, target)| {
+        // The scratch root is created on demand, so a top-level directory always has its parent
+        let _ = fs::create_dir_all(&root);
+        fs::create_dir(&target).is_ok()
+    });
+    DexValue::Boolean(created)
+}
+
+fn file_mkdirs(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    let created = resolve(interpreter, &path).is_some_and(|(_, target)| !target.exists() && fs::create_dir_all(&target).is_ok());
+    DexValue::Boolean(created)
+}
+
+fn file_create_new_file(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    let Some((_, target)) = resolve(interpreter, &path) else {
+        return DexValue::Boolean(false);
+    };
+    if target.exists() {
+        return DexValue::Boolean(false);
+    }
+    write_file(interpreter, &path, &[], false);
+    DexValue::Boolean(target.exists())
+}
+
+/// Names of the directory's entries, null if it isn't a directory
+fn file_list(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, this);
+    let Some(entries) = resolve(interpreter, &path).and_then(|(_, target)| fs::read_dir(target).ok()) else {
+        return DexValue::Null;
+    };
+
+    let mut names: Vec<String> = entries.flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    let id = interpreter.alloc_object("[Ljava/lang/String;");
+    interpreter.heap.get_mut(&id).unwrap().array = Some(names.into_iter().map(DexValue::String).collect());
+    DexValue::Object(id)
+}
+
+/// File.createTempFile(prefix, suffix), inside /tmp of the scratch directory
+fn file_create_temp_file(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let prefix = string_arg(&args, 0).unwrap_or_default();
+    let suffix = string_arg(&args, 1).unwrap_or_else(|| ".tmp".to_string());
+
+    let Some((_, tmp)) = resolve(interpreter, "/tmp") else {
+        return DexValue::Null;
+    };
+    let index = (0..).find(|index| !tmp.join(format!("{}{}{}", prefix, index, suffix)).exists()).unwrap_or(0);
+    let path = format!("/tmp/{}{}{}", prefix, index, suffix);
+
+    write_file(interpreter, &path, &[], false);
+    new_file(interpreter, &path)
+}
+
+// kotlin.io extensions are static methods taking the File first, charsets are always UTF-8
+
+fn files_read_text(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, object_arg(&args, 0));
+    match read_file(interpreter, &path) {
+        Some(data) => DexValue::String(String::from_utf8_lossy(&data).to_string()),
+        None => DexValue::Null,
+    }
+}
+
+fn files_write_text(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, object_arg(&args, 0));
+    write_file(interpreter, &path, string_arg(&args, 1).unwrap_or_default().as_bytes(), false);
+    DexValue::Void
+}
+
+fn files_append_text(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, object_arg(&args, 0));
+    write_file(interpreter, &path, string_arg(&args, 1).unwrap_or_default().as_bytes(), true);
+    DexValue::Void
+}
+
+fn files_read_bytes(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, object_arg(&args, 0));
+    match read_file(interpreter, &path) {
+        Some(data) => new_byte_array(interpreter, data),
+        None => DexValue::Null,
+    }
+}
+
+fn files_write_bytes(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, object_arg(&args, 0));
+    let data = bytes_of(interpreter, object_arg(&args, 1));
+    write_file(interpreter, &path, &data, false);
+    DexValue::Void
+}
+
+fn files_append_bytes(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let path = path_of(interpreter, object_arg(&args, 0));
+    let data = bytes_of(interpreter, object_arg(&args, 1));
+    write_file(interpreter, &path, &data, true);
+    DexValue::Void
+}
+
+/// File class methods by class descriptor and method key, static ones are called without `this`
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (FILE, "<init>:(Ljava/lang/String;)V") => file_init,
+        (FILE, "<init>:(Ljava/lang/String;Ljava/lang/String;)V" | "<init>:(Ljava/io/File;Ljava/lang/String;)V") => file_init_child,
+        (FILE, "getPath:()Ljava/lang/String;" | "toString:()Ljava/lang/String;") => file_get_path,
+        (FILE, "getName:()Ljava/lang/String;") => file_get_name,
+        (FILE, "getParent:()Ljava/lang/String;") => file_get_parent,
+        (FILE, "getParentFile:()Ljava/io/File;") => file_get_parent_file,
+        (FILE, "getAbsolutePath:()Ljava/lang/String;" | "getCanonicalPath:()Ljava/lang/String;") => file_get_absolute_path,
+        (FILE, "getAbsoluteFile:()Ljava/io/File;" | "getCanonicalFile:()Ljava/io/File;") => file_get_absolute_file,
+        (FILE, "exists:()Z") => file_exists,
+        (FILE, "isFile:()Z") => file_is_file,
+        (FILE, "isDirectory:()Z") => file_is_directory,
+        (FILE, "length:()J") => file_length,
+        (FILE, "lastModified:()J") => file_last_modified,
+        (FILE, "delete:()Z") => file_delete,
+        (FILE, "mkdir:()Z") => file_mkdir,
+        (FILE, "mkdirs:()Z") => file_mkdirs,
+        (FILE, "createNewFile:()Z") => file_create_new_file,
+        (FILE, "list:()[Ljava/lang/String;") => file_list,
+        (FILE, "createTempFile:(Ljava/lang/String;Ljava/lang/String;)Ljava/io/File;") => file_create_temp_file,
+
+        // The $default variants carry a mask and a marker after the regular arguments
+        (
+            FILES_KT,
+            "readText:(Ljava/io/File;Ljava/nio/charset/Charset;)Ljava/lang/String;"
+            | "readText$default:(Ljava/io/File;Ljava/nio/charset/Charset;ILjava/lang/Object;)Ljava/lang/String;",
+        ) => files_read_text,
+        (
+            FILES_KT,
+            "writeText:(Ljava/io/File;Ljava/lang/String;Ljava/nio/charset/Charset;)V"
+            | "writeText$default:(Ljava/io/File;Ljava/lang/String;Ljava/nio/charset/Charset;ILjava/lang/Object;)V",
+        ) => files_write_text,
+        (
+            FILES_KT,
+            "appendText:(Ljava/io/File;Ljava/lang/String;Ljava/nio/charset/Charset;)V"
+            | "appendText$default:(Ljava/io/File;Ljava/lang/String;Ljava/nio/charset/Charset;ILjava/lang/Object;)V",
+        ) => files_append_text,
+        (FILES_KT, "readBytes:(Ljava/io/File;)[B") => files_read_bytes,
+        (FILES_KT, "writeBytes:(Ljava/io/File;[B)V") => files_write_bytes,
+        (FILES_KT, "appendBytes:(Ljava/io/File;[B)V") => files_append_bytes,
+        _ => return None,
+    };
+    Some(method)
+}
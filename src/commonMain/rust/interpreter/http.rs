@@ -0,0 +1,2022 @@
+// OkHttp as extensions use it, along with the request helpers and network plumbing of the source
+// api. Requests, responses, headers, bodies and urls are heap objects holding what OkHttp's value
+// classes hold, and `Call.execute()` runs the client's interceptors, interpreted code like any
+// other, before the last one hands the request to ureq, blocking until the whole body is in. A
+// response body stays in memory and can be read more than once, unlike OkHttp's streams.
+// Redirects are followed here rather than by ureq so every hop goes through the source's cookie
+// jar, see cookies.rs. There is no HTTP/2 or cache behind the client.
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::time::Duration;
+
+use lazy_static::lazy_static;
+
+use crate::interpreter::charset::Charset;
+use crate::interpreter::collections::{elements_of, map_entries, new_list, new_set};
+use crate::interpreter::clock;
+use crate::interpreter::cookies::{self, Cookie};
+use crate::interpreter::coroutines;
+use crate::interpreter::exception::{
+    ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, CONNECT_EXCEPTION, HTTP_EXCEPTION, ILLEGAL_ARGUMENT_EXCEPTION, ILLEGAL_STATE_EXCEPTION, IO_EXCEPTION,
+    NULL_POINTER_EXCEPTION, PROTOCOL_EXCEPTION, SOCKET_TIMEOUT_EXCEPTION, UNKNOWN_HOST_EXCEPTION,
+};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{bytes_of, new_byte_array, PlatformMethod, HTTP_SOURCE};
+use crate::interpreter::url::{percent_decode, remove_dot_segments, resolve_reference};
+use crate::types::{DexValue, ObjectId};
+
+pub const OK_HTTP_CLIENT: &str = "Lokhttp3/OkHttpClient;";
+pub const OK_HTTP_CLIENT_BUILDER: &str = "Lokhttp3/OkHttpClient$Builder;";
+pub const CALL: &str = "Lokhttp3/internal/connection/RealCall;";
+pub const CHAIN: &str = "Lokhttp3/internal/http/RealInterceptorChain;";
+pub const REQUEST: &str = "Lokhttp3/Request;";
+pub const REQUEST_BUILDER: &str = "Lokhttp3/Request$Builder;";
+pub const RESPONSE: &str = "Lokhttp3/Response;";
+pub const RESPONSE_BUILDER: &str = "Lokhttp3/Response$Builder;";
+pub const HEADERS: &str = "Lokhttp3/Headers;";
+pub const HEADERS_BUILDER: &str = "Lokhttp3/Headers$Builder;";
+pub const HEADERS_COMPANION: &str = "Lokhttp3/Headers$Companion;";
+pub const HTTP_URL: &str = "Lokhttp3/HttpUrl;";
+pub const HTTP_URL_BUILDER: &str = "Lokhttp3/HttpUrl$Builder;";
+pub const HTTP_URL_COMPANION: &str = "Lokhttp3/HttpUrl$Companion;";
+pub const MEDIA_TYPE: &str = "Lokhttp3/MediaType;";
+pub const MEDIA_TYPE_COMPANION: &str = "Lokhttp3/MediaType$Companion;";
+pub const REQUEST_BODY: &str = "Lokhttp3/RequestBody;";
+pub const REQUEST_BODY_COMPANION: &str = "Lokhttp3/RequestBody$Companion;";
+pub const FORM_BODY: &str = "Lokhttp3/FormBody;";
+pub const FORM_BODY_BUILDER: &str = "Lokhttp3/FormBody$Builder;";
+pub const RESPONSE_BODY: &str = "Lokhttp3/ResponseBody;";
+pub const RESPONSE_BODY_COMPANION: &str = "Lokhttp3/ResponseBody$Companion;";
+pub const COOKIE: &str = "Lokhttp3/Cookie;";
+pub const COOKIE_COMPANION: &str = "Lokhttp3/Cookie$Companion;";
+/// What both `network.cookieJar` and `client.cookieJar` give, a view of the source's jar
+pub const COOKIE_JAR: &str = "Leu/kanade/tachiyomi/network/AndroidCookieJar;";
+pub const REQUESTS_KT: &str = "Leu/kanade/tachiyomi/network/RequestsKt;";
+pub const NETWORK_HELPER: &str = "Leu/kanade/tachiyomi/network/NetworkHelper;";
+pub const OK_HTTP_EXTENSIONS_KT: &str = "Leu/kanade/tachiyomi/network/OkHttpExtensionsKt;";
+
+/// What `network.defaultUserAgentProvider()` answers on a stock Mihon install
+pub const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Linux; Android 10; K) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Mobile Safari/537.36";
+
+// Components of an HttpUrl and its builder, encoded like they appear in the url
+const SCHEME_FIELD: &str = "scheme";
+/// Lowercase host, with the port if it isn't the scheme's default
+const AUTHORITY_FIELD: &str = "authority";
+const PATH_FIELD: &str = "path";
+const QUERY_FIELD: &str = "query";
+const FRAGMENT_FIELD: &str = "fragment";
+
+// Fields of requests, responses and calls. Header names and values alternate in the `array` of
+// a Headers object or a builder, the bytes of a body are in its `array`.
+const URL_FIELD: &str = "url";
+const METHOD_FIELD: &str = "method";
+const HEADERS_FIELD: &str = "headers";
+const BODY_FIELD: &str = "body";
+const REQUEST_FIELD: &str = "request";
+const CODE_FIELD: &str = "code";
+const MESSAGE_FIELD: &str = "message";
+const CONTENT_TYPE_FIELD: &str = "contentType";
+const MEDIA_TYPE_FIELD: &str = "mediaType";
+
+// Lists of the interceptors of a client or its builder, those of a chain are in its `array`
+const INTERCEPTORS_FIELD: &str = "interceptors";
+const NETWORK_INTERCEPTORS_FIELD: &str = "networkInterceptors";
+const CLIENT_FIELD: &str = "client";
+const CALL_FIELD: &str = "call";
+/// Position in its `array` of the interceptor a chain goes on with
+const INDEX_FIELD: &str = "index";
+
+const INTERCEPT: &str = "intercept:(Lokhttp3/Interceptor$Chain;)Lokhttp3/Response;";
+
+// What an HttpSource keeps once asked, named so no Kotlin property can clash with them
+const SOURCE_CLIENT_FIELD: &str = "$client";
+const SOURCE_HEADERS_FIELD: &str = "$headers";
+
+// Fields of a Cookie object, named like its properties
+const NAME_FIELD: &str = "name";
+const VALUE_FIELD: &str = "value";
+const DOMAIN_FIELD: &str = "domain";
+const COOKIE_PATH_FIELD: &str = "path";
+const EXPIRES_AT_FIELD: &str = "expiresAt";
+const SECURE_FIELD: &str = "secure";
+const HTTP_ONLY_FIELD: &str = "httpOnly";
+const HOST_ONLY_FIELD: &str = "hostOnly";
+const PERSISTENT_FIELD: &str = "persistent";
+
+/// `expiresAt` of a session cookie, the latest date OkHttp represents
+const MAX_DATE: i64 = 253_402_300_799_999;
+
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+// Chars OkHttp percent-encodes in each part of a url, besides controls, non-ASCII and stray `%`
+const PATH_ENCODE_SET: &str = " \"<>^`{}|";
+const PATH_SEGMENT_ENCODE_SET: &str = " \"<>^`{}|/\\?#";
+const QUERY_ENCODE_SET: &str = " \"'<>#";
+const QUERY_COMPONENT_ENCODE_SET: &str = " !\"#$&'(),/:;<=>?@[]\\^`{|}~+";
+const QUERY_COMPONENT_REENCODE_SET: &str = " \"'<>#&=";
+const FRAGMENT_ENCODE_SET: &str = "";
+const FORM_ENCODE_SET: &str = " !\"#$&'()+,/:;<=>?@[\\]^`{|}~";
+
+/// OkHttp's defaults for the connect, read and write timeouts
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Redirects OkHttp follows before giving up
+const MAX_FOLLOW_UPS: usize = 20;
+
+lazy_static! {
+    /// Shared so connections to a host are reused across calls
+    static ref AGENT: ureq::Agent = ureq::AgentBuilder::new()
+        .timeout_connect(TIMEOUT)
+        .timeout_read(TIMEOUT)
+        .timeout_write(TIMEOUT)
+        .redirects(0)
+        .user_agent("okhttp/5.0.0-alpha.14")
+        .build();
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (HTTP_SOURCE, "getClient:()Lokhttp3/OkHttpClient;") => source_client,
+        (HTTP_SOURCE, "getNetwork:()Leu/kanade/tachiyomi/network/NetworkHelper;") => |interpreter, _, _| DexValue::Object(interpreter.alloc_object(NETWORK_HELPER)),
+        (HTTP_SOURCE, "getHeaders:()Lokhttp3/Headers;") => source_headers,
+        (HTTP_SOURCE, "headersBuilder:()Lokhttp3/Headers$Builder;") => source_headers_builder,
+        (NETWORK_HELPER, "getClient:()Lokhttp3/OkHttpClient;" | "getCloudflareClient:()Lokhttp3/OkHttpClient;") => |interpreter, _, _| {
+            new_client(interpreter, Vec::new(), Vec::new())
+        },
+        (NETWORK_HELPER, "defaultUserAgentProvider:()Ljava/lang/String;") => |_, _, _| DexValue::String(DEFAULT_USER_AGENT.to_string()),
+        (NETWORK_HELPER, "getCookieJar:()Leu/kanade/tachiyomi/network/AndroidCookieJar;") => |interpreter, _, _| {
+            DexValue::Object(interpreter.alloc_object(COOKIE_JAR))
+        },
+        (OK_HTTP_CLIENT, "cookieJar:()Lokhttp3/CookieJar;") => |interpreter, _, _| DexValue::Object(interpreter.alloc_object(COOKIE_JAR)),
+        (COOKIE_JAR, "loadForRequest:(Lokhttp3/HttpUrl;)Ljava/util/List;" | "get:(Lokhttp3/HttpUrl;)Ljava/util/List;") => cookie_jar_load,
+        (COOKIE_JAR, "saveFromResponse:(Lokhttp3/HttpUrl;Ljava/util/List;)V") => cookie_jar_save,
+        (COOKIE_JAR, "removeAll:()V") => |interpreter, _, _| {
+            cookies::clear(&interpreter.source_name().unwrap_or_default(), None);
+            DexValue::Void
+        },
+        (COOKIE | COOKIE_COMPANION, "parse:(Lokhttp3/HttpUrl;Ljava/lang/String;)Lokhttp3/Cookie;") => |interpreter, _, args| {
+            let url = url_of(interpreter, object_arg(&args, 0));
+            let header = string_arg(&args, 1).unwrap_or_default();
+            match Cookie::parse(url.host(), &url.path, &header, clock::now_millis()) {
+                Some(cookie) => new_cookie(interpreter, &cookie),
+                None => DexValue::Null,
+            }
+        },
+        (COOKIE | COOKIE_COMPANION, "parseAll:(Lokhttp3/HttpUrl;Lokhttp3/Headers;)Ljava/util/List;") => |interpreter, _, args| {
+            let url = url_of(interpreter, object_arg(&args, 0));
+            let headers = header_pairs(interpreter, &arg(&args, 1));
+            let cookies = set_cookies(&url, &headers).iter().map(|cookie| new_cookie(interpreter, cookie)).collect();
+            DexValue::Object(new_list(interpreter, cookies))
+        },
+        (COOKIE, "name:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, NAME_FIELD),
+        (COOKIE, "value:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, VALUE_FIELD),
+        (COOKIE, "domain:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, DOMAIN_FIELD),
+        (COOKIE, "path:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, COOKIE_PATH_FIELD),
+        (COOKIE, "expiresAt:()J") => |interpreter, this, _| field(interpreter, this, EXPIRES_AT_FIELD),
+        (COOKIE, "secure:()Z") => |interpreter, this, _| field(interpreter, this, SECURE_FIELD),
+        (COOKIE, "httpOnly:()Z") => |interpreter, this, _| field(interpreter, this, HTTP_ONLY_FIELD),
+        (COOKIE, "hostOnly:()Z") => |interpreter, this, _| field(interpreter, this, HOST_ONLY_FIELD),
+        (COOKIE, "persistent:()Z") => |interpreter, this, _| field(interpreter, this, PERSISTENT_FIELD),
+        (COOKIE, "matches:(Lokhttp3/HttpUrl;)Z") => |interpreter, this, args| {
+            let url = url_of(interpreter, object_arg(&args, 0));
+            DexValue::Boolean(cookie_of(interpreter, this).matches(url.host(), &url.path, url.scheme == "https"))
+        },
+        (COOKIE, "toString:()Ljava/lang/String;") => cookie_to_string,
+
+        (REQUESTS_KT, "GET:(Ljava/lang/String;Lokhttp3/Headers;Lokhttp3/CacheControl;)Lokhttp3/Request;"
+            | "GET:(Lokhttp3/HttpUrl;Lokhttp3/Headers;Lokhttp3/CacheControl;)Lokhttp3/Request;") => |interpreter, _, args| {
+            helper_request(interpreter, "GET", &args, 0)
+        },
+        (REQUESTS_KT, "GET$default:(Ljava/lang/String;Lokhttp3/Headers;Lokhttp3/CacheControl;ILjava/lang/Object;)Lokhttp3/Request;"
+            | "GET$default:(Lokhttp3/HttpUrl;Lokhttp3/Headers;Lokhttp3/CacheControl;ILjava/lang/Object;)Lokhttp3/Request;") => |interpreter, _, args| {
+            let mask = int_arg(&args, 3);
+            helper_request(interpreter, "GET", &args, mask)
+        },
+        (REQUESTS_KT, "POST:(Ljava/lang/String;Lokhttp3/Headers;Lokhttp3/RequestBody;Lokhttp3/CacheControl;)Lokhttp3/Request;") => |interpreter, _, args| {
+            helper_request(interpreter, "POST", &args, 0)
+        },
+        (REQUESTS_KT, "POST$default:(Ljava/lang/String;Lokhttp3/Headers;Lokhttp3/RequestBody;Lokhttp3/CacheControl;ILjava/lang/Object;)Lokhttp3/Request;") => |interpreter, _, args| {
+            let mask = int_arg(&args, 4);
+            helper_request(interpreter, "POST", &args, mask)
+        },
+        (REQUESTS_KT, "PUT:(Ljava/lang/String;Lokhttp3/Headers;Lokhttp3/RequestBody;Lokhttp3/CacheControl;)Lokhttp3/Request;") => |interpreter, _, args| {
+            helper_request(interpreter, "PUT", &args, 0)
+        },
+        (REQUESTS_KT, "PUT$default:(Ljava/lang/String;Lokhttp3/Headers;Lokhttp3/RequestBody;Lokhttp3/CacheControl;ILjava/lang/Object;)Lokhttp3/Request;") => |interpreter, _, args| {
+            let mask = int_arg(&args, 4);
+            helper_request(interpreter, "PUT", &args, mask)
+        },
+        (REQUESTS_KT, "DELETE:(Ljava/lang/String;Lokhttp3/Headers;Lokhttp3/RequestBody;Lokhttp3/CacheControl;)Lokhttp3/Request;") => |interpreter, _, args| {
+            helper_request(interpreter, "DELETE", &args, 0)
+        },
+        (REQUESTS_KT, "DELETE$default:(Ljava/lang/String;Lokhttp3/Headers;Lokhttp3/RequestBody;Lokhttp3/CacheControl;ILjava/lang/Object;)Lokhttp3/Request;") => |interpreter, _, args| {
+            let mask = int_arg(&args, 4);
+            helper_request(interpreter, "DELETE", &args, mask)
+        },
+
+        (OK_HTTP_CLIENT | OK_HTTP_CLIENT_BUILDER, "<init>:()V") => |interpreter, this, _| {
+            store_interceptors(interpreter, this, Vec::new(), Vec::new());
+            DexValue::Void
+        },
+        (OK_HTTP_CLIENT, "newCall:(Lokhttp3/Request;)Lokhttp3/Call;") => new_call,
+        (OK_HTTP_CLIENT, "newBuilder:()Lokhttp3/OkHttpClient$Builder;") => |interpreter, this, _| {
+            let (interceptors, network_interceptors) = interceptors_of(interpreter, this);
+            let id = interpreter.alloc_object(OK_HTTP_CLIENT_BUILDER);
+            store_interceptors(interpreter, Some(id), interceptors, network_interceptors);
+            DexValue::Object(id)
+        },
+        (OK_HTTP_CLIENT | OK_HTTP_CLIENT_BUILDER, "interceptors:()Ljava/util/List;") => |interpreter, this, _| {
+            field(interpreter, this, INTERCEPTORS_FIELD)
+        },
+        (OK_HTTP_CLIENT | OK_HTTP_CLIENT_BUILDER, "networkInterceptors:()Ljava/util/List;") => |interpreter, this, _| {
+            field(interpreter, this, NETWORK_INTERCEPTORS_FIELD)
+        },
+        (OK_HTTP_CLIENT_BUILDER, "addInterceptor:(Lokhttp3/Interceptor;)Lokhttp3/OkHttpClient$Builder;") => |interpreter, this, args| {
+            add_interceptor(interpreter, this, INTERCEPTORS_FIELD, arg(&args, 0))
+        },
+        (OK_HTTP_CLIENT_BUILDER, "addNetworkInterceptor:(Lokhttp3/Interceptor;)Lokhttp3/OkHttpClient$Builder;") => |interpreter, this, args| {
+            add_interceptor(interpreter, this, NETWORK_INTERCEPTORS_FIELD, arg(&args, 0))
+        },
+        // Timeouts and connection settings are those of the shared agent
+        (OK_HTTP_CLIENT_BUILDER, "connectTimeout:(JLjava/util/concurrent/TimeUnit;)Lokhttp3/OkHttpClient$Builder;"
+            | "readTimeout:(JLjava/util/concurrent/TimeUnit;)Lokhttp3/OkHttpClient$Builder;"
+            | "writeTimeout:(JLjava/util/concurrent/TimeUnit;)Lokhttp3/OkHttpClient$Builder;"
+            | "callTimeout:(JLjava/util/concurrent/TimeUnit;)Lokhttp3/OkHttpClient$Builder;"
+            | "connectTimeout:(Ljava/time/Duration;)Lokhttp3/OkHttpClient$Builder;"
+            | "readTimeout:(Ljava/time/Duration;)Lokhttp3/OkHttpClient$Builder;"
+            | "writeTimeout:(Ljava/time/Duration;)Lokhttp3/OkHttpClient$Builder;"
+            | "callTimeout:(Ljava/time/Duration;)Lokhttp3/OkHttpClient$Builder;"
+            | "followRedirects:(Z)Lokhttp3/OkHttpClient$Builder;"
+            | "followSslRedirects:(Z)Lokhttp3/OkHttpClient$Builder;"
+            | "retryOnConnectionFailure:(Z)Lokhttp3/OkHttpClient$Builder;"
+            | "cache:(Lokhttp3/Cache;)Lokhttp3/OkHttpClient$Builder;"
+            | "cookieJar:(Lokhttp3/CookieJar;)Lokhttp3/OkHttpClient$Builder;"
+            | "dns:(Lokhttp3/Dns;)Lokhttp3/OkHttpClient$Builder;"
+            | "protocols:(Ljava/util/List;)Lokhttp3/OkHttpClient$Builder;"
+            | "connectionPool:(Lokhttp3/ConnectionPool;)Lokhttp3/OkHttpClient$Builder;"
+            | "dispatcher:(Lokhttp3/Dispatcher;)Lokhttp3/OkHttpClient$Builder;") => |_, this, _| this_value(this),
+        (OK_HTTP_CLIENT_BUILDER, "build:()Lokhttp3/OkHttpClient;") => |interpreter, this, _| {
+            let (interceptors, network_interceptors) = interceptors_of(interpreter, this);
+            new_client(interpreter, interceptors, network_interceptors)
+        },
+        (CALL, "execute:()Lokhttp3/Response;") => call_execute,
+        (OK_HTTP_EXTENSIONS_KT, "await:(Lokhttp3/Call;Lkotlin/coroutines/Continuation;)Ljava/lang/Object;") => |interpreter, _, args| {
+            call_await(interpreter, &args, false)
+        },
+        (OK_HTTP_EXTENSIONS_KT, "awaitSuccess:(Lokhttp3/Call;Lkotlin/coroutines/Continuation;)Ljava/lang/Object;") => |interpreter, _, args| {
+            call_await(interpreter, &args, true)
+        },
+        (HTTP_EXCEPTION, "getCode:()I") => |interpreter, this, _| field(interpreter, this, CODE_FIELD),
+        (CALL, "request:()Lokhttp3/Request;") => |interpreter, this, _| field(interpreter, this, REQUEST_FIELD),
+        (CALL, "clone:()Lokhttp3/Call;") => |interpreter, this, _| {
+            let client = object_of(&field(interpreter, this, CLIENT_FIELD));
+            let request = field(interpreter, this, REQUEST_FIELD);
+            new_call(interpreter, client, vec![request])
+        },
+        (CALL, "cancel:()V") => |_, _, _| DexValue::Void,
+        (CALL, "isCanceled:()Z") => |_, _, _| DexValue::Boolean(false),
+        (CHAIN, "request:()Lokhttp3/Request;") => |interpreter, this, _| field(interpreter, this, REQUEST_FIELD),
+        (CHAIN, "proceed:(Lokhttp3/Request;)Lokhttp3/Response;") => chain_proceed,
+        (CHAIN, "call:()Lokhttp3/Call;") => |interpreter, this, _| field(interpreter, this, CALL_FIELD),
+        (CHAIN, "connection:()Lokhttp3/Connection;") => |_, _, _| DexValue::Null,
+        (CHAIN, "connectTimeoutMillis:()I" | "readTimeoutMillis:()I" | "writeTimeoutMillis:()I") => |_, _, _| {
+            DexValue::Int(TIMEOUT.as_millis() as i32)
+        },
+        (CHAIN, "withConnectTimeout:(ILjava/util/concurrent/TimeUnit;)Lokhttp3/Interceptor$Chain;"
+            | "withReadTimeout:(ILjava/util/concurrent/TimeUnit;)Lokhttp3/Interceptor$Chain;"
+            | "withWriteTimeout:(ILjava/util/concurrent/TimeUnit;)Lokhttp3/Interceptor$Chain;") => |_, this, _| this_value(this),
+
+        (REQUEST_BUILDER, "<init>:()V") => request_builder_init,
+        (REQUEST_BUILDER, "url:(Ljava/lang/String;)Lokhttp3/Request$Builder;" | "url:(Lokhttp3/HttpUrl;)Lokhttp3/Request$Builder;") => request_builder_url,
+        (REQUEST_BUILDER, "header:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Request$Builder;") => |interpreter, this, args| {
+            headers_builder_set(interpreter, this, args)
+        },
+        (REQUEST_BUILDER, "addHeader:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Request$Builder;") => |interpreter, this, args| {
+            headers_builder_add(interpreter, this, args)
+        },
+        (REQUEST_BUILDER, "removeHeader:(Ljava/lang/String;)Lokhttp3/Request$Builder;") => headers_builder_remove_all,
+        (REQUEST_BUILDER, "headers:(Lokhttp3/Headers;)Lokhttp3/Request$Builder;") => request_builder_headers,
+        (REQUEST_BUILDER, "get:()Lokhttp3/Request$Builder;") => |interpreter, this, _| request_builder_method(interpreter, this, "GET", DexValue::Null),
+        (REQUEST_BUILDER, "head:()Lokhttp3/Request$Builder;") => |interpreter, this, _| request_builder_method(interpreter, this, "HEAD", DexValue::Null),
+        (REQUEST_BUILDER, "post:(Lokhttp3/RequestBody;)Lokhttp3/Request$Builder;") => |interpreter, this, args| {
+            request_builder_method(interpreter, this, "POST", arg(&args, 0))
+        },
+        (REQUEST_BUILDER, "put:(Lokhttp3/RequestBody;)Lokhttp3/Request$Builder;") => |interpreter, this, args| {
+            request_builder_method(interpreter, this, "PUT", arg(&args, 0))
+        },
+        (REQUEST_BUILDER, "patch:(Lokhttp3/RequestBody;)Lokhttp3/Request$Builder;") => |interpreter, this, args| {
+            request_builder_method(interpreter, this, "PATCH", arg(&args, 0))
+        },
+        (REQUEST_BUILDER, "delete:()Lokhttp3/Request$Builder;") => |interpreter, this, _| {
+            let body = new_body(interpreter, REQUEST_BODY, Vec::new(), DexValue::Null);
+            request_builder_method(interpreter, this, "DELETE", body)
+        },
+        (REQUEST_BUILDER, "delete:(Lokhttp3/RequestBody;)Lokhttp3/Request$Builder;") => |interpreter, this, args| {
+            request_builder_method(interpreter, this, "DELETE", arg(&args, 0))
+        },
+        (REQUEST_BUILDER, "method:(Ljava/lang/String;Lokhttp3/RequestBody;)Lokhttp3/Request$Builder;") => |interpreter, this, args| {
+            let method = string_arg(&args, 0).unwrap_or_default();
+            request_builder_method(interpreter, this, &method, arg(&args, 1))
+        },
+        // Nothing is cached and tags are never read back
+        (REQUEST_BUILDER, "cacheControl:(Lokhttp3/CacheControl;)Lokhttp3/Request$Builder;"
+            | "tag:(Ljava/lang/Object;)Lokhttp3/Request$Builder;"
+            | "tag:(Ljava/lang/Class;Ljava/lang/Object;)Lokhttp3/Request$Builder;") => |_, this, _| this_value(this),
+        (REQUEST_BUILDER, "build:()Lokhttp3/Request;") => request_builder_build,
+
+        (REQUEST, "url:()Lokhttp3/HttpUrl;") => |interpreter, this, _| field(interpreter, this, URL_FIELD),
+        (REQUEST, "method:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, METHOD_FIELD),
+        (REQUEST, "headers:()Lokhttp3/Headers;") => |interpreter, this, _| field(interpreter, this, HEADERS_FIELD),
+        (REQUEST, "header:(Ljava/lang/String;)Ljava/lang/String;") => |interpreter, this, args| {
+            let headers = field(interpreter, this, HEADERS_FIELD);
+            header_value(interpreter, &headers, &string_arg(&args, 0).unwrap_or_default())
+        },
+        (REQUEST, "headers:(Ljava/lang/String;)Ljava/util/List;") => |interpreter, this, args| {
+            let headers = field(interpreter, this, HEADERS_FIELD);
+            header_values(interpreter, &headers, &string_arg(&args, 0).unwrap_or_default())
+        },
+        (REQUEST, "body:()Lokhttp3/RequestBody;") => |interpreter, this, _| field(interpreter, this, BODY_FIELD),
+        (REQUEST, "newBuilder:()Lokhttp3/Request$Builder;") => request_new_builder,
+        (REQUEST, "toString:()Ljava/lang/String;") => request_to_string,
+
+        (RESPONSE, "request:()Lokhttp3/Request;") => |interpreter, this, _| field(interpreter, this, REQUEST_FIELD),
+        (RESPONSE, "code:()I") => |interpreter, this, _| field(interpreter, this, CODE_FIELD),
+        (RESPONSE, "message:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, MESSAGE_FIELD),
+        (RESPONSE, "isSuccessful:()Z") => |interpreter, this, _| {
+            let code = field(interpreter, this, CODE_FIELD).as_int().unwrap_or(0);
+            DexValue::Boolean((200..300).contains(&code))
+        },
+        (RESPONSE, "headers:()Lokhttp3/Headers;") => |interpreter, this, _| field(interpreter, this, HEADERS_FIELD),
+        (RESPONSE, "header:(Ljava/lang/String;)Ljava/lang/String;" | "header:(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;") => response_header,
+        (RESPONSE, "header$default:(Lokhttp3/Response;Ljava/lang/String;Ljava/lang/String;ILjava/lang/Object;)Ljava/lang/String;") => |interpreter, _, args| {
+            let this = object_arg(&args, 0);
+            response_header(interpreter, this, vec![arg(&args, 1)])
+        },
+        (RESPONSE, "headers:(Ljava/lang/String;)Ljava/util/List;") => |interpreter, this, args| {
+            let headers = field(interpreter, this, HEADERS_FIELD);
+            header_values(interpreter, &headers, &string_arg(&args, 0).unwrap_or_default())
+        },
+        (RESPONSE, "body:()Lokhttp3/ResponseBody;") => |interpreter, this, _| field(interpreter, this, BODY_FIELD),
+        (RESPONSE, "close:()V") => |_, _, _| DexValue::Void,
+        (RESPONSE, "newBuilder:()Lokhttp3/Response$Builder;") => response_new_builder,
+        (RESPONSE_BUILDER, "<init>:()V") => |interpreter, this, _| {
+            set_header_pairs(interpreter, this, &[]);
+            set_field(interpreter, this, CODE_FIELD, DexValue::Int(-1));
+            DexValue::Void
+        },
+        (RESPONSE_BUILDER, "request:(Lokhttp3/Request;)Lokhttp3/Response$Builder;") => |interpreter, this, args| {
+            set_field(interpreter, this, REQUEST_FIELD, arg(&args, 0));
+            this_value(this)
+        },
+        (RESPONSE_BUILDER, "code:(I)Lokhttp3/Response$Builder;") => |interpreter, this, args| {
+            set_field(interpreter, this, CODE_FIELD, DexValue::Int(int_arg(&args, 0)));
+            this_value(this)
+        },
+        (RESPONSE_BUILDER, "message:(Ljava/lang/String;)Lokhttp3/Response$Builder;") => |interpreter, this, args| {
+            set_field(interpreter, this, MESSAGE_FIELD, arg(&args, 0));
+            this_value(this)
+        },
+        (RESPONSE_BUILDER, "body:(Lokhttp3/ResponseBody;)Lokhttp3/Response$Builder;") => |interpreter, this, args| {
+            set_field(interpreter, this, BODY_FIELD, arg(&args, 0));
+            this_value(this)
+        },
+        (RESPONSE_BUILDER, "header:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Response$Builder;") => headers_builder_set,
+        (RESPONSE_BUILDER, "addHeader:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Response$Builder;") => headers_builder_add,
+        (RESPONSE_BUILDER, "removeHeader:(Ljava/lang/String;)Lokhttp3/Response$Builder;") => headers_builder_remove_all,
+        (RESPONSE_BUILDER, "headers:(Lokhttp3/Headers;)Lokhttp3/Response$Builder;") => request_builder_headers,
+        // Everything goes over http/1.1 and nothing is cached
+        (RESPONSE_BUILDER, "protocol:(Lokhttp3/Protocol;)Lokhttp3/Response$Builder;"
+            | "networkResponse:(Lokhttp3/Response;)Lokhttp3/Response$Builder;"
+            | "cacheResponse:(Lokhttp3/Response;)Lokhttp3/Response$Builder;"
+            | "priorResponse:(Lokhttp3/Response;)Lokhttp3/Response$Builder;") => |_, this, _| this_value(this),
+        (RESPONSE_BUILDER, "build:()Lokhttp3/Response;") => response_builder_build,
+        (RESPONSE, "toString:()Ljava/lang/String;") => response_to_string,
+
+        (HEADERS_BUILDER, "<init>:()V") => headers_builder_init,
+        (HEADERS_BUILDER, "add:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Headers$Builder;") => headers_builder_add,
+        (HEADERS_BUILDER, "add:(Ljava/lang/String;)Lokhttp3/Headers$Builder;") => headers_builder_add_line,
+        (HEADERS_BUILDER, "addAll:(Lokhttp3/Headers;)Lokhttp3/Headers$Builder;") => headers_builder_add_all,
+        (HEADERS_BUILDER, "set:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Headers$Builder;") => headers_builder_set,
+        (HEADERS_BUILDER, "removeAll:(Ljava/lang/String;)Lokhttp3/Headers$Builder;") => headers_builder_remove_all,
+        (HEADERS_BUILDER, "get:(Ljava/lang/String;)Ljava/lang/String;") => |interpreter, this, args| {
+            header_value(interpreter, &this_value(this), &string_arg(&args, 0).unwrap_or_default())
+        },
+        (HEADERS_BUILDER, "build:()Lokhttp3/Headers;") => |interpreter, this, _| {
+            let pairs = header_pairs(interpreter, &this_value(this));
+            new_headers(interpreter, HEADERS, &pairs)
+        },
+        (HEADERS | HEADERS_COMPANION, "of:([Ljava/lang/String;)Lokhttp3/Headers;") => headers_of,
+        (HEADERS | HEADERS_COMPANION, "of:(Ljava/util/Map;)Lokhttp3/Headers;") => headers_of_map,
+        (HEADERS, "get:(Ljava/lang/String;)Ljava/lang/String;") => |interpreter, this, args| {
+            header_value(interpreter, &this_value(this), &string_arg(&args, 0).unwrap_or_default())
+        },
+        (HEADERS, "values:(Ljava/lang/String;)Ljava/util/List;") => |interpreter, this, args| {
+            header_values(interpreter, &this_value(this), &string_arg(&args, 0).unwrap_or_default())
+        },
+        (HEADERS, "size:()I") => |interpreter, this, _| DexValue::Int(header_pairs(interpreter, &this_value(this)).len() as i32),
+        (HEADERS, "name:(I)Ljava/lang/String;") => |interpreter, this, args| header_at(interpreter, this, &args, false),
+        (HEADERS, "value:(I)Ljava/lang/String;") => |interpreter, this, args| header_at(interpreter, this, &args, true),
+        (HEADERS, "names:()Ljava/util/Set;") => headers_names,
+        (HEADERS, "newBuilder:()Lokhttp3/Headers$Builder;") => |interpreter, this, _| {
+            let pairs = header_pairs(interpreter, &this_value(this));
+            new_headers(interpreter, HEADERS_BUILDER, &pairs)
+        },
+        (HEADERS, "toString:()Ljava/lang/String;") => headers_to_string,
+
+        (HTTP_URL | HTTP_URL_COMPANION, "get:(Ljava/lang/String;)Lokhttp3/HttpUrl;") => |interpreter, _, args| {
+            let text = string_arg(&args, 0).unwrap_or_default();
+            url_or_throw(interpreter, &text)
+        },
+        (HTTP_URL | HTTP_URL_COMPANION, "parse:(Ljava/lang/String;)Lokhttp3/HttpUrl;") => |interpreter, _, args| {
+            match Url::parse(&string_arg(&args, 0).unwrap_or_default()) {
+                Ok(url) => new_url(interpreter, HTTP_URL, &url),
+                Err(_) => DexValue::Null,
+            }
+        },
+        (HTTP_URL, "scheme:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, SCHEME_FIELD),
+        (HTTP_URL, "host:()Ljava/lang/String;") => |interpreter, this, _| DexValue::String(url_of(interpreter, this).host().to_string()),
+        (HTTP_URL, "port:()I") => |interpreter, this, _| DexValue::Int(url_of(interpreter, this).port()),
+        (HTTP_URL, "encodedPath:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, PATH_FIELD),
+        (HTTP_URL, "pathSegments:()Ljava/util/List;") => url_path_segments,
+        (HTTP_URL, "encodedQuery:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, QUERY_FIELD),
+        (HTTP_URL, "query:()Ljava/lang/String;") => |interpreter, this, _| {
+            url_of(interpreter, this).query.map_or(DexValue::Null, |query| DexValue::String(decode_query_component(&query)))
+        },
+        (HTTP_URL, "queryParameter:(Ljava/lang/String;)Ljava/lang/String;") => url_query_parameter,
+        (HTTP_URL, "fragment:()Ljava/lang/String;") => |interpreter, this, _| {
+            url_of(interpreter, this).fragment.map_or(DexValue::Null, |fragment| DexValue::String(percent_decode(&fragment)))
+        },
+        (HTTP_URL, "resolve:(Ljava/lang/String;)Lokhttp3/HttpUrl;") => url_resolve,
+        (HTTP_URL, "newBuilder:()Lokhttp3/HttpUrl$Builder;") => |interpreter, this, _| {
+            let url = url_of(interpreter, this);
+            new_url(interpreter, HTTP_URL_BUILDER, &url)
+        },
+        (HTTP_URL, "toString:()Ljava/lang/String;" | "url:()Ljava/net/URL;") => |interpreter, this, _| {
+            DexValue::String(url_of(interpreter, this).compose())
+        },
+        (HTTP_URL, "equals:(Ljava/lang/Object;)Z") => url_equals,
+        (HTTP_URL, "hashCode:()I") => |interpreter, this, _| {
+            let text = url_of(interpreter, this).compose();
+            DexValue::Int(text.encode_utf16().fold(0i32, |hash, unit| hash.wrapping_mul(31).wrapping_add(unit as i32)))
+        },
+        (HTTP_URL_BUILDER, "<init>:()V") => |interpreter, this, _| {
+            store_url(interpreter, this, &Url { path: "/".to_string(), ..Url::default() });
+            DexValue::Void
+        },
+        (HTTP_URL_BUILDER, "scheme:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => url_builder_scheme,
+        (HTTP_URL_BUILDER, "host:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => url_builder_host,
+        (HTTP_URL_BUILDER, "addPathSegment:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => |interpreter, this, args| {
+            url_builder_add_path_segments(interpreter, this, &args, false, false)
+        },
+        (HTTP_URL_BUILDER, "addPathSegments:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => |interpreter, this, args| {
+            url_builder_add_path_segments(interpreter, this, &args, true, false)
+        },
+        (HTTP_URL_BUILDER, "addEncodedPathSegment:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => |interpreter, this, args| {
+            url_builder_add_path_segments(interpreter, this, &args, false, true)
+        },
+        (HTTP_URL_BUILDER, "addEncodedPathSegments:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => |interpreter, this, args| {
+            url_builder_add_path_segments(interpreter, this, &args, true, true)
+        },
+        (HTTP_URL_BUILDER, "encodedPath:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => url_builder_encoded_path,
+        (HTTP_URL_BUILDER, "addQueryParameter:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => |interpreter, this, args| {
+            url_builder_query_parameter(interpreter, this, &args, false, false)
+        },
+        (HTTP_URL_BUILDER, "addEncodedQueryParameter:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => |interpreter, this, args| {
+            url_builder_query_parameter(interpreter, this, &args, true, false)
+        },
+        (HTTP_URL_BUILDER, "setQueryParameter:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => |interpreter, this, args| {
+            url_builder_query_parameter(interpreter, this, &args, false, true)
+        },
+        (HTTP_URL_BUILDER, "setEncodedQueryParameter:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => |interpreter, this, args| {
+            url_builder_query_parameter(interpreter, this, &args, true, true)
+        },
+        (HTTP_URL_BUILDER, "removeAllQueryParameters:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => url_builder_remove_query_parameters,
+        (HTTP_URL_BUILDER, "fragment:(Ljava/lang/String;)Lokhttp3/HttpUrl$Builder;") => url_builder_fragment,
+        (HTTP_URL_BUILDER, "build:()Lokhttp3/HttpUrl;") => url_builder_build,
+        (HTTP_URL_BUILDER, "toString:()Ljava/lang/String;") => |interpreter, this, _| DexValue::String(url_of(interpreter, this).compose()),
+
+        (MEDIA_TYPE | MEDIA_TYPE_COMPANION, "get:(Ljava/lang/String;)Lokhttp3/MediaType;") => |interpreter, _, args| {
+            let text = string_arg(&args, 0).unwrap_or_default();
+            if media_type_parts(&text).is_none() {
+                return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &format!("No subtype found for: \"{}\"", text));
+            }
+            new_media_type(interpreter, &text)
+        },
+        (MEDIA_TYPE | MEDIA_TYPE_COMPANION, "parse:(Ljava/lang/String;)Lokhttp3/MediaType;") => |interpreter, _, args| {
+            let text = string_arg(&args, 0).unwrap_or_default();
+            if media_type_parts(&text).is_none() {
+                return DexValue::Null;
+            }
+            new_media_type(interpreter, &text)
+        },
+        (MEDIA_TYPE, "type:()Ljava/lang/String;") => |interpreter, this, _| media_type_part(interpreter, this, false),
+        (MEDIA_TYPE, "subtype:()Ljava/lang/String;") => |interpreter, this, _| media_type_part(interpreter, this, true),
+        (MEDIA_TYPE, "toString:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, MEDIA_TYPE_FIELD),
+
+        // The Kotlin extensions toRequestBody() and toResponseBody() land on the companions
+        (REQUEST_BODY_COMPANION, "create:(Ljava/lang/String;Lokhttp3/MediaType;)Lokhttp3/RequestBody;") => |interpreter, _, args| {
+            text_body(interpreter, REQUEST_BODY, arg(&args, 0), arg(&args, 1))
+        },
+        (REQUEST_BODY_COMPANION, "create:(Lokhttp3/MediaType;Ljava/lang/String;)Lokhttp3/RequestBody;") => |interpreter, _, args| {
+            text_body(interpreter, REQUEST_BODY, arg(&args, 1), arg(&args, 0))
+        },
+        (REQUEST_BODY_COMPANION, "create$default:(Lokhttp3/RequestBody$Companion;Ljava/lang/String;Lokhttp3/MediaType;ILjava/lang/Object;)Lokhttp3/RequestBody;") => |interpreter, _, args| {
+            let content_type = if int_arg(&args, 3) & 1 != 0 { DexValue::Null } else { arg(&args, 2) };
+            text_body(interpreter, REQUEST_BODY, arg(&args, 1), content_type)
+        },
+        (REQUEST_BODY_COMPANION, "create:([BLokhttp3/MediaType;II)Lokhttp3/RequestBody;") => |interpreter, _, args| {
+            bytes_body(interpreter, &args, 0, 0)
+        },
+        (REQUEST_BODY_COMPANION, "create$default:(Lokhttp3/RequestBody$Companion;[BLokhttp3/MediaType;IIILjava/lang/Object;)Lokhttp3/RequestBody;") => |interpreter, _, args| {
+            let mask = int_arg(&args, 5);
+            bytes_body(interpreter, &args, 1, mask)
+        },
+        (REQUEST_BODY | FORM_BODY, "contentType:()Lokhttp3/MediaType;") => |interpreter, this, _| field(interpreter, this, CONTENT_TYPE_FIELD),
+        (REQUEST_BODY | FORM_BODY, "contentLength:()J") => |interpreter, this, _| DexValue::Long(bytes_of(interpreter, this).len() as i64),
+
+        (FORM_BODY_BUILDER, "<init>:()V" | "<init>:(Ljava/nio/charset/Charset;)V") => |interpreter, this, _| {
+            if let Some(builder) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+                builder.array = Some(Vec::new());
+            }
+            DexValue::Void
+        },
+        (FORM_BODY_BUILDER, "<init>:(Ljava/nio/charset/Charset;ILkotlin/jvm/internal/DefaultConstructorMarker;)V") => |interpreter, this, _| {
+            if let Some(builder) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+                builder.array = Some(Vec::new());
+            }
+            DexValue::Void
+        },
+        (FORM_BODY_BUILDER, "add:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/FormBody$Builder;") => |interpreter, this, args| {
+            form_builder_add(interpreter, this, &args, false)
+        },
+        (FORM_BODY_BUILDER, "addEncoded:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/FormBody$Builder;") => |interpreter, this, args| {
+            form_builder_add(interpreter, this, &args, true)
+        },
+        (FORM_BODY_BUILDER, "build:()Lokhttp3/FormBody;") => form_builder_build,
+
+        (RESPONSE_BODY_COMPANION, "create:(Ljava/lang/String;Lokhttp3/MediaType;)Lokhttp3/ResponseBody;") => |interpreter, _, args| {
+            text_body(interpreter, RESPONSE_BODY, arg(&args, 0), arg(&args, 1))
+        },
+        (RESPONSE_BODY_COMPANION, "create:([BLokhttp3/MediaType;)Lokhttp3/ResponseBody;") => |interpreter, _, args| {
+            let bytes = bytes_of(interpreter, object_arg(&args, 0));
+            new_body(interpreter, RESPONSE_BODY, bytes, arg(&args, 1))
+        },
+        (RESPONSE_BODY, "string:()Ljava/lang/String;") => response_body_string,
+        (RESPONSE_BODY, "bytes:()[B") => |interpreter, this, _| {
+            let bytes = bytes_of(interpreter, this);
+            new_byte_array(interpreter, bytes)
+        },
+        (RESPONSE_BODY, "contentType:()Lokhttp3/MediaType;") => |interpreter, this, _| field(interpreter, this, CONTENT_TYPE_FIELD),
+        (RESPONSE_BODY, "contentLength:()J") => |interpreter, this, _| DexValue::Long(bytes_of(interpreter, this).len() as i64),
+        (RESPONSE_BODY, "close:()V") => |_, _, _| DexValue::Void,
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// Whether `class_name` is reached through a `Companion` static, the way Kotlin callers get
+/// to OkHttp's factory methods
+pub fn has_companion(class_name: &str) -> bool {
+    matches!(class_name, COOKIE | HEADERS | HTTP_URL | MEDIA_TYPE | REQUEST_BODY | RESPONSE_BODY)
+}
+
+/// Static fields of a class with a companion
+pub fn companion_statics(interpreter: &mut Interpreter, class_name: &str) -> HashMap<String, DexValue> {
+    let companion = format!("{}$Companion;", class_name.trim_end_matches(';'));
+    let id = interpreter.alloc_object(&companion);
+    HashMap::from([("Companion".to_string(), DexValue::Object(id))])
+}
+
+fn this_value(this: Option<ObjectId>) -> DexValue {
+    this.map_or(DexValue::Null, DexValue::Object)
+}
+
+fn arg(args: &[DexValue], index: usize) -> DexValue {
+    args.get(index).cloned().unwrap_or(DexValue::Null)
+}
+
+fn string_arg(args: &[DexValue], index: usize) -> Option<String> {
+    match args.get(index) {
+        Some(DexValue::String(text)) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> i32 {
+    args.get(index).and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+fn object_arg(args: &[DexValue], index: usize) -> Option<ObjectId> {
+    args.get(index).and_then(object_of)
+}
+
+fn object_of(value: &DexValue) -> Option<ObjectId> {
+    match value {
+        DexValue::Object(id) => Some(*id),
+        _ => None,
+    }
+}
+
+fn field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> DexValue {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.fields.get(name).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn text_field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> Option<String> {
+    match field(interpreter, this, name) {
+        DexValue::String(text) => Some(text),
+        _ => None,
+    }
+}
+
+fn set_field(interpreter: &mut Interpreter, this: Option<ObjectId>, name: &str, value: DexValue) {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(name.to_string(), value);
+    }
+}
+
+fn throw(interpreter: &mut Interpreter, class_name: &str, message: &str) -> DexValue {
+    interpreter.throw_new(class_name, message);
+    DexValue::Void
+}
+
+/// A url OkHttp accepts, that is an http or https one with a host. The path, query and fragment
+/// are kept percent-encoded.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Url {
+    scheme: String,
+    authority: String,
+    path: String,
+    query: Option<String>,
+    fragment: Option<String>,
+}
+
+impl Url {
+    /// Parse `input` leniently like `toHttpUrl()`: whitespace around it is dropped, backslashes
+    /// count as slashes and chars that may not appear in a url are encoded
+    fn parse(input: &str) -> Result<Url, String> {
+        let input = input.trim_matches(|c: char| c.is_ascii_whitespace() || c.is_ascii_control());
+        let scheme = input.split_once(':')
+            .map(|(scheme, _)| scheme)
+            .filter(|scheme| {
+                scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+            });
+        let Some(scheme) = scheme else {
+            return Err(format!("Expected URL scheme 'http' or 'https' but no scheme was found for {}", input));
+        };
+        let scheme = scheme.to_ascii_lowercase();
+        if scheme != "http" && scheme != "https" {
+            return Err(format!("Expected URL scheme 'http' or 'https' but was '{}'", scheme));
+        }
+
+        let rest = input[scheme.len() + 1..].trim_start_matches(['/', '\\']);
+        let end = rest.find(['/', '\\', '?', '#']).unwrap_or(rest.len());
+        let mut url = Url { scheme, ..Url::default() };
+        url.set_authority(&rest[..end])?;
+
+        let rest = &rest[end..];
+        let (rest, fragment) = match rest.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (rest, None),
+        };
+        let (path, query) = match rest.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (rest, None),
+        };
+        url.set_path(&canonicalize(&path.replace('\\', "/"), PATH_ENCODE_SET, true));
+        url.query = query.map(|query| canonicalize(query, QUERY_ENCODE_SET, true));
+        url.fragment = fragment.map(|fragment| canonicalize(fragment, FRAGMENT_ENCODE_SET, true));
+        Ok(url)
+    }
+
+    /// Take the host and port of `authority`, user info is dropped
+    fn set_authority(&mut self, authority: &str) -> Result<(), String> {
+        let host_port = authority.rsplit_once('@').map_or(authority, |(_, host_port)| host_port);
+        let (host, port) = match host_port.rfind(':').filter(|colon| !host_port[*colon..].contains(']')) {
+            Some(colon) => (&host_port[..colon], Some(&host_port[colon + 1..])),
+            None => (host_port, None),
+        };
+        if host.is_empty() || host.contains([' ', '%', '<', '>', '"', '^', '|']) {
+            return Err(format!("Invalid URL host: \"{}\"", host));
+        }
+        let port = match port {
+            None | Some("") => None,
+            Some(port) => match port.parse::<u16>() {
+                Ok(number) if number > 0 => Some(number),
+                _ => return Err(format!("Invalid URL port: \"{}\"", port)),
+            },
+        };
+        self.authority = host.to_lowercase();
+        if let Some(port) = port.filter(|port| *port as i32 != default_port(&self.scheme)) {
+            self.authority.push_str(&format!(":{}", port));
+        }
+        Ok(())
+    }
+
+    fn set_path(&mut self, path: &str) {
+        let path = remove_dot_segments(path);
+        self.path = if path.starts_with('/') { path } else { format!("/{}", path) };
+    }
+
+    fn host(&self) -> &str {
+        match self.authority.rfind(':').filter(|colon| !self.authority[*colon..].contains(']')) {
+            Some(colon) => &self.authority[..colon],
+            None => &self.authority,
+        }
+    }
+
+    fn port(&self) -> i32 {
+        self.authority[self.host().len()..].strip_prefix(':')
+            .and_then(|port| port.parse().ok())
+            .unwrap_or_else(|| default_port(&self.scheme))
+    }
+
+    fn compose(&self) -> String {
+        let mut text = format!("{}://{}{}", self.scheme, self.authority, self.path);
+        if let Some(query) = &self.query {
+            text.push('?');
+            text.push_str(query);
+        }
+        if let Some(fragment) = &self.fragment {
+            text.push('#');
+            text.push_str(fragment);
+        }
+        text
+    }
+
+    /// Encoded names and values of the query, a name without `=` has no value
+    fn query_parameters(&self) -> Vec<(String, Option<String>)> {
+        self.query.iter()
+            .flat_map(|query| query.split('&'))
+            .map(|parameter| match parameter.split_once('=') {
+                Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                None => (parameter.to_string(), None),
+            })
+            .collect()
+    }
+
+    fn set_query_parameters(&mut self, parameters: Vec<(String, Option<String>)>) {
+        if parameters.is_empty() {
+            self.query = None;
+            return;
+        }
+        let query: Vec<String> = parameters.into_iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{}={}", name, value),
+                None => name,
+            })
+            .collect();
+        self.query = Some(query.join("&"));
+    }
+}
+
+fn default_port(scheme: &str) -> i32 {
+    if scheme == "https" { 443 } else { 80 }
+}
+
+/// Percent-encode controls, non-ASCII chars and those of `encode_set` in `text`. With
+/// `already_encoded`, a `%` starting a valid escape is left alone.
+pub fn canonicalize(text: &str, encode_set: &str, already_encoded: bool) -> String {
+    let bytes = text.as_bytes();
+    let mut encoded = String::with_capacity(text.len());
+    for (index, c) in text.char_indices() {
+        let is_escape = c == '%' && already_encoded
+            && bytes.get(index + 1..index + 3).is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit));
+        if c.is_ascii_control() || !c.is_ascii() || encode_set.contains(c) || c == '%' && !is_escape {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        } else {
+            encoded.push(c);
+        }
+    }
+    encoded
+}
+
+/// A query name or value as `queryParameter()` returns it, `+` standing for a space
+fn decode_query_component(text: &str) -> String {
+    percent_decode(&text.replace('+', " "))
+}
+
+fn store_url(interpreter: &mut Interpreter, this: Option<ObjectId>, url: &Url) {
+    let optional = |text: &Option<String>| text.clone().map_or(DexValue::Null, DexValue::String);
+    set_field(interpreter, this, SCHEME_FIELD, DexValue::String(url.scheme.clone()));
+    set_field(interpreter, this, AUTHORITY_FIELD, DexValue::String(url.authority.clone()));
+    set_field(interpreter, this, PATH_FIELD, DexValue::String(url.path.clone()));
+    set_field(interpreter, this, QUERY_FIELD, optional(&url.query));
+    set_field(interpreter, this, FRAGMENT_FIELD, optional(&url.fragment));
+}
+
+fn url_of(interpreter: &Interpreter, this: Option<ObjectId>) -> Url {
+    Url {
+        scheme: text_field(interpreter, this, SCHEME_FIELD).unwrap_or_default(),
+        authority: text_field(interpreter, this, AUTHORITY_FIELD).unwrap_or_default(),
+        path: text_field(interpreter, this, PATH_FIELD).unwrap_or_default(),
+        query: text_field(interpreter, this, QUERY_FIELD),
+        fragment: text_field(interpreter, this, FRAGMENT_FIELD),
+    }
+}
+
+/// An HttpUrl or a builder holding `url`
+fn new_url(interpreter: &mut Interpreter, class_name: &str, url: &Url) -> DexValue {
+    let id = interpreter.alloc_object(class_name);
+    store_url(interpreter, Some(id), url);
+    DexValue::Object(id)
+}
+
+fn url_or_throw(interpreter: &mut Interpreter, text: &str) -> DexValue {
+    match Url::parse(text) {
+        Ok(url) => new_url(interpreter, HTTP_URL, &url),
+        Err(message) => throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &message),
+    }
+}
+
+fn url_path_segments(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let url = url_of(interpreter, this);
+    let segments = url.path[1..].split('/').map(|segment| DexValue::String(percent_decode(segment))).collect();
+    DexValue::Object(new_list(interpreter, segments))
+}
+
+/// Value of the first query parameter named like the argument
+fn url_query_parameter(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = string_arg(&args, 0).unwrap_or_default();
+    url_of(interpreter, this).query_parameters().into_iter()
+        .find(|(parameter, _)| decode_query_component(parameter) == name)
+        .and_then(|(_, value)| value)
+        .map_or(DexValue::Null, |value| DexValue::String(decode_query_component(&value)))
+}
+
+/// The link resolved against this url, null unless that's an http or https url
+fn url_resolve(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let base = url_of(interpreter, this).compose();
+    let link = canonicalize(&string_arg(&args, 0).unwrap_or_default().replace('\\', "/"), " \"<>^`{}|", true);
+    match resolve_reference(&base, &link).map(|resolved| Url::parse(&resolved)) {
+        Some(Ok(url)) => new_url(interpreter, HTTP_URL, &url),
+        _ => DexValue::Null,
+    }
+}
+
+fn url_equals(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let other = object_arg(&args, 0);
+    let is_url = other.and_then(|id| interpreter.heap.get(&id)).is_some_and(|object| object.class_name == HTTP_URL);
+    DexValue::Boolean(is_url && url_of(interpreter, this) == url_of(interpreter, other))
+}
+
+fn url_builder_scheme(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let scheme = string_arg(&args, 0).unwrap_or_default().to_ascii_lowercase();
+    if scheme != "http" && scheme != "https" {
+        return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &format!("unexpected scheme: {}", scheme));
+    }
+    set_field(interpreter, this, SCHEME_FIELD, DexValue::String(scheme));
+    this_value(this)
+}
+
+fn url_builder_host(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let host = string_arg(&args, 0).unwrap_or_default();
+    let mut url = url_of(interpreter, this);
+    if let Err(message) = url.set_authority(&host) {
+        return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &message.replace("Invalid URL host", "unexpected host"));
+    }
+    store_url(interpreter, this, &url);
+    this_value(this)
+}
+
+/// Append one segment, or several separated by `/` with `multiple`. An empty last segment is
+/// replaced rather than followed, so `/a/` plus `b` is `/a/b`.
+fn url_builder_add_path_segments(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue], multiple: bool, encoded: bool) -> DexValue {
+    let text = string_arg(args, 0).unwrap_or_default();
+    let mut url = url_of(interpreter, this);
+    let segments: Vec<&str> = if multiple { text.split(['/', '\\']).collect() } else { vec![&text] };
+    for segment in segments {
+        let segment = canonicalize(segment, PATH_SEGMENT_ENCODE_SET, encoded);
+        if url.path.ends_with('/') {
+            url.path.push_str(&segment);
+        } else {
+            url.path = format!("{}/{}", url.path, segment);
+        }
+    }
+    store_url(interpreter, this, &url);
+    this_value(this)
+}
+
+fn url_builder_encoded_path(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let path = string_arg(&args, 0).unwrap_or_default();
+    if !path.starts_with('/') {
+        return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &format!("unexpected encodedPath: {}", path));
+    }
+    let mut url = url_of(interpreter, this);
+    url.set_path(&canonicalize(&path, PATH_ENCODE_SET, true));
+    store_url(interpreter, this, &url);
+    this_value(this)
+}
+
+/// Add a query parameter, or with `replace` drop those with the same name first. A null value
+/// adds the name alone.
+fn url_builder_query_parameter(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue], encoded: bool, replace: bool) -> DexValue {
+    let encode = |text: String| if encoded {
+        canonicalize(&text, QUERY_COMPONENT_REENCODE_SET, true)
+    } else {
+        canonicalize(&text, QUERY_COMPONENT_ENCODE_SET, false)
+    };
+    let name = encode(string_arg(args, 0).unwrap_or_default());
+    let value = string_arg(args, 1).map(encode);
+    let mut url = url_of(interpreter, this);
+    let mut parameters = url.query_parameters();
+    if replace {
+        parameters.retain(|(parameter, _)| decode_query_component(parameter) != decode_query_component(&name));
+    }
+    parameters.push((name, value));
+    url.set_query_parameters(parameters);
+    store_url(interpreter, this, &url);
+    this_value(this)
+}
+
+fn url_builder_remove_query_parameters(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = string_arg(&args, 0).unwrap_or_default();
+    let mut url = url_of(interpreter, this);
+    let mut parameters = url.query_parameters();
+    parameters.retain(|(parameter, _)| decode_query_component(parameter) != name);
+    url.set_query_parameters(parameters);
+    store_url(interpreter, this, &url);
+    this_value(this)
+}
+
+fn url_builder_fragment(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let fragment = string_arg(&args, 0).map(|fragment| canonicalize(&fragment, FRAGMENT_ENCODE_SET, false));
+    set_field(interpreter, this, FRAGMENT_FIELD, fragment.map_or(DexValue::Null, DexValue::String));
+    this_value(this)
+}
+
+fn url_builder_build(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let url = url_of(interpreter, this);
+    if url.scheme.is_empty() {
+        return throw(interpreter, ILLEGAL_STATE_EXCEPTION, "scheme == null");
+    }
+    if url.authority.is_empty() {
+        return throw(interpreter, ILLEGAL_STATE_EXCEPTION, "host == null");
+    }
+    new_url(interpreter, HTTP_URL, &url)
+}
+
+/// Type and subtype of a media type like `text/html; charset=utf-8`
+fn media_type_parts(text: &str) -> Option<(String, String)> {
+    let essence = text.split(';').next().unwrap_or_default().trim();
+    let (kind, subtype) = essence.split_once('/')?;
+    let is_token = |part: &str| !part.is_empty() && part.chars().all(|c| c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?=".contains(c));
+    if !is_token(kind) || !is_token(subtype) {
+        return None;
+    }
+    Some((kind.to_ascii_lowercase(), subtype.to_ascii_lowercase()))
+}
+
+fn new_media_type(interpreter: &mut Interpreter, text: &str) -> DexValue {
+    let id = interpreter.alloc_object(MEDIA_TYPE);
+    set_field(interpreter, Some(id), MEDIA_TYPE_FIELD, DexValue::String(text.to_string()));
+    DexValue::Object(id)
+}
+
+fn media_type_part(interpreter: &mut Interpreter, this: Option<ObjectId>, subtype: bool) -> DexValue {
+    let text = text_field(interpreter, this, MEDIA_TYPE_FIELD).unwrap_or_default();
+    let (kind, sub) = media_type_parts(&text).unwrap_or_default();
+    DexValue::String(if subtype { sub } else { kind })
+}
+
+/// The `charset` parameter of a media type object
+fn charset_of(interpreter: &Interpreter, media_type: &DexValue) -> Option<String> {
+    let DexValue::Object(id) = media_type else {
+        return None;
+    };
+    let text = text_field(interpreter, Some(*id), MEDIA_TYPE_FIELD)?;
+    text.split(';').skip(1)
+        .filter_map(|parameter| parameter.trim().split_once('='))
+        .find(|(name, _)| name.eq_ignore_ascii_case("charset"))
+        .map(|(_, value)| value.trim_matches('"').to_string())
+}
+
+/// A request or response body holding `bytes`
+fn new_body(interpreter: &mut Interpreter, class_name: &str, bytes: Vec<u8>, content_type: DexValue) -> DexValue {
+    let id = interpreter.alloc_object(class_name);
+    let body = interpreter.heap.get_mut(&id).unwrap();
+    body.array = Some(bytes.into_iter().map(|byte| DexValue::Byte(byte as i8)).collect());
+    body.fields.insert(CONTENT_TYPE_FIELD.to_string(), content_type);
+    DexValue::Object(id)
+}
+
+/// A body of text encoded in the charset of its media type. Like OkHttp, a media type without
+/// one gets `; charset=utf-8` added.
+fn text_body(interpreter: &mut Interpreter, class_name: &str, text: DexValue, content_type: DexValue) -> DexValue {
+    let text = match text {
+        DexValue::String(text) => text,
+        _ => String::new(),
+    };
+    let (bytes, content_type) = match charset_of(interpreter, &content_type) {
+        // OkHttp falls back on UTF-8 for charsets it doesn't know too
+        Some(charset) => (Charset::for_name(&charset).unwrap_or(Charset::Utf8).encode(&text), content_type),
+        None => {
+            let content_type = match &content_type {
+                DexValue::Object(id) => {
+                    let media_type = text_field(interpreter, Some(*id), MEDIA_TYPE_FIELD).unwrap_or_default();
+                    new_media_type(interpreter, &format!("{}; charset=utf-8", media_type))
+                }
+                _ => DexValue::Null,
+            };
+            (text.into_bytes(), content_type)
+        }
+    };
+    new_body(interpreter, class_name, bytes, content_type)
+}
+
+/// `ByteArray.toRequestBody(contentType, offset, byteCount)`, the arguments start at `start`.
+/// The `$default` mask leaves out the media type, offset and count from its low bit up.
+fn bytes_body(interpreter: &mut Interpreter, args: &[DexValue], start: usize, mask: i32) -> DexValue {
+    let bytes = bytes_of(interpreter, object_arg(args, start));
+    let content_type = if mask & 1 != 0 { DexValue::Null } else { arg(args, start + 1) };
+    let offset = if mask & 2 != 0 { 0 } else { int_arg(args, start + 2) };
+    let count = if mask & 4 != 0 { bytes.len() as i32 - offset } else { int_arg(args, start + 3) };
+    if offset < 0 || count < 0 || offset as usize + count as usize > bytes.len() {
+        return throw(interpreter, ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, &format!(
+            "length={}; regionStart={}; regionLength={}", bytes.len(), offset, count,
+        ));
+    }
+    let bytes = bytes[offset as usize..(offset + count) as usize].to_vec();
+    new_body(interpreter, REQUEST_BODY, bytes, content_type)
+}
+
+fn form_builder_add(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue], encoded: bool) -> DexValue {
+    let encode = |text: String| canonicalize(&text, FORM_ENCODE_SET, encoded);
+    let name = encode(string_arg(args, 0).unwrap_or_default());
+    let value = encode(string_arg(args, 1).unwrap_or_default());
+    if let Some(pairs) = this.and_then(|id| interpreter.heap.get_mut(&id)).and_then(|builder| builder.array.as_mut()) {
+        pairs.push(DexValue::String(name));
+        pairs.push(DexValue::String(value));
+    }
+    this_value(this)
+}
+
+fn form_builder_build(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let content = header_pairs(interpreter, &this_value(this)).into_iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join("&");
+    let content_type = new_media_type(interpreter, FORM_CONTENT_TYPE);
+    new_body(interpreter, FORM_BODY, content.into_bytes(), content_type)
+}
+
+fn response_body_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let bytes = bytes_of(interpreter, this);
+    let content_type = field(interpreter, this, CONTENT_TYPE_FIELD);
+    let text = match charset_of(interpreter, &content_type).and_then(|charset| Charset::for_name(&charset)) {
+        Some(Charset::Utf8) | None => {
+            let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+            String::from_utf8_lossy(bytes).into_owned()
+        }
+        Some(charset) => charset.decode(&bytes),
+    };
+    DexValue::String(text)
+}
+
+/// Names and values of a Headers object or a builder, in order
+fn header_pairs(interpreter: &Interpreter, headers: &DexValue) -> Vec<(String, String)> {
+    let DexValue::Object(id) = headers else {
+        return Vec::new();
+    };
+    let elements = interpreter.heap.get(id).and_then(|object| object.array.as_deref()).unwrap_or(&[]);
+    elements.chunks(2)
+        .map(|pair| {
+            let text = |value: Option<&DexValue>| match value {
+                Some(DexValue::String(text)) => text.clone(),
+                _ => String::new(),
+            };
+            (text(pair.first()), text(pair.get(1)))
+        })
+        .collect()
+}
+
+fn set_header_pairs(interpreter: &mut Interpreter, this: Option<ObjectId>, pairs: &[(String, String)]) {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.array = Some(pairs.iter()
+            .flat_map(|(name, value)| [DexValue::String(name.clone()), DexValue::String(value.clone())])
+            .collect());
+    }
+}
+
+fn new_headers(interpreter: &mut Interpreter, class_name: &str, pairs: &[(String, String)]) -> DexValue {
+    let id = interpreter.alloc_object(class_name);
+    set_header_pairs(interpreter, Some(id), pairs);
+    DexValue::Object(id)
+}
+
+/// Last value of the header `name`, matched ignoring case
+fn header_value(interpreter: &Interpreter, headers: &DexValue, name: &str) -> DexValue {
+    header_pairs(interpreter, headers).into_iter().rev()
+        .find(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map_or(DexValue::Null, |(_, value)| DexValue::String(value))
+}
+
+fn header_values(interpreter: &mut Interpreter, headers: &DexValue, name: &str) -> DexValue {
+    let values = header_pairs(interpreter, headers).into_iter()
+        .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+        .map(|(_, value)| DexValue::String(value))
+        .collect();
+    DexValue::Object(new_list(interpreter, values))
+}
+
+fn header_at(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue], value: bool) -> DexValue {
+    let pairs = header_pairs(interpreter, &this_value(this));
+    let index = int_arg(args, 0);
+    match usize::try_from(index).ok().and_then(|index| pairs.get(index)) {
+        Some((name, text)) => DexValue::String(if value { text.clone() } else { name.clone() }),
+        None => throw(interpreter, ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, &format!("length={}; index={}", pairs.len() * 2, index * 2)),
+    }
+}
+
+fn headers_names(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let mut names: Vec<String> = header_pairs(interpreter, &this_value(this)).into_iter().map(|(name, _)| name).collect();
+    names.sort_by_key(|name| name.to_ascii_lowercase());
+    names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    let names = names.into_iter().map(DexValue::String).collect();
+    DexValue::Object(new_set(interpreter, names))
+}
+
+fn headers_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let text: String = header_pairs(interpreter, &this_value(this)).into_iter()
+        .map(|(name, value)| format!("{}: {}\n", name, value))
+        .collect();
+    DexValue::String(text)
+}
+
+/// OkHttp refuses empty names and line breaks in names and values, which would split the header
+fn check_header(interpreter: &mut Interpreter, name: &str, value: &str) -> bool {
+    let message = if name.is_empty() {
+        "name is empty".to_string()
+    } else if let Some((index, c)) = name.char_indices().find(|(_, c)| !(c.is_ascii_graphic())) {
+        format!("Unexpected char 0x{:02x} at {} in header name: {}", c as u32, index, name)
+    } else if let Some((index, c)) = value.char_indices().find(|(_, c)| *c != '\t' && c.is_ascii_control()) {
+        format!("Unexpected char 0x{:02x} at {} in {} value: {}", c as u32, index, name, value)
+    } else {
+        return true;
+    };
+    throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &message);
+    false
+}
+
+fn headers_builder_init(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    set_header_pairs(interpreter, this, &[]);
+    DexValue::Void
+}
+
+fn headers_builder_add(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = string_arg(&args, 0).unwrap_or_default();
+    let value = string_arg(&args, 1).unwrap_or_default();
+    if !check_header(interpreter, &name, &value) {
+        return DexValue::Void;
+    }
+    let mut pairs = header_pairs(interpreter, &this_value(this));
+    pairs.push((name, value.trim().to_string()));
+    set_header_pairs(interpreter, this, &pairs);
+    this_value(this)
+}
+
+/// `add("Name: value")`
+fn headers_builder_add_line(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let line = string_arg(&args, 0).unwrap_or_default();
+    let Some((name, value)) = line.split_once(':') else {
+        return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &format!("Unexpected header: {}", line));
+    };
+    let args = vec![DexValue::String(name.trim().to_string()), DexValue::String(value.trim().to_string())];
+    headers_builder_add(interpreter, this, args)
+}
+
+fn headers_builder_add_all(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut pairs = header_pairs(interpreter, &this_value(this));
+    pairs.extend(header_pairs(interpreter, &arg(&args, 0)));
+    set_header_pairs(interpreter, this, &pairs);
+    this_value(this)
+}
+
+fn headers_builder_set(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = string_arg(&args, 0).unwrap_or_default();
+    let mut pairs = header_pairs(interpreter, &this_value(this));
+    pairs.retain(|(header, _)| !header.eq_ignore_ascii_case(&name));
+    set_header_pairs(interpreter, this, &pairs);
+    headers_builder_add(interpreter, this, args)
+}
+
+fn headers_builder_remove_all(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = string_arg(&args, 0).unwrap_or_default();
+    let mut pairs = header_pairs(interpreter, &this_value(this));
+    pairs.retain(|(header, _)| !header.eq_ignore_ascii_case(&name));
+    set_header_pairs(interpreter, this, &pairs);
+    this_value(this)
+}
+
+/// `headersOf("Name", "value", ...)`
+fn headers_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let values = elements_of(interpreter, &arg(&args, 0));
+    if !values.len().is_multiple_of(2) {
+        return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, "Expected alternating header names and values");
+    }
+    let builder = new_headers(interpreter, HEADERS_BUILDER, &[]);
+    for pair in values.chunks(2) {
+        let DexValue::Object(id) = builder else {
+            break;
+        };
+        headers_builder_add(interpreter, Some(id), pair.to_vec());
+        if interpreter.pending_exception.is_some() {
+            return DexValue::Void;
+        }
+    }
+    let pairs = header_pairs(interpreter, &builder);
+    new_headers(interpreter, HEADERS, &pairs)
+}
+
+/// `Map<String, String>.toHeaders()`
+fn headers_of_map(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let entries = map_entries(interpreter, &arg(&args, 0));
+    let values = entries.into_iter().flat_map(|(name, value)| [name, value]).collect();
+    let array = DexValue::Array(values);
+    headers_of(interpreter, None, vec![array])
+}
+
+fn source_client(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    if let DexValue::Object(client) = field(interpreter, this, SOURCE_CLIENT_FIELD) {
+        return DexValue::Object(client);
+    }
+    let client = new_client(interpreter, Vec::new(), Vec::new());
+    set_field(interpreter, this, SOURCE_CLIENT_FIELD, client.clone());
+    client
+}
+
+/// Built once from `headersBuilder()`, which sources override to add their referer and such
+fn source_headers(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    if let DexValue::Object(headers) = field(interpreter, this, SOURCE_HEADERS_FIELD) {
+        return DexValue::Object(headers);
+    }
+    let Some(source) = this else {
+        return DexValue::Null;
+    };
+    let builder = interpreter.invoke_virtual(source, "headersBuilder:()Lokhttp3/Headers$Builder;", Vec::new());
+    if interpreter.pending_exception.is_some() {
+        return DexValue::Void;
+    }
+    let pairs = header_pairs(interpreter, &builder.unwrap_or(DexValue::Null));
+    let headers = new_headers(interpreter, HEADERS, &pairs);
+    set_field(interpreter, this, SOURCE_HEADERS_FIELD, headers.clone());
+    headers
+}
+
+fn source_headers_builder(interpreter: &mut Interpreter, _this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    new_headers(interpreter, HEADERS_BUILDER, &[("User-Agent".to_string(), DEFAULT_USER_AGENT.to_string())])
+}
+
+/// `GET(url, headers, cache)` and the others of the source api. `mask` is that of the `$default`
+/// variants, a bit per parameter from the url up: bit 1 set means the headers were left out,
+/// bit 2 the body of the methods taking one.
+fn helper_request(interpreter: &mut Interpreter, method: &str, args: &[DexValue], mask: i32) -> DexValue {
+    let url = match arg(args, 0) {
+        DexValue::String(text) => url_or_throw(interpreter, &text),
+        url => url,
+    };
+    if interpreter.pending_exception.is_some() {
+        return DexValue::Void;
+    }
+    let headers = if mask & 2 != 0 { new_headers(interpreter, HEADERS, &[]) } else { arg(args, 1) };
+    let body = match method {
+        "GET" => DexValue::Null,
+        _ if mask & 4 != 0 => {
+            let content_type = new_media_type(interpreter, FORM_CONTENT_TYPE);
+            new_body(interpreter, FORM_BODY, Vec::new(), content_type)
+        }
+        _ => arg(args, 2),
+    };
+    let id = interpreter.alloc_object(REQUEST);
+    let request = interpreter.heap.get_mut(&id).unwrap();
+    request.fields.insert(URL_FIELD.to_string(), url);
+    request.fields.insert(METHOD_FIELD.to_string(), DexValue::String(method.to_string()));
+    request.fields.insert(HEADERS_FIELD.to_string(), headers);
+    request.fields.insert(BODY_FIELD.to_string(), body);
+    DexValue::Object(id)
+}
+
+fn request_builder_init(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    set_header_pairs(interpreter, this, &[]);
+    set_field(interpreter, this, METHOD_FIELD, DexValue::String("GET".to_string()));
+    DexValue::Void
+}
+
+/// Websocket urls are taken as the http ones they start with
+fn request_builder_url(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let url = match arg(&args, 0) {
+        DexValue::String(text) => {
+            let text = match text.get(..4) {
+                Some(scheme) if scheme.eq_ignore_ascii_case("ws:/") => format!("http:{}", &text[3..]),
+                _ => match text.get(..5) {
+                    Some(scheme) if scheme.eq_ignore_ascii_case("wss:/") => format!("https:{}", &text[4..]),
+                    _ => text,
+                },
+            };
+            url_or_throw(interpreter, &text)
+        }
+        url => url,
+    };
+    if interpreter.pending_exception.is_some() {
+        return DexValue::Void;
+    }
+    set_field(interpreter, this, URL_FIELD, url);
+    this_value(this)
+}
+
+fn request_builder_headers(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let pairs = header_pairs(interpreter, &arg(&args, 0));
+    set_header_pairs(interpreter, this, &pairs);
+    this_value(this)
+}
+
+fn requires_body(method: &str) -> bool {
+    matches!(method, "POST" | "PUT" | "PATCH" | "PROPPATCH" | "REPORT")
+}
+
+fn request_builder_method(interpreter: &mut Interpreter, this: Option<ObjectId>, method: &str, body: DexValue) -> DexValue {
+    let has_body = matches!(body, DexValue::Object(_));
+    if method.is_empty() {
+        return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, "method.isEmpty() == true");
+    }
+    if has_body && (method == "GET" || method == "HEAD") {
+        return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &format!("method {} must not have a request body.", method));
+    }
+    if !has_body && requires_body(method) {
+        return throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, &format!("method {} must have a request body.", method));
+    }
+    set_field(interpreter, this, METHOD_FIELD, DexValue::String(method.to_string()));
+    set_field(interpreter, this, BODY_FIELD, if has_body { body } else { DexValue::Null });
+    this_value(this)
+}
+
+fn request_builder_build(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let url = field(interpreter, this, URL_FIELD);
+    if !matches!(url, DexValue::Object(_)) {
+        return throw(interpreter, ILLEGAL_STATE_EXCEPTION, "url == null");
+    }
+    let pairs = header_pairs(interpreter, &this_value(this));
+    let headers = new_headers(interpreter, HEADERS, &pairs);
+    let method = field(interpreter, this, METHOD_FIELD);
+    let body = field(interpreter, this, BODY_FIELD);
+    let id = interpreter.alloc_object(REQUEST);
+    let request = interpreter.heap.get_mut(&id).unwrap();
+    request.fields.insert(URL_FIELD.to_string(), url);
+    request.fields.insert(METHOD_FIELD.to_string(), method);
+    request.fields.insert(HEADERS_FIELD.to_string(), headers);
+    request.fields.insert(BODY_FIELD.to_string(), body);
+    DexValue::Object(id)
+}
+
+fn request_new_builder(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let pairs = header_pairs(interpreter, &field(interpreter, this, HEADERS_FIELD));
+    let builder = new_headers(interpreter, REQUEST_BUILDER, &pairs);
+    let DexValue::Object(id) = builder else {
+        return DexValue::Void;
+    };
+    for name in [URL_FIELD, METHOD_FIELD, BODY_FIELD] {
+        let value = field(interpreter, this, name);
+        set_field(interpreter, Some(id), name, value);
+    }
+    builder
+}
+
+fn request_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let method = text_field(interpreter, this, METHOD_FIELD).unwrap_or_default();
+    let url = match field(interpreter, this, URL_FIELD) {
+        DexValue::Object(id) => url_of(interpreter, Some(id)).compose(),
+        _ => String::new(),
+    };
+    DexValue::String(format!("Request{{method={}, url={}}}", method, url))
+}
+
+fn response_header(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let headers = field(interpreter, this, HEADERS_FIELD);
+    match header_value(interpreter, &headers, &string_arg(&args, 0).unwrap_or_default()) {
+        DexValue::Null => arg(&args, 1),
+        value => value,
+    }
+}
+
+fn response_new_builder(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let pairs = header_pairs(interpreter, &field(interpreter, this, HEADERS_FIELD));
+    let builder = new_headers(interpreter, RESPONSE_BUILDER, &pairs);
+    for name in [REQUEST_FIELD, CODE_FIELD, MESSAGE_FIELD, BODY_FIELD] {
+        let value = field(interpreter, this, name);
+        set_field(interpreter, object_of(&builder), name, value);
+    }
+    builder
+}
+
+fn response_builder_build(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let code = field(interpreter, this, CODE_FIELD).as_int().unwrap_or(-1);
+    let request = field(interpreter, this, REQUEST_FIELD);
+    let message = field(interpreter, this, MESSAGE_FIELD);
+    if code < 0 {
+        return throw(interpreter, ILLEGAL_STATE_EXCEPTION, &format!("code < 0: {}", code));
+    }
+    if !matches!(request, DexValue::Object(_)) {
+        return throw(interpreter, ILLEGAL_STATE_EXCEPTION, "request == null");
+    }
+    if !matches!(message, DexValue::String(_)) {
+        return throw(interpreter, ILLEGAL_STATE_EXCEPTION, "message == null");
+    }
+    let pairs = header_pairs(interpreter, &this_value(this));
+    let headers = new_headers(interpreter, HEADERS, &pairs);
+    // OkHttp leaves a missing body empty rather than null since 5.0
+    let body = match field(interpreter, this, BODY_FIELD) {
+        DexValue::Object(body) => DexValue::Object(body),
+        _ => new_body(interpreter, RESPONSE_BODY, Vec::new(), DexValue::Null),
+    };
+    let id = interpreter.alloc_object(RESPONSE);
+    let response = interpreter.heap.get_mut(&id).unwrap();
+    response.fields.insert(REQUEST_FIELD.to_string(), request);
+    response.fields.insert(CODE_FIELD.to_string(), DexValue::Int(code));
+    response.fields.insert(MESSAGE_FIELD.to_string(), message);
+    response.fields.insert(HEADERS_FIELD.to_string(), headers);
+    response.fields.insert(BODY_FIELD.to_string(), body);
+    DexValue::Object(id)
+}
+
+fn response_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let code = field(interpreter, this, CODE_FIELD).as_int().unwrap_or(0);
+    let message = text_field(interpreter, this, MESSAGE_FIELD).unwrap_or_default();
+    let url = match field(interpreter, this, REQUEST_FIELD) {
+        DexValue::Object(request) => match field(interpreter, Some(request), URL_FIELD) {
+            DexValue::Object(url) => url_of(interpreter, Some(url)).compose(),
+            _ => String::new(),
+        },
+        _ => String::new(),
+    };
+    DexValue::String(format!("Response{{protocol=http/1.1, code={}, message={}, url={}}}", code, message, url))
+}
+
+fn new_cookie(interpreter: &mut Interpreter, cookie: &Cookie) -> DexValue {
+    let id = interpreter.alloc_object(COOKIE);
+    let object = interpreter.heap.get_mut(&id).unwrap();
+    let fields = [
+        (NAME_FIELD, DexValue::String(cookie.name.clone())),
+        (VALUE_FIELD, DexValue::String(cookie.value.clone())),
+        (DOMAIN_FIELD, DexValue::String(cookie.domain.clone())),
+        (COOKIE_PATH_FIELD, DexValue::String(cookie.path.clone())),
+        (EXPIRES_AT_FIELD, DexValue::Long(cookie.expires_at.unwrap_or(MAX_DATE))),
+        (SECURE_FIELD, DexValue::Boolean(cookie.secure)),
+        (HTTP_ONLY_FIELD, DexValue::Boolean(cookie.http_only)),
+        (HOST_ONLY_FIELD, DexValue::Boolean(cookie.host_only)),
+        (PERSISTENT_FIELD, DexValue::Boolean(cookie.expires_at.is_some())),
+    ];
+    for (name, value) in fields {
+        object.fields.insert(name.to_string(), value);
+    }
+    DexValue::Object(id)
+}
+
+fn cookie_of(interpreter: &Interpreter, this: Option<ObjectId>) -> Cookie {
+    let flag = |name| matches!(field(interpreter, this, name), DexValue::Boolean(true));
+    Cookie {
+        name: text_field(interpreter, this, NAME_FIELD).unwrap_or_default(),
+        value: text_field(interpreter, this, VALUE_FIELD).unwrap_or_default(),
+        domain: text_field(interpreter, this, DOMAIN_FIELD).unwrap_or_default(),
+        host_only: flag(HOST_ONLY_FIELD),
+        path: text_field(interpreter, this, COOKIE_PATH_FIELD).unwrap_or_else(|| "/".to_string()),
+        expires_at: field(interpreter, this, EXPIRES_AT_FIELD).as_long().filter(|_| flag(PERSISTENT_FIELD)),
+        secure: flag(SECURE_FIELD),
+        http_only: flag(HTTP_ONLY_FIELD),
+    }
+}
+
+fn cookie_jar_load(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let url = url_of(interpreter, object_arg(&args, 0));
+    let source = interpreter.source_name().unwrap_or_default();
+    let cookies = cookies::load(&source, url.host(), &url.path, url.scheme == "https").iter()
+        .map(|cookie| new_cookie(interpreter, cookie))
+        .collect();
+    DexValue::Object(new_list(interpreter, cookies))
+}
+
+fn cookie_jar_save(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let cookies = elements_of(interpreter, &arg(&args, 1)).iter()
+        .filter_map(object_of)
+        .map(|id| cookie_of(interpreter, Some(id)))
+        .collect();
+    cookies::save(&interpreter.source_name().unwrap_or_default(), cookies);
+    DexValue::Void
+}
+
+/// Like a Set-Cookie header, which `Cookie.parse()` reads back
+fn cookie_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let cookie = cookie_of(interpreter, this);
+    let mut text = format!("{}={}", cookie.name, cookie.value);
+    match cookie.expires_at {
+        Some(i64::MIN) => text.push_str("; max-age=0"),
+        Some(expires_at) => {
+            if let Some(date) = chrono::DateTime::from_timestamp_millis(expires_at) {
+                text.push_str(&date.format("; expires=%a, %d %b %Y %H:%M:%S GMT").to_string());
+            }
+        }
+        None => {}
+    }
+    if !cookie.host_only {
+        text.push_str(&format!("; domain={}", cookie.domain));
+    }
+    text.push_str(&format!("; path={}", cookie.path));
+    if cookie.secure {
+        text.push_str("; secure");
+    }
+    if cookie.http_only {
+        text.push_str("; httponly");
+    }
+    DexValue::String(text)
+}
+
+/// Give a client or its builder the lists `interceptors` and `network_interceptors`
+fn store_interceptors(interpreter: &mut Interpreter, this: Option<ObjectId>, interceptors: Vec<DexValue>, network_interceptors: Vec<DexValue>) {
+    let interceptors = new_list(interpreter, interceptors);
+    let network_interceptors = new_list(interpreter, network_interceptors);
+    set_field(interpreter, this, INTERCEPTORS_FIELD, DexValue::Object(interceptors));
+    set_field(interpreter, this, NETWORK_INTERCEPTORS_FIELD, DexValue::Object(network_interceptors));
+}
+
+/// The interceptors and network interceptors of a client or its builder
+fn interceptors_of(interpreter: &Interpreter, this: Option<ObjectId>) -> (Vec<DexValue>, Vec<DexValue>) {
+    let interceptors = elements_of(interpreter, &field(interpreter, this, INTERCEPTORS_FIELD));
+    let network_interceptors = elements_of(interpreter, &field(interpreter, this, NETWORK_INTERCEPTORS_FIELD));
+    (interceptors, network_interceptors)
+}
+
+fn new_client(interpreter: &mut Interpreter, interceptors: Vec<DexValue>, network_interceptors: Vec<DexValue>) -> DexValue {
+    let id = interpreter.alloc_object(OK_HTTP_CLIENT);
+    store_interceptors(interpreter, Some(id), interceptors, network_interceptors);
+    DexValue::Object(id)
+}
+
+fn add_interceptor(interpreter: &mut Interpreter, this: Option<ObjectId>, list: &str, interceptor: DexValue) -> DexValue {
+    if !matches!(interceptor, DexValue::Object(_)) {
+        return throw(interpreter, NULL_POINTER_EXCEPTION, "interceptor == null");
+    }
+    let list = object_of(&field(interpreter, this, list));
+    if let Some(elements) = list.and_then(|id| interpreter.heap.get_mut(&id)).and_then(|list| list.array.as_mut()) {
+        elements.push(interceptor);
+    }
+    this_value(this)
+}
+
+fn new_call(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let id = interpreter.alloc_object(CALL);
+    set_field(interpreter, Some(id), CLIENT_FIELD, this_value(this));
+    set_field(interpreter, Some(id), REQUEST_FIELD, arg(&args, 0));
+    DexValue::Object(id)
+}
+
+/// Run the request through the client's interceptors, then its network interceptors. With no
+/// cache or bridge of OkHttp's own in between, the two only differ in order.
+fn call_execute(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let client = object_of(&field(interpreter, this, CLIENT_FIELD));
+    let (mut interceptors, network_interceptors) = interceptors_of(interpreter, client);
+    interceptors.extend(network_interceptors);
+    let request = field(interpreter, this, REQUEST_FIELD);
+    let chain = new_chain(interpreter, this, interceptors, 0, request.clone());
+    chain_proceed(interpreter, object_of(&chain), vec![request])
+}
+
+/// `call.await()` and `call.awaitSuccess()`, which suspend until the response is in. It resumes
+/// the continuation, or the exception does when there is none or, for the latter, when it isn't
+/// a 2xx one.
+fn call_await(interpreter: &mut Interpreter, args: &[DexValue], success: bool) -> DexValue {
+    let response = call_execute(interpreter, object_of(&arg(args, 0)), Vec::new());
+    if interpreter.pending_exception.is_none() && success {
+        let code = field(interpreter, object_of(&response), CODE_FIELD).as_int().unwrap_or(0);
+        if !(200..300).contains(&code) {
+            interpreter.throw_new(HTTP_EXCEPTION, &format!("HTTP error {}", code));
+            let exception = interpreter.pending_exception.as_ref().and_then(|pending| object_of(&pending.exception));
+            set_field(interpreter, exception, CODE_FIELD, DexValue::Int(code));
+        }
+    }
+
+    let outcome = match interpreter.pending_exception.take() {
+        Some(pending) => coroutines::failure(interpreter, pending.exception),
+        None => response,
+    };
+    coroutines::resume_later(interpreter, &arg(args, 1), outcome);
+    coroutines::suspended(interpreter)
+}
+
+fn new_chain(interpreter: &mut Interpreter, call: Option<ObjectId>, interceptors: Vec<DexValue>, index: usize, request: DexValue) -> DexValue {
+    let id = interpreter.alloc_object(CHAIN);
+    let chain = interpreter.heap.get_mut(&id).unwrap();
+    chain.array = Some(interceptors);
+    chain.fields.insert(CALL_FIELD.to_string(), this_value(call));
+    chain.fields.insert(INDEX_FIELD.to_string(), DexValue::Int(index as i32));
+    chain.fields.insert(REQUEST_FIELD.to_string(), request);
+    DexValue::Object(id)
+}
+
+/// Hand `request` to the next interceptor with a chain one further along, the one past the last
+/// interceptor sends it
+fn chain_proceed(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let request = arg(&args, 0);
+    let interceptors = elements_of(interpreter, &this_value(this));
+    let index = field(interpreter, this, INDEX_FIELD).as_int().unwrap_or(0) as usize;
+    let Some(interceptor) = interceptors.get(index).and_then(object_of) else {
+        return execute(interpreter, &request).unwrap_or(DexValue::Void);
+    };
+
+    let call = object_of(&field(interpreter, this, CALL_FIELD));
+    let next = new_chain(interpreter, call, interceptors, index + 1, request);
+    let response = interpreter.invoke_virtual(interceptor, INTERCEPT, vec![next]);
+    if interpreter.pending_exception.is_some() {
+        return DexValue::Void;
+    }
+    match response {
+        Some(DexValue::Object(response)) => DexValue::Object(response),
+        _ => {
+            let class_name = interpreter.heap.get(&interceptor).map(|object| object.class_name.clone()).unwrap_or_default();
+            let name = class_name.trim_start_matches('L').trim_end_matches(';').replace('/', ".");
+            throw(interpreter, ILLEGAL_STATE_EXCEPTION, &format!("interceptor {} returned null", name))
+        }
+    }
+}
+
+/// A request as it goes over the wire
+#[derive(Clone)]
+pub struct HttpRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<Vec<u8>>,
+}
+
+/// A response with its whole body, `url` is the one it came from after redirects
+pub struct HttpResponse {
+    pub code: i32,
+    pub message: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Send the Request object `request` and return the Response object for what came back, `None`
+/// with an IOException pending when there was no response
+pub fn execute(interpreter: &mut Interpreter, request: &DexValue) -> Option<DexValue> {
+    let DexValue::Object(request_id) = request else {
+        throw(interpreter, ILLEGAL_ARGUMENT_EXCEPTION, "request == null");
+        return None;
+    };
+    let source = interpreter.source_name().unwrap_or_default();
+    let mut outgoing = http_request(interpreter, *request_id);
+    let original_url = outgoing.url.clone();
+    let mut follow_ups = 0;
+    loop {
+        let incoming = match exchange(&source, &outgoing) {
+            Ok(incoming) => incoming,
+            Err((class_name, message)) => {
+                throw(interpreter, class_name, &message);
+                return None;
+            }
+        };
+        let Some(next) = follow_up(&outgoing, &incoming) else {
+            return Some(new_response(interpreter, *request_id, &original_url, incoming));
+        };
+        follow_ups += 1;
+        if follow_ups > MAX_FOLLOW_UPS {
+            throw(interpreter, PROTOCOL_EXCEPTION, &format!("Too many follow-up requests: {}", follow_ups));
+            return None;
+        }
+        outgoing = next;
+    }
+}
+
+/// Send `request` with the cookies of `source` it matches and keep those the response sets.
+/// Like OkHttp's bridge interceptor, cookies from the jar replace a Cookie header of the request.
+fn exchange(source: &str, request: &HttpRequest) -> Result<HttpResponse, (&'static str, String)> {
+    let url = Url::parse(&request.url).map_err(|message| (IO_EXCEPTION, message))?;
+    let jar = cookies::load(source, url.host(), &url.path, url.scheme == "https");
+    let response = if jar.is_empty() {
+        send(request)?
+    } else {
+        let mut request = request.clone();
+        request.headers.retain(|(name, _)| !name.eq_ignore_ascii_case("Cookie"));
+        request.headers.push(("Cookie".to_string(), cookies::header_value(&jar)));
+        send(&request)?
+    };
+    cookies::save(source, set_cookies(&url, &response.headers));
+    Ok(response)
+}
+
+/// Cookies the Set-Cookie headers among `headers` set for `url`
+fn set_cookies(url: &Url, headers: &[(String, String)]) -> Vec<Cookie> {
+    let now = clock::now_millis();
+    headers.iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Set-Cookie"))
+        .filter_map(|(_, value)| Cookie::parse(url.host(), &url.path, value, now))
+        .collect()
+}
+
+/// The request a redirect asks for, `None` unless `response` is one OkHttp follows. A 300 to 303
+/// turns the request into a GET without body, a 307 or 308 is only followed for GET and HEAD.
+fn follow_up(request: &HttpRequest, response: &HttpResponse) -> Option<HttpRequest> {
+    if !matches!(response.code, 300..=303 | 307 | 308) {
+        return None;
+    }
+    let location = response.headers.iter().find(|(name, _)| name.eq_ignore_ascii_case("Location"))?;
+    let location = canonicalize(&location.1, PATH_ENCODE_SET, true);
+    let url = Url::parse(&resolve_reference(&request.url, &location)?).ok()?;
+    let is_get = request.method == "GET" || request.method == "HEAD";
+    if matches!(response.code, 307 | 308) && !is_get {
+        return None;
+    }
+
+    let mut next = request.clone();
+    if !is_get && request.method != "PROPFIND" {
+        next.method = "GET".to_string();
+        next.body = None;
+        next.headers.retain(|(name, _)| {
+            !["Transfer-Encoding", "Content-Length", "Content-Type"].iter().any(|header| name.eq_ignore_ascii_case(header))
+        });
+    }
+    // Credentials stay with the host they were meant for
+    let same_host = Url::parse(&request.url).is_ok_and(|previous| previous.authority == url.authority && previous.scheme == url.scheme);
+    if !same_host {
+        next.headers.retain(|(name, _)| !name.eq_ignore_ascii_case("Authorization"));
+    }
+    next.url = url.compose();
+    Some(next)
+}
+
+/// Host, encoded path and whether it's https of the http or https url `text`
+pub fn url_parts(text: &str) -> Option<(String, String, bool)> {
+    let url = Url::parse(text).ok()?;
+    Some((url.host().to_string(), url.path.clone(), url.scheme == "https"))
+}
+
+/// Host of an HttpUrl object
+pub fn url_host(interpreter: &Interpreter, url: &DexValue) -> Option<String> {
+    let url = url_of(interpreter, Some(object_of(url)?));
+    (!url.authority.is_empty()).then(|| url.host().to_string())
+}
+
+/// What the Request object asks for, with the Content-Type of its body like OkHttp's bridge
+/// interceptor adds it
+fn http_request(interpreter: &Interpreter, request: ObjectId) -> HttpRequest {
+    let url = match field(interpreter, Some(request), URL_FIELD) {
+        DexValue::Object(id) => url_of(interpreter, Some(id)).compose(),
+        _ => String::new(),
+    };
+    let mut headers = header_pairs(interpreter, &field(interpreter, Some(request), HEADERS_FIELD));
+    let body = match field(interpreter, Some(request), BODY_FIELD) {
+        DexValue::Object(id) => {
+            let content_type = field(interpreter, Some(id), CONTENT_TYPE_FIELD);
+            if let Some(media_type) = text_field(interpreter, object_of(&content_type), MEDIA_TYPE_FIELD)
+                && !headers.iter().any(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+            {
+                headers.push(("Content-Type".to_string(), media_type));
+            }
+            Some(bytes_of(interpreter, Some(id)))
+        }
+        _ => None,
+    };
+    HttpRequest {
+        method: text_field(interpreter, Some(request), METHOD_FIELD).unwrap_or_else(|| "GET".to_string()),
+        url,
+        headers,
+        body,
+    }
+}
+
+/// Send `request` with ureq. Failures come back as the exception OkHttp would throw and its
+/// message.
+pub fn send(request: &HttpRequest) -> Result<HttpResponse, (&'static str, String)> {
+    let mut call = AGENT.request(&request.method, &request.url);
+    // ureq keeps one value per name, repeated headers are folded like HTTP allows
+    let mut names: Vec<&str> = Vec::new();
+    for (name, _) in &request.headers {
+        if !names.iter().any(|seen| seen.eq_ignore_ascii_case(name)) {
+            names.push(name);
+        }
+    }
+    for name in names {
+        let values: Vec<&str> = request.headers.iter()
+            .filter(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+            .collect();
+        call = call.set(name, &values.join(", "));
+    }
+
+    let result = match &request.body {
+        Some(body) => call.send_bytes(body),
+        None => call.call(),
+    };
+    let response = match result {
+        Ok(response) | Err(ureq::Error::Status(_, response)) => response,
+        Err(ureq::Error::Transport(transport)) => return Err(transport_error(request, transport)),
+    };
+
+    let code = response.status() as i32;
+    let message = response.status_text().to_string();
+    let url = response.get_url().to_string();
+    // ureq already gunzipped the body, so the headers about its encoding no longer hold
+    let gzipped = response.header("Content-Encoding").is_some_and(|encoding| encoding.eq_ignore_ascii_case("gzip"));
+    let mut headers = Vec::new();
+    let mut seen: Vec<String> = Vec::new();
+    for name in response.headers_names() {
+        if seen.contains(&name) || gzipped && (name == "content-encoding" || name == "content-length") {
+            continue;
+        }
+        headers.extend(response.all(&name).into_iter().map(|value| (name.clone(), value.to_string())));
+        seen.push(name);
+    }
+    let mut body = Vec::new();
+    if let Err(error) = response.into_reader().read_to_end(&mut body) {
+        return Err((IO_EXCEPTION, error.to_string()));
+    }
+    Ok(HttpResponse { code, message, url, headers, body })
+}
+
+fn transport_error(request: &HttpRequest, transport: ureq::Transport) -> (&'static str, String) {
+    let host = Url::parse(&request.url).map(|url| url.host().to_string()).unwrap_or_default();
+    let message = transport.to_string();
+    match transport.kind() {
+        ureq::ErrorKind::Dns => (UNKNOWN_HOST_EXCEPTION, format!("Unable to resolve host \"{}\": No address associated with hostname", host)),
+        ureq::ErrorKind::ConnectionFailed => (CONNECT_EXCEPTION, format!("Failed to connect to {}", host)),
+        _ if message.contains("timed out") => (SOCKET_TIMEOUT_EXCEPTION, "timeout".to_string()),
+        _ => (IO_EXCEPTION, message),
+    }
+}
+
+/// The Response object for `incoming`. After a redirect its request is a copy of the original
+/// one with the url it ended up at.
+fn new_response(interpreter: &mut Interpreter, request: ObjectId, original_url: &str, incoming: HttpResponse) -> DexValue {
+    let mut request = DexValue::Object(request);
+    if incoming.url != original_url
+        && let Ok(url) = Url::parse(&incoming.url)
+    {
+        let url = new_url(interpreter, HTTP_URL, &url);
+        let copy = interpreter.alloc_object(REQUEST);
+        for name in [METHOD_FIELD, HEADERS_FIELD, BODY_FIELD] {
+            let value = field(interpreter, object_of(&request), name);
+            set_field(interpreter, Some(copy), name, value);
+        }
+        set_field(interpreter, Some(copy), URL_FIELD, url);
+        request = DexValue::Object(copy);
+    }
+
+    let content_type = incoming.headers.iter()
+        .rev()
+        .find(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+        .filter(|(_, value)| media_type_parts(value).is_some())
+        .map(|(_, value)| value.clone());
+    let content_type = match content_type {
+        Some(media_type) => new_media_type(interpreter, &media_type),
+        None => DexValue::Null,
+    };
+    let body = new_body(interpreter, RESPONSE_BODY, incoming.body, content_type);
+    let headers = new_headers(interpreter, HEADERS, &incoming.headers);
+
+    let id = interpreter.alloc_object(RESPONSE);
+    let response = interpreter.heap.get_mut(&id).unwrap();
+    response.fields.insert(REQUEST_FIELD.to_string(), request);
+    response.fields.insert(CODE_FIELD.to_string(), DexValue::Int(incoming.code));
+    response.fields.insert(MESSAGE_FIELD.to_string(), DexValue::String(incoming.message));
+    response.fields.insert(HEADERS_FIELD.to_string(), headers);
+    response.fields.insert(BODY_FIELD.to_string(), body);
+    DexValue::Object(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex, self_test_cases};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+
+    fn call(interpreter: &mut Interpreter, class_name: &str, method_key: &str, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+        platform_method(class_name, method_key).unwrap()(interpreter, this, args)
+    }
+
+    fn string(text: &str) -> DexValue {
+        DexValue::String(text.to_string())
+    }
+
+    /// A POST of `body` as text/plain to `url` with an X-Test header set to `a` and `b`
+    fn post_request(interpreter: &mut Interpreter, url: &str, body: &str) -> DexValue {
+        let builder = Some(interpreter.alloc_object(REQUEST_BUILDER));
+        call(interpreter, REQUEST_BUILDER, "<init>:()V", builder, Vec::new());
+        call(interpreter, REQUEST_BUILDER, "url:(Ljava/lang/String;)Lokhttp3/Request$Builder;", builder, vec![string(url)]);
+        call(interpreter, REQUEST_BUILDER, "header:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Request$Builder;", builder, vec![string("X-Test"), string("a")]);
+        call(interpreter, REQUEST_BUILDER, "addHeader:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Request$Builder;", builder, vec![string("X-Test"), string("b")]);
+        call(interpreter, REQUEST_BUILDER, "header:(Ljava/lang/String;Ljava/lang/String;)Lokhttp3/Request$Builder;", builder, vec![string("Removed"), string("c")]);
+        call(interpreter, REQUEST_BUILDER, "removeHeader:(Ljava/lang/String;)Lokhttp3/Request$Builder;", builder, vec![string("removed")]);
+        let media_type = call(interpreter, MEDIA_TYPE_COMPANION, "get:(Ljava/lang/String;)Lokhttp3/MediaType;", None, vec![string("text/plain; charset=utf-8")]);
+        let body = call(interpreter, REQUEST_BODY_COMPANION, "create:(Ljava/lang/String;Lokhttp3/MediaType;)Lokhttp3/RequestBody;", None, vec![string(body), media_type]);
+        call(interpreter, REQUEST_BUILDER, "post:(Lokhttp3/RequestBody;)Lokhttp3/Request$Builder;", builder, vec![body]);
+        call(interpreter, REQUEST_BUILDER, "build:()Lokhttp3/Request;", builder, Vec::new())
+    }
+
+    /// Answers one connection after the other with `responses`, closing each, and hands back the
+    /// requests that came in, head and body
+    fn serve(responses: Vec<&'static str>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            responses.into_iter()
+                .map(|response| {
+                    let (mut stream, _) = listener.accept().unwrap();
+                    let mut reader = BufReader::new(stream.try_clone().unwrap());
+                    let mut request = String::new();
+                    let mut length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if let Some((name, value)) = line.split_once(':') && name.eq_ignore_ascii_case("Content-Length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                        request.push_str(&line);
+                        if line == "\r\n" {
+                            break;
+                        }
+                    }
+                    let mut body = vec![0; length];
+                    reader.read_exact(&mut body).unwrap();
+                    request.push_str(&String::from_utf8(body).unwrap());
+                    stream.write_all(response.as_bytes()).unwrap();
+                    request
+                })
+                .collect()
+        });
+        (base, server)
+    }
+
+    #[test]
+    fn builds_requests() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let request = post_request(&mut interpreter, "HTTPS://Example.com/a/./b?q=1", "hello");
+        let id = object_of(&request);
+        assert_eq!(call(&mut interpreter, REQUEST, "method:()Ljava/lang/String;", id, Vec::new()), string("POST"));
+        // The last value of a header, looked up by any case
+        assert_eq!(call(&mut interpreter, REQUEST, "header:(Ljava/lang/String;)Ljava/lang/String;", id, vec![string("x-test")]), string("b"));
+        assert_eq!(call(&mut interpreter, REQUEST, "header:(Ljava/lang/String;)Ljava/lang/String;", id, vec![string("Removed")]), DexValue::Null);
+
+        let outgoing = http_request(&interpreter, id.unwrap());
+        assert_eq!(outgoing.method, "POST");
+        assert_eq!(outgoing.url, "https://example.com/a/b?q=1");
+        let headers: Vec<(&str, &str)> = outgoing.headers.iter().map(|(name, value)| (name.as_str(), value.as_str())).collect();
+        assert_eq!(headers, [("X-Test", "a"), ("X-Test", "b"), ("Content-Type", "text/plain; charset=utf-8")]);
+        assert_eq!(outgoing.body.as_deref(), Some(&b"hello"[..]));
+    }
+
+    #[test]
+    fn sends_requests_and_reads_responses() {
+        let (base, server) = serve(vec![
+            "HTTP/1.1 201 Created\r\nX-Reply: yes\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: 7\r\nConnection: close\r\n\r\ncreated",
+        ]);
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let request = post_request(&mut interpreter, &format!("{}/items?q=1", base), "hello");
+        let response = execute(&mut interpreter, &request).unwrap();
+        let received = server.join().unwrap();
+
+        let head = received[0].to_ascii_lowercase();
+        assert!(head.starts_with("post /items?q=1 http/1.1\r\n"), "{}", received[0]);
+        assert!(head.contains("\r\nx-test: a, b\r\n"), "{}", received[0]);
+        assert!(head.contains("\r\ncontent-type: text/plain; charset=utf-8\r\n"), "{}", received[0]);
+        assert!(received[0].ends_with("\r\n\r\nhello"), "{}", received[0]);
+
+        let id = object_of(&response);
+        assert_eq!(call(&mut interpreter, RESPONSE, "code:()I", id, Vec::new()), DexValue::Int(201));
+        assert_eq!(call(&mut interpreter, RESPONSE, "message:()Ljava/lang/String;", id, Vec::new()), string("Created"));
+        assert_eq!(call(&mut interpreter, RESPONSE, "header:(Ljava/lang/String;)Ljava/lang/String;", id, vec![string("X-Reply")]), string("yes"));
+        let body = call(&mut interpreter, RESPONSE, "body:()Lokhttp3/ResponseBody;", id, Vec::new());
+        // A body can be read more than once
+        for _ in 0..2 {
+            assert_eq!(call(&mut interpreter, RESPONSE_BODY, "string:()Ljava/lang/String;", object_of(&body), Vec::new()), string("created"));
+        }
+    }
+
+    /// A 302 turns the POST into a GET without body, the response's request is the one it ended at
+    #[test]
+    fn follows_redirects() {
+        let (base, server) = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /next\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\ndone",
+        ]);
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let request = post_request(&mut interpreter, &format!("{}/start", base), "hello");
+        let response = execute(&mut interpreter, &request).unwrap();
+        let received = server.join().unwrap();
+
+        assert!(received[0].starts_with("POST /start "), "{}", received[0]);
+        assert!(received[1].starts_with("GET /next "), "{}", received[1]);
+        assert!(!received[1].to_ascii_lowercase().contains("content-type"), "{}", received[1]);
+        assert!(received[1].ends_with("\r\n\r\n"), "{}", received[1]);
+
+        let id = object_of(&response);
+        assert_eq!(call(&mut interpreter, RESPONSE, "code:()I", id, Vec::new()), DexValue::Int(200));
+        let request = field(&interpreter, id, REQUEST_FIELD);
+        assert_eq!(http_request(&interpreter, object_of(&request).unwrap()).url, format!("{}/next", base));
+    }
+}
@@ -1,28 +1,120 @@
-use crate::parser::class::get_name_of_class;
-use crate::parser::parser::Parser;
-use crate::parser::strings::parse_string_at_offset;
-use crate::{call_method, has_method, rust_log};
-use crate::types::{DexClass, DexValue, Frame, Instruction, Object, ObjectId};
+use crate::parser::parser::{DexTables, Parser};
+use crate::{call_method_with_args, has_method};
+use crate::interpreter::{boxing, charset, collections, coroutines, date, http, json, jsoup, native, serialization, threads};
+use crate::interpreter::error::InterpreterError;
+use crate::interpreter::exception::{
+    builtin_super_class, PendingException, ABSTRACT_METHOD_ERROR, ARITHMETIC_EXCEPTION, ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, ASSERTION_ERROR,
+    CAUSE_FIELD, EXCEPTION_IN_INITIALIZER_ERROR, ILLEGAL_ARGUMENT_EXCEPTION, ILLEGAL_STATE_EXCEPTION,
+    KOTLIN_NULL_POINTER_EXCEPTION, MESSAGE_FIELD, NEGATIVE_ARRAY_SIZE_EXCEPTION, NO_CLASS_DEF_FOUND_ERROR, NO_SUCH_METHOD_ERROR,
+    NULL_POINTER_EXCEPTION, STACK_OVERFLOW_ERROR, STACK_TRACE_FIELD, THROWABLE, UNINITIALIZED_PROPERTY_ACCESS_EXCEPTION,
+    UNSUPPORTED_OPERATION_EXCEPTION, VERIFY_ERROR,
+};
+use crate::interpreter::strings::{double_to_string, float_to_string, new_string, string_method};
+use crate::interpreter::native::{binary_name, native_methods, platform_method, PlatformMethod, CLASS_DESCRIPTOR_FIELD, JAVA_LANG_CLASS};
+use crate::types::{method_key, method_name_of, AccessFlags, DexClass, DexMethod, DexValue, Frame, Instruction, Lambda, MethodHandleItem, NativeMethod, Object, ObjectId, Payload};
 use crate::utils::class_file_to_class;
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use jni::objects::GlobalRef;
 
+const KOTLIN_INTRINSICS: &str = "Lkotlin/jvm/internal/Intrinsics;";
+const KOTLIN_REFLECTION: &str = "Lkotlin/jvm/internal/Reflection;";
+const KOTLIN_CLASS_MAPPING: &str = "Lkotlin/jvm/JvmClassMappingKt;";
+/// The only bootstrap method invoke-custom links, which is how D8 compiles lambdas
+pub const LAMBDA_METAFACTORY: &str = "Ljava/lang/invoke/LambdaMetafactory;";
+/// The MethodHandle methods invoke-polymorphic can call, VarHandle access isn't supported
+pub const METHOD_HANDLE_INVOKERS: [&str; 2] = ["invoke", "invokeExact"];
+
+/// Frames a call may stack up before it fails with a StackOverflowError
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1024;
+
+static MAX_CALL_DEPTH: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CALL_DEPTH);
+
+/// Limit the number of frames on the interpreter's stack, applies to calls made from now on
+pub fn set_max_call_depth(depth: usize) {
+    MAX_CALL_DEPTH.store(depth, Ordering::SeqCst);
+}
+
+/// Whether `execute` runs `instr` rather than throwing a VerifyError. Range forms are run as
+/// their regular forms.
+pub fn is_implemented(instr: &Instruction) -> bool {
+    !matches!(instr, Instruction::ConstMethodType { .. })
+}
+
+/// Whether the interpreter provides method `method_key` of `class_name`, a class without a class
+/// file, or inherits it from a builtin superclass: as a platform method, a native shim, a String
+/// method or one of the Kotlin helpers run by name. Object's methods and lambdas need nothing.
+pub fn provides_method(class_name: &str, method_key: &str) -> bool {
+    if method_name_of(method_key) == "getClass" || class_name.starts_with("Lkotlin/jvm/functions/") {
+        return true;
+    }
+    let mut current = Some(class_name);
+    while let Some(name) = current {
+        let provided = matches!(name, "Ljava/lang/Object;" | KOTLIN_INTRINSICS | KOTLIN_REFLECTION | KOTLIN_CLASS_MAPPING)
+            || platform_method(name, method_key).is_some()
+            || native_methods(name).contains_key(method_key)
+            // Which constructors `new_string` takes only shows with the arguments
+            || (name == "Ljava/lang/String;" && (string_method(method_key).is_some() || method_name_of(method_key) == "<init>"));
+        if provided {
+            return true;
+        }
+        current = builtin_super_class(name)
+            .or_else(|| jsoup::builtin_super_class(name))
+            .or_else(|| coroutines::builtin_super_class(name));
+    }
+    false
+}
+
 #[macro_export]
 macro_rules! interpreter_log {
     ($interpreter:expr, $($arg:tt)*) => {
         if $interpreter.parser.debug_enabled {
-            println!($($arg)*);
+            $crate::logging::log(&format!($($arg)*));
         }
     };
 }
 
+/// How far a class got with its static initialization, classes without an entry weren't used yet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassState {
+    /// `<clinit>` is running, uses from within it see the class as initialized
+    Initializing,
+    Initialized,
+    /// `<clinit>` threw, later uses fail with a NoClassDefFoundError
+    Erroneous,
+}
+
 pub struct Interpreter {
     pub parser: Parser, // owned parser, no lifetime parameter
     pub heap: HashMap<ObjectId, Object>,
     pub object_refs: Vec<GlobalRef>,
     pub frames: Vec<Frame>, // call stack
     pub main_idx: usize,
+    /// Instance of the extension's main class created by the host
+    pub main_object: Option<ObjectId>,
     pub next_object_id: ObjectId,
+    pub pending_exception: Option<PendingException>,
+    /// Static field values per class, a class is present once its initialization started
+    pub statics: HashMap<String, HashMap<String, DexValue>>,
+    /// Initialization state per class descriptor, see `initialize_class`
+    pub class_states: HashMap<String, ClassState>,
+    /// Objects created by invoke-custom call sites and what they forward to
+    pub lambdas: HashMap<ObjectId, Lambda>,
+    /// The single Class object of each class descriptor, so class literals compare by identity
+    pub class_objects: HashMap<String, ObjectId>,
+    /// State of the seeded generator behind randomUUID(), for tests that need the same ids on
+    /// every run. `None` draws from the OS.
+    pub random_seed: Option<u64>,
+    /// Suspended continuations to resume and what with, in the order their platform suspend
+    /// functions finished, see `coroutines::complete`
+    pub resumptions: VecDeque<(ObjectId, DexValue)>,
+    /// How many times the running thread holds each object's monitor. The interpreter runs on
+    /// one thread, so nothing ever waits on them, see `threads`.
+    pub monitors: HashMap<ObjectId, u32>,
+    /// Set when a cached class of another install generation was refused during a call
+    generation_mismatch: RefCell<Option<InterpreterError>>,
 }
 
 impl Interpreter {
@@ -33,37 +125,90 @@ impl Interpreter {
             object_refs: Vec::new(),
             frames: Vec::new(),
             main_idx: 0,
+            main_object: None,
             next_object_id: 0,
+            pending_exception: None,
+            statics: HashMap::new(),
+            class_states: HashMap::new(),
+            lambdas: HashMap::new(),
+            class_objects: HashMap::new(),
+            random_seed: None,
+            resumptions: VecDeque::new(),
+            monitors: HashMap::new(),
+            generation_mismatch: RefCell::new(None),
         }
     }
 
-    pub fn push_frame(&mut self, class_idx: usize, method_name: String, args: Vec<DexValue>) {
-        let class = self.parser.classes[class_idx].clone();
+    pub fn push_frame(&mut self, class_idx: usize, method_name: String, receiver: Option<ObjectId>, args: Vec<DexValue>) -> bool {
+        let class = Arc::new(self.parser.classes[class_idx].clone());
 
-        self.push_frame_with_class(&class, class_idx, method_name, args);
+        self.push_frame_with_class(class, method_name, receiver, args)
     }
 
-    pub fn push_frame_with_class(&mut self, class: &DexClass, class_idx: usize, method_name: String, args: Vec<DexValue>) {
-        
-        let method_regs = {
-            let method = class.methods.get(&method_name).expect("method not found");
-            method.registers as usize
+    /// `receiver` is the object an instance method is called on, `None` for static methods.
+    /// `args` are register values, a long or double may be given without its high half.
+    /// `method_name` is a key of `class.methods` or a bare name, resolved by the number of `args`.
+    /// Returns false if the class has no such method or its code can't be decoded, a
+    /// NoSuchMethodError or VerifyError is thrown then.
+    pub fn push_frame_with_class(&mut self, class: Arc<DexClass>, method_name: String, receiver: Option<ObjectId>, args: Vec<DexValue>) -> bool {
+        let argc = args.iter().filter(|arg| **arg != DexValue::WideHigh).count();
+        let Some(method_key) = class.resolve_method_key(&method_name, Some(argc)) else {
+            interpreter_log!(self, "Method {}.{} not found", class.name, method_name);
+            self.throw_new(NO_SUCH_METHOD_ERROR, &format!("{}.{}", binary_name(&class.name), method_name));
+            return false;
         };
+        let mut class = class;
+        if class.methods[&method_key].code_pending() {
+            match self.parser.decoded_method(&class, &method_key) {
+                Ok(Some(method)) => {
+                    Arc::make_mut(&mut class).methods.insert(method_key.clone(), method);
+                }
+                Ok(None) => {}
+                Err(error) => {
+                    interpreter_log!(self, "Decoding {}.{} failed: {}", class.name, method_key, error);
+                    let message = format!("{}.{}: {}", binary_name(&class.name), method_key, error);
+                    self.throw_new(VERIFY_ERROR, &message);
+                    return false;
+                }
+            }
+        }
+        let method = &class.methods[&method_key];
+
+        let mut ins: Vec<DexValue> = receiver.map(DexValue::Object).into_iter().collect();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            let wide = arg.is_wide();
+            ins.push(arg);
+            if wide && args.peek() != Some(&DexValue::WideHigh) {
+                ins.push(DexValue::WideHigh);
+            }
+        }
+
+        // `this` and the arguments go into the last ins_size registers
+        let method_regs = method.registers as usize;
+        let ins_size = if method.ins_size == 0 { ins.len() } else { method.ins_size as usize };
+        let first_in = method_regs.saturating_sub(ins_size);
 
         let mut registers = vec![DexValue::Null; method_regs];
-        if args.len() > 0 {
-            for i in 2..method_regs {
-                registers[i] = args[i - 2].clone();
-            }
+        for (register, value) in registers[first_in..].iter_mut().zip(ins) {
+            *register = value;
         }
 
         self.frames.push(Frame {
             registers,
             temp: None,
-            class_idx,
-            method_name,
+            class_name: class.name.clone(),
+            class,
+            method_key,
             pc: 0,
+            exception: None,
         });
+        true
+    }
+
+    /// Descriptor of the loaded source's main class, which identifies the source to the host
+    pub fn source_name(&self) -> Option<String> {
+        self.parser.classes.get(self.main_idx).map(|class| class.name.clone())
     }
 
     pub fn pop_frame(&mut self) -> Option<Frame> {
@@ -71,6 +216,7 @@ impl Interpreter {
     }
 
     pub fn alloc_object(&mut self, class_name: &str) -> ObjectId {
+        let methods = self.native_methods_of(class_name);
         let id = self.next_object_id;
         self.next_object_id += 1;
         self.heap.insert(
@@ -78,17 +224,161 @@ impl Interpreter {
             Object {
                 class_name: class_name.to_string(),
                 fields: HashMap::new(),
-                methods: HashMap::new()
+                methods,
+                array: None,
             },
         );
         id
     }
 
-    pub fn alloc_object_and_assign(&mut self, class_name: &str, dst: &u8) {
+    /// The java.lang.Class object standing for `descriptor`, created on first use
+    pub fn class_object(&mut self, descriptor: &str) -> ObjectId {
+        if let Some(id) = self.class_objects.get(descriptor) {
+            return *id;
+        }
+
+        let id = self.alloc_object(JAVA_LANG_CLASS);
+        self.heap.get_mut(&id).unwrap().fields.insert(CLASS_DESCRIPTOR_FIELD.to_string(), DexValue::String(descriptor.to_string()));
+        self.class_objects.insert(descriptor.to_string(), id);
+        id
+    }
+
+    /// The loaded class a Class object stands for, `None` for platform classes
+    pub fn class_of(&self, class_object: ObjectId) -> Option<DexClass> {
+        match self.heap.get(&class_object)?.fields.get(CLASS_DESCRIPTOR_FIELD)? {
+            DexValue::String(descriptor) if descriptor.starts_with('L') => self.load_class(descriptor),
+            _ => None,
+        }
+    }
+
+    /// `String.valueOf(Object)`: the text of a string, "null", or what the object's toString() returns
+    pub fn string_value_of(&mut self, value: &DexValue) -> String {
+        const TO_STRING: &str = "toString:()Ljava/lang/String;";
+
+        let id = match value {
+            DexValue::String(text) => return text.clone(),
+            DexValue::Null => return "null".to_string(),
+            DexValue::Boolean(value) => return value.to_string(),
+            DexValue::Char(unit) => return String::from_utf16_lossy(&[*unit]),
+            DexValue::Byte(value) => return value.to_string(),
+            DexValue::Short(value) => return value.to_string(),
+            DexValue::Int(value) => return value.to_string(),
+            DexValue::Long(value) => return value.to_string(),
+            DexValue::Float(value) => return float_to_string(*value),
+            DexValue::Double(value) => return double_to_string(*value),
+            DexValue::Object(id) => *id,
+            other => return format!("{:?}", other),
+        };
+
+        let Some(class_name) = self.heap.get(&id).map(|object| object.class_name.clone()) else {
+            return "null".to_string();
+        };
+        let text = if let Some(method) = platform_method(&class_name, TO_STRING) {
+            Some(method(self, Some(id), Vec::new()))
+        } else if let Some(class) = self.find_virtual_method(&class_name, TO_STRING) {
+            self.invoke_method(class, TO_STRING.to_string(), Some(id), Vec::new())
+        } else if let Some(method) = self.inherited_platform_method(&class_name, TO_STRING) {
+            Some(method(self, Some(id), Vec::new()))
+        } else {
+            let object = self.heap.get_mut(&id).unwrap();
+            object.methods.get(TO_STRING).copied().flatten().map(|method| method(object, Vec::new()))
+        };
+
+        match text {
+            Some(DexValue::String(text)) => text,
+            // Object.toString()
+            _ => format!("{}@{:x}", binary_name(&class_name), id),
+        }
+    }
+
+    /// `Objects.equals(a, b)`: strings by their text, objects through their equals()
+    pub fn values_equal(&mut self, a: &DexValue, b: &DexValue) -> bool {
+        const EQUALS: &str = "equals:(Ljava/lang/Object;)Z";
+
+        let DexValue::Object(id) = a else {
+            return a == b;
+        };
+        if a == b {
+            return true;
+        }
+
+        let Some(class_name) = self.heap.get(id).map(|object| object.class_name.clone()) else {
+            return false;
+        };
+        let equal = if let Some(method) = platform_method(&class_name, EQUALS) {
+            Some(method(self, Some(*id), vec![b.clone()]))
+        } else if let Some(class) = self.find_virtual_method(&class_name, EQUALS) {
+            self.invoke_method(class, EQUALS.to_string(), Some(*id), vec![b.clone()])
+        } else {
+            // Object.equals() is identity, checked above
+            None
+        };
+        equal.and_then(|value| value.as_int()).is_some_and(|value| value != 0)
+    }
+
+    /// `invoke()` of a Kotlin function object, a lambda or a class implementing FunctionN
+    pub fn invoke_function(&mut self, function: &DexValue, args: Vec<DexValue>) -> Option<DexValue> {
+        let DexValue::Object(id) = function else {
+            return None;
+        };
+        if let Some(lambda) = self.lambda_for(Some(*id), "invoke") {
+            return self.invoke_lambda(lambda, args);
+        }
+
+        // The erased bridge every FunctionN has
+        let key = format!("invoke:({})Ljava/lang/Object;", "Ljava/lang/Object;".repeat(args.len()));
+        let class_name = self.heap.get(id)?.class_name.clone();
+        let class = self.find_virtual_method(&class_name, &key)?;
+        self.invoke_method(class, key, Some(*id), args)
+    }
+
+    /// Run the virtual method `method_key` on `receiver` from a platform method, e.g. a method of
+    /// a class the extension declares that a platform class calls back into. `None` when the
+    /// class has no such method or it threw.
+    pub fn invoke_virtual(&mut self, receiver: ObjectId, method_key: &str, args: Vec<DexValue>) -> Option<DexValue> {
+        let method_name = method_key.split(':').next().unwrap_or_default();
+        if let Some(lambda) = self.lambda_for(Some(receiver), method_name) {
+            return self.invoke_lambda(lambda, args);
+        }
+        let class_name = self.heap.get(&receiver)?.class_name.clone();
+        if let Some(method) = platform_method(&class_name, method_key) {
+            return Some(method(self, Some(receiver), args));
+        }
+        if let Some(class) = self.find_virtual_method(&class_name, method_key) {
+            return self.invoke_method(class, method_key.to_string(), Some(receiver), args);
+        }
+        let method = self.inherited_platform_method(&class_name, method_key)?;
+        Some(method(self, Some(receiver), args))
+    }
+
+    /// Native methods available on instances of `class_name`, including inherited ones
+    fn native_methods_of(&self, class_name: &str) -> HashMap<String, Option<NativeMethod>> {
+        let mut methods = HashMap::new();
+        let mut current = Some(class_name.to_string());
+
+        while let Some(name) = current.filter(|name| name != "Ljava/lang/Object;") {
+            for (key, method) in native_methods(&name) {
+                methods.entry(key).or_insert(method);
+            }
+            current = self.super_class_of(&name);
+        }
+        methods
+    }
+
+    /// Allocate an array of `length` zeroed elements, `type_name` is the array descriptor (e.g. `[I`)
+    pub fn alloc_array(&mut self, type_name: &str, length: usize) -> ObjectId {
+        let default = DexValue::default_for(type_name.strip_prefix('[').unwrap_or(""));
+
+        let id = self.alloc_object(type_name);
+        self.heap.get_mut(&id).unwrap().array = Some(vec![default; length]);
+        id
+    }
+
+    pub fn alloc_object_and_assign(&mut self, class_name: &str, dst: u16) {
         let id = self.alloc_object(class_name);
 
         if let Some(frame) = self.frames.last_mut(){
-            frame.registers[*dst as usize] = DexValue::Object(id);
+            frame.registers[dst as usize] = DexValue::Object(id);
         }
     }
 
@@ -110,390 +400,2654 @@ impl Interpreter {
         return None
     }
 
-    /// call a method by pointing to its owner class index and name
+    /// call a method by pointing to its owner class index and name, `receiver` is `this` for instance methods
     pub fn call_method(
         &mut self,
         class_idx: usize,
         method_name: &str,
-        args: Vec<DexValue>,
-    ) -> Option<DexValue> {
-        self.push_frame(class_idx, method_name.to_string(), args);
-        self.run(class_idx)
-    }
-
-    /// Main execution loop. Returns an optional DexValue if top-level method returned a value.
-    pub fn run(&mut self, class_idx: usize) -> Option<DexValue> {
-        let class = self.parser.classes[class_idx].clone();
-        
-        self.run_with_class(&class, class_idx)
-    }
-
-    pub fn run_with_class(&mut self, class: &DexClass, class_idx: usize) -> Option<DexValue> {
-        let mut return_value: Option<DexValue> = None;
+        receiver: Option<ObjectId>,
+        mut args: Vec<DexValue>,
+    ) -> Result<Option<DexValue>, InterpreterError> {
+        let class_name = self.parser.classes[class_idx].name.clone();
+        if !self.initialize_class(&class_name) {
+            let pending = self.pending_exception.take().unwrap();
+            return Err(self.uncaught_exception(pending));
+        }
 
-        while let Some(frame) = self.frames.last_mut() {
-            let method = class.methods
-                .get(&frame.method_name);
-            if let Some(method) = method {
-                if frame.pc == 0 {
-                    if frame.registers.len() < 2 {
-                        frame.registers[0] = DexValue::Object(1);
-                    } else {
-                        frame.registers[1] = DexValue::Object(1);
-                    }
-                }
-                if frame.pc >= method.instructions.len() {
-                    self.pop_frame();
-                    continue;
-                }
-                let instr = &method.instructions[frame.pc].clone();
-                frame.pc += 1;
-                     
-                let value = self.execute(instr, class_idx);
+        // A suspend function gets a completion to wait on in place of the caller's continuation
+        let argc = args.iter().filter(|arg| **arg != DexValue::WideHigh).count();
+        let completion = coroutines::is_suspend_call(&self.parser.classes[class_idx], method_name, argc)
+            .then(|| coroutines::new_completion(self));
+        args.extend(completion.map(DexValue::Object));
 
-                if let Some(value) = value {
-                    return_value = Some(value);
-                    self.pop_frame();
-                    break
-                }
+        let mut value = None;
+        if self.push_frame(class_idx, method_name.to_string(), receiver, args) {
+            value = self.run();
+        }
+        if let Some(completion) = completion && self.pending_exception.is_none() {
+            value = coroutines::complete(self, completion, value);
+        }
+        self.check_generation()?;
+
+        match self.pending_exception.take() {
+            Some(pending) => {
+                self.frames.clear();
+                self.resumptions.clear();
+                self.monitors.clear();
+                Err(self.uncaught_exception(pending))
             }
+            None => Ok(value),
         }
-        return_value
     }
 
-    /// Execute one instruction with mutable access to interpreter (heap, frames, etc.)
-    fn execute(&mut self, instr: &Instruction, class_idx: usize) -> Option<DexValue> {
-        let frame = self.frames.last_mut().unwrap();
+    /// Call `method_name` on `receiver`, dispatching on its runtime class like invoke-virtual
+    pub fn call_virtual(
+        &mut self,
+        receiver: ObjectId,
+        method_name: &str,
+        args: Vec<DexValue>,
+    ) -> Result<Option<DexValue>, InterpreterError> {
+        let class_name = self.heap.get(&receiver).map(|object| object.class_name.clone()).unwrap_or_default();
+
+        let value = if let Some(lambda) = self.lambda_for(Some(receiver), method_name) {
+            self.invoke_lambda(lambda, args)
+        } else if let Some(class) = self.find_virtual_method(&class_name, method_name) {
+            self.invoke_method(class, method_name.to_string(), Some(receiver), args)
+        } else {
+            // Without a method_id the descriptor is unknown, match the native method by name
+            let prefix = format!("{}:", method_name);
+            let object = self.heap.get_mut(&receiver).unwrap();
+            let method = object.methods.iter()
+                .find(|(key, _)| key.starts_with(&prefix))
+                .and_then(|(_, method)| *method);
+            method.map(|method| method(object, args))
+        };
+        self.check_generation()?;
 
-        match instr {
-            Instruction::ConstString { dest, string_idx } => {
-                interpreter_log!(self, "String_idx {:?}", &string_idx);
-                let s = &self.parser.strings[*string_idx as usize];
-                interpreter_log!(self, "Storing {:?} into v{}", &s, &dest);
-                frame.registers[*dest as usize] = DexValue::String(s.to_string());
-                interpreter_log!(self, "registers -> {:?}", &frame.registers);
+        match self.pending_exception.take() {
+            Some(pending) => {
+                self.frames.clear();
+                Err(self.uncaught_exception(pending))
             }
+            None => Ok(value.filter(|value| *value != DexValue::Void)),
+        }
+    }
 
-            Instruction::Const4Bit { dst, signed_int } => {
-                interpreter_log!(self, "Signed int: {} -> v{}", signed_int, dst);
-                frame.registers[*dst as usize] = DexValue::Int(*signed_int as i32);
+    /// Fail the call if it ran into classes of another install, whatever it computed is suspect
+    fn check_generation(&mut self) -> Result<(), InterpreterError> {
+        match self.generation_mismatch.take() {
+            Some(error) => {
+                self.frames.clear();
+                self.pending_exception = None;
+                Err(error)
             }
+            None => Ok(()),
+        }
+    }
 
-            Instruction::MoveObject { dst, src } => {
-                interpreter_log!(self, "Moving Object {:?} from v{} -> v{}", frame.registers[*src as usize].clone(), src, dst);
-                frame.registers[*dst as usize] = frame.registers[*src as usize].clone();
-            }
+    fn uncaught_exception(&mut self, pending: PendingException) -> InterpreterError {
+        let class_name = match &pending.exception {
+            DexValue::Object(id) => self.heap.get(id).map(|object| object.class_name.clone()),
+            _ => None,
+        }.unwrap_or_else(|| THROWABLE.to_string());
+        let message = self.exception_message(&pending.exception);
+
+        InterpreterError::UncaughtException {
+            class_name,
+            message,
+            stack_trace: pending.stack_trace,
+        }
+    }
 
-            Instruction::MoveResultObject { dst } => {
-                interpreter_log!(self, "Moving Result Object {:?} -> v{}", &frame.temp, dst);
-                if let Some(temp) = &frame.temp {
-                    frame.registers[*dst as usize] = temp.clone();
-                    frame.temp = None;
-                }
-                interpreter_log!(self, "registers -> {:?}", &frame.registers);
-            }
+    /// Detail message of an exception, from its getMessage() if the extension overrides it
+    pub fn exception_message(&mut self, exception: &DexValue) -> Option<String> {
+        const GET_MESSAGE: &str = "getMessage:()Ljava/lang/String;";
 
-            Instruction::MoveResult { dst } => {
-                if let Some(temp) = &frame.temp {
-                    frame.registers[*dst as usize] = temp.clone();
-                    frame.temp = None;
-                }
+        let DexValue::Object(id) = exception else {
+            return None;
+        };
+        let object = self.heap.get(id)?;
+        let message = match self.find_virtual_method(&object.class_name.clone(), GET_MESSAGE) {
+            Some(class) => {
+                let message = self.invoke_method(class, GET_MESSAGE.to_string(), Some(*id), Vec::new());
+                // A getMessage() that throws has no message to offer
+                self.pending_exception.take().map_or(message, |_| None)
             }
+            None => object.fields.get(MESSAGE_FIELD).cloned(),
+        };
+        match message {
+            Some(DexValue::String(message)) => Some(message),
+            _ => None,
+        }
+    }
 
-            Instruction::InvokeStatic {
-                args, method_idx, argc
-            } => {
-                interpreter_log!(self, "Registers -> {:?}", &frame.registers);
-                if args.len() < 2 {
-                    return None
-                }
-                let first = &frame.registers[args[0] as usize];
-                let second = &frame.registers[args[1] as usize];
-
-                interpreter_log!(self, "First: {:?}, Second: {:?}", first, second);
-                let method_name_idx = self.parser.container.clone().unwrap().method_id_to_string_offset(*method_idx as usize);
-
-                if let Some(method_name_idx) = method_name_idx {
-                    let method_name = parse_string_at_offset(&self.parser.data, method_name_idx, &self.parser.container.clone().unwrap().header_item, 0).1;
-                    let class_name_idx = self.parser.container.clone().unwrap().method_id_to_class_string_offset(*method_idx as usize);
-                    let class_name = get_name_of_class(class_name_idx, &self.parser.data, &self.parser.container.clone().unwrap().header_item, &self.parser.container.clone().unwrap().type_id_items, &self.parser.container.clone().unwrap().string_id_items);
-                    interpreter_log!(self, "InvokeStatic -> {}{}", class_name, method_name);
-                    
-                    match method_name.as_str() {
-                        "areEqual" => {
-                            assert!(args.len() == 2, "areEqual requires 2 parameters");
-                            let first = &frame.registers[args[0] as usize];
-                            let second = &frame.registers[args[1] as usize];
-
-                            interpreter_log!(self, "First: {:?}, Second: {:?}", first, second);
-                            assert!(first == second, "Parameter {:?} is not equal to Parameter {:?}", first, second);
-                            frame.temp = Some(DexValue::Boolean(first == second));
-
-                        } 
-                        "checkNotNullParameter" => {
-                            let parameter = &frame.registers[args[1] as usize];
-                            assert!(*parameter != DexValue::Null, "NullPointerException: parameter {:?} expected SOMETHING but found null", frame.registers[args[0] as usize]);
-                        }
-                        _ => {
+    /// Methods on the stack, innermost first, written `com.example.Source.method(descriptor)`
+    /// followed by ` (Source.kt:42)` when the class names its source file or has line numbers
+    pub fn stack_trace(&self) -> Vec<String> {
+        self.frames.iter()
+            .rev()
+            .map(|frame| {
+                // The pc has moved past the running instruction, an invoke in the frames below
+                let line = frame.class.methods.get(&frame.method_key).and_then(|method| {
+                    let address = *method.addresses.get(frame.pc.saturating_sub(1))?;
+                    method.line_at(address)
+                });
+                let location = match (&frame.class.source_file, line) {
+                    (Some(file), Some(line)) => format!(" ({}:{})", file, line),
+                    (Some(file), None) => format!(" ({})", file),
+                    (None, Some(line)) => format!(" (Unknown Source:{})", line),
+                    (None, None) => String::new(),
+                };
+                format!("{}.{}{}", binary_name(&frame.class_name), frame.method_key.replacen(':', "", 1), location)
+            })
+            .collect()
+    }
 
-                        }
-                    }
-                }
-            }
-            Instruction::InvokeSuper {
-                args, method_idx, ..
-            } => {
-                let method_name_idx = self.parser.container.clone().unwrap().method_id_to_string_offset(*method_idx as usize);
+    /// Start unwinding the stack with `exception`. Exceptions keep the stack trace recorded when
+    /// they were created, or else when first thrown.
+    pub fn throw(&mut self, exception: DexValue) {
+        interpreter_log!(self, "Throwing {:?}", &exception);
+        let object = match &exception {
+            DexValue::Object(id) => self.heap.get(id),
+            _ => None,
+        };
 
-                if let Some(method_name_idx) = method_name_idx {
-                    let method_name = parse_string_at_offset(&self.parser.data, method_name_idx, &self.parser.container.clone().unwrap().header_item, 0).1;
-                    let class_name_idx = self.parser.container.clone().unwrap().method_id_to_class_string_offset(*method_idx as usize);
-                    let class_name = get_name_of_class(class_name_idx, &self.parser.data, &self.parser.container.clone().unwrap().header_item, &self.parser.container.clone().unwrap().type_id_items, &self.parser.container.clone().unwrap().string_id_items);
-                    interpreter_log!(self, "InvokeSuper -> {}{}. Skipping for now.", class_name, method_name);
-                }
-            }
-            Instruction::InvokeInterface {
-                args, method_idx, ..
-            } => {
-                let method_name_idx = self.parser.container.clone().unwrap().method_id_to_string_offset(*method_idx as usize);
-
-                if let Some(method_name_idx) = method_name_idx {
-                    let method_name = parse_string_at_offset(&self.parser.data, method_name_idx, &self.parser.container.clone().unwrap().header_item, 0).1;
-                    let parameters = "()";
-                    let return_value = "Ljava/lang/String;";
-                    let class_name_idx = self.parser.container.clone().unwrap().method_id_to_class_string_offset(*method_idx as usize);
-                    let class_name = get_name_of_class(class_name_idx, &self.parser.data, &self.parser.container.clone().unwrap().header_item, &self.parser.container.clone().unwrap().type_id_items, &self.parser.container.clone().unwrap().string_id_items);
-                    interpreter_log!(self, "Registers -> {:?}", &frame.registers);
-                    interpreter_log!(self, "InvokeInterface -> {}{}.", class_name, method_name);
-
-                    // find method in register objects
-                    let mut object = self.heap.get(&1).unwrap().clone();
-                    interpreter_log!(self, "Object -> {:?}", object);
-                    for (_, field) in object.fields.clone() {
-                        match field {
-                            DexValue::Object(id) => {
-                                interpreter_log!(self, "Object found. -> {}", id);
-                                let ob = self.heap.get(&id).unwrap();
-                                interpreter_log!(self, "Object -> {:?}", &ob);
-
-                                if let Some(method) = ob.methods.get(&format!("{}:{}{}", &method_name, parameters, return_value)) {
-                                    interpreter_log!(self, "Method found.");
-
-                                    // check if GlobalRef exists with the needed method
-                                    let signature = format!("{}{}{}", &method_name, &parameters, &return_value);
-                                    for global_ref in self.object_refs.clone() {
-                                        let obj = global_ref.as_obj(); // Get JObject
-                                        
-                                        if has_method(obj, &signature) {
-                                            rust_log("Found it");
-                                            let ret_value = call_method(obj, &method_name, &format!("{}{}", &parameters, &return_value).to_string(), &[]);
-                                            interpreter_log!(self, "ret -> {:?}", &ret_value);
-                                            frame.temp = Some(ret_value);
-                                        }
-                                    }
-
-                                    if let Some(method) = method {
-                                        let ret_value = method(&mut object, Vec::new());
-                                        interpreter_log!(self, "Native Function -> {:?}", &ret_value);
-                                        frame.temp = Some(ret_value);
-                                        interpreter_log!(self, "registers -> {:?}", &frame.registers);
-                                    }
-                                }
-                            }
-                            _ => {
-
-                            }
-                        }
-                    }
+        let stack_trace = match object.and_then(|object| object.fields.get(STACK_TRACE_FIELD)) {
+            Some(DexValue::Array(trace)) => trace.iter()
+                .filter_map(|method| match method {
+                    DexValue::String(method) => Some(method.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => {
+                let stack_trace = self.stack_trace();
+                let object = match &exception {
+                    DexValue::Object(id) => self.heap.get_mut(id),
+                    _ => None,
+                };
+                if let Some(object) = object {
+                    let trace = stack_trace.iter().cloned().map(DexValue::String).collect();
+                    object.fields.insert(STACK_TRACE_FIELD.to_string(), DexValue::Array(trace));
                 }
+                stack_trace
             }
-            Instruction::InvokeDirect {
-                args, method_idx, ..
-            } => {
-                interpreter_log!(self, "Starting InvokeDirect");
-                let method_name_idx = self.parser.container.clone().unwrap().method_id_to_string_offset(*method_idx as usize);
+        };
 
-                if let Some(method_name_idx) = method_name_idx {
-                    let method_name = parse_string_at_offset(&self.parser.data, method_name_idx, &self.parser.container.clone().unwrap().header_item, 0).1;
-                    let class_name_idx = self.parser.container.clone().unwrap().method_id_to_class_string_offset(*method_idx as usize);
-                    let class_name = get_name_of_class(class_name_idx, &self.parser.data, &self.parser.container.clone().unwrap().header_item, &self.parser.container.clone().unwrap().type_id_items, &self.parser.container.clone().unwrap().string_id_items);
-                    
-                    // let object_id = self.alloc_object(class_name.clone().as_str());
+        self.pending_exception = Some(PendingException { exception, stack_trace });
+    }
 
-                    // out/{class_name}.json
-                    interpreter_log!(self, "Loading {}", &class_name);
-                    if class_name.contains("Ljava/lang/") {
-                        return None
-                    }
-                    let loaded_class = class_file_to_class(&class_name);
+    /// Allocate a `class_name` exception carrying `message` and start unwinding with it
+    pub fn throw_new(&mut self, class_name: &str, message: &str) {
+        let id = self.alloc_object(class_name);
+        self.heap.get_mut(&id).unwrap()
+            .fields
+            .insert(MESSAGE_FIELD.to_string(), DexValue::String(message.to_string()));
+        self.throw(DexValue::Object(id));
+    }
 
-                    if let Some(loaded_class) = loaded_class {
-                        interpreter_log!(self, "Calling {}", &method_name);
-                        let mut call_args = Vec::new();
-                        for arg in args {
-                            call_args.push(frame.registers[*arg as usize].clone());
-                        }
+    /// KClass is represented by the Class object itself, converting between the two is a no-op
+    fn invoke_kotlin_reflection(&self, method_name: &str, args: &[DexValue]) -> Option<DexValue> {
+        match method_name {
+            "getOrCreateKotlinClass" | "getJavaClass" | "getJavaObjectType" | "getKotlinClass" => args.first().cloned(),
+            _ => None,
+        }
+    }
 
-                        self.push_frame_with_class(&loaded_class, class_name_idx, method_name, call_args);
-                        let ret_value = self.run_with_class(&loaded_class, class_name_idx);
-                        interpreter_log!(self, "Finished InvokeDirect");
-                        return None;
-                    }
-                    
-                }
+    /// Run the `kotlin.jvm.internal.Intrinsics` helpers the Kotlin compiler emits, failed checks
+    /// are thrown as exceptions the extension can catch
+    fn invoke_kotlin_intrinsic(&mut self, method_key: &str, args: &[DexValue]) {
+        // Wide arguments come with their upper half, which none of the helpers need
+        let args: Vec<DexValue> = args.iter().filter(|arg| **arg != DexValue::WideHigh).cloned().collect();
+        // Registers are untyped, `const/4 vX, #0` is how null gets loaded
+        let is_null = |index: usize| matches!(args.get(index), Some(DexValue::Null | DexValue::Int(0)) | None);
+        let string_arg = |index: usize| match args.get(index) {
+            Some(DexValue::String(string)) => string.clone(),
+            _ => String::new(),
+        };
+        let method_name = method_name_of(method_key);
+        let parameters = method_key.split_once('(')
+            .and_then(|(_, rest)| rest.split_once(')'))
+            .map(|(parameters, _)| parameters)
+            .unwrap_or("");
+
+        match method_name {
+            // The Double and Float overloads compare by value like ==, NaN is unequal to itself
+            "areEqual" if parameters != "Ljava/lang/Object;Ljava/lang/Object;" => {
+                let first = self.intrinsic_number(parameters.starts_with(['D', 'F']).then(|| &parameters[..1]), &args, 0);
+                let second = self.intrinsic_number(parameters.ends_with(['D', 'F']).then(|| &parameters[parameters.len() - 1..]), &args, 1);
+                self.set_result(Some(DexValue::Boolean(first == second)));
             }
-            Instruction::InvokeVirtual {
-                args, method_idx, ..
-            } => {
-                let method_name_idx = self.parser.container.clone().unwrap().method_id_to_string_offset(*method_idx as usize);
-
-                if let Some(method_name_idx) = method_name_idx {
-                    let method_name = parse_string_at_offset(&self.parser.data, method_name_idx, &self.parser.container.clone().unwrap().header_item, 0).1;
-                    let class_name_idx = self.parser.container.clone().unwrap().method_id_to_class_string_offset(*method_idx as usize);
-                    let class_name = get_name_of_class(class_name_idx, &self.parser.data, &self.parser.container.clone().unwrap().header_item, &self.parser.container.clone().unwrap().type_id_items, &self.parser.container.clone().unwrap().string_id_items);
-                    interpreter_log!(self, "InvokeVirtual -> {}{}. Skipping for now.", class_name, method_name);
-
-                    // check if current class has method
-                    match self.parser.classes[class_idx].methods.get(&class_name) {
-                        Some(method) => {
-                            interpreter_log!(self, "Method found in class. Executing now.");
-                        }
-                        None => {
-                            interpreter_log!(self, "Method not found in class. Checking super class.");
-
-                            let super_class = self.parser.classes[class_idx].super_class.clone().unwrap();
-                            let loaded_class = class_file_to_class(&super_class);
-
-                            let mut ret_value: Option<DexValue> = None;
-                            if let Some(loaded_class) = loaded_class {
-                                interpreter_log!(self, "Loaded class.");
-                                interpreter_log!(self, "Calling {}", &method_name);
-                                let mut call_args = Vec::new();
-                                for arg in args {
-                                    call_args.push(frame.registers[*arg as usize].clone());
-                                }
-
-                                self.push_frame_with_class(&loaded_class, class_name_idx, method_name, call_args);
-                                ret_value = self.run_with_class(&loaded_class, class_name_idx);
-                                interpreter_log!(self, "Finished InvokeDirect -> {:?}", ret_value);
-                                return None;
-                            }
-                            frame.temp = ret_value.clone();
-                        }
-                    }
-                }
+            "areEqual" => {
+                let equal = match (is_null(0), is_null(1)) {
+                    (true, true) => true,
+                    (false, false) => self.values_equal(&args[0], &args[1]),
+                    _ => false,
+                };
+                self.set_result(Some(DexValue::Boolean(equal)));
             }
-
-            Instruction::IPutObject {
-                src,
-                obj,
-                instance_field_idx,
-            } => {
-                interpreter_log!(self, "Instance field -> {:02X}", instance_field_idx);
-                interpreter_log!(self, "Registers -> {:?}", frame.registers);
-                if let DexValue::Object(obj_id) = &frame.registers[*obj as usize] {
-
-                    if let Some(object) = self.heap.get_mut(obj_id) {
-                        let value = frame.registers[*src as usize].clone();
-                        object
-                            .fields
-                            .insert(format!("field@{}", instance_field_idx), value.clone());
-                        interpreter_log!(self, "IPutObject: Object {:?}, field@{} -> {:?}", object, instance_field_idx, value)
+            "compare" => {
+                let ordering = match (args.first(), args.get(1)) {
+                    (Some(DexValue::Long(a)), Some(DexValue::Long(b))) => a.cmp(b),
+                    (a, b) => {
+                        let int = |value: Option<&DexValue>| value.and_then(|value| value.as_int()).unwrap_or(0);
+                        int(a).cmp(&int(b))
                     }
-                }
+                };
+                self.set_result(Some(DexValue::Int(ordering as i32)));
             }
-
-            Instruction::IPutBoolean {
-                src,
-                obj,
-                instance_field_idx,
-            } => {
-                interpreter_log!(self, "Instance field -> {:02X}", instance_field_idx);
-                if let DexValue::Object(obj_id) = &frame.registers[*obj as usize] {
-
-                    if let Some(object) = self.heap.get_mut(obj_id) {
-                        let value = frame.registers[*src as usize].clone();
-                        object
-                            .fields
-                            .insert(format!("field@{}", instance_field_idx), value.clone().to_boolean().unwrap());
-                        interpreter_log!(self, "IPutBoolean: Object {:?}, field@{} -> {:?}", object, instance_field_idx, value)
-                    }
-                }
+            "stringPlus" => {
+                let first = self.string_value_of(args.first().unwrap_or(&DexValue::Null));
+                let second = self.string_value_of(args.get(1).unwrap_or(&DexValue::Null));
+                self.set_result(Some(DexValue::String(first + &second)));
             }
-
-            Instruction::IGetObject { src, obj, instance_field_idx } => {
-                if let DexValue::Object(obj_id) = &frame.registers[*obj as usize] {
-                    if let Some(object) = self.heap.get_mut(obj_id) {
-                        frame.registers[*src as usize] = object.fields[format!("field@{}", instance_field_idx).as_str()].clone();
-                        interpreter_log!(self, "IGetObject: Object {:?}, field@{}", object, instance_field_idx)
-                    }
-                }
+            "checkNotNullParameter" | "checkParameterIsNotNull" if is_null(0) => {
+                let caller = self.frames.last()
+                    .map(|frame| format!("{}.{}", frame.class_name, method_name_of(&frame.method_key)))
+                    .unwrap_or_default();
+                let message = format!("Parameter specified as non-null is null: method {}, parameter {}", caller, string_arg(1));
+                self.throw_new(KOTLIN_NULL_POINTER_EXCEPTION, &message);
             }
-
-            Instruction::IGetBoolean { src, obj, instance_field_idx } => {
-                if let DexValue::Object(obj_id) = &frame.registers[*obj as usize] {
-                    if let Some(object) = self.heap.get_mut(obj_id) {
-                        interpreter_log!(self, "{:?}", object.fields);
-                        frame.registers[*src as usize] = object.fields[format!("field@{}", instance_field_idx).as_str()].clone();
-                        interpreter_log!(self, "IGetBoolean: Object {:?}, field@{}", object, instance_field_idx)
-                    }
-                }
+            "checkNotNullExpressionValue" | "checkExpressionValueIsNotNull" if is_null(0) => {
+                self.throw_new(KOTLIN_NULL_POINTER_EXCEPTION, &format!("{} must not be null", string_arg(1)));
             }
-
-            Instruction::NewInstance { dst, type_idx } => {
-                let string_idx = self.parser
-                    .container
-                    .clone()
-                    .unwrap()
-                    .type_to_string_id(*type_idx as usize)
-                    .unwrap_or(0);
-                
-                if let Some(type_name) = self.parser.strings.get(string_idx) {
-                    let type_name = type_name.clone();
-                    interpreter_log!(self, "NewInstance: Type name -> {}", type_name);
-                    self.alloc_object_and_assign(&type_name, dst);
-                }
+            "checkNotNull" if is_null(0) => {
+                self.throw_new(KOTLIN_NULL_POINTER_EXCEPTION, &string_arg(1));
+            }
+            "checkReturnedValueIsNotNull" if is_null(0) => {
+                let message = format!("Method specified as non-null returned null: {}.{}", string_arg(1), string_arg(2));
+                self.throw_new(ILLEGAL_STATE_EXCEPTION, &message);
+            }
+            "checkFieldIsNotNull" if is_null(0) => {
+                let message = format!("Field specified as non-null is null: {}.{}", string_arg(1), string_arg(2));
+                self.throw_new(ILLEGAL_STATE_EXCEPTION, &message);
+            }
+            "throwNpe" | "throwJavaNpe" => {
+                self.throw_new(KOTLIN_NULL_POINTER_EXCEPTION, &string_arg(0));
+            }
+            "throwParameterIsNullNPE" => {
+                let message = format!("Parameter specified as non-null is null: {}", string_arg(0));
+                self.throw_new(NULL_POINTER_EXCEPTION, &message);
+            }
+            "throwParameterIsNullIAE" => {
+                let message = format!("Parameter specified as non-null is null: {}", string_arg(0));
+                self.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &message);
+            }
+            "throwUninitializedPropertyAccessException" => {
+                let message = format!("lateinit property {} has not been initialized", string_arg(0));
+                self.throw_new(UNINITIALIZED_PROPERTY_ACCESS_EXCEPTION, &message);
             }
+            "throwUninitializedProperty" => {
+                self.throw_new(UNINITIALIZED_PROPERTY_ACCESS_EXCEPTION, &string_arg(0));
+            }
+            "throwIllegalArgument" => {
+                self.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &string_arg(0));
+            }
+            "throwIllegalState" => {
+                self.throw_new(ILLEGAL_STATE_EXCEPTION, &string_arg(0));
+            }
+            "throwAssert" => {
+                self.throw_new(ASSERTION_ERROR, &string_arg(0));
+            }
+            "throwUndefinedForReified" => {
+                let message = "This function has a reified type parameter and thus can only be inlined at compilation time, not called directly.";
+                self.throw_new(UNSUPPORTED_OPERATION_EXCEPTION, message);
+            }
+            // Returns its argument, the stack traces it would trim are ours
+            "sanitizeStackTrace" => {
+                self.set_result(args.first().cloned());
+            }
+            _ => {
+                // Passed checks, and markers such as reifiedOperationMarker that only guide the compiler
+                interpreter_log!(self, "Intrinsics.{} passed or is not supported", method_name);
+            }
+        }
+    }
 
-            Instruction::CheckCast { ref_bearing_reg, type_idx } => {
-                interpreter_log!(self, "Starting CheckCast. Registers -> {:?}", &frame.registers);
-                let string_idx = self.parser.container.clone().unwrap().type_to_string_id(*type_idx as usize).unwrap_or(0);
-                let type_name = self.parser.strings.get(string_idx);
+    /// Argument `index` of an areEqual overload as a number, `None` for null. `primitive` is the
+    /// descriptor of a primitive parameter, boxed parameters are unboxed.
+    fn intrinsic_number(&self, primitive: Option<&str>, args: &[DexValue], index: usize) -> Option<f64> {
+        let value = args.get(index)?;
+        match primitive {
+            Some("D") => value.as_double(),
+            Some(_) => value.as_float().map(f64::from),
+            None => match boxing::unboxed(&self.heap, value)? {
+                DexValue::Double(value) => Some(value),
+                DexValue::Float(value) => Some(value as f64),
+                value => value.as_int().map(f64::from),
+            },
+        }
+    }
 
-                if let Some(type_name) = type_name {
-                    // TODO: Do proper cast check
-                    interpreter_log!(self, "CheckCast: Type name -> {}", type_name);
-                }
-            }
+    /// Resolve `array_reg[index_reg]` of the current frame, throwing like ART does when the
+    /// array is null or the index is out of bounds
+    fn array_index(&mut self, array_reg: u8, index_reg: u8, null_message: &str) -> Option<(ObjectId, usize)> {
+        let frame = self.frames.last().unwrap();
+        let index = frame.registers[index_reg as usize].as_int().unwrap_or(0);
+        let length = match &frame.registers[array_reg as usize] {
+            DexValue::Object(id) => self.heap.get(id)
+                .and_then(|object| object.array.as_ref())
+                .map(|array| (*id, array.len())),
+            _ => None,
+        };
 
-            Instruction::ReturnVoid => {
-                interpreter_log!(self, "-----------------------------------------------------------------");
-                interpreter_log!(self, "Registers -> {:?}", frame.registers);
+        let Some((id, length)) = length else {
+            self.throw_new(NULL_POINTER_EXCEPTION, null_message);
+            return None;
+        };
+        if index < 0 || index as usize >= length {
+            self.throw_new(ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, &format!("length={}; index={}", length, index));
+            return None;
+        }
+        Some((id, index as usize))
+    }
 
-                return Some(DexValue::Void)
-            }
+    /// Find a class by descriptor in the loaded dex or the class cache, arrays and primitives
+    /// have no class file. Cached classes of another install generation are refused.
+    fn load_class(&self, class_name: &str) -> Option<DexClass> {
+        if let Some(class) = self.parser.classes.iter().find(|class| class.name == class_name) {
+            return Some(class.clone());
+        }
 
-            Instruction::ReturnObject { src } => {
-                interpreter_log!(self, "-----------------------------------------------------------------");
-                interpreter_log!(self, "Registers -> {:?}", frame.registers);
+        let class = class_name.starts_with('L').then(|| class_file_to_class(&self.parser.cache_dir, class_name)).flatten()?;
+        // Parsers that never went through the cache, like the self test's, belong to no generation
+        if self.parser.persist && class.generation != self.parser.generation {
+            interpreter_log!(self, "Refusing {} of install generation {}, running {}", class_name, class.generation, self.parser.generation);
+            self.generation_mismatch.replace(Some(InterpreterError::GenerationMismatch {
+                class_name: class_name.to_string(),
+                expected: self.parser.generation,
+                found: class.generation,
+            }));
+            return None;
+        }
+        Some(class)
+    }
 
-                return Some(frame.registers[*src as usize].clone())
+    /// Initialize `class_name` on its first active use: seed its static fields, initialize its
+    /// superclass and run its `<clinit>`, once. Returns false when the class can't be used, an
+    /// ExceptionInInitializerError or NoClassDefFoundError is pending then.
+    pub fn initialize_class(&mut self, class_name: &str) -> bool {
+        match self.class_states.get(class_name) {
+            // Uses from the class's own <clinit>, or one it triggered, go ahead like on the JVM
+            Some(ClassState::Initialized | ClassState::Initializing) => return true,
+            Some(ClassState::Erroneous) => {
+                self.throw_new(NO_CLASS_DEF_FOUND_ERROR, &format!("Could not initialize class {}", class_name));
+                return false;
             }
+            None => {}
+        }
 
-            Instruction::Return { reg } => {
-                interpreter_log!(self, "-----------------------------------------------------------------");
-                interpreter_log!(self, "Registers -> {:?}", frame.registers);
-                interpreter_log!(self, "Return Register: v{} -> {:?}", *reg, frame.registers);
-                
+        let Some(class) = self.load_class(class_name) else {
+            // Classes we don't have, e.g. from the platform, just get an empty table
+            self.statics.insert(class_name.to_string(), HashMap::new());
+            let statics = match class_name {
+                date::LOCALE => date::locale_statics(self),
+                date::TIME_UNIT => date::time_unit_statics(self),
+                charset::STANDARD_CHARSETS | charset::KOTLIN_CHARSETS => charset::charset_statics(self),
+                coroutines::COROUTINE_SINGLETONS => coroutines::coroutine_singletons_statics(self),
+                threads::THREAD => threads::thread_statics(self),
+                json::JSON_OBJECT => json::json_object_statics(self),
+                serialization::JSON => serialization::json_statics(self),
+                _ if serialization::is_serializer_object(class_name) => serialization::serializer_statics(self, class_name),
+                _ if http::has_companion(class_name) => http::companion_statics(self, class_name),
+                _ => HashMap::new(),
+            };
+            self.statics.insert(class_name.to_string(), statics);
+            self.class_states.insert(class_name.to_string(), ClassState::Initialized);
+            return true;
+        };
 
-                return Some(frame.registers[*reg as usize].clone())
-            }
+        let fields = class.static_fields.iter()
+            .map(|(name, field)| (name.clone(), field.value.clone().unwrap_or_else(|| DexValue::default_for(&field.ty))))
+            .collect();
+        // Registered before anything runs so recursive accesses find the fields
+        self.statics.insert(class_name.to_string(), fields);
+        self.class_states.insert(class_name.to_string(), ClassState::Initializing);
+
+        // A companion's <clinit> may read what its parent's set up
+        if let Some(super_class) = &class.super_class && !self.initialize_class(super_class) {
+            self.class_states.insert(class_name.to_string(), ClassState::Erroneous);
+            return false;
+        }
 
-            _ => {
-                interpreter_log!(self, "Unimplemented instruction: {:?}", instr);
-            }
+        if class.resolve_method_key("<clinit>", None).is_some() {
+            interpreter_log!(self, "Running <clinit> of {}", class_name);
+            self.invoke_method(class, "<clinit>".to_string(), None, Vec::new());
         }
 
-        None
+        if let Some(pending) = self.pending_exception.take() {
+            interpreter_log!(self, "<clinit> of {} threw {:?}", class_name, pending.exception);
+            self.class_states.insert(class_name.to_string(), ClassState::Erroneous);
+            self.throw_initializer_error(pending);
+            return false;
+        }
+        self.class_states.insert(class_name.to_string(), ClassState::Initialized);
+        true
+    }
+
+    /// Rethrow what a `<clinit>` threw, exceptions get wrapped into an ExceptionInInitializerError
+    fn throw_initializer_error(&mut self, pending: PendingException) {
+        let object = match &pending.exception {
+            DexValue::Object(id) => self.heap.get(id),
+            _ => None,
+        };
+        let cause_class = object
+            .map(|object| object.class_name.clone())
+            .unwrap_or_else(|| "Ljava/lang/Throwable;".to_string());
+        let message = match object.and_then(|object| object.fields.get(MESSAGE_FIELD)) {
+            Some(DexValue::String(message)) => format!("{}: {}", cause_class, message),
+            _ => cause_class.clone(),
+        };
+
+        if self.is_subclass_of(&cause_class, "Ljava/lang/Error;") {
+            self.pending_exception = Some(pending);
+            return;
+        }
+
+        let id = self.alloc_object(EXCEPTION_IN_INITIALIZER_ERROR);
+        let fields = &mut self.heap.get_mut(&id).unwrap().fields;
+        fields.insert(MESSAGE_FIELD.to_string(), DexValue::String(message));
+        fields.insert(CAUSE_FIELD.to_string(), pending.exception);
+        self.throw(DexValue::Object(id));
+    }
+
+    /// Tables of the dex file the running method comes from, which its indices point into
+    fn tables(&self) -> Option<DexTables<'_>> {
+        self.parser.tables(self.frames.last().map_or(0, |frame| frame.class.dex))
+    }
+
+    /// Resolve a method_id into the descriptor of the class it is referenced on and its name
+    fn resolve_method(&self, method_idx: u16) -> Option<(Arc<str>, Arc<str>)> {
+        self.tables()?.resolve_method(method_idx)
+    }
+
+    /// Descriptor of a method_id, e.g. `(Ljava/lang/String;I)V`
+    fn resolve_method_descriptor(&self, method_idx: u16) -> Option<String> {
+        self.tables()?.resolve_method_descriptor(method_idx)
+    }
+
+    /// `name:descriptor` key of a method_id, see `DexClass.methods`
+    fn method_key_of(&self, method_idx: u16) -> Option<String> {
+        let (_, method_name) = self.resolve_method(method_idx)?;
+        Some(method_key(&method_name, &self.resolve_method_descriptor(method_idx)?))
+    }
+
+    /// Find the most derived implementation of `method`, starting at `class_name` and walking
+    /// up its superclasses. `method` is a method key, or a bare name when called from the host.
+    fn find_virtual_method(&self, class_name: &str, method: &str) -> Option<DexClass> {
+        let mut current = Some(class_name.to_string());
+
+        while let Some(name) = current {
+            let class = self.load_class(&name);
+            if let Some(class) = &class && class.resolve_method_key(method, None).is_some() {
+                return Some(class.clone());
+            }
+            current = class.and_then(|class| class.super_class);
+        }
+        self.find_default_method(class_name, method)
+    }
+
+    /// Find the interface of `class_name` or its superclasses with a default method `method`,
+    /// one with a body
+    fn find_default_method(&self, class_name: &str, method: &str) -> Option<DexClass> {
+        let mut pending = vec![class_name.to_string()];
+        let mut seen = HashSet::new();
+        while let Some(name) = pending.pop() {
+            if !seen.insert(name.clone()) {
+                continue;
+            }
+            let Some(class) = self.load_class(&name) else {
+                continue;
+            };
+            let has_body = class.resolve_method_key(method, None)
+                .and_then(|key| class.methods.get(&key))
+                .is_some_and(|method| method.has_code());
+            if has_body {
+                return Some(class);
+            }
+            pending.extend(class.interfaces.iter().cloned());
+            pending.extend(class.super_class.clone());
+        }
+        None
+    }
+
+    /// Declared type of the instance field `field_name` of `class_name` or one of its superclasses
+    pub fn instance_field_type(&self, class_name: &str, field_name: &str) -> Option<String> {
+        let mut current = Some(class_name.to_string());
+
+        while let Some(name) = current {
+            let class = self.load_class(&name)?;
+            if let Some(field) = class.instance_fields.get(field_name) {
+                return Some(field.ty.clone());
+            }
+            current = class.super_class;
+        }
+        None
+    }
+
+    /// Push a frame for `method_name` of `class`, or throw a StackOverflowError when the stack
+    /// is full and an AbstractMethodError when the method has no body. Returns whether the frame
+    /// was pushed.
+    fn enter_method(&mut self, class: DexClass, method_name: String, receiver: Option<ObjectId>, args: Vec<DexValue>) -> bool {
+        let max_depth = MAX_CALL_DEPTH.load(Ordering::SeqCst);
+        if self.frames.len() >= max_depth {
+            interpreter_log!(self, "Call stack exceeded {} frames entering {}.{}", max_depth, class.name, method_name);
+            self.throw_new(STACK_OVERFLOW_ERROR, &format!("stack size {} frames", max_depth));
+            return false;
+        }
+        let argc = args.iter().filter(|arg| **arg != DexValue::WideHigh).count();
+        let method = class.resolve_method_key(&method_name, Some(argc)).and_then(|key| class.methods.get(&key));
+        if let Some(method) = method && method.access_flags.contains(AccessFlags::ABSTRACT) {
+            interpreter_log!(self, "Entering abstract method {}.{}", class.name, method.key());
+            self.throw_new(ABSTRACT_METHOD_ERROR, &format!("abstract method \"{}.{}\"", binary_name(&class.name), method.key()));
+            return false;
+        }
+
+        self.push_frame_with_class(Arc::new(class), method_name, receiver, args)
+    }
+
+    /// Run `method_name` of `class` to completion and return what it returned. Invoke
+    /// instructions don't come through here, their frames run in the loop of the caller.
+    fn invoke_method(&mut self, class: DexClass, method_name: String, receiver: Option<ObjectId>, args: Vec<DexValue>) -> Option<DexValue> {
+        if !self.enter_method(class, method_name, receiver, args) {
+            return None;
+        }
+        self.run()
+    }
+
+    /// Platform method `method_key` of `class_name` or the closest superclass that has one, so
+    /// e.g. exceptions declared by the extension get Throwable's
+    fn inherited_platform_method(&self, class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+        let mut current = Some(class_name.to_string());
+
+        while let Some(name) = current.filter(|name| name != "Ljava/lang/Object;") {
+            if let Some(method) = platform_method(&name, method_key) {
+                return Some(method);
+            }
+            current = self.super_class_of(&name);
+        }
+        None
+    }
+
+    /// Run the native method the receiver has for `method_idx`, `None` if there is none
+    fn invoke_native(&mut self, receiver: Option<ObjectId>, method_idx: u16, method_name: &str, args: Vec<DexValue>) -> Option<DexValue> {
+        let descriptor = self.resolve_method_descriptor(method_idx).unwrap_or_default();
+        let key = method_key(method_name, &descriptor);
+        let class_name = self.heap.get(&receiver?)?.class_name.clone();
+        if let Some(method) = self.inherited_platform_method(&class_name, &key) {
+            return Some(method(self, receiver, args));
+        }
+
+        let object = self.heap.get_mut(&receiver?)?;
+        let method = object.methods.get(&key).copied().flatten()?;
+
+        Some(method(object, args))
+    }
+
+    /// The lambda behind `receiver` if `method_name` is the interface method it implements
+    fn lambda_for(&self, receiver: Option<ObjectId>, method_name: &str) -> Option<Lambda> {
+        self.lambdas.get(&receiver?)
+            .filter(|lambda| lambda.method_name == method_name)
+            .cloned()
+    }
+
+    fn invoke_lambda(&mut self, lambda: Lambda, args: Vec<DexValue>) -> Option<DexValue> {
+        let mut call_args = lambda.captured;
+        call_args.extend(args);
+        self.invoke_method_handle(lambda.dex, lambda.implementation, call_args)
+    }
+
+    /// Run the method a method_handle points to, instance handles take the receiver as first argument
+    fn invoke_method_handle(&mut self, dex: usize, handle_idx: u32, args: Vec<DexValue>) -> Option<DexValue> {
+        let tables = self.parser.tables(dex)?;
+        let item = tables.container.method_handle(handle_idx as usize)?.clone();
+        let Some(method_idx) = item.method_id() else {
+            interpreter_log!(self, "Method handle: field accessor handles are not supported");
+            return None;
+        };
+        let (class_name, method_name) = tables.resolve_method(method_idx)?;
+        let method_key = method_key(&method_name, &tables.resolve_method_descriptor(method_idx)?);
+
+        let receiver = match item.method_handle_type {
+            MethodHandleItem::INVOKE_STATIC => {
+                let Some(target) = self.find_virtual_method(&class_name, &method_key) else {
+                    if &*class_name == KOTLIN_INTRINSICS {
+                        self.invoke_kotlin_intrinsic(&method_key, &args);
+                    } else {
+                        interpreter_log!(self, "Method handle: {}.{} not found", class_name, method_key);
+                    }
+                    return None;
+                };
+                if !self.initialize_class(&target.name) {
+                    return None;
+                }
+                return self.invoke_method(target, method_key, None, args);
+            }
+            MethodHandleItem::INVOKE_CONSTRUCTOR => {
+                if !self.initialize_class(&class_name) {
+                    return None;
+                }
+                let id = self.alloc_object(&class_name);
+                match self.load_class(&class_name) {
+                    Some(class) => {
+                        self.invoke_method(class, method_key, Some(id), args);
+                    }
+                    None => {
+                        self.invoke_native(Some(id), method_idx, &method_name, args);
+                    }
+                }
+                return Some(DexValue::Object(id));
+            }
+            MethodHandleItem::INVOKE_INSTANCE | MethodHandleItem::INVOKE_DIRECT | MethodHandleItem::INVOKE_INTERFACE => {
+                match args.first() {
+                    Some(DexValue::Object(id)) => *id,
+                    _ => {
+                        self.throw_new(NULL_POINTER_EXCEPTION, &format!("Attempt to invoke method {} on a null object reference", method_name));
+                        return None;
+                    }
+                }
+            }
+            method_handle_type => {
+                interpreter_log!(self, "Method handle: unknown method handle type 0x{:02X}", method_handle_type);
+                return None;
+            }
+        };
+        let call_args = args[1..].to_vec();
+
+        // Direct handles run the named class's method, the others dispatch on the receiver
+        let dispatch_class = if item.method_handle_type == MethodHandleItem::INVOKE_DIRECT {
+            class_name.to_string()
+        } else {
+            self.heap.get(&receiver).map(|object| object.class_name.clone()).unwrap_or_else(|| class_name.to_string())
+        };
+
+        if let Some(lambda) = self.lambda_for(Some(receiver), &method_name) {
+            return self.invoke_lambda(lambda, call_args);
+        }
+        match self.find_virtual_method(&dispatch_class, &method_key) {
+            Some(target) => self.invoke_method(target, method_key, Some(receiver), call_args),
+            None => self.invoke_native(Some(receiver), method_idx, &method_name, call_args),
+        }
+    }
+
+    /// Link an invoke-custom call site. Only LambdaMetafactory bootstraps are supported, they
+    /// produce an instance of the call site's return type forwarding to the implementation handle.
+    fn link_call_site(&mut self, call_site_idx: u16, captured: Vec<DexValue>) -> Option<DexValue> {
+        let call_site = self.tables()?.container.call_site(call_site_idx as usize)?.clone();
+        let bootstrap = self.tables()?.container.method_handle(call_site.bootstrap_method_handle as usize)?.clone();
+        let (bootstrap_class, bootstrap_name) = self.resolve_method(bootstrap.method_id()?)?;
+
+        if &*bootstrap_class != LAMBDA_METAFACTORY {
+            interpreter_log!(self, "InvokeCustom: bootstrap {}.{} is not supported", bootstrap_class, bootstrap_name);
+            return None;
+        }
+
+        // metafactory and altMetafactory both take (samMethodType, implMethod, instantiatedMethodType, ...)
+        let Some(DexValue::MethodHandle(implementation)) = call_site.arguments.get(1).cloned() else {
+            interpreter_log!(self, "InvokeCustom: call site {} has no implementation method handle", call_site_idx);
+            return None;
+        };
+        let interface = call_site.method_type
+            .rsplit_once(')')
+            .map(|(_, return_type)| return_type.to_string())
+            .unwrap_or_default();
+        interpreter_log!(self, "InvokeCustom: {}.{} via method handle {}", interface, call_site.method_name, implementation);
+
+        let id = self.alloc_object(&interface);
+        let dex = self.frames.last().map_or(0, |frame| frame.class.dex);
+        self.lambdas.insert(id, Lambda { method_name: call_site.method_name, implementation, dex, captured });
+        Some(DexValue::Object(id))
+    }
+
+    /// Value of a field that was never written, based on the field's type
+    fn field_default(&self, field_idx: u16) -> DexValue {
+        self.tables()
+            .and_then(|tables| tables.resolve_field_type(field_idx))
+            .map(|descriptor| DexValue::default_for(&descriptor))
+            .unwrap_or(DexValue::Null)
+    }
+
+    /// Resolve a field_id into its declaring class descriptor and field name
+    fn resolve_field(&self, field_idx: u16) -> Option<(Arc<str>, Arc<str>)> {
+        self.tables()?.resolve_field(field_idx)
+    }
+
+    /// Class declaring the static `field_name` referenced through `class_name`, which might be a
+    /// subclass. Only the declaring class gets initialized by the access.
+    fn static_field_owner(&mut self, class_name: &str, field_name: &str) -> String {
+        let mut current = class_name.to_string();
+        loop {
+            let declares = match self.load_class(&current) {
+                Some(class) => class.static_fields.contains_key(field_name),
+                // Platform classes only know their fields once they are set up
+                None => self.initialize_class(&current)
+                    && self.statics[&current].contains_key(field_name),
+            };
+            if declares {
+                return current;
+            }
+            match self.super_class_of(&current) {
+                Some(super_class) if super_class != "Ljava/lang/Object;" => current = super_class,
+                _ => return class_name.to_string(),
+            }
+        }
+    }
+
+    fn get_static(&mut self, field_idx: u16) -> DexValue {
+        let Some((class_name, field_name)) = self.resolve_field(field_idx) else {
+            return DexValue::Null;
+        };
+
+        let owner = self.static_field_owner(&class_name, &field_name);
+        if !self.initialize_class(&owner) {
+            return DexValue::Null;
+        }
+        let value = self.statics[&owner].get(&*field_name).cloned().unwrap_or(DexValue::Null);
+        interpreter_log!(self, "SGet {}.{} -> {:?}", owner, field_name, value);
+        value
+    }
+
+    fn put_static(&mut self, field_idx: u16, value: DexValue) {
+        let Some((class_name, field_name)) = self.resolve_field(field_idx) else {
+            return;
+        };
+
+        let owner = self.static_field_owner(&class_name, &field_name);
+        if !self.initialize_class(&owner) {
+            return;
+        }
+        interpreter_log!(self, "SPut {}.{} <- {:?}", owner, field_name, value);
+        self.statics.get_mut(&owner).unwrap().insert(field_name.to_string(), value);
+    }
+
+    /// Superclass of `class_name`, looking at loaded classes, the class cache and then the
+    /// built-in exception and Jsoup node hierarchies
+    pub fn super_class_of(&self, class_name: &str) -> Option<String> {
+        if let Some(class) = self.parser.classes.iter().find(|class| class.name == class_name) {
+            return class.super_class.clone();
+        }
+        if let Some(super_class) = builtin_super_class(class_name)
+            .or_else(|| jsoup::builtin_super_class(class_name))
+            .or_else(|| coroutines::builtin_super_class(class_name)) {
+            return Some(super_class.to_string());
+        }
+
+        self.load_class(class_name).and_then(|class| class.super_class)
+    }
+
+    pub fn is_subclass_of(&self, class_name: &str, target: &str) -> bool {
+        let mut current = Some(class_name.to_string());
+
+        while let Some(name) = current {
+            if name == target {
+                return true;
+            }
+            if name == "Ljava/lang/Object;" {
+                break;
+            }
+            current = self.super_class_of(&name);
+        }
+        false
+    }
+
+    /// Interfaces `class_name` declares, from its class file or the built-in collection and
+    /// source api hierarchies
+    fn interfaces_of(&self, class_name: &str) -> Vec<String> {
+        if let Some(interfaces) = collections::builtin_interfaces(class_name).or_else(|| native::builtin_interfaces(class_name)) {
+            return interfaces.iter().map(|interface| interface.to_string()).collect();
+        }
+        self.load_class(class_name).map(|class| class.interfaces).unwrap_or_default()
+    }
+
+    /// Whether an object of class `class_name` is an instance of `target`, through its
+    /// superclasses and the interfaces any of them implements, like instanceof
+    pub fn is_instance_of(&self, class_name: &str, target: &str) -> bool {
+        if target == "Ljava/lang/Object;" {
+            return true;
+        }
+        // Arrays of references are covariant, String[] is an Object[]
+        if let (Some(element), Some(target_element)) = (class_name.strip_prefix('['), target.strip_prefix('[')) {
+            return element == target_element
+                || (element.starts_with(['L', '[']) && target_element.starts_with(['L', '[']) && self.is_instance_of(element, target_element));
+        }
+
+        let mut pending = vec![class_name.to_string()];
+        let mut seen = HashSet::new();
+        while let Some(name) = pending.pop() {
+            if name == target {
+                return true;
+            }
+            if !seen.insert(name.clone()) || name.starts_with('[') {
+                continue;
+            }
+            pending.extend(self.interfaces_of(&name));
+            pending.extend(self.super_class_of(&name));
+        }
+        false
+    }
+
+    /// Find the handler in `method` that catches `exception` thrown at `pc`
+    fn find_handler(&self, method: &DexMethod, pc: usize, exception: &DexValue) -> Option<usize> {
+        let address = method.address_of(pc)?;
+        let try_block = method.tries.iter()
+            .find(|try_block| try_block.start_addr <= address && address < try_block.end_addr)?;
+
+        let class_name = match exception {
+            DexValue::Object(id) => self.heap.get(id).map(|object| object.class_name.as_str()),
+            _ => None,
+        }.unwrap_or("Ljava/lang/Throwable;");
+
+        let handler_addr = try_block.handlers.iter()
+            .find(|(type_name, _)| self.is_subclass_of(class_name, type_name))
+            .map(|(_, addr)| *addr)
+            .or(try_block.catch_all_addr)?;
+
+        method.pc_for_address(handler_addr)
+    }
+
+
+    /// Read the arguments of an instance invoke, the first register holds the receiver
+    fn split_receiver(frame: &Frame, args: &[u16]) -> (Option<ObjectId>, Vec<DexValue>) {
+        let receiver = match args.first().map(|reg| &frame.registers[*reg as usize]) {
+            Some(DexValue::Object(id)) => Some(*id),
+            _ => None,
+        };
+        let call_args = args.iter()
+            .skip(1)
+            .map(|reg| frame.registers[*reg as usize].clone())
+            .collect();
+
+        (receiver, call_args)
+    }
+
+    /// Text of the receiver of an instance invoke if it's a string value
+    fn string_receiver(frame: &Frame, args: &[u16]) -> Option<String> {
+        match args.first().map(|reg| &frame.registers[*reg as usize]) {
+            Some(DexValue::String(text)) => Some(text.clone()),
+            _ => None,
+        }
+    }
+
+    /// Value of the receiver of an instance invoke if it's a bare primitive
+    fn primitive_receiver(frame: &Frame, args: &[u16]) -> Option<DexValue> {
+        match args.first().map(|reg| &frame.registers[*reg as usize])? {
+            value @ (DexValue::Int(_)
+            | DexValue::Long(_)
+            | DexValue::Double(_)
+            | DexValue::Float(_)
+            | DexValue::Boolean(_)
+            | DexValue::Char(_)
+            | DexValue::Byte(_)
+            | DexValue::Short(_)) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    /// Run the java.lang.String method `method_key` on `text` and store its result
+    fn invoke_string(&mut self, text: &str, method_key: &str, args: Vec<DexValue>) {
+        let Some(method) = string_method(method_key) else {
+            interpreter_log!(self, "String.{} not implemented", method_key);
+            self.set_result(None);
+            return;
+        };
+
+        // Shims that threw return Void, leaving no result
+        let ret_value = method(self, text, args);
+        self.set_result(Some(ret_value));
+    }
+
+    /// `new String(...)`. Strings are register values, so the instance new-instance allocated is
+    /// swapped for the text in every register of the frame that refers to it.
+    fn construct_string(&mut self, receiver: Option<ObjectId>, method_key: &str, args: &[DexValue]) {
+        let Some(text) = new_string(self, method_key, args) else {
+            if self.pending_exception.is_none() {
+                interpreter_log!(self, "String.{} not implemented", method_key);
+            }
+            return;
+        };
+        let (Some(id), Some(frame)) = (receiver, self.frames.last_mut()) else {
+            return;
+        };
+        for register in frame.registers.iter_mut().filter(|register| **register == DexValue::Object(id)) {
+            *register = DexValue::String(text.clone());
+        }
+        self.heap.remove(&id);
+    }
+
+    /// Main execution loop. Runs the frame on top of the stack, and the frames its invokes push,
+    /// until it returns. Returns an optional DexValue if that method returned a value.
+    pub fn run(&mut self) -> Option<DexValue> {
+        // Frames below the one on top belong to whoever runs the loop further down
+        let base = self.frames.len();
+
+        loop {
+            if let Some(pending) = &self.pending_exception {
+                let frame = self.frames.last()?;
+                let class = Arc::clone(&frame.class);
+                let handler_pc = class.methods.get(&frame.method_key)
+                    .and_then(|method| self.find_handler(method, frame.pc.saturating_sub(1), &pending.exception));
+
+                match handler_pc {
+                    Some(handler_pc) => {
+                        let pending = self.pending_exception.take().unwrap();
+                        let frame = self.frames.last_mut().unwrap();
+                        frame.pc = handler_pc;
+                        frame.exception = Some(pending.exception);
+                    }
+                    None => {
+                        // Leave the exception pending so the calling frame gets to handle it
+                        self.pop_frame();
+                        if self.frames.len() < base {
+                            return None;
+                        }
+                    }
+                }
+                continue;
+            }
+
+            let frame = self.frames.last_mut()?;
+            let class = Arc::clone(&frame.class);
+            let Some(method) = class.methods.get(&frame.method_key) else {
+                let method_key = frame.method_key.clone();
+                interpreter_log!(self, "{} has no method {}", class.name, method_key);
+                self.pop_frame();
+                if self.frames.len() < base {
+                    return None;
+                }
+                continue;
+            };
+            if frame.pc >= method.instructions.len() {
+                // Ran off the end, only a void method gets away with that
+                self.pop_frame();
+                if self.frames.len() < base {
+                    return None;
+                }
+                continue;
+            }
+            let instr = &method.instructions[frame.pc];
+            frame.pc += 1;
+
+            if let Some(value) = self.execute(instr) {
+                self.pop_frame();
+                if self.frames.len() < base {
+                    return Some(value);
+                }
+                self.set_result(Some(value));
+            }
+        }
+    }
+
+    /// Take the branch of a goto or if-* when `taken`. A target that isn't the start of an
+    /// instruction fails the method with a VerifyError.
+    fn branch_if(&mut self, taken: bool, offset: i32) {
+        let frame = self.frames.last_mut().unwrap();
+        if taken && !frame.branch(offset) {
+            let message = format!("{}.{}: branch {:+} doesn't land on an instruction", binary_name(&frame.class_name), frame.method_key, offset);
+            self.throw_new(VERIFY_ERROR, &message);
+        }
+    }
+
+    /// The payload of a switch or fill-array-data, a missing one fails the method with a
+    /// VerifyError
+    fn payload(&mut self, offset: i32) -> Option<Payload> {
+        let frame = self.frames.last().unwrap();
+        let payload = frame.payload(offset);
+        if payload.is_none() {
+            let message = format!("{}.{}: no payload at {:+}", binary_name(&frame.class_name), frame.method_key, offset);
+            self.throw_new(VERIFY_ERROR, &message);
+        }
+        payload
+    }
+
+    /// Write the elements of a fill-array-data payload to the start of `array`
+    fn fill_array_data(&mut self, array: &DexValue, element_width: u16, data: &[u8]) {
+        if !matches!(element_width, 1 | 2 | 4 | 8) {
+            self.throw_new(VERIFY_ERROR, &format!("fill-array-data of {}-byte elements", element_width));
+            return;
+        }
+        let DexValue::Object(id) = array else {
+            self.throw_new(NULL_POINTER_EXCEPTION, "Attempt to fill a null array");
+            return;
+        };
+        let Some(object) = self.heap.get_mut(id) else {
+            return;
+        };
+        let component = object.class_name.strip_prefix('[').unwrap_or_default().to_string();
+        let Some(elements) = object.array.as_mut() else {
+            return;
+        };
+        let count = data.len() / element_width as usize;
+        if count > elements.len() {
+            let message = format!("length={}; index={}", elements.len(), count - 1);
+            self.throw_new(ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION, &message);
+            return;
+        }
+
+        for (element, bytes) in elements.iter_mut().zip(data.chunks_exact(element_width as usize)) {
+            // Little-endian whatever the byte order of the dex, sign-extended for the signed types
+            let mut raw = [0; 8];
+            raw[..bytes.len()].copy_from_slice(bytes);
+            let bits = u64::from_le_bytes(raw);
+            *element = match component.as_str() {
+                "Z" => DexValue::Boolean(bits != 0),
+                "B" => DexValue::Byte(bits as i8),
+                "S" => DexValue::Short(bits as i16),
+                "C" => DexValue::Char(bits as u16),
+                "F" => DexValue::Float(f32::from_bits(bits as u32)),
+                "J" => DexValue::Long(bits as i64),
+                "D" => DexValue::Double(f64::from_bits(bits)),
+                _ => DexValue::Int(bits as i32),
+            };
+        }
+    }
+
+    /// Hand the value returned by a call to the caller's move-result, void calls leave nothing
+    fn set_result(&mut self, value: Option<DexValue>) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.temp = value.filter(|value| *value != DexValue::Void);
+        }
+    }
+
+    /// Execute one instruction with mutable access to interpreter (heap, frames, etc.)
+    fn execute(&mut self, instr: &Instruction) -> Option<DexValue> {
+        // Range forms go through the same code as their regular forms, their registers are 16-bit
+        let expanded = instr.expand_range();
+        let instr = expanded.as_ref().unwrap_or(instr);
+
+        let frame = self.frames.last_mut().unwrap();
+        // Indices in the instruction point into the tables of the dex file of the method
+        let dex = frame.class.dex;
+
+        // The result of a call can only be read by the move-result right after it, so a call that
+        // returns nothing never leaves an older result behind
+        if !matches!(instr, Instruction::MoveResult { .. } | Instruction::MoveResultWide { .. } | Instruction::MoveResultObject { .. }) {
+            frame.temp = None;
+        }
+
+        match instr {
+            Instruction::IGet { obj, .. }
+            | Instruction::IGetWide { obj, .. }
+            | Instruction::IGetObject { obj, .. }
+            | Instruction::IGetBoolean { obj, .. }
+            | Instruction::IGetByte { obj, .. }
+            | Instruction::IGetChar { obj, .. }
+            | Instruction::IGetShort { obj, .. }
+                if frame.registers[*obj as usize] == DexValue::Null =>
+            {
+                self.throw_new(NULL_POINTER_EXCEPTION, "Attempt to read from field on a null object reference");
+            }
+
+            Instruction::IPut { obj, .. }
+            | Instruction::IPutWide { obj, .. }
+            | Instruction::IPutObject { obj, .. }
+            | Instruction::IPutBoolean { obj, .. }
+            | Instruction::IPutByte { obj, .. }
+            | Instruction::IPutChar { obj, .. }
+            | Instruction::IPutShort { obj, .. }
+                if frame.registers[*obj as usize] == DexValue::Null =>
+            {
+                self.throw_new(NULL_POINTER_EXCEPTION, "Attempt to write to field on a null object reference");
+            }
+
+            Instruction::InvokeVirtual { args, .. } | Instruction::InvokeInterface { args, .. }
+                if args.first().is_some_and(|receiver| frame.registers[*receiver as usize] == DexValue::Null) =>
+            {
+                self.throw_new(NULL_POINTER_EXCEPTION, "Attempt to invoke virtual method on a null object reference");
+            }
+
+            Instruction::ConstString { dest, string_idx } => {
+                interpreter_log!(self, "String_idx {:?}", &string_idx);
+                let s = &self.parser.tables(dex).unwrap().strings[*string_idx as usize];
+                interpreter_log!(self, "Storing {:?} into v{}", &s, &dest);
+                frame.registers[*dest as usize] = DexValue::String(s.to_string());
+                interpreter_log!(self, "registers -> {:?}", &frame.registers);
+            }
+
+            Instruction::Const4Bit { dst, signed_int } => {
+                interpreter_log!(self, "Signed int: {} -> v{}", signed_int, dst);
+                frame.registers[*dst as usize] = DexValue::Int(*signed_int as i32);
+            }
+
+            Instruction::ConstStringJumbo { dest, string_idx } => {
+                let s = &self.parser.tables(dex).unwrap().strings[*string_idx as usize];
+                frame.registers[*dest as usize] = DexValue::String(s.to_string());
+            }
+
+            // Floats are loaded as their raw bits, `as_float` reads them back
+            Instruction::Const16Bit { dst, signed_int } => {
+                frame.registers[*dst as usize] = DexValue::Int(*signed_int as i32);
+            }
+
+            Instruction::Const32Bit { dst, literal } | Instruction::Const { dst, signed_int: literal } => {
+                frame.registers[*dst as usize] = DexValue::Int(*literal as i32);
+            }
+
+            Instruction::ConstHigh16 { dst, literal } => {
+                frame.registers[*dst as usize] = DexValue::Int((*literal as i32) << 16);
+            }
+
+            Instruction::MoveObject { dst, src } => {
+                interpreter_log!(self, "Moving Object {:?} from v{} -> v{}", frame.registers[*src as usize].clone(), src, dst);
+                frame.registers[*dst as usize] = frame.registers[*src as usize].clone();
+            }
+
+            Instruction::Move { dst, src } => {
+                frame.registers[*dst as usize] = frame.registers[*src as usize].clone();
+            }
+
+            Instruction::MoveFrom16 { dst, src } | Instruction::MoveObjectFrom16 { dst, src } => {
+                frame.registers[*dst as usize] = frame.registers[*src as usize].clone();
+            }
+
+            Instruction::Move16 { dst, src } | Instruction::MoveObject16 { dst, src } => {
+                frame.registers[*dst as usize] = frame.registers[*src as usize].clone();
+            }
+
+            Instruction::MoveResultObject { dst } => {
+                interpreter_log!(self, "Moving Result Object {:?} -> v{}", &frame.temp, dst);
+                if let Some(temp) = &frame.temp {
+                    frame.registers[*dst as usize] = temp.clone();
+                    frame.temp = None;
+                }
+                interpreter_log!(self, "registers -> {:?}", &frame.registers);
+            }
+
+            // A primitive result can come back boxed from a platform method, arithmetic needs the value
+            Instruction::MoveResult { dst } => {
+                if let Some(temp) = frame.temp.take() {
+                    frame.registers[*dst as usize] = boxing::unboxed(&self.heap, &temp).unwrap_or(temp);
+                }
+            }
+
+            Instruction::InvokeStatic { args, method_idx, .. } => {
+                let call_args: Vec<DexValue> = args.iter().map(|reg| frame.registers[*reg as usize].clone()).collect();
+                let (class_name, method_name) = self.resolve_method(*method_idx)?;
+                let method_key = self.method_key_of(*method_idx)?;
+                interpreter_log!(self, "InvokeStatic -> {}.{}", class_name, method_key);
+
+                // Static methods can be inherited, e.g. a companion helper called through a subclass
+                if let Some(target) = self.find_virtual_method(&class_name, &method_key) {
+                    if self.initialize_class(&target.name) {
+                        self.enter_method(target, method_key, None, call_args);
+                    }
+                    return None;
+                }
+
+                if &*class_name == KOTLIN_INTRINSICS {
+                    self.invoke_kotlin_intrinsic(&method_key, &call_args);
+                } else if &*class_name == KOTLIN_REFLECTION || &*class_name == KOTLIN_CLASS_MAPPING {
+                    let ret_value = self.invoke_kotlin_reflection(&method_name, &call_args);
+                    self.set_result(ret_value);
+                } else if let Some(method) = platform_method(&class_name, &method_key) {
+                    let ret_value = method(self, None, call_args);
+                    self.set_result(Some(ret_value));
+                } else {
+                    interpreter_log!(self, "InvokeStatic: {}.{} not found", class_name, method_name);
+                }
+            }
+            Instruction::InvokeSuper { args, method_idx, .. } => {
+                let (receiver, call_args) = Self::split_receiver(frame, args);
+                let current_class = frame.class_name.clone();
+                let (class_name, method_name) = self.resolve_method(*method_idx)?;
+
+                // Resolution starts at the parent of the class declaring the running method,
+                // regardless of the receiver's runtime class
+                let Some(super_class) = self.super_class_of(&current_class) else {
+                    interpreter_log!(self, "InvokeSuper -> {}.{} from {} without a superclass", class_name, method_name, current_class);
+                    return None;
+                };
+                interpreter_log!(self, "InvokeSuper -> {}.{} starting at {}", class_name, method_name, super_class);
+
+                let method_key = self.method_key_of(*method_idx)?;
+                if let Some(target) = self.find_virtual_method(&super_class, &method_key) {
+                    self.enter_method(target, method_key, receiver, call_args);
+                    return None;
+                }
+
+                let ret_value = self.invoke_native(receiver, *method_idx, &method_name, call_args);
+                if ret_value.is_none() {
+                    // Platform parents such as java.lang.Object have nothing to run
+                    interpreter_log!(self, "InvokeSuper: no implementation of {} above {}, skipping", method_name, super_class);
+                }
+                self.set_result(ret_value);
+            }
+            Instruction::InvokeInterface { args, method_idx, .. } => {
+                let (receiver, call_args) = Self::split_receiver(frame, args);
+                let string_receiver = Self::string_receiver(frame, args);
+                let (class_name, method_name) = self.resolve_method(*method_idx)?;
+                let method_key = self.method_key_of(*method_idx)?;
+
+                // CharSequence methods on a string value
+                if let Some(text) = string_receiver {
+                    self.invoke_string(&text, &method_key, call_args);
+                    return None;
+                }
+
+                let Some(receiver_id) = receiver else {
+                    interpreter_log!(self, "InvokeInterface -> {}.{} on null", class_name, method_name);
+                    self.throw_new(NULL_POINTER_EXCEPTION, &format!("Attempt to invoke interface method '{}.{}' on a null object reference", binary_name(&class_name), method_name));
+                    return None;
+                };
+                let Some(mut object) = self.heap.get(&receiver_id).cloned() else {
+                    interpreter_log!(self, "InvokeInterface -> {}.{} on a receiver outside of the heap", class_name, method_name);
+                    return None;
+                };
+                interpreter_log!(self, "InvokeInterface -> {}.{} on {}", class_name, method_name, object.class_name);
+
+                if let Some(lambda) = self.lambda_for(receiver, &method_name) {
+                    let ret_value = self.invoke_lambda(lambda, call_args);
+                    self.set_result(ret_value);
+                    return None;
+                }
+
+                // Interpreted implementation of the interface
+                if let Some(target) = self.find_virtual_method(&object.class_name, &method_key) {
+                    self.enter_method(target, method_key, receiver, call_args);
+                    return None;
+                }
+
+                // Platform implementations such as java.util.ArrayList, or inherited from a
+                // platform class, e.g. compareTo() of an enum through Comparable
+                if let Some(method) = self.inherited_platform_method(&object.class_name, &method_key) {
+                    let ret_value = method(self, receiver, call_args);
+                    self.set_result(Some(ret_value));
+                    return None;
+                }
+
+                let descriptor = self.resolve_method_descriptor(*method_idx).unwrap_or_default();
+                let ret_value = match object.methods.get(&format!("{}:{}", method_name, descriptor)).copied() {
+                    // Native shim
+                    Some(Some(method)) => {
+                        let ret_value = method(&mut object, call_args);
+                        self.heap.insert(receiver_id, object);
+                        Some(ret_value)
+                    }
+                    // Implemented by the host, forwarded to the GlobalRef providing it
+                    Some(None) => {
+                        let signature = format!("{}{}", method_name, descriptor);
+                        let global_ref = self.object_refs.iter().find(|global_ref| has_method(global_ref.as_obj(), &signature));
+                        let ret_value = global_ref.map(|global_ref| call_method_with_args(global_ref.as_obj(), &method_name, &descriptor, &call_args));
+                        if ret_value == Some(None) {
+                            interpreter_log!(self, "InvokeInterface: the arguments of {} can't be passed to the host", signature);
+                            self.throw_new(UNSUPPORTED_OPERATION_EXCEPTION, &format!("{}.{} can't be called with {:?}", binary_name(&object.class_name), signature, call_args));
+                            return None;
+                        }
+                        ret_value.flatten()
+                    }
+                    None => {
+                        interpreter_log!(self, "InvokeInterface: {} not implemented by {}", method_name, object.class_name);
+                        None
+                    }
+                };
+
+                interpreter_log!(self, "ret -> {:?}", &ret_value);
+                self.set_result(ret_value);
+            }
+            Instruction::InvokeDirect {
+                args, method_idx, ..
+            } => {
+                interpreter_log!(self, "Starting InvokeDirect");
+                let tables = self.parser.tables(dex).unwrap();
+
+                if let Some((class_name, method_name)) = tables.resolve_method(*method_idx) {
+
+                    // let object_id = self.alloc_object(class_name.clone().as_str());
+
+                    // out/{class_name}.json
+                    interpreter_log!(self, "Loading {}", &class_name);
+                    // Platform classes have no class file, only native shims such as StringBuilder's
+                    if class_name.contains("Ljava/lang/") {
+                        let (receiver, call_args) = Self::split_receiver(self.frames.last().unwrap(), args);
+                        if &*class_name == "Ljava/lang/String;" && &*method_name == "<init>" {
+                            let method_key = self.method_key_of(*method_idx)?;
+                            self.construct_string(receiver, &method_key, &call_args);
+                            return None;
+                        }
+                        let ret_value = self.invoke_native(receiver, *method_idx, &method_name, call_args);
+                        self.set_result(ret_value);
+                        return None
+                    }
+                    let loaded_class = self.load_class(&class_name);
+
+                    if let Some(loaded_class) = loaded_class {
+                        let method_key = self.method_key_of(*method_idx)?;
+                        interpreter_log!(self, "Calling {}", &method_key);
+                        let (receiver, call_args) = Self::split_receiver(self.frames.last().unwrap(), args);
+
+                        self.enter_method(loaded_class, method_key, receiver, call_args);
+                        return None;
+                    }
+
+                    // Classes outside of the dex, e.g. source api models, only exist as native shims
+                    let (receiver, call_args) = Self::split_receiver(self.frames.last().unwrap(), args);
+                    let ret_value = self.invoke_native(receiver, *method_idx, &method_name, call_args);
+                    if ret_value.is_none() {
+                        interpreter_log!(self, "InvokeDirect: {}.{} not found", class_name, method_name);
+                    }
+                    self.set_result(ret_value);
+                }
+            }
+            Instruction::InvokeVirtual { args, method_idx, .. } => {
+                let (receiver, call_args) = Self::split_receiver(frame, args);
+                let string_receiver = Self::string_receiver(frame, args);
+                let primitive_receiver = Self::primitive_receiver(frame, args);
+                let (class_name, method_name) = self.resolve_method(*method_idx)?;
+                let method_key = self.method_key_of(*method_idx)?;
+
+                // Strings live in registers, not on the heap
+                if let Some(text) = string_receiver {
+                    self.invoke_string(&text, &method_key, call_args);
+                    return None;
+                }
+
+                // A primitive the host or the codec left unboxed, e.g. in a field typed Int?
+                let receiver = match primitive_receiver {
+                    Some(value) if boxing::is_box(&class_name) => Some(boxing::box_value(self, &class_name, value)),
+                    _ => receiver,
+                };
+
+                // Dispatch on the class the receiver was created with, not the one named in the call site
+                let runtime_class = receiver
+                    .and_then(|id| self.heap.get(&id))
+                    .map(|object| object.class_name.clone())
+                    .unwrap_or_else(|| class_name.to_string());
+                interpreter_log!(self, "InvokeVirtual -> {}.{} on {}", class_name, method_name, runtime_class);
+
+                // Object.getClass() is final, no extension can override it
+                if &*method_name == "getClass" && call_args.is_empty() {
+                    let class_object = self.class_object(&runtime_class);
+                    self.set_result(Some(DexValue::Object(class_object)));
+                    return None;
+                }
+
+                // Needs the class hierarchy, which the native Class methods can't reach
+                if runtime_class == JAVA_LANG_CLASS && &*method_name == "getSuperclass" {
+                    let super_class = receiver
+                        .and_then(|id| self.class_of(id))
+                        .and_then(|class| class.super_class)
+                        .map(|super_class| DexValue::Object(self.class_object(&super_class)));
+                    self.set_result(Some(super_class.unwrap_or(DexValue::Null)));
+                    return None;
+                }
+
+                // Functional interfaces such as kotlin.jvm.functions.Function1 are abstract classes
+                if let Some(lambda) = self.lambda_for(receiver, &method_name) {
+                    let ret_value = self.invoke_lambda(lambda, call_args);
+                    self.set_result(ret_value);
+                    return None;
+                }
+
+                match self.find_virtual_method(&runtime_class, &method_key) {
+                    Some(target) => {
+                        self.enter_method(target, method_key, receiver, call_args);
+                    }
+                    None => {
+                        let ret_value = self.invoke_native(receiver, *method_idx, &method_name, call_args);
+                        if ret_value.is_none() {
+                            interpreter_log!(self, "InvokeVirtual: {} not found in {} or its superclasses", method_name, runtime_class);
+                        }
+                        self.set_result(ret_value);
+                    }
+                }
+            }
+
+            Instruction::ConstClass { dst, type_idx } => {
+                let descriptor = self.parser.tables(dex)?.resolve_type(*type_idx as usize)?;
+
+                // Unlike new-instance this doesn't initialize the class
+                let class_object = self.class_object(&descriptor);
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.registers[*dst as usize] = DexValue::Object(class_object);
+                }
+            }
+
+            Instruction::NewInstance { dst, type_idx } => {
+                if let Some(type_name) = self.parser.tables(dex).and_then(|tables| tables.resolve_type(*type_idx as usize)) {
+                    interpreter_log!(self, "NewInstance: Type name -> {}", type_name);
+                    if self.initialize_class(&type_name) {
+                        self.alloc_object_and_assign(&type_name, *dst as u16);
+                    }
+                }
+            }
+
+            Instruction::CheckCast { ref_bearing_reg, type_idx } => {
+                interpreter_log!(self, "Starting CheckCast. Registers -> {:?}", &frame.registers);
+                let type_name = self.parser.tables(dex).and_then(|tables| tables.resolve_type(*type_idx as usize));
+
+                if let Some(type_name) = type_name {
+                    // TODO: Do proper cast check
+                    interpreter_log!(self, "CheckCast: Type name -> {}", type_name);
+                }
+            }
+
+            Instruction::InstanceOf { dst, ref_bearing_reg, type_idx } => {
+                let type_name = self.parser.tables(dex).and_then(|tables| tables.resolve_type(*type_idx as usize)).unwrap_or_default();
+                let value = frame.registers[*ref_bearing_reg as usize].clone();
+                let is_instance = match &value {
+                    DexValue::String(_) => matches!(
+                        &*type_name,
+                        "Ljava/lang/String;" | "Ljava/lang/CharSequence;" | "Ljava/lang/Comparable;" | "Ljava/io/Serializable;" | "Ljava/lang/Object;"
+                    ),
+                    DexValue::Object(id) => self.heap.get(id).is_some_and(|object| self.is_instance_of(&object.class_name, &type_name)),
+                    // null, which is also a zero int, is an instance of nothing
+                    _ => false,
+                };
+                interpreter_log!(self, "InstanceOf: {} -> {}", type_name, is_instance);
+                self.frames.last_mut().unwrap().registers[*dst as usize] = DexValue::Int(is_instance as i32);
+            }
+
+            Instruction::ReturnVoid => {
+                interpreter_log!(self, "-----------------------------------------------------------------");
+                interpreter_log!(self, "Registers -> {:?}", frame.registers);
+
+                return Some(DexValue::Void)
+            }
+
+            Instruction::ReturnObject { src } => {
+                interpreter_log!(self, "-----------------------------------------------------------------");
+                interpreter_log!(self, "Registers -> {:?}", frame.registers);
+
+                return Some(frame.registers[*src as usize].clone())
+            }
+
+            Instruction::Return { reg } => {
+                interpreter_log!(self, "-----------------------------------------------------------------");
+                interpreter_log!(self, "Registers -> {:?}", frame.registers);
+                interpreter_log!(self, "Return Register: v{} -> {:?}", *reg, frame.registers);
+                
+
+                return Some(frame.registers[*reg as usize].clone())
+            }
+
+            Instruction::ConstWide16Bit { dst, signed_int } => {
+                frame.set_wide(*dst as usize, DexValue::Long(*signed_int as i64));
+            }
+
+            Instruction::ConstWide32 { dst, literal } => {
+                frame.set_wide(*dst as usize, DexValue::Long(*literal as i64));
+            }
+
+            Instruction::ConstWide64Bit { dst, literal } => {
+                frame.set_wide(*dst as usize, DexValue::Long(*literal as i64));
+            }
+
+            Instruction::ConstWide16BitHigh { dst, signed_int } => {
+                frame.set_wide(*dst as usize, DexValue::Long((*signed_int as i64) << 48));
+            }
+
+            Instruction::MoveWide { dst, src } => {
+                frame.move_wide(*dst as usize, *src as usize);
+            }
+
+            Instruction::MoveWideFrom16 { dst, src } => {
+                frame.move_wide(*dst as usize, *src as usize);
+            }
+
+            Instruction::MoveWide16 { dst, src } => {
+                frame.move_wide(*dst as usize, *src as usize);
+            }
+
+            Instruction::MoveResultWide { dst } => {
+                if let Some(temp) = frame.temp.take() {
+                    frame.set_wide(*dst as usize, boxing::unboxed(&self.heap, &temp).unwrap_or(temp));
+                }
+            }
+
+            Instruction::ReturnWide { reg } => {
+                interpreter_log!(self, "-----------------------------------------------------------------");
+                interpreter_log!(self, "Registers -> {:?}", frame.registers);
+
+                return Some(frame.get_wide(*reg as usize))
+            }
+
+            // Instance fields are stored under their name so native code can find them
+            Instruction::IGet { src, obj, instance_field_idx }
+            | Instruction::IGetWide { src, obj, instance_field_idx }
+            | Instruction::IGetObject { src, obj, instance_field_idx }
+            | Instruction::IGetBoolean { src, obj, instance_field_idx }
+            | Instruction::IGetByte { src, obj, instance_field_idx }
+            | Instruction::IGetChar { src, obj, instance_field_idx }
+            | Instruction::IGetShort { src, obj, instance_field_idx } => {
+                let DexValue::Object(obj_id) = frame.registers[*obj as usize] else {
+                    return None;
+                };
+                let (_, field_name) = self.resolve_field(*instance_field_idx)?;
+                let value = self.heap.get(&obj_id)
+                    .and_then(|object| object.fields.get(&*field_name).cloned())
+                    .unwrap_or_else(|| self.field_default(*instance_field_idx));
+
+                let frame = self.frames.last_mut().unwrap();
+                if matches!(instr, Instruction::IGetWide { .. }) {
+                    frame.set_wide(*src as usize, value);
+                } else {
+                    frame.registers[*src as usize] = value;
+                }
+            }
+
+            Instruction::IPut { src, obj, instance_field_idx }
+            | Instruction::IPutWide { src, obj, instance_field_idx }
+            | Instruction::IPutObject { src, obj, instance_field_idx }
+            | Instruction::IPutBoolean { src, obj, instance_field_idx }
+            | Instruction::IPutByte { src, obj, instance_field_idx }
+            | Instruction::IPutChar { src, obj, instance_field_idx }
+            | Instruction::IPutShort { src, obj, instance_field_idx } => {
+                let DexValue::Object(obj_id) = frame.registers[*obj as usize] else {
+                    return None;
+                };
+                let value = if matches!(instr, Instruction::IPutWide { .. }) {
+                    frame.get_wide(*src as usize)
+                } else {
+                    frame.registers[*src as usize].clone()
+                };
+                let (_, field_name) = self.resolve_field(*instance_field_idx)?;
+
+                if let Some(object) = self.heap.get_mut(&obj_id) {
+                    object.fields.insert(field_name.to_string(), value);
+                }
+            }
+
+            Instruction::AddLong { dst, first_src, second_src } => {
+                frame.binary_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_add(b)));
+            }
+
+            Instruction::AddLong2Addr { dst_and_first_src, second_src } => {
+                frame.binary_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_add(b)));
+            }
+
+            Instruction::SubLong { dst, first_src, second_src } => {
+                frame.binary_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_sub(b)));
+            }
+
+            Instruction::SubLong2Addr { dst_and_first_src, second_src } => {
+                frame.binary_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_sub(b)));
+            }
+
+            Instruction::MulLong { dst, first_src, second_src } => {
+                frame.binary_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_mul(b)));
+            }
+
+            Instruction::MulLong2Addr { dst_and_first_src, second_src } => {
+                frame.binary_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_mul(b)));
+            }
+
+            Instruction::DivLong { dst, first_src, second_src } => {
+                if frame.registers[*second_src as usize].as_long() == Some(0) {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_div(b)));
+                }
+            }
+
+            Instruction::DivLong2Addr { dst_and_first_src, second_src } => {
+                if frame.registers[*second_src as usize].as_long() == Some(0) {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_div(b)));
+                }
+            }
+
+            Instruction::RemLong { dst, first_src, second_src } => {
+                if frame.registers[*second_src as usize].as_long() == Some(0) {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_rem(b)));
+                }
+            }
+
+            Instruction::RemLong2Addr { dst_and_first_src, second_src } => {
+                if frame.registers[*second_src as usize].as_long() == Some(0) {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| Some(a.wrapping_rem(b)));
+                }
+            }
+
+            Instruction::AndLong { dst, first_src, second_src } => {
+                frame.binary_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| Some(a & b));
+            }
+
+            Instruction::AndLong2Addr { dst_and_first_src, second_src } => {
+                frame.binary_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| Some(a & b));
+            }
+
+            Instruction::OrLong { dst, first_src, second_src } => {
+                frame.binary_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| Some(a | b));
+            }
+
+            Instruction::OrLong2Addr { dst_and_first_src, second_src } => {
+                frame.binary_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| Some(a | b));
+            }
+
+            Instruction::XorLong { dst, first_src, second_src } => {
+                frame.binary_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| Some(a ^ b));
+            }
+
+            Instruction::XorLong2Addr { dst_and_first_src, second_src } => {
+                frame.binary_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| Some(a ^ b));
+            }
+
+            Instruction::ShLLong { dst, first_src, second_src } => {
+                frame.shift_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_shl(b));
+            }
+
+            Instruction::ShlLong2Addr { dst_and_first_src, second_src } => {
+                frame.shift_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_shl(b));
+            }
+
+            Instruction::ShRLong { dst, first_src, second_src } => {
+                frame.shift_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_shr(b));
+            }
+
+            Instruction::ShrLong2Addr { dst_and_first_src, second_src } => {
+                frame.shift_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_shr(b));
+            }
+
+            Instruction::UShRLong { dst, first_src, second_src } => {
+                frame.shift_long(*dst as usize, *first_src as usize, *second_src as usize, |a, b| ((a as u64) >> b) as i64);
+            }
+
+            Instruction::UshrLong2Addr { dst_and_first_src, second_src } => {
+                frame.shift_long(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| ((a as u64) >> b) as i64);
+            }
+
+            Instruction::AddFloat { dst, first_src, second_src } => {
+                frame.binary_float(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a + b);
+            }
+
+            Instruction::AddFloat2Addr { dst_and_first_src, second_src } => {
+                frame.binary_float(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a + b);
+            }
+
+            Instruction::SubFloat { dst, first_src, second_src } => {
+                frame.binary_float(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a - b);
+            }
+
+            Instruction::SubFloat2Addr { dst_and_first_src, second_src } => {
+                frame.binary_float(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a - b);
+            }
+
+            Instruction::MulFloat { dst, first_src, second_src } => {
+                frame.binary_float(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a * b);
+            }
+
+            Instruction::MulFloat2Addr { dst_and_first_src, second_src } => {
+                frame.binary_float(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a * b);
+            }
+
+            Instruction::DivFloat { dst, first_src, second_src } => {
+                frame.binary_float(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a / b);
+            }
+
+            Instruction::DivFloat2Addr { dst_and_first_src, second_src } => {
+                frame.binary_float(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a / b);
+            }
+
+            Instruction::RemFloat { dst, first_src, second_src } => {
+                frame.binary_float(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a % b);
+            }
+
+            Instruction::RemFloat2Addr { dst_and_first_src, second_src } => {
+                frame.binary_float(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a % b);
+            }
+
+            Instruction::AddDouble { dst, first_src, second_src } => {
+                frame.binary_double(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a + b);
+            }
+
+            Instruction::AddDouble2Addr { dst_and_first_src, second_src } => {
+                frame.binary_double(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a + b);
+            }
+
+            Instruction::SubDouble { dst, first_src, second_src } => {
+                frame.binary_double(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a - b);
+            }
+
+            Instruction::SubDouble2Addr { dst_and_first_src, second_src } => {
+                frame.binary_double(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a - b);
+            }
+
+            Instruction::MulDouble { dst, first_src, second_src } => {
+                frame.binary_double(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a * b);
+            }
+
+            Instruction::MulDouble2Addr { dst_and_first_src, second_src } => {
+                frame.binary_double(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a * b);
+            }
+
+            Instruction::DivDouble { dst, first_src, second_src } => {
+                frame.binary_double(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a / b);
+            }
+
+            Instruction::DivDouble2Addr { dst_and_first_src, second_src } => {
+                frame.binary_double(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a / b);
+            }
+
+            Instruction::RemDouble { dst, first_src, second_src } => {
+                frame.binary_double(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a % b);
+            }
+
+            Instruction::RemDouble2Addr { dst_and_first_src, second_src } => {
+                frame.binary_double(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a % b);
+            }
+
+
+            Instruction::CmpLessFloat { dst, first_reg, second_reg } => {
+                if let (Some(a), Some(b)) = (frame.registers[*first_reg as usize].as_float(), frame.registers[*second_reg as usize].as_float()) {
+                    frame.compare(*dst as usize, a, b, -1);
+                }
+            }
+
+            Instruction::CmpGreaterFloat { dst, first_reg, second_reg } => {
+                if let (Some(a), Some(b)) = (frame.registers[*first_reg as usize].as_float(), frame.registers[*second_reg as usize].as_float()) {
+                    frame.compare(*dst as usize, a, b, 1);
+                }
+            }
+
+            Instruction::CmpLessDouble { dst, first_reg, second_reg } => {
+                if let (Some(a), Some(b)) = (frame.registers[*first_reg as usize].as_double(), frame.registers[*second_reg as usize].as_double()) {
+                    frame.compare(*dst as usize, a, b, -1);
+                }
+            }
+
+            Instruction::CmpGreaterDouble { dst, first_reg, second_reg } => {
+                if let (Some(a), Some(b)) = (frame.registers[*first_reg as usize].as_double(), frame.registers[*second_reg as usize].as_double()) {
+                    frame.compare(*dst as usize, a, b, 1);
+                }
+            }
+
+            Instruction::CmpLong { dst, first_reg, second_reg } => {
+                if let (Some(a), Some(b)) = (frame.registers[*first_reg as usize].as_long(), frame.registers[*second_reg as usize].as_long()) {
+                    // Longs are always ordered, the bias is never used
+                    frame.compare(*dst as usize, a, b, 0);
+                }
+            }
+
+            Instruction::NegInt { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_int() {
+                    frame.registers[*dst as usize] = DexValue::Int(value.wrapping_neg());
+                }
+            }
+
+            Instruction::NotInt { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_int() {
+                    frame.registers[*dst as usize] = DexValue::Int(!value);
+                }
+            }
+
+            Instruction::NegFloat { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_float() {
+                    frame.registers[*dst as usize] = DexValue::Float(-value);
+                }
+            }
+
+            Instruction::NegLong { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_long() {
+                    frame.set_wide(*dst as usize, DexValue::Long(value.wrapping_neg()));
+                }
+            }
+
+            Instruction::NotLong { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_long() {
+                    frame.set_wide(*dst as usize, DexValue::Long(!value));
+                }
+            }
+
+            Instruction::NegDouble { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_double() {
+                    frame.set_wide(*dst as usize, DexValue::Double(-value));
+                }
+            }
+
+            Instruction::IntToLong { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_int() {
+                    frame.set_wide(*dst as usize, DexValue::Long(value as i64));
+                }
+            }
+
+            Instruction::IntToFloat { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_int() {
+                    frame.registers[*dst as usize] = DexValue::Float(value as f32);
+                }
+            }
+
+            Instruction::IntToDouble { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_int() {
+                    frame.set_wide(*dst as usize, DexValue::Double(value as f64));
+                }
+            }
+
+            Instruction::LongToInt { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_long() {
+                    frame.registers[*dst as usize] = DexValue::Int(value as i32);
+                }
+            }
+
+            Instruction::LongToFloat { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_long() {
+                    frame.registers[*dst as usize] = DexValue::Float(value as f32);
+                }
+            }
+
+            Instruction::LongToDouble { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_long() {
+                    frame.set_wide(*dst as usize, DexValue::Double(value as f64));
+                }
+            }
+
+            // Casts saturate and take NaN to 0, as in Java
+            Instruction::FloatToInt { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_float() {
+                    frame.registers[*dst as usize] = DexValue::Int(value as i32);
+                }
+            }
+
+            Instruction::FloatToLong { dst, src } => {
+                if let DexValue::Float(value) = frame.registers[*src as usize] {
+                    frame.set_wide(*dst as usize, DexValue::Long(value as i64));
+                }
+            }
+
+            Instruction::FloatToDouble { dst, src } => {
+                if let DexValue::Float(value) = frame.registers[*src as usize] {
+                    frame.set_wide(*dst as usize, DexValue::Double(value as f64));
+                }
+            }
+
+            Instruction::DoubleToInt { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_double() {
+                    frame.registers[*dst as usize] = DexValue::Int(value as i32);
+                }
+            }
+
+            Instruction::DoubleToLong { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_double() {
+                    frame.set_wide(*dst as usize, DexValue::Long(value as i64));
+                }
+            }
+
+            Instruction::DoubleToFloat { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_double() {
+                    frame.registers[*dst as usize] = DexValue::Float(value as f32);
+                }
+            }
+
+            Instruction::IntToByte { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_int() {
+                    frame.registers[*dst as usize] = DexValue::Int(value as i8 as i32);
+                }
+            }
+
+            Instruction::IntToChar { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_int() {
+                    frame.registers[*dst as usize] = DexValue::Int(value as u16 as i32);
+                }
+            }
+
+            Instruction::IntToShort { dst, src } => {
+                if let Some(value) = frame.registers[*src as usize].as_int() {
+                    frame.registers[*dst as usize] = DexValue::Int(value as i16 as i32);
+                }
+            }
+
+            Instruction::AddInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_add(b));
+            }
+
+            Instruction::SubInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_sub(b));
+            }
+
+            Instruction::MulInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_mul(b));
+            }
+
+            Instruction::DivInt { dst, first_src, second_src } => {
+                if frame.registers[*second_src as usize].as_int() == Some(0) {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_div(b));
+                }
+            }
+
+            Instruction::RemInt { dst, first_src, second_src } => {
+                if frame.registers[*second_src as usize].as_int() == Some(0) {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_rem(b));
+                }
+            }
+
+            Instruction::AndInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a & b);
+            }
+
+            Instruction::OrInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a | b);
+            }
+
+            Instruction::XorInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a ^ b);
+            }
+
+            Instruction::ShLInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_shl(b as u32 & 0x1f));
+            }
+
+            Instruction::ShRInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| a.wrapping_shr(b as u32 & 0x1f));
+            }
+
+            Instruction::UShRInt { dst, first_src, second_src } => {
+                frame.binary_int(*dst as usize, *first_src as usize, *second_src as usize, |a, b| ((a as u32) >> (b as u32 & 0x1f)) as i32);
+            }
+
+            Instruction::AddInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_add(b));
+            }
+
+            Instruction::SubInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_sub(b));
+            }
+
+            Instruction::MulInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_mul(b));
+            }
+
+            Instruction::DivInt2Addr { dst_and_first_src, second_src } => {
+                if frame.registers[*second_src as usize].as_int() == Some(0) {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_div(b));
+                }
+            }
+
+            Instruction::RemInt2Addr { dst_and_first_src, second_src } => {
+                if frame.registers[*second_src as usize].as_int() == Some(0) {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_rem(b));
+                }
+            }
+
+            Instruction::AndInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a & b);
+            }
+
+            Instruction::OrInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a | b);
+            }
+
+            Instruction::XorInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a ^ b);
+            }
+
+            Instruction::ShlInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_shl(b as u32 & 0x1f));
+            }
+
+            Instruction::ShrInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| a.wrapping_shr(b as u32 & 0x1f));
+            }
+
+            Instruction::UshrInt2Addr { dst_and_first_src, second_src } => {
+                frame.binary_int(*dst_and_first_src as usize, *dst_and_first_src as usize, *second_src as usize, |a, b| ((a as u32) >> (b as u32 & 0x1f)) as i32);
+            }
+
+            Instruction::AddIntLit16 { dst, src, literal } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *literal as i32, |a, b| a.wrapping_add(b));
+            }
+
+            Instruction::RSubIntLit16 { dst, src, literal } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *literal as i32, |a, b| b.wrapping_sub(a));
+            }
+
+            Instruction::MulIntLit16 { dst, src, literal } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *literal as i32, |a, b| a.wrapping_mul(b));
+            }
+
+            Instruction::DivIntLit16 { dst, src, literal } => {
+                if *literal == 0 {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_int_lit(*dst as usize, *src as usize, *literal as i32, |a, b| a.wrapping_div(b));
+                }
+            }
+
+            Instruction::RemIntLit16 { dst, src, literal } => {
+                if *literal == 0 {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_int_lit(*dst as usize, *src as usize, *literal as i32, |a, b| a.wrapping_rem(b));
+                }
+            }
+
+            Instruction::AndIntLit16 { dst, src, literal } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *literal as i32, |a, b| a & b);
+            }
+
+            Instruction::OrIntLit16 { dst, src, literal } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *literal as i32, |a, b| a | b);
+            }
+
+            Instruction::XorIntLit16 { dst, src, literal } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *literal as i32, |a, b| a ^ b);
+            }
+
+            Instruction::AddInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a.wrapping_add(b));
+            }
+
+            Instruction::RSubInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| b.wrapping_sub(a));
+            }
+
+            Instruction::MulInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a.wrapping_mul(b));
+            }
+
+            Instruction::DivInt8Lit8 { dst, src, signed_int_const } => {
+                if *signed_int_const == 0 {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a.wrapping_div(b));
+                }
+            }
+
+            Instruction::RemInt8Lit8 { dst, src, signed_int_const } => {
+                if *signed_int_const == 0 {
+                    self.throw_new(ARITHMETIC_EXCEPTION, "divide by zero");
+                } else {
+                    frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a.wrapping_rem(b));
+                }
+            }
+
+            Instruction::AndInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a & b);
+            }
+
+            Instruction::OrInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a | b);
+            }
+
+            Instruction::XorInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a ^ b);
+            }
+
+            Instruction::ShLInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a.wrapping_shl(b as u32 & 0x1f));
+            }
+
+            Instruction::ShRInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| a.wrapping_shr(b as u32 & 0x1f));
+            }
+
+            Instruction::UShRInt8Lit8 { dst, src, signed_int_const } => {
+                frame.binary_int_lit(*dst as usize, *src as usize, *signed_int_const as i32, |a, b| ((a as u32) >> (b as u32 & 0x1f)) as i32);
+            }
+
+            Instruction::NewArray { dst, size, type_idx } => {
+                let length = frame.registers[*size as usize].as_int().unwrap_or(0);
+                let type_name = self.parser.tables(dex).and_then(|tables| tables.resolve_type(*type_idx as usize)).unwrap_or_default();
+
+                if length < 0 {
+                    self.throw_new(NEGATIVE_ARRAY_SIZE_EXCEPTION, &length.to_string());
+                } else {
+                    let id = self.alloc_array(&type_name, length as usize);
+                    self.frames.last_mut().unwrap().registers[*dst as usize] = DexValue::Object(id);
+                }
+            }
+
+            Instruction::FilledNewArray { args, type_idx, .. } => {
+                let elements: Vec<DexValue> = args.iter().map(|reg| frame.registers[*reg as usize].clone()).collect();
+                let type_name = self.parser.tables(dex).and_then(|tables| tables.resolve_type(*type_idx as usize)).unwrap_or_default();
+
+                let id = self.alloc_object(&type_name);
+                self.heap.get_mut(&id).unwrap().array = Some(elements);
+                // Picked up by move-result-object like the result of an invoke
+                self.set_result(Some(DexValue::Object(id)));
+            }
+
+            Instruction::ArrayLength { dst, array_ref_bearing_reg } => {
+                let length = match &frame.registers[*array_ref_bearing_reg as usize] {
+                    DexValue::Object(id) => self.heap.get(id).and_then(|object| object.array.as_ref()).map(|array| array.len()),
+                    _ => None,
+                };
+
+                match length {
+                    Some(length) => frame.registers[*dst as usize] = DexValue::Int(length as i32),
+                    None => self.throw_new(NULL_POINTER_EXCEPTION, "Attempt to get length of null array"),
+                }
+            }
+
+            Instruction::AGet { src, array_reg, index_reg }
+            | Instruction::AGetObject { src, array_reg, index_reg }
+            | Instruction::AGetBoolean { src, array_reg, index_reg }
+            | Instruction::AGetByte { src, array_reg, index_reg }
+            | Instruction::AGetChar { src, array_reg, index_reg }
+            | Instruction::AGetShort { src, array_reg, index_reg } => {
+                if let Some((id, index)) = self.array_index(*array_reg, *index_reg, "Attempt to read from null array") {
+                    let value = self.heap[&id].array.as_ref().unwrap()[index].clone();
+                    self.frames.last_mut().unwrap().registers[*src as usize] = value;
+                }
+            }
+
+            Instruction::AGetWide { src, array_reg, index_reg } => {
+                if let Some((id, index)) = self.array_index(*array_reg, *index_reg, "Attempt to read from null array") {
+                    let value = self.heap[&id].array.as_ref().unwrap()[index].clone();
+                    self.frames.last_mut().unwrap().set_wide(*src as usize, value);
+                }
+            }
+
+            Instruction::APut { src, array_reg, index_reg }
+            | Instruction::APutWide { src, array_reg, index_reg }
+            | Instruction::APutObject { src, array_reg, index_reg }
+            | Instruction::APutBoolean { src, array_reg, index_reg }
+            | Instruction::APutByte { src, array_reg, index_reg }
+            | Instruction::APutChar { src, array_reg, index_reg }
+            | Instruction::APutShort { src, array_reg, index_reg } => {
+                let value = frame.registers[*src as usize].clone();
+                if let Some((id, index)) = self.array_index(*array_reg, *index_reg, "Attempt to write to null array") {
+                    self.heap.get_mut(&id).unwrap().array.as_mut().unwrap()[index] = value;
+                }
+            }
+
+            Instruction::SGet { src, static_field_idx }
+            | Instruction::SGetObject { src, static_field_idx }
+            | Instruction::SGetBoolean { src, static_field_idx }
+            | Instruction::SGetByte { src, static_field_idx }
+            | Instruction::SGetChar { src, static_field_idx }
+            | Instruction::SGetShort { src, static_field_idx } => {
+                let value = self.get_static(*static_field_idx);
+                if self.pending_exception.is_none() {
+                    self.frames.last_mut().unwrap().registers[*src as usize] = value;
+                }
+            }
+
+            Instruction::SGetWide { src, static_field_idx } => {
+                let value = self.get_static(*static_field_idx);
+                if self.pending_exception.is_none() {
+                    self.frames.last_mut().unwrap().set_wide(*src as usize, value);
+                }
+            }
+
+            Instruction::SPut { src, static_field_idx }
+            | Instruction::SPutObject { src, static_field_idx }
+            | Instruction::SPutBoolean { src, static_field_idx }
+            | Instruction::SPutByte { src, static_field_idx }
+            | Instruction::SPutChar { src, static_field_idx }
+            | Instruction::SPutShort { src, static_field_idx } => {
+                let value = frame.registers[*src as usize].clone();
+                self.put_static(*static_field_idx, value);
+            }
+
+            Instruction::SPutWide { src, static_field_idx } => {
+                let value = frame.get_wide(*src as usize);
+                self.put_static(*static_field_idx, value);
+            }
+
+            Instruction::ConstMethodHandle { dst, method_handle_idx } => {
+                frame.registers[*dst as usize] = DexValue::MethodHandle(*method_handle_idx as u32);
+            }
+
+            Instruction::InvokePolymorphic { args, method_idx, .. } => {
+                let handle = args.first().map(|reg| frame.registers[*reg as usize].clone());
+                let call_args: Vec<DexValue> = args.iter().skip(1).map(|reg| frame.registers[*reg as usize].clone()).collect();
+                let (class_name, method_name) = self.resolve_method(*method_idx)?;
+
+                let handle_idx = match handle {
+                    Some(DexValue::MethodHandle(handle_idx)) => handle_idx,
+                    Some(DexValue::Null | DexValue::Int(0)) => {
+                        self.throw_new(NULL_POINTER_EXCEPTION, "Attempt to invoke a method on a null MethodHandle");
+                        return None;
+                    }
+                    _ => {
+                        interpreter_log!(self, "InvokePolymorphic -> {}.{} on {:?}, only dex method handles are supported", class_name, method_name, handle);
+                        return None;
+                    }
+                };
+                if !METHOD_HANDLE_INVOKERS.contains(&&*method_name) {
+                    interpreter_log!(self, "InvokePolymorphic: {}.{} is not supported", class_name, method_name);
+                    return None;
+                }
+
+                let ret_value = self.invoke_method_handle(dex, handle_idx, call_args);
+                self.set_result(ret_value);
+            }
+
+            Instruction::InvokeCustom { args, call_site_idx, .. } => {
+                let captured: Vec<DexValue> = args.iter().map(|reg| frame.registers[*reg as usize].clone()).collect();
+                let ret_value = self.link_call_site(*call_site_idx, captured);
+                self.set_result(ret_value);
+            }
+
+            Instruction::MoveException { dst } => {
+                // The exception was taken off the pending state when the handler was entered
+                frame.registers[*dst as usize] = frame.exception.take().unwrap_or(DexValue::Null);
+            }
+
+            Instruction::Throw { reg } => {
+                let exception = frame.registers[*reg as usize].clone();
+                // Registers are untyped, `const/4 vX, #0` is how null gets loaded
+                if matches!(exception, DexValue::Null | DexValue::Int(0)) {
+                    self.throw_new(NULL_POINTER_EXCEPTION, "throw with null exception");
+                } else {
+                    self.throw(exception);
+                }
+            }
+            Instruction::MonitorEnter { ref_bearing_reg } => {
+                match frame.registers[*ref_bearing_reg as usize].clone() {
+                    DexValue::Null | DexValue::Int(0) => self.throw_new(NULL_POINTER_EXCEPTION, "Attempt to lock a null object"),
+                    DexValue::Object(id) => threads::enter(self, id),
+                    // Strings and boxed values have no identity here, locking them guards nothing
+                    _ => {}
+                }
+            }
+            Instruction::MonitorExit { ref_bearing_reg } => {
+                match frame.registers[*ref_bearing_reg as usize].clone() {
+                    DexValue::Null | DexValue::Int(0) => self.throw_new(NULL_POINTER_EXCEPTION, "Attempt to unlock a null object"),
+                    DexValue::Object(id) => threads::exit(self, id),
+                    _ => {}
+                }
+            }
+
+            Instruction::Goto { signed_branch_off } => self.branch_if(true, *signed_branch_off as i32),
+            Instruction::Goto16 { signed_branch_off } => self.branch_if(true, *signed_branch_off as i32),
+            Instruction::Goto32 { signed_branch_off } => self.branch_if(true, *signed_branch_off),
+
+            Instruction::TestIfEqual { first_reg, second_reg, signed_branch_off }
+            | Instruction::TestIfNotEqual { first_reg, second_reg, signed_branch_off } => {
+                let equal = same_operand(&frame.registers[*first_reg as usize], &frame.registers[*second_reg as usize]);
+                let taken = equal == matches!(instr, Instruction::TestIfEqual { .. });
+                self.branch_if(taken, *signed_branch_off as i32);
+            }
+            Instruction::TestIfLessThan { first_reg, second_reg, signed_branch_off }
+            | Instruction::TestIfGreaterEqual { first_reg, second_reg, signed_branch_off }
+            | Instruction::TestIfGreaterThan { first_reg, second_reg, signed_branch_off }
+            | Instruction::TestIfLessEqual { first_reg, second_reg, signed_branch_off } => {
+                let ordering = frame.registers[*first_reg as usize].as_int()
+                    .zip(frame.registers[*second_reg as usize].as_int())
+                    .map(|(a, b)| a.cmp(&b));
+                let taken = ordering.is_some_and(|ordering| match instr {
+                    Instruction::TestIfLessThan { .. } => ordering.is_lt(),
+                    Instruction::TestIfGreaterEqual { .. } => ordering.is_ge(),
+                    Instruction::TestIfGreaterThan { .. } => ordering.is_gt(),
+                    _ => ordering.is_le(),
+                });
+                self.branch_if(taken, *signed_branch_off as i32);
+            }
+
+            Instruction::BranchIfEqualZero { test_reg, signed_branch_off } => {
+                let taken = is_zero(&frame.registers[*test_reg as usize]);
+                self.branch_if(taken, *signed_branch_off as i32);
+            }
+            Instruction::BranchIfNotEqualZero { test_reg, signed_branch_off } => {
+                let taken = !is_zero(&frame.registers[*test_reg as usize]);
+                self.branch_if(taken, *signed_branch_off as i32);
+            }
+            Instruction::BranchIfLessThanZero { test_reg, signed_branch_off }
+            | Instruction::BranchIfGreaterEqualZero { test_reg, signed_branch_off }
+            | Instruction::BranchIfGreaterThanZero { test_reg, signed_branch_off }
+            | Instruction::BranchIfLessEqualZero { test_reg, signed_branch_off } => {
+                let taken = frame.registers[*test_reg as usize].as_int().is_some_and(|value| match instr {
+                    Instruction::BranchIfLessThanZero { .. } => value < 0,
+                    Instruction::BranchIfGreaterEqualZero { .. } => value >= 0,
+                    Instruction::BranchIfGreaterThanZero { .. } => value > 0,
+                    _ => value <= 0,
+                });
+                self.branch_if(taken, *signed_branch_off as i32);
+            }
+
+            Instruction::PackedSwitch { test_reg, signed_fake_branch_off } => {
+                let value = frame.registers[*test_reg as usize].as_int();
+                if let Some(Payload::PackedSwitch { first_key, targets }) = self.payload(*signed_fake_branch_off) {
+                    // No matching key falls through to the next instruction
+                    let target = value.and_then(|value| targets.get(value.wrapping_sub(first_key) as u32 as usize));
+                    if let Some(target) = target {
+                        self.branch_if(true, *target);
+                    }
+                }
+            }
+            Instruction::SparseSwitch { test_reg, signed_fake_branch_off } => {
+                let value = frame.registers[*test_reg as usize].as_int();
+                if let Some(Payload::SparseSwitch { keys, targets }) = self.payload(*signed_fake_branch_off) {
+                    let target = value.and_then(|value| targets.get(keys.binary_search(&value).ok()?));
+                    if let Some(target) = target {
+                        self.branch_if(true, *target);
+                    }
+                }
+            }
+            Instruction::FilledArrayData { array_ref, signed_fake_branch_off } => {
+                let array = frame.registers[*array_ref as usize].clone();
+                if let Some(Payload::FillArrayData { element_width, data }) = self.payload(*signed_fake_branch_off) {
+                    self.fill_array_data(&array, element_width, &data);
+                }
+            }
+
+            Instruction::Nop => {}
+
+            // See `is_implemented`. Running on would compute a wrong result, so the method fails.
+            _ => {
+                interpreter_log!(self, "Unimplemented instruction: {:?}", instr);
+                let message = format!("{}.{}: unsupported instruction {:?}", binary_name(&frame.class_name), frame.method_key, instr);
+                self.throw_new(VERIFY_ERROR, &message);
+            }
+        }
+
+        None
+    }
+}
+
+/// Whether if-eqz takes its branch on `value`. Registers are untyped, null and the int 0 are the
+/// same to it.
+fn is_zero(value: &DexValue) -> bool {
+    *value == DexValue::Null || value.as_int() == Some(0)
+}
+
+/// Whether if-eq takes its branch: ints by value, null equals 0, references by identity. Strings
+/// have no identity here and compare by their text.
+fn same_operand(a: &DexValue, b: &DexValue) -> bool {
+    match (a.as_int(), b.as_int()) {
+        (Some(a), Some(b)) => a == b,
+        _ => (is_zero(a) && is_zero(b)) || a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::endian::Endian;
+    use crate::selftest::{build_micro_dex, SelfTestCase, SELF_TEST_STRING};
+
+    /// Builds the code of a test method from the micro dex string pool
+    type Insns = fn(&[String]) -> Vec<u8>;
+
+    /// Interpreter of the micro dex with `insns` as its static method `test`
+    fn interpreter_for(return_type: &'static str, registers: u16, insns: Insns) -> Interpreter {
+        let case = SelfTestCase { name: "test", return_type, registers, insns, expected: || DexValue::Void };
+        let mut parser = Parser::new(build_micro_dex(&[case], Endian::Little), false);
+        parser.persist = false;
+        parser.parse().unwrap();
+        Interpreter::new(parser)
+    }
+
+    fn run(return_type: &'static str, registers: u16, insns: Insns) -> Result<Option<DexValue>, InterpreterError> {
+        interpreter_for(return_type, registers, insns).call_method(0, "test", None, Vec::new())
+    }
+
+    /// Code units as the bytes of a little-endian dex
+    fn units(units: &[u16]) -> Vec<u8> {
+        units.iter().flat_map(|unit| unit.to_le_bytes()).collect()
+    }
+
+    /// Switches on `value`, returning 7 for the key at +6, 5 for the one at +8 and 0 otherwise
+    fn switch(value: u16, switch_op: u16, payload: &[u16]) -> Vec<u8> {
+        let mut code = vec![
+            0x0012 | (value & 0xF) << 12, // 0: const/4 v0, #value
+            switch_op, 0x0009, 0x0000, // 1: *-switch v0, +9
+            0x0012, 0x000F, // 4: const/4 v0, #0; return v0
+            0x7012, 0x000F, // 6: const/4 v0, #7; return v0
+            0x5012, 0x000F, // 8: const/4 v0, #5; return v0
+        ];
+        code.extend_from_slice(payload);
+        units(&code)
+    }
+
+    /// Keys 0 and 1, branching to +5 and +7 from the switch
+    const PACKED_PAYLOAD: [u16; 8] = [0x0100, 0x0002, 0x0000, 0x0000, 0x0005, 0x0000, 0x0007, 0x0000];
+    /// Keys -5 and -2, branching to +7 and +5 from the switch
+    const SPARSE_PAYLOAD: [u16; 10] = [0x0200, 0x0002, 0xFFFB, 0xFFFF, 0xFFFE, 0xFFFF, 0x0007, 0x0000, 0x0005, 0x0000];
+
+    #[test]
+    fn branches_through_switch_payloads() {
+        assert_eq!(run("I", 1, |_| switch(1, 0x002B, &PACKED_PAYLOAD)).unwrap(), Some(DexValue::Int(5)));
+        assert_eq!(run("I", 1, |_| switch(0, 0x002B, &PACKED_PAYLOAD)).unwrap(), Some(DexValue::Int(7)));
+        assert_eq!(run("I", 1, |_| switch(2, 0x002B, &PACKED_PAYLOAD)).unwrap(), Some(DexValue::Int(0)));
+        assert_eq!(run("I", 1, |_| switch(-2i16 as u16, 0x002B, &PACKED_PAYLOAD)).unwrap(), Some(DexValue::Int(0)));
+
+        assert_eq!(run("I", 1, |_| switch(-5i16 as u16, 0x002C, &SPARSE_PAYLOAD)).unwrap(), Some(DexValue::Int(5)));
+        assert_eq!(run("I", 1, |_| switch(-2i16 as u16, 0x002C, &SPARSE_PAYLOAD)).unwrap(), Some(DexValue::Int(7)));
+        assert_eq!(run("I", 1, |_| switch(3, 0x002C, &SPARSE_PAYLOAD)).unwrap(), Some(DexValue::Int(0)));
+    }
+
+    #[test]
+    fn loads_constants() {
+        // const/16 v0, #-300; return v0
+        assert_eq!(run("I", 1, |_| units(&[0x0013, 0xFED4, 0x000F])).unwrap(), Some(DexValue::Int(-300)));
+        // const v0, #0x12345678; return v0
+        assert_eq!(run("I", 1, |_| units(&[0x0014, 0x5678, 0x1234, 0x000F])).unwrap(), Some(DexValue::Int(0x12345678)));
+        // const/high16 v0, #10.0f; return v0
+        let float = run("F", 1, |_| units(&[0x0015, 0x4120, 0x000F])).unwrap();
+        assert_eq!(float.and_then(|value| value.as_float()), Some(10.0));
+        // const-string/jumbo v0, "selftest"; return-object v0
+        let jumbo = run("Ljava/lang/String;", 1, |strings| {
+            let index = strings.iter().position(|s| s == SELF_TEST_STRING).unwrap() as u32;
+            units(&[0x001B, index as u16, (index >> 16) as u16, 0x0011])
+        });
+        assert_eq!(jumbo.unwrap(), Some(DexValue::String(SELF_TEST_STRING.to_string())));
+    }
+
+    #[test]
+    fn runs_int_unary_ops() {
+        // const/16 v1, #200; <op> v0, v1; return v0
+        let cases: [(Insns, i32); 5] = [
+            (|_| units(&[0x0113, 0x00C8, 0x107B, 0x000F]), -200),
+            (|_| units(&[0x0113, 0x00C8, 0x107C, 0x000F]), !200),
+            (|_| units(&[0x0113, 0x00C8, 0x108D, 0x000F]), -56),
+            (|_| units(&[0x0113, 0xFF38, 0x108E, 0x000F]), 0xFF38),
+            (|_| units(&[0x0114, 0x8765, 0x0001, 0x108F, 0x000F]), -30875),
+        ];
+        for (index, (insns, expected)) in cases.into_iter().enumerate() {
+            assert_eq!(run("I", 2, insns).unwrap(), Some(DexValue::Int(expected)), "case {}", index);
+        }
+    }
+
+    #[test]
+    fn runs_float_ops() {
+        // const/high16 v0, #10.0f; const/high16 v1, #3.0f; <op>; return v0
+        let cases: [(Insns, f32); 4] = [
+            (|_| units(&[0x0015, 0x4120, 0x0115, 0x4040, 0x00A6, 0x0100, 0x000F]), 13.0),
+            (|_| units(&[0x0015, 0x4120, 0x0115, 0x4040, 0x00AA, 0x0100, 0x000F]), 1.0),
+            (|_| units(&[0x0015, 0x4120, 0x0115, 0x4040, 0x10C8, 0x000F]), 30.0),
+            (|_| units(&[0x0015, 0x4120, 0x0115, 0x4040, 0x107F, 0x000F]), -3.0),
+        ];
+        for (index, (insns, expected)) in cases.into_iter().enumerate() {
+            let result = run("F", 2, insns).unwrap();
+            assert_eq!(result.and_then(|value| value.as_float()), Some(expected), "case {}", index);
+        }
+
+        // const/16 v0, #-7; int-to-float v0, v0; float-to-int v0, v0; return v0
+        assert_eq!(run("I", 1, |_| units(&[0x0013, 0xFFF9, 0x0082, 0x0087, 0x000F])).unwrap(), Some(DexValue::Int(-7)));
+        // const v0, #NaN; float-to-int v0, v0; return v0
+        assert_eq!(run("I", 1, |_| units(&[0x0014, 0x0000, 0x7FC0, 0x0087, 0x000F])).unwrap(), Some(DexValue::Int(0)));
+    }
+
+    #[test]
+    fn refuses_switches_without_a_payload() {
+        // The switch points at the return at +5 rather than a payload
+        let result = run("I", 1, |_| units(&[0x1012, 0x002B, 0x0005, 0x0000, 0x000F, 0x0012, 0x000F]));
+        assert_eq!(thrown(result).as_deref(), Some(VERIFY_ERROR));
+    }
+
+    /// new-array of `length` ints filled with 1, -2 and 300
+    fn fill_array(strings: &[String], length: u16) -> Vec<u8> {
+        // The type ids of the micro dex are its descriptors in string order
+        let mut types: Vec<&String> = strings.iter()
+            .filter(|string| ["Lmihonx/selftest/SelfTest;", "Ljava/lang/Object;", "I", "[I"].contains(&string.as_str()))
+            .collect();
+        types.sort();
+        let int_array = types.iter().position(|string| *string == "[I").unwrap() as u16;
+        units(&[
+            0x0112 | length << 12, // 0: const/4 v1, #length
+            0x1023, int_array, // 1: new-array v0, v1, [I
+            0x0026, 0x0005, 0x0000, // 3: fill-array-data v0, +5
+            0x0011, // 6: return-object v0
+            0x0000, // 7: nop, aligning the payload
+            0x0300, 0x0004, 0x0003, 0x0000, 0x0001, 0x0000, 0xFFFE, 0xFFFF, 0x012C, 0x0000,
+        ])
+    }
+
+    #[test]
+    fn fills_arrays_from_payloads() {
+        let mut interpreter = interpreter_for("[I", 2, |strings| fill_array(strings, 4));
+        let Ok(Some(DexValue::Object(id))) = interpreter.call_method(0, "test", None, Vec::new()) else {
+            panic!("no array returned");
+        };
+        let elements = interpreter.heap[&id].array.clone();
+        assert_eq!(elements, Some(vec![DexValue::Int(1), DexValue::Int(-2), DexValue::Int(300), DexValue::Int(0)]));
+
+        let result = run("[I", 2, |strings| fill_array(strings, 2));
+        assert_eq!(thrown(result).as_deref(), Some(ARRAY_INDEX_OUT_OF_BOUNDS_EXCEPTION));
+    }
+
+    fn thrown(result: Result<Option<DexValue>, InterpreterError>) -> Option<String> {
+        match result {
+            Err(InterpreterError::UncaughtException { class_name, .. }) => Some(class_name),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn loops_through_goto_and_if() {
+        // sum = 0; i = 0; n = 5; while (i < n) { sum += i; i++ } return sum
+        let result = run("I", 3, |_| vec![
+            0x12, 0x00, // 0: const/4 v0, #0
+            0x12, 0x01, // 1: const/4 v1, #0
+            0x12, 0x52, // 2: const/4 v2, #5
+            0x35, 0x21, 0x06, 0x00, // 3: if-ge v1, v2, +6
+            0xB0, 0x10, // 5: add-int/2addr v0, v1
+            0xD8, 0x01, 0x01, 0x01, // 6: add-int/lit8 v1, v1, #1
+            0x28, 0xFB, // 8: goto -5
+            0x0F, 0x00, // 9: return v0
+        ]);
+        assert_eq!(result.unwrap(), Some(DexValue::Int(10)));
+    }
+
+    #[test]
+    fn branches_on_cmp_results() {
+        // cmp-long of 7 and 5 is 1, so if-lez falls through
+        let result = run("I", 5, |_| vec![
+            0x16, 0x00, 0x07, 0x00, // 0: const-wide/16 v0, #7
+            0x16, 0x02, 0x05, 0x00, // 2: const-wide/16 v2, #5
+            0x31, 0x04, 0x00, 0x02, // 4: cmp-long v4, v0, v2
+            0x3D, 0x04, 0x04, 0x00, // 6: if-lez v4, +4
+            0x12, 0x10, // 8: const/4 v0, #1
+            0x0F, 0x00, // 9: return v0
+            0x12, 0x00, // 10: const/4 v0, #0
+            0x0F, 0x00, // 11: return v0
+        ]);
+        assert_eq!(result.unwrap(), Some(DexValue::Int(1)));
+
+        // cmpg-double of NaN is 1, cmpl-double -1, so both if-gtz and if-ltz branch
+        let result = run("I", 5, |_| vec![
+            0x19, 0x00, 0xF8, 0x7F, // 0: const-wide/high16 v0, #NaN
+            0x30, 0x04, 0x00, 0x00, // 2: cmpg-double v4, v0, v0
+            0x3C, 0x04, 0x04, 0x00, // 4: if-gtz v4, +4
+            0x12, 0x00, // 6: const/4 v0, #0
+            0x0F, 0x00, // 7: return v0
+            0x2F, 0x04, 0x00, 0x00, // 8: cmpl-double v4, v0, v0
+            0x3A, 0x04, 0xFC, 0xFF, // 10: if-ltz v4, -4
+            0x12, 0x10, // 12: const/4 v0, #1
+            0x0F, 0x00, // 13: return v0
+        ]);
+        assert_eq!(result.unwrap(), Some(DexValue::Int(0)));
+    }
+
+    #[test]
+    fn compares_null_with_zero() {
+        // if-eq of const/4 #0 and a null reference is taken, if-eqz of a string isn't
+        let result = run("I", 2, |strings| {
+            let [lo, hi] = (strings.iter().position(|s| s == "selftest").unwrap() as u16).to_le_bytes();
+            vec![
+                0x12, 0x00, // 0: const/4 v0, #0
+                0x32, 0x10, 0x03, 0x00, // 1: if-eq v0, v1, +3
+                0x0F, 0x00, // 3: return v0
+                0x1A, 0x01, lo, hi, // 4: const-string v1, "selftest"
+                0x38, 0x01, 0xFD, 0xFF, // 6: if-eqz v1, -3
+                0x39, 0x01, 0x03, 0x00, // 8: if-nez v1, +3
+                0x0F, 0x00, // 10: return v0
+                0x12, 0x20, // 11: const/4 v0, #2
+                0x0F, 0x00, // 12: return v0
+            ]
+        });
+        assert_eq!(result.unwrap(), Some(DexValue::Int(2)));
+    }
+
+    #[test]
+    fn refuses_branches_into_an_instruction() {
+        let result = run("V", 2, |_| vec![
+            0x28, 0x02, // 0: goto +2, the middle of the const-wide/16
+            0x16, 0x00, 0x07, 0x00, // 1: const-wide/16 v0, #7
+            0x0E, 0x00, // 3: return-void
+        ]);
+        assert_eq!(thrown(result).as_deref(), Some(VERIFY_ERROR));
+    }
+
+    #[test]
+    fn refuses_unimplemented_instructions() {
+        let result = run("I", 1, |_| vec![
+            0x12, 0x10, // 0: const/4 v0, #1
+            0xFF, 0x00, 0x00, 0x00, // 1: const-method-type v0, proto@0
+            0x0F, 0x00, // 3: return v0
+        ]);
+        assert_eq!(thrown(result).as_deref(), Some(VERIFY_ERROR));
+    }
+
+    #[test]
+    fn refuses_calls_to_missing_methods() {
+        let result = interpreter_for("V", 1, |_| units(&[0x000E])).call_method(0, "missing", None, Vec::new());
+        assert_eq!(thrown(result).as_deref(), Some(NO_SUCH_METHOD_ERROR));
+    }
+
+    #[test]
+    fn throws_on_interface_calls_on_null() {
+        let result = run("V", 1, |_| units(&[
+            0x0012, // 0: const/4 v0, #0
+            0x1072, 0x0000, 0x0000, // 1: invoke-interface {v0}, method@0
+            0x000E, // 4: return-void
+        ]));
+        assert_eq!(thrown(result).as_deref(), Some(NULL_POINTER_EXCEPTION));
+    }
+
+    /// Two objects allocated ahead of the receiver, so its id is none the interpreter could guess
+    fn allocate_receiver(interpreter: &mut Interpreter) -> ObjectId {
+        for _ in 0..2 {
+            interpreter.alloc_object("Ljava/lang/Object;");
+        }
+        interpreter.alloc_object("Ljava/lang/Object;")
+    }
+
+    #[test]
+    fn runs_methods_on_their_receiver() {
+        // return-object v1
+        let mut interpreter = interpreter_for("Ljava/lang/Object;", 2, |_| units(&[0x0111]));
+        let receiver = allocate_receiver(&mut interpreter);
+        let result = interpreter.call_method(0, "test", Some(receiver), Vec::new());
+        assert_eq!(result, Ok(Some(DexValue::Object(receiver))));
+    }
+
+    #[test]
+    fn passes_the_receiver_on_invoke() {
+        let cases = [
+            // method@0, returns its receiver: return-object v0
+            SelfTestCase { name: "self", return_type: "Ljava/lang/Object;", registers: 1, insns: |_| units(&[0x0011]), expected: || DexValue::Void },
+            SelfTestCase {
+                name: "test",
+                return_type: "Ljava/lang/Object;",
+                registers: 2,
+                insns: |_| units(&[
+                    0x1070, 0x0000, 0x0001, // 0: invoke-direct {v1}, method@0
+                    0x000C, // 3: move-result-object v0
+                    0x0011, // 4: return-object v0
+                ]),
+                expected: || DexValue::Void,
+            },
+        ];
+        let mut parser = Parser::new(build_micro_dex(&cases, Endian::Little), false);
+        parser.persist = false;
+        parser.parse().unwrap();
+        let mut interpreter = Interpreter::new(parser);
+        let receiver = allocate_receiver(&mut interpreter);
+
+        let result = interpreter.call_method(0, "test", Some(receiver), Vec::new());
+        assert_eq!(result, Ok(Some(DexValue::Object(receiver))));
     }
 }
@@ -0,0 +1,768 @@
+// org.json's JSONObject and JSONArray as Android ships them. Text is read by serde_json and
+// turned into heap objects right away, so the JSONObject getJSONObject() hands out is the one
+// its parent holds and writes go through. A JSONObject keeps its entries like a LinkedHashMap
+// does and a JSONArray its values like an ArrayList. Values are what Android's would be:
+// strings, boxed Integer, Long, Double and Boolean, nested objects and arrays, and the
+// JSONObject.NULL sentinel for a JSON null. Getters convert the way Android's do, so getInt()
+// reads "12" and getString() reads 12.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::interpreter::boxing::{box_value, java_double, unboxed, BOOLEAN, DOUBLE, INTEGER, LONG};
+use crate::interpreter::codec::MAX_DEPTH;
+use crate::interpreter::collections::{elements_of, map_entries, map_value, new_iterator, new_list, put, remove_key, ARRAY_LIST, HASH_MAP, LINKED_HASH_MAP};
+use crate::interpreter::exception::JSON_EXCEPTION;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{binary_name, parameters, PlatformMethod};
+use crate::interpreter::strings::double_to_string;
+use crate::types::{DexValue, ObjectId};
+
+pub const JSON_OBJECT: &str = "Lorg/json/JSONObject;";
+pub const JSON_ARRAY: &str = "Lorg/json/JSONArray;";
+/// Class of JSONObject.NULL, an anonymous class on Android
+pub const JSON_NULL: &str = "Lorg/json/JSONObject$1;";
+
+const NULL_FIELD: &str = "NULL";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (JSON_OBJECT, "<init>:()V") => object_init,
+        (JSON_OBJECT, "<init>:(Ljava/lang/String;)V") => object_init_with_text,
+        (JSON_OBJECT, "<init>:(Ljava/util/Map;)V") => object_init_with_map,
+        (JSON_OBJECT, "quote:(Ljava/lang/String;)Ljava/lang/String;") => object_quote,
+        (JSON_OBJECT, "has:(Ljava/lang/String;)Z") => object_has,
+        (JSON_OBJECT, "keys:()Ljava/util/Iterator;") => object_keys,
+        (JSON_OBJECT, "names:()Lorg/json/JSONArray;") => object_names,
+        (JSON_OBJECT, "put:(Ljava/lang/String;Ljava/lang/Object;)Lorg/json/JSONObject;") => object_put,
+        (JSON_OBJECT, "put:(Ljava/lang/String;I)Lorg/json/JSONObject;") => |interpreter, this, args| object_put_boxed(interpreter, this, args, INTEGER),
+        (JSON_OBJECT, "put:(Ljava/lang/String;J)Lorg/json/JSONObject;") => |interpreter, this, args| object_put_boxed(interpreter, this, args, LONG),
+        (JSON_OBJECT, "put:(Ljava/lang/String;D)Lorg/json/JSONObject;") => |interpreter, this, args| object_put_boxed(interpreter, this, args, DOUBLE),
+        (JSON_OBJECT, "put:(Ljava/lang/String;Z)Lorg/json/JSONObject;") => |interpreter, this, args| object_put_boxed(interpreter, this, args, BOOLEAN),
+        (JSON_OBJECT, "putOpt:(Ljava/lang/String;Ljava/lang/Object;)Lorg/json/JSONObject;") => object_put_opt,
+        (JSON_OBJECT, "remove:(Ljava/lang/String;)Ljava/lang/Object;") => object_remove,
+
+        (JSON_ARRAY, "<init>:()V") => array_init,
+        (JSON_ARRAY, "<init>:(Ljava/lang/String;)V") => array_init_with_text,
+        (JSON_ARRAY, "<init>:(Ljava/util/Collection;)V") => array_init_with_collection,
+        (JSON_ARRAY, "put:(Ljava/lang/Object;)Lorg/json/JSONArray;") => array_put,
+        (JSON_ARRAY, "put:(I)Lorg/json/JSONArray;") => |interpreter, this, args| array_put_boxed(interpreter, this, args, INTEGER),
+        (JSON_ARRAY, "put:(J)Lorg/json/JSONArray;") => |interpreter, this, args| array_put_boxed(interpreter, this, args, LONG),
+        (JSON_ARRAY, "put:(D)Lorg/json/JSONArray;") => |interpreter, this, args| array_put_boxed(interpreter, this, args, DOUBLE),
+        (JSON_ARRAY, "put:(Z)Lorg/json/JSONArray;") => |interpreter, this, args| array_put_boxed(interpreter, this, args, BOOLEAN),
+        (JSON_ARRAY, "put:(ILjava/lang/Object;)Lorg/json/JSONArray;") => array_set,
+        (JSON_ARRAY, "remove:(I)Ljava/lang/Object;") => array_remove,
+        (JSON_ARRAY, "join:(Ljava/lang/String;)Ljava/lang/String;") => array_join,
+
+        // The getters of both take a name or an index
+        (JSON_OBJECT | JSON_ARRAY, "length:()I") => length,
+        (JSON_OBJECT, "get:(Ljava/lang/String;)Ljava/lang/Object;") | (JSON_ARRAY, "get:(I)Ljava/lang/Object;") => get,
+        (JSON_OBJECT, "opt:(Ljava/lang/String;)Ljava/lang/Object;") | (JSON_ARRAY, "opt:(I)Ljava/lang/Object;") => opt,
+        (JSON_OBJECT, "isNull:(Ljava/lang/String;)Z") | (JSON_ARRAY, "isNull:(I)Z") => is_null,
+        (JSON_OBJECT, "getString:(Ljava/lang/String;)Ljava/lang/String;") | (JSON_ARRAY, "getString:(I)Ljava/lang/String;") => get_string,
+        (JSON_OBJECT, "optString:(Ljava/lang/String;)Ljava/lang/String;" | "optString:(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;")
+            | (JSON_ARRAY, "optString:(I)Ljava/lang/String;" | "optString:(ILjava/lang/String;)Ljava/lang/String;") => opt_string,
+        (JSON_OBJECT, "getInt:(Ljava/lang/String;)I") | (JSON_ARRAY, "getInt:(I)I") => get_int,
+        (JSON_OBJECT, "optInt:(Ljava/lang/String;)I" | "optInt:(Ljava/lang/String;I)I") | (JSON_ARRAY, "optInt:(I)I" | "optInt:(II)I") => opt_int,
+        (JSON_OBJECT, "getLong:(Ljava/lang/String;)J") | (JSON_ARRAY, "getLong:(I)J") => get_long,
+        (JSON_OBJECT, "optLong:(Ljava/lang/String;)J" | "optLong:(Ljava/lang/String;J)J") | (JSON_ARRAY, "optLong:(I)J" | "optLong:(IJ)J") => opt_long,
+        (JSON_OBJECT, "getDouble:(Ljava/lang/String;)D") | (JSON_ARRAY, "getDouble:(I)D") => get_double,
+        (JSON_OBJECT, "optDouble:(Ljava/lang/String;)D" | "optDouble:(Ljava/lang/String;D)D") | (JSON_ARRAY, "optDouble:(I)D" | "optDouble:(ID)D") => opt_double,
+        (JSON_OBJECT, "getBoolean:(Ljava/lang/String;)Z") | (JSON_ARRAY, "getBoolean:(I)Z") => get_boolean,
+        (JSON_OBJECT, "optBoolean:(Ljava/lang/String;)Z" | "optBoolean:(Ljava/lang/String;Z)Z") | (JSON_ARRAY, "optBoolean:(I)Z" | "optBoolean:(IZ)Z") => opt_boolean,
+        (JSON_OBJECT, "getJSONObject:(Ljava/lang/String;)Lorg/json/JSONObject;") | (JSON_ARRAY, "getJSONObject:(I)Lorg/json/JSONObject;") => get_json_object,
+        (JSON_OBJECT, "optJSONObject:(Ljava/lang/String;)Lorg/json/JSONObject;") | (JSON_ARRAY, "optJSONObject:(I)Lorg/json/JSONObject;") => opt_json_object,
+        (JSON_OBJECT, "getJSONArray:(Ljava/lang/String;)Lorg/json/JSONArray;") | (JSON_ARRAY, "getJSONArray:(I)Lorg/json/JSONArray;") => get_json_array,
+        (JSON_OBJECT, "optJSONArray:(Ljava/lang/String;)Lorg/json/JSONArray;") | (JSON_ARRAY, "optJSONArray:(I)Lorg/json/JSONArray;") => opt_json_array,
+        (JSON_OBJECT | JSON_ARRAY, "toString:()Ljava/lang/String;") => to_string,
+        (JSON_OBJECT | JSON_ARRAY, "toString:(I)Ljava/lang/String;") => to_string_indented,
+
+        (JSON_NULL, "toString:()Ljava/lang/String;") => |_, _, _| DexValue::String("null".to_string()),
+        (JSON_NULL, "equals:(Ljava/lang/Object;)Z") => null_equals,
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// JSONObject's static fields
+pub fn json_object_statics(interpreter: &mut Interpreter) -> HashMap<String, DexValue> {
+    let null = interpreter.alloc_object(JSON_NULL);
+    HashMap::from([(NULL_FIELD.to_string(), DexValue::Object(null))])
+}
+
+/// The JSONObject.NULL sentinel
+fn json_null(interpreter: &mut Interpreter) -> DexValue {
+    interpreter.initialize_class(JSON_OBJECT);
+    interpreter.statics.get(JSON_OBJECT)
+        .and_then(|statics| statics.get(NULL_FIELD).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn class_of(interpreter: &Interpreter, value: &DexValue) -> Option<String> {
+    match value {
+        DexValue::Object(id) => interpreter.heap.get(id).map(|object| object.class_name.clone()),
+        _ => None,
+    }
+}
+
+/// A null from Java or JSONObject.NULL
+pub fn is_null_value(interpreter: &Interpreter, value: &DexValue) -> bool {
+    *value == DexValue::Null || class_of(interpreter, value).as_deref() == Some(JSON_NULL)
+}
+
+/// A reference argument, null comes as a zero from `const/4`
+fn object_arg(args: &[DexValue], index: usize) -> DexValue {
+    match args.get(index) {
+        None | Some(DexValue::Int(0) | DexValue::Void) => DexValue::Null,
+        Some(value) => value.clone(),
+    }
+}
+
+fn string_arg(args: &[DexValue], index: usize) -> Option<String> {
+    match args.get(index) {
+        Some(DexValue::String(value)) => Some(value.clone()),
+        _ => None,
+    }
+}
+
+fn this_value(this: Option<ObjectId>) -> DexValue {
+    this.map_or(DexValue::Null, DexValue::Object)
+}
+
+fn throw_json_exception(interpreter: &mut Interpreter, message: &str) -> DexValue {
+    interpreter.throw_new(JSON_EXCEPTION, message);
+    DexValue::Void
+}
+
+fn new_json_object(interpreter: &mut Interpreter, entries: Vec<(String, DexValue)>) -> DexValue {
+    let id = interpreter.alloc_object(JSON_OBJECT);
+    interpreter.heap.get_mut(&id).unwrap().array = Some(Vec::new());
+    for (name, value) in entries {
+        put(interpreter, Some(id), DexValue::String(name), value);
+    }
+    DexValue::Object(id)
+}
+
+fn new_json_array(interpreter: &mut Interpreter, values: Vec<DexValue>) -> DexValue {
+    let id = interpreter.alloc_object(JSON_ARRAY);
+    interpreter.heap.get_mut(&id).unwrap().array = Some(values);
+    DexValue::Object(id)
+}
+
+/// Heap value of a parsed JSON value. Integers become an Integer when they fit one, like
+/// JSONTokener reads them.
+fn from_json(interpreter: &mut Interpreter, json: Value) -> DexValue {
+    match json {
+        Value::Null => json_null(interpreter),
+        Value::Bool(value) => DexValue::Object(box_value(interpreter, BOOLEAN, DexValue::Boolean(value))),
+        Value::Number(number) => {
+            let (class_name, value) = match number.as_i64() {
+                Some(value) => match i32::try_from(value) {
+                    Ok(value) => (INTEGER, DexValue::Int(value)),
+                    Err(_) => (LONG, DexValue::Long(value)),
+                },
+                None => (DOUBLE, DexValue::Double(number.as_f64().unwrap_or(f64::NAN))),
+            };
+            DexValue::Object(box_value(interpreter, class_name, value))
+        }
+        Value::String(text) => DexValue::String(text),
+        Value::Array(values) => {
+            let values = values.into_iter().map(|value| from_json(interpreter, value)).collect();
+            new_json_array(interpreter, values)
+        }
+        Value::Object(fields) => {
+            let entries = fields.into_iter().map(|(name, value)| (name, from_json(interpreter, value))).collect();
+            new_json_object(interpreter, entries)
+        }
+    }
+}
+
+/// Read the first JSON value of `text`, anything after it is ignored like JSONTokener does
+pub fn parse(interpreter: &mut Interpreter, text: &str) -> Result<DexValue, String> {
+    match serde_json::Deserializer::from_str(text).into_iter::<Value>().next() {
+        Some(Ok(json)) => Ok(from_json(interpreter, json)),
+        Some(Err(error)) if !error.is_eof() => {
+            // serde_json ends its messages with the line and column
+            let message = error.to_string();
+            let reason = message.split(" at line ").next().unwrap_or_default();
+            let offset: usize = text.split('\n').take(error.line().saturating_sub(1)).map(|line| line.chars().count() + 1).sum();
+            Err(format!("{} at character {} of {}", reason, offset + error.column().saturating_sub(1), text))
+        }
+        _ => Err(format!("End of input at character {} of {}", text.chars().count(), text)),
+    }
+}
+
+/// Parse `text` into a value of `class_name`, copying its entries or values into `this`
+fn init_with_text(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue], class_name: &str) -> DexValue {
+    let text = string_arg(args, 0).unwrap_or_default();
+    let parsed = match parse(interpreter, &text) {
+        Ok(parsed) => parsed,
+        Err(message) => return throw_json_exception(interpreter, &message),
+    };
+    if class_of(interpreter, &parsed).as_deref() != Some(class_name) {
+        let simple_name = binary_name(class_name).rsplit('.').next().unwrap_or_default().to_string();
+        let message = mismatch(interpreter, None, &parsed, &simple_name);
+        return throw_json_exception(interpreter, &message);
+    }
+
+    let DexValue::Object(parsed) = parsed else {
+        return DexValue::Void;
+    };
+    let values = interpreter.heap.remove(&parsed).and_then(|object| object.array);
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.array = Some(values.unwrap_or_default());
+    }
+    DexValue::Void
+}
+
+/// `value` as JSONObject.wrap() stores it: null as JSONObject.NULL and maps and collections as
+/// JSONObject and JSONArray
+fn wrap(interpreter: &mut Interpreter, value: DexValue) -> DexValue {
+    if value == DexValue::Null {
+        return json_null(interpreter);
+    }
+    match class_of(interpreter, &value).as_deref() {
+        Some(HASH_MAP | LINKED_HASH_MAP) => {
+            let entries = map_entries(interpreter, &value).into_iter()
+                .map(|(key, value)| (interpreter.string_value_of(&key), value))
+                .collect::<Vec<_>>();
+            let entries = entries.into_iter().map(|(key, value)| (key, wrap(interpreter, value))).collect();
+            new_json_object(interpreter, entries)
+        }
+        Some(class_name) if class_name == ARRAY_LIST || class_name.starts_with('[') => {
+            let values = elements_of(interpreter, &value).into_iter().map(|value| wrap(interpreter, value)).collect();
+            new_json_array(interpreter, values)
+        }
+        _ => value,
+    }
+}
+
+/// `Value 12 at name of type java.lang.Integer cannot be converted to JSONObject`
+fn mismatch(interpreter: &mut Interpreter, location: Option<&str>, actual: &DexValue, required: &str) -> String {
+    let class_name = match actual {
+        DexValue::String(_) => "Ljava/lang/String;".to_string(),
+        value => class_of(interpreter, value).unwrap_or_default(),
+    };
+    let location = location.map(|location| format!(" at {}", location)).unwrap_or_default();
+    format!(
+        "Value {}{} of type {} cannot be converted to {}",
+        interpreter.string_value_of(actual), location, binary_name(&class_name), required,
+    )
+}
+
+fn object_init(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.array = Some(Vec::new());
+    }
+    DexValue::Void
+}
+
+fn object_init_with_text(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    init_with_text(interpreter, this, &args, JSON_OBJECT)
+}
+
+fn object_init_with_map(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    object_init(interpreter, this, Vec::new());
+    for (key, value) in map_entries(interpreter, &object_arg(&args, 0)) {
+        let value = wrap(interpreter, value);
+        let key = DexValue::String(interpreter.string_value_of(&key));
+        put(interpreter, this, key, value);
+    }
+    DexValue::Void
+}
+
+fn object_quote(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut quoted = String::new();
+    write_string(&string_arg(&args, 0).unwrap_or_default(), &mut quoted);
+    DexValue::String(quoted)
+}
+
+fn object_has(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(lookup(interpreter, this, &args).1.is_some())
+}
+
+fn names(interpreter: &Interpreter, this: Option<ObjectId>) -> Vec<DexValue> {
+    map_entries(interpreter, &this_value(this)).into_iter().map(|(name, _)| name).collect()
+}
+
+/// Iterates a snapshot of the names
+fn object_keys(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let names = names(interpreter, this);
+    let list = new_list(interpreter, names);
+    new_iterator(interpreter, Some(list))
+}
+
+/// The names as a JSONArray, null when there are none
+fn object_names(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let names = names(interpreter, this);
+    if names.is_empty() {
+        return DexValue::Null;
+    }
+    new_json_array(interpreter, names)
+}
+
+/// Throws for NaN and the infinities, which JSON can't hold
+fn check_number(interpreter: &mut Interpreter, value: &DexValue) -> bool {
+    match unboxed(&interpreter.heap, value) {
+        Some(DexValue::Double(number)) if !number.is_finite() => {
+            throw_json_exception(interpreter, &format!("Forbidden numeric value: {}", double_to_string(number)));
+            false
+        }
+        _ => true,
+    }
+}
+
+/// Store `value` under the name in `args`, a null value removes the name
+fn put_named(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue], value: DexValue) -> DexValue {
+    let Some(name) = string_arg(args, 0) else {
+        return throw_json_exception(interpreter, "Names must be non-null");
+    };
+    if value == DexValue::Null {
+        remove_key(interpreter, this, &DexValue::String(name));
+    } else if check_number(interpreter, &value) {
+        put(interpreter, this, DexValue::String(name), value);
+    } else {
+        return DexValue::Void;
+    }
+    this_value(this)
+}
+
+fn object_put(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    put_named(interpreter, this, &args, object_arg(&args, 1))
+}
+
+fn object_put_boxed(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>, class_name: &str) -> DexValue {
+    let value = boxed_arg(interpreter, &parameters(&args), 1, class_name);
+    put_named(interpreter, this, &args, value)
+}
+
+fn object_put_opt(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    if string_arg(&args, 0).is_none() || object_arg(&args, 1) == DexValue::Null {
+        return this_value(this);
+    }
+    object_put(interpreter, this, args)
+}
+
+fn object_remove(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = object_arg(&args, 0);
+    remove_key(interpreter, this, &name).unwrap_or(DexValue::Null)
+}
+
+/// The primitive argument at `index` in a new box of `class_name`
+fn boxed_arg(interpreter: &mut Interpreter, args: &[DexValue], index: usize, class_name: &str) -> DexValue {
+    let arg = args.get(index);
+    let value = match class_name {
+        LONG => DexValue::Long(arg.and_then(|value| value.as_long()).unwrap_or(0)),
+        DOUBLE => DexValue::Double(arg.and_then(|value| value.as_double()).unwrap_or(0.0)),
+        BOOLEAN => DexValue::Boolean(arg.and_then(|value| value.as_int()).unwrap_or(0) != 0),
+        _ => DexValue::Int(arg.and_then(|value| value.as_int()).unwrap_or(0)),
+    };
+    DexValue::Object(box_value(interpreter, class_name, value))
+}
+
+fn array_init(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    object_init(interpreter, this, Vec::new())
+}
+
+fn array_init_with_text(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    init_with_text(interpreter, this, &args, JSON_ARRAY)
+}
+
+fn array_init_with_collection(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let values = elements_of(interpreter, &object_arg(&args, 0)).into_iter()
+        .map(|value| wrap(interpreter, value))
+        .collect();
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.array = Some(values);
+    }
+    DexValue::Void
+}
+
+fn push(interpreter: &mut Interpreter, this: Option<ObjectId>, value: DexValue) -> DexValue {
+    if !check_number(interpreter, &value) {
+        return DexValue::Void;
+    }
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.array.get_or_insert_with(Vec::new).push(value);
+    }
+    this_value(this)
+}
+
+fn array_put(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    push(interpreter, this, object_arg(&args, 0))
+}
+
+fn array_put_boxed(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>, class_name: &str) -> DexValue {
+    let value = boxed_arg(interpreter, &parameters(&args), 0, class_name);
+    push(interpreter, this, value)
+}
+
+/// Store a value at an index, padding with nulls up to it
+fn array_set(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = object_arg(&args, 1);
+    let Ok(index) = usize::try_from(args.first().and_then(|value| value.as_int()).unwrap_or(0)) else {
+        return throw_json_exception(interpreter, "Index out of range");
+    };
+    if !check_number(interpreter, &value) {
+        return DexValue::Void;
+    }
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        let values = object.array.get_or_insert_with(Vec::new);
+        if values.len() <= index {
+            values.resize(index + 1, DexValue::Null);
+        }
+        values[index] = value;
+    }
+    this_value(this)
+}
+
+/// The removed value, null for an index out of range
+fn array_remove(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let index = args.first().and_then(|value| value.as_int()).unwrap_or(0);
+    this.and_then(|id| interpreter.heap.get_mut(&id))
+        .and_then(|object| object.array.as_mut())
+        .and_then(|values| usize::try_from(index).ok().filter(|index| *index < values.len()).map(|index| values.remove(index)))
+        .unwrap_or(DexValue::Null)
+}
+
+/// The values as they'd be written in JSON, strings in quotes
+fn array_join(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let separator = string_arg(&args, 0).unwrap_or_default();
+    let mut texts = Vec::new();
+    for value in elements_of(interpreter, &this_value(this)) {
+        let mut text = String::new();
+        if !write(interpreter, &value, None, 0, &mut text) {
+            return throw_json_exception(interpreter, "Nesting too deep");
+        }
+        texts.push(text);
+    }
+    DexValue::String(texts.join(&separator))
+}
+
+fn length(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(elements_of(interpreter, &this_value(this)).len() as i32)
+}
+
+/// The name or index in `args` as messages show it and the value `this` holds there, `None`
+/// when it holds nothing there
+fn lookup(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue]) -> (String, Option<DexValue>) {
+    if class_of(interpreter, &this_value(this)).as_deref() == Some(JSON_ARRAY) {
+        let index = args.first().and_then(|value| value.as_int()).unwrap_or(0);
+        let value = usize::try_from(index).ok().and_then(|index| elements_of(interpreter, &this_value(this)).get(index).cloned());
+        return (index.to_string(), value);
+    }
+    let name = object_arg(args, 0);
+    let value = map_value(interpreter, this, &name);
+    (interpreter.string_value_of(&name), value)
+}
+
+/// The value at the name or index in `args`, throwing when there's none or a Java null
+fn required(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue]) -> Option<(String, DexValue)> {
+    let (location, value) = lookup(interpreter, this, args);
+    let message = match value {
+        Some(DexValue::Null) => format!("Value at {} is null.", location),
+        Some(value) => return Some((location, value)),
+        None if class_of(interpreter, &this_value(this)).as_deref() == Some(JSON_ARRAY) => {
+            let length = elements_of(interpreter, &this_value(this)).len();
+            format!("Index {} out of range [0..{})", location, length)
+        }
+        None => format!("No value for {}", location),
+    };
+    throw_json_exception(interpreter, &message);
+    None
+}
+
+/// The required value converted by `convert`, throwing when it can't be
+fn get_as<T>(
+    interpreter: &mut Interpreter,
+    this: Option<ObjectId>,
+    args: &[DexValue],
+    required_type: &str,
+    convert: fn(&mut Interpreter, &DexValue) -> Option<T>,
+) -> Option<T> {
+    let (location, value) = required(interpreter, this, args)?;
+    let converted = convert(interpreter, &value);
+    if converted.is_none() {
+        let message = mismatch(interpreter, Some(&location), &value, required_type);
+        throw_json_exception(interpreter, &message);
+    }
+    converted
+}
+
+/// The value converted by `convert`, `None` when it's missing or can't be
+fn opt_as<T>(
+    interpreter: &mut Interpreter,
+    this: Option<ObjectId>,
+    args: &[DexValue],
+    convert: fn(&mut Interpreter, &DexValue) -> Option<T>,
+) -> Option<T> {
+    let value = lookup(interpreter, this, args).1?;
+    convert(interpreter, &value)
+}
+
+fn to_string_value(interpreter: &mut Interpreter, value: &DexValue) -> Option<String> {
+    match value {
+        DexValue::Null => None,
+        value => Some(interpreter.string_value_of(value)),
+    }
+}
+
+/// A string is read as a double, numbers are cast like Number.intValue() and friends do
+fn to_number(interpreter: &mut Interpreter, value: &DexValue) -> Option<DexValue> {
+    match value {
+        DexValue::String(text) => java_double(text).map(DexValue::Double),
+        value => unboxed(&interpreter.heap, value).filter(|value| matches!(value, DexValue::Int(_) | DexValue::Long(_) | DexValue::Double(_))),
+    }
+}
+
+fn to_int(interpreter: &mut Interpreter, value: &DexValue) -> Option<i32> {
+    match to_number(interpreter, value)? {
+        DexValue::Int(value) => Some(value),
+        DexValue::Long(value) => Some(value as i32),
+        DexValue::Double(value) => Some(value as i32),
+        _ => None,
+    }
+}
+
+fn to_long(interpreter: &mut Interpreter, value: &DexValue) -> Option<i64> {
+    match to_number(interpreter, value)? {
+        DexValue::Int(value) => Some(value as i64),
+        DexValue::Long(value) => Some(value),
+        DexValue::Double(value) => Some(value as i64),
+        _ => None,
+    }
+}
+
+fn to_double(interpreter: &mut Interpreter, value: &DexValue) -> Option<f64> {
+    match to_number(interpreter, value)? {
+        DexValue::Int(value) => Some(value as f64),
+        DexValue::Long(value) => Some(value as f64),
+        DexValue::Double(value) => Some(value),
+        _ => None,
+    }
+}
+
+/// A Boolean, or a string saying true or false in any case
+fn to_boolean(interpreter: &mut Interpreter, value: &DexValue) -> Option<bool> {
+    match value {
+        DexValue::String(text) if text.eq_ignore_ascii_case("true") => Some(true),
+        DexValue::String(text) if text.eq_ignore_ascii_case("false") => Some(false),
+        value => match unboxed(&interpreter.heap, value)? {
+            DexValue::Boolean(value) => Some(value),
+            _ => None,
+        },
+    }
+}
+
+fn to_json_object(interpreter: &mut Interpreter, value: &DexValue) -> Option<DexValue> {
+    (class_of(interpreter, value).as_deref() == Some(JSON_OBJECT)).then(|| value.clone())
+}
+
+fn to_json_array(interpreter: &mut Interpreter, value: &DexValue) -> Option<DexValue> {
+    (class_of(interpreter, value).as_deref() == Some(JSON_ARRAY)).then(|| value.clone())
+}
+
+fn get(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    required(interpreter, this, &args).map_or(DexValue::Void, |(_, value)| value)
+}
+
+fn opt(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    lookup(interpreter, this, &args).1.unwrap_or(DexValue::Null)
+}
+
+fn is_null(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = lookup(interpreter, this, &args).1;
+    DexValue::Boolean(value.is_none_or(|value| is_null_value(interpreter, &value)))
+}
+
+/// JSONObject.NULL reads as "null" like any other value's toString()
+fn get_string(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    get_as(interpreter, this, &args, "String", to_string_value).map_or(DexValue::Void, DexValue::String)
+}
+
+fn opt_string(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = opt_as(interpreter, this, &args, to_string_value);
+    DexValue::String(value.or_else(|| string_arg(&args, 1)).unwrap_or_default())
+}
+
+fn get_int(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    get_as(interpreter, this, &args, "int", to_int).map_or(DexValue::Void, DexValue::Int)
+}
+
+fn opt_int(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let fallback = args.get(1).and_then(|value| value.as_int()).unwrap_or(0);
+    DexValue::Int(opt_as(interpreter, this, &args, to_int).unwrap_or(fallback))
+}
+
+fn get_long(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    get_as(interpreter, this, &args, "long", to_long).map_or(DexValue::Void, DexValue::Long)
+}
+
+fn opt_long(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let args = parameters(&args);
+    let fallback = args.get(1).and_then(|value| value.as_long()).unwrap_or(0);
+    DexValue::Long(opt_as(interpreter, this, &args, to_long).unwrap_or(fallback))
+}
+
+fn get_double(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    get_as(interpreter, this, &args, "double", to_double).map_or(DexValue::Void, DexValue::Double)
+}
+
+/// Defaults to NaN
+fn opt_double(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let args = parameters(&args);
+    let fallback = args.get(1).and_then(|value| value.as_double()).unwrap_or(f64::NAN);
+    DexValue::Double(opt_as(interpreter, this, &args, to_double).unwrap_or(fallback))
+}
+
+fn get_boolean(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    get_as(interpreter, this, &args, "boolean", to_boolean).map_or(DexValue::Void, DexValue::Boolean)
+}
+
+fn opt_boolean(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let fallback = args.get(1).and_then(|value| value.as_int()).unwrap_or(0) != 0;
+    DexValue::Boolean(opt_as(interpreter, this, &args, to_boolean).unwrap_or(fallback))
+}
+
+fn get_json_object(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    get_as(interpreter, this, &args, "JSONObject", to_json_object).unwrap_or(DexValue::Void)
+}
+
+fn opt_json_object(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    opt_as(interpreter, this, &args, to_json_object).unwrap_or(DexValue::Null)
+}
+
+fn get_json_array(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    get_as(interpreter, this, &args, "JSONArray", to_json_array).unwrap_or(DexValue::Void)
+}
+
+fn opt_json_array(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    opt_as(interpreter, this, &args, to_json_array).unwrap_or(DexValue::Null)
+}
+
+/// The JSON text, or null when something in it can't be written, like toString() on Android
+fn to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let mut text = String::new();
+    if write(interpreter, &this_value(this), None, 0, &mut text) { DexValue::String(text) } else { DexValue::Null }
+}
+
+/// The JSON text with each value on its own line, nested `indent` spaces deeper than its parent.
+/// Throws when something in it can't be written.
+fn to_string_indented(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let indent = " ".repeat(args.first().and_then(|value| value.as_int()).unwrap_or(0).max(0) as usize);
+    let mut text = String::new();
+    if !write(interpreter, &this_value(this), Some(&indent), 0, &mut text) {
+        return throw_json_exception(interpreter, "Nesting too deep or a non-finite number");
+    }
+    DexValue::String(text)
+}
+
+fn null_equals(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let other = object_arg(&args, 0);
+    DexValue::Boolean(other == DexValue::Null || other == this_value(this) || is_null_value(interpreter, &other))
+}
+
+/// Quote `text` the way JSONStringer does, which also escapes `/`
+fn write_string(text: &str, out: &mut String) {
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' | '\\' | '/' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '\t' => out.push_str("\\t"),
+            '\u{8}' => out.push_str("\\b"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{c}' => out.push_str("\\f"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// A whole double without its fraction, like JSONObject.numberToString()
+fn write_number(value: &DexValue, out: &mut String) -> bool {
+    match value {
+        DexValue::Double(number) if !number.is_finite() => return false,
+        DexValue::Double(number) if *number == 0.0 && number.is_sign_negative() => out.push_str("-0"),
+        DexValue::Double(number) if *number == (*number as i64) as f64 => out.push_str(&(*number as i64).to_string()),
+        DexValue::Double(number) => out.push_str(&double_to_string(*number)),
+        DexValue::Int(number) => out.push_str(&number.to_string()),
+        DexValue::Long(number) => out.push_str(&number.to_string()),
+        value => out.push_str(&format!("{}", value.as_int().unwrap_or(0))),
+    }
+    true
+}
+
+/// Start a line `depth` levels deep when writing indented
+fn newline(indent: Option<&str>, depth: usize, out: &mut String) {
+    if let Some(indent) = indent {
+        out.push('\n');
+        out.push_str(&indent.repeat(depth));
+    }
+}
+
+/// Write `value` as JSON text, false for a number JSON can't hold or nesting that goes on
+/// forever because an object contains itself
+fn write(interpreter: &mut Interpreter, value: &DexValue, indent: Option<&str>, depth: usize, out: &mut String) -> bool {
+    if depth > MAX_DEPTH {
+        return false;
+    }
+    if is_null_value(interpreter, value) {
+        out.push_str("null");
+        return true;
+    }
+
+    match class_of(interpreter, value).as_deref() {
+        Some(JSON_OBJECT) => {
+            let entries = map_entries(interpreter, value);
+            out.push('{');
+            for (index, (name, value)) in entries.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                newline(indent, depth + 1, out);
+                write_string(&interpreter.string_value_of(name), out);
+                out.push_str(if indent.is_some() { ": " } else { ":" });
+                if !write(interpreter, value, indent, depth + 1, out) {
+                    return false;
+                }
+            }
+            if !entries.is_empty() {
+                newline(indent, depth, out);
+            }
+            out.push('}');
+        }
+        Some(JSON_ARRAY) => {
+            let values = elements_of(interpreter, value);
+            out.push('[');
+            for (index, value) in values.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                newline(indent, depth + 1, out);
+                if !write(interpreter, value, indent, depth + 1, out) {
+                    return false;
+                }
+            }
+            if !values.is_empty() {
+                newline(indent, depth, out);
+            }
+            out.push(']');
+        }
+        _ => match unboxed(&interpreter.heap, value) {
+            Some(DexValue::Boolean(value)) => out.push_str(&value.to_string()),
+            Some(DexValue::Char(unit)) => write_string(&String::from_utf16_lossy(&[unit]), out),
+            Some(number) => return write_number(&number, out),
+            None => {
+                let text = interpreter.string_value_of(value);
+                write_string(&text, out);
+            }
+        },
+    }
+    true
+}
@@ -0,0 +1,1158 @@
+// Jsoup's Document, Element, Elements and the other nodes, plus Mihon's `asJsoup()`. Html is
+// parsed by html5ever through scraper and copied into heap objects: an element keeps its child
+// nodes in its `array` like a list does, its attributes in source order and its parent as
+// fields. Elements is an ArrayList of elements, so iterating and indexing work like on any list.
+// Text extraction follows Jsoup's whitespace normalization; html is written without Jsoup's
+// pretty printing, as with `prettyPrint(false)`. Selectors are in the selector module.
+
+use std::collections::HashSet;
+
+use scraper::{Html, Node};
+
+use crate::interpreter::collections::{new_list, LINKED_HASH_SET};
+use crate::interpreter::exception::{ILLEGAL_ARGUMENT_EXCEPTION, INDEX_OUT_OF_BOUNDS_EXCEPTION, SELECTOR_PARSE_EXCEPTION};
+use crate::interpreter::http;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::PlatformMethod;
+use crate::interpreter::selector::Selector;
+use crate::interpreter::url::resolve_reference;
+use crate::types::{DexValue, ObjectId};
+
+pub const JSOUP: &str = "Lorg/jsoup/Jsoup;";
+pub const NODE: &str = "Lorg/jsoup/nodes/Node;";
+pub const ELEMENT: &str = "Lorg/jsoup/nodes/Element;";
+pub const DOCUMENT: &str = "Lorg/jsoup/nodes/Document;";
+pub const OUTPUT_SETTINGS: &str = "Lorg/jsoup/nodes/Document$OutputSettings;";
+pub const TEXT_NODE: &str = "Lorg/jsoup/nodes/TextNode;";
+pub const DATA_NODE: &str = "Lorg/jsoup/nodes/DataNode;";
+pub const COMMENT: &str = "Lorg/jsoup/nodes/Comment;";
+pub const DOCUMENT_TYPE: &str = "Lorg/jsoup/nodes/DocumentType;";
+pub const ELEMENTS: &str = "Lorg/jsoup/select/Elements;";
+const JSOUP_EXTENSIONS_KT: &str = "Leu/kanade/tachiyomi/util/JsoupExtensionsKt;";
+
+/// Lowercase tag name of an element, `#root` for a document
+const TAG_FIELD: &str = "tagName";
+/// Attribute names and values, alternating
+const ATTRIBUTES_FIELD: &str = "attributes";
+const PARENT_FIELD: &str = "parent";
+/// Text of a text, data or comment node, the declaration of a doctype
+const TEXT_FIELD: &str = "text";
+/// Url a document was parsed for
+const LOCATION_FIELD: &str = "location";
+/// Url links of a document resolve against, its location unless a `<base>` changes it
+const BASE_URI_FIELD: &str = "baseUri";
+
+/// Tags Jsoup formats as blocks, text() separates their text from what's around it
+const BLOCK_TAGS: [&str; 64] = [
+    "html", "head", "body", "frameset", "script", "noscript", "style", "meta", "link", "title", "frame", "noframes",
+    "section", "nav", "aside", "hgroup", "header", "footer", "p", "h1", "h2", "h3", "h4", "h5", "h6", "ul", "ol",
+    "pre", "div", "blockquote", "hr", "address", "figure", "figcaption", "form", "fieldset", "ins", "del", "dl",
+    "dt", "dd", "li", "table", "caption", "thead", "tfoot", "tbody", "colgroup", "col", "tr", "th", "td", "video",
+    "audio", "canvas", "details", "menu", "plaintext", "template", "article", "main", "svg", "math", "center",
+];
+/// Tags without an end tag
+const VOID_TAGS: [&str; 21] = [
+    "meta", "link", "base", "frame", "img", "br", "wbr", "embed", "hr", "input", "keygen", "col", "command", "device",
+    "area", "basefont", "bgsound", "menuitem", "param", "source", "track",
+];
+/// Attributes written without a value when they're empty or repeat their name
+const BOOLEAN_ATTRIBUTES: [&str; 31] = [
+    "allowfullscreen", "async", "autofocus", "checked", "compact", "declare", "default", "defer", "disabled",
+    "formnovalidate", "hidden", "inert", "ismap", "itemscope", "multiple", "muted", "nohref", "noresize", "noshade",
+    "novalidate", "nowrap", "open", "readonly", "required", "reversed", "seamless", "selected", "sortable",
+    "truespeed", "typemustmatch", "indeterminate",
+];
+/// Tags whose text keeps its whitespace, for themselves and a few levels of descendants
+const PRESERVE_WHITESPACE_TAGS: [&str; 4] = ["pre", "plaintext", "title", "textarea"];
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (JSOUP, "parse:(Ljava/lang/String;)Lorg/jsoup/nodes/Document;"
+            | "parse:(Ljava/lang/String;Ljava/lang/String;)Lorg/jsoup/nodes/Document;"
+            | "parseBodyFragment:(Ljava/lang/String;)Lorg/jsoup/nodes/Document;"
+            | "parseBodyFragment:(Ljava/lang/String;Ljava/lang/String;)Lorg/jsoup/nodes/Document;") => |interpreter, _, args| {
+            let html = string_arg(&args, 0).unwrap_or_default();
+            let base_uri = string_arg(&args, 1).unwrap_or_default();
+            DexValue::Object(parse(interpreter, &html, &base_uri))
+        },
+        (JSOUP_EXTENSIONS_KT, "asJsoup:(Lokhttp3/Response;Ljava/lang/String;)Lorg/jsoup/nodes/Document;") => as_jsoup,
+        (JSOUP_EXTENSIONS_KT, "asJsoup$default:(Lokhttp3/Response;Ljava/lang/String;ILjava/lang/Object;)Lorg/jsoup/nodes/Document;") => |interpreter, this, mut args| {
+            if int_arg(&args, 2) & 1 != 0 {
+                args[1] = DexValue::Null;
+            }
+            as_jsoup(interpreter, this, args)
+        },
+
+        (NODE, "attr:(Ljava/lang/String;)Ljava/lang/String;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            DexValue::String(attr(interpreter, this, &key))
+        },
+        (NODE, "attr:(Ljava/lang/String;Ljava/lang/String;)Lorg/jsoup/nodes/Node;")
+        | (ELEMENT, "attr:(Ljava/lang/String;Ljava/lang/String;)Lorg/jsoup/nodes/Element;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            set_attribute(interpreter, this, &key, &string_arg(&args, 1).unwrap_or_default());
+            this_value(this)
+        },
+        (NODE, "removeAttr:(Ljava/lang/String;)Lorg/jsoup/nodes/Node;")
+        | (ELEMENT, "removeAttr:(Ljava/lang/String;)Lorg/jsoup/nodes/Element;") => |interpreter, this, args| {
+            remove_attribute(interpreter, this, &string_arg(&args, 0).unwrap_or_default());
+            this_value(this)
+        },
+        (NODE, "hasAttr:(Ljava/lang/String;)Z") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            DexValue::Boolean(has_attr(interpreter, this, &key))
+        },
+        (NODE, "absUrl:(Ljava/lang/String;)Ljava/lang/String;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            DexValue::String(abs_url(interpreter, this, &key))
+        },
+        (NODE, "baseUri:()Ljava/lang/String;") => |interpreter, this, _| DexValue::String(base_uri(interpreter, this)),
+        (NODE, "childNodes:()Ljava/util/List;") => |interpreter, this, _| {
+            let children = this.map(|id| child_nodes(interpreter, id)).unwrap_or_default();
+            let children = children.into_iter().map(DexValue::Object).collect();
+            DexValue::Object(new_list(interpreter, children))
+        },
+        (NODE, "childNodeSize:()I") => |interpreter, this, _| {
+            DexValue::Int(this.map(|id| child_nodes(interpreter, id).len()).unwrap_or(0) as i32)
+        },
+        (NODE, "childNode:(I)Lorg/jsoup/nodes/Node;") => |interpreter, this, args| {
+            let children = this.map(|id| child_nodes(interpreter, id)).unwrap_or_default();
+            child_at(interpreter, &children, int_arg(&args, 0))
+        },
+        (NODE, "parent:()Lorg/jsoup/nodes/Node;" | "parentNode:()Lorg/jsoup/nodes/Node;") => |interpreter, this, _| {
+            optional(this.and_then(|id| parent_of(interpreter, id)))
+        },
+        (NODE, "nextSibling:()Lorg/jsoup/nodes/Node;") => |interpreter, this, _| {
+            optional(sibling(interpreter, this, &child_nodes, 1))
+        },
+        (NODE, "previousSibling:()Lorg/jsoup/nodes/Node;") => |interpreter, this, _| {
+            optional(sibling(interpreter, this, &child_nodes, -1))
+        },
+        (NODE, "siblingIndex:()I") => |interpreter, this, _| {
+            DexValue::Int(sibling_index(interpreter, this, &child_nodes).unwrap_or(0) as i32)
+        },
+        (NODE, "nodeName:()Ljava/lang/String;") => |interpreter, this, _| DexValue::String(node_name(interpreter, this)),
+        (NODE, "outerHtml:()Ljava/lang/String;" | "toString:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.map(|id| html(interpreter, id, true)).unwrap_or_default())
+        },
+        (NODE, "remove:()V") => |interpreter, this, _| {
+            if let Some(id) = this {
+                detach(interpreter, id);
+            }
+            DexValue::Void
+        },
+        (NODE, "ownerDocument:()Lorg/jsoup/nodes/Document;") => |interpreter, this, _| {
+            let root = this.map(|id| root_of(interpreter, id));
+            optional(root.filter(|root| is_document(interpreter, *root)))
+        },
+
+        (ELEMENT, "select:(Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let Some(selector) = selector_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            let selected = this.map(|id| selector.select(interpreter, id)).unwrap_or_default();
+            new_elements(interpreter, selected)
+        },
+        (ELEMENT, "selectFirst:(Ljava/lang/String;)Lorg/jsoup/nodes/Element;") => |interpreter, this, args| {
+            let Some(selector) = selector_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            let root = this.unwrap_or_default();
+            optional(descendant_elements(interpreter, root).into_iter().find(|id| selector.matches(interpreter, root, *id)))
+        },
+        (ELEMENT, "is:(Ljava/lang/String;)Z") => |interpreter, this, args| {
+            let Some(selector) = selector_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            DexValue::Boolean(this.is_some_and(|id| selector.matches(interpreter, id, id)))
+        },
+        (ELEMENT, "closest:(Ljava/lang/String;)Lorg/jsoup/nodes/Element;") => |interpreter, this, args| {
+            let Some(selector) = selector_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            let mut current = this;
+            while let Some(id) = current.filter(|id| is_element(interpreter, *id)) {
+                if selector.matches(interpreter, id, id) {
+                    return DexValue::Object(id);
+                }
+                current = parent_of(interpreter, id);
+            }
+            DexValue::Null
+        },
+        (ELEMENT, "text:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.map(|id| text(interpreter, id)).unwrap_or_default())
+        },
+        (ELEMENT, "ownText:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.map(|id| own_text(interpreter, id)).unwrap_or_default())
+        },
+        (ELEMENT, "wholeText:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.map(|id| whole_text(interpreter, id)).unwrap_or_default())
+        },
+        (ELEMENT, "data:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.map(|id| data(interpreter, id)).unwrap_or_default())
+        },
+        (ELEMENT, "html:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.map(|id| html(interpreter, id, false)).unwrap_or_default())
+        },
+        (ELEMENT, "hasText:()Z") => |interpreter, this, _| DexValue::Boolean(this.is_some_and(|id| has_text(interpreter, id))),
+        (ELEMENT, "text:(Ljava/lang/String;)Lorg/jsoup/nodes/Element;") => |interpreter, this, args| {
+            if let Some(id) = this {
+                clear_children(interpreter, id);
+                let text = new_node(interpreter, TEXT_NODE, &string_arg(&args, 0).unwrap_or_default());
+                append_child(interpreter, id, text);
+            }
+            this_value(this)
+        },
+        (ELEMENT, "html:(Ljava/lang/String;)Lorg/jsoup/nodes/Element;") => |interpreter, this, args| {
+            if let Some(id) = this {
+                clear_children(interpreter, id);
+                let fragment = Html::parse_fragment(&string_arg(&args, 0).unwrap_or_default());
+                build(interpreter, &fragment, true, id);
+            }
+            this_value(this)
+        },
+        (ELEMENT, "tagName:()Ljava/lang/String;" | "normalName:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.and_then(|id| tag_name(interpreter, id)).unwrap_or_default().to_string())
+        },
+        (ELEMENT, "id:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.and_then(|id| attribute(interpreter, id, "id")).unwrap_or_default())
+        },
+        (ELEMENT, "className:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(this.and_then(|id| attribute(interpreter, id, "class")).unwrap_or_default().trim().to_string())
+        },
+        (ELEMENT, "classNames:()Ljava/util/Set;") => |interpreter, this, _| {
+            let classes = this.and_then(|id| attribute(interpreter, id, "class")).unwrap_or_default();
+            let mut names: Vec<DexValue> = Vec::new();
+            for name in classes.split_whitespace().map(|name| DexValue::String(name.to_string())) {
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            let id = interpreter.alloc_object(LINKED_HASH_SET);
+            interpreter.heap.get_mut(&id).unwrap().array = Some(names);
+            DexValue::Object(id)
+        },
+        (ELEMENT, "hasClass:(Ljava/lang/String;)Z") => |interpreter, this, args| {
+            let class = string_arg(&args, 0).unwrap_or_default();
+            DexValue::Boolean(this.is_some_and(|id| has_class(interpreter, id, &class)))
+        },
+        (ELEMENT, "val:()Ljava/lang/String;") => |interpreter, this, _| DexValue::String(this.map(|id| value(interpreter, id)).unwrap_or_default()),
+        (ELEMENT, "children:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            let children = this.map(|id| element_children(interpreter, id)).unwrap_or_default();
+            new_elements(interpreter, children)
+        },
+        (ELEMENT, "childrenSize:()I") => |interpreter, this, _| {
+            DexValue::Int(this.map(|id| element_children(interpreter, id).len()).unwrap_or(0) as i32)
+        },
+        (ELEMENT, "child:(I)Lorg/jsoup/nodes/Element;") => |interpreter, this, args| {
+            let children = this.map(|id| element_children(interpreter, id)).unwrap_or_default();
+            child_at(interpreter, &children, int_arg(&args, 0))
+        },
+        (ELEMENT, "firstElementChild:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| {
+            optional(this.and_then(|id| element_children(interpreter, id).first().copied()))
+        },
+        (ELEMENT, "lastElementChild:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| {
+            optional(this.and_then(|id| element_children(interpreter, id).last().copied()))
+        },
+        (ELEMENT, "parent:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| {
+            optional(this.and_then(|id| parent_of(interpreter, id)))
+        },
+        (ELEMENT, "parents:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            let parents = this.map(|id| ancestors(interpreter, id)).unwrap_or_default();
+            new_elements(interpreter, parents)
+        },
+        (ELEMENT, "nextElementSibling:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| {
+            optional(sibling(interpreter, this, &element_children, 1))
+        },
+        (ELEMENT, "previousElementSibling:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| {
+            optional(sibling(interpreter, this, &element_children, -1))
+        },
+        (ELEMENT, "firstElementSibling:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| {
+            optional(element_siblings(interpreter, this).first().copied())
+        },
+        (ELEMENT, "lastElementSibling:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| {
+            optional(element_siblings(interpreter, this).last().copied())
+        },
+        (ELEMENT, "nextElementSiblings:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            let siblings = element_siblings(interpreter, this);
+            let index = sibling_index(interpreter, this, &element_children).unwrap_or(siblings.len());
+            new_elements(interpreter, siblings.into_iter().skip(index + 1).collect())
+        },
+        (ELEMENT, "previousElementSiblings:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            let siblings = element_siblings(interpreter, this);
+            let index = sibling_index(interpreter, this, &element_children).unwrap_or(0);
+            new_elements(interpreter, siblings.into_iter().take(index).rev().collect())
+        },
+        (ELEMENT, "siblingElements:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            let siblings = element_siblings(interpreter, this).into_iter().filter(|id| Some(*id) != this).collect();
+            new_elements(interpreter, siblings)
+        },
+        (ELEMENT, "elementSiblingIndex:()I") => |interpreter, this, _| {
+            DexValue::Int(sibling_index(interpreter, this, &element_children).unwrap_or(0) as i32)
+        },
+        (ELEMENT, "getElementById:(Ljava/lang/String;)Lorg/jsoup/nodes/Element;") => |interpreter, this, args| {
+            let id = string_arg(&args, 0).unwrap_or_default();
+            let found = this.map(|root| descendant_elements(interpreter, root)).unwrap_or_default()
+                .into_iter()
+                .find(|element| attribute(interpreter, *element, "id").is_some_and(|value| value == id));
+            optional(found)
+        },
+        (ELEMENT, "getElementsByTag:(Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let tag = string_arg(&args, 0).unwrap_or_default().trim().to_lowercase();
+            elements_where(interpreter, this, |interpreter, id| tag_name(interpreter, id) == Some(tag.as_str()))
+        },
+        (ELEMENT, "getElementsByClass:(Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let class = string_arg(&args, 0).unwrap_or_default();
+            elements_where(interpreter, this, |interpreter, id| has_class(interpreter, id, &class))
+        },
+        (ELEMENT, "getElementsByAttribute:(Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            elements_where(interpreter, this, |interpreter, id| attribute(interpreter, id, key.trim()).is_some())
+        },
+        (ELEMENT, "getElementsByAttributeValue:(Ljava/lang/String;Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            let value = string_arg(&args, 1).unwrap_or_default();
+            elements_where(interpreter, this, |interpreter, id| {
+                attribute(interpreter, id, key.trim()).is_some_and(|actual| actual.trim().eq_ignore_ascii_case(value.trim()))
+            })
+        },
+        (ELEMENT, "getAllElements:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            elements_where(interpreter, this, |_, _| true)
+        },
+
+        (DOCUMENT, "location:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(text_field(interpreter, this, LOCATION_FIELD).unwrap_or_default())
+        },
+        (DOCUMENT, "head:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| optional(document_child(interpreter, this, &["head"])),
+        (DOCUMENT, "body:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| {
+            optional(document_child(interpreter, this, &["body", "frameset"]))
+        },
+        (DOCUMENT, "title:()Ljava/lang/String;") => |interpreter, this, _| {
+            let head = document_child(interpreter, this, &["head"]);
+            let title = head.map(|head| descendant_elements(interpreter, head)).unwrap_or_default()
+                .into_iter()
+                .find(|id| tag_name(interpreter, *id) == Some("title"));
+            let title = normalize_whitespace(&title.map(|id| text(interpreter, id)).unwrap_or_default());
+            DexValue::String(java_trim(&title).to_string())
+        },
+        (DOCUMENT, "outputSettings:()Lorg/jsoup/nodes/Document$OutputSettings;") => |interpreter, _, _| DexValue::Object(interpreter.alloc_object(OUTPUT_SETTINGS)),
+        (DOCUMENT, "outputSettings:(Lorg/jsoup/nodes/Document$OutputSettings;)Lorg/jsoup/nodes/Document;") => |_, this, _| this_value(this),
+        // Html is never pretty printed, so there's nothing to set
+        (OUTPUT_SETTINGS, "prettyPrint:(Z)Lorg/jsoup/nodes/Document$OutputSettings;"
+            | "outline:(Z)Lorg/jsoup/nodes/Document$OutputSettings;"
+            | "indentAmount:(I)Lorg/jsoup/nodes/Document$OutputSettings;"
+            | "charset:(Ljava/lang/String;)Lorg/jsoup/nodes/Document$OutputSettings;") => |_, this, _| this_value(this),
+        (OUTPUT_SETTINGS, "prettyPrint:()Z" | "outline:()Z") => |_, _, _| DexValue::Boolean(false),
+
+        (TEXT_NODE, "text:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(normalize_whitespace(&text_field(interpreter, this, TEXT_FIELD).unwrap_or_default()))
+        },
+        (TEXT_NODE, "getWholeText:()Ljava/lang/String;") | (DATA_NODE, "getWholeData:()Ljava/lang/String;")
+        | (COMMENT, "getData:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(text_field(interpreter, this, TEXT_FIELD).unwrap_or_default())
+        },
+        (TEXT_NODE, "isBlank:()Z") => |interpreter, this, _| {
+            DexValue::Boolean(is_blank(&text_field(interpreter, this, TEXT_FIELD).unwrap_or_default()))
+        },
+
+        (ELEMENTS, "text:()Ljava/lang/String;") => |interpreter, this, _| {
+            let texts: Vec<String> = list_elements(interpreter, this).into_iter().map(|id| text(interpreter, id)).collect();
+            DexValue::String(texts.join(" "))
+        },
+        (ELEMENTS, "html:()Ljava/lang/String;") => |interpreter, this, _| {
+            let htmls: Vec<String> = list_elements(interpreter, this).into_iter().map(|id| html(interpreter, id, false)).collect();
+            DexValue::String(htmls.join("\n"))
+        },
+        (ELEMENTS, "outerHtml:()Ljava/lang/String;" | "toString:()Ljava/lang/String;") => |interpreter, this, _| {
+            let htmls: Vec<String> = list_elements(interpreter, this).into_iter().map(|id| html(interpreter, id, true)).collect();
+            DexValue::String(htmls.join("\n"))
+        },
+        (ELEMENTS, "attr:(Ljava/lang/String;)Ljava/lang/String;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            let element = list_elements(interpreter, this).into_iter().find(|id| has_attr(interpreter, Some(*id), &key));
+            DexValue::String(element.map(|id| attr(interpreter, Some(id), &key)).unwrap_or_default())
+        },
+        (ELEMENTS, "attr:(Ljava/lang/String;Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            let value = string_arg(&args, 1).unwrap_or_default();
+            for id in list_elements(interpreter, this) {
+                set_attribute(interpreter, Some(id), &key, &value);
+            }
+            this_value(this)
+        },
+        (ELEMENTS, "removeAttr:(Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            for id in list_elements(interpreter, this) {
+                remove_attribute(interpreter, Some(id), &key);
+            }
+            this_value(this)
+        },
+        (ELEMENTS, "hasAttr:(Ljava/lang/String;)Z") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            DexValue::Boolean(list_elements(interpreter, this).into_iter().any(|id| has_attr(interpreter, Some(id), &key)))
+        },
+        (ELEMENTS, "hasClass:(Ljava/lang/String;)Z") => |interpreter, this, args| {
+            let class = string_arg(&args, 0).unwrap_or_default();
+            DexValue::Boolean(list_elements(interpreter, this).into_iter().any(|id| has_class(interpreter, id, &class)))
+        },
+        (ELEMENTS, "hasText:()Z") => |interpreter, this, _| {
+            DexValue::Boolean(list_elements(interpreter, this).into_iter().any(|id| has_text(interpreter, id)))
+        },
+        (ELEMENTS, "val:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(list_elements(interpreter, this).first().map(|id| value(interpreter, *id)).unwrap_or_default())
+        },
+        (ELEMENTS, "eachAttr:(Ljava/lang/String;)Ljava/util/List;") => |interpreter, this, args| {
+            let key = string_arg(&args, 0).unwrap_or_default();
+            let values = list_elements(interpreter, this).into_iter()
+                .filter(|id| has_attr(interpreter, Some(*id), &key))
+                .map(|id| DexValue::String(attr(interpreter, Some(id), &key)))
+                .collect();
+            DexValue::Object(new_list(interpreter, values))
+        },
+        (ELEMENTS, "eachText:()Ljava/util/List;") => |interpreter, this, _| {
+            let texts = list_elements(interpreter, this).into_iter()
+                .filter(|id| has_text(interpreter, *id))
+                .map(|id| DexValue::String(text(interpreter, id)))
+                .collect();
+            DexValue::Object(new_list(interpreter, texts))
+        },
+        (ELEMENTS, "first:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| optional(list_elements(interpreter, this).first().copied()),
+        (ELEMENTS, "last:()Lorg/jsoup/nodes/Element;") => |interpreter, this, _| optional(list_elements(interpreter, this).last().copied()),
+        (ELEMENTS, "eq:(I)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let element = usize::try_from(int_arg(&args, 0)).ok().and_then(|index| list_elements(interpreter, this).get(index).copied());
+            new_elements(interpreter, element.into_iter().collect())
+        },
+        (ELEMENTS, "select:(Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let Some(selector) = selector_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            let selected = list_elements(interpreter, this).into_iter().flat_map(|id| selector.select(interpreter, id)).collect();
+            new_elements(interpreter, distinct(selected))
+        },
+        (ELEMENTS, "not:(Ljava/lang/String;)Lorg/jsoup/select/Elements;") => |interpreter, this, args| {
+            let Some(selector) = selector_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            let elements = list_elements(interpreter, this);
+            let kept = elements.into_iter().filter(|id| !selector.matches(interpreter, *id, *id)).collect();
+            new_elements(interpreter, kept)
+        },
+        (ELEMENTS, "is:(Ljava/lang/String;)Z") => |interpreter, this, args| {
+            let Some(selector) = selector_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            DexValue::Boolean(list_elements(interpreter, this).into_iter().any(|id| selector.matches(interpreter, id, id)))
+        },
+        (ELEMENTS, "parents:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            let parents = list_elements(interpreter, this).into_iter().flat_map(|id| ancestors(interpreter, id)).collect();
+            new_elements(interpreter, distinct(parents))
+        },
+        (ELEMENTS, "next:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            let next = list_elements(interpreter, this).into_iter().filter_map(|id| sibling(interpreter, Some(id), &element_children, 1)).collect();
+            new_elements(interpreter, distinct(next))
+        },
+        (ELEMENTS, "prev:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            let previous = list_elements(interpreter, this).into_iter().filter_map(|id| sibling(interpreter, Some(id), &element_children, -1)).collect();
+            new_elements(interpreter, distinct(previous))
+        },
+        (ELEMENTS, "remove:()Lorg/jsoup/select/Elements;") => |interpreter, this, _| {
+            for id in list_elements(interpreter, this) {
+                detach(interpreter, id);
+            }
+            this_value(this)
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// Superclasses of the node classes, Elements being a list
+pub fn builtin_super_class(class_name: &str) -> Option<&'static str> {
+    match class_name {
+        DOCUMENT => Some(ELEMENT),
+        ELEMENT | TEXT_NODE | DATA_NODE | COMMENT | DOCUMENT_TYPE => Some(NODE),
+        NODE => Some("Ljava/lang/Object;"),
+        ELEMENTS => Some(crate::interpreter::collections::ARRAY_LIST),
+        _ => None,
+    }
+}
+
+fn this_value(this: Option<ObjectId>) -> DexValue {
+    this.map_or(DexValue::Null, DexValue::Object)
+}
+
+fn optional(id: Option<ObjectId>) -> DexValue {
+    id.map_or(DexValue::Null, DexValue::Object)
+}
+
+fn string_arg(args: &[DexValue], index: usize) -> Option<String> {
+    match args.get(index) {
+        Some(DexValue::String(text)) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> i32 {
+    args.get(index).and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+fn text_field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> Option<String> {
+    match this.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(name)) {
+        Some(DexValue::String(text)) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+/// The selector of the query in `args[0]`, `None` with the exception thrown if it doesn't parse
+fn selector_arg(interpreter: &mut Interpreter, args: &[DexValue]) -> Option<std::sync::Arc<Selector>> {
+    let query = string_arg(args, 0).unwrap_or_default();
+    if query.trim().is_empty() {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "String must not be empty");
+        return None;
+    }
+    match Selector::parse(&query) {
+        Ok(selector) => Some(selector),
+        Err(message) => {
+            interpreter.throw_new(SELECTOR_PARSE_EXCEPTION, &message);
+            None
+        }
+    }
+}
+
+/// A Document of `html`, links resolving against `base_uri`
+pub fn parse(interpreter: &mut Interpreter, html: &str, base_uri: &str) -> ObjectId {
+    let document = interpreter.alloc_object(DOCUMENT);
+    let object = interpreter.heap.get_mut(&document).unwrap();
+    object.fields.insert(TAG_FIELD.to_string(), DexValue::String("#root".to_string()));
+    object.fields.insert(LOCATION_FIELD.to_string(), DexValue::String(base_uri.to_string()));
+    object.fields.insert(BASE_URI_FIELD.to_string(), DexValue::String(base_uri.to_string()));
+    object.array = Some(Vec::new());
+    build(interpreter, &Html::parse_document(html), false, document);
+
+    // The first <base href> changes what links resolve against, like in a browser
+    let base = descendant_elements(interpreter, document).into_iter()
+        .find(|id| tag_name(interpreter, *id) == Some("base") && attribute(interpreter, *id, "href").is_some());
+    if let Some(base) = base {
+        let href = abs_url(interpreter, Some(base), "href");
+        if !href.is_empty() {
+            interpreter.heap.get_mut(&document).unwrap().fields.insert(BASE_URI_FIELD.to_string(), DexValue::String(href));
+        }
+    }
+    document
+}
+
+/// Copy the nodes html5ever parsed under `parent`, those of the fragment's context element if
+/// `fragment` is set
+fn build(interpreter: &mut Interpreter, parsed: &Html, fragment: bool, parent: ObjectId) {
+    let root = if fragment { parsed.tree.root().first_child() } else { Some(parsed.tree.root()) };
+    let mut pending: Vec<_> = root.into_iter().flat_map(|root| root.children().rev()).map(|node| (node, parent)).collect();
+    while let Some((node, parent)) = pending.pop() {
+        let id = match node.value() {
+            Node::Element(element) => {
+                let attributes = element.attrs.iter()
+                    .flat_map(|(name, value)| {
+                        let name = match &name.prefix {
+                            Some(prefix) => format!("{}:{}", prefix, name.local),
+                            None => name.local.to_string(),
+                        };
+                        [DexValue::String(name), DexValue::String(value.to_string())]
+                    })
+                    .collect();
+                let id = interpreter.alloc_object(ELEMENT);
+                let object = interpreter.heap.get_mut(&id).unwrap();
+                object.fields.insert(TAG_FIELD.to_string(), DexValue::String(element.name().to_string()));
+                object.fields.insert(ATTRIBUTES_FIELD.to_string(), DexValue::Array(attributes));
+                object.array = Some(Vec::new());
+                pending.extend(node.children().rev().map(|child| (child, id)));
+                id
+            }
+            // Jsoup keeps the contents of scripts and styles as data rather than text
+            Node::Text(text) if matches!(tag_name(interpreter, parent), Some("script" | "style")) => {
+                new_node(interpreter, DATA_NODE, &text.text)
+            }
+            Node::Text(text) => new_node(interpreter, TEXT_NODE, &text.text),
+            Node::Comment(comment) => new_node(interpreter, COMMENT, &comment.comment),
+            Node::Doctype(doctype) => {
+                let mut declaration = doctype.name().to_string();
+                if !doctype.public_id().is_empty() {
+                    declaration.push_str(&format!(" PUBLIC \"{}\"", doctype.public_id()));
+                } else if !doctype.system_id().is_empty() {
+                    declaration.push_str(" SYSTEM");
+                }
+                if !doctype.system_id().is_empty() {
+                    declaration.push_str(&format!(" \"{}\"", doctype.system_id()));
+                }
+                new_node(interpreter, DOCUMENT_TYPE, &declaration)
+            }
+            _ => continue,
+        };
+        append_child(interpreter, parent, id);
+    }
+}
+
+fn new_node(interpreter: &mut Interpreter, class_name: &str, text: &str) -> ObjectId {
+    let id = interpreter.alloc_object(class_name);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(TEXT_FIELD.to_string(), DexValue::String(text.to_string()));
+    id
+}
+
+fn append_child(interpreter: &mut Interpreter, parent: ObjectId, child: ObjectId) {
+    if let Some(object) = interpreter.heap.get_mut(&child) {
+        object.fields.insert(PARENT_FIELD.to_string(), DexValue::Object(parent));
+    }
+    if let Some(object) = interpreter.heap.get_mut(&parent) {
+        object.array.get_or_insert_with(Vec::new).push(DexValue::Object(child));
+    }
+}
+
+/// Take the node out of its parent
+fn detach(interpreter: &mut Interpreter, id: ObjectId) {
+    let Some(parent) = parent_of(interpreter, id) else {
+        return;
+    };
+    if let Some(children) = interpreter.heap.get_mut(&parent).and_then(|object| object.array.as_mut()) {
+        children.retain(|child| *child != DexValue::Object(id));
+    }
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(PARENT_FIELD.to_string(), DexValue::Null);
+}
+
+fn clear_children(interpreter: &mut Interpreter, id: ObjectId) {
+    for child in child_nodes(interpreter, id) {
+        detach(interpreter, child);
+    }
+}
+
+/// `Response.asJsoup(html)`: the document of `html` or else of the body, for the request's url
+fn as_jsoup(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(DexValue::Object(response)) = args.first().cloned() else {
+        return DexValue::Void;
+    };
+    let html = match string_arg(&args, 1) {
+        Some(html) => html,
+        None => {
+            let body = interpreter.invoke_virtual(response, "body:()Lokhttp3/ResponseBody;", Vec::new());
+            match body.and_then(|body| match body {
+                DexValue::Object(body) => interpreter.invoke_virtual(body, "string:()Ljava/lang/String;", Vec::new()),
+                _ => None,
+            }) {
+                Some(DexValue::String(html)) => html,
+                _ => return DexValue::Void,
+            }
+        }
+    };
+    let request = interpreter.invoke_virtual(response, "request:()Lokhttp3/Request;", Vec::new());
+    let url = match request {
+        Some(DexValue::Object(request)) => interpreter.invoke_virtual(request, "url:()Lokhttp3/HttpUrl;", Vec::new()),
+        _ => None,
+    };
+    let base_uri = match url {
+        Some(DexValue::Object(url)) => interpreter.invoke_virtual(url, "toString:()Ljava/lang/String;", Vec::new()),
+        _ => None,
+    };
+    let base_uri = match base_uri {
+        Some(DexValue::String(base_uri)) => base_uri,
+        _ => String::new(),
+    };
+    DexValue::Object(parse(interpreter, &html, &base_uri))
+}
+
+fn new_elements(interpreter: &mut Interpreter, elements: Vec<ObjectId>) -> DexValue {
+    let id = interpreter.alloc_object(ELEMENTS);
+    interpreter.heap.get_mut(&id).unwrap().array = Some(elements.into_iter().map(DexValue::Object).collect());
+    DexValue::Object(id)
+}
+
+/// The elements of the Elements `this`
+fn list_elements(interpreter: &Interpreter, this: Option<ObjectId>) -> Vec<ObjectId> {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.array.as_deref())
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|value| match value {
+            DexValue::Object(id) => Some(*id),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Elements of `this` and its descendants for which `predicate` holds
+fn elements_where(interpreter: &mut Interpreter, this: Option<ObjectId>, predicate: impl Fn(&Interpreter, ObjectId) -> bool) -> DexValue {
+    let elements = this.map(|root| descendant_elements(interpreter, root)).unwrap_or_default()
+        .into_iter()
+        .filter(|id| predicate(interpreter, *id))
+        .collect();
+    new_elements(interpreter, elements)
+}
+
+fn distinct(ids: Vec<ObjectId>) -> Vec<ObjectId> {
+    let mut seen = HashSet::new();
+    ids.into_iter().filter(|id| seen.insert(*id)).collect()
+}
+
+fn child_at(interpreter: &mut Interpreter, children: &[ObjectId], index: i32) -> DexValue {
+    match usize::try_from(index).ok().and_then(|index| children.get(index)) {
+        Some(child) => DexValue::Object(*child),
+        None => {
+            let message = format!("Index {} out of bounds for length {}", index, children.len());
+            interpreter.throw_new(INDEX_OUT_OF_BOUNDS_EXCEPTION, &message);
+            DexValue::Void
+        }
+    }
+}
+
+fn class_of(interpreter: &Interpreter, id: ObjectId) -> &str {
+    interpreter.heap.get(&id).map(|object| object.class_name.as_str()).unwrap_or("")
+}
+
+pub fn is_document(interpreter: &Interpreter, id: ObjectId) -> bool {
+    class_of(interpreter, id) == DOCUMENT
+}
+
+pub fn is_element(interpreter: &Interpreter, id: ObjectId) -> bool {
+    matches!(class_of(interpreter, id), ELEMENT | DOCUMENT)
+}
+
+pub fn tag_name(interpreter: &Interpreter, id: ObjectId) -> Option<&str> {
+    match interpreter.heap.get(&id)?.fields.get(TAG_FIELD)? {
+        DexValue::String(tag) => Some(tag),
+        _ => None,
+    }
+}
+
+fn node_name(interpreter: &Interpreter, this: Option<ObjectId>) -> String {
+    let Some(id) = this else {
+        return String::new();
+    };
+    match class_of(interpreter, id) {
+        DOCUMENT => "#document".to_string(),
+        TEXT_NODE => "#text".to_string(),
+        DATA_NODE => "#data".to_string(),
+        COMMENT => "#comment".to_string(),
+        DOCUMENT_TYPE => "#doctype".to_string(),
+        _ => tag_name(interpreter, id).unwrap_or_default().to_string(),
+    }
+}
+
+pub fn parent_of(interpreter: &Interpreter, id: ObjectId) -> Option<ObjectId> {
+    match interpreter.heap.get(&id)?.fields.get(PARENT_FIELD)? {
+        DexValue::Object(parent) => Some(*parent),
+        _ => None,
+    }
+}
+
+fn root_of(interpreter: &Interpreter, id: ObjectId) -> ObjectId {
+    let mut root = id;
+    while let Some(parent) = parent_of(interpreter, root) {
+        root = parent;
+    }
+    root
+}
+
+/// Ancestors up to the html element, closest first
+fn ancestors(interpreter: &Interpreter, id: ObjectId) -> Vec<ObjectId> {
+    let mut ancestors = Vec::new();
+    let mut current = parent_of(interpreter, id);
+    while let Some(parent) = current.filter(|parent| !is_document(interpreter, *parent)) {
+        ancestors.push(parent);
+        current = parent_of(interpreter, parent);
+    }
+    ancestors
+}
+
+fn child_nodes(interpreter: &Interpreter, id: ObjectId) -> Vec<ObjectId> {
+    interpreter.heap.get(&id)
+        .and_then(|object| object.array.as_deref())
+        .unwrap_or(&[])
+        .iter()
+        .filter_map(|child| match child {
+            DexValue::Object(id) => Some(*id),
+            _ => None,
+        })
+        .collect()
+}
+
+pub fn element_children(interpreter: &Interpreter, id: ObjectId) -> Vec<ObjectId> {
+    child_nodes(interpreter, id).into_iter().filter(|child| is_element(interpreter, *child)).collect()
+}
+
+/// The element children of the parent, the element among them
+fn element_siblings(interpreter: &Interpreter, this: Option<ObjectId>) -> Vec<ObjectId> {
+    match this.and_then(|id| parent_of(interpreter, id)) {
+        Some(parent) => element_children(interpreter, parent),
+        None => this.into_iter().collect(),
+    }
+}
+
+/// Index of the node among the children `children` lists for its parent
+fn sibling_index(interpreter: &Interpreter, this: Option<ObjectId>, children: &dyn Fn(&Interpreter, ObjectId) -> Vec<ObjectId>) -> Option<usize> {
+    let id = this?;
+    let parent = parent_of(interpreter, id)?;
+    children(interpreter, parent).iter().position(|child| *child == id)
+}
+
+/// The sibling `offset` places away among the children `children` lists
+fn sibling(interpreter: &Interpreter, this: Option<ObjectId>, children: &dyn Fn(&Interpreter, ObjectId) -> Vec<ObjectId>, offset: isize) -> Option<ObjectId> {
+    let parent = parent_of(interpreter, this?)?;
+    let index = sibling_index(interpreter, this, children)?;
+    children(interpreter, parent).get(index.checked_add_signed(offset)?).copied()
+}
+
+/// The element and its descendant elements in document order
+pub fn descendant_elements(interpreter: &Interpreter, root: ObjectId) -> Vec<ObjectId> {
+    let mut elements = Vec::new();
+    let mut pending = vec![root];
+    while let Some(id) = pending.pop() {
+        if is_element(interpreter, id) {
+            elements.push(id);
+            pending.extend(child_nodes(interpreter, id).into_iter().rev());
+        }
+    }
+    elements
+}
+
+/// Child of the document's html element with one of the `tags`
+fn document_child(interpreter: &Interpreter, this: Option<ObjectId>, tags: &[&str]) -> Option<ObjectId> {
+    let html = element_children(interpreter, this?).into_iter().find(|id| tag_name(interpreter, *id) == Some("html"))?;
+    element_children(interpreter, html).into_iter().find(|id| tag_name(interpreter, *id).is_some_and(|tag| tags.contains(&tag)))
+}
+
+pub fn attributes(interpreter: &Interpreter, id: ObjectId) -> Vec<(String, String)> {
+    let Some(DexValue::Array(pairs)) = interpreter.heap.get(&id).and_then(|object| object.fields.get(ATTRIBUTES_FIELD)) else {
+        return Vec::new();
+    };
+    pairs.chunks(2)
+        .map(|pair| {
+            let text = |value: Option<&DexValue>| match value {
+                Some(DexValue::String(text)) => text.clone(),
+                _ => String::new(),
+            };
+            (text(pair.first()), text(pair.get(1)))
+        })
+        .collect()
+}
+
+fn set_attributes(interpreter: &mut Interpreter, id: ObjectId, pairs: Vec<(String, String)>) {
+    if let Some(object) = interpreter.heap.get_mut(&id) {
+        let pairs = pairs.into_iter().flat_map(|(name, value)| [DexValue::String(name), DexValue::String(value)]).collect();
+        object.fields.insert(ATTRIBUTES_FIELD.to_string(), DexValue::Array(pairs));
+    }
+}
+
+/// Value of the attribute `key`, whose case doesn't matter
+pub fn attribute(interpreter: &Interpreter, id: ObjectId, key: &str) -> Option<String> {
+    attributes(interpreter, id).into_iter().find(|(name, _)| name.eq_ignore_ascii_case(key)).map(|(_, value)| value)
+}
+
+fn set_attribute(interpreter: &mut Interpreter, this: Option<ObjectId>, key: &str, value: &str) {
+    let Some(id) = this.filter(|id| is_element(interpreter, *id)) else {
+        return;
+    };
+    let key = key.trim().to_lowercase();
+    let mut pairs = attributes(interpreter, id);
+    match pairs.iter_mut().find(|(name, _)| name.eq_ignore_ascii_case(&key)) {
+        Some(pair) => pair.1 = value.to_string(),
+        None => pairs.push((key, value.to_string())),
+    }
+    set_attributes(interpreter, id, pairs);
+}
+
+fn remove_attribute(interpreter: &mut Interpreter, this: Option<ObjectId>, key: &str) {
+    let Some(id) = this else {
+        return;
+    };
+    let mut pairs = attributes(interpreter, id);
+    pairs.retain(|(name, _)| !name.eq_ignore_ascii_case(key));
+    set_attributes(interpreter, id, pairs);
+}
+
+/// `attr(key)`: the value or an empty string, `abs:` in front of the key asks for the value
+/// as an absolute url
+fn attr(interpreter: &Interpreter, this: Option<ObjectId>, key: &str) -> String {
+    let value = this.and_then(|id| attribute(interpreter, id, key)).unwrap_or_default();
+    match key.get(..4) {
+        Some(prefix) if value.is_empty() && prefix.eq_ignore_ascii_case("abs:") => abs_url(interpreter, this, &key[4..]),
+        _ => value,
+    }
+}
+
+fn has_attr(interpreter: &Interpreter, this: Option<ObjectId>, key: &str) -> bool {
+    if let Some(prefix) = key.get(..4).filter(|prefix| prefix.eq_ignore_ascii_case("abs:")) {
+        let key = &key[prefix.len()..];
+        if this.is_some_and(|id| attribute(interpreter, id, key).is_some()) && !abs_url(interpreter, this, key).is_empty() {
+            return true;
+        }
+    }
+    this.is_some_and(|id| attribute(interpreter, id, key).is_some())
+}
+
+pub fn has_class(interpreter: &Interpreter, id: ObjectId, class: &str) -> bool {
+    let class = class.to_lowercase();
+    attribute(interpreter, id, "class").is_some_and(|classes| classes.split_whitespace().any(|name| name.to_lowercase() == class))
+}
+
+fn value(interpreter: &Interpreter, id: ObjectId) -> String {
+    if tag_name(interpreter, id) == Some("textarea") {
+        text(interpreter, id)
+    } else {
+        attribute(interpreter, id, "value").unwrap_or_default()
+    }
+}
+
+fn base_uri(interpreter: &Interpreter, this: Option<ObjectId>) -> String {
+    let document = this.map(|id| root_of(interpreter, id));
+    text_field(interpreter, document, BASE_URI_FIELD).unwrap_or_default()
+}
+
+/// The attribute `key` resolved against the base uri, empty if there's no such attribute or
+/// it can't be made absolute
+fn abs_url(interpreter: &Interpreter, this: Option<ObjectId>, key: &str) -> String {
+    let Some(link) = this.and_then(|id| attribute(interpreter, id, key)) else {
+        return String::new();
+    };
+    // java.net.URL ignores control chars and surrounding whitespace, and takes chars a URI can't
+    // have that the canonicalizing below encodes
+    let link: String = link.chars().filter(|c| !c.is_ascii_control()).collect();
+    let link = http::canonicalize(link.trim(), " \"<>\\^`{|}", true);
+    let base = base_uri(interpreter, this);
+    match resolve_reference(&base, &link) {
+        Some(resolved) if !base.is_empty() => resolved,
+        _ if has_scheme(&link) => link,
+        _ => String::new(),
+    }
+}
+
+fn has_scheme(link: &str) -> bool {
+    let scheme = link.split([':', '/', '?', '#']).next().unwrap_or("");
+    link[scheme.len()..].starts_with(':')
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Whether the element has no child nodes besides blank text and comments, for `:empty`
+pub fn is_empty(interpreter: &Interpreter, id: ObjectId) -> bool {
+    child_nodes(interpreter, id).into_iter().all(|child| match class_of(interpreter, child) {
+        TEXT_NODE => is_blank(&text_field(interpreter, Some(child), TEXT_FIELD).unwrap_or_default()),
+        COMMENT | DOCUMENT_TYPE => true,
+        _ => false,
+    })
+}
+
+fn has_text(interpreter: &Interpreter, id: ObjectId) -> bool {
+    let mut pending = vec![id];
+    while let Some(node) = pending.pop() {
+        match class_of(interpreter, node) {
+            TEXT_NODE if !is_blank(&text_field(interpreter, Some(node), TEXT_FIELD).unwrap_or_default()) => return true,
+            ELEMENT | DOCUMENT => pending.extend(child_nodes(interpreter, node)),
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Whitespace to Jsoup's StringUtil: what `isBlank()` skips
+fn is_blank(text: &str) -> bool {
+    text.chars().all(|c| matches!(c, ' ' | '\t' | '\n' | '\x0c' | '\r'))
+}
+
+/// Append `text` with runs of whitespace, no-break spaces included, collapsed to one space and
+/// zero-width chars dropped, leading whitespace too if `strip_leading` is set
+fn append_normalized(accumulated: &mut String, text: &str, strip_leading: bool) {
+    let mut last_was_white = false;
+    let mut reached_non_white = false;
+    for c in text.chars() {
+        if matches!(c, ' ' | '\t' | '\n' | '\x0c' | '\r' | '\u{a0}') {
+            if (strip_leading && !reached_non_white) || last_was_white {
+                continue;
+            }
+            accumulated.push(' ');
+            last_was_white = true;
+        } else if !matches!(c, '\u{200b}' | '\u{ad}') {
+            accumulated.push(c);
+            last_was_white = false;
+            reached_non_white = true;
+        }
+    }
+}
+
+/// `text` with runs of whitespace collapsed to one space
+pub fn normalize_whitespace(text: &str) -> String {
+    let mut normalized = String::new();
+    append_normalized(&mut normalized, text, false);
+    normalized
+}
+
+/// Append the text node's text, normalized unless it's in a `<pre>` or the like
+fn append_text_node(interpreter: &Interpreter, accumulated: &mut String, id: ObjectId) {
+    let text = text_field(interpreter, Some(id), TEXT_FIELD).unwrap_or_default();
+    let mut ancestor = parent_of(interpreter, id);
+    for _ in 0..6 {
+        let Some(current) = ancestor else {
+            break;
+        };
+        if tag_name(interpreter, current).is_some_and(|tag| PRESERVE_WHITESPACE_TAGS.contains(&tag)) {
+            accumulated.push_str(&text);
+            return;
+        }
+        ancestor = parent_of(interpreter, current);
+    }
+    let strip_leading = accumulated.ends_with(' ');
+    append_normalized(accumulated, &text, strip_leading);
+}
+
+/// String.trim(), which drops every char up to a space
+fn java_trim(text: &str) -> &str {
+    text.trim_matches(|c: char| c <= ' ')
+}
+
+fn is_block(interpreter: &Interpreter, id: ObjectId) -> bool {
+    tag_name(interpreter, id).is_some_and(|tag| BLOCK_TAGS.contains(&tag))
+}
+
+/// `text()`: the text of the element and its descendants, normalized, with blocks and line
+/// breaks keeping their words apart
+pub fn text(interpreter: &Interpreter, id: ObjectId) -> String {
+    enum Visit {
+        Head(ObjectId),
+        Tail(ObjectId),
+    }
+    let mut accumulated = String::new();
+    let mut pending = vec![Visit::Head(id)];
+    while let Some(visit) = pending.pop() {
+        match visit {
+            Visit::Head(node) => match class_of(interpreter, node) {
+                TEXT_NODE => append_text_node(interpreter, &mut accumulated, node),
+                ELEMENT | DOCUMENT => {
+                    let separates = is_block(interpreter, node) || tag_name(interpreter, node) == Some("br");
+                    if !accumulated.is_empty() && separates && !accumulated.ends_with(' ') {
+                        accumulated.push(' ');
+                    }
+                    pending.push(Visit::Tail(node));
+                    pending.extend(child_nodes(interpreter, node).into_iter().rev().map(Visit::Head));
+                }
+                _ => {}
+            },
+            Visit::Tail(node) => {
+                let next_is_text = sibling(interpreter, Some(node), &child_nodes, 1)
+                    .is_some_and(|next| class_of(interpreter, next) == TEXT_NODE);
+                if is_block(interpreter, node) && next_is_text && !accumulated.ends_with(' ') {
+                    accumulated.push(' ');
+                }
+            }
+        }
+    }
+    java_trim(&accumulated).to_string()
+}
+
+/// `ownText()`: the text of the element's own text nodes
+pub fn own_text(interpreter: &Interpreter, id: ObjectId) -> String {
+    let mut accumulated = String::new();
+    for child in child_nodes(interpreter, id) {
+        match class_of(interpreter, child) {
+            TEXT_NODE => append_text_node(interpreter, &mut accumulated, child),
+            ELEMENT if tag_name(interpreter, child) == Some("br") && !accumulated.ends_with(' ') => accumulated.push(' '),
+            _ => {}
+        }
+    }
+    java_trim(&accumulated).to_string()
+}
+
+/// `wholeText()`: the text as it is in the html, line breaks included
+pub fn whole_text(interpreter: &Interpreter, id: ObjectId) -> String {
+    let mut accumulated = String::new();
+    let mut pending = vec![id];
+    while let Some(node) = pending.pop() {
+        match class_of(interpreter, node) {
+            TEXT_NODE => accumulated.push_str(&text_field(interpreter, Some(node), TEXT_FIELD).unwrap_or_default()),
+            ELEMENT | DOCUMENT => {
+                if tag_name(interpreter, node) == Some("br") {
+                    accumulated.push('\n');
+                }
+                pending.extend(child_nodes(interpreter, node).into_iter().rev());
+            }
+            _ => {}
+        }
+    }
+    accumulated
+}
+
+/// `data()`: the contents of the scripts, styles and comments in the element
+pub fn data(interpreter: &Interpreter, id: ObjectId) -> String {
+    let mut accumulated = String::new();
+    let mut pending: Vec<ObjectId> = child_nodes(interpreter, id).into_iter().rev().collect();
+    while let Some(node) = pending.pop() {
+        match class_of(interpreter, node) {
+            DATA_NODE | COMMENT => accumulated.push_str(&text_field(interpreter, Some(node), TEXT_FIELD).unwrap_or_default()),
+            ELEMENT => pending.extend(child_nodes(interpreter, node).into_iter().rev()),
+            _ => {}
+        }
+    }
+    accumulated
+}
+
+/// Html of the node, with the element's own tags if `outer` is set
+pub fn html(interpreter: &Interpreter, id: ObjectId, outer: bool) -> String {
+    enum Write {
+        Node(ObjectId),
+        EndTag(String),
+    }
+    let mut html = String::new();
+    let mut pending = if outer || !is_element(interpreter, id) {
+        vec![Write::Node(id)]
+    } else {
+        child_nodes(interpreter, id).into_iter().rev().map(Write::Node).collect()
+    };
+    while let Some(write) = pending.pop() {
+        let node = match write {
+            Write::Node(node) => node,
+            Write::EndTag(tag) => {
+                html.push_str(&format!("</{}>", tag));
+                continue;
+            }
+        };
+        let text = || text_field(interpreter, Some(node), TEXT_FIELD).unwrap_or_default();
+        match class_of(interpreter, node) {
+            TEXT_NODE => escape(&mut html, &text(), false),
+            DATA_NODE => html.push_str(&text()),
+            COMMENT => html.push_str(&format!("<!--{}-->", text())),
+            DOCUMENT_TYPE => html.push_str(&format!("<!doctype {}>", text())),
+            DOCUMENT => pending.extend(child_nodes(interpreter, node).into_iter().rev().map(Write::Node)),
+            _ => {
+                let tag = tag_name(interpreter, node).unwrap_or_default().to_string();
+                html.push('<');
+                html.push_str(&tag);
+                for (name, value) in attributes(interpreter, node) {
+                    html.push(' ');
+                    html.push_str(&name);
+                    let collapses = BOOLEAN_ATTRIBUTES.contains(&name.to_lowercase().as_str())
+                        && (value.is_empty() || value.eq_ignore_ascii_case(&name));
+                    if !collapses {
+                        html.push_str("=\"");
+                        escape(&mut html, &value, true);
+                        html.push('"');
+                    }
+                }
+                html.push('>');
+                if !VOID_TAGS.contains(&tag.as_str()) {
+                    pending.push(Write::EndTag(tag));
+                    pending.extend(child_nodes(interpreter, node).into_iter().rev().map(Write::Node));
+                }
+            }
+        }
+    }
+    html
+}
+
+/// Append `text` with the chars html gives a meaning escaped, those of an attribute value if
+/// `attribute` is set
+fn escape(html: &mut String, text: &str, attribute: bool) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '\u{a0}' => html.push_str("&nbsp;"),
+            '"' if attribute => html.push_str("&quot;"),
+            '<' if !attribute => html.push_str("&lt;"),
+            '>' if !attribute => html.push_str("&gt;"),
+            c => html.push(c),
+        }
+    }
+}
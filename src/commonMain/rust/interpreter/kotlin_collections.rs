@@ -0,0 +1,526 @@
+// The kotlin.collections facade classes, CollectionsKt, MapsKt and SetsKt, the kotlin.Pair
+// that mapOf() takes and the kotlin.Lazy behind `by lazy`. Extensions link against the stdlib rather than bundling it, so these run
+// on the java.util shims: every list is an ArrayList, every map a LinkedHashMap and every set a
+// LinkedHashSet. The read-only and empty collections Kotlin returns are mutable here, nothing
+// a working extension does can tell. Sequences are eager lists as well, so the SequencesKt
+// operators run their lambda over every element up front.
+
+use crate::interpreter::boxing::unboxed;
+use crate::interpreter::collections::{elements_of, map_entries, new_list, new_map, new_set};
+use crate::interpreter::exception::{ILLEGAL_ARGUMENT_EXCEPTION, NO_SUCH_ELEMENT_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{self, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const COLLECTIONS_KT: &str = "Lkotlin/collections/CollectionsKt;";
+pub const MAPS_KT: &str = "Lkotlin/collections/MapsKt;";
+pub const SETS_KT: &str = "Lkotlin/collections/SetsKt;";
+pub const SEQUENCES_KT: &str = "Lkotlin/sequences/SequencesKt;";
+pub const TUPLES_KT: &str = "Lkotlin/TuplesKt;";
+pub const PAIR: &str = "Lkotlin/Pair;";
+pub const LAZY_KT: &str = "Lkotlin/LazyKt;";
+pub const LAZY: &str = "Lkotlin/SynchronizedLazyImpl;";
+
+const FIRST_FIELD: &str = "first";
+const SECOND_FIELD: &str = "second";
+const INITIALIZER_FIELD: &str = "initializer";
+/// Result of the initializer once it ran, absent before
+const VALUE_FIELD: &str = "value";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (COLLECTIONS_KT, "listOf:(Ljava/lang/Object;)Ljava/util/List;") => list_of_element,
+        (COLLECTIONS_KT, "listOf:([Ljava/lang/Object;)Ljava/util/List;"
+            | "mutableListOf:([Ljava/lang/Object;)Ljava/util/List;"
+            | "arrayListOf:([Ljava/lang/Object;)Ljava/util/ArrayList;") => list_of,
+        (COLLECTIONS_KT, "emptyList:()Ljava/util/List;") => empty_list,
+        (COLLECTIONS_KT, "listOfNotNull:(Ljava/lang/Object;)Ljava/util/List;") => list_of_not_null_element,
+        (COLLECTIONS_KT, "listOfNotNull:([Ljava/lang/Object;)Ljava/util/List;") => list_of_not_null,
+        (COLLECTIONS_KT, "first:(Ljava/util/List;)Ljava/lang/Object;" | "first:(Ljava/lang/Iterable;)Ljava/lang/Object;") => first,
+        (COLLECTIONS_KT, "firstOrNull:(Ljava/util/List;)Ljava/lang/Object;" | "firstOrNull:(Ljava/lang/Iterable;)Ljava/lang/Object;") => first_or_null,
+        (COLLECTIONS_KT, "last:(Ljava/util/List;)Ljava/lang/Object;" | "last:(Ljava/lang/Iterable;)Ljava/lang/Object;") => last,
+        (COLLECTIONS_KT, "lastOrNull:(Ljava/util/List;)Ljava/lang/Object;" | "lastOrNull:(Ljava/lang/Iterable;)Ljava/lang/Object;") => last_or_null,
+        (COLLECTIONS_KT, "getOrNull:(Ljava/util/List;I)Ljava/lang/Object;") => get_or_null,
+        (COLLECTIONS_KT, "getLastIndex:(Ljava/util/List;)I") => last_index,
+        (COLLECTIONS_KT, "toList:(Ljava/lang/Iterable;)Ljava/util/List;"
+            | "toMutableList:(Ljava/lang/Iterable;)Ljava/util/List;"
+            | "toMutableList:(Ljava/util/Collection;)Ljava/util/List;") => to_list,
+        (COLLECTIONS_KT, "toSet:(Ljava/lang/Iterable;)Ljava/util/Set;"
+            | "toMutableSet:(Ljava/lang/Iterable;)Ljava/util/Set;"
+            | "toHashSet:(Ljava/lang/Iterable;)Ljava/util/HashSet;") => to_set,
+        (COLLECTIONS_KT, "reversed:(Ljava/lang/Iterable;)Ljava/util/List;") => reversed,
+        (COLLECTIONS_KT, "distinct:(Ljava/lang/Iterable;)Ljava/util/List;") => distinct,
+        (COLLECTIONS_KT, "plus:(Ljava/util/Collection;Ljava/lang/Object;)Ljava/util/List;") => plus_element,
+        (COLLECTIONS_KT, "plus:(Ljava/util/Collection;Ljava/lang/Iterable;)Ljava/util/List;") => plus_elements,
+        (COLLECTIONS_KT, "take:(Ljava/lang/Iterable;I)Ljava/util/List;") => take,
+        (COLLECTIONS_KT, "drop:(Ljava/lang/Iterable;I)Ljava/util/List;") => drop,
+        (COLLECTIONS_KT, "addAll:(Ljava/util/Collection;Ljava/lang/Iterable;)Z") => add_all,
+        (COLLECTIONS_KT, "joinToString:(Ljava/lang/Iterable;Ljava/lang/CharSequence;Ljava/lang/CharSequence;Ljava/lang/CharSequence;ILjava/lang/CharSequence;Lkotlin/jvm/functions/Function1;)Ljava/lang/String;") => join_to_string,
+        (COLLECTIONS_KT, "joinToString$default:(Ljava/lang/Iterable;Ljava/lang/CharSequence;Ljava/lang/CharSequence;Ljava/lang/CharSequence;ILjava/lang/CharSequence;Lkotlin/jvm/functions/Function1;ILjava/lang/Object;)Ljava/lang/String;") => join_to_string_default,
+        // Sizing helpers the inlined map {} and friends call
+        (COLLECTIONS_KT, "collectionSizeOrDefault:(Ljava/lang/Iterable;I)I") => collection_size_or_default,
+        (MAPS_KT, "mapCapacity:(I)I") => map_capacity,
+
+        (MAPS_KT, "mapOf:(Lkotlin/Pair;)Ljava/util/Map;") => map_of_pair,
+        (MAPS_KT, "mapOf:([Lkotlin/Pair;)Ljava/util/Map;"
+            | "mutableMapOf:([Lkotlin/Pair;)Ljava/util/Map;"
+            | "hashMapOf:([Lkotlin/Pair;)Ljava/util/HashMap;"
+            | "linkedMapOf:([Lkotlin/Pair;)Ljava/util/LinkedHashMap;") => map_of,
+        (MAPS_KT, "emptyMap:()Ljava/util/Map;") => empty_map,
+        (MAPS_KT, "toMap:(Ljava/lang/Iterable;)Ljava/util/Map;") => map_of,
+        (MAPS_KT, "plus:(Ljava/util/Map;Lkotlin/Pair;)Ljava/util/Map;") => map_plus_pair,
+        (MAPS_KT, "getValue:(Ljava/util/Map;Ljava/lang/Object;)Ljava/lang/Object;") => map_get_value,
+
+        (SETS_KT, "setOf:(Ljava/lang/Object;)Ljava/util/Set;") => set_of_element,
+        (SETS_KT, "setOf:([Ljava/lang/Object;)Ljava/util/Set;"
+            | "mutableSetOf:([Ljava/lang/Object;)Ljava/util/Set;"
+            | "hashSetOf:([Ljava/lang/Object;)Ljava/util/HashSet;"
+            | "linkedSetOf:([Ljava/lang/Object;)Ljava/util/LinkedHashSet;") => set_of,
+        (SETS_KT, "emptySet:()Ljava/util/Set;") => empty_set,
+
+        (SEQUENCES_KT, "toList:(Lkotlin/sequences/Sequence;)Ljava/util/List;"
+            | "toMutableList:(Lkotlin/sequences/Sequence;)Ljava/util/List;") => to_list,
+        (SEQUENCES_KT, "first:(Lkotlin/sequences/Sequence;)Ljava/lang/Object;") => first,
+        (SEQUENCES_KT, "firstOrNull:(Lkotlin/sequences/Sequence;)Ljava/lang/Object;") => first_or_null,
+        (SEQUENCES_KT, "count:(Lkotlin/sequences/Sequence;)I") => count,
+        (SEQUENCES_KT, "map:(Lkotlin/sequences/Sequence;Lkotlin/jvm/functions/Function1;)Lkotlin/sequences/Sequence;") => sequence_map,
+        (SEQUENCES_KT, "mapNotNull:(Lkotlin/sequences/Sequence;Lkotlin/jvm/functions/Function1;)Lkotlin/sequences/Sequence;") => sequence_map_not_null,
+        (SEQUENCES_KT, "filter:(Lkotlin/sequences/Sequence;Lkotlin/jvm/functions/Function1;)Lkotlin/sequences/Sequence;") => sequence_filter,
+
+        (TUPLES_KT, "to:(Ljava/lang/Object;Ljava/lang/Object;)Lkotlin/Pair;") => to_pair,
+        (PAIR, "<init>:(Ljava/lang/Object;Ljava/lang/Object;)V") => pair_init,
+        (PAIR, "getFirst:()Ljava/lang/Object;" | "component1:()Ljava/lang/Object;") => pair_first,
+        (PAIR, "getSecond:()Ljava/lang/Object;" | "component2:()Ljava/lang/Object;") => pair_second,
+        (PAIR, "equals:(Ljava/lang/Object;)Z") => pair_equals,
+        (PAIR, "toString:()Ljava/lang/String;") => pair_to_string,
+
+        // Every thread safety mode is the same without threads, the initializer is the last argument
+        (LAZY_KT, "lazy:(Lkotlin/jvm/functions/Function0;)Lkotlin/Lazy;"
+            | "lazy:(Lkotlin/LazyThreadSafetyMode;Lkotlin/jvm/functions/Function0;)Lkotlin/Lazy;"
+            | "lazy:(Ljava/lang/Object;Lkotlin/jvm/functions/Function0;)Lkotlin/Lazy;") => lazy,
+        (LAZY_KT, "lazyOf:(Ljava/lang/Object;)Lkotlin/Lazy;") => lazy_of,
+        (LAZY, "getValue:()Ljava/lang/Object;") => lazy_value,
+        (LAZY, "isInitialized:()Z") => |interpreter, this, _| DexValue::Boolean(lazy_result(interpreter, this).is_some()),
+        (LAZY, "toString:()Ljava/lang/String;") => lazy_to_string,
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn arg(args: &[DexValue], index: usize) -> DexValue {
+    args.get(index).cloned().unwrap_or(DexValue::Null)
+}
+
+/// Elements of the collection or vararg array passed as argument `index`
+fn elements_arg(interpreter: &Interpreter, args: &[DexValue], index: usize) -> Vec<DexValue> {
+    elements_of(interpreter, args.get(index).unwrap_or(&DexValue::Null))
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> i32 {
+    args.get(index).and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+/// Registers are untyped, a null reference can also be an Int(0)
+fn is_null(value: &DexValue) -> bool {
+    !matches!(value, DexValue::Object(_) | DexValue::String(_))
+}
+
+fn list(interpreter: &mut Interpreter, elements: Vec<DexValue>) -> DexValue {
+    DexValue::Object(new_list(interpreter, elements))
+}
+
+fn list_of_element(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    list(interpreter, vec![arg(&args, 0)])
+}
+
+fn list_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let elements = elements_arg(interpreter, &args, 0);
+    list(interpreter, elements)
+}
+
+fn empty_list(interpreter: &mut Interpreter, _this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    list(interpreter, Vec::new())
+}
+
+fn list_of_not_null_element(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let elements = Some(arg(&args, 0)).filter(|element| !is_null(element)).into_iter().collect();
+    list(interpreter, elements)
+}
+
+fn list_of_not_null(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let elements = elements_arg(interpreter, &args, 0).into_iter().filter(|element| !is_null(element)).collect();
+    list(interpreter, elements)
+}
+
+fn first(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match elements_arg(interpreter, &args, 0).into_iter().next() {
+        Some(element) => element,
+        None => {
+            interpreter.throw_new(NO_SUCH_ELEMENT_EXCEPTION, "List is empty.");
+            DexValue::Void
+        }
+    }
+}
+
+fn first_or_null(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    elements_arg(interpreter, &args, 0).into_iter().next().unwrap_or(DexValue::Null)
+}
+
+fn last(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match elements_arg(interpreter, &args, 0).pop() {
+        Some(element) => element,
+        None => {
+            interpreter.throw_new(NO_SUCH_ELEMENT_EXCEPTION, "List is empty.");
+            DexValue::Void
+        }
+    }
+}
+
+fn last_or_null(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    elements_arg(interpreter, &args, 0).pop().unwrap_or(DexValue::Null)
+}
+
+fn get_or_null(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let elements = elements_arg(interpreter, &args, 0);
+    usize::try_from(int_arg(&args, 1)).ok()
+        .and_then(|index| elements.get(index).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn last_index(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(elements_arg(interpreter, &args, 0).len() as i32 - 1)
+}
+
+fn to_list(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    list_of(interpreter, None, args)
+}
+
+fn to_set(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    set_of(interpreter, None, args)
+}
+
+fn reversed(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut elements = elements_arg(interpreter, &args, 0);
+    elements.reverse();
+    list(interpreter, elements)
+}
+
+fn distinct(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let elements = elements_arg(interpreter, &args, 0);
+    let set = new_set(interpreter, elements);
+    list_of(interpreter, None, vec![DexValue::Object(set)])
+}
+
+fn plus_element(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut elements = elements_arg(interpreter, &args, 0);
+    elements.push(arg(&args, 1));
+    list(interpreter, elements)
+}
+
+fn plus_elements(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut elements = elements_arg(interpreter, &args, 0);
+    elements.extend(elements_arg(interpreter, &args, 1));
+    list(interpreter, elements)
+}
+
+/// The element count of take() and drop(), which throw for a negative one
+fn count_arg(interpreter: &mut Interpreter, args: &[DexValue]) -> Option<usize> {
+    let count = int_arg(args, 1);
+    if count < 0 {
+        let message = format!("Requested element count {} is less than zero.", count);
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &message);
+        return None;
+    }
+    Some(count as usize)
+}
+
+fn take(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(count) = count_arg(interpreter, &args) else {
+        return DexValue::Void;
+    };
+    let elements = elements_arg(interpreter, &args, 0).into_iter().take(count).collect();
+    list(interpreter, elements)
+}
+
+fn drop(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(count) = count_arg(interpreter, &args) else {
+        return DexValue::Void;
+    };
+    let elements = elements_arg(interpreter, &args, 0).into_iter().skip(count).collect();
+    list(interpreter, elements)
+}
+
+/// `MutableCollection.addAll(Iterable)`, through the collection's own add() so sets stay distinct
+fn add_all(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let DexValue::Object(collection) = arg(&args, 0) else {
+        return DexValue::Boolean(false);
+    };
+    let class_name = interpreter.heap.get(&collection).map(|object| object.class_name.clone()).unwrap_or_default();
+    let Some(add) = native::platform_method(&class_name, "addAll:(Ljava/util/Collection;)Z") else {
+        return DexValue::Boolean(false);
+    };
+    let added = elements_arg(interpreter, &args, 1);
+    add(interpreter, Some(collection), vec![DexValue::Array(added)])
+}
+
+/// `joinToString(separator, prefix, postfix, limit, truncated, transform)`
+fn join_to_string(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let elements = elements_arg(interpreter, &args, 0);
+    let separator = interpreter.string_value_of(&arg(&args, 1));
+    let prefix = interpreter.string_value_of(&arg(&args, 2));
+    let postfix = interpreter.string_value_of(&arg(&args, 3));
+    let limit = int_arg(&args, 4);
+    let truncated = interpreter.string_value_of(&arg(&args, 5));
+    let transform = arg(&args, 6);
+
+    let mut texts = Vec::new();
+    for (index, element) in elements.iter().enumerate() {
+        if limit >= 0 && index as i32 >= limit {
+            texts.push(truncated);
+            break;
+        }
+        let text = match &transform {
+            DexValue::Object(_) => interpreter.invoke_function(&transform, vec![element.clone()]).unwrap_or(DexValue::Null),
+            _ => element.clone(),
+        };
+        texts.push(interpreter.string_value_of(&text));
+    }
+    DexValue::String(format!("{}{}{}", prefix, texts.join(&separator), postfix))
+}
+
+/// The `$default` stub: bit n of the mask stands for the default of parameter n
+fn join_to_string_default(interpreter: &mut Interpreter, this: Option<ObjectId>, mut args: Vec<DexValue>) -> DexValue {
+    let mask = int_arg(&args, 7);
+    let defaults = [
+        DexValue::String(", ".to_string()),
+        DexValue::String(String::new()),
+        DexValue::String(String::new()),
+        DexValue::Int(-1),
+        DexValue::String("...".to_string()),
+        DexValue::Null,
+    ];
+    for (bit, default) in defaults.into_iter().enumerate() {
+        if mask & (1 << bit) != 0 {
+            args[bit + 1] = default;
+        }
+    }
+    args.truncate(7);
+    join_to_string(interpreter, this, args)
+}
+
+fn collection_size_or_default(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match arg(&args, 0) {
+        DexValue::Object(_) => DexValue::Int(elements_arg(interpreter, &args, 0).len() as i32),
+        _ => DexValue::Int(int_arg(&args, 1)),
+    }
+}
+
+/// The HashMap capacity that holds `expectedSize` entries without rehashing
+fn map_capacity(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let expected = int_arg(&args, 0);
+    let capacity = match expected {
+        ..0 => expected,
+        0..3 => expected + 1,
+        3..0x4000_0000 => (expected as f32 / 0.75 + 1.0) as i32,
+        _ => i32::MAX,
+    };
+    DexValue::Int(capacity)
+}
+
+fn count(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(elements_arg(interpreter, &args, 0).len() as i32)
+}
+
+/// `function(element)` for each element, stopping at the first exception
+fn apply(interpreter: &mut Interpreter, args: &[DexValue]) -> Option<Vec<(DexValue, DexValue)>> {
+    let function = arg(args, 1);
+    let mut results = Vec::new();
+    for element in elements_arg(interpreter, args, 0) {
+        let result = interpreter.invoke_function(&function, vec![element.clone()]).unwrap_or(DexValue::Null);
+        if interpreter.pending_exception.is_some() {
+            return None;
+        }
+        results.push((element, result));
+    }
+    Some(results)
+}
+
+fn sequence_map(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match apply(interpreter, &args) {
+        Some(results) => list(interpreter, results.into_iter().map(|(_, result)| result).collect()),
+        None => DexValue::Void,
+    }
+}
+
+fn sequence_map_not_null(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match apply(interpreter, &args) {
+        Some(results) => list(interpreter, results.into_iter().map(|(_, result)| result).filter(|result| !is_null(result)).collect()),
+        None => DexValue::Void,
+    }
+}
+
+/// The predicate returns a boxed Boolean through the erased invoke()
+fn sequence_filter(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(results) = apply(interpreter, &args) else {
+        return DexValue::Void;
+    };
+    let kept = results.into_iter()
+        .filter(|(_, result)| {
+            let value = unboxed(&interpreter.heap, result).unwrap_or_else(|| result.clone());
+            value.as_int().is_some_and(|value| value != 0)
+        })
+        .map(|(element, _)| element)
+        .collect();
+    list(interpreter, kept)
+}
+
+fn pair_parts(interpreter: &Interpreter, pair: &DexValue) -> (DexValue, DexValue) {
+    let field = |name: &str| match pair {
+        DexValue::Object(id) => interpreter.heap.get(id)
+            .and_then(|object| object.fields.get(name).cloned())
+            .unwrap_or(DexValue::Null),
+        _ => DexValue::Null,
+    };
+    (field(FIRST_FIELD), field(SECOND_FIELD))
+}
+
+fn map(interpreter: &mut Interpreter, pairs: Vec<DexValue>) -> DexValue {
+    let entries = pairs.iter().map(|pair| pair_parts(interpreter, pair)).collect();
+    DexValue::Object(new_map(interpreter, entries))
+}
+
+fn map_of_pair(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    map(interpreter, vec![arg(&args, 0)])
+}
+
+fn map_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let pairs = elements_arg(interpreter, &args, 0);
+    map(interpreter, pairs)
+}
+
+fn empty_map(interpreter: &mut Interpreter, _this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    map(interpreter, Vec::new())
+}
+
+fn map_plus_pair(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut entries = map_entries(interpreter, &arg(&args, 0));
+    entries.push(pair_parts(interpreter, &arg(&args, 1)));
+    DexValue::Object(new_map(interpreter, entries))
+}
+
+/// `Map.getValue(key)`, throws where get() would return null for a missing key
+fn map_get_value(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let key = arg(&args, 1);
+    for (entry_key, value) in map_entries(interpreter, &arg(&args, 0)) {
+        if interpreter.values_equal(&entry_key, &key) {
+            return value;
+        }
+    }
+    let message = format!("Key {} is missing in the map.", interpreter.string_value_of(&key));
+    interpreter.throw_new(NO_SUCH_ELEMENT_EXCEPTION, &message);
+    DexValue::Void
+}
+
+fn set_of_element(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Object(new_set(interpreter, vec![arg(&args, 0)]))
+}
+
+fn set_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let elements = elements_arg(interpreter, &args, 0);
+    DexValue::Object(new_set(interpreter, elements))
+}
+
+fn empty_set(interpreter: &mut Interpreter, _this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Object(new_set(interpreter, Vec::new()))
+}
+
+fn to_pair(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let id = interpreter.alloc_object(PAIR);
+    pair_init(interpreter, Some(id), args);
+    DexValue::Object(id)
+}
+
+fn pair_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    if let Some(pair) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        pair.fields.insert(FIRST_FIELD.to_string(), arg(&args, 0));
+        pair.fields.insert(SECOND_FIELD.to_string(), arg(&args, 1));
+    }
+    DexValue::Void
+}
+
+fn pair_first(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    pair_parts(interpreter, &this.map_or(DexValue::Null, DexValue::Object)).0
+}
+
+fn pair_second(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    pair_parts(interpreter, &this.map_or(DexValue::Null, DexValue::Object)).1
+}
+
+/// Pair is a data class, equal pairs have equal parts
+fn pair_equals(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let other = arg(&args, 0);
+    let is_pair = match &other {
+        DexValue::Object(id) => interpreter.heap.get(id).is_some_and(|object| object.class_name == PAIR),
+        _ => false,
+    };
+    if !is_pair {
+        return DexValue::Boolean(false);
+    }
+    let (first, second) = pair_parts(interpreter, &this.map_or(DexValue::Null, DexValue::Object));
+    let (other_first, other_second) = pair_parts(interpreter, &other);
+    DexValue::Boolean(interpreter.values_equal(&first, &other_first) && interpreter.values_equal(&second, &other_second))
+}
+
+/// `(first, second)`
+fn pair_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let (first, second) = pair_parts(interpreter, &this.map_or(DexValue::Null, DexValue::Object));
+    DexValue::String(format!("({}, {})", interpreter.string_value_of(&first), interpreter.string_value_of(&second)))
+}
+
+fn lazy(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let id = interpreter.alloc_object(LAZY);
+    let initializer = args.last().cloned().unwrap_or(DexValue::Null);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(INITIALIZER_FIELD.to_string(), initializer);
+    DexValue::Object(id)
+}
+
+fn lazy_of(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let id = interpreter.alloc_object(LAZY);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(VALUE_FIELD.to_string(), arg(&args, 0));
+    DexValue::Object(id)
+}
+
+fn lazy_result(interpreter: &Interpreter, this: Option<ObjectId>) -> Option<DexValue> {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|lazy| lazy.fields.get(VALUE_FIELD).cloned())
+}
+
+/// Run the initializer on first access and keep its result, a throwing one runs again next time
+fn lazy_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    if let Some(value) = lazy_result(interpreter, this) {
+        return value;
+    }
+    let Some(lazy) = this.and_then(|id| interpreter.heap.get_mut(&id)) else {
+        return DexValue::Null;
+    };
+    let initializer = lazy.fields.remove(INITIALIZER_FIELD).unwrap_or(DexValue::Null);
+    let value = interpreter.invoke_function(&initializer, Vec::new());
+    let lazy = interpreter.heap.get_mut(&this.unwrap()).unwrap();
+    match value {
+        Some(value) if interpreter.pending_exception.is_none() => {
+            lazy.fields.insert(VALUE_FIELD.to_string(), value.clone());
+            value
+        }
+        _ => {
+            lazy.fields.insert(INITIALIZER_FIELD.to_string(), initializer);
+            DexValue::Void
+        }
+    }
+}
+
+fn lazy_to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    match lazy_result(interpreter, this) {
+        Some(value) => DexValue::String(interpreter.string_value_of(&value)),
+        None => DexValue::String("Lazy value not initialized yet.".to_string()),
+    }
+}
@@ -0,0 +1,324 @@
+// java.lang.Math, the kotlin.math functions that aren't inlined into Math calls and the
+// kotlin.ranges coercions, for page counts and image split sizes. Results follow the JVM where
+// Rust differs: Math.round() rounds halves up rather than away from zero, and integer abs() of
+// MIN_VALUE overflows back to itself.
+
+use crate::interpreter::exception::{ARITHMETIC_EXCEPTION, ILLEGAL_ARGUMENT_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{parameters, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const MATH: &str = "Ljava/lang/Math;";
+pub const STRICT_MATH: &str = "Ljava/lang/StrictMath;";
+pub const MATH_KT: &str = "Lkotlin/math/MathKt;";
+pub const RANGES_KT: &str = "Lkotlin/ranges/RangesKt;";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (MATH | STRICT_MATH, "abs:(I)I") => abs_int,
+        (MATH | STRICT_MATH, "abs:(J)J") => abs_long,
+        (MATH | STRICT_MATH, "abs:(F)F") => abs_float,
+        (MATH | STRICT_MATH, "abs:(D)D") => abs_double,
+        (MATH | STRICT_MATH, "max:(II)I") => max_int,
+        (MATH | STRICT_MATH, "max:(JJ)J") => max_long,
+        (MATH | STRICT_MATH, "max:(FF)F") => max_float,
+        (MATH | STRICT_MATH, "max:(DD)D") => max_double,
+        (MATH | STRICT_MATH, "min:(II)I") => min_int,
+        (MATH | STRICT_MATH, "min:(JJ)J") => min_long,
+        (MATH | STRICT_MATH, "min:(FF)F") => min_float,
+        (MATH | STRICT_MATH, "min:(DD)D") => min_double,
+        (MATH | STRICT_MATH, "ceil:(D)D") => ceil,
+        (MATH | STRICT_MATH, "floor:(D)D") => floor,
+        (MATH | STRICT_MATH, "rint:(D)D") => rint,
+        (MATH | STRICT_MATH, "round:(D)J") => round_double,
+        (MATH | STRICT_MATH, "round:(F)I") => round_float,
+        (MATH | STRICT_MATH, "signum:(D)D") => signum,
+        (MATH | STRICT_MATH, "sqrt:(D)D") => sqrt,
+        (MATH | STRICT_MATH, "cbrt:(D)D") => cbrt,
+        (MATH | STRICT_MATH, "pow:(DD)D") => pow,
+        (MATH | STRICT_MATH, "exp:(D)D") => exp,
+        (MATH | STRICT_MATH, "log:(D)D") => log,
+        (MATH | STRICT_MATH, "log10:(D)D") => log10,
+        (MATH | STRICT_MATH, "hypot:(DD)D") => hypot,
+        (MATH | STRICT_MATH, "floorDiv:(II)I") => floor_div,
+        (MATH | STRICT_MATH, "floorMod:(II)I") => floor_mod,
+
+        (MATH_KT, "roundToInt:(D)I") => round_to_int,
+        (MATH_KT, "roundToInt:(F)I") => round_float_to_int,
+        (MATH_KT, "roundToLong:(D)J") => round_to_long,
+        (MATH_KT, "getSign:(D)D") => signum,
+        (MATH_KT, "getSign:(I)I") => sign_int,
+        (MATH_KT, "log2:(D)D") => log2,
+
+        (RANGES_KT, "coerceAtLeast:(II)I") => max_int,
+        (RANGES_KT, "coerceAtLeast:(JJ)J") => max_long,
+        (RANGES_KT, "coerceAtLeast:(DD)D") => max_double,
+        (RANGES_KT, "coerceAtMost:(II)I") => min_int,
+        (RANGES_KT, "coerceAtMost:(JJ)J") => min_long,
+        (RANGES_KT, "coerceAtMost:(DD)D") => min_double,
+        (RANGES_KT, "coerceIn:(III)I") => coerce_in_int,
+        (RANGES_KT, "coerceIn:(JJJ)J") => coerce_in_long,
+        (RANGES_KT, "coerceIn:(DDD)D") => coerce_in_double,
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> i32 {
+    args.get(index).and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+fn float_arg(args: &[DexValue], index: usize) -> f32 {
+    args.get(index).and_then(|value| value.as_float()).unwrap_or(0.0)
+}
+
+/// Longs and doubles of a call, the upper halves left out
+fn longs(args: &[DexValue]) -> Vec<i64> {
+    parameters(args).iter().map(|value| value.as_long().unwrap_or(0)).collect()
+}
+
+fn doubles(args: &[DexValue]) -> Vec<f64> {
+    parameters(args).iter().map(|value| value.as_double().unwrap_or(0.0)).collect()
+}
+
+/// Apply `f` to the only double argument
+fn unary(args: &[DexValue], f: fn(f64) -> f64) -> DexValue {
+    DexValue::Double(f(doubles(args).first().copied().unwrap_or(0.0)))
+}
+
+/// Apply `f` to both double arguments
+fn binary(args: &[DexValue], f: fn(f64, f64) -> f64) -> DexValue {
+    let values = doubles(args);
+    DexValue::Double(f(values.first().copied().unwrap_or(0.0), values.get(1).copied().unwrap_or(0.0)))
+}
+
+/// `Math.max()` for floating point: NaN wins, and 0.0 is above -0.0
+fn java_max(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        return f64::NAN;
+    }
+    if a == b { if a.is_sign_negative() { b } else { a } } else { a.max(b) }
+}
+
+fn java_min(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() {
+        return f64::NAN;
+    }
+    if a == b { if a.is_sign_negative() { a } else { b } } else { a.min(b) }
+}
+
+/// `Math.round()`: the closest integer with ties rounded up, NaN is 0 and the rest saturates
+fn java_round(value: f64) -> i64 {
+    let floor = value.floor();
+    let rounded = if value - floor >= 0.5 { floor + 1.0 } else { floor };
+    rounded as i64
+}
+
+fn abs_int(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(int_arg(&args, 0).wrapping_abs())
+}
+
+fn abs_long(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Long(longs(&args).first().copied().unwrap_or(0).wrapping_abs())
+}
+
+fn abs_float(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Float(float_arg(&args, 0).abs())
+}
+
+fn abs_double(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::abs)
+}
+
+fn max_int(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(int_arg(&args, 0).max(int_arg(&args, 1)))
+}
+
+fn max_long(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Long(longs(&args).into_iter().max().unwrap_or(0))
+}
+
+fn max_float(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Float(java_max(float_arg(&args, 0) as f64, float_arg(&args, 1) as f64) as f32)
+}
+
+fn max_double(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    binary(&args, java_max)
+}
+
+fn min_int(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(int_arg(&args, 0).min(int_arg(&args, 1)))
+}
+
+fn min_long(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Long(longs(&args).into_iter().min().unwrap_or(0))
+}
+
+fn min_float(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Float(java_min(float_arg(&args, 0) as f64, float_arg(&args, 1) as f64) as f32)
+}
+
+fn min_double(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    binary(&args, java_min)
+}
+
+fn ceil(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::ceil)
+}
+
+fn floor(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::floor)
+}
+
+/// Rounds ties to the even neighbour
+fn rint(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::round_ties_even)
+}
+
+fn round_double(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Long(java_round(doubles(&args).first().copied().unwrap_or(0.0)))
+}
+
+fn round_float(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(java_round(float_arg(&args, 0) as f64).clamp(i32::MIN as i64, i32::MAX as i64) as i32)
+}
+
+/// 1.0, -1.0, or the argument itself for zeros and NaN
+fn signum(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, |value| if value == 0.0 || value.is_nan() { value } else { value.signum() })
+}
+
+fn sign_int(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(int_arg(&args, 0).signum())
+}
+
+fn sqrt(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::sqrt)
+}
+
+fn cbrt(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::cbrt)
+}
+
+fn pow(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    binary(&args, f64::powf)
+}
+
+fn exp(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::exp)
+}
+
+fn log(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::ln)
+}
+
+fn log10(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::log10)
+}
+
+fn log2(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    unary(&args, f64::log2)
+}
+
+fn hypot(_interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    binary(&args, f64::hypot)
+}
+
+/// The divisor of floorDiv() and floorMod(), throws for zero
+fn divisor(interpreter: &mut Interpreter, args: &[DexValue]) -> Option<i32> {
+    let divisor = int_arg(args, 1);
+    if divisor == 0 {
+        interpreter.throw_new(ARITHMETIC_EXCEPTION, "/ by zero");
+        return None;
+    }
+    Some(divisor)
+}
+
+/// Division rounding towards negative infinity, MIN_VALUE / -1 overflows like `/`
+fn floor_div(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(divisor) = divisor(interpreter, &args) else {
+        return DexValue::Void;
+    };
+    let dividend = int_arg(&args, 0);
+    let quotient = dividend.wrapping_div(divisor);
+    let adjust = (dividend.wrapping_rem(divisor) != 0 && (dividend < 0) != (divisor < 0)) as i32;
+    DexValue::Int(quotient - adjust)
+}
+
+/// Remainder with the sign of the divisor
+fn floor_mod(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(divisor) = divisor(interpreter, &args) else {
+        return DexValue::Void;
+    };
+    let remainder = int_arg(&args, 0).wrapping_rem(divisor);
+    let adjust = if remainder != 0 && (remainder < 0) != (divisor < 0) { divisor } else { 0 };
+    DexValue::Int(remainder + adjust)
+}
+
+/// Kotlin's roundToInt() and roundToLong() refuse NaN rather than returning 0
+fn rounded(interpreter: &mut Interpreter, value: f64) -> Option<i64> {
+    if value.is_nan() {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "Cannot round NaN value.");
+        return None;
+    }
+    Some(java_round(value))
+}
+
+fn round_to_int(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = doubles(&args).first().copied().unwrap_or(0.0);
+    match rounded(interpreter, value) {
+        Some(value) => DexValue::Int(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32),
+        None => DexValue::Void,
+    }
+}
+
+fn round_float_to_int(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match rounded(interpreter, float_arg(&args, 0) as f64) {
+        Some(value) => DexValue::Int(value.clamp(i32::MIN as i64, i32::MAX as i64) as i32),
+        None => DexValue::Void,
+    }
+}
+
+fn round_to_long(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = doubles(&args).first().copied().unwrap_or(0.0);
+    match rounded(interpreter, value) {
+        Some(value) => DexValue::Long(value),
+        None => DexValue::Void,
+    }
+}
+
+/// The range of coerceIn(), which throws for an empty one
+fn checked_range<T: PartialOrd + std::fmt::Display>(interpreter: &mut Interpreter, min: T, max: T) -> bool {
+    if min > max {
+        let message = format!("Cannot coerce value to an empty range: maximum {} is less than minimum {}.", max, min);
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &message);
+        return false;
+    }
+    true
+}
+
+fn coerce_in_int(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let (value, min, max) = (int_arg(&args, 0), int_arg(&args, 1), int_arg(&args, 2));
+    if !checked_range(interpreter, min, max) {
+        return DexValue::Void;
+    }
+    DexValue::Int(value.clamp(min, max))
+}
+
+fn coerce_in_long(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let values = longs(&args);
+    let value = |index: usize| values.get(index).copied().unwrap_or(0);
+    if !checked_range(interpreter, value(1), value(2)) {
+        return DexValue::Void;
+    }
+    DexValue::Long(value(0).clamp(value(1), value(2)))
+}
+
+fn coerce_in_double(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let values = doubles(&args);
+    let value = |index: usize| values.get(index).copied().unwrap_or(0.0);
+    if !checked_range(interpreter, value(1), value(2)) {
+        return DexValue::Void;
+    }
+    DexValue::Double(value(0).max(value(1)).min(value(2)))
+}
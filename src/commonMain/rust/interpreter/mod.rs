@@ -1 +1,31 @@
-pub mod interpreter;
\ No newline at end of file
+pub mod base64;
+pub mod boxing;
+pub mod charset;
+pub mod cipher;
+pub mod clock;
+pub mod codec;
+pub mod collections;
+pub mod cookies;
+pub mod coroutines;
+pub mod date;
+pub mod digest;
+pub mod enums;
+pub mod error;
+pub mod exception;
+pub mod files;
+pub mod http;
+pub mod json;
+pub mod jsoup;
+pub mod kotlin_collections;
+pub mod math;
+pub mod native;
+pub mod rate_limit;
+pub mod regex;
+pub mod selector;
+pub mod serialization;
+pub mod string_builder;
+pub mod strings;
+pub mod threads;
+pub mod url;
+pub mod uuid;
+pub mod interpreter;
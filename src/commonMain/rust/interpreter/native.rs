@@ -0,0 +1,207 @@
+// Native stand-ins for classes extensions use but don't ship in their dex, such as the source
+// api models. Each class maps `name:descriptor` keys to the function implementing the method.
+
+use std::collections::HashMap;
+
+use crate::interpreter::{base64, boxing, charset, cipher, clock, collections, coroutines, date, digest, enums, exception, files, http, json, jsoup, kotlin_collections, math, rate_limit, regex, serialization, string_builder, threads, url, uuid};
+use crate::interpreter::interpreter::Interpreter;
+use crate::types::{DexValue, NativeMethod, Object, ObjectId};
+
+pub const MANGAS_PAGE: &str = "Leu/kanade/tachiyomi/source/model/MangasPage;";
+pub const HTTP_SOURCE: &str = "Leu/kanade/tachiyomi/source/online/HttpSource;";
+pub const JAVA_LANG_CLASS: &str = "Ljava/lang/Class;";
+
+/// Field of a Class object holding the descriptor of the class it stands for
+pub const CLASS_DESCRIPTOR_FIELD: &str = "descriptor";
+
+/// Interfaces of the source api classes the interpreter knows without a class file, for
+/// instanceof
+pub fn builtin_interfaces(class_name: &str) -> Option<&'static [&'static str]> {
+    match class_name {
+        HTTP_SOURCE => Some(&["Leu/kanade/tachiyomi/source/CatalogueSource;"]),
+        "Leu/kanade/tachiyomi/source/CatalogueSource;" => Some(&["Leu/kanade/tachiyomi/source/Source;"]),
+        _ => None,
+    }
+}
+
+/// Platform class method that needs the interpreter, e.g. to allocate objects or throw. Receives
+/// the receiver, `None` for static methods, and the arguments.
+pub type PlatformMethod = fn(&mut Interpreter, Option<ObjectId>, Vec<DexValue>) -> DexValue;
+
+/// Platform method `method_key` of `class_name`, looked up when the class has no class file
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    date::platform_method(class_name, method_key)
+        .or_else(|| files::platform_method(class_name, method_key))
+        .or_else(|| string_builder::platform_method(class_name, method_key))
+        .or_else(|| collections::platform_method(class_name, method_key))
+        .or_else(|| boxing::platform_method(class_name, method_key))
+        .or_else(|| kotlin_collections::platform_method(class_name, method_key))
+        .or_else(|| math::platform_method(class_name, method_key))
+        .or_else(|| regex::platform_method(class_name, method_key))
+        .or_else(|| base64::platform_method(class_name, method_key))
+        .or_else(|| url::platform_method(class_name, method_key))
+        .or_else(|| json::platform_method(class_name, method_key))
+        .or_else(|| exception::platform_method(class_name, method_key))
+        .or_else(|| serialization::platform_method(class_name, method_key))
+        .or_else(|| http::platform_method(class_name, method_key))
+        .or_else(|| rate_limit::platform_method(class_name, method_key))
+        .or_else(|| jsoup::platform_method(class_name, method_key))
+        .or_else(|| digest::platform_method(class_name, method_key))
+        .or_else(|| cipher::platform_method(class_name, method_key))
+        .or_else(|| uuid::platform_method(class_name, method_key))
+        .or_else(|| clock::platform_method(class_name, method_key))
+        .or_else(|| charset::platform_method(class_name, method_key))
+        .or_else(|| coroutines::platform_method(class_name, method_key))
+        .or_else(|| threads::platform_method(class_name, method_key))
+        .or_else(|| enums::platform_method(class_name, method_key))
+}
+
+/// Arguments without the upper halves of longs and doubles, so indices count parameters
+pub fn parameters(args: &[DexValue]) -> Vec<DexValue> {
+    args.iter().filter(|arg| **arg != DexValue::WideHigh).cloned().collect()
+}
+
+/// Allocate a byte[] holding `data`
+pub fn new_byte_array(interpreter: &mut Interpreter, data: Vec<u8>) -> DexValue {
+    let id = interpreter.alloc_object("[B");
+    interpreter.heap.get_mut(&id).unwrap().array = Some(data.into_iter().map(|byte| DexValue::Byte(byte as i8)).collect());
+    DexValue::Object(id)
+}
+
+/// Bytes of a byte[], empty for anything else
+pub fn bytes_of(interpreter: &Interpreter, array: Option<ObjectId>) -> Vec<u8> {
+    array.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.array.as_ref())
+        .map(|elements| elements.iter().map(|element| element.as_int().unwrap_or(0) as u8).collect())
+        .unwrap_or_default()
+}
+
+/// Native methods declared by `class_name` itself, superclasses are not included
+pub fn native_methods(class_name: &str) -> HashMap<String, Option<NativeMethod>> {
+    let methods: Vec<(&str, NativeMethod)> = match class_name {
+        MANGAS_PAGE => vec![
+            ("<init>:(Ljava/util/List;Z)V", mangas_page_init),
+            ("getMangas:()Ljava/util/List;", mangas_page_get_mangas),
+            ("getHasNextPage:()Z", mangas_page_get_has_next_page),
+        ],
+        // Constructed through super() by every extension, its client and headers are in http.rs
+        HTTP_SOURCE => vec![("<init>:()V", no_op)],
+        // Also stands in for the KClass of Kotlin class literals, hence getQualifiedName
+        JAVA_LANG_CLASS => vec![
+            ("getName:()Ljava/lang/String;", class_get_name),
+            ("getTypeName:()Ljava/lang/String;", class_get_canonical_name),
+            ("getSimpleName:()Ljava/lang/String;", class_get_simple_name),
+            ("getCanonicalName:()Ljava/lang/String;", class_get_canonical_name),
+            ("getQualifiedName:()Ljava/lang/String;", class_get_canonical_name),
+            ("isArray:()Z", class_is_array),
+            ("toString:()Ljava/lang/String;", class_to_string),
+        ],
+        _ => Vec::new(),
+    };
+
+    methods.into_iter()
+        .map(|(key, method)| (key.to_string(), Some(method)))
+        .collect()
+}
+
+fn no_op(_object: &mut Object, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Void
+}
+
+fn mangas_page_init(object: &mut Object, args: Vec<DexValue>) -> DexValue {
+    let mut args = args.into_iter();
+    object.fields.insert("mangas".to_string(), args.next().unwrap_or(DexValue::Null));
+    let has_next_page = args.next().and_then(|value| value.as_int()).unwrap_or(0) != 0;
+    object.fields.insert("hasNextPage".to_string(), DexValue::Boolean(has_next_page));
+    DexValue::Void
+}
+
+fn mangas_page_get_mangas(object: &mut Object, _args: Vec<DexValue>) -> DexValue {
+    object.fields.get("mangas").cloned().unwrap_or(DexValue::Null)
+}
+
+fn mangas_page_get_has_next_page(object: &mut Object, _args: Vec<DexValue>) -> DexValue {
+    object.fields.get("hasNextPage").cloned().unwrap_or(DexValue::Boolean(false))
+}
+
+fn class_descriptor(object: &Object) -> String {
+    match object.fields.get(CLASS_DESCRIPTOR_FIELD) {
+        Some(DexValue::String(descriptor)) => descriptor.clone(),
+        _ => "Ljava/lang/Object;".to_string(),
+    }
+}
+
+/// Java name of a primitive descriptor, e.g. `int` for `I`
+fn primitive_name(descriptor: &str) -> Option<&'static str> {
+    let name = match descriptor {
+        "Z" => "boolean",
+        "B" => "byte",
+        "S" => "short",
+        "C" => "char",
+        "I" => "int",
+        "J" => "long",
+        "F" => "float",
+        "D" => "double",
+        "V" => "void",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// `Class.getName()`: `com.example.Outer$Inner`, arrays keep their descriptor with dots
+pub fn binary_name(descriptor: &str) -> String {
+    if let Some(name) = primitive_name(descriptor) {
+        return name.to_string();
+    }
+    if descriptor.starts_with('[') {
+        return descriptor.replace('/', ".");
+    }
+    descriptor.trim_start_matches('L').trim_end_matches(';').replace('/', ".")
+}
+
+/// `Class.getCanonicalName()`: `com.example.Outer.Inner`, `int[]` for arrays
+fn canonical_name(descriptor: &str) -> String {
+    match descriptor.strip_prefix('[') {
+        Some(component) => format!("{}[]", canonical_name(component)),
+        None => binary_name(descriptor).replace('$', "."),
+    }
+}
+
+/// `Class.getSimpleName()`: `Inner`, empty for anonymous classes
+fn simple_name(descriptor: &str) -> String {
+    if let Some(component) = descriptor.strip_prefix('[') {
+        return format!("{}[]", simple_name(component));
+    }
+    let name = binary_name(descriptor);
+    let name = name.rsplit('.').next().unwrap_or(&name);
+    let name = name.rsplit('$').next().unwrap_or(name);
+    if name.chars().all(|c| c.is_ascii_digit()) {
+        String::new()
+    } else {
+        name.to_string()
+    }
+}
+
+fn class_get_name(object: &mut Object, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(binary_name(&class_descriptor(object)))
+}
+
+fn class_get_simple_name(object: &mut Object, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(simple_name(&class_descriptor(object)))
+}
+
+fn class_get_canonical_name(object: &mut Object, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(canonical_name(&class_descriptor(object)))
+}
+
+fn class_is_array(object: &mut Object, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(class_descriptor(object).starts_with('['))
+}
+
+fn class_to_string(object: &mut Object, _args: Vec<DexValue>) -> DexValue {
+    let descriptor = class_descriptor(object);
+    match primitive_name(&descriptor) {
+        Some(name) => DexValue::String(name.to_string()),
+        None => DexValue::String(format!("class {}", binary_name(&descriptor))),
+    }
+}
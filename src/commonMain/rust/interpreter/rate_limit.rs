@@ -0,0 +1,187 @@
+// `rateLimit()` and `rateLimitHost()` of the Mihon network lib. Both add a native interceptor to
+// the client builder which holds a token bucket per request host: a bucket starts with `permits`
+// tokens, refills at `permits` per period and a request waits for a token before it proceeds.
+// `rateLimitHost()` only limits the host of its url, requests elsewhere pass right through.
+// Waiting blocks the interpreter, like the calls it delays.
+
+use std::time::Duration;
+
+use crate::interpreter::{clock, date};
+use crate::interpreter::exception::ILLEGAL_ARGUMENT_EXCEPTION;
+use crate::interpreter::http;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::PlatformMethod;
+use crate::types::{DexValue, ObjectId};
+
+const RATE_LIMIT_INTERCEPTOR_KT: &str = "Leu/kanade/tachiyomi/network/interceptor/RateLimitInterceptorKt;";
+const SPECIFIC_HOST_RATE_LIMIT_INTERCEPTOR_KT: &str = "Leu/kanade/tachiyomi/network/interceptor/SpecificHostRateLimitInterceptorKt;";
+pub const RATE_LIMIT_INTERCEPTOR: &str = "Leu/kanade/tachiyomi/network/interceptor/RateLimitInterceptor;";
+
+/// Host the interceptor is limited to, null for every host
+const HOST_FIELD: &str = "host";
+const PERMITS_FIELD: &str = "permits";
+const PERIOD_FIELD: &str = "periodMillis";
+/// Prefix of the fields holding the tokens left and when they were counted for each host
+const BUCKET_FIELD_PREFIX: &str = "bucket:";
+
+const INTERCEPT: &str = "intercept:(Lokhttp3/Interceptor$Chain;)Lokhttp3/Response;";
+const ADD_INTERCEPTOR: &str = "addInterceptor:(Lokhttp3/Interceptor;)Lokhttp3/OkHttpClient$Builder;";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (RATE_LIMIT_INTERCEPTOR, INTERCEPT) => intercept,
+        (RATE_LIMIT_INTERCEPTOR_KT, "rateLimit:(Lokhttp3/OkHttpClient$Builder;IJLjava/util/concurrent/TimeUnit;)Lokhttp3/OkHttpClient$Builder;") => |interpreter, _, args| add_rate_limit(interpreter, &args, false, Period::TimeUnit, 0),
+        (RATE_LIMIT_INTERCEPTOR_KT, "rateLimit$default:(Lokhttp3/OkHttpClient$Builder;IJLjava/util/concurrent/TimeUnit;ILjava/lang/Object;)Lokhttp3/OkHttpClient$Builder;") => |interpreter, _, args| {
+            let mask = args.get(4).and_then(|mask| mask.as_int()).unwrap_or(0);
+            add_rate_limit(interpreter, &args, false, Period::TimeUnit, mask)
+        },
+        (SPECIFIC_HOST_RATE_LIMIT_INTERCEPTOR_KT, "rateLimitHost:(Lokhttp3/OkHttpClient$Builder;Lokhttp3/HttpUrl;IJLjava/util/concurrent/TimeUnit;)Lokhttp3/OkHttpClient$Builder;") => |interpreter, _, args| add_rate_limit(interpreter, &args, true, Period::TimeUnit, 0),
+        (SPECIFIC_HOST_RATE_LIMIT_INTERCEPTOR_KT, "rateLimitHost$default:(Lokhttp3/OkHttpClient$Builder;Lokhttp3/HttpUrl;IJLjava/util/concurrent/TimeUnit;ILjava/lang/Object;)Lokhttp3/OkHttpClient$Builder;") => |interpreter, _, args| {
+            let mask = args.get(5).and_then(|mask| mask.as_int()).unwrap_or(0);
+            add_rate_limit(interpreter, &args, true, Period::TimeUnit, mask)
+        },
+        // Overloads taking a kotlin.time.Duration, an inline class, get a name mangled with a hash
+        (RATE_LIMIT_INTERCEPTOR_KT, key) if is_duration_overload(key, "rateLimit", "IJ") => |interpreter, _, args| add_rate_limit(interpreter, &args, false, Period::Duration, 0),
+        (RATE_LIMIT_INTERCEPTOR_KT, key) if is_duration_overload(key, "rateLimit", "IJILjava/lang/Object;") => |interpreter, _, args| {
+            let mask = args.get(3).and_then(|mask| mask.as_int()).unwrap_or(0);
+            add_rate_limit(interpreter, &args, false, Period::Duration, mask)
+        },
+        (SPECIFIC_HOST_RATE_LIMIT_INTERCEPTOR_KT, key)
+            if is_duration_overload(key, "rateLimitHost", "Lokhttp3/HttpUrl;IJ")
+                || is_duration_overload(key, "rateLimitHost", "Ljava/lang/String;IJ") => |interpreter, _, args| add_rate_limit(interpreter, &args, true, Period::Duration, 0),
+        (SPECIFIC_HOST_RATE_LIMIT_INTERCEPTOR_KT, key)
+            if is_duration_overload(key, "rateLimitHost", "Lokhttp3/HttpUrl;IJILjava/lang/Object;")
+                || is_duration_overload(key, "rateLimitHost", "Ljava/lang/String;IJILjava/lang/Object;") => |interpreter, _, args| {
+            let mask = args.get(4).and_then(|mask| mask.as_int()).unwrap_or(0);
+            add_rate_limit(interpreter, &args, true, Period::Duration, mask)
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// Whether `method_key` is the Duration overload of `name` taking the builder and `parameters`,
+/// `$default` ones included
+fn is_duration_overload(method_key: &str, name: &str, parameters: &str) -> bool {
+    let Some((method_name, descriptor)) = method_key.split_once(':') else {
+        return false;
+    };
+    method_name.strip_prefix(name).is_some_and(|rest| rest.starts_with('-'))
+        && descriptor == format!("(Lokhttp3/OkHttpClient$Builder;{})Lokhttp3/OkHttpClient$Builder;", parameters)
+}
+
+/// How the period is given
+enum Period {
+    /// A long followed by a TimeUnit, one second by default
+    TimeUnit,
+    /// A kotlin.time.Duration, which is passed as its raw long
+    Duration,
+}
+
+/// Milliseconds in the raw value of a kotlin.time.Duration. The lowest bit tells whether the
+/// rest counts nanoseconds or, for long durations, milliseconds.
+fn duration_millis(raw: i64) -> i64 {
+    if raw & 1 == 0 { (raw >> 1) / 1_000_000 } else { raw >> 1 }
+}
+
+/// Add a limiter to the builder in `args[0]`, its arguments follow: the url of the host for
+/// `for_host`, the permits and the period. Bits of the `$default` variants' `mask` count the
+/// parameters after the builder.
+fn add_rate_limit(interpreter: &mut Interpreter, args: &[DexValue], for_host: bool, period: Period, mask: i32) -> DexValue {
+    let Some(DexValue::Object(builder)) = args.first() else {
+        return DexValue::Void;
+    };
+    let first = if for_host { 2 } else { 1 };
+    let defaulted = |index: usize| mask & (1 << (index - 1)) != 0;
+
+    let host = if for_host {
+        match &args[1] {
+            DexValue::String(url) => http::url_parts(url).map(|(host, _, _)| host),
+            url => http::url_host(interpreter, url),
+        }
+    } else {
+        None
+    };
+    if for_host && host.is_none() {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "Expected URL scheme 'http' or 'https'");
+        return DexValue::Void;
+    }
+    let permits = args.get(first).and_then(|permits| permits.as_int()).unwrap_or(0);
+    let count = args.get(first + 1).and_then(|count| count.as_long()).unwrap_or(0);
+    let period_millis = match period {
+        Period::TimeUnit if defaulted(first + 2) => 1_000,
+        Period::TimeUnit => {
+            let count = if defaulted(first + 1) { 1 } else { count };
+            date::time_unit_to_millis(interpreter, count, args.get(first + 2).unwrap_or(&DexValue::Null)).unwrap_or(count * 1_000)
+        }
+        Period::Duration if defaulted(first + 1) => 1_000,
+        Period::Duration => duration_millis(count),
+    };
+    if permits <= 0 {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &format!("permits must be positive, was {}", permits));
+        return DexValue::Void;
+    }
+    if period_millis <= 0 {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &format!("period must be positive, was {}ms", period_millis));
+        return DexValue::Void;
+    }
+
+    let id = interpreter.alloc_object(RATE_LIMIT_INTERCEPTOR);
+    let interceptor = interpreter.heap.get_mut(&id).unwrap();
+    interceptor.fields.insert(HOST_FIELD.to_string(), host.map_or(DexValue::Null, DexValue::String));
+    interceptor.fields.insert(PERMITS_FIELD.to_string(), DexValue::Int(permits));
+    interceptor.fields.insert(PERIOD_FIELD.to_string(), DexValue::Long(period_millis));
+    interpreter.invoke_virtual(*builder, ADD_INTERCEPTOR, vec![DexValue::Object(id)]).unwrap_or(DexValue::Void)
+}
+
+/// Wait for a token of the request's host, then let the request proceed
+fn intercept(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(DexValue::Object(chain)) = args.first().cloned() else {
+        return DexValue::Void;
+    };
+    let request = interpreter.invoke_virtual(chain, "request:()Lokhttp3/Request;", Vec::new()).unwrap_or(DexValue::Null);
+    let url = match &request {
+        DexValue::Object(request) => interpreter.invoke_virtual(*request, "url:()Lokhttp3/HttpUrl;", Vec::new()),
+        _ => None,
+    };
+    let host = url.and_then(|url| http::url_host(interpreter, &url)).unwrap_or_default();
+
+    let limited = match this.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(HOST_FIELD)) {
+        Some(DexValue::String(limited)) => limited.eq_ignore_ascii_case(&host),
+        _ => true,
+    };
+    if limited && let Some(wait) = take_token(interpreter, this, &host) {
+        clock::sleep(wait);
+    }
+    interpreter.invoke_virtual(chain, "proceed:(Lokhttp3/Request;)Lokhttp3/Response;", vec![request]).unwrap_or(DexValue::Void)
+}
+
+/// Take a token from the bucket of `host`, or the next one to come if it's empty. Returns how
+/// long to wait for it.
+fn take_token(interpreter: &mut Interpreter, this: Option<ObjectId>, host: &str) -> Option<Duration> {
+    let interceptor = interpreter.heap.get_mut(&this?)?;
+    let permits = interceptor.fields.get(PERMITS_FIELD).and_then(|permits| permits.as_int()).unwrap_or(1) as f64;
+    let period = interceptor.fields.get(PERIOD_FIELD).and_then(|period| period.as_long()).unwrap_or(1_000) as f64;
+    let rate = permits / period;
+    // Monotonic milliseconds, so changes of the wall clock don't matter
+    let now = clock::nano_time() as f64 / 1_000_000.0;
+
+    let key = format!("{}{}", BUCKET_FIELD_PREFIX, host.to_ascii_lowercase());
+    let (tokens, counted_at) = match interceptor.fields.get(&key) {
+        Some(DexValue::Array(bucket)) => match bucket[..] {
+            [DexValue::Double(tokens), DexValue::Double(counted_at)] => (tokens, counted_at),
+            _ => (permits, now),
+        },
+        _ => (permits, now),
+    };
+    // A bucket may have been counted ahead of now by the request waiting for its last token
+    let tokens = (tokens + (now - counted_at).max(0.0) * rate).min(permits);
+    let (tokens, counted_at, wait) = if tokens >= 1.0 {
+        (tokens - 1.0, now.max(counted_at), 0.0)
+    } else {
+        let wait = (1.0 - tokens) / rate;
+        (0.0, now + wait, wait)
+    };
+    interceptor.fields.insert(key, DexValue::Array(vec![DexValue::Double(tokens), DexValue::Double(counted_at)]));
+    (wait > 0.0).then(|| Duration::from_secs_f64(wait / 1_000.0))
+}
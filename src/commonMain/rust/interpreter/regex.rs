@@ -0,0 +1,1066 @@
+// java.util.regex Pattern and Matcher, kotlin.text.Regex with its MatchResult, and the String
+// methods that take a regex. Patterns run on fancy-regex, which has the lookaround and
+// backreferences Java patterns use; Java-only syntax (\Q...\E quoting, the flag constants) is
+// rewritten before compiling. Match positions are kept in UTF-16 code units like every other
+// string index the interpreter hands out.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::interpreter::collections::new_list;
+use crate::interpreter::exception::{
+    ILLEGAL_ARGUMENT_EXCEPTION, ILLEGAL_STATE_EXCEPTION, INDEX_OUT_OF_BOUNDS_EXCEPTION, PATTERN_SYNTAX_EXCEPTION,
+};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::PlatformMethod;
+use crate::types::{DexValue, ObjectId};
+
+pub const PATTERN: &str = "Ljava/util/regex/Pattern;";
+pub const MATCHER: &str = "Ljava/util/regex/Matcher;";
+pub const REGEX: &str = "Lkotlin/text/Regex;";
+pub const MATCH_RESULT: &str = "Lkotlin/text/MatcherMatchResult;";
+pub const MATCH_GROUPS: &str = "Lkotlin/text/MatcherMatchResult$groups$1;";
+pub const MATCH_GROUP: &str = "Lkotlin/text/MatchGroup;";
+pub const DESTRUCTURED: &str = "Lkotlin/text/MatchResult$Destructured;";
+
+const SOURCE_FIELD: &str = "source";
+/// The Pattern or Regex a matcher or match came from
+const PATTERN_FIELD: &str = "pattern";
+const FLAGS_FIELD: &str = "flags";
+const INPUT_FIELD: &str = "input";
+const POSITION_FIELD: &str = "position";
+const MATCH_FIELD: &str = "match";
+const VALUE_FIELD: &str = "value";
+
+// Pattern flags with an inline equivalent
+const CASE_INSENSITIVE: i32 = 0x02;
+const COMMENTS: i32 = 0x04;
+const MULTILINE: i32 = 0x08;
+const LITERAL: i32 = 0x10;
+const DOTALL: i32 = 0x20;
+
+lazy_static! {
+    /// Compiled regexes by their rewritten pattern, extensions compile the same few over and over
+    static ref COMPILED: Mutex<HashMap<String, Arc<Regex>>> = Mutex::new(HashMap::new());
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (PATTERN, "compile:(Ljava/lang/String;)Ljava/util/regex/Pattern;"
+            | "compile:(Ljava/lang/String;I)Ljava/util/regex/Pattern;") => pattern_compile,
+        (PATTERN, "matches:(Ljava/lang/String;Ljava/lang/CharSequence;)Z") => pattern_matches,
+        (PATTERN, "quote:(Ljava/lang/String;)Ljava/lang/String;") => pattern_quote,
+        (PATTERN, "matcher:(Ljava/lang/CharSequence;)Ljava/util/regex/Matcher;") => pattern_matcher,
+        (PATTERN, "pattern:()Ljava/lang/String;" | "toString:()Ljava/lang/String;") => source,
+        (PATTERN, "flags:()I") => pattern_flags,
+        (PATTERN, "split:(Ljava/lang/CharSequence;)[Ljava/lang/String;"
+            | "split:(Ljava/lang/CharSequence;I)[Ljava/lang/String;") => pattern_split,
+
+        (MATCHER, "matches:()Z") => matcher_matches,
+        (MATCHER, "lookingAt:()Z") => matcher_looking_at,
+        (MATCHER, "find:()Z") => matcher_find,
+        (MATCHER, "find:(I)Z") => matcher_find_from,
+        (MATCHER, "group:()Ljava/lang/String;" | "group:(I)Ljava/lang/String;") => matcher_group,
+        (MATCHER, "group:(Ljava/lang/String;)Ljava/lang/String;") => matcher_named_group,
+        (MATCHER, "groupCount:()I") => matcher_group_count,
+        (MATCHER, "start:()I" | "start:(I)I") => matcher_start,
+        (MATCHER, "end:()I" | "end:(I)I") => matcher_end,
+        (MATCHER, "replaceAll:(Ljava/lang/String;)Ljava/lang/String;") => matcher_replace_all,
+        (MATCHER, "replaceFirst:(Ljava/lang/String;)Ljava/lang/String;") => matcher_replace_first,
+        (MATCHER, "reset:()Ljava/util/regex/Matcher;") => matcher_reset,
+
+        (REGEX, "<init>:(Ljava/lang/String;)V") => regex_init,
+        (REGEX, "find:(Ljava/lang/CharSequence;I)Lkotlin/text/MatchResult;") => regex_find,
+        (REGEX, "find$default:(Lkotlin/text/Regex;Ljava/lang/CharSequence;IILjava/lang/Object;)Lkotlin/text/MatchResult;") => regex_find_default,
+        (REGEX, "findAll:(Ljava/lang/CharSequence;I)Lkotlin/sequences/Sequence;") => regex_find_all,
+        (REGEX, "findAll$default:(Lkotlin/text/Regex;Ljava/lang/CharSequence;IILjava/lang/Object;)Lkotlin/sequences/Sequence;") => regex_find_all_default,
+        (REGEX, "matchEntire:(Ljava/lang/CharSequence;)Lkotlin/text/MatchResult;") => regex_match_entire,
+        (REGEX, "matches:(Ljava/lang/CharSequence;)Z") => regex_matches,
+        (REGEX, "containsMatchIn:(Ljava/lang/CharSequence;)Z") => regex_contains_match_in,
+        (REGEX, "replace:(Ljava/lang/CharSequence;Ljava/lang/String;)Ljava/lang/String;") => regex_replace,
+        (REGEX, "replace:(Ljava/lang/CharSequence;Lkotlin/jvm/functions/Function1;)Ljava/lang/String;") => regex_replace_with,
+        (REGEX, "replaceFirst:(Ljava/lang/CharSequence;Ljava/lang/String;)Ljava/lang/String;") => regex_replace_first,
+        (REGEX, "split:(Ljava/lang/CharSequence;I)Ljava/util/List;") => regex_split,
+        (REGEX, "split$default:(Lkotlin/text/Regex;Ljava/lang/CharSequence;IILjava/lang/Object;)Ljava/util/List;") => regex_split_default,
+        (REGEX, "getPattern:()Ljava/lang/String;" | "toString:()Ljava/lang/String;") => source,
+
+        (MATCH_RESULT, "getValue:()Ljava/lang/String;") => match_value,
+        (MATCH_RESULT, "getGroupValues:()Ljava/util/List;") => match_group_values,
+        (MATCH_RESULT, "getGroups:()Lkotlin/text/MatchGroupCollection;") => match_groups,
+        (MATCH_RESULT, "getDestructured:()Lkotlin/text/MatchResult$Destructured;") => match_destructured,
+        (MATCH_RESULT, "next:()Lkotlin/text/MatchResult;") => match_next,
+        (MATCH_GROUPS, "get:(I)Lkotlin/text/MatchGroup;") => groups_get,
+        (MATCH_GROUPS, "get:(Ljava/lang/String;)Lkotlin/text/MatchGroup;") => groups_get_named,
+        (MATCH_GROUPS, "size:()I") => groups_size,
+        (MATCH_GROUP, "getValue:()Ljava/lang/String;") => group_value,
+        (DESTRUCTURED, "getMatch:()Lkotlin/text/MatchResult;") => destructured_match,
+        (DESTRUCTURED, "toList:()Ljava/util/List;") => destructured_to_list,
+        (DESTRUCTURED, "component1:()Ljava/lang/String;") => destructured_component::<1>,
+        (DESTRUCTURED, "component2:()Ljava/lang/String;") => destructured_component::<2>,
+        (DESTRUCTURED, "component3:()Ljava/lang/String;") => destructured_component::<3>,
+        (DESTRUCTURED, "component4:()Ljava/lang/String;") => destructured_component::<4>,
+        (DESTRUCTURED, "component5:()Ljava/lang/String;") => destructured_component::<5>,
+        (DESTRUCTURED, "component6:()Ljava/lang/String;") => destructured_component::<6>,
+        (DESTRUCTURED, "component7:()Ljava/lang/String;") => destructured_component::<7>,
+        (DESTRUCTURED, "component8:()Ljava/lang/String;") => destructured_component::<8>,
+        (DESTRUCTURED, "component9:()Ljava/lang/String;") => destructured_component::<9>,
+        (DESTRUCTURED, "component10:()Ljava/lang/String;") => destructured_component::<10>,
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// Start and end of each group of a match, `None` for groups that didn't take part
+type Groups = Vec<Option<(usize, usize)>>;
+
+/// How much of the input a pattern has to cover
+#[derive(Clone, Copy)]
+enum Anchor {
+    None,
+    Start,
+    Both,
+}
+
+fn byte_offset(text: &str, unit: usize) -> usize {
+    let mut units = 0;
+    for (offset, c) in text.char_indices() {
+        if units >= unit {
+            return offset;
+        }
+        units += c.len_utf16();
+    }
+    text.len()
+}
+
+fn unit_offset(text: &str, byte: usize) -> usize {
+    text[..byte].encode_utf16().count()
+}
+
+fn slice(text: &str, start: usize, end: usize) -> String {
+    text[byte_offset(text, start)..byte_offset(text, end)].to_string()
+}
+
+/// Java pattern syntax fancy-regex doesn't know, rewritten to what it does
+fn translate(source: &str, flags: i32) -> String {
+    if flags & LITERAL != 0 {
+        return fancy_regex::escape(source).into_owned();
+    }
+
+    let mut pattern = String::new();
+    for (flag, letter) in [(CASE_INSENSITIVE, 'i'), (COMMENTS, 'x'), (MULTILINE, 'm'), (DOTALL, 's')] {
+        if flags & flag != 0 {
+            pattern.push_str(&format!("(?{})", letter));
+        }
+    }
+
+    // \Q...\E quotes everything in between
+    let mut rest = source;
+    while let Some(start) = rest.find("\\Q") {
+        // An escaped backslash before \Q makes it a literal Q
+        if rest[..start].chars().rev().take_while(|c| *c == '\\').count() % 2 == 1 {
+            pattern.push_str(&rest[..start + 2]);
+            rest = &rest[start + 2..];
+            continue;
+        }
+        pattern.push_str(&rest[..start]);
+        let quoted = &rest[start + 2..];
+        let end = quoted.find("\\E").unwrap_or(quoted.len());
+        pattern.push_str(&fancy_regex::escape(&quoted[..end]));
+        rest = quoted.get(end + 2..).unwrap_or("");
+    }
+    pattern.push_str(rest);
+    pattern
+}
+
+/// The compiled regex for a Java pattern, throws a PatternSyntaxException for invalid ones
+fn compiled(interpreter: &mut Interpreter, source: &str, flags: i32, anchor: Anchor) -> Option<Arc<Regex>> {
+    let pattern = translate(source, flags);
+    let pattern = match anchor {
+        Anchor::None => pattern,
+        Anchor::Start => format!(r"\A(?:{})", pattern),
+        Anchor::Both => format!(r"\A(?:{})\z", pattern),
+    };
+
+    match compile_translated(pattern, source) {
+        Ok(regex) => Some(regex),
+        Err(message) => {
+            interpreter.throw_new(PATTERN_SYNTAX_EXCEPTION, &message);
+            None
+        }
+    }
+}
+
+/// The compiled regex for a Java pattern without flags, for natives that take one
+pub fn compile(source: &str) -> Result<Arc<Regex>, String> {
+    compile_translated(translate(source, 0), source)
+}
+
+/// The cached regex for a translated pattern, or the PatternSyntaxException message for `source`
+fn compile_translated(pattern: String, source: &str) -> Result<Arc<Regex>, String> {
+    if let Some(regex) = COMPILED.lock().unwrap().get(&pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Arc::new(Regex::new(&pattern).map_err(|error| format!("{}\n{}", error, source))?);
+    COMPILED.lock().unwrap().insert(pattern, regex.clone());
+    Ok(regex)
+}
+
+/// The first match at or after code unit `from`. A pattern that backtracks past the limit
+/// finds nothing rather than failing the call.
+fn search(regex: &Regex, text: &str, from: usize) -> Option<Groups> {
+    let captures = regex.captures_from_pos(text, byte_offset(text, from)).ok()??;
+    let groups = (0..captures.len())
+        .map(|index| captures.get(index).map(|group| (unit_offset(text, group.start()), unit_offset(text, group.end()))))
+        .collect();
+    Some(groups)
+}
+
+/// Where the search after `found` starts: its end, one char further for an empty match
+fn next_position(text: &str, found: &Groups) -> usize {
+    let (start, end) = found[0].unwrap_or((0, 0));
+    if start != end {
+        return end;
+    }
+    let byte = byte_offset(text, end);
+    end + text[byte..].chars().next().map_or(1, char::len_utf16)
+}
+
+/// Every successive match of `regex` in `text`
+fn all_matches(regex: &Regex, text: &str) -> Vec<Groups> {
+    let mut matches = Vec::new();
+    let mut position = 0;
+    let length = text.encode_utf16().count();
+    while position <= length {
+        let Some(found) = search(regex, text, position) else {
+            break;
+        };
+        position = next_position(text, &found);
+        matches.push(found);
+    }
+    matches
+}
+
+fn group_text(text: &str, groups: &Groups, index: usize) -> Option<String> {
+    groups.get(index).copied().flatten().map(|(start, end)| slice(text, start, end))
+}
+
+/// Java's replacement syntax: `$n` and `${name}` insert a group, a backslash escapes the next char
+fn expand(interpreter: &mut Interpreter, regex: &Regex, replacement: &str, text: &str, groups: &Groups) -> Option<String> {
+    let mut expanded = String::new();
+    let mut chars = replacement.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) => expanded.push(escaped),
+                None => {
+                    interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "character to be escaped is missing");
+                    return None;
+                }
+            },
+            '$' if chars.peek() == Some(&'{') => {
+                chars.next();
+                let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+                let index = regex.capture_names().position(|group| group == Some(name.as_str()));
+                let Some(index) = index else {
+                    interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &format!("No group with name {{{}}}", name));
+                    return None;
+                };
+                expanded.push_str(&group_text(text, groups, index).unwrap_or_default());
+            }
+            '$' => {
+                let Some(first) = chars.next().and_then(|c| c.to_digit(10)) else {
+                    interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, "Illegal group reference");
+                    return None;
+                };
+                // Further digits belong to the reference while it still names a group
+                let mut index = first as usize;
+                while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                    let longer = index * 10 + digit as usize;
+                    if longer >= groups.len() {
+                        break;
+                    }
+                    index = longer;
+                    chars.next();
+                }
+                if index >= groups.len() {
+                    interpreter.throw_new(INDEX_OUT_OF_BOUNDS_EXCEPTION, &format!("No group {}", index));
+                    return None;
+                }
+                expanded.push_str(&group_text(text, groups, index).unwrap_or_default());
+            }
+            c => expanded.push(c),
+        }
+    }
+    Some(expanded)
+}
+
+/// `text` with the first `limit` matches, or all of them, replaced
+fn replace(interpreter: &mut Interpreter, regex: &Regex, text: &str, replacement: &str, limit: Option<usize>) -> Option<String> {
+    let mut replaced = String::new();
+    let mut last = 0;
+    for found in all_matches(regex, text).into_iter().take(limit.unwrap_or(usize::MAX)) {
+        let (start, end) = found[0].unwrap_or((0, 0));
+        replaced.push_str(&slice(text, last, start));
+        replaced.push_str(&expand(interpreter, regex, replacement, text, &found)?);
+        last = end;
+    }
+    replaced.push_str(&slice(text, last, text.encode_utf16().count()));
+    Some(replaced)
+}
+
+/// `String.split()` and `Pattern.split()`: a zero-width match at the start doesn't make a
+/// leading empty string, and a zero limit drops the trailing ones
+fn java_split(regex: &Regex, text: &str, limit: i32) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut index = 0;
+    for found in all_matches(regex, text) {
+        if limit > 0 && parts.len() as i32 >= limit - 1 {
+            break;
+        }
+        let (start, end) = found[0].unwrap_or((0, 0));
+        if index == 0 && start == 0 && end == 0 {
+            continue;
+        }
+        parts.push(slice(text, index, start));
+        index = end;
+    }
+    if parts.is_empty() {
+        return vec![text.to_string()];
+    }
+    parts.push(slice(text, index, text.encode_utf16().count()));
+
+    if limit == 0 {
+        while parts.last().is_some_and(String::is_empty) {
+            parts.pop();
+        }
+    }
+    parts
+}
+
+/// `Regex.split()`: like the JVM's but keeping empty strings at both ends
+fn kotlin_split(regex: &Regex, text: &str, limit: i32) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut index = 0;
+    for found in all_matches(regex, text) {
+        if limit > 0 && parts.len() as i32 >= limit - 1 {
+            break;
+        }
+        let (start, end) = found[0].unwrap_or((0, 0));
+        parts.push(slice(text, index, start));
+        index = end;
+    }
+    parts.push(slice(text, index, text.encode_utf16().count()));
+    parts
+}
+
+fn string_array(interpreter: &mut Interpreter, strings: Vec<String>) -> DexValue {
+    let id = interpreter.alloc_object("[Ljava/lang/String;");
+    interpreter.heap.get_mut(&id).unwrap().array = Some(strings.into_iter().map(DexValue::String).collect());
+    DexValue::Object(id)
+}
+
+fn string_list(interpreter: &mut Interpreter, strings: Vec<String>) -> DexValue {
+    DexValue::Object(new_list(interpreter, strings.into_iter().map(DexValue::String).collect()))
+}
+
+fn field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> DexValue {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.fields.get(name).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn text_field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> String {
+    match field(interpreter, this, name) {
+        DexValue::String(text) => text,
+        _ => String::new(),
+    }
+}
+
+fn object_field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> Option<ObjectId> {
+    match field(interpreter, this, name) {
+        DexValue::Object(id) => Some(id),
+        _ => None,
+    }
+}
+
+fn text_arg(interpreter: &mut Interpreter, args: &[DexValue], index: usize) -> String {
+    interpreter.string_value_of(args.get(index).unwrap_or(&DexValue::Null))
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> i32 {
+    args.get(index).and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+/// The compiled regex of a Pattern or Regex object
+fn regex_of(interpreter: &mut Interpreter, pattern: Option<ObjectId>, anchor: Anchor) -> Option<Arc<Regex>> {
+    let source = text_field(interpreter, pattern, SOURCE_FIELD);
+    let flags = field(interpreter, pattern, FLAGS_FIELD).as_int().unwrap_or(0);
+    compiled(interpreter, &source, flags, anchor)
+}
+
+/// Store `source` in a Pattern or Regex object, compiling it once to throw for bad syntax
+fn init_pattern(interpreter: &mut Interpreter, this: Option<ObjectId>, source: String, flags: i32) {
+    if compiled(interpreter, &source, flags, Anchor::None).is_none() {
+        return;
+    }
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(SOURCE_FIELD.to_string(), DexValue::String(source));
+        object.fields.insert(FLAGS_FIELD.to_string(), DexValue::Int(flags));
+    }
+}
+
+/// Groups of the last match of a Matcher or of a MatchResult
+fn groups_of(interpreter: &Interpreter, this: Option<ObjectId>) -> Option<Groups> {
+    let bounds = this.and_then(|id| interpreter.heap.get(&id))?.array.as_ref()?;
+    let groups = bounds.chunks(2)
+        .map(|pair| match (pair[0].as_int(), pair[1].as_int()) {
+            (Some(start), Some(end)) if start >= 0 => Some((start as usize, end as usize)),
+            _ => None,
+        })
+        .collect();
+    Some(groups)
+}
+
+fn store_groups(interpreter: &mut Interpreter, this: Option<ObjectId>, groups: Option<&Groups>) {
+    let bounds = groups.map(|groups| groups.iter()
+        .flat_map(|group| match group {
+            Some((start, end)) => [DexValue::Int(*start as i32), DexValue::Int(*end as i32)],
+            None => [DexValue::Int(-1), DexValue::Int(-1)],
+        })
+        .collect());
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.array = bounds;
+    }
+}
+
+fn source(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(text_field(interpreter, this, SOURCE_FIELD))
+}
+
+fn pattern_compile(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let source = text_arg(interpreter, &args, 0);
+    let id = interpreter.alloc_object(PATTERN);
+    init_pattern(interpreter, Some(id), source, int_arg(&args, 1));
+    DexValue::Object(id)
+}
+
+fn pattern_matches(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let source = text_arg(interpreter, &args, 0);
+    let text = text_arg(interpreter, &args, 1);
+    match compiled(interpreter, &source, 0, Anchor::Both) {
+        Some(regex) => DexValue::Boolean(search(&regex, &text, 0).is_some()),
+        None => DexValue::Void,
+    }
+}
+
+/// `\Q...\E`, with any `\E` in the text closed and reopened around an escaped one
+fn pattern_quote(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let text = text_arg(interpreter, &args, 0);
+    DexValue::String(format!("\\Q{}\\E", text.replace("\\E", "\\E\\\\E\\Q")))
+}
+
+fn pattern_flags(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(field(interpreter, this, FLAGS_FIELD).as_int().unwrap_or(0))
+}
+
+fn pattern_matcher(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    let id = interpreter.alloc_object(MATCHER);
+    let matcher = interpreter.heap.get_mut(&id).unwrap();
+    matcher.fields.insert(PATTERN_FIELD.to_string(), this.map_or(DexValue::Null, DexValue::Object));
+    matcher.fields.insert(INPUT_FIELD.to_string(), DexValue::String(input));
+    matcher.fields.insert(POSITION_FIELD.to_string(), DexValue::Int(0));
+    DexValue::Object(id)
+}
+
+fn pattern_split(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let text = text_arg(interpreter, &args, 0);
+    match regex_of(interpreter, this, Anchor::None) {
+        Some(regex) => {
+            let parts = java_split(&regex, &text, int_arg(&args, 1));
+            string_array(interpreter, parts)
+        }
+        None => DexValue::Void,
+    }
+}
+
+/// Match the matcher's input against its pattern from `from` and remember the outcome
+fn matcher_search(interpreter: &mut Interpreter, this: Option<ObjectId>, anchor: Anchor, from: usize) -> DexValue {
+    let pattern = object_field(interpreter, this, PATTERN_FIELD);
+    let input = text_field(interpreter, this, INPUT_FIELD);
+    let Some(regex) = regex_of(interpreter, pattern, anchor) else {
+        return DexValue::Void;
+    };
+    let found = search(&regex, &input, from);
+
+    store_groups(interpreter, this, found.as_ref());
+    if let (Some(found), Some(matcher)) = (&found, this.and_then(|id| interpreter.heap.get_mut(&id))) {
+        matcher.fields.insert(POSITION_FIELD.to_string(), DexValue::Int(next_position(&input, found) as i32));
+    }
+    DexValue::Boolean(found.is_some())
+}
+
+fn matcher_matches(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    matcher_search(interpreter, this, Anchor::Both, 0)
+}
+
+fn matcher_looking_at(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    matcher_search(interpreter, this, Anchor::Start, 0)
+}
+
+fn matcher_find(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let position = field(interpreter, this, POSITION_FIELD).as_int().unwrap_or(0) as usize;
+    if position > text_field(interpreter, this, INPUT_FIELD).encode_utf16().count() {
+        store_groups(interpreter, this, None);
+        return DexValue::Boolean(false);
+    }
+    matcher_search(interpreter, this, Anchor::None, position)
+}
+
+fn matcher_find_from(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let from = int_arg(&args, 0);
+    let length = text_field(interpreter, this, INPUT_FIELD).encode_utf16().count();
+    if from < 0 || from as usize > length {
+        interpreter.throw_new(INDEX_OUT_OF_BOUNDS_EXCEPTION, "Illegal start index");
+        return DexValue::Void;
+    }
+    matcher_search(interpreter, this, Anchor::None, from as usize)
+}
+
+/// Bounds of group `index` of the last match, throws without one or for a missing group
+fn matcher_bounds(interpreter: &mut Interpreter, this: Option<ObjectId>, index: i32) -> Option<Option<(usize, usize)>> {
+    let Some(groups) = groups_of(interpreter, this) else {
+        interpreter.throw_new(ILLEGAL_STATE_EXCEPTION, "No match found");
+        return None;
+    };
+    match usize::try_from(index).ok().and_then(|index| groups.get(index)) {
+        Some(bounds) => Some(*bounds),
+        None => {
+            interpreter.throw_new(INDEX_OUT_OF_BOUNDS_EXCEPTION, &format!("No group {}", index));
+            None
+        }
+    }
+}
+
+fn matcher_group(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_field(interpreter, this, INPUT_FIELD);
+    match matcher_bounds(interpreter, this, int_arg(&args, 0)) {
+        Some(Some((start, end))) => DexValue::String(slice(&input, start, end)),
+        Some(None) => DexValue::Null,
+        None => DexValue::Void,
+    }
+}
+
+fn matcher_named_group(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = text_arg(interpreter, &args, 0);
+    let pattern = object_field(interpreter, this, PATTERN_FIELD);
+    let Some(regex) = regex_of(interpreter, pattern, Anchor::None) else {
+        return DexValue::Void;
+    };
+    match regex.capture_names().position(|group| group == Some(name.as_str())) {
+        Some(index) => matcher_group(interpreter, this, vec![DexValue::Int(index as i32)]),
+        None => {
+            interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &format!("No group with name <{}>", name));
+            DexValue::Void
+        }
+    }
+}
+
+fn matcher_group_count(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let pattern = object_field(interpreter, this, PATTERN_FIELD);
+    match regex_of(interpreter, pattern, Anchor::None) {
+        Some(regex) => DexValue::Int(regex.captures_len() as i32 - 1),
+        None => DexValue::Void,
+    }
+}
+
+fn matcher_start(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match matcher_bounds(interpreter, this, int_arg(&args, 0)) {
+        Some(bounds) => DexValue::Int(bounds.map_or(-1, |(start, _)| start as i32)),
+        None => DexValue::Void,
+    }
+}
+
+fn matcher_end(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match matcher_bounds(interpreter, this, int_arg(&args, 0)) {
+        Some(bounds) => DexValue::Int(bounds.map_or(-1, |(_, end)| end as i32)),
+        None => DexValue::Void,
+    }
+}
+
+fn matcher_replace(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>, limit: Option<usize>) -> DexValue {
+    let replacement = text_arg(interpreter, &args, 0);
+    let input = text_field(interpreter, this, INPUT_FIELD);
+    let pattern = object_field(interpreter, this, PATTERN_FIELD);
+    let Some(regex) = regex_of(interpreter, pattern, Anchor::None) else {
+        return DexValue::Void;
+    };
+    matcher_reset(interpreter, this, Vec::new());
+    replace(interpreter, &regex, &input, &replacement, limit).map_or(DexValue::Void, DexValue::String)
+}
+
+fn matcher_replace_all(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    matcher_replace(interpreter, this, args, None)
+}
+
+fn matcher_replace_first(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    matcher_replace(interpreter, this, args, Some(1))
+}
+
+fn matcher_reset(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    store_groups(interpreter, this, None);
+    if let Some(matcher) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        matcher.fields.insert(POSITION_FIELD.to_string(), DexValue::Int(0));
+    }
+    this.map_or(DexValue::Null, DexValue::Object)
+}
+
+fn regex_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let source = text_arg(interpreter, &args, 0);
+    init_pattern(interpreter, this, source, 0);
+    DexValue::Void
+}
+
+/// A MatchResult for `groups` of `input`, null without a match
+fn match_result(interpreter: &mut Interpreter, regex: Option<ObjectId>, input: &str, groups: Option<Groups>) -> DexValue {
+    let Some(groups) = groups else {
+        return DexValue::Null;
+    };
+    let id = interpreter.alloc_object(MATCH_RESULT);
+    let result = interpreter.heap.get_mut(&id).unwrap();
+    result.fields.insert(PATTERN_FIELD.to_string(), regex.map_or(DexValue::Null, DexValue::Object));
+    result.fields.insert(INPUT_FIELD.to_string(), DexValue::String(input.to_string()));
+    store_groups(interpreter, Some(id), Some(&groups));
+    DexValue::Object(id)
+}
+
+fn regex_find(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    let from = int_arg(&args, 1);
+    let length = input.encode_utf16().count();
+    if from < 0 || from as usize > length {
+        interpreter.throw_new(INDEX_OUT_OF_BOUNDS_EXCEPTION, &format!("Start index out of bounds: {}, input length: {}", from, length));
+        return DexValue::Void;
+    }
+    let Some(regex) = regex_of(interpreter, this, Anchor::None) else {
+        return DexValue::Void;
+    };
+    let found = search(&regex, &input, from as usize);
+    match_result(interpreter, this, &input, found)
+}
+
+/// The `$default` stubs are static and take the regex first, bit 1 of the mask defaults the
+/// start index or limit to 0
+fn default_stub(args: Vec<DexValue>) -> (Option<ObjectId>, Vec<DexValue>) {
+    let this = match args.first() {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    };
+    let input = args.get(1).cloned().unwrap_or(DexValue::Null);
+    let value = if int_arg(&args, 3) & 2 != 0 { 0 } else { int_arg(&args, 2) };
+    (this, vec![input, DexValue::Int(value)])
+}
+
+fn regex_find_default(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let (this, args) = default_stub(args);
+    regex_find(interpreter, this, args)
+}
+
+/// The matches as a list, which iterates like the lazy sequence Kotlin returns
+fn regex_find_all(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    let from = int_arg(&args, 1).max(0) as usize;
+    let Some(regex) = regex_of(interpreter, this, Anchor::None) else {
+        return DexValue::Void;
+    };
+    let offset = byte_offset(&input, from);
+    let found: Vec<Groups> = all_matches(&regex, &input[offset..]).into_iter()
+        .map(|groups| groups.into_iter().map(|group| group.map(|(start, end)| (start + from, end + from))).collect())
+        .collect();
+    let results = found.into_iter().map(|groups| match_result(interpreter, this, &input, Some(groups))).collect();
+    DexValue::Object(new_list(interpreter, results))
+}
+
+fn regex_find_all_default(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let (this, args) = default_stub(args);
+    regex_find_all(interpreter, this, args)
+}
+
+fn regex_match_entire(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    let Some(regex) = regex_of(interpreter, this, Anchor::Both) else {
+        return DexValue::Void;
+    };
+    let found = search(&regex, &input, 0);
+    match_result(interpreter, this, &input, found)
+}
+
+fn regex_matches(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    match regex_of(interpreter, this, Anchor::Both) {
+        Some(regex) => DexValue::Boolean(search(&regex, &input, 0).is_some()),
+        None => DexValue::Void,
+    }
+}
+
+fn regex_contains_match_in(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    match regex_of(interpreter, this, Anchor::None) {
+        Some(regex) => DexValue::Boolean(search(&regex, &input, 0).is_some()),
+        None => DexValue::Void,
+    }
+}
+
+fn regex_replace_matches(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>, limit: Option<usize>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    let replacement = text_arg(interpreter, &args, 1);
+    let Some(regex) = regex_of(interpreter, this, Anchor::None) else {
+        return DexValue::Void;
+    };
+    replace(interpreter, &regex, &input, &replacement, limit).map_or(DexValue::Void, DexValue::String)
+}
+
+fn regex_replace(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    regex_replace_matches(interpreter, this, args, None)
+}
+
+fn regex_replace_first(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    regex_replace_matches(interpreter, this, args, Some(1))
+}
+
+/// `replace(input) { match -> ... }`, each match replaced by the text the lambda returns
+fn regex_replace_with(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    let transform = args.get(1).cloned().unwrap_or(DexValue::Null);
+    let Some(regex) = regex_of(interpreter, this, Anchor::None) else {
+        return DexValue::Void;
+    };
+
+    let mut replaced = String::new();
+    let mut last = 0;
+    for found in all_matches(&regex, &input) {
+        let (start, end) = found[0].unwrap_or((0, 0));
+        replaced.push_str(&slice(&input, last, start));
+        let result = match_result(interpreter, this, &input, Some(found));
+        let replacement = interpreter.invoke_function(&transform, vec![result]).unwrap_or(DexValue::Null);
+        if interpreter.pending_exception.is_some() {
+            return DexValue::Void;
+        }
+        replaced.push_str(&interpreter.string_value_of(&replacement));
+        last = end;
+    }
+    replaced.push_str(&slice(&input, last, input.encode_utf16().count()));
+    DexValue::String(replaced)
+}
+
+fn regex_split(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let input = text_arg(interpreter, &args, 0);
+    let limit = int_arg(&args, 1);
+    if limit < 0 {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &format!("Limit must be non-negative, but was {}", limit));
+        return DexValue::Void;
+    }
+    let Some(regex) = regex_of(interpreter, this, Anchor::None) else {
+        return DexValue::Void;
+    };
+    let parts = kotlin_split(&regex, &input, limit);
+    string_list(interpreter, parts)
+}
+
+fn regex_split_default(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let (this, args) = default_stub(args);
+    regex_split(interpreter, this, args)
+}
+
+/// Every group's text, with the empty string for groups that didn't take part
+fn group_values(interpreter: &Interpreter, this: Option<ObjectId>) -> Vec<String> {
+    let input = text_field(interpreter, this, INPUT_FIELD);
+    let groups = groups_of(interpreter, this).unwrap_or_default();
+    (0..groups.len()).map(|index| group_text(&input, &groups, index).unwrap_or_default()).collect()
+}
+
+fn match_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(group_values(interpreter, this).into_iter().next().unwrap_or_default())
+}
+
+fn match_group_values(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let values = group_values(interpreter, this);
+    string_list(interpreter, values)
+}
+
+/// An object pointing back at the match, for the groups and destructured views
+fn match_view(interpreter: &mut Interpreter, class_name: &str, this: Option<ObjectId>) -> DexValue {
+    let id = interpreter.alloc_object(class_name);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(MATCH_FIELD.to_string(), this.map_or(DexValue::Null, DexValue::Object));
+    DexValue::Object(id)
+}
+
+fn match_groups(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    match_view(interpreter, MATCH_GROUPS, this)
+}
+
+fn match_destructured(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    match_view(interpreter, DESTRUCTURED, this)
+}
+
+fn match_next(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let regex = object_field(interpreter, this, PATTERN_FIELD);
+    let input = text_field(interpreter, this, INPUT_FIELD);
+    let Some(groups) = groups_of(interpreter, this) else {
+        return DexValue::Null;
+    };
+    let position = next_position(&input, &groups);
+    if position > input.encode_utf16().count() {
+        return DexValue::Null;
+    }
+    let Some(compiled) = regex_of(interpreter, regex, Anchor::None) else {
+        return DexValue::Void;
+    };
+    let found = search(&compiled, &input, position);
+    match_result(interpreter, regex, &input, found)
+}
+
+/// A MatchGroup for group `index` of the match behind a groups view, null for a missing group
+fn match_group(interpreter: &mut Interpreter, this: Option<ObjectId>, index: Option<usize>) -> DexValue {
+    let result = object_field(interpreter, this, MATCH_FIELD);
+    let input = text_field(interpreter, result, INPUT_FIELD);
+    let groups = groups_of(interpreter, result).unwrap_or_default();
+    let Some(value) = index.and_then(|index| group_text(&input, &groups, index)) else {
+        return DexValue::Null;
+    };
+    let id = interpreter.alloc_object(MATCH_GROUP);
+    interpreter.heap.get_mut(&id).unwrap().fields.insert(VALUE_FIELD.to_string(), DexValue::String(value));
+    DexValue::Object(id)
+}
+
+fn groups_get(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    match_group(interpreter, this, usize::try_from(int_arg(&args, 0)).ok())
+}
+
+fn groups_get_named(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = text_arg(interpreter, &args, 0);
+    let result = object_field(interpreter, this, MATCH_FIELD);
+    let regex = object_field(interpreter, result, PATTERN_FIELD);
+    let Some(compiled) = regex_of(interpreter, regex, Anchor::None) else {
+        return DexValue::Void;
+    };
+    let Some(index) = compiled.capture_names().position(|group| group == Some(name.as_str())) else {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &format!("No group with name <{}>", name));
+        return DexValue::Void;
+    };
+    match_group(interpreter, this, Some(index))
+}
+
+fn groups_size(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let result = object_field(interpreter, this, MATCH_FIELD);
+    DexValue::Int(groups_of(interpreter, result).map_or(0, |groups| groups.len()) as i32)
+}
+
+fn group_value(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    field(interpreter, this, VALUE_FIELD)
+}
+
+fn destructured_match(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    field(interpreter, this, MATCH_FIELD)
+}
+
+/// The group values without the whole match
+fn destructured_to_list(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let result = object_field(interpreter, this, MATCH_FIELD);
+    let values = group_values(interpreter, result).into_iter().skip(1).collect();
+    string_list(interpreter, values)
+}
+
+/// `componentN()` is group N, out of range ones throw like `groupValues[N]`
+fn destructured_component<const N: usize>(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let result = object_field(interpreter, this, MATCH_FIELD);
+    let values = group_values(interpreter, result);
+    match values.get(N) {
+        Some(value) => DexValue::String(value.clone()),
+        None => {
+            let message = format!("Index {} out of bounds for length {}", N, values.len());
+            interpreter.throw_new(INDEX_OUT_OF_BOUNDS_EXCEPTION, &message);
+            DexValue::Void
+        }
+    }
+}
+
+// String methods taking a regex, `string_method` hands them the receiver's text
+
+pub fn string_matches(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    let source = text_arg(interpreter, &args, 0);
+    match compiled(interpreter, &source, 0, Anchor::Both) {
+        Some(regex) => DexValue::Boolean(search(&regex, text, 0).is_some()),
+        None => DexValue::Void,
+    }
+}
+
+pub fn string_replace_all(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    string_replace(interpreter, text, args, None)
+}
+
+pub fn string_replace_first(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    string_replace(interpreter, text, args, Some(1))
+}
+
+fn string_replace(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>, limit: Option<usize>) -> DexValue {
+    let source = text_arg(interpreter, &args, 0);
+    let replacement = text_arg(interpreter, &args, 1);
+    let Some(regex) = compiled(interpreter, &source, 0, Anchor::None) else {
+        return DexValue::Void;
+    };
+    replace(interpreter, &regex, text, &replacement, limit).map_or(DexValue::Void, DexValue::String)
+}
+
+pub fn string_split(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    let source = text_arg(interpreter, &args, 0);
+    let Some(regex) = compiled(interpreter, &source, 0, Anchor::None) else {
+        return DexValue::Void;
+    };
+    let parts = java_split(&regex, text, int_arg(&args, 1));
+    string_array(interpreter, parts)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::collections::elements_of;
+    use crate::selftest::{parse_micro_dex, self_test_cases};
+
+    fn call(interpreter: &mut Interpreter, class_name: &str, method_key: &str, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+        platform_method(class_name, method_key).unwrap()(interpreter, this, args)
+    }
+
+    fn string(text: &str) -> DexValue {
+        DexValue::String(text.to_string())
+    }
+
+    fn strings(interpreter: &Interpreter, value: &DexValue) -> Vec<String> {
+        elements_of(interpreter, value).into_iter()
+            .map(|element| match element {
+                DexValue::String(text) => text,
+                element => panic!("{:?} is no string", element),
+            })
+            .collect()
+    }
+
+    /// Class of the exception the last call threw, if any
+    fn thrown(interpreter: &mut Interpreter) -> Option<String> {
+        match interpreter.pending_exception.take()?.exception {
+            DexValue::Object(id) => Some(interpreter.heap[&id].class_name.clone()),
+            _ => None,
+        }
+    }
+
+    fn matcher(interpreter: &mut Interpreter, source: &str, input: &str) -> Option<ObjectId> {
+        let pattern = call(interpreter, PATTERN, "compile:(Ljava/lang/String;)Ljava/util/regex/Pattern;", None, vec![string(source)]);
+        match call(interpreter, PATTERN, "matcher:(Ljava/lang/CharSequence;)Ljava/util/regex/Matcher;", object_id(&pattern), vec![string(input)]) {
+            DexValue::Object(id) => Some(id),
+            _ => None,
+        }
+    }
+
+    fn object_id(value: &DexValue) -> Option<ObjectId> {
+        match value {
+            DexValue::Object(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    fn kotlin_regex(interpreter: &mut Interpreter, source: &str) -> Option<ObjectId> {
+        let regex = interpreter.alloc_object(REGEX);
+        call(interpreter, REGEX, "<init>:(Ljava/lang/String;)V", Some(regex), vec![string(source)]);
+        Some(regex)
+    }
+
+    /// Positions are UTF-16 code units, the emoji takes two
+    #[test]
+    fn finds_matches_and_groups() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let matcher = matcher(&mut interpreter, r"(\w+)@(?<host>\w+)\.com", "😀 ab@cd.com, e@f.com");
+        assert_eq!(call(&mut interpreter, MATCHER, "groupCount:()I", matcher, Vec::new()), DexValue::Int(2));
+
+        assert_eq!(call(&mut interpreter, MATCHER, "find:()Z", matcher, Vec::new()), DexValue::Boolean(true));
+        assert_eq!(call(&mut interpreter, MATCHER, "group:()Ljava/lang/String;", matcher, Vec::new()), string("ab@cd.com"));
+        assert_eq!(call(&mut interpreter, MATCHER, "group:(I)Ljava/lang/String;", matcher, vec![DexValue::Int(1)]), string("ab"));
+        assert_eq!(call(&mut interpreter, MATCHER, "group:(Ljava/lang/String;)Ljava/lang/String;", matcher, vec![string("host")]), string("cd"));
+        assert_eq!(call(&mut interpreter, MATCHER, "start:()I", matcher, Vec::new()), DexValue::Int(3));
+        assert_eq!(call(&mut interpreter, MATCHER, "end:(I)I", matcher, vec![DexValue::Int(2)]), DexValue::Int(8));
+
+        assert_eq!(call(&mut interpreter, MATCHER, "find:()Z", matcher, Vec::new()), DexValue::Boolean(true));
+        assert_eq!(call(&mut interpreter, MATCHER, "group:()Ljava/lang/String;", matcher, Vec::new()), string("e@f.com"));
+        assert_eq!(call(&mut interpreter, MATCHER, "find:()Z", matcher, Vec::new()), DexValue::Boolean(false));
+        call(&mut interpreter, MATCHER, "group:()Ljava/lang/String;", matcher, Vec::new());
+        assert_eq!(thrown(&mut interpreter).as_deref(), Some(ILLEGAL_STATE_EXCEPTION));
+
+        let regex = kotlin_regex(&mut interpreter, r"(\w+)@(\w+)\.com");
+        let found = call(&mut interpreter, REGEX, "find:(Ljava/lang/CharSequence;I)Lkotlin/text/MatchResult;", regex, vec![string("ab@cd.com, e@f.com"), DexValue::Int(1)]);
+        let values = call(&mut interpreter, MATCH_RESULT, "getGroupValues:()Ljava/util/List;", object_id(&found), Vec::new());
+        assert_eq!(strings(&interpreter, &values), ["b@cd.com", "b", "cd"]);
+        let next = call(&mut interpreter, MATCH_RESULT, "next:()Lkotlin/text/MatchResult;", object_id(&found), Vec::new());
+        assert_eq!(call(&mut interpreter, MATCH_RESULT, "getValue:()Ljava/lang/String;", object_id(&next), Vec::new()), string("e@f.com"));
+        let last = call(&mut interpreter, MATCH_RESULT, "next:()Lkotlin/text/MatchResult;", object_id(&next), Vec::new());
+        assert_eq!(last, DexValue::Null);
+    }
+
+    #[test]
+    fn replaces_with_group_references() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        let text = "ab@cd.com, e@f.com";
+        let matcher = matcher(&mut interpreter, r"(\w+)@(?<host>\w+)\.com", text);
+        let replace_all = "replaceAll:(Ljava/lang/String;)Ljava/lang/String;";
+        assert_eq!(call(&mut interpreter, MATCHER, replace_all, matcher, vec![string("$2/$1")]), string("cd/ab, f/e"));
+        assert_eq!(call(&mut interpreter, MATCHER, replace_all, matcher, vec![string("${host}")]), string("cd, f"));
+        // An escaped dollar is literal, and a digit past the last group isn't part of the reference
+        assert_eq!(call(&mut interpreter, MATCHER, replace_all, matcher, vec![string(r"\$$10")]), string("$ab0, $e0"));
+        assert_eq!(call(&mut interpreter, MATCHER, "replaceFirst:(Ljava/lang/String;)Ljava/lang/String;", matcher, vec![string("<$0>")]), string("<ab@cd.com>, e@f.com"));
+        call(&mut interpreter, MATCHER, replace_all, matcher, vec![string("$3")]);
+        assert_eq!(thrown(&mut interpreter).as_deref(), Some(INDEX_OUT_OF_BOUNDS_EXCEPTION));
+        call(&mut interpreter, MATCHER, replace_all, matcher, vec![string("${port}")]);
+        assert_eq!(thrown(&mut interpreter).as_deref(), Some(ILLEGAL_ARGUMENT_EXCEPTION));
+
+        assert_eq!(string_replace_all(&mut interpreter, "2024-10-16", vec![string(r"(\d+)-(\d+)-(\d+)"), string("$3.$2.$1")]), string("16.10.2024"));
+        assert_eq!(string_replace_first(&mut interpreter, "a.b.c", vec![string(r"\."), string("$")]), DexValue::Void);
+        assert_eq!(thrown(&mut interpreter).as_deref(), Some(ILLEGAL_ARGUMENT_EXCEPTION));
+
+        let regex = kotlin_regex(&mut interpreter, "(a)(b)?");
+        let replaced = call(&mut interpreter, REGEX, "replace:(Ljava/lang/CharSequence;Ljava/lang/String;)Ljava/lang/String;", regex, vec![string("ab a"), string("[$1$2]")]);
+        assert_eq!(replaced, string("[ab] [a]"));
+    }
+
+    #[test]
+    fn splits_like_java_and_kotlin() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        for (limit, expected) in [(0, &["a", "b", "", "c"][..]), (-1, &["a", "b", "", "c", "", ""]), (2, &["a", "b,,c,,"])] {
+            let parts = string_split(&mut interpreter, "a,b,,c,,", vec![string(","), DexValue::Int(limit)]);
+            assert_eq!(strings(&interpreter, &parts), expected, "limit {}", limit);
+        }
+        // No leading empty string for a zero-width match at the start
+        let parts = string_split(&mut interpreter, "abc", vec![string(""), DexValue::Int(0)]);
+        assert_eq!(strings(&interpreter, &parts), ["a", "b", "c"]);
+
+        let pattern = call(&mut interpreter, PATTERN, "compile:(Ljava/lang/String;)Ljava/util/regex/Pattern;", None, vec![string(r"\s*;\s*")]);
+        let parts = call(&mut interpreter, PATTERN, "split:(Ljava/lang/CharSequence;)[Ljava/lang/String;", object_id(&pattern), vec![string("x ; y;z")]);
+        assert_eq!(strings(&interpreter, &parts), ["x", "y", "z"]);
+
+        // Kotlin keeps the empty strings at both ends
+        let regex = kotlin_regex(&mut interpreter, ",");
+        let parts = call(&mut interpreter, REGEX, "split:(Ljava/lang/CharSequence;I)Ljava/util/List;", regex, vec![string(",a,b,"), DexValue::Int(0)]);
+        assert_eq!(strings(&interpreter, &parts), ["", "a", "b", ""]);
+        call(&mut interpreter, REGEX, "split:(Ljava/lang/CharSequence;I)Ljava/util/List;", regex, vec![string("a"), DexValue::Int(-1)]);
+        assert_eq!(thrown(&mut interpreter).as_deref(), Some(ILLEGAL_ARGUMENT_EXCEPTION));
+    }
+
+    #[test]
+    fn throws_for_invalid_patterns() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        call(&mut interpreter, PATTERN, "compile:(Ljava/lang/String;)Ljava/util/regex/Pattern;", None, vec![string("(unclosed")]);
+        assert_eq!(thrown(&mut interpreter).as_deref(), Some(PATTERN_SYNTAX_EXCEPTION));
+        // \Q...\E quotes what would otherwise be syntax
+        assert_eq!(string_matches(&mut interpreter, "a+(b", vec![string(r"\Qa+(b\E")]), DexValue::Boolean(true));
+    }
+}
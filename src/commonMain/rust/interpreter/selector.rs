@@ -0,0 +1,672 @@
+// CSS selectors of Jsoup's select(), run against the node objects of the jsoup module. Queries
+// are parsed the way Jsoup's QueryParser reads them, including a leading combinator that is
+// relative to the element select() was called on, and matched right to left. Like Jsoup, tag,
+// class and attribute comparisons ignore case while ids don't, and ancestors are only looked for
+// up to that element. Jsoup's own pseudo classes are supported too: `:contains` and `:matches`
+// with their variants, `:has`, `:is`, the sibling indexes `:eq`, `:lt` and `:gt`, and `~=`
+// matching an attribute value against a regex rather than a word.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::jsoup;
+use crate::interpreter::regex;
+use crate::types::ObjectId;
+
+lazy_static! {
+    /// Parsed selectors by their query, extensions select with the same few over and over
+    static ref PARSED: Mutex<HashMap<String, Arc<Selector>>> = Mutex::new(HashMap::new());
+}
+
+/// Comma-separated alternatives, an element matches if any does
+#[derive(Debug)]
+pub struct Selector {
+    alternatives: Vec<Complex>,
+}
+
+/// Compounds left to right, each with the combinator joining it to the one before
+#[derive(Debug)]
+struct Complex {
+    parts: Vec<(Combinator, Vec<Simple>)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Combinator {
+    Descendant,
+    Child,
+    NextSibling,
+    SubsequentSibling,
+}
+
+#[derive(Debug)]
+enum Simple {
+    /// Lowercase tag name
+    Tag(String),
+    Id(String),
+    Class(String),
+    Attribute(String, AttributeTest),
+    /// `[^prefix]`, some attribute's name starts with it
+    AttributePrefix(String),
+    /// The element the query runs on, standing left of a leading combinator
+    Scope,
+    /// `:root`
+    Root,
+    /// The structural pseudo classes: position `a`n+`b` among the element siblings, or those of
+    /// the same tag, counted from the first or the last
+    Nth { a: i32, b: i32, backwards: bool, of_type: bool },
+    OnlyChild,
+    OnlyOfType,
+    Empty,
+    Not(Selector),
+    /// `:is`, any of the selectors matches
+    Is(Selector),
+    /// `:has`, a descendant matches with the element as the root
+    Has(Selector),
+    /// `:eq`, `:lt` and `:gt`: how the index among the element siblings compares to the number
+    Index(Ordering, i32),
+    /// `:contains` and its variants: the text holds the lowercase search text, or the search text
+    /// as given for the whole text
+    Contains(TextSource, String),
+    /// `:matches` and its variants: the regex finds a match in the text
+    Matches(TextSource, Arc<Regex>),
+}
+
+/// The text of an element a pseudo class looks at
+#[derive(Debug, Clone, Copy)]
+enum TextSource {
+    /// `text()`, normalized
+    Text,
+    /// `ownText()`
+    Own,
+    /// `wholeText()`, as it is
+    Whole,
+    /// `data()`, of scripts, styles and comments
+    Data,
+}
+
+/// What an attribute's value is tested with, values are lowercase and trimmed
+#[derive(Debug)]
+enum AttributeTest {
+    Exists,
+    Equals(String),
+    NotEquals(String),
+    Prefix(String),
+    Suffix(String),
+    Contains(String),
+    /// `~=`, the regex finds a match in the value as it is
+    Matches(Arc<Regex>),
+}
+
+impl Selector {
+    /// The parsed `query`, or the message of the SelectorParseException Jsoup throws for it
+    pub fn parse(query: &str) -> Result<Arc<Selector>, String> {
+        if let Some(selector) = PARSED.lock().unwrap().get(query) {
+            return Ok(selector.clone());
+        }
+        let selector = Arc::new(QueryParser::new(query).parse()?);
+        PARSED.lock().unwrap().insert(query.to_string(), selector.clone());
+        Ok(selector)
+    }
+
+    /// Whether `element` matches, `root` being the element the query runs on
+    pub fn matches(&self, interpreter: &Interpreter, root: ObjectId, element: ObjectId) -> bool {
+        self.alternatives.iter().any(|complex| complex.matches(interpreter, root, element, complex.parts.len() - 1))
+    }
+
+    /// `root` and its descendant elements that match, in document order
+    pub fn select(&self, interpreter: &Interpreter, root: ObjectId) -> Vec<ObjectId> {
+        jsoup::descendant_elements(interpreter, root)
+            .into_iter()
+            .filter(|element| self.matches(interpreter, root, *element))
+            .collect()
+    }
+}
+
+impl Complex {
+    /// Whether `element` matches the parts up to `last`
+    fn matches(&self, interpreter: &Interpreter, root: ObjectId, element: ObjectId, last: usize) -> bool {
+        let (combinator, compound) = &self.parts[last];
+        if !compound.iter().all(|simple| simple.matches(interpreter, root, element)) {
+            return false;
+        }
+        if last == 0 {
+            return true;
+        }
+        if element == root {
+            return false;
+        }
+        match combinator {
+            Combinator::Descendant => {
+                let mut ancestor = jsoup::parent_of(interpreter, element);
+                while let Some(current) = ancestor {
+                    if self.matches(interpreter, root, current, last - 1) {
+                        return true;
+                    }
+                    if current == root {
+                        break;
+                    }
+                    ancestor = jsoup::parent_of(interpreter, current);
+                }
+                false
+            }
+            Combinator::Child => {
+                jsoup::parent_of(interpreter, element).is_some_and(|parent| self.matches(interpreter, root, parent, last - 1))
+            }
+            Combinator::NextSibling => {
+                let siblings = sibling_elements(interpreter, element);
+                let index = siblings.iter().position(|sibling| *sibling == element).unwrap_or(0);
+                index > 0 && self.matches(interpreter, root, siblings[index - 1], last - 1)
+            }
+            Combinator::SubsequentSibling => {
+                let siblings = sibling_elements(interpreter, element);
+                siblings.iter()
+                    .take_while(|sibling| **sibling != element)
+                    .any(|sibling| self.matches(interpreter, root, *sibling, last - 1))
+            }
+        }
+    }
+}
+
+impl Simple {
+    fn matches(&self, interpreter: &Interpreter, root: ObjectId, element: ObjectId) -> bool {
+        match self {
+            Simple::Tag(tag) => jsoup::tag_name(interpreter, element).is_some_and(|name| name.eq_ignore_ascii_case(tag)),
+            Simple::Id(id) => jsoup::attribute(interpreter, element, "id").is_some_and(|value| value == *id),
+            Simple::Class(class) => jsoup::has_class(interpreter, element, class),
+            Simple::Attribute(key, test) => {
+                let value = jsoup::attribute(interpreter, element, key);
+                let normalized = || value.as_deref().unwrap_or("").trim().to_lowercase();
+                match test {
+                    AttributeTest::Exists => value.is_some(),
+                    AttributeTest::Equals(expected) => value.is_some() && normalized() == *expected,
+                    AttributeTest::NotEquals(expected) => normalized() != *expected,
+                    AttributeTest::Prefix(prefix) => value.is_some() && normalized().starts_with(prefix.as_str()),
+                    AttributeTest::Suffix(suffix) => value.is_some() && normalized().ends_with(suffix.as_str()),
+                    AttributeTest::Contains(part) => value.is_some() && normalized().contains(part.as_str()),
+                    AttributeTest::Matches(regex) => value.is_some_and(|value| regex.is_match(&value).unwrap_or(false)),
+                }
+            }
+            Simple::AttributePrefix(prefix) => {
+                jsoup::attributes(interpreter, element).iter().any(|(key, _)| key.to_lowercase().starts_with(prefix.as_str()))
+            }
+            Simple::Scope => element == root,
+            Simple::Root => {
+                let root = if jsoup::is_document(interpreter, root) {
+                    jsoup::element_children(interpreter, root).first().copied()
+                } else {
+                    Some(root)
+                };
+                root == Some(element)
+            }
+            Simple::Nth { a, b, backwards, of_type } => {
+                let Some(position) = position(interpreter, element, *backwards, *of_type) else {
+                    return false;
+                };
+                if *a == 0 {
+                    return position == *b;
+                }
+                (position - b) * a.signum() >= 0 && (position - b) % a == 0
+            }
+            Simple::OnlyChild => {
+                has_element_parent(interpreter, element) && sibling_elements(interpreter, element).len() == 1
+            }
+            Simple::OnlyOfType => {
+                has_element_parent(interpreter, element) && same_type_siblings(interpreter, element).len() == 1
+            }
+            Simple::Empty => jsoup::is_empty(interpreter, element),
+            Simple::Not(selector) => !selector.matches(interpreter, root, element),
+            Simple::Is(selector) => selector.matches(interpreter, root, element),
+            Simple::Has(selector) => {
+                jsoup::descendant_elements(interpreter, element)
+                    .into_iter()
+                    .any(|descendant| descendant != element && selector.matches(interpreter, element, descendant))
+            }
+            Simple::Index(ordering, index) => {
+                let position = sibling_elements(interpreter, element).iter().position(|sibling| *sibling == element).unwrap_or(0);
+                // Like in Jsoup, `:lt` never matches the element the query runs on
+                (*ordering != Ordering::Less || element != root) && (position as i32).cmp(index) == *ordering
+            }
+            Simple::Contains(source, search) => match source {
+                TextSource::Text => jsoup::text(interpreter, element).to_lowercase().contains(search.as_str()),
+                TextSource::Own => jsoup::own_text(interpreter, element).to_lowercase().contains(search.as_str()),
+                TextSource::Whole => jsoup::whole_text(interpreter, element).contains(search.as_str()),
+                TextSource::Data => jsoup::data(interpreter, element).to_lowercase().contains(search.as_str()),
+            },
+            Simple::Matches(source, regex) => {
+                let text = match source {
+                    TextSource::Text => jsoup::text(interpreter, element),
+                    TextSource::Own => jsoup::own_text(interpreter, element),
+                    TextSource::Whole => jsoup::whole_text(interpreter, element),
+                    TextSource::Data => jsoup::data(interpreter, element),
+                };
+                regex.is_match(&text).unwrap_or(false)
+            }
+        }
+    }
+}
+
+/// Whether the parent is an element and not the document, which structural pseudo classes need
+fn has_element_parent(interpreter: &Interpreter, element: ObjectId) -> bool {
+    jsoup::parent_of(interpreter, element).is_some_and(|parent| !jsoup::is_document(interpreter, parent))
+}
+
+/// The element children of the element's parent, the element among them
+fn sibling_elements(interpreter: &Interpreter, element: ObjectId) -> Vec<ObjectId> {
+    match jsoup::parent_of(interpreter, element) {
+        Some(parent) => jsoup::element_children(interpreter, parent),
+        None => vec![element],
+    }
+}
+
+fn same_type_siblings(interpreter: &Interpreter, element: ObjectId) -> Vec<ObjectId> {
+    let tag = jsoup::tag_name(interpreter, element);
+    sibling_elements(interpreter, element)
+        .into_iter()
+        .filter(|sibling| jsoup::tag_name(interpreter, *sibling) == tag)
+        .collect()
+}
+
+/// 1-based position of the element among its siblings for the nth pseudo classes
+fn position(interpreter: &Interpreter, element: ObjectId, backwards: bool, of_type: bool) -> Option<i32> {
+    if !has_element_parent(interpreter, element) {
+        return None;
+    }
+    let mut siblings = if of_type { same_type_siblings(interpreter, element) } else { sibling_elements(interpreter, element) };
+    if backwards {
+        siblings.reverse();
+    }
+    siblings.iter().position(|sibling| *sibling == element).map(|index| index as i32 + 1)
+}
+
+/// `an+b` of an nth pseudo class, `odd` and `even` included
+fn parse_nth(argument: &str) -> Option<(i32, i32)> {
+    let argument: String = argument.chars().filter(|c| !c.is_whitespace()).collect::<String>().to_lowercase();
+    match argument.as_str() {
+        "odd" => return Some((2, 1)),
+        "even" => return Some((2, 0)),
+        _ => {}
+    }
+    let Some((a, b)) = argument.split_once('n') else {
+        return argument.parse().ok().map(|b| (0, b));
+    };
+    let a = match a {
+        "" | "+" => 1,
+        "-" => -1,
+        a => a.parse().ok()?,
+    };
+    let b = match b {
+        "" => 0,
+        b if b.starts_with(['+', '-']) => b.parse().ok()?,
+        _ => return None,
+    };
+    Some((a, b))
+}
+
+/// Reads a query char by char like Jsoup's TokenQueue
+struct QueryParser<'a> {
+    query: &'a str,
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn new(query: &'a str) -> Self {
+        QueryParser { query, chars: query.trim().chars().collect(), position: 0 }
+    }
+
+    fn parse(mut self) -> Result<Selector, String> {
+        let mut alternatives = Vec::new();
+        loop {
+            alternatives.push(self.parse_complex()?);
+            self.skip_whitespace();
+            if self.position == self.chars.len() {
+                break;
+            }
+            if !self.consume(',') {
+                return Err(self.unexpected());
+            }
+        }
+        Ok(Selector { alternatives })
+    }
+
+    fn parse_complex(&mut self) -> Result<Complex, String> {
+        let mut parts = Vec::new();
+        self.skip_whitespace();
+        let mut combinator = self.combinator();
+        if combinator.is_some() {
+            parts.push((Combinator::Descendant, vec![Simple::Scope]));
+        }
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            if !parts.is_empty() && combinator.is_none() {
+                match self.peek() {
+                    None | Some(',') => break,
+                    _ => {}
+                }
+                combinator = self.combinator();
+                if combinator.is_none() && had_whitespace {
+                    combinator = Some(Combinator::Descendant);
+                }
+                if combinator.is_none() {
+                    return Err(self.unexpected());
+                }
+                self.skip_whitespace();
+            }
+            let compound = self.parse_compound()?;
+            parts.push((combinator.take().unwrap_or(Combinator::Descendant), compound));
+        }
+        Ok(Complex { parts })
+    }
+
+    fn combinator(&mut self) -> Option<Combinator> {
+        let combinator = match self.peek()? {
+            '>' => Combinator::Child,
+            '+' => Combinator::NextSibling,
+            '~' => Combinator::SubsequentSibling,
+            _ => return None,
+        };
+        self.position += 1;
+        Some(combinator)
+    }
+
+    fn parse_compound(&mut self) -> Result<Vec<Simple>, String> {
+        let start = self.position;
+        let mut simple = Vec::new();
+        if self.consume('*') {
+            // Any element, which is what an empty compound matches
+        } else if self.peek().is_some_and(is_name_char) {
+            let name = self.identifier().replace('|', ":");
+            simple.push(Simple::Tag(name.to_lowercase()));
+        }
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.position += 1;
+                    simple.push(Simple::Id(self.required_identifier()?));
+                }
+                Some('.') => {
+                    self.position += 1;
+                    simple.push(Simple::Class(self.required_identifier()?.to_lowercase()));
+                }
+                Some('[') => {
+                    let content = self.balanced('[', ']')?;
+                    simple.push(self.attribute(&content)?);
+                }
+                Some(':') => {
+                    self.position += 1;
+                    simple.push(self.pseudo()?);
+                }
+                _ => break,
+            }
+        }
+        if self.position == start {
+            return Err(self.unexpected());
+        }
+        Ok(simple)
+    }
+
+    fn attribute(&self, content: &str) -> Result<Simple, String> {
+        const OPERATORS: [&str; 6] = ["!=", "^=", "$=", "*=", "~=", "="];
+        let operator = content.find(['=', '!', '^', '$', '*', '~'])
+            .filter(|index| *index > 0 || !content.starts_with('^'))
+            .and_then(|index| OPERATORS.iter().find(|operator| content[index..].starts_with(**operator)).map(|operator| (index, *operator)));
+        let Some((index, operator)) = operator else {
+            let key = content.trim().to_lowercase();
+            if key.is_empty() {
+                return Err(self.unexpected());
+            }
+            return Ok(match key.strip_prefix('^') {
+                Some(prefix) => Simple::AttributePrefix(prefix.to_string()),
+                None => Simple::Attribute(key, AttributeTest::Exists),
+            });
+        };
+        let key = content[..index].trim().to_lowercase();
+        let raw_value = content[index + operator.len()..].trim();
+        let value = unquote(raw_value).trim().to_lowercase();
+        if key.is_empty() {
+            return Err(self.unexpected());
+        }
+        let test = match operator {
+            // The regex is taken as written, quotes included
+            "~=" => AttributeTest::Matches(regex::compile(raw_value)?),
+            "=" => AttributeTest::Equals(value),
+            "!=" => AttributeTest::NotEquals(value),
+            "^=" => AttributeTest::Prefix(value),
+            "$=" => AttributeTest::Suffix(value),
+            _ => AttributeTest::Contains(value),
+        };
+        Ok(Simple::Attribute(key, test))
+    }
+
+    fn pseudo(&mut self) -> Result<Simple, String> {
+        let name = self.required_identifier()?.to_lowercase();
+        let argument = if self.peek() == Some('(') { Some(self.balanced('(', ')')?) } else { None };
+        let nth = |backwards, of_type| match argument.as_deref().map(parse_nth) {
+            Some(Some((a, b))) => Ok(Simple::Nth { a, b, backwards, of_type }),
+            _ => Err(format!("Could not parse nth-index '{}': unexpected format", argument.as_deref().unwrap_or(""))),
+        };
+        match (name.as_str(), &argument) {
+            ("first-child", None) => Ok(Simple::Nth { a: 0, b: 1, backwards: false, of_type: false }),
+            ("last-child", None) => Ok(Simple::Nth { a: 0, b: 1, backwards: true, of_type: false }),
+            ("first-of-type", None) => Ok(Simple::Nth { a: 0, b: 1, backwards: false, of_type: true }),
+            ("last-of-type", None) => Ok(Simple::Nth { a: 0, b: 1, backwards: true, of_type: true }),
+            ("only-child", None) => Ok(Simple::OnlyChild),
+            ("only-of-type", None) => Ok(Simple::OnlyOfType),
+            ("empty", None) => Ok(Simple::Empty),
+            ("root", None) => Ok(Simple::Root),
+            ("nth-child", Some(_)) => nth(false, false),
+            ("nth-last-child", Some(_)) => nth(true, false),
+            ("nth-of-type", Some(_)) => nth(false, true),
+            ("nth-last-of-type", Some(_)) => nth(true, true),
+            ("not", Some(query)) => Ok(Simple::Not(QueryParser::new(query).parse()?)),
+            ("is", Some(query)) => Ok(Simple::Is(QueryParser::new(query).parse()?)),
+            ("has", Some(query)) => Ok(Simple::Has(QueryParser::new(query).parse()?)),
+            ("eq", Some(index)) => Ok(Simple::Index(Ordering::Equal, parse_index(index)?)),
+            ("lt", Some(index)) => Ok(Simple::Index(Ordering::Less, parse_index(index)?)),
+            ("gt", Some(index)) => Ok(Simple::Index(Ordering::Greater, parse_index(index)?)),
+            ("contains", Some(search)) => {
+                let search = jsoup::normalize_whitespace(&unescape(search)).to_lowercase();
+                Ok(Simple::Contains(TextSource::Text, required_argument(search, &name)?))
+            }
+            ("containsown", Some(search)) => {
+                let search = jsoup::normalize_whitespace(&unescape(search)).to_lowercase();
+                Ok(Simple::Contains(TextSource::Own, required_argument(search, &name)?))
+            }
+            ("containswholetext", Some(search)) => {
+                Ok(Simple::Contains(TextSource::Whole, required_argument(unescape(search), &name)?))
+            }
+            ("containsdata", Some(search)) => {
+                let search = unescape(search).to_lowercase();
+                Ok(Simple::Contains(TextSource::Data, required_argument(search, &name)?))
+            }
+            ("matches", Some(pattern)) => Ok(Simple::Matches(TextSource::Text, regex::compile(pattern)?)),
+            ("matchesown", Some(pattern)) => Ok(Simple::Matches(TextSource::Own, regex::compile(pattern)?)),
+            ("matcheswholetext", Some(pattern)) => Ok(Simple::Matches(TextSource::Whole, regex::compile(pattern)?)),
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn consume(&mut self, c: char) -> bool {
+        let matched = self.peek() == Some(c);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn skip_whitespace(&mut self) -> bool {
+        let start = self.position;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+        self.position > start
+    }
+
+    /// A name, with backslash escapes taken literally
+    fn identifier(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\\' && self.position + 1 < self.chars.len() {
+                name.push(self.chars[self.position + 1]);
+                self.position += 2;
+            } else if is_name_char(c) {
+                name.push(c);
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+        name
+    }
+
+    fn required_identifier(&mut self) -> Result<String, String> {
+        let name = self.identifier();
+        if name.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(name)
+    }
+
+    /// What's between `open` at the cursor and the `close` balancing it, quoted text skipped
+    fn balanced(&mut self, open: char, close: char) -> Result<String, String> {
+        let start = self.position;
+        let mut depth = 0;
+        let mut quote = None;
+        while let Some(c) = self.peek() {
+            self.position += 1;
+            match (c, quote) {
+                ('\\', _) => self.position += 1,
+                (_, Some(q)) if c == q => quote = None,
+                (_, Some(_)) => {}
+                ('"' | '\'', None) => quote = Some(c),
+                _ if c == open => depth += 1,
+                _ if c == close => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(self.chars[start + 1..self.position - 1].iter().collect());
+                    }
+                }
+                _ => {}
+            }
+        }
+        self.position = start;
+        Err(format!("Did not find balanced marker at '{}'", self.remainder()))
+    }
+
+    fn remainder(&self) -> String {
+        self.chars[self.position.min(self.chars.len())..].iter().collect()
+    }
+
+    fn unexpected(&self) -> String {
+        format!("Could not parse query '{}': unexpected token at '{}'", self.query, self.remainder())
+    }
+}
+
+/// The number of `:eq`, `:lt` or `:gt`
+fn parse_index(argument: &str) -> Result<i32, String> {
+    let argument = argument.trim();
+    if argument.is_empty() || !argument.chars().all(|c| c.is_ascii_digit()) {
+        return Err("Index must be numeric".to_string());
+    }
+    argument.parse().map_err(|_| "Index must be numeric".to_string())
+}
+
+/// The search text of a `:contains`, which may not be empty
+fn required_argument(search: String, pseudo_class: &str) -> Result<String, String> {
+    if search.is_empty() {
+        return Err(format!(":{}(text) query must not be empty", pseudo_class));
+    }
+    Ok(search)
+}
+
+/// `text` with backslash escapes replaced by the char they escape
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_' || c == '|' || c == '\\'
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|rest| rest.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::endian::Endian;
+    use crate::parser::parser::Parser;
+    use crate::selftest::{build_micro_dex, self_test_cases};
+
+    /// A manga listing and chapter list the way sources serve them, trimmed down
+    const SELECTOR_FIXTURE: &str = r#"<!DOCTYPE html>
+<html><head><title>Latest  Updates</title>
+<script>var chapterImages = ["p1.jpg", "p2.jpg"];</script></head>
+<body><div id="content" class="wrap">
+<ul class="manga-list">
+<li class="item"><a href="/manga/one" title="One">One Piece</a><span class="status">Ongoing</span></li>
+<li class="item hot"><a href="/manga/two" title="Two">Two Moons</a><span class="status">Completed</span></li>
+<li class="item"><a href="/manga/three" data-src="//cdn.example.com/3.webp">Three Kingdoms</a></li>
+</ul>
+<div class="chapters"><p>Chapter 12 <em>new</em></p><p>Chapter 11</p><p>Extra: Chapter 10.5</p></div>
+</div></body></html>"#;
+
+    /// Queries against the fixture, with the text of every element they select joined by '|'
+    const SELECTOR_CASES: &[(&str, &str)] = &[
+        ("li.item > a", "One Piece|Two Moons|Three Kingdoms"),
+        ("li:has(span:contains(completed)) a", "Two Moons"),
+        ("li:not(:has(.status)) a", "Three Kingdoms"),
+        ("li:eq(1) a", "Two Moons"),
+        ("li:gt(0) > a", "Two Moons|Three Kingdoms"),
+        ("li:lt(1) > a", "One Piece"),
+        ("a[href~=/manga/t\\w+]", "Two Moons|Three Kingdoms"),
+        ("a[data-src~=\\.webp$]", "Three Kingdoms"),
+        (".chapters p:containsOwn(chapter 12)", "Chapter 12 new"),
+        (".chapters p:matches(^Chapter \\d+$)", "Chapter 11"),
+        (".chapters p:matchesOwn(\\d+\\.\\d)", "Extra: Chapter 10.5"),
+        ("script:containsData(chapterimages)", ""),
+        ("ul:has(> li.hot) > li:is(.hot, :last-child) span", "Completed"),
+    ];
+
+    #[test]
+    fn selects_like_jsoup() {
+        let mut parser = Parser::new(build_micro_dex(&self_test_cases(), Endian::Little), false);
+        parser.persist = false;
+        parser.parse().unwrap();
+        let mut interpreter = Interpreter::new(parser);
+        let document = jsoup::parse(&mut interpreter, SELECTOR_FIXTURE, "https://example.com/latest");
+
+        for (query, expected) in SELECTOR_CASES {
+            let selector = Selector::parse(query).unwrap_or_else(|error| panic!("{} ({})", query, error));
+            let texts = selector.select(&interpreter, document)
+                .into_iter()
+                .map(|element| jsoup::text(&interpreter, element))
+                .collect::<Vec<_>>()
+                .join("|");
+            assert_eq!(texts, *expected, "{}", query);
+        }
+    }
+}
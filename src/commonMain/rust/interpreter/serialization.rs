@@ -0,0 +1,867 @@
+// kotlinx.serialization's Json. Extensions link against the library without bundling it, but the
+// serializers its compiler plugin generates for their models are in the dex and run as they
+// are: a generated `deserialize()` walks the descriptor its `<clinit>` built and asks the decoder
+// for one element at a time, and the decoder answers from the text the org.json shim parsed up
+// front. The library's serializers for primitives, enums, nullables, lists and maps are native.
+// Polymorphic classes and JsonElement aren't supported yet.
+
+use std::collections::HashMap;
+
+use crate::interpreter::boxing::{box_value, unboxed, BOOLEAN, CHARACTER, DOUBLE, INTEGER, LONG};
+use crate::interpreter::collections::{elements_of, map_entries, new_list, new_map, new_set};
+use crate::interpreter::enums;
+use crate::interpreter::exception::{
+    JSON_DECODING_EXCEPTION, MESSAGE_FIELD, MISSING_FIELD_EXCEPTION, SERIALIZATION_EXCEPTION, UNKNOWN_FIELD_EXCEPTION,
+};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::json::{self, JSON_ARRAY, JSON_OBJECT};
+use crate::interpreter::native::{binary_name, PlatformMethod};
+use crate::interpreter::strings::double_to_string;
+use crate::types::{DexValue, ObjectId};
+
+pub const JSON: &str = "Lkotlinx/serialization/json/Json;";
+pub const JSON_DEFAULT: &str = "Lkotlinx/serialization/json/Json$Default;";
+pub const JSON_KT: &str = "Lkotlinx/serialization/json/JsonKt;";
+pub const JSON_BUILDER: &str = "Lkotlinx/serialization/json/JsonBuilder;";
+pub const SERIALIZERS_MODULE: &str = "Lkotlinx/serialization/modules/SerialModuleImpl;";
+pub const DECODER: &str = "Lkotlinx/serialization/json/internal/StreamingJsonDecoder;";
+pub const PLUGIN_DESCRIPTOR: &str = "Lkotlinx/serialization/internal/PluginGeneratedSerialDescriptor;";
+pub const PLUGIN_EXCEPTIONS_KT: &str = "Lkotlinx/serialization/internal/PluginExceptionsKt;";
+pub const BUILTIN_SERIALIZERS_KT: &str = "Lkotlinx/serialization/builtins/BuiltinSerializersKt;";
+pub const ENUMS_KT: &str = "Lkotlinx/serialization/internal/EnumsKt;";
+
+pub const STRING_SERIALIZER: &str = "Lkotlinx/serialization/internal/StringSerializer;";
+pub const INT_SERIALIZER: &str = "Lkotlinx/serialization/internal/IntSerializer;";
+pub const LONG_SERIALIZER: &str = "Lkotlinx/serialization/internal/LongSerializer;";
+pub const SHORT_SERIALIZER: &str = "Lkotlinx/serialization/internal/ShortSerializer;";
+pub const BYTE_SERIALIZER: &str = "Lkotlinx/serialization/internal/ByteSerializer;";
+pub const DOUBLE_SERIALIZER: &str = "Lkotlinx/serialization/internal/DoubleSerializer;";
+pub const FLOAT_SERIALIZER: &str = "Lkotlinx/serialization/internal/FloatSerializer;";
+pub const BOOLEAN_SERIALIZER: &str = "Lkotlinx/serialization/internal/BooleanSerializer;";
+pub const CHAR_SERIALIZER: &str = "Lkotlinx/serialization/internal/CharSerializer;";
+pub const NULLABLE_SERIALIZER: &str = "Lkotlinx/serialization/internal/NullableSerializer;";
+pub const ENUM_SERIALIZER: &str = "Lkotlinx/serialization/internal/EnumSerializer;";
+pub const ARRAY_LIST_SERIALIZER: &str = "Lkotlinx/serialization/internal/ArrayListSerializer;";
+pub const LINKED_HASH_SET_SERIALIZER: &str = "Lkotlinx/serialization/internal/LinkedHashSetSerializer;";
+pub const HASH_SET_SERIALIZER: &str = "Lkotlinx/serialization/internal/HashSetSerializer;";
+pub const LINKED_HASH_MAP_SERIALIZER: &str = "Lkotlinx/serialization/internal/LinkedHashMapSerializer;";
+pub const HASH_MAP_SERIALIZER: &str = "Lkotlinx/serialization/internal/HashMapSerializer;";
+
+const DESERIALIZE: &str = "deserialize:(Lkotlinx/serialization/encoding/Decoder;)Ljava/lang/Object;";
+const CHILD_SERIALIZERS: &str = "childSerializers:()[Lkotlinx/serialization/KSerializer;";
+
+// Options of a Json object and its builder
+const IGNORE_UNKNOWN_KEYS_FIELD: &str = "ignoreUnknownKeys";
+const IS_LENIENT_FIELD: &str = "isLenient";
+const COERCE_INPUT_VALUES_FIELD: &str = "coerceInputValues";
+const EXPLICIT_NULLS_FIELD: &str = "explicitNulls";
+
+// Fields of a descriptor
+const SERIAL_NAME_FIELD: &str = "serialName";
+const GENERATED_SERIALIZER_FIELD: &str = "generatedSerializer";
+const NAMES_FIELD: &str = "names";
+const OPTIONAL_FIELD: &str = "optional";
+/// Whether each element's serializer is a NullableSerializer, asked for the first time it matters
+const NULLABLE_FIELD: &str = "nullable";
+
+// Fields of a serializer taking other serializers
+const SERIALIZER_FIELD: &str = "serializer";
+const KEY_SERIALIZER_FIELD: &str = "keySerializer";
+
+// Fields of an enum's serializer, its serial name is kept like a descriptor's
+const VALUES_FIELD: &str = "values";
+/// The @SerialName of each constant, null for the ones without
+const SERIAL_NAMES_FIELD: &str = "serialNames";
+
+// Fields of a decoder
+const JSON_FIELD: &str = "json";
+const VALUE_FIELD: &str = "value";
+const PATH_FIELD: &str = "path";
+/// Index of the next entry of the object a structure decoder reads
+const POSITION_FIELD: &str = "position";
+/// Elements a structure decoder returned an index for
+const SEEN_FIELD: &str = "seen";
+const ELEMENT_FIELD: &str = "element";
+const ELEMENT_PATH_FIELD: &str = "elementPath";
+
+/// CompositeDecoder.DECODE_DONE
+const DECODE_DONE: i32 = -1;
+
+/// The primitives the library's serializers and the decoder's `decodeX()` methods read
+#[derive(Clone, Copy, PartialEq)]
+enum Primitive {
+    String,
+    Int,
+    Long,
+    Short,
+    Byte,
+    Double,
+    Float,
+    Boolean,
+    Char,
+}
+
+impl Primitive {
+    fn of_serializer(class_name: &str) -> Option<Primitive> {
+        let primitive = match class_name {
+            STRING_SERIALIZER => Primitive::String,
+            INT_SERIALIZER => Primitive::Int,
+            LONG_SERIALIZER => Primitive::Long,
+            SHORT_SERIALIZER => Primitive::Short,
+            BYTE_SERIALIZER => Primitive::Byte,
+            DOUBLE_SERIALIZER => Primitive::Double,
+            FLOAT_SERIALIZER => Primitive::Float,
+            BOOLEAN_SERIALIZER => Primitive::Boolean,
+            CHAR_SERIALIZER => Primitive::Char,
+            _ => return None,
+        };
+        Some(primitive)
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Primitive::String => "string",
+            Primitive::Int => "int",
+            Primitive::Long => "long",
+            Primitive::Short => "short",
+            Primitive::Byte => "byte",
+            Primitive::Double => "double",
+            Primitive::Float => "float",
+            Primitive::Boolean => "boolean",
+            Primitive::Char => "char",
+        }
+    }
+}
+
+/// The options of a Json object that change how input is read
+#[derive(Clone, Copy)]
+struct Config {
+    ignore_unknown_keys: bool,
+    is_lenient: bool,
+    coerce_input_values: bool,
+    explicit_nulls: bool,
+}
+
+impl Config {
+    const DEFAULT: Config = Config { ignore_unknown_keys: false, is_lenient: false, coerce_input_values: false, explicit_nulls: true };
+
+    fn of(interpreter: &Interpreter, json: &DexValue) -> Config {
+        let option = |name: &str, default: bool| match field(interpreter, json, name) {
+            DexValue::Boolean(value) => value,
+            _ => default,
+        };
+        Config {
+            ignore_unknown_keys: option(IGNORE_UNKNOWN_KEYS_FIELD, false),
+            is_lenient: option(IS_LENIENT_FIELD, false),
+            coerce_input_values: option(COERCE_INPUT_VALUES_FIELD, false),
+            explicit_nulls: option(EXPLICIT_NULLS_FIELD, true),
+        }
+    }
+
+    fn store(self, interpreter: &mut Interpreter, id: ObjectId) {
+        let fields = &mut interpreter.heap.get_mut(&id).unwrap().fields;
+        fields.insert(IGNORE_UNKNOWN_KEYS_FIELD.to_string(), DexValue::Boolean(self.ignore_unknown_keys));
+        fields.insert(IS_LENIENT_FIELD.to_string(), DexValue::Boolean(self.is_lenient));
+        fields.insert(COERCE_INPUT_VALUES_FIELD.to_string(), DexValue::Boolean(self.coerce_input_values));
+        fields.insert(EXPLICIT_NULLS_FIELD.to_string(), DexValue::Boolean(self.explicit_nulls));
+    }
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (JSON | JSON_DEFAULT, "decodeFromString:(Lkotlinx/serialization/DeserializationStrategy;Ljava/lang/String;)Ljava/lang/Object;") => decode_from_string,
+        (JSON | JSON_DEFAULT, "getSerializersModule:()Lkotlinx/serialization/modules/SerializersModule;") => serializers_module,
+        (JSON_KT, "Json:(Lkotlinx/serialization/json/Json;Lkotlin/jvm/functions/Function1;)Lkotlinx/serialization/json/Json;") => json_with_builder,
+        (JSON_KT, "Json$default:(Lkotlinx/serialization/json/Json;Lkotlin/jvm/functions/Function1;ILjava/lang/Object;)Lkotlinx/serialization/json/Json;") => json_with_builder,
+        (JSON_BUILDER, "setIgnoreUnknownKeys:(Z)V") => |interpreter, this, args| set_option(interpreter, this, &args, IGNORE_UNKNOWN_KEYS_FIELD),
+        (JSON_BUILDER, "setLenient:(Z)V") => |interpreter, this, args| set_option(interpreter, this, &args, IS_LENIENT_FIELD),
+        (JSON_BUILDER, "setCoerceInputValues:(Z)V") => |interpreter, this, args| set_option(interpreter, this, &args, COERCE_INPUT_VALUES_FIELD),
+        (JSON_BUILDER, "setExplicitNulls:(Z)V") => |interpreter, this, args| set_option(interpreter, this, &args, EXPLICIT_NULLS_FIELD),
+        // Options for encoding or for input the decoder doesn't read anyway
+        (JSON_BUILDER, "setEncodeDefaults:(Z)V"
+            | "setPrettyPrint:(Z)V"
+            | "setPrettyPrintIndent:(Ljava/lang/String;)V"
+            | "setAllowSpecialFloatingPointValues:(Z)V"
+            | "setAllowStructuredMapKeys:(Z)V"
+            | "setUseAlternativeNames:(Z)V"
+            | "setUseArrayPolymorphism:(Z)V"
+            | "setClassDiscriminator:(Ljava/lang/String;)V"
+            | "setSerializersModule:(Lkotlinx/serialization/modules/SerializersModule;)V") => |_, _, _| DexValue::Void,
+
+        (PLUGIN_DESCRIPTOR, "<init>:(Ljava/lang/String;Lkotlinx/serialization/internal/GeneratedSerializer;I)V") => descriptor_init,
+        (PLUGIN_DESCRIPTOR, "addElement:(Ljava/lang/String;Z)V") => descriptor_add_element,
+        (PLUGIN_DESCRIPTOR, "pushAnnotation:(Ljava/lang/annotation/Annotation;)V"
+            | "pushClassAnnotation:(Ljava/lang/annotation/Annotation;)V") => |_, _, _| DexValue::Void,
+        (PLUGIN_DESCRIPTOR, "getSerialName:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, &this_value(this), SERIAL_NAME_FIELD),
+        (PLUGIN_DESCRIPTOR, "getElementsCount:()I") => |interpreter, this, _| DexValue::Int(element_names(interpreter, &this_value(this)).len() as i32),
+        (PLUGIN_DESCRIPTOR, "getElementName:(I)Ljava/lang/String;") => descriptor_element_name,
+        (PLUGIN_DESCRIPTOR, "getElementIndex:(Ljava/lang/String;)I") => descriptor_element_index,
+        (PLUGIN_DESCRIPTOR, "isElementOptional:(I)Z") => descriptor_is_element_optional,
+
+        (PLUGIN_EXCEPTIONS_KT, "throwMissingFieldException:(IILkotlinx/serialization/descriptors/SerialDescriptor;)V") => throw_missing_field,
+        (PLUGIN_EXCEPTIONS_KT, "throwArrayMissingFieldException:([I[ILkotlinx/serialization/descriptors/SerialDescriptor;)V") => throw_array_missing_field,
+        (UNKNOWN_FIELD_EXCEPTION, "<init>:(I)V") => unknown_field_init,
+
+        (BUILTIN_SERIALIZERS_KT, "getNullable:(Lkotlinx/serialization/KSerializer;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, args| {
+            wrapping_serializer(interpreter, NULLABLE_SERIALIZER, &args)
+        },
+        (BUILTIN_SERIALIZERS_KT, "ListSerializer:(Lkotlinx/serialization/KSerializer;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, args| {
+            wrapping_serializer(interpreter, ARRAY_LIST_SERIALIZER, &args)
+        },
+        (BUILTIN_SERIALIZERS_KT, "SetSerializer:(Lkotlinx/serialization/KSerializer;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, args| {
+            wrapping_serializer(interpreter, LINKED_HASH_SET_SERIALIZER, &args)
+        },
+        (BUILTIN_SERIALIZERS_KT, "MapSerializer:(Lkotlinx/serialization/KSerializer;Lkotlinx/serialization/KSerializer;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, args| {
+            wrapping_serializer(interpreter, LINKED_HASH_MAP_SERIALIZER, &args)
+        },
+        // String.serializer() and friends, the companion they extend doesn't matter
+        (BUILTIN_SERIALIZERS_KT, "serializer:(Lkotlin/jvm/internal/StringCompanionObject;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, _| instance(interpreter, STRING_SERIALIZER),
+        (BUILTIN_SERIALIZERS_KT, "serializer:(Lkotlin/jvm/internal/IntCompanionObject;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, _| instance(interpreter, INT_SERIALIZER),
+        (BUILTIN_SERIALIZERS_KT, "serializer:(Lkotlin/jvm/internal/LongCompanionObject;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, _| instance(interpreter, LONG_SERIALIZER),
+        (BUILTIN_SERIALIZERS_KT, "serializer:(Lkotlin/jvm/internal/DoubleCompanionObject;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, _| instance(interpreter, DOUBLE_SERIALIZER),
+        (BUILTIN_SERIALIZERS_KT, "serializer:(Lkotlin/jvm/internal/FloatCompanionObject;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, _| instance(interpreter, FLOAT_SERIALIZER),
+        (BUILTIN_SERIALIZERS_KT, "serializer:(Lkotlin/jvm/internal/BooleanCompanionObject;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, _| instance(interpreter, BOOLEAN_SERIALIZER),
+        (ARRAY_LIST_SERIALIZER | LINKED_HASH_SET_SERIALIZER | HASH_SET_SERIALIZER | NULLABLE_SERIALIZER,
+            "<init>:(Lkotlinx/serialization/KSerializer;)V") => serializer_init,
+        (LINKED_HASH_MAP_SERIALIZER | HASH_MAP_SERIALIZER,
+            "<init>:(Lkotlinx/serialization/KSerializer;Lkotlinx/serialization/KSerializer;)V") => serializer_init,
+        // What the plugin generates for enums, the annotations don't change how they're read
+        (ENUM_SERIALIZER, "<init>:(Ljava/lang/String;[Ljava/lang/Enum;)V") => enum_serializer_init,
+        (ENUMS_KT, "createSimpleEnumSerializer:(Ljava/lang/String;[Ljava/lang/Enum;)Lkotlinx/serialization/KSerializer;"
+            | "createMarkedEnumSerializer:(Ljava/lang/String;[Ljava/lang/Enum;[Ljava/lang/String;[[Ljava/lang/annotation/Annotation;)Lkotlinx/serialization/KSerializer;"
+            | "createAnnotatedEnumSerializer:(Ljava/lang/String;[Ljava/lang/Enum;[Ljava/lang/String;[[Ljava/lang/annotation/Annotation;[Ljava/lang/annotation/Annotation;)Lkotlinx/serialization/KSerializer;") => |interpreter, _, args| {
+            let id = interpreter.alloc_object(ENUM_SERIALIZER);
+            enum_serializer_init(interpreter, Some(id), args);
+            DexValue::Object(id)
+        },
+
+        (DECODER, "beginStructure:(Lkotlinx/serialization/descriptors/SerialDescriptor;)Lkotlinx/serialization/encoding/CompositeDecoder;") => begin_structure,
+        (DECODER, "endStructure:(Lkotlinx/serialization/descriptors/SerialDescriptor;)V") => |_, _, _| DexValue::Void,
+        (DECODER, "decodeSequentially:()Z") => |_, _, _| DexValue::Boolean(false),
+        (DECODER, "decodeCollectionSize:(Lkotlinx/serialization/descriptors/SerialDescriptor;)I") => |_, _, _| DexValue::Int(-1),
+        (DECODER, "decodeElementIndex:(Lkotlinx/serialization/descriptors/SerialDescriptor;)I") => decode_element_index,
+        (DECODER, "decodeStringElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)Ljava/lang/String;") => |interpreter, this, _| decode_element(interpreter, this, Primitive::String),
+        (DECODER, "decodeIntElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)I") => |interpreter, this, _| decode_element(interpreter, this, Primitive::Int),
+        (DECODER, "decodeLongElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)J") => |interpreter, this, _| decode_element(interpreter, this, Primitive::Long),
+        (DECODER, "decodeShortElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)S") => |interpreter, this, _| decode_element(interpreter, this, Primitive::Short),
+        (DECODER, "decodeByteElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)B") => |interpreter, this, _| decode_element(interpreter, this, Primitive::Byte),
+        (DECODER, "decodeDoubleElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)D") => |interpreter, this, _| decode_element(interpreter, this, Primitive::Double),
+        (DECODER, "decodeFloatElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)F") => |interpreter, this, _| decode_element(interpreter, this, Primitive::Float),
+        (DECODER, "decodeBooleanElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)Z") => |interpreter, this, _| decode_element(interpreter, this, Primitive::Boolean),
+        (DECODER, "decodeCharElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)C") => |interpreter, this, _| decode_element(interpreter, this, Primitive::Char),
+        (DECODER, "decodeSerializableElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;ILkotlinx/serialization/DeserializationStrategy;Ljava/lang/Object;)Ljava/lang/Object;") => decode_serializable_element,
+        (DECODER, "decodeNullableSerializableElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;ILkotlinx/serialization/DeserializationStrategy;Ljava/lang/Object;)Ljava/lang/Object;") => decode_nullable_serializable_element,
+        (DECODER, "decodeInlineElement:(Lkotlinx/serialization/descriptors/SerialDescriptor;I)Lkotlinx/serialization/encoding/Decoder;") => decode_inline_element,
+        (DECODER, "decodeString:()Ljava/lang/String;") => |interpreter, this, _| decode_value(interpreter, this, Primitive::String),
+        (DECODER, "decodeInt:()I") => |interpreter, this, _| decode_value(interpreter, this, Primitive::Int),
+        (DECODER, "decodeLong:()J") => |interpreter, this, _| decode_value(interpreter, this, Primitive::Long),
+        (DECODER, "decodeShort:()S") => |interpreter, this, _| decode_value(interpreter, this, Primitive::Short),
+        (DECODER, "decodeByte:()B") => |interpreter, this, _| decode_value(interpreter, this, Primitive::Byte),
+        (DECODER, "decodeDouble:()D") => |interpreter, this, _| decode_value(interpreter, this, Primitive::Double),
+        (DECODER, "decodeFloat:()F") => |interpreter, this, _| decode_value(interpreter, this, Primitive::Float),
+        (DECODER, "decodeBoolean:()Z") => |interpreter, this, _| decode_value(interpreter, this, Primitive::Boolean),
+        (DECODER, "decodeChar:()C") => |interpreter, this, _| decode_value(interpreter, this, Primitive::Char),
+        (DECODER, "decodeNotNullMark:()Z") => |interpreter, this, _| {
+            let value = field(interpreter, &this_value(this), VALUE_FIELD);
+            DexValue::Boolean(!json::is_null_value(interpreter, &value))
+        },
+        (DECODER, "decodeNull:()Ljava/lang/Void;") => |_, _, _| DexValue::Null,
+        (DECODER, "decodeInline:(Lkotlinx/serialization/descriptors/SerialDescriptor;)Lkotlinx/serialization/encoding/Decoder;") => |_, this, _| this_value(this),
+        (DECODER, "decodeSerializableValue:(Lkotlinx/serialization/DeserializationStrategy;)Ljava/lang/Object;") => decode_serializable_value,
+        (DECODER, "getJson:()Lkotlinx/serialization/json/Json;") => |interpreter, this, _| field(interpreter, &this_value(this), JSON_FIELD),
+        (DECODER, "getSerializersModule:()Lkotlinx/serialization/modules/SerializersModule;") => serializers_module,
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// Whether `class_name` is one of the library's serializer objects, reached through `INSTANCE`
+pub fn is_serializer_object(class_name: &str) -> bool {
+    Primitive::of_serializer(class_name).is_some()
+}
+
+/// Static fields of a serializer object
+pub fn serializer_statics(interpreter: &mut Interpreter, class_name: &str) -> HashMap<String, DexValue> {
+    let instance = interpreter.alloc_object(class_name);
+    HashMap::from([("INSTANCE".to_string(), DexValue::Object(instance))])
+}
+
+/// Json's static fields, `Json.Default` has the default options
+pub fn json_statics(interpreter: &mut Interpreter) -> HashMap<String, DexValue> {
+    let default = interpreter.alloc_object(JSON_DEFAULT);
+    Config::DEFAULT.store(interpreter, default);
+    HashMap::from([("Default".to_string(), DexValue::Object(default))])
+}
+
+fn this_value(this: Option<ObjectId>) -> DexValue {
+    this.map_or(DexValue::Null, DexValue::Object)
+}
+
+fn field(interpreter: &Interpreter, object: &DexValue, name: &str) -> DexValue {
+    match object {
+        DexValue::Object(id) => interpreter.heap.get(id)
+            .and_then(|object| object.fields.get(name).cloned())
+            .unwrap_or(DexValue::Null),
+        _ => DexValue::Null,
+    }
+}
+
+fn set_field(interpreter: &mut Interpreter, object: Option<ObjectId>, name: &str, value: DexValue) {
+    if let Some(object) = object.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(name.to_string(), value);
+    }
+}
+
+fn class_of(interpreter: &Interpreter, value: &DexValue) -> Option<String> {
+    match value {
+        DexValue::Object(id) => interpreter.heap.get(id).map(|object| object.class_name.clone()),
+        _ => None,
+    }
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> i32 {
+    args.get(index).and_then(|value| value.as_int()).unwrap_or(0)
+}
+
+fn throw(interpreter: &mut Interpreter, class_name: &str, message: &str) -> DexValue {
+    interpreter.throw_new(class_name, message);
+    DexValue::Void
+}
+
+/// The `INSTANCE` of a serializer object
+fn instance(interpreter: &mut Interpreter, class_name: &str) -> DexValue {
+    interpreter.initialize_class(class_name);
+    interpreter.statics.get(class_name)
+        .and_then(|statics| statics.get("INSTANCE").cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn serializers_module(interpreter: &mut Interpreter, _this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Object(interpreter.alloc_object(SERIALIZERS_MODULE))
+}
+
+/// `Json(from) { ... }`, the builder starts out with the options of `from`
+fn json_with_builder(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    // Json$default passes a mask whose first bit says `from` was left out
+    let from = match args.first() {
+        Some(DexValue::Object(id)) if !(args.len() == 4 && int_arg(&args, 2) & 1 != 0) => DexValue::Object(*id),
+        _ => {
+            interpreter.initialize_class(JSON);
+            interpreter.statics[JSON].get("Default").cloned().unwrap_or(DexValue::Null)
+        }
+    };
+    let builder = interpreter.alloc_object(JSON_BUILDER);
+    Config::of(interpreter, &from).store(interpreter, builder);
+
+    let action = args.get(1).cloned().unwrap_or(DexValue::Null);
+    interpreter.invoke_function(&action, vec![DexValue::Object(builder)]);
+    if interpreter.pending_exception.is_some() {
+        return DexValue::Void;
+    }
+
+    let json = interpreter.alloc_object(JSON);
+    Config::of(interpreter, &DexValue::Object(builder)).store(interpreter, json);
+    DexValue::Object(json)
+}
+
+fn set_option(interpreter: &mut Interpreter, this: Option<ObjectId>, args: &[DexValue], name: &str) -> DexValue {
+    set_field(interpreter, this, name, DexValue::Boolean(int_arg(args, 0) != 0));
+    DexValue::Void
+}
+
+fn decode_from_string(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let strategy = args.first().cloned().unwrap_or(DexValue::Null);
+    let text = match args.get(1) {
+        Some(DexValue::String(text)) => text.clone(),
+        _ => String::new(),
+    };
+    let value = match json::parse(interpreter, &text) {
+        Ok(value) => value,
+        Err(message) => return throw(interpreter, JSON_DECODING_EXCEPTION, &format!("Unexpected JSON token: {}", message)),
+    };
+    deserialize(interpreter, &this_value(this), &strategy, value, "$").unwrap_or(DexValue::Void)
+}
+
+/// Run `strategy` on the parsed JSON `value` found at `path`, `None` if it threw
+fn deserialize(interpreter: &mut Interpreter, json: &DexValue, strategy: &DexValue, value: DexValue, path: &str) -> Option<DexValue> {
+    let Some(class_name) = class_of(interpreter, strategy) else {
+        throw(interpreter, SERIALIZATION_EXCEPTION, "Serializer is null");
+        return None;
+    };
+
+    if let Some(primitive) = Primitive::of_serializer(&class_name) {
+        let config = Config::of(interpreter, json);
+        let primitive_value = read_primitive(interpreter, config, &value, path, primitive)?;
+        return Some(boxed(interpreter, primitive_value));
+    }
+
+    match class_name.as_str() {
+        ENUM_SERIALIZER => {
+            let config = Config::of(interpreter, json);
+            let DexValue::String(name) = read_primitive(interpreter, config, &value, path, Primitive::String)? else {
+                return None;
+            };
+            let constants = elements_of(interpreter, &field(interpreter, strategy, VALUES_FIELD));
+            let serial_names = elements_of(interpreter, &field(interpreter, strategy, SERIAL_NAMES_FIELD));
+            let constant = constants.into_iter().enumerate().find(|(index, constant)| match serial_names.get(*index) {
+                Some(DexValue::String(serial_name)) => *serial_name == name,
+                _ => matches!(constant, DexValue::Object(id) if enums::name_of(interpreter, Some(*id)) == name),
+            });
+            if constant.is_none() {
+                let serial_name = interpreter.string_value_of(&field(interpreter, strategy, SERIAL_NAME_FIELD));
+                let message = format!("{} does not contain element with name '{}' at path {}", serial_name, name, path);
+                throw(interpreter, SERIALIZATION_EXCEPTION, &message);
+            }
+            constant.map(|(_, constant)| constant)
+        }
+        NULLABLE_SERIALIZER => {
+            if json::is_null_value(interpreter, &value) {
+                return Some(DexValue::Null);
+            }
+            let serializer = field(interpreter, strategy, SERIALIZER_FIELD);
+            deserialize(interpreter, json, &serializer, value, path)
+        }
+        ARRAY_LIST_SERIALIZER | LINKED_HASH_SET_SERIALIZER | HASH_SET_SERIALIZER => {
+            expect_kind(interpreter, &value, JSON_ARRAY, "list", path)?;
+            let serializer = field(interpreter, strategy, SERIALIZER_FIELD);
+            let mut elements = Vec::new();
+            for (index, element) in elements_of(interpreter, &value).into_iter().enumerate() {
+                elements.push(deserialize(interpreter, json, &serializer, element, &format!("{}[{}]", path, index))?);
+            }
+            let collection = if class_name == ARRAY_LIST_SERIALIZER { new_list(interpreter, elements) } else { new_set(interpreter, elements) };
+            Some(DexValue::Object(collection))
+        }
+        LINKED_HASH_MAP_SERIALIZER | HASH_MAP_SERIALIZER => {
+            expect_kind(interpreter, &value, JSON_OBJECT, "map", path)?;
+            let key_serializer = field(interpreter, strategy, KEY_SERIALIZER_FIELD);
+            let serializer = field(interpreter, strategy, SERIALIZER_FIELD);
+            let mut entries = Vec::new();
+            for (key, element) in map_entries(interpreter, &value) {
+                let element_path = format!("{}.{}", path, interpreter.string_value_of(&key));
+                let key = deserialize(interpreter, json, &key_serializer, key, path)?;
+                entries.push((key, deserialize(interpreter, json, &serializer, element, &element_path)?));
+            }
+            Some(DexValue::Object(new_map(interpreter, entries)))
+        }
+        _ => {
+            let DexValue::Object(strategy) = strategy else {
+                return None;
+            };
+            let decoder = new_decoder(interpreter, json.clone(), value, path);
+            let result = interpreter.invoke_virtual(*strategy, DESERIALIZE, vec![decoder]);
+            if interpreter.pending_exception.is_some() {
+                return None;
+            }
+            Some(result.unwrap_or(DexValue::Null))
+        }
+    }
+}
+
+/// How kotlinx.serialization names the kind of a JSON value in its messages
+fn kind_name(interpreter: &Interpreter, value: &DexValue) -> &'static str {
+    if json::is_null_value(interpreter, value) {
+        return "JsonNull";
+    }
+    match class_of(interpreter, value).as_deref() {
+        Some(JSON_OBJECT) => "JsonObject",
+        Some(JSON_ARRAY) => "JsonArray",
+        _ => "JsonLiteral",
+    }
+}
+
+/// Throws unless `value` is a JSON object or array, as `class_name` says
+fn expect_kind(interpreter: &mut Interpreter, value: &DexValue, class_name: &str, serial_name: &str, path: &str) -> Option<()> {
+    if class_of(interpreter, value).as_deref() == Some(class_name) {
+        return Some(());
+    }
+    let expected = if class_name == JSON_OBJECT { "JsonObject" } else { "JsonArray" };
+    let message = format!(
+        "Expected {}, but had {} as the serialized body of {} at element: {}",
+        expected, kind_name(interpreter, value), serial_name, path,
+    );
+    throw(interpreter, JSON_DECODING_EXCEPTION, &message);
+    None
+}
+
+/// A primitive read by a serializer in the box its Kotlin type has
+fn boxed(interpreter: &mut Interpreter, value: DexValue) -> DexValue {
+    let class_name = match value {
+        DexValue::Int(_) => INTEGER,
+        DexValue::Long(_) => LONG,
+        DexValue::Double(_) => DOUBLE,
+        DexValue::Boolean(_) => BOOLEAN,
+        DexValue::Char(_) => CHARACTER,
+        DexValue::Float(_) => "Ljava/lang/Float;",
+        DexValue::Short(_) => "Ljava/lang/Short;",
+        DexValue::Byte(_) => "Ljava/lang/Byte;",
+        value => return value,
+    };
+    DexValue::Object(box_value(interpreter, class_name, value))
+}
+
+/// The JSON `value` read as `primitive`. Numbers may be quoted, other literals are only taken
+/// as strings and quoted booleans only read when the Json is lenient, like the library does.
+fn read_primitive(interpreter: &mut Interpreter, config: Config, value: &DexValue, path: &str, primitive: Primitive) -> Option<DexValue> {
+    if json::is_null_value(interpreter, value) {
+        let message = format!(
+            "Unexpected JSON token: Expected {} but found null at path: {}\nUse 'coerceInputValues = true' in 'Json {{}}' builder to coerce nulls if property has a default value.",
+            primitive.name(), path,
+        );
+        throw(interpreter, JSON_DECODING_EXCEPTION, &message);
+        return None;
+    }
+    let (text, quoted) = match value {
+        DexValue::String(text) => (text.clone(), true),
+        value => match unboxed(&interpreter.heap, value) {
+            Some(DexValue::Boolean(literal)) => (literal.to_string(), false),
+            Some(DexValue::Int(number)) => (number.to_string(), false),
+            Some(DexValue::Long(number)) => (number.to_string(), false),
+            Some(DexValue::Double(number)) => (double_to_string(number), false),
+            _ => {
+                let message = format!(
+                    "Unexpected JSON token: Expected {} but found {} at path: {}",
+                    primitive.name(), kind_name(interpreter, value), path,
+                );
+                throw(interpreter, JSON_DECODING_EXCEPTION, &message);
+                return None;
+            }
+        },
+    };
+
+    let parsed = match primitive {
+        Primitive::String if quoted || config.is_lenient => Some(DexValue::String(text.clone())),
+        Primitive::Char if quoted || config.is_lenient => match text.encode_utf16().collect::<Vec<_>>()[..] {
+            [unit] => Some(DexValue::Char(unit)),
+            _ => None,
+        },
+        Primitive::Boolean if !quoted || config.is_lenient => match text.to_ascii_lowercase().as_str() {
+            "true" => Some(DexValue::Boolean(true)),
+            "false" => Some(DexValue::Boolean(false)),
+            _ => None,
+        },
+        Primitive::Int => text.parse().ok().map(DexValue::Int),
+        Primitive::Long => text.parse().ok().map(DexValue::Long),
+        Primitive::Short => text.parse().ok().map(DexValue::Short),
+        Primitive::Byte => text.parse().ok().map(DexValue::Byte),
+        Primitive::Double => text.parse().ok().filter(|number: &f64| number.is_finite()).map(DexValue::Double),
+        Primitive::Float => text.parse().ok().filter(|number: &f32| number.is_finite()).map(DexValue::Float),
+        _ => None,
+    };
+    if parsed.is_none() {
+        let message = format!("Failed to parse type '{}' for input '{}' at path: {}", primitive.name(), text, path);
+        throw(interpreter, JSON_DECODING_EXCEPTION, &message);
+    }
+    parsed
+}
+
+fn new_decoder(interpreter: &mut Interpreter, json: DexValue, value: DexValue, path: &str) -> DexValue {
+    let id = interpreter.alloc_object(DECODER);
+    let fields = &mut interpreter.heap.get_mut(&id).unwrap().fields;
+    fields.insert(JSON_FIELD.to_string(), json);
+    fields.insert(VALUE_FIELD.to_string(), value);
+    fields.insert(PATH_FIELD.to_string(), DexValue::String(path.to_string()));
+    DexValue::Object(id)
+}
+
+fn path_of(interpreter: &Interpreter, decoder: &DexValue, name: &str) -> String {
+    match field(interpreter, decoder, name) {
+        DexValue::String(path) => path,
+        _ => "$".to_string(),
+    }
+}
+
+fn serializer_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    // A map's serializers come key first, the one for values is stored where a list keeps its
+    // element's
+    let (key, value) = match &args[..] {
+        [key, value, ..] => (Some(key.clone()), value.clone()),
+        [value] => (None, value.clone()),
+        [] => (None, DexValue::Null),
+    };
+    if let Some(key) = key {
+        set_field(interpreter, this, KEY_SERIALIZER_FIELD, key);
+    }
+    set_field(interpreter, this, SERIALIZER_FIELD, value);
+    DexValue::Void
+}
+
+/// The serial name and constants of an enum, then the @SerialName of each constant if it has any
+fn enum_serializer_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let mut args = args.into_iter();
+    set_field(interpreter, this, SERIAL_NAME_FIELD, args.next().unwrap_or(DexValue::Null));
+    set_field(interpreter, this, VALUES_FIELD, args.next().unwrap_or(DexValue::Null));
+    set_field(interpreter, this, SERIAL_NAMES_FIELD, args.next().unwrap_or(DexValue::Null));
+    DexValue::Void
+}
+
+fn wrapping_serializer(interpreter: &mut Interpreter, class_name: &str, args: &[DexValue]) -> DexValue {
+    let id = interpreter.alloc_object(class_name);
+    serializer_init(interpreter, Some(id), args.to_vec());
+    DexValue::Object(id)
+}
+
+fn descriptor_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    set_field(interpreter, this, SERIAL_NAME_FIELD, args.first().cloned().unwrap_or(DexValue::Null));
+    set_field(interpreter, this, GENERATED_SERIALIZER_FIELD, args.get(1).cloned().unwrap_or(DexValue::Null));
+    set_field(interpreter, this, NAMES_FIELD, DexValue::Array(Vec::new()));
+    set_field(interpreter, this, OPTIONAL_FIELD, DexValue::Array(Vec::new()));
+    DexValue::Void
+}
+
+fn descriptor_add_element(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = args.first().cloned().unwrap_or(DexValue::Null);
+    let optional = DexValue::Boolean(int_arg(&args, 1) != 0);
+    let Some(descriptor) = this.and_then(|id| interpreter.heap.get_mut(&id)) else {
+        return DexValue::Void;
+    };
+    for (field, value) in [(NAMES_FIELD, name), (OPTIONAL_FIELD, optional)] {
+        if let Some(DexValue::Array(values)) = descriptor.fields.get_mut(field) {
+            values.push(value);
+        }
+    }
+    DexValue::Void
+}
+
+fn element_names(interpreter: &Interpreter, descriptor: &DexValue) -> Vec<String> {
+    match field(interpreter, descriptor, NAMES_FIELD) {
+        DexValue::Array(names) => names.into_iter()
+            .map(|name| match name {
+                DexValue::String(name) => name,
+                _ => String::new(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The flag `name` of each element of `descriptor`
+fn element_flags(interpreter: &Interpreter, descriptor: &DexValue, name: &str) -> Vec<bool> {
+    match field(interpreter, descriptor, name) {
+        DexValue::Array(flags) => flags.iter().map(|flag| *flag == DexValue::Boolean(true)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Whether each element of `descriptor` has a nullable type, known from the serializers the
+/// generated serializer uses for them
+fn element_nullability(interpreter: &mut Interpreter, descriptor: &DexValue) -> Vec<bool> {
+    if let DexValue::Array(_) = field(interpreter, descriptor, NULLABLE_FIELD) {
+        return element_flags(interpreter, descriptor, NULLABLE_FIELD);
+    }
+    let serializers = match field(interpreter, descriptor, GENERATED_SERIALIZER_FIELD) {
+        DexValue::Object(serializer) => interpreter.invoke_virtual(serializer, CHILD_SERIALIZERS, Vec::new()),
+        _ => None,
+    };
+    let nullable: Vec<DexValue> = serializers.map(|serializers| elements_of(interpreter, &serializers)).unwrap_or_default()
+        .iter()
+        .map(|serializer| DexValue::Boolean(class_of(interpreter, serializer).as_deref() == Some(NULLABLE_SERIALIZER)))
+        .collect();
+    if let DexValue::Object(id) = descriptor {
+        set_field(interpreter, Some(*id), NULLABLE_FIELD, DexValue::Array(nullable));
+    }
+    element_flags(interpreter, descriptor, NULLABLE_FIELD)
+}
+
+fn descriptor_element_name(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let names = element_names(interpreter, &this_value(this));
+    usize::try_from(int_arg(&args, 0)).ok()
+        .and_then(|index| names.get(index).cloned())
+        .map_or(DexValue::Null, DexValue::String)
+}
+
+/// The index of the element named like the argument, CompositeDecoder.UNKNOWN_NAME if none is
+fn descriptor_element_index(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = match args.first() {
+        Some(DexValue::String(name)) => name.clone(),
+        _ => String::new(),
+    };
+    let index = element_names(interpreter, &this_value(this)).iter().position(|element| *element == name);
+    DexValue::Int(index.map_or(-3, |index| index as i32))
+}
+
+fn descriptor_is_element_optional(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let optional = element_flags(interpreter, &this_value(this), OPTIONAL_FIELD);
+    let index = usize::try_from(int_arg(&args, 0)).unwrap_or(usize::MAX);
+    DexValue::Boolean(optional.get(index).copied().unwrap_or(false))
+}
+
+/// `Field 'id' is required for type with serial name 'com.example.Dto', but it was missing`
+fn missing_fields_message(interpreter: &Interpreter, descriptor: &DexValue, missing: Vec<usize>) -> String {
+    let names = element_names(interpreter, descriptor);
+    let missing: Vec<&str> = missing.into_iter().filter_map(|index| names.get(index).map(String::as_str)).collect();
+    let serial_name = match field(interpreter, descriptor, SERIAL_NAME_FIELD) {
+        DexValue::String(name) => name,
+        _ => String::new(),
+    };
+    match &missing[..] {
+        [name] => format!("Field '{}' is required for type with serial name '{}', but it was missing", name, serial_name),
+        names => format!("Fields [{}] are required for type with serial name '{}', but they were missing", names.join(", "), serial_name),
+    }
+}
+
+/// Bits set in `golden` but not in `seen`, counting from `offset`
+fn missing_bits(seen: i32, golden: i32, offset: usize) -> impl Iterator<Item = usize> {
+    let missing = golden & !seen;
+    (0..32).filter(move |bit| missing & (1 << bit) != 0).map(move |bit| offset + bit)
+}
+
+fn throw_missing_field(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let missing = missing_bits(int_arg(&args, 0), int_arg(&args, 1), 0).collect();
+    let message = missing_fields_message(interpreter, args.get(2).unwrap_or(&DexValue::Null), missing);
+    throw(interpreter, MISSING_FIELD_EXCEPTION, &message)
+}
+
+/// The masks of classes with more than 32 properties, one int per 32
+fn throw_array_missing_field(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let seen = elements_of(interpreter, args.first().unwrap_or(&DexValue::Null));
+    let golden = elements_of(interpreter, args.get(1).unwrap_or(&DexValue::Null));
+    let missing = seen.iter().zip(&golden).enumerate()
+        .flat_map(|(chunk, (seen, golden))| missing_bits(seen.as_int().unwrap_or(0), golden.as_int().unwrap_or(0), chunk * 32))
+        .collect();
+    let message = missing_fields_message(interpreter, args.get(2).unwrap_or(&DexValue::Null), missing);
+    throw(interpreter, MISSING_FIELD_EXCEPTION, &message)
+}
+
+fn unknown_field_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let message = format!("An unknown field for index {}", int_arg(&args, 0));
+    set_field(interpreter, this, MESSAGE_FIELD, DexValue::String(message));
+    DexValue::Void
+}
+
+/// A decoder for the elements of the JSON object the receiver holds
+fn begin_structure(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let decoder = this_value(this);
+    let value = field(interpreter, &decoder, VALUE_FIELD);
+    let path = path_of(interpreter, &decoder, PATH_FIELD);
+    let descriptor = args.first().cloned().unwrap_or(DexValue::Null);
+    let serial_name = match field(interpreter, &descriptor, SERIAL_NAME_FIELD) {
+        DexValue::String(name) => name,
+        _ => binary_name(&class_of(interpreter, &descriptor).unwrap_or_default()),
+    };
+    if expect_kind(interpreter, &value, JSON_OBJECT, &serial_name, &path).is_none() {
+        return DexValue::Void;
+    }
+
+    let count = element_names(interpreter, &descriptor).len();
+    let json = field(interpreter, &decoder, JSON_FIELD);
+    let structure = new_decoder(interpreter, json, value, &path);
+    let DexValue::Object(id) = structure else {
+        return DexValue::Void;
+    };
+    set_field(interpreter, Some(id), POSITION_FIELD, DexValue::Int(0));
+    set_field(interpreter, Some(id), SEEN_FIELD, DexValue::Array(vec![DexValue::Boolean(false); count]));
+    structure
+}
+
+/// Step to the next entry whose name is an element of the descriptor and return the element's
+/// index. Unknown names throw unless the Json ignores them. Once the entries run out, nullable
+/// elements without a default that were never seen come out as null when the Json has
+/// `explicitNulls = false`.
+fn decode_element_index(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let decoder = this_value(this);
+    let descriptor = args.first().cloned().unwrap_or(DexValue::Null);
+    let config = Config::of(interpreter, &field(interpreter, &decoder, JSON_FIELD));
+    let path = path_of(interpreter, &decoder, PATH_FIELD);
+    let names = element_names(interpreter, &descriptor);
+    let optional = element_flags(interpreter, &descriptor, OPTIONAL_FIELD);
+    let entries = map_entries(interpreter, &field(interpreter, &decoder, VALUE_FIELD));
+    let mut position = field(interpreter, &decoder, POSITION_FIELD).as_int().unwrap_or(0).max(0) as usize;
+
+    let found = loop {
+        let Some((name, element)) = entries.get(position).cloned() else {
+            break None;
+        };
+        position += 1;
+        let name = interpreter.string_value_of(&name);
+        match names.iter().position(|element_name| *element_name == name) {
+            // A null for an optional property that can't hold it leaves its default
+            Some(index) if config.coerce_input_values
+                && optional.get(index) == Some(&true)
+                && json::is_null_value(interpreter, &element)
+                && !element_nullability(interpreter, &descriptor).get(index).copied().unwrap_or(false) => {}
+            Some(index) => break Some((index, element, format!("{}.{}", path, name))),
+            None if config.ignore_unknown_keys => {}
+            None => {
+                set_field(interpreter, this, POSITION_FIELD, DexValue::Int(position as i32));
+                let message = format!(
+                    "Encountered an unknown key '{}' at path: {}\nUse 'ignoreUnknownKeys = true' in 'Json {{}}' builder to ignore unknown keys.",
+                    name, path,
+                );
+                return throw(interpreter, JSON_DECODING_EXCEPTION, &message);
+            }
+        }
+    };
+    set_field(interpreter, this, POSITION_FIELD, DexValue::Int(position as i32));
+
+    let mut seen = element_flags(interpreter, &decoder, SEEN_FIELD);
+    let found = found.or_else(|| {
+        if config.explicit_nulls {
+            return None;
+        }
+        let nullable = element_nullability(interpreter, &descriptor);
+        (0..names.len())
+            .find(|index| !seen.get(*index).copied().unwrap_or(true) && !optional.get(*index).copied().unwrap_or(false) && nullable.get(*index).copied().unwrap_or(false))
+            .map(|index| (index, DexValue::Null, format!("{}.{}", path, names[index])))
+    });
+    let Some((index, element, element_path)) = found else {
+        return DexValue::Int(DECODE_DONE);
+    };
+
+    if let Some(flag) = seen.get_mut(index) {
+        *flag = true;
+    }
+    set_field(interpreter, this, SEEN_FIELD, DexValue::Array(seen.into_iter().map(DexValue::Boolean).collect()));
+    set_field(interpreter, this, ELEMENT_FIELD, element);
+    set_field(interpreter, this, ELEMENT_PATH_FIELD, DexValue::String(element_path));
+    DexValue::Int(index as i32)
+}
+
+fn decode_element(interpreter: &mut Interpreter, this: Option<ObjectId>, primitive: Primitive) -> DexValue {
+    let decoder = this_value(this);
+    let config = Config::of(interpreter, &field(interpreter, &decoder, JSON_FIELD));
+    let element = field(interpreter, &decoder, ELEMENT_FIELD);
+    let path = path_of(interpreter, &decoder, ELEMENT_PATH_FIELD);
+    read_primitive(interpreter, config, &element, &path, primitive).unwrap_or(DexValue::Void)
+}
+
+fn decode_value(interpreter: &mut Interpreter, this: Option<ObjectId>, primitive: Primitive) -> DexValue {
+    let decoder = this_value(this);
+    let config = Config::of(interpreter, &field(interpreter, &decoder, JSON_FIELD));
+    let value = field(interpreter, &decoder, VALUE_FIELD);
+    let path = path_of(interpreter, &decoder, PATH_FIELD);
+    read_primitive(interpreter, config, &value, &path, primitive).unwrap_or(DexValue::Void)
+}
+
+fn decode_serializable_element(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let decoder = this_value(this);
+    let json = field(interpreter, &decoder, JSON_FIELD);
+    let element = field(interpreter, &decoder, ELEMENT_FIELD);
+    let path = path_of(interpreter, &decoder, ELEMENT_PATH_FIELD);
+    let strategy = args.get(2).cloned().unwrap_or(DexValue::Null);
+    deserialize(interpreter, &json, &strategy, element, &path).unwrap_or(DexValue::Void)
+}
+
+fn decode_nullable_serializable_element(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let element = field(interpreter, &this_value(this), ELEMENT_FIELD);
+    if json::is_null_value(interpreter, &element) {
+        return DexValue::Null;
+    }
+    decode_serializable_element(interpreter, this, args)
+}
+
+fn decode_inline_element(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let decoder = this_value(this);
+    let json = field(interpreter, &decoder, JSON_FIELD);
+    let element = field(interpreter, &decoder, ELEMENT_FIELD);
+    let path = path_of(interpreter, &decoder, ELEMENT_PATH_FIELD);
+    new_decoder(interpreter, json, element, &path)
+}
+
+fn decode_serializable_value(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let decoder = this_value(this);
+    let json = field(interpreter, &decoder, JSON_FIELD);
+    let value = field(interpreter, &decoder, VALUE_FIELD);
+    let path = path_of(interpreter, &decoder, PATH_FIELD);
+    let strategy = args.first().cloned().unwrap_or(DexValue::Null);
+    deserialize(interpreter, &json, &strategy, value, &path).unwrap_or(DexValue::Void)
+}
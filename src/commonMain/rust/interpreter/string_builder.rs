@@ -0,0 +1,155 @@
+// java.lang.StringBuilder and StringBuffer, which Kotlin string templates and `buildString`
+// compile to. The text is kept as a String value in a field of the builder object. StringBuffer
+// only adds locking, which has nothing to guard in the interpreter.
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::PlatformMethod;
+use crate::interpreter::strings::{double_to_string, float_to_string};
+use crate::types::{DexValue, ObjectId};
+
+pub const STRING_BUILDER: &str = "Ljava/lang/StringBuilder;";
+pub const STRING_BUFFER: &str = "Ljava/lang/StringBuffer;";
+
+const VALUE_FIELD: &str = "value";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    if class_name != STRING_BUILDER && class_name != STRING_BUFFER {
+        return None;
+    }
+
+    // Both classes have the same methods, only the return type of the chaining ones differs
+    let (name, descriptor) = method_key.split_once(':')?;
+    let (parameters, _) = descriptor.strip_prefix('(')?.split_once(')')?;
+    let method: PlatformMethod = match (name, parameters) {
+        // The capacity is only a hint
+        ("<init>", "" | "I") => init,
+        ("<init>", "Ljava/lang/String;" | "Ljava/lang/CharSequence;") => init_with_text,
+        ("append", "Z") => append_boolean,
+        ("append", "C") => append_char,
+        ("append", "I") => append_int,
+        ("append", "J") => append_long,
+        ("append", "F") => append_float,
+        ("append", "D") => append_double,
+        ("append", "[C") => append_chars,
+        ("append", "Ljava/lang/String;" | "Ljava/lang/CharSequence;" | "Ljava/lang/Object;" | "Ljava/lang/StringBuffer;") => append_object,
+        ("append", "Ljava/lang/CharSequence;II") => append_range,
+        ("length", "") => length,
+        ("setLength", "I") => set_length,
+        ("toString", "") => to_string,
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn text_of(interpreter: &Interpreter, this: Option<ObjectId>) -> String {
+    match this.and_then(|id| interpreter.heap.get(&id)).and_then(|object| object.fields.get(VALUE_FIELD)) {
+        Some(DexValue::String(text)) => text.clone(),
+        _ => String::new(),
+    }
+}
+
+fn set_text(interpreter: &mut Interpreter, this: Option<ObjectId>, text: String) {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(VALUE_FIELD.to_string(), DexValue::String(text));
+    }
+}
+
+/// Append `text` in place and return the builder for the next call of the chain
+fn push(interpreter: &mut Interpreter, this: Option<ObjectId>, text: &str) -> DexValue {
+    let Some(id) = this else {
+        return DexValue::Null;
+    };
+    if let Some(object) = interpreter.heap.get_mut(&id) {
+        match object.fields.get_mut(VALUE_FIELD) {
+            Some(DexValue::String(value)) => value.push_str(text),
+            _ => {
+                object.fields.insert(VALUE_FIELD.to_string(), DexValue::String(text.to_string()));
+            }
+        }
+    }
+    DexValue::Object(id)
+}
+
+fn init(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    set_text(interpreter, this, String::new());
+    DexValue::Void
+}
+
+fn init_with_text(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let text = interpreter.string_value_of(args.first().unwrap_or(&DexValue::Null));
+    set_text(interpreter, this, text);
+    DexValue::Void
+}
+
+// Registers are untyped, so primitives are read as the descriptor says rather than by variant
+
+fn append_boolean(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = args.first().and_then(|value| value.as_int()).unwrap_or(0) != 0;
+    push(interpreter, this, &value.to_string())
+}
+
+fn append_char(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let unit = args.first().and_then(|value| value.as_int()).unwrap_or(0) as u16;
+    push(interpreter, this, &String::from_utf16_lossy(&[unit]))
+}
+
+fn append_int(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = args.first().and_then(|value| value.as_int()).unwrap_or(0);
+    push(interpreter, this, &value.to_string())
+}
+
+fn append_long(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = args.first().and_then(|value| value.as_long()).unwrap_or(0);
+    push(interpreter, this, &value.to_string())
+}
+
+fn append_float(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = args.first().and_then(|value| value.as_float()).unwrap_or(0.0);
+    push(interpreter, this, &float_to_string(value))
+}
+
+fn append_double(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let value = args.first().and_then(|value| value.as_double()).unwrap_or(0.0);
+    push(interpreter, this, &double_to_string(value))
+}
+
+fn append_chars(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let units: Vec<u16> = match args.first() {
+        Some(DexValue::Object(id)) => interpreter.heap.get(id)
+            .and_then(|object| object.array.as_ref())
+            .map(|chars| chars.iter().filter_map(|unit| unit.as_int()).map(|unit| unit as u16).collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    push(interpreter, this, &String::from_utf16_lossy(&units))
+}
+
+fn append_object(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let text = interpreter.string_value_of(args.first().unwrap_or(&DexValue::Null));
+    push(interpreter, this, &text)
+}
+
+/// append(CharSequence, start, end), a null sequence appends part of "null"
+fn append_range(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let text: Vec<u16> = interpreter.string_value_of(args.first().unwrap_or(&DexValue::Null)).encode_utf16().collect();
+    let start = args.get(1).and_then(|value| value.as_int()).unwrap_or(0).clamp(0, text.len() as i32) as usize;
+    let end = args.get(2).and_then(|value| value.as_int()).unwrap_or(0).clamp(start as i32, text.len() as i32) as usize;
+    push(interpreter, this, &String::from_utf16_lossy(&text[start..end]))
+}
+
+fn length(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(text_of(interpreter, this).encode_utf16().count() as i32)
+}
+
+/// Truncates, or pads with NUL chars like the JVM
+fn set_length(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let length = args.first().and_then(|value| value.as_int()).unwrap_or(0).max(0) as usize;
+    let mut units: Vec<u16> = text_of(interpreter, this).encode_utf16().collect();
+    units.resize(length, 0);
+    set_text(interpreter, this, String::from_utf16_lossy(&units));
+    DexValue::Void
+}
+
+fn to_string(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(text_of(interpreter, this))
+}
@@ -0,0 +1,336 @@
+// java.lang.String methods. Strings aren't heap objects but `DexValue::String` register values,
+// so these get the receiver's text instead of an object. Indices count UTF-16 code units like on
+// the JVM, which only differs from chars for text outside the basic multilingual plane.
+
+use crate::interpreter::charset::charset_arg;
+use crate::interpreter::exception::{NULL_POINTER_EXCEPTION, STRING_INDEX_OUT_OF_BOUNDS_EXCEPTION, UNSUPPORTED_ENCODING_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{bytes_of, new_byte_array};
+use crate::interpreter::regex;
+use crate::types::DexValue;
+
+/// String method receiving the interpreter, the receiver's text and the arguments
+pub type StringMethod = fn(&mut Interpreter, &str, Vec<DexValue>) -> DexValue;
+
+/// The String method `method_key`, CharSequence methods included
+pub fn string_method(method_key: &str) -> Option<StringMethod> {
+    let method: StringMethod = match method_key {
+        "length:()I" => length,
+        "isEmpty:()Z" => is_empty,
+        "charAt:(I)C" => char_at,
+        "substring:(I)Ljava/lang/String;"
+        | "substring:(II)Ljava/lang/String;"
+        | "subSequence:(II)Ljava/lang/CharSequence;" => substring,
+        "indexOf:(I)I" | "indexOf:(II)I" | "indexOf:(Ljava/lang/String;)I" | "indexOf:(Ljava/lang/String;I)I" => index_of,
+        "lastIndexOf:(I)I" | "lastIndexOf:(Ljava/lang/String;)I" => last_index_of,
+        "contains:(Ljava/lang/CharSequence;)Z" => contains,
+        "startsWith:(Ljava/lang/String;)Z" | "startsWith:(Ljava/lang/String;I)Z" => starts_with,
+        "endsWith:(Ljava/lang/String;)Z" => ends_with,
+        "equals:(Ljava/lang/Object;)Z" | "contentEquals:(Ljava/lang/CharSequence;)Z" => equals,
+        "equalsIgnoreCase:(Ljava/lang/String;)Z" => equals_ignore_case,
+        "toLowerCase:()Ljava/lang/String;" | "toLowerCase:(Ljava/util/Locale;)Ljava/lang/String;" => to_lower_case,
+        "toUpperCase:()Ljava/lang/String;" | "toUpperCase:(Ljava/util/Locale;)Ljava/lang/String;" => to_upper_case,
+        "replace:(CC)Ljava/lang/String;"
+        | "replace:(Ljava/lang/CharSequence;Ljava/lang/CharSequence;)Ljava/lang/String;" => replace,
+        "matches:(Ljava/lang/String;)Z" => regex::string_matches,
+        "replaceAll:(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;" => regex::string_replace_all,
+        "replaceFirst:(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;" => regex::string_replace_first,
+        "split:(Ljava/lang/String;)[Ljava/lang/String;" | "split:(Ljava/lang/String;I)[Ljava/lang/String;" => regex::string_split,
+        "trim:()Ljava/lang/String;" => trim,
+        "concat:(Ljava/lang/String;)Ljava/lang/String;" => concat,
+        "toString:()Ljava/lang/String;" | "intern:()Ljava/lang/String;" => to_string,
+        "hashCode:()I" => hash_code,
+        "getBytes:()[B" | "getBytes:(Ljava/nio/charset/Charset;)[B" | "getBytes:(Ljava/lang/String;)[B" => get_bytes,
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn utf16(text: &str) -> Vec<u16> {
+    text.encode_utf16().collect()
+}
+
+/// Text of a String argument, throws a NullPointerException for null
+fn text_arg(interpreter: &mut Interpreter, arg: Option<&DexValue>) -> Option<String> {
+    match arg {
+        Some(DexValue::String(text)) => Some(text.clone()),
+        _ => {
+            interpreter.throw_new(NULL_POINTER_EXCEPTION, "Attempt to use a null string");
+            None
+        }
+    }
+}
+
+fn int_arg(args: &[DexValue], index: usize) -> Option<i32> {
+    args.get(index).and_then(|arg| arg.as_int())
+}
+
+/// Index of `needle` in `haystack` at or after `from`, in code units
+fn find(haystack: &[u16], needle: &[u16], from: usize) -> Option<usize> {
+    if needle.is_empty() {
+        return (from <= haystack.len()).then_some(from);
+    }
+    (from..haystack.len()).find(|&start| haystack[start..].starts_with(needle))
+}
+
+/// The needle of indexOf and friends, a code point or a string
+fn needle(interpreter: &mut Interpreter, arg: Option<&DexValue>) -> Option<Vec<u16>> {
+    match arg {
+        Some(DexValue::String(text)) => Some(utf16(text)),
+        Some(DexValue::Null) | None => text_arg(interpreter, None).map(|text| utf16(&text)),
+        Some(value) => {
+            let code_point = value.as_int().unwrap_or(0) as u32;
+            let c = char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER);
+            Some(utf16(&c.to_string()))
+        }
+    }
+}
+
+fn length(_interpreter: &mut Interpreter, text: &str, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Int(text.encode_utf16().count() as i32)
+}
+
+fn is_empty(_interpreter: &mut Interpreter, text: &str, _args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(text.is_empty())
+}
+
+fn char_at(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    let units = utf16(text);
+    let index = int_arg(&args, 0).unwrap_or(0);
+    match usize::try_from(index).ok().and_then(|index| units.get(index)) {
+        Some(unit) => DexValue::Char(*unit),
+        None => {
+            let message = format!("index: {}, length: {}", index, units.len());
+            interpreter.throw_new(STRING_INDEX_OUT_OF_BOUNDS_EXCEPTION, &message);
+            DexValue::Void
+        }
+    }
+}
+
+fn substring(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    let units = utf16(text);
+    let begin = int_arg(&args, 0).unwrap_or(0);
+    let end = int_arg(&args, 1).unwrap_or(units.len() as i32);
+
+    if begin < 0 || end > units.len() as i32 || begin > end {
+        let message = format!("begin {}, end {}, length {}", begin, end, units.len());
+        interpreter.throw_new(STRING_INDEX_OUT_OF_BOUNDS_EXCEPTION, &message);
+        return DexValue::Void;
+    }
+    DexValue::String(String::from_utf16_lossy(&units[begin as usize..end as usize]))
+}
+
+fn index_of(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    let Some(needle) = needle(interpreter, args.first()) else {
+        return DexValue::Void;
+    };
+    let from = int_arg(&args, 1).unwrap_or(0).max(0) as usize;
+
+    DexValue::Int(find(&utf16(text), &needle, from).map_or(-1, |index| index as i32))
+}
+
+fn last_index_of(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    let Some(needle) = needle(interpreter, args.first()) else {
+        return DexValue::Void;
+    };
+    let units = utf16(text);
+    let last = (0..=units.len().saturating_sub(needle.len()))
+        .rev()
+        .find(|&start| units[start..].starts_with(&needle));
+
+    DexValue::Int(last.map_or(-1, |index| index as i32))
+}
+
+fn contains(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    match text_arg(interpreter, args.first()) {
+        Some(other) => DexValue::Boolean(text.contains(other.as_str())),
+        None => DexValue::Void,
+    }
+}
+
+fn starts_with(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    let Some(prefix) = text_arg(interpreter, args.first()) else {
+        return DexValue::Void;
+    };
+    let units = utf16(text);
+    let offset = int_arg(&args, 1).unwrap_or(0);
+
+    let matches = usize::try_from(offset).ok()
+        .and_then(|offset| units.get(offset..))
+        .is_some_and(|rest| rest.starts_with(&utf16(&prefix)));
+    DexValue::Boolean(matches)
+}
+
+fn ends_with(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    match text_arg(interpreter, args.first()) {
+        Some(suffix) => DexValue::Boolean(text.ends_with(suffix.as_str())),
+        None => DexValue::Void,
+    }
+}
+
+fn equals(_interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    DexValue::Boolean(matches!(args.first(), Some(DexValue::String(other)) if other == text))
+}
+
+fn equals_ignore_case(_interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    let equal = match args.first() {
+        Some(DexValue::String(other)) => {
+            let fold = |text: &str| text.chars().flat_map(char::to_lowercase).collect::<String>();
+            fold(text) == fold(other)
+        }
+        _ => false,
+    };
+    DexValue::Boolean(equal)
+}
+
+fn to_lower_case(_interpreter: &mut Interpreter, text: &str, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(text.to_lowercase())
+}
+
+fn to_upper_case(_interpreter: &mut Interpreter, text: &str, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(text.to_uppercase())
+}
+
+fn replace(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    match (args.first(), args.get(1)) {
+        (Some(DexValue::String(target)), Some(DexValue::String(replacement))) => {
+            DexValue::String(replace_all(text, target, replacement))
+        }
+        (Some(DexValue::String(_)) | Some(DexValue::Null), _) | (_, Some(DexValue::String(_)) | Some(DexValue::Null)) => {
+            interpreter.throw_new(NULL_POINTER_EXCEPTION, "Attempt to replace with a null string");
+            DexValue::Void
+        }
+        (old, new) => {
+            let as_char = |value: Option<&DexValue>| value
+                .and_then(|value| value.as_int())
+                .and_then(|unit| char::from_u32(unit as u32))
+                .unwrap_or(char::REPLACEMENT_CHARACTER);
+            DexValue::String(text.replace(as_char(old), &as_char(new).to_string()))
+        }
+    }
+}
+
+/// `String.replace(CharSequence, CharSequence)`, an empty target inserts the replacement
+/// between every char and at both ends like on the JVM
+fn replace_all(text: &str, target: &str, replacement: &str) -> String {
+    if !target.is_empty() {
+        return text.replace(target, replacement);
+    }
+
+    let mut replaced = String::from(replacement);
+    for c in text.chars() {
+        replaced.push(c);
+        replaced.push_str(replacement);
+    }
+    replaced
+}
+
+fn trim(_interpreter: &mut Interpreter, text: &str, _args: Vec<DexValue>) -> DexValue {
+    // Java trims every char up to and including the space, not Unicode whitespace
+    DexValue::String(text.trim_matches(|c: char| c <= ' ').to_string())
+}
+
+fn concat(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    match text_arg(interpreter, args.first()) {
+        Some(other) => DexValue::String(format!("{}{}", text, other)),
+        None => DexValue::Void,
+    }
+}
+
+fn to_string(_interpreter: &mut Interpreter, text: &str, _args: Vec<DexValue>) -> DexValue {
+    DexValue::String(text.to_string())
+}
+
+/// `s[0]*31^(n-1) + ... + s[n-1]` over the code units, the same value the JVM computes
+fn hash_code(_interpreter: &mut Interpreter, text: &str, _args: Vec<DexValue>) -> DexValue {
+    let hash = text.encode_utf16().fold(0i32, |hash, unit| hash.wrapping_mul(31).wrapping_add(unit as i32));
+    DexValue::Int(hash)
+}
+
+fn get_bytes(interpreter: &mut Interpreter, text: &str, args: Vec<DexValue>) -> DexValue {
+    match charset_arg(interpreter, args.first()) {
+        Ok(charset) => new_byte_array(interpreter, charset.encode(text)),
+        Err(name) => {
+            interpreter.throw_new(UNSUPPORTED_ENCODING_EXCEPTION, &name);
+            DexValue::Void
+        }
+    }
+}
+
+/// Text of `new String(...)`, `method_key` being the constructor called. Malformed input is
+/// replaced like the JVM does, `None` if the constructor isn't implemented or threw.
+pub fn new_string(interpreter: &mut Interpreter, method_key: &str, args: &[DexValue]) -> Option<String> {
+    let parameters = method_key.split_once('(')?.1.split_once(')')?.0;
+    let array = match args.first() {
+        Some(DexValue::Object(id)) => Some(*id),
+        _ => None,
+    };
+    // The (offset, count) overloads
+    let range = |length: usize| {
+        let offset = int_arg(args, 1).unwrap_or(0).clamp(0, length as i32) as usize;
+        let count = int_arg(args, 2).unwrap_or(0).clamp(0, (length - offset) as i32) as usize;
+        offset..offset + count
+    };
+
+    let text = match parameters {
+        "" => String::new(),
+        "Ljava/lang/String;" | "Ljava/lang/StringBuilder;" | "Ljava/lang/StringBuffer;" => {
+            interpreter.string_value_of(args.first().unwrap_or(&DexValue::Null))
+        }
+        "[B" | "[BLjava/nio/charset/Charset;" | "[BLjava/lang/String;" | "[BII" | "[BIILjava/nio/charset/Charset;" | "[BIILjava/lang/String;" => {
+            let mut bytes = bytes_of(interpreter, array);
+            if parameters.starts_with("[BII") {
+                bytes = bytes[range(bytes.len())].to_vec();
+            }
+            let charset = if parameters.ends_with(';') { args.last() } else { None };
+            match charset_arg(interpreter, charset) {
+                Ok(charset) => charset.decode(&bytes),
+                Err(name) => {
+                    interpreter.throw_new(UNSUPPORTED_ENCODING_EXCEPTION, &name);
+                    return None;
+                }
+            }
+        }
+        "[C" | "[CII" => {
+            let mut units: Vec<u16> = array.and_then(|id| interpreter.heap.get(&id))
+                .and_then(|object| object.array.as_ref())
+                .map(|chars| chars.iter().map(|unit| unit.as_int().unwrap_or(0) as u16).collect())
+                .unwrap_or_default();
+            if parameters == "[CII" {
+                units = units[range(units.len())].to_vec();
+            }
+            String::from_utf16_lossy(&units)
+        }
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// `Double.toString()`: plain decimals from 10^-3 up to 10^7, `1.0E10` notation outside them
+pub fn double_to_string(value: f64) -> String {
+    java_decimal(value, format!("{:?}", value), format!("{:E}", value))
+}
+
+/// `Float.toString()`, the shortest digits that read back as the same float
+pub fn float_to_string(value: f32) -> String {
+    java_decimal(value as f64, format!("{:?}", value), format!("{:E}", value))
+}
+
+fn java_decimal(value: f64, plain: String, scientific: String) -> String {
+    if value.is_nan() {
+        return "NaN".to_string();
+    }
+    if value.is_infinite() {
+        return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    // Debug output keeps a fraction and only uses exponents far outside this range
+    if value == 0.0 || (1e-3..1e7).contains(&value.abs()) {
+        return plain;
+    }
+
+    // Java always has a fraction in the mantissa, 1.0E10 rather than 1E10
+    match scientific.split_once('E') {
+        Some((mantissa, exponent)) if !mantissa.contains('.') => format!("{}.0E{}", mantissa, exponent),
+        _ => scientific,
+    }
+}
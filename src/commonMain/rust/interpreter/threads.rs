@@ -0,0 +1,301 @@
+// Monitors, java.lang.Thread, ReentrantLock and the Executors thread pools. The heap belongs to
+// the one thread running the interpreter, so work handed to another thread or a pool runs
+// inline on it instead: Thread.start() runs run() before returning and a pool runs each task as
+// it is submitted, handing back a Future that is already done. A task that throws doesn't take
+// the caller down, its exception is reported like an uncaught one on a thread of its own or
+// kept in its Future. Monitors only count how often the running thread holds them, which keeps
+// `synchronized` blocks and lock()/unlock() pairs checked without anything to wait for.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::interpreter::clock;
+use crate::interpreter::collections::{elements_of, new_list};
+use crate::interpreter::exception::{CAUSE_FIELD, EXECUTION_EXCEPTION, ILLEGAL_MONITOR_STATE_EXCEPTION, MESSAGE_FIELD, NULL_POINTER_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{binary_name, parameters, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const THREAD: &str = "Ljava/lang/Thread;";
+pub const EXECUTORS: &str = "Ljava/util/concurrent/Executors;";
+/// What every Executors factory returns
+pub const THREAD_POOL_EXECUTOR: &str = "Ljava/util/concurrent/ThreadPoolExecutor;";
+/// What submit() returns
+pub const FUTURE_TASK: &str = "Ljava/util/concurrent/FutureTask;";
+pub const REENTRANT_LOCK: &str = "Ljava/util/concurrent/locks/ReentrantLock;";
+
+/// Static field of Thread holding the thread the interpreter runs on
+const CURRENT_THREAD_FIELD: &str = "$current";
+const NAME_FIELD: &str = "name";
+/// Field of a Thread holding the Runnable it was created with
+const TARGET_FIELD: &str = "target";
+const SHUTDOWN_FIELD: &str = "shutdown";
+/// Fields of a FutureTask holding what the task returned or the exception it threw
+const RESULT_FIELD: &str = "result";
+const EXCEPTION_FIELD: &str = "exception";
+
+const RUN: &str = "run:()V";
+const CALL: &str = "call:()Ljava/lang/Object;";
+
+/// Thread's static fields, along with the thread the interpreter runs on
+pub fn thread_statics(interpreter: &mut Interpreter) -> HashMap<String, DexValue> {
+    let main = interpreter.alloc_object(THREAD);
+    set_field(interpreter, Some(main), NAME_FIELD, DexValue::String("main".to_string()));
+    HashMap::from([
+        ("MIN_PRIORITY".to_string(), DexValue::Int(1)),
+        ("NORM_PRIORITY".to_string(), DexValue::Int(5)),
+        ("MAX_PRIORITY".to_string(), DexValue::Int(10)),
+        (CURRENT_THREAD_FIELD.to_string(), DexValue::Object(main)),
+    ])
+}
+
+/// `monitor-enter` and lock(), `object` is held once more
+pub fn enter(interpreter: &mut Interpreter, object: ObjectId) {
+    *interpreter.monitors.entry(object).or_insert(0) += 1;
+}
+
+/// `monitor-exit` and unlock(), throws an IllegalMonitorStateException if `object` isn't held
+pub fn exit(interpreter: &mut Interpreter, object: ObjectId) {
+    match interpreter.monitors.get_mut(&object) {
+        Some(count) if *count > 1 => *count -= 1,
+        Some(_) => {
+            interpreter.monitors.remove(&object);
+        }
+        None => interpreter.throw_new(ILLEGAL_MONITOR_STATE_EXCEPTION, ""),
+    }
+}
+
+fn field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> DexValue {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.fields.get(name).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn set_field(interpreter: &mut Interpreter, this: Option<ObjectId>, name: &str, value: DexValue) {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(name.to_string(), value);
+    }
+}
+
+fn arg(args: &[DexValue], index: usize) -> DexValue {
+    args.get(index).cloned().unwrap_or(DexValue::Null)
+}
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (THREAD, "<init>:()V") => |_, _, _| DexValue::Void,
+        (THREAD, "<init>:(Ljava/lang/Runnable;)V") => |interpreter, this, args| {
+            set_field(interpreter, this, TARGET_FIELD, arg(&args, 0));
+            DexValue::Void
+        },
+        (THREAD, "<init>:(Ljava/lang/String;)V") => |interpreter, this, args| {
+            set_field(interpreter, this, NAME_FIELD, arg(&args, 0));
+            DexValue::Void
+        },
+        (THREAD, "<init>:(Ljava/lang/Runnable;Ljava/lang/String;)V") => |interpreter, this, args| {
+            set_field(interpreter, this, TARGET_FIELD, arg(&args, 0));
+            set_field(interpreter, this, NAME_FIELD, arg(&args, 1));
+            DexValue::Void
+        },
+        (THREAD, "currentThread:()Ljava/lang/Thread;") => |interpreter, _, _| {
+            interpreter.initialize_class(THREAD);
+            interpreter.statics.get(THREAD)
+                .and_then(|statics| statics.get(CURRENT_THREAD_FIELD).cloned())
+                .unwrap_or(DexValue::Null)
+        },
+        (THREAD, "sleep:(J)V") => |_, _, args| {
+            let millis = parameters(&args).first().and_then(|value| value.as_long()).unwrap_or(0);
+            if millis > 0 {
+                clock::sleep(Duration::from_millis(millis as u64));
+            }
+            DexValue::Void
+        },
+        (THREAD, "start:()V") => thread_start,
+        (THREAD, "run:()V") => |interpreter, this, _| {
+            if let DexValue::Object(target) = field(interpreter, this, TARGET_FIELD) {
+                interpreter.invoke_virtual(target, RUN, Vec::new());
+            }
+            DexValue::Void
+        },
+        (THREAD, "join:()V" | "join:(J)V" | "interrupt:()V" | "setDaemon:(Z)V" | "setPriority:(I)V") => |_, _, _| DexValue::Void,
+        (THREAD, "isAlive:()Z" | "isInterrupted:()Z" | "isDaemon:()Z") => |_, _, _| DexValue::Boolean(false),
+        (THREAD, "getName:()Ljava/lang/String;") => |interpreter, this, _| match field(interpreter, this, NAME_FIELD) {
+            DexValue::Null => DexValue::String(format!("Thread-{}", this.unwrap_or_default())),
+            name => name,
+        },
+        (THREAD, "setName:(Ljava/lang/String;)V") => |interpreter, this, args| {
+            set_field(interpreter, this, NAME_FIELD, arg(&args, 0));
+            DexValue::Void
+        },
+        (THREAD, "getId:()J") => |_, this, _| DexValue::Long(this.unwrap_or_default() as i64),
+
+        (EXECUTORS, "newFixedThreadPool:(I)Ljava/util/concurrent/ExecutorService;"
+            | "newCachedThreadPool:()Ljava/util/concurrent/ExecutorService;"
+            | "newSingleThreadExecutor:()Ljava/util/concurrent/ExecutorService;"
+            | "newWorkStealingPool:()Ljava/util/concurrent/ExecutorService;"
+            | "newWorkStealingPool:(I)Ljava/util/concurrent/ExecutorService;") => |interpreter, _, _| {
+            DexValue::Object(interpreter.alloc_object(THREAD_POOL_EXECUTOR))
+        },
+        (THREAD_POOL_EXECUTOR, "execute:(Ljava/lang/Runnable;)V") => |interpreter, _, args| {
+            let Some(task) = task_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            if let Err(exception) = run_task(interpreter, task, RUN) {
+                report_uncaught(interpreter, "pool-1-thread-1", &exception);
+            }
+            DexValue::Void
+        },
+        (THREAD_POOL_EXECUTOR, "submit:(Ljava/util/concurrent/Callable;)Ljava/util/concurrent/Future;") => |interpreter, _, args| {
+            let Some(task) = task_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            let outcome = run_task(interpreter, task, CALL);
+            new_future(interpreter, outcome)
+        },
+        (THREAD_POOL_EXECUTOR, "submit:(Ljava/lang/Runnable;)Ljava/util/concurrent/Future;") => |interpreter, _, args| {
+            let Some(task) = task_arg(interpreter, &args) else {
+                return DexValue::Void;
+            };
+            let outcome = run_task(interpreter, task, RUN).map(|_| DexValue::Null);
+            new_future(interpreter, outcome)
+        },
+        (THREAD_POOL_EXECUTOR, "invokeAll:(Ljava/util/Collection;)Ljava/util/List;") => |interpreter, _, args| {
+            let mut futures = Vec::new();
+            for task in elements_of(interpreter, &arg(&args, 0)) {
+                let DexValue::Object(task) = task else {
+                    interpreter.throw_new(NULL_POINTER_EXCEPTION, "");
+                    return DexValue::Void;
+                };
+                let outcome = run_task(interpreter, task, CALL);
+                futures.push(new_future(interpreter, outcome));
+            }
+            DexValue::Object(new_list(interpreter, futures))
+        },
+        (THREAD_POOL_EXECUTOR, "shutdown:()V") => |interpreter, this, _| {
+            set_field(interpreter, this, SHUTDOWN_FIELD, DexValue::Boolean(true));
+            DexValue::Void
+        },
+        (THREAD_POOL_EXECUTOR, "shutdownNow:()Ljava/util/List;") => |interpreter, this, _| {
+            set_field(interpreter, this, SHUTDOWN_FIELD, DexValue::Boolean(true));
+            DexValue::Object(new_list(interpreter, Vec::new()))
+        },
+        (THREAD_POOL_EXECUTOR, "isShutdown:()Z" | "isTerminated:()Z") => |interpreter, this, _| {
+            DexValue::Boolean(field(interpreter, this, SHUTDOWN_FIELD) == DexValue::Boolean(true))
+        },
+        (THREAD_POOL_EXECUTOR, "awaitTermination:(JLjava/util/concurrent/TimeUnit;)Z") => |_, _, _| DexValue::Boolean(true),
+
+        (FUTURE_TASK, "get:()Ljava/lang/Object;" | "get:(JLjava/util/concurrent/TimeUnit;)Ljava/lang/Object;") => future_get,
+        (FUTURE_TASK, "isDone:()Z") => |_, _, _| DexValue::Boolean(true),
+        (FUTURE_TASK, "isCancelled:()Z" | "cancel:(Z)Z") => |_, _, _| DexValue::Boolean(false),
+
+        (REENTRANT_LOCK, "<init>:()V" | "<init>:(Z)V") => |_, _, _| DexValue::Void,
+        (REENTRANT_LOCK, "lock:()V" | "lockInterruptibly:()V") => |interpreter, this, _| {
+            if let Some(lock) = this {
+                enter(interpreter, lock);
+            }
+            DexValue::Void
+        },
+        (REENTRANT_LOCK, "tryLock:()Z" | "tryLock:(JLjava/util/concurrent/TimeUnit;)Z") => |interpreter, this, _| {
+            if let Some(lock) = this {
+                enter(interpreter, lock);
+            }
+            DexValue::Boolean(true)
+        },
+        (REENTRANT_LOCK, "unlock:()V") => |interpreter, this, _| {
+            if let Some(lock) = this {
+                exit(interpreter, lock);
+            }
+            DexValue::Void
+        },
+        (REENTRANT_LOCK, "isLocked:()Z" | "isHeldByCurrentThread:()Z") => |interpreter, this, _| {
+            DexValue::Boolean(this.is_some_and(|lock| interpreter.monitors.contains_key(&lock)))
+        },
+        (REENTRANT_LOCK, "getHoldCount:()I") => |interpreter, this, _| {
+            DexValue::Int(this.and_then(|lock| interpreter.monitors.get(&lock)).copied().unwrap_or(0) as i32)
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// The Runnable or Callable handed to a pool, throws a NullPointerException for none
+fn task_arg(interpreter: &mut Interpreter, args: &[DexValue]) -> Option<ObjectId> {
+    match args.first() {
+        Some(DexValue::Object(task)) => Some(*task),
+        _ => {
+            interpreter.throw_new(NULL_POINTER_EXCEPTION, "");
+            None
+        }
+    }
+}
+
+/// Run `method_key` of `task`, a Runnable or a Callable, and take back what it threw
+fn run_task(interpreter: &mut Interpreter, task: ObjectId, method_key: &str) -> Result<DexValue, DexValue> {
+    let value = interpreter.invoke_virtual(task, method_key, Vec::new());
+    match interpreter.pending_exception.take() {
+        Some(pending) => Err(pending.exception),
+        None => Ok(value.unwrap_or(DexValue::Null)),
+    }
+}
+
+/// Log `exception` the way the JVM reports an exception nothing on its thread caught
+fn report_uncaught(interpreter: &mut Interpreter, thread_name: &str, exception: &DexValue) {
+    let class_name = match exception {
+        DexValue::Object(id) => interpreter.heap.get(id).map(|object| object.class_name.clone()).unwrap_or_default(),
+        _ => String::new(),
+    };
+    let message = interpreter.exception_message(exception);
+    crate::logging::log(&format!(
+        "Exception in thread \"{}\" {}{}",
+        thread_name,
+        binary_name(&class_name),
+        message.map(|message| format!(": {}", message)).unwrap_or_default(),
+    ));
+}
+
+/// `thread.start()`, which runs the thread's run() to its end
+fn thread_start(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let Some(thread) = this else {
+        return DexValue::Void;
+    };
+    if let Err(exception) = run_task(interpreter, thread, RUN) {
+        let name = match field(interpreter, this, NAME_FIELD) {
+            DexValue::String(name) => name,
+            _ => format!("Thread-{}", thread),
+        };
+        report_uncaught(interpreter, &name, &exception);
+    }
+    DexValue::Void
+}
+
+fn new_future(interpreter: &mut Interpreter, outcome: Result<DexValue, DexValue>) -> DexValue {
+    let id = interpreter.alloc_object(FUTURE_TASK);
+    let (name, value) = match outcome {
+        Ok(value) => (RESULT_FIELD, value),
+        Err(exception) => (EXCEPTION_FIELD, exception),
+    };
+    set_field(interpreter, Some(id), name, value);
+    DexValue::Object(id)
+}
+
+/// `future.get()`, the task's exception comes wrapped in an ExecutionException
+fn future_get(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let exception = field(interpreter, this, EXCEPTION_FIELD);
+    if exception == DexValue::Null {
+        return field(interpreter, this, RESULT_FIELD);
+    }
+
+    let cause = match &exception {
+        DexValue::Object(id) => interpreter.heap.get(id).map(|object| binary_name(&object.class_name)).unwrap_or_default(),
+        _ => String::new(),
+    };
+    let message = match interpreter.exception_message(&exception) {
+        Some(message) => format!("{}: {}", cause, message),
+        None => cause,
+    };
+    let wrapper = interpreter.alloc_object(EXECUTION_EXCEPTION);
+    set_field(interpreter, Some(wrapper), MESSAGE_FIELD, DexValue::String(message));
+    set_field(interpreter, Some(wrapper), CAUSE_FIELD, exception);
+    interpreter.throw(DexValue::Object(wrapper));
+    DexValue::Void
+}
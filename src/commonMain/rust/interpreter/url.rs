@@ -0,0 +1,566 @@
+// URLEncoder, URLDecoder and java.net.URI. Extensions build search queries with the former and
+// resolve the relative links of scraped pages with the latter. A URI object holds its raw
+// components as fields, the getters without a `Raw` in their name decode them.
+
+use crate::interpreter::charset::{charset_arg, Charset};
+use crate::interpreter::exception::{ILLEGAL_ARGUMENT_EXCEPTION, NULL_POINTER_EXCEPTION, UNSUPPORTED_ENCODING_EXCEPTION, URI_SYNTAX_EXCEPTION};
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::PlatformMethod;
+use crate::types::{DexValue, ObjectId};
+
+pub const URL_ENCODER: &str = "Ljava/net/URLEncoder;";
+pub const URL_DECODER: &str = "Ljava/net/URLDecoder;";
+pub const URI: &str = "Ljava/net/URI;";
+
+const SCHEME_FIELD: &str = "scheme";
+const SCHEME_SPECIFIC_PART_FIELD: &str = "schemeSpecificPart";
+const AUTHORITY_FIELD: &str = "authority";
+const USER_INFO_FIELD: &str = "userInfo";
+const HOST_FIELD: &str = "host";
+const PORT_FIELD: &str = "port";
+const PATH_FIELD: &str = "path";
+const QUERY_FIELD: &str = "query";
+const FRAGMENT_FIELD: &str = "fragment";
+/// The text the URI was parsed from, or its components put back together
+const STRING_FIELD: &str = "string";
+
+/// Chars that are illegal anywhere in a URI besides controls, spaces and `%` not starting an escape
+const ILLEGAL_CHARS: &str = "\"<>\\^`{|}";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (URL_ENCODER, "encode:(Ljava/lang/String;)Ljava/lang/String;"
+            | "encode:(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;"
+            | "encode:(Ljava/lang/String;Ljava/nio/charset/Charset;)Ljava/lang/String;") => url_encode,
+        (URL_DECODER, "decode:(Ljava/lang/String;)Ljava/lang/String;"
+            | "decode:(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;"
+            | "decode:(Ljava/lang/String;Ljava/nio/charset/Charset;)Ljava/lang/String;") => url_decode,
+
+        (URI, "<init>:(Ljava/lang/String;)V") => uri_init,
+        (URI, "create:(Ljava/lang/String;)Ljava/net/URI;") => uri_create,
+        (URI, "getScheme:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, SCHEME_FIELD),
+        (URI, "getRawSchemeSpecificPart:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, SCHEME_SPECIFIC_PART_FIELD),
+        (URI, "getSchemeSpecificPart:()Ljava/lang/String;") => |interpreter, this, _| decoded_field(interpreter, this, SCHEME_SPECIFIC_PART_FIELD),
+        (URI, "getRawAuthority:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, AUTHORITY_FIELD),
+        (URI, "getAuthority:()Ljava/lang/String;") => |interpreter, this, _| decoded_field(interpreter, this, AUTHORITY_FIELD),
+        (URI, "getRawUserInfo:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, USER_INFO_FIELD),
+        (URI, "getUserInfo:()Ljava/lang/String;") => |interpreter, this, _| decoded_field(interpreter, this, USER_INFO_FIELD),
+        (URI, "getHost:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, HOST_FIELD),
+        (URI, "getPort:()I") => |interpreter, this, _| field(interpreter, this, PORT_FIELD),
+        (URI, "getRawPath:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, PATH_FIELD),
+        (URI, "getPath:()Ljava/lang/String;") => |interpreter, this, _| decoded_field(interpreter, this, PATH_FIELD),
+        (URI, "getRawQuery:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, QUERY_FIELD),
+        (URI, "getQuery:()Ljava/lang/String;") => |interpreter, this, _| decoded_field(interpreter, this, QUERY_FIELD),
+        (URI, "getRawFragment:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, FRAGMENT_FIELD),
+        (URI, "getFragment:()Ljava/lang/String;") => |interpreter, this, _| decoded_field(interpreter, this, FRAGMENT_FIELD),
+        (URI, "isAbsolute:()Z") => |interpreter, this, _| DexValue::Boolean(uri_of(interpreter, this).scheme.is_some()),
+        (URI, "isOpaque:()Z") => |interpreter, this, _| DexValue::Boolean(uri_of(interpreter, this).path.is_none()),
+        (URI, "resolve:(Ljava/lang/String;)Ljava/net/URI;" | "resolve:(Ljava/net/URI;)Ljava/net/URI;") => uri_resolve,
+        (URI, "normalize:()Ljava/net/URI;") => uri_normalize,
+        (URI, "toString:()Ljava/lang/String;" | "toASCIIString:()Ljava/lang/String;") => |interpreter, this, _| field(interpreter, this, STRING_FIELD),
+        (URI, "equals:(Ljava/lang/Object;)Z") => uri_equals,
+        (URI, "hashCode:()I") => uri_hash_code,
+        _ => return None,
+    };
+    Some(method)
+}
+
+/// `application/x-www-form-urlencoded` form of `text`: spaces become `+`, and the bytes of
+/// anything but letters, digits and `.-*_` become `%XX`
+fn form_encode(text: &str, charset: Charset) -> String {
+    let bytes = charset.encode(text);
+    let mut encoded = String::with_capacity(bytes.len());
+    for byte in bytes {
+        match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'.' | b'-' | b'*' | b'_' => encoded.push(byte as char),
+            b' ' => encoded.push('+'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn form_decode(text: &str, charset: Charset) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let Some(escape) = bytes.get(index + 1..index + 3) else {
+                    return Err("URLDecoder: Incomplete trailing escape (%) pattern".to_string());
+                };
+                let Some(byte) = std::str::from_utf8(escape).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) else {
+                    return Err(format!(
+                        "URLDecoder: Illegal hex characters in escape (%) pattern - Error at index 0 in: \"{}\"",
+                        String::from_utf8_lossy(escape)
+                    ));
+                };
+                decoded.push(byte);
+                index += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+    Ok(charset.decode(&decoded))
+}
+
+/// The charset argument of URLEncoder and URLDecoder, throwing for names that aren't one
+fn charset(interpreter: &mut Interpreter, args: &[DexValue]) -> Option<Charset> {
+    match charset_arg(interpreter, args.get(1)) {
+        Ok(charset) => Some(charset),
+        Err(name) => {
+            interpreter.throw_new(UNSUPPORTED_ENCODING_EXCEPTION, &name);
+            None
+        }
+    }
+}
+
+fn url_encode(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(DexValue::String(text)) = args.first() else {
+        interpreter.throw_new(NULL_POINTER_EXCEPTION, "Attempt to invoke URLEncoder.encode on a null string");
+        return DexValue::Void;
+    };
+    match charset(interpreter, &args) {
+        Some(charset) => DexValue::String(form_encode(text, charset)),
+        None => DexValue::Void,
+    }
+}
+
+fn url_decode(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some(DexValue::String(text)) = args.first() else {
+        interpreter.throw_new(NULL_POINTER_EXCEPTION, "Attempt to invoke URLDecoder.decode on a null string");
+        return DexValue::Void;
+    };
+    let Some(charset) = charset(interpreter, &args) else {
+        return DexValue::Void;
+    };
+    match form_decode(text, charset) {
+        Ok(decoded) => DexValue::String(decoded),
+        Err(message) => {
+            interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &message);
+            DexValue::Void
+        }
+    }
+}
+
+/// Raw components of a URI. Opaque URIs like `mailto:a@b.c` have no path, and a URI whose
+/// authority isn't a server, e.g. one with `_` in its host, has no host like on the JVM.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Uri {
+    scheme: Option<String>,
+    scheme_specific_part: String,
+    authority: Option<String>,
+    user_info: Option<String>,
+    host: Option<String>,
+    port: i32,
+    path: Option<String>,
+    query: Option<String>,
+    fragment: Option<String>,
+}
+
+impl Uri {
+    /// Parse `input` the way `new URI(input)` does, failing with the URISyntaxException message
+    fn parse(input: &str) -> Result<Uri, String> {
+        let error = |reason: &str, index: usize| Err(format!("{} at index {}: {}", reason, index, input));
+        let mut uri = Uri { port: -1, ..Uri::default() };
+
+        let (rest, fragment) = match input.split_once('#') {
+            Some((rest, fragment)) => (rest, Some(fragment)),
+            None => (input, None),
+        };
+        if let Some(fragment) = fragment {
+            check(input, fragment, rest.len() + 1, "fragment", "")?;
+            uri.fragment = Some(fragment.to_string());
+        }
+
+        // A scheme is whatever comes before the first `:`, if it comes before any `/` or `?`
+        let mut start = 0;
+        if let Some(colon) = rest.find(':').filter(|colon| !rest[..*colon].contains(['/', '?'])) {
+            let scheme = &rest[..colon];
+            if scheme.is_empty() {
+                return error("Expected scheme name", 0);
+            }
+            if let Some(index) = scheme.char_indices()
+                .position(|(index, c)| !(c.is_ascii_alphabetic() || index > 0 && (c.is_ascii_digit() || "+-.".contains(c))))
+            {
+                return error("Illegal character in scheme name", index);
+            }
+            if colon + 1 == rest.len() {
+                return error("Expected scheme-specific part", colon + 1);
+            }
+            uri.scheme = Some(scheme.to_string());
+            start = colon + 1;
+        }
+        uri.scheme_specific_part = rest[start..].to_string();
+
+        let rest = &rest[start..];
+        if uri.scheme.is_some() && !rest.starts_with('/') {
+            check(input, rest, start, "opaque part", "")?;
+            return Ok(uri);
+        }
+
+        let (rest, query) = match rest.split_once('?') {
+            Some((rest, query)) => (rest, Some(query)),
+            None => (rest, None),
+        };
+        if let Some(query) = query {
+            check(input, query, start + rest.len() + 1, "query", "")?;
+            uri.query = Some(query.to_string());
+        }
+
+        let mut path_start = start;
+        let mut path = rest;
+        if let Some(after_slashes) = rest.strip_prefix("//") {
+            let end = after_slashes.find('/').unwrap_or(after_slashes.len());
+            let authority = &after_slashes[..end];
+            path = &after_slashes[end..];
+            path_start = start + 2 + end;
+            if authority.is_empty() && path.is_empty() && query.is_none() {
+                return error("Expected authority", start + 2);
+            }
+            if !authority.is_empty() {
+                check(input, authority, start + 2, "authority", "")?;
+                uri.parse_authority(authority);
+            }
+        }
+        check(input, path, path_start, "path", "[]")?;
+        uri.path = Some(path.to_string());
+        Ok(uri)
+    }
+
+    /// Split a server authority `user@host:port`. Anything else is kept as a registry-based
+    /// authority without host and port.
+    fn parse_authority(&mut self, authority: &str) {
+        self.authority = Some(authority.to_string());
+        let (user_info, host_port) = match authority.rsplit_once('@') {
+            Some((user_info, host_port)) => (Some(user_info), host_port),
+            None => (None, authority),
+        };
+        let (host, port) = match host_port.rfind(':').filter(|colon| !host_port[*colon..].contains(']')) {
+            Some(colon) => (&host_port[..colon], &host_port[colon + 1..]),
+            None => (host_port, ""),
+        };
+        let port = match port {
+            "" => -1,
+            port => match port.parse::<i32>() {
+                Ok(number) if port.bytes().all(|byte| byte.is_ascii_digit()) => number,
+                _ => return,
+            },
+        };
+        if !is_server_host(host) {
+            return;
+        }
+        self.user_info = user_info.map(str::to_string);
+        self.host = Some(host.to_string());
+        self.port = port;
+    }
+
+    /// The URI as text, put together from its components
+    fn compose(&self) -> String {
+        let mut text = String::new();
+        if let Some(scheme) = &self.scheme {
+            text.push_str(scheme);
+            text.push(':');
+        }
+        match &self.path {
+            None => text.push_str(&self.scheme_specific_part),
+            Some(path) => {
+                if let Some(authority) = &self.authority {
+                    text.push_str("//");
+                    text.push_str(authority);
+                }
+                text.push_str(path);
+                if let Some(query) = &self.query {
+                    text.push('?');
+                    text.push_str(query);
+                }
+            }
+        }
+        if let Some(fragment) = &self.fragment {
+            text.push('#');
+            text.push_str(fragment);
+        }
+        text
+    }
+
+    /// `reference` resolved against this URI following RFC 3986, the way browsers and OkHttp
+    /// resolve links rather than with the JVM's quirks for empty paths
+    fn resolve(&self, reference: &Uri) -> Uri {
+        if reference.scheme.is_some() || self.path.is_none() || reference.path.is_none() {
+            return reference.clone();
+        }
+
+        let mut resolved = Uri { scheme: self.scheme.clone(), fragment: reference.fragment.clone(), ..reference.clone() };
+        let reference_path = reference.path.as_deref().unwrap_or_default();
+        if reference.authority.is_some() {
+            resolved.path = Some(remove_dot_segments(reference_path));
+        } else {
+            resolved.authority = self.authority.clone();
+            resolved.user_info = self.user_info.clone();
+            resolved.host = self.host.clone();
+            resolved.port = self.port;
+            let base_path = self.path.as_deref().unwrap_or_default();
+            if reference_path.is_empty() {
+                resolved.path = Some(base_path.to_string());
+                if reference.query.is_none() {
+                    resolved.query = self.query.clone();
+                }
+            } else if reference_path.starts_with('/') {
+                resolved.path = Some(remove_dot_segments(reference_path));
+            } else {
+                let merged = if self.authority.is_some() && base_path.is_empty() {
+                    format!("/{}", reference_path)
+                } else {
+                    format!("{}{}", &base_path[..base_path.rfind('/').map_or(0, |slash| slash + 1)], reference_path)
+                };
+                resolved.path = Some(remove_dot_segments(&merged));
+            }
+        }
+        resolved.update_scheme_specific_part();
+        resolved
+    }
+
+    fn update_scheme_specific_part(&mut self) {
+        let fragment = self.fragment.take();
+        let scheme = self.scheme.take();
+        self.scheme_specific_part = self.compose();
+        self.scheme = scheme;
+        self.fragment = fragment;
+    }
+}
+
+/// `reference` resolved against the absolute `base`, `None` unless both parse as URIs
+pub fn resolve_reference(base: &str, reference: &str) -> Option<String> {
+    let base = Uri::parse(base).ok()?;
+    let reference = Uri::parse(reference).ok()?;
+    Some(base.resolve(&reference).compose())
+}
+
+/// `%` has to start an escape, the chars of `extra_illegal` are illegal in this component too
+fn check(input: &str, component: &str, offset: usize, name: &str, extra_illegal: &str) -> Result<(), String> {
+    let bytes = component.as_bytes();
+    for (index, c) in component.char_indices() {
+        if c.is_ascii_control() || c == ' ' || ILLEGAL_CHARS.contains(c) || extra_illegal.contains(c) {
+            return Err(format!("Illegal character in {} at index {}: {}", name, offset + index, input));
+        }
+        if c == '%' && !bytes.get(index + 1..index + 3).is_some_and(|hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+            return Err(format!("Malformed escape pair at index {}: {}", offset + index, input));
+        }
+    }
+    Ok(())
+}
+
+/// Whether `host` is a domain name, an IPv4 address or a bracketed IPv6 address
+fn is_server_host(host: &str) -> bool {
+    if host.starts_with('[') {
+        return host.ends_with(']') && host[1..host.len() - 1].chars().all(|c| c.is_ascii_hexdigit() || ":.".contains(c));
+    }
+    if host.split('.').count() == 4 && host.split('.').all(|part| part.parse::<u8>().is_ok()) {
+        return true;
+    }
+    let labels: Vec<&str> = host.strip_suffix('.').unwrap_or(host).split('.').collect();
+    let valid_labels = labels.iter().all(|label| {
+        !label.is_empty()
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+    });
+    // The top label starts with a letter so a domain can't be mistaken for an address
+    valid_labels && labels.last().is_some_and(|label| label.starts_with(|c: char| c.is_ascii_alphabetic()))
+}
+
+/// Drop `.` segments and `..` ones with the segment they undo. A relative path keeps the `..`
+/// segments that go above its start.
+pub fn remove_dot_segments(path: &str) -> String {
+    let absolute = path.starts_with('/');
+    let segments: Vec<&str> = path.strip_prefix('/').unwrap_or(path).split('/').collect();
+    let mut kept: Vec<&str> = Vec::with_capacity(segments.len());
+    let ends_in_directory = matches!(segments.last(), Some(&"." | &".."));
+    for segment in segments {
+        match segment {
+            "." => {}
+            ".." => match kept.last() {
+                Some(last) if *last != ".." => {
+                    kept.pop();
+                }
+                _ if !absolute => kept.push(".."),
+                _ => {}
+            },
+            segment => kept.push(segment),
+        }
+    }
+    let mut normalized = if absolute { "/".to_string() } else { String::new() };
+    normalized.push_str(&kept.join("/"));
+    if ends_in_directory && !kept.is_empty() {
+        normalized.push('/');
+    }
+    normalized
+}
+
+/// Decode the `%XX` escapes of a raw component, malformed UTF-8 being replaced
+pub fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escape = bytes.get(index + 1..index + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], escape) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn optional(text: &Option<String>) -> DexValue {
+    text.clone().map_or(DexValue::Null, DexValue::String)
+}
+
+fn field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> DexValue {
+    this.and_then(|id| interpreter.heap.get(&id))
+        .and_then(|object| object.fields.get(name).cloned())
+        .unwrap_or(DexValue::Null)
+}
+
+fn decoded_field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> DexValue {
+    match field(interpreter, this, name) {
+        DexValue::String(text) => DexValue::String(percent_decode(&text)),
+        value => value,
+    }
+}
+
+fn text_field(interpreter: &Interpreter, this: Option<ObjectId>, name: &str) -> Option<String> {
+    match field(interpreter, this, name) {
+        DexValue::String(text) => Some(text),
+        _ => None,
+    }
+}
+
+/// Components of the URI object `this`
+fn uri_of(interpreter: &Interpreter, this: Option<ObjectId>) -> Uri {
+    Uri {
+        scheme: text_field(interpreter, this, SCHEME_FIELD),
+        scheme_specific_part: text_field(interpreter, this, SCHEME_SPECIFIC_PART_FIELD).unwrap_or_default(),
+        authority: text_field(interpreter, this, AUTHORITY_FIELD),
+        user_info: text_field(interpreter, this, USER_INFO_FIELD),
+        host: text_field(interpreter, this, HOST_FIELD),
+        port: field(interpreter, this, PORT_FIELD).as_int().unwrap_or(-1),
+        path: text_field(interpreter, this, PATH_FIELD),
+        query: text_field(interpreter, this, QUERY_FIELD),
+        fragment: text_field(interpreter, this, FRAGMENT_FIELD),
+    }
+}
+
+/// Store `uri` in the URI object `id`, `text` being what it was parsed from
+fn store(interpreter: &mut Interpreter, id: ObjectId, uri: &Uri, text: String) {
+    let Some(object) = interpreter.heap.get_mut(&id) else { return };
+    let fields = [
+        (SCHEME_FIELD, optional(&uri.scheme)),
+        (SCHEME_SPECIFIC_PART_FIELD, DexValue::String(uri.scheme_specific_part.clone())),
+        (AUTHORITY_FIELD, optional(&uri.authority)),
+        (USER_INFO_FIELD, optional(&uri.user_info)),
+        (HOST_FIELD, optional(&uri.host)),
+        (PORT_FIELD, DexValue::Int(uri.port)),
+        (PATH_FIELD, optional(&uri.path)),
+        (QUERY_FIELD, optional(&uri.query)),
+        (FRAGMENT_FIELD, optional(&uri.fragment)),
+        (STRING_FIELD, DexValue::String(text)),
+    ];
+    for (name, value) in fields {
+        object.fields.insert(name.to_string(), value);
+    }
+}
+
+fn new_uri(interpreter: &mut Interpreter, uri: &Uri) -> DexValue {
+    let id = interpreter.alloc_object(URI);
+    store(interpreter, id, uri, uri.compose());
+    DexValue::Object(id)
+}
+
+/// Parse the string argument, throwing `exception` with the syntax error if it's malformed
+fn parse_arg(interpreter: &mut Interpreter, args: &[DexValue], exception: &str) -> Option<(Uri, String)> {
+    let Some(DexValue::String(text)) = args.first() else {
+        interpreter.throw_new(NULL_POINTER_EXCEPTION, "uri == null");
+        return None;
+    };
+    match Uri::parse(text) {
+        Ok(uri) => Some((uri, text.clone())),
+        Err(message) => {
+            interpreter.throw_new(exception, &message);
+            None
+        }
+    }
+}
+
+fn uri_init(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    if let (Some(id), Some((uri, text))) = (this, parse_arg(interpreter, &args, URI_SYNTAX_EXCEPTION)) {
+        store(interpreter, id, &uri, text);
+    }
+    DexValue::Void
+}
+
+/// `URI.create` throws the syntax error as an IllegalArgumentException
+fn uri_create(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let Some((uri, text)) = parse_arg(interpreter, &args, ILLEGAL_ARGUMENT_EXCEPTION) else {
+        return DexValue::Void;
+    };
+    let id = interpreter.alloc_object(URI);
+    store(interpreter, id, &uri, text);
+    DexValue::Object(id)
+}
+
+fn uri_resolve(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let reference = match args.first() {
+        Some(DexValue::Object(id)) => uri_of(interpreter, Some(*id)),
+        _ => match parse_arg(interpreter, &args, ILLEGAL_ARGUMENT_EXCEPTION) {
+            Some((uri, _)) => uri,
+            None => return DexValue::Void,
+        },
+    };
+    let resolved = uri_of(interpreter, this).resolve(&reference);
+    new_uri(interpreter, &resolved)
+}
+
+fn uri_normalize(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let mut uri = uri_of(interpreter, this);
+    let Some(path) = &uri.path else {
+        return this.map_or(DexValue::Null, DexValue::Object);
+    };
+    uri.path = Some(remove_dot_segments(path));
+    uri.update_scheme_specific_part();
+    new_uri(interpreter, &uri)
+}
+
+/// The text equality goes by, with the case-insensitive scheme and host lowercased
+fn comparable(uri: &Uri) -> String {
+    let mut uri = uri.clone();
+    uri.scheme = uri.scheme.map(|scheme| scheme.to_ascii_lowercase());
+    if let (Some(host), Some(authority)) = (&uri.host, &uri.authority) {
+        uri.authority = Some(authority.replacen(host.as_str(), &host.to_ascii_lowercase(), 1));
+    }
+    uri.compose()
+}
+
+fn uri_equals(interpreter: &mut Interpreter, this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let other = match args.first() {
+        Some(DexValue::Object(id)) if interpreter.heap.get(id).is_some_and(|object| object.class_name == URI) => Some(*id),
+        _ => return DexValue::Boolean(false),
+    };
+    DexValue::Boolean(comparable(&uri_of(interpreter, this)) == comparable(&uri_of(interpreter, other)))
+}
+
+fn uri_hash_code(interpreter: &mut Interpreter, this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let text = comparable(&uri_of(interpreter, this));
+    DexValue::Int(text.encode_utf16().fold(0i32, |hash, unit| hash.wrapping_mul(31).wrapping_add(unit as i32)))
+}
@@ -0,0 +1,142 @@
+// java.util.UUID, which extensions use for device and session ids sent with their requests.
+// randomUUID() draws from the OS unless the interpreter has a random seed, set by tests that
+// compare output between runs, in which case the same seed gives the same sequence of ids.
+
+use md5::{Digest, Md5};
+
+use crate::interpreter::exception::ILLEGAL_ARGUMENT_EXCEPTION;
+use crate::interpreter::interpreter::Interpreter;
+use crate::interpreter::native::{bytes_of, parameters, PlatformMethod};
+use crate::types::{DexValue, ObjectId};
+
+pub const UUID: &str = "Ljava/util/UUID;";
+
+const MOST_SIGNIFICANT_FIELD: &str = "mostSigBits";
+const LEAST_SIGNIFICANT_FIELD: &str = "leastSigBits";
+
+pub fn platform_method(class_name: &str, method_key: &str) -> Option<PlatformMethod> {
+    let method: PlatformMethod = match (class_name, method_key) {
+        (UUID, "randomUUID:()Ljava/util/UUID;") => random_uuid,
+        (UUID, "nameUUIDFromBytes:([B)Ljava/util/UUID;") => name_uuid_from_bytes,
+        (UUID, "fromString:(Ljava/lang/String;)Ljava/util/UUID;") => from_string,
+        (UUID, "<init>:(JJ)V") => |interpreter, this, args| {
+            let args = parameters(&args);
+            let most = args.first().and_then(|value| value.as_long()).unwrap_or(0);
+            let least = args.get(1).and_then(|value| value.as_long()).unwrap_or(0);
+            set_bits(interpreter, this, [most, least]);
+            DexValue::Void
+        },
+        (UUID, "toString:()Ljava/lang/String;") => |interpreter, this, _| {
+            DexValue::String(to_string(bits_of(interpreter, this)))
+        },
+        (UUID, "getMostSignificantBits:()J") => |interpreter, this, _| DexValue::Long(bits_of(interpreter, this)[0]),
+        (UUID, "getLeastSignificantBits:()J") => |interpreter, this, _| DexValue::Long(bits_of(interpreter, this)[1]),
+        (UUID, "version:()I") => |interpreter, this, _| DexValue::Int((bits_of(interpreter, this)[0] >> 12 & 0xf) as i32),
+        (UUID, "equals:(Ljava/lang/Object;)Z") => |interpreter, this, args| {
+            let other = match args.first() {
+                Some(DexValue::Object(id)) if interpreter.heap.get(id).is_some_and(|object| object.class_name == UUID) => Some(*id),
+                _ => None,
+            };
+            DexValue::Boolean(other.is_some() && bits_of(interpreter, this) == bits_of(interpreter, other))
+        },
+        (UUID, "hashCode:()I") => |interpreter, this, _| {
+            let [most, least] = bits_of(interpreter, this);
+            let bits = most ^ least;
+            DexValue::Int((bits >> 32) as i32 ^ bits as i32)
+        },
+        _ => return None,
+    };
+    Some(method)
+}
+
+fn bits_of(interpreter: &Interpreter, this: Option<ObjectId>) -> [i64; 2] {
+    let object = this.and_then(|id| interpreter.heap.get(&id));
+    let field = |name| object.and_then(|object| object.fields.get(name)).and_then(|value| value.as_long()).unwrap_or(0);
+    [field(MOST_SIGNIFICANT_FIELD), field(LEAST_SIGNIFICANT_FIELD)]
+}
+
+fn set_bits(interpreter: &mut Interpreter, this: Option<ObjectId>, [most, least]: [i64; 2]) {
+    if let Some(object) = this.and_then(|id| interpreter.heap.get_mut(&id)) {
+        object.fields.insert(MOST_SIGNIFICANT_FIELD.to_string(), DexValue::Long(most));
+        object.fields.insert(LEAST_SIGNIFICANT_FIELD.to_string(), DexValue::Long(least));
+    }
+}
+
+fn new_uuid(interpreter: &mut Interpreter, bits: [i64; 2]) -> DexValue {
+    let id = interpreter.alloc_object(UUID);
+    set_bits(interpreter, Some(id), bits);
+    DexValue::Object(id)
+}
+
+/// The UUID of 16 bytes with its version and the IETF variant stamped in
+fn from_bytes(mut bytes: [u8; 16], version: u8) -> [i64; 2] {
+    bytes[6] = bytes[6] & 0x0f | version << 4;
+    bytes[8] = bytes[8] & 0x3f | 0x80;
+    [
+        i64::from_be_bytes(bytes[..8].try_into().unwrap()),
+        i64::from_be_bytes(bytes[8..].try_into().unwrap()),
+    ]
+}
+
+/// `xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx`, lowercase
+fn to_string([most, least]: [i64; 2]) -> String {
+    let (most, least) = (most as u64, least as u64);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        most >> 32,
+        most >> 16 & 0xffff,
+        most & 0xffff,
+        least >> 48,
+        least & 0xffff_ffff_ffff,
+    )
+}
+
+fn random_uuid(interpreter: &mut Interpreter, _this: Option<ObjectId>, _args: Vec<DexValue>) -> DexValue {
+    let mut bytes = [0u8; 16];
+    match interpreter.random_seed.as_mut() {
+        Some(seed) => {
+            bytes[..8].copy_from_slice(&split_mix(seed).to_be_bytes());
+            bytes[8..].copy_from_slice(&split_mix(seed).to_be_bytes());
+        }
+        None => getrandom::getrandom(&mut bytes).expect("OS random source unavailable"),
+    }
+    new_uuid(interpreter, from_bytes(bytes, 4))
+}
+
+/// Advance `state` and return the next number of the SplitMix64 sequence
+fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ z >> 30).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ z >> 27).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ z >> 31
+}
+
+/// A version 3 UUID, from the MD5 of the name
+fn name_uuid_from_bytes(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let name = match args.first() {
+        Some(DexValue::Object(id)) => bytes_of(interpreter, Some(*id)),
+        _ => Vec::new(),
+    };
+    new_uuid(interpreter, from_bytes(Md5::digest(&name).into(), 3))
+}
+
+fn from_string(interpreter: &mut Interpreter, _this: Option<ObjectId>, args: Vec<DexValue>) -> DexValue {
+    let text = match args.first() {
+        Some(DexValue::String(text)) => text.clone(),
+        _ => String::new(),
+    };
+    let parts: Vec<Option<u64>> = text.split('-').map(|part| u64::from_str_radix(part, 16).ok().filter(|_| part.len() <= 16)).collect();
+    let [Some(time_low), Some(time_mid), Some(time_high), Some(clock), Some(node)] = parts[..] else {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &format!("Invalid UUID string: {}", text));
+        return DexValue::Void;
+    };
+    if text.len() > 36 {
+        interpreter.throw_new(ILLEGAL_ARGUMENT_EXCEPTION, &format!("UUID string too large: {}", text));
+        return DexValue::Void;
+    }
+
+    let most = (time_low & 0xffff_ffff) << 32 | (time_mid & 0xffff) << 16 | time_high & 0xffff;
+    let least = (clock & 0xffff) << 48 | node & 0xffff_ffff_ffff;
+    new_uuid(interpreter, [most as i64, least as i64])
+}
@@ -1,18 +1,40 @@
 // The rust entry point of the mihonx-runner jni bindings
 
 use jni::JNIEnv;
-use jni::objects::{GlobalRef, JByteArray, JClass, JObject, JString, JValue};
-use jni::sys::{jboolean, jstring};
+use jni::objects::{GlobalRef, JByteArray, JClass, JObject, JObjectArray, JString, JValue, JValueGen, JValueOwned};
+use jni::sys::{jboolean, jdouble, jint, jlong, jstring};
 use jni::JavaVM;
 use lazy_static::lazy_static;
 use std::collections::HashMap;
+use std::ffi::c_void;
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
+use std::time::Duration;
 
+use crate::parser::bytes::DexBytes;
+use crate::parser::error::ParseError;
+use crate::parser::filter::{class_filter, set_class_filter, ClassFilter};
 use crate::parser::parser::Parser;
-use crate::interpreter::interpreter::Interpreter;
-use crate::types::{DexValue, Object};
+use crate::interpreter::{clock, codec, cookies, date, http};
+use crate::interpreter::error::InterpreterError;
+use crate::interpreter::interpreter::{set_max_call_depth, Interpreter};
+use crate::types::{DexMethod, DexValue, Object, ObjectId};
 
+mod apk;
+mod cache;
+mod compatibility;
+mod dependencies;
+mod install;
+mod lifecycle;
+mod logging;
+pub mod model;
+mod pagination;
 mod parser;
+mod policy;
+mod repo;
+mod selftest;
+pub mod soak;
+mod stubs;
 mod utils;
 mod types;
 mod interpreter;
@@ -20,16 +42,77 @@ mod interpreter;
 lazy_static! {
     static ref JVM: Mutex<Option<JavaVM>> = Mutex::new(None);
     static ref INTERPRETER: Mutex<Option<Interpreter>> = Mutex::new(None);
+    /// Cache directory of the extension the interpreter runs, the cache root unless one of a
+    /// batch was picked with `rustUseInstalledExtension`
+    static ref EXTENSION_DIR: Mutex<PathBuf> = Mutex::new(PathBuf::from(cache::CACHE_ROOT));
 }
 
-/// Initialize interpreter if not already initialized
-fn get_or_init_interpreter() -> std::sync::MutexGuard<'static, Option<Interpreter>> {
+/// Initialize interpreter if not already initialized, or anew once another install replaced
+/// the cached extension or another one was picked, so their classes never mix with the ones
+/// already loaded. The guard holds an interpreter unless an error is returned.
+fn get_or_init_interpreter() -> Result<std::sync::MutexGuard<'static, Option<Interpreter>>, ParseError> {
     let mut guard = INTERPRETER.lock().unwrap();
+    let dir = EXTENSION_DIR.lock().unwrap().clone();
+    if guard.as_ref().is_some_and(|interpreter| interpreter.parser.cache_dir != dir || interpreter.parser.generation != utils::load_generation(&dir)) {
+        rust_log("Extension was reinstalled or replaced, reloading it.");
+        *guard = None;
+    }
     if guard.is_none() {
-        let parser = Parser::initialize_from_files();
+        let parser = Parser::initialize_from_dir(&dir)?;
         *guard = Some(Interpreter::new(parser));
     }
-    guard
+    Ok(guard)
+}
+
+/// `get_or_init_interpreter` for the JNI functions, `None` with an IOException thrown if the
+/// installed extension couldn't be loaded
+fn loaded_interpreter(env: &mut JNIEnv) -> Option<std::sync::MutexGuard<'static, Option<Interpreter>>> {
+    match get_or_init_interpreter() {
+        Ok(guard) => Some(guard),
+        Err(error) => {
+            rust_log(&format!("Loading the extension failed: {}", error));
+            let _ = env.throw_new("java/io/IOException", error.to_string());
+            None
+        }
+    }
+}
+
+/// Value of a JNI call made by the JNI functions, `None` with an exception thrown if it failed:
+/// the one the JVM has pending, or an IllegalArgumentException, e.g. for a null argument
+fn jni_value<T>(env: &mut JNIEnv, result: jni::errors::Result<T>) -> Option<T> {
+    match result {
+        Ok(value) => Some(value),
+        Err(error) => {
+            if !env.exception_check().unwrap_or(false) {
+                let _ = env.throw_new("java/lang/IllegalArgumentException", error.to_string());
+            }
+            None
+        }
+    }
+}
+
+/// Contents of the Java string `string`, see `jni_value`
+fn java_string(env: &mut JNIEnv, string: &JString) -> Option<String> {
+    let result = env.get_string(string).map(String::from);
+    jni_value(env, result)
+}
+
+/// The elements of the Java array `array`, see `jni_value`
+fn array_elements<'local>(env: &mut JNIEnv<'local>, array: &JObjectArray) -> Option<Vec<JObject<'local>>> {
+    let length = env.get_array_length(array);
+    let length = jni_value(env, length)?;
+    (0..length)
+        .map(|i| {
+            let element = env.get_object_array_element(array, i);
+            jni_value(env, element)
+        })
+        .collect()
+}
+
+/// Contents of the Java byte array `array`, see `jni_value`
+fn byte_array(env: &mut JNIEnv, array: &JByteArray) -> Option<Vec<u8>> {
+    let result = env.convert_byte_array(array);
+    jni_value(env, result)
 }
 
 pub fn call_method(
@@ -42,38 +125,102 @@ pub fn call_method(
     let vm = vm_guard.as_ref().unwrap(); // JavaVM
     let mut env = vm.attach_current_thread().expect("Failed to attach thread");
     
-    let ret_value = env.call_method(obj, method_name, signature, args);
+    match env.call_method(obj, method_name, signature, args) {
+        Ok(ret_value) => jvalue_to_dex(&mut env, ret_value),
+        Err(_) => DexValue::Null,
+    }
+}
 
-    if let Ok(ret_value) = ret_value {
-        match ret_value {
-            jni::objects::JValueGen::Bool(value) => {
-                return DexValue::Boolean(value != 0)
-            },
-            jni::objects::JValueGen::Int(value) => {
-                return DexValue::Boolean(value != 0)
-            }
-            jni::objects::JValueGen::Object(obj) => {
-                let string_class = env.find_class("java/lang/String").unwrap();
-                if !env.is_instance_of(&obj, string_class).unwrap() {
-                    return DexValue::Null
-                }
+/// `call_method` with interpreter values as the arguments of `signature`: primitives as they
+/// are, null and strings as objects. `None` without calling if an argument can't be passed,
+/// e.g. an object of the interpreter's heap, or the arguments don't match the parameters.
+pub fn call_method_with_args(
+    obj: &JObject,
+    method_name: &str,
+    signature: &str,
+    args: &[DexValue],
+) -> Option<DexValue> {
+    let vm_guard = JVM.lock().unwrap();
+    let vm = vm_guard.as_ref()?;
+    let mut env = vm.attach_current_thread().expect("Failed to attach thread");
 
-                // Cast JObject to JString
-                let jstring = obj.into();
+    let parameters = parameter_types(signature);
+    let args = interpreter::native::parameters(args);
+    if parameters.len() != args.len() {
+        return None;
+    }
+    let mut values: Vec<JValueOwned> = Vec::with_capacity(args.len());
+    for (parameter, arg) in parameters.into_iter().zip(&args) {
+        let value = match parameter {
+            "Z" => JValueOwned::Bool((arg.as_int()? != 0) as jboolean),
+            "B" => JValueOwned::Byte(arg.as_int()? as i8),
+            "S" => JValueOwned::Short(arg.as_int()? as i16),
+            "C" => JValueOwned::Char(arg.as_int()? as u16),
+            "I" => JValueOwned::Int(arg.as_int()?),
+            "J" => JValueOwned::Long(arg.as_long()?),
+            "F" => JValueOwned::Float(arg.as_float()?),
+            "D" => JValueOwned::Double(arg.as_double()?),
+            _ => match arg {
+                DexValue::Null => JValueOwned::Object(JObject::null()),
+                DexValue::String(text) if matches!(parameter, "Ljava/lang/String;" | "Ljava/lang/CharSequence;" | "Ljava/lang/Object;") => {
+                    JValueOwned::Object(env.new_string(text).ok()?.into())
+                }
+                _ => return None,
+            },
+        };
+        values.push(value);
+    }
+    let values: Vec<JValue> = values.iter().map(JValueOwned::borrow).collect();
 
-                // Get the Java String content and convert to Rust String
-                let rust_string = env.get_string(&jstring).unwrap().into();
+    match env.call_method(obj, method_name, signature, &values) {
+        Ok(ret_value) => Some(jvalue_to_dex(&mut env, ret_value)),
+        Err(_) => Some(DexValue::Null),
+    }
+}
 
-                return DexValue::String(rust_string);
-            }
-            _ => {
-                rust_log("unknown");
-                return DexValue::Null
+/// Descriptors of the parameters of the method descriptor `signature`, e.g. `I` and
+/// `[Ljava/lang/String;` of `(I[Ljava/lang/String;)V`
+fn parameter_types(signature: &str) -> Vec<&str> {
+    let parameters = signature.strip_prefix('(').and_then(|rest| rest.split_once(')')).map_or("", |(parameters, _)| parameters);
+    let mut types = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    while end < parameters.len() {
+        match parameters.as_bytes()[end] {
+            b'[' => {
+                end += 1;
+                continue;
             }
+            b'L' => end = parameters[end..].find(';').map_or(parameters.len(), |semicolon| end + semicolon + 1),
+            _ => end += 1,
         }
+        types.push(&parameters[start..end]);
+        start = end;
     }
+    types
+}
 
-    return DexValue::Null
+/// A value returned by the host, objects other than strings become null
+fn jvalue_to_dex(env: &mut JNIEnv, value: JValueOwned) -> DexValue {
+    match value {
+        JValueGen::Bool(value) => DexValue::Boolean(value != 0),
+        JValueGen::Int(value) => DexValue::Int(value),
+        JValueGen::Long(value) => DexValue::Long(value),
+        JValueGen::Double(value) => DexValue::Double(value),
+        JValueGen::Float(value) => DexValue::Float(value),
+        JValueGen::Short(value) => DexValue::Short(value),
+        JValueGen::Byte(value) => DexValue::Byte(value),
+        JValueGen::Char(value) => DexValue::Char(value),
+        JValueGen::Void => DexValue::Void,
+        JValueGen::Object(obj) => {
+            let string_class = env.find_class("java/lang/String").unwrap();
+            if !env.is_instance_of(&obj, string_class).unwrap() {
+                return DexValue::Null;
+            }
+            let jstring = obj.into();
+            env.get_string(&jstring).map(|string| DexValue::String(string.into())).unwrap_or(DexValue::Null)
+        }
+    }
 }
 
 pub fn has_method(obj: &JObject, sig: &str) -> bool {
@@ -98,14 +245,66 @@ pub fn has_method(obj: &JObject, sig: &str) -> bool {
     env.get_method_id(cls, name, &desc).is_ok()
 }
 
-/// Called once at startup from Kotlin's init block
+/// Called once at startup from Kotlin's init block. `install_policy` is the JSON of a
+/// `policy::InstallPolicy` restricting what installed extensions may use, null allows everything.
 #[unsafe(no_mangle)]
 pub extern "system" fn Java_mihonx_runner_RustBridge_nativeInit(
-    env: JNIEnv,
+    mut env: JNIEnv,
     _class: JClass,
+    install_policy: JString,
 ) {
     let vm = env.get_java_vm().expect("Failed to get JavaVM");
     *JVM.lock().unwrap() = Some(vm);
+    lifecycle::reset();
+
+    let install_policy = if install_policy.is_null() {
+        Ok(policy::InstallPolicy::default())
+    } else {
+        let Some(json) = java_string(&mut env, &install_policy) else {
+            return;
+        };
+        serde_json::from_str(&json)
+    };
+    match install_policy {
+        Ok(install_policy) => policy::set_install_policy(install_policy),
+        Err(error) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid install policy: {}", error));
+        }
+    }
+}
+
+/// Releases everything the library holds on to before the host unloads it: waits up to
+/// `timeout_ms` for running workers, drops the interpreter with its GlobalRefs and forgets
+/// the JavaVM. Returns false if some workers were still running when the timeout passed.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_nativeShutdown(
+    _env: JNIEnv,
+    _class: JClass,
+    timeout_ms: jlong,
+) -> jboolean {
+    shutdown(Duration::from_millis(timeout_ms.max(0) as u64)) as jboolean
+}
+
+/// The JVM unloads the library without the host calling nativeShutdown, e.g. on process exit
+#[unsafe(no_mangle)]
+pub extern "system" fn JNI_OnUnload(_vm: *mut jni::sys::JavaVM, _reserved: *mut c_void) {
+    shutdown(Duration::from_secs(1));
+}
+
+fn shutdown(timeout: Duration) -> bool {
+    // From here on log lines no longer attach threads to the JVM
+    let drained = lifecycle::begin_shutdown(timeout);
+    if !drained {
+        logging::log("Shutting down with workers still running");
+    }
+
+    // GlobalRefs are released while the JavaVM is still known
+    INTERPRETER.lock().unwrap().take();
+    cache::forget();
+    logging::flush();
+    JVM.lock().unwrap().take();
+
+    drained
 }
 
 #[unsafe(no_mangle)]
@@ -128,61 +327,205 @@ pub extern "system" fn Java_mihonx_runner_RustBridge_rustUseExtensionContext(
 
 #[unsafe(no_mangle)]
 pub extern "system" fn Java_mihonx_runner_RustBridge_rustGetDexVersion(
-    env: JNIEnv,
+    mut env: JNIEnv,
     _this: JObject
 ) -> jstring {
-    let mut guard = get_or_init_interpreter();
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
     let interpreter = guard.as_mut().unwrap();
 
     return env.new_string(format!("v{:?}", String::from_utf8(interpreter.parser.container.clone().unwrap().header_item.magic[4..6].into()))).unwrap().into_raw()
 }
 
+/// Installs an extension given as its APK or as a bare dex file. Throws an
+/// IllegalArgumentException if the APK can't be read, a DexFormatException if a dex file isn't
+/// one the parser can read and a SecurityException listing the violations if the install policy
+/// rejects it.
 #[unsafe(no_mangle)]
 pub extern "system" fn Java_mihonx_runner_RustBridge_rustInstallExtension(
-    env: JNIEnv,
+    mut env: JNIEnv,
     _this: JObject,
     bytes: JByteArray
 ) {
     rust_log("Installing extension.");
 
-    let array_len = env.get_array_length(&bytes).unwrap();
-    let mut rust_bytes = vec![0; array_len as usize];
-    env.get_byte_array_region(&bytes, 0, &mut rust_bytes)
-        .unwrap();
+    let Some(bytes) = byte_array(&mut env, &bytes) else {
+        return;
+    };
+    let parser = match apk::extension_parser(bytes, true) {
+        Ok(parser) => parser,
+        Err(error) => {
+            rust_log(&format!("Reading the extension failed: {}", error));
+            let _ = env.throw_new("java/lang/IllegalArgumentException", error.to_string());
+            return;
+        }
+    };
+    install_parsed(&mut env, parser);
+}
 
-    let mut parser = Parser::new(rust_bytes.into_iter().map(|x| x as u8).collect(), true);
-    parser.parse();
+/// Installs the extension at `path`, an APK or a bare dex file, like rustInstallExtension. The
+/// file is mapped rather than copied into memory, so it must not change until this returns.
+/// Throws an IOException if it can't be opened.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustInstallExtensionFile(
+    mut env: JNIEnv,
+    _this: JObject,
+    path: JString
+) {
+    let Some(path) = java_string(&mut env, &path) else {
+        return;
+    };
+    rust_log(&format!("Installing extension from {}.", path));
+
+    let parser = match DexBytes::map_file(&path).map(|bytes| apk::extension_parser(bytes, true)) {
+        Ok(Ok(parser)) => parser,
+        Ok(Err(error)) => {
+            rust_log(&format!("Reading the extension failed: {}", error));
+            let _ = env.throw_new("java/lang/IllegalArgumentException", error.to_string());
+            return;
+        }
+        Err(error) => {
+            rust_log(&format!("Opening {} failed: {}", path, error));
+            let _ = env.throw_new("java/io/IOException", error.to_string());
+            return;
+        }
+    };
+    install_parsed(&mut env, parser);
 }
 
+/// Installs an extension shipping several dex files, classes.dex first, like
+/// rustInstallExtension
 #[unsafe(no_mangle)]
-pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionGetName(
-    env: JNIEnv,
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustInstallMultiDexExtension(
+    mut env: JNIEnv,
     _this: JObject,
-    ctx: JObject,
+    dexes: JObjectArray
+) {
+    let Some(elements) = array_elements(&mut env, &dexes) else {
+        return;
+    };
+    rust_log(&format!("Installing extension with {} dex files.", elements.len()));
+
+    let mut dex_files = Vec::with_capacity(elements.len());
+    for bytes in elements {
+        let Some(bytes) = byte_array(&mut env, &JByteArray::from(bytes)) else {
+            return;
+        };
+        dex_files.push(bytes);
+    }
+
+    let parser = Parser::new_multi_dex(dex_files, true);
+    install_parsed(&mut env, parser);
+}
+
+/// Parse and persist an extension unless it couldn't be parsed, a DexFormatException is thrown
+/// then, or the install policy rejects it, a SecurityException listing the violations is thrown
+/// then
+fn install_parsed(env: &mut JNIEnv, mut parser: Parser) {
+    parser.persist = false;
+    parser.verify_integrity = true;
+    parser.class_filter = class_filter();
+    if let Err(error) = parser.parse() {
+        rust_log(&format!("Parsing the extension failed: {}", error));
+        let _ = env.throw_new("mihonx/runner/DexFormatException", error.to_string());
+        return;
+    }
+
+    let violations = policy::check_install(&parser);
+    if !violations.is_empty() {
+        let report = policy::rejection_report(&violations);
+        rust_log(&report);
+        let _ = env.throw_new("java/lang/SecurityException", report);
+        return;
+    }
+    if let Err(error) = parser.save() {
+        rust_log(&format!("Saving the extension failed: {}", error));
+        let _ = env.throw_new("java/io/IOException", error.to_string());
+        return;
+    }
+    *EXTENSION_DIR.lock().unwrap() = parser.cache_dir;
+}
+
+/// Installs a batch of extensions and returns a JSON array with one result per extension, see
+/// `install::InstallResult`. Each is cached apart from the others and the interpreter keeps
+/// running the extension it ran before until one is picked with rustUseInstalledExtension.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustInstallExtensions(
+    mut env: JNIEnv,
+    _this: JObject,
+    batch: JObjectArray
 ) -> jstring {
-    rust_log("Running init and getName");
+    let Some(elements) = array_elements(&mut env, &batch) else {
+        return JObject::null().into_raw();
+    };
+    rust_log(&format!("Installing {} extensions.", elements.len()));
 
-    let mut guard = get_or_init_interpreter();
-    let interpreter = guard.as_mut().unwrap();
+    let mut extensions = Vec::with_capacity(elements.len());
+    for bytes in elements {
+        let Some(bytes) = byte_array(&mut env, &JByteArray::from(bytes)) else {
+            return JObject::null().into_raw();
+        };
+        extensions.push(bytes);
+    }
 
-    let global_ctx = env.new_global_ref(ctx).unwrap();
-    interpreter.object_refs.push(global_ctx);
+    let results = install::install_extensions(extensions, Path::new(cache::CACHE_ROOT));
+    env.new_string(serde_json::to_string(&results).unwrap()).unwrap().into_raw()
+}
 
-    let main_idx = interpreter
-        .parser
-        .classes
-        .iter()
-        .position(|class| {
-            class
-                .super_class
-                .as_ref()
-                .map(|sc| sc.contains("Source"))
-                .unwrap_or(false)
-        })
-        .expect("No Main Class found.");
+/// Makes the extension of `source`, installed with rustInstallExtensions, the one the interpreter
+/// runs. `source` is the name of its main class as reported in the install results. Throws an
+/// IllegalArgumentException if no extension of that source was installed.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustUseInstalledExtension(
+    mut env: JNIEnv,
+    _this: JObject,
+    source: JString
+) {
+    let Some(source) = java_string(&mut env, &source) else {
+        return;
+    };
+    let dir = cache::extension_dir(Path::new(cache::CACHE_ROOT), &utils::class_descriptor(&source));
+    if !dir.join(utils::GENERATION_FILE).is_file() {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("No extension of {} was installed", source));
+        return;
+    }
+    rust_log(&format!("Using the installed extension of {}.", source));
+    *EXTENSION_DIR.lock().unwrap() = dir;
+}
 
-    if !interpreter.parser.classes[main_idx].methods.contains_key("<init>") {
-        panic!("No <init> method found for main class");
+/// Restricts the installs started from now on to the classes of `packages`, Java package names
+/// like `eu.kanade.tachiyomi.extension`, and their subpackages. Bundled library classes are then
+/// neither parsed nor persisted. An empty array parses every class again.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustSetClassFilter(
+    mut env: JNIEnv,
+    _this: JObject,
+    packages: JObjectArray
+) {
+    let Some(elements) = array_elements(&mut env, &packages) else {
+        return;
+    };
+    let mut names = Vec::with_capacity(elements.len());
+    for package in elements {
+        let Some(package) = java_string(&mut env, &JString::from(package)) else {
+            return;
+        };
+        names.push(package);
+    }
+
+    rust_log(&format!("Restricting installs to the packages {:?}.", names));
+    set_class_filter(ClassFilter::packages(&names));
+}
+
+/// Find the extension's main class, create its instance with an ExtensionContext the host
+/// implements and run its constructor. Returns the instance, which becomes `main_object`.
+pub(crate) fn init_extension(interpreter: &mut Interpreter) -> Result<ObjectId, InterpreterError> {
+    let main_idx = interpreter.parser.main_class().ok_or(InterpreterError::NoMainClass)?;
+
+    let main_class = &interpreter.parser.classes[main_idx];
+    if main_class.resolve_method_key("<init>", None).is_none() {
+        return Err(InterpreterError::NoMainConstructor { class_name: main_class.name.clone() });
     }
 
     interpreter.main_idx = main_idx;
@@ -191,6 +534,7 @@ pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionGetName(
         class_name: "mihonx.ExtensionContextImpl".to_string(),
         fields: HashMap::new(),
         methods: HashMap::new(),
+        array: None,
     };
 
     ctx.methods
@@ -200,13 +544,42 @@ pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionGetName(
     let args = vec![DexValue::Object(extension_context)];
 
     let class_name = interpreter.parser.classes[main_idx].name.clone();
-    interpreter.alloc_object(&class_name);
+    let extension = interpreter.alloc_object(&class_name);
+    interpreter.main_object = Some(extension);
+
+    interpreter.call_method(main_idx, "<init>", Some(extension), args)?;
+    Ok(extension)
+}
+
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionGetName(
+    mut env: JNIEnv,
+    _this: JObject,
+    ctx: JObject,
+) -> jstring {
+    rust_log("Running init and getName");
+
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
 
-    interpreter.call_method(main_idx, "<init>", args).unwrap();
-    let name = interpreter.call_method(main_idx, "getName", Vec::new()).unwrap();
+    let global_ctx = env.new_global_ref(ctx).unwrap();
+    interpreter.object_refs.push(global_ctx);
+
+    let name = init_extension(interpreter)
+        .and_then(|extension| interpreter.call_method(interpreter.main_idx, "getName", Some(extension), Vec::new()));
+
+    let name = match name {
+        Ok(name) => name,
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            return JObject::null().into_raw();
+        }
+    };
 
     match name {
-        DexValue::String(string) => {
+        Some(DexValue::String(string)) => {
             rust_log(&string);
             env.new_string(string).unwrap().into_raw()
         }
@@ -216,16 +589,24 @@ pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionGetName(
 
 #[unsafe(no_mangle)]
 pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionIsUserAgentEqual(
-    env: JNIEnv,
+    mut env: JNIEnv,
     _this: JObject
 ) -> jboolean {
-    let mut guard = get_or_init_interpreter();
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return false as jni::sys::jboolean;
+    };
     let interpreter = guard.as_mut().unwrap();
 
-    let isCorrectUserAgent= interpreter.call_method(interpreter.main_idx, "isCorrectUserAgent", Vec::new()).unwrap();
+    let isCorrectUserAgent = match interpreter.call_method(interpreter.main_idx, "isCorrectUserAgent", interpreter.main_object, Vec::new()) {
+        Ok(value) => value,
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            return false as jni::sys::jboolean;
+        }
+    };
 
     match isCorrectUserAgent {
-        DexValue::Boolean(value) => {
+        Some(DexValue::Boolean(value)) => {
             return value as jni::sys::jboolean;
         }
         _ => {
@@ -240,7 +621,9 @@ pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionCallMethod(
     _this: JObject,
     method_name: JString,
 ) {
-    let mut guard = get_or_init_interpreter();
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return;
+    };
     let interpreter = guard.as_mut().unwrap();
 
     let jstring_obj = JString::from(method_name);
@@ -251,12 +634,16 @@ pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionCallMethod(
     // 3. Convert JavaStr to Rust String
     let rust_string: String = java_str.unwrap().into();
 
-    let returnValue= interpreter.call_method(interpreter.main_idx, &rust_string, vec![]).unwrap();
-
-    match returnValue {
-        DexValue::Object(id) => {
-            println!("{:?}", interpreter.heap[&id])
+    let returnValue = match interpreter.call_method(interpreter.main_idx, &rust_string, interpreter.main_object, vec![]) {
+        Ok(value) => value,
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            return;
         }
+    };
+
+    match returnValue.as_ref().map(|value| codec::encode(interpreter, value)) {
+        Some(Ok(json)) => rust_log(&json.to_string()),
         _ => {
             let message = format!("{:?}", returnValue);
 
@@ -265,10 +652,802 @@ pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionCallMethod(
     }
 }
 
-/// Rust function you can call anywhere to log via Kotlin
+/// Calls a method of the extension's main instance with arguments given as a JSON array and
+/// returns its result as JSON. Objects go through the heap codec, so data classes such as
+/// SManga can be passed in and out by their fields.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionCallMethodJson(
+    mut env: JNIEnv,
+    _this: JObject,
+    method_name: JString,
+    args_json: JString,
+) -> jstring {
+    let Some(method_name) = java_string(&mut env, &method_name) else {
+        return JObject::null().into_raw();
+    };
+    let Some(args_json) = java_string(&mut env, &args_json) else {
+        return JObject::null().into_raw();
+    };
+
+    let args: Vec<serde_json::Value> = match serde_json::from_str(&args_json) {
+        Ok(args) => args,
+        Err(error) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid arguments: {}", error));
+            return JObject::null().into_raw();
+        }
+    };
+
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    match call_method_json(interpreter, &method_name, &args) {
+        Ok(json) => env.new_string(json.to_string()).unwrap().into_raw(),
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            JObject::null().into_raw()
+        }
+    }
+}
+
+fn call_method_json(interpreter: &mut Interpreter, method_name: &str, args: &[serde_json::Value]) -> Result<serde_json::Value, InterpreterError> {
+    let result = call_with_json_args(interpreter, method_name, args)?;
+    Ok(codec::encode(interpreter, &result.unwrap_or(DexValue::Null))?)
+}
+
+/// Calls a method of the extension's main instance with arguments given as a JSON array and
+/// returns whatever it returned as JSON, e.g. a list of SManga. Unlike
+/// `rustExtensionCallMethodJson` this never fails on the shape of the result: each object carries
+/// its heap id under `@id` and is written once, objects referenced again, shared or cyclic, are
+/// `{"@ref": id}`.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionCallMethodGraph(
+    mut env: JNIEnv,
+    _this: JObject,
+    method_name: JString,
+    args_json: JString,
+) -> jstring {
+    let Some(method_name) = java_string(&mut env, &method_name) else {
+        return JObject::null().into_raw();
+    };
+    let Some(args_json) = java_string(&mut env, &args_json) else {
+        return JObject::null().into_raw();
+    };
+
+    let args: Vec<serde_json::Value> = match serde_json::from_str(&args_json) {
+        Ok(args) => args,
+        Err(error) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid arguments: {}", error));
+            return JObject::null().into_raw();
+        }
+    };
+
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    match call_with_json_args(interpreter, &method_name, &args) {
+        Ok(result) => {
+            let json = codec::encode_graph(interpreter, &result.unwrap_or(DexValue::Null));
+            env.new_string(json.to_string()).unwrap().into_raw()
+        }
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            JObject::null().into_raw()
+        }
+    }
+}
+
+/// Calls a method of the extension's main instance with arguments decoded from JSON
+fn call_with_json_args(interpreter: &mut Interpreter, method_name: &str, args: &[serde_json::Value]) -> Result<Option<DexValue>, InterpreterError> {
+    // The declared parameter types decide how numbers are read and which class untagged objects get
+    let parameters = interpreter.parser.classes.get(interpreter.main_idx)
+        .and_then(|class| {
+            let method_key = class.resolve_method_key(method_name, Some(args.len()))?;
+            class.methods.get(&method_key)
+        })
+        .map(|method| method.parameters.clone())
+        .unwrap_or_default();
+
+    let args = args.iter()
+        .enumerate()
+        .map(|(i, arg)| codec::decode(interpreter, arg, parameters.get(i).map(String::as_str)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    interpreter.call_method(interpreter.main_idx, method_name, interpreter.main_object, args)
+}
+
+/// Calls a method of the extension's main instance with boxed arguments and returns its result
+/// boxed. `signature` is the method's descriptor, e.g. `(ILjava/lang/String;)Ljava/util/List;`,
+/// null picks the overload by the number of arguments. Arguments may be null, strings, boxed
+/// primitives and arrays of those. Results that are objects come back as the JSON of the heap
+/// codec, like `rustExtensionCallMethodJson` returns them.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionCallMethodWithArgs<'local>(
+    mut env: JNIEnv<'local>,
+    _this: JObject,
+    method_name: JString,
+    signature: JString,
+    args: JObjectArray,
+) -> JObject<'local> {
+    let Some(mut method_name) = java_string(&mut env, &method_name) else {
+        return JObject::null();
+    };
+    if !signature.is_null() {
+        let Some(signature) = java_string(&mut env, &signature) else {
+            return JObject::null();
+        };
+        method_name = types::method_key(&method_name, &signature);
+    }
+
+    let args = if args.is_null() {
+        Vec::new()
+    } else {
+        let Some(args) = array_elements(&mut env, &args) else {
+            return JObject::null();
+        };
+        args
+    };
+    let mut json_args = Vec::with_capacity(args.len());
+    for (i, arg) in args.iter().enumerate() {
+        match boxed_to_json(&mut env, arg) {
+            Ok(json) => json_args.push(json),
+            Err(reason) => {
+                // A JNI call that failed left its exception pending
+                if !env.exception_check().unwrap_or(false) {
+                    let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Argument {} is {}", i, reason));
+                }
+                return JObject::null();
+            }
+        }
+    }
+
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    let result = call_with_json_args(interpreter, &method_name, &json_args);
+
+    let value = match result {
+        Ok(value) => value.unwrap_or(DexValue::Void),
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            return JObject::null();
+        }
+    };
+    match dex_value_to_boxed(&mut env, interpreter, &value) {
+        Ok(object) => object,
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            JObject::null()
+        }
+    }
+}
+
+/// JSON of a boxed argument for the heap codec, why it can't be passed otherwise, e.g. the name
+/// of its class
+fn boxed_to_json(env: &mut JNIEnv, object: &JObject) -> Result<serde_json::Value, String> {
+    if object.is_null() {
+        return Ok(serde_json::Value::Null);
+    }
+    let is = |env: &mut JNIEnv, class_name: &str| env.is_instance_of(object, class_name).unwrap_or(false);
+    let failed = |error: jni::errors::Error| format!("unreadable: {}", error);
+
+    if is(env, "java/lang/String") {
+        let string = JString::from(env.new_local_ref(object).map_err(failed)?);
+        let text: String = env.get_string(&string).map_err(failed)?.into();
+        return Ok(serde_json::Value::String(text));
+    }
+    if is(env, "java/lang/Boolean") {
+        return Ok(serde_json::Value::Bool(env.call_method(object, "booleanValue", "()Z", &[]).and_then(|value| value.z()).map_err(failed)?));
+    }
+    if is(env, "java/lang/Character") {
+        let unit = env.call_method(object, "charValue", "()C", &[]).and_then(|value| value.c()).map_err(failed)?;
+        return Ok(serde_json::Value::String(String::from_utf16_lossy(&[unit])));
+    }
+    if is(env, "java/lang/Double") || is(env, "java/lang/Float") {
+        let value = env.call_method(object, "doubleValue", "()D", &[]).and_then(|value| value.d()).map_err(failed)?;
+        return Ok(serde_json::json!(value));
+    }
+    if is(env, "java/lang/Number") {
+        let value = env.call_method(object, "longValue", "()J", &[]).and_then(|value| value.j()).map_err(failed)?;
+        return Ok(serde_json::json!(value));
+    }
+    if is(env, "[B") {
+        let array = JByteArray::from(env.new_local_ref(object).map_err(failed)?);
+        let bytes = env.convert_byte_array(array).map_err(failed)?;
+        return Ok(serde_json::json!(bytes.into_iter().map(|byte| byte as i8).collect::<Vec<_>>()));
+    }
+    if is(env, "[Ljava/lang/Object;") {
+        let array = JObjectArray::from(env.new_local_ref(object).map_err(failed)?);
+        let length = env.get_array_length(&array).map_err(failed)?;
+        let mut elements = Vec::with_capacity(length as usize);
+        for i in 0..length {
+            let element = env.get_object_array_element(&array, i).map_err(failed)?;
+            elements.push(boxed_to_json(env, &element)?);
+        }
+        return Ok(serde_json::Value::Array(elements));
+    }
+
+    let class = env.get_object_class(object).map_err(failed)?;
+    let name = env.call_method(&class, "getName", "()Ljava/lang/String;", &[]).and_then(|name| name.l()).map_err(failed)?;
+    let name = env.get_string(&JString::from(name)).map(String::from).unwrap_or_default();
+    Err(format!("a {}, which can't be passed", name))
+}
+
+/// `value` boxed for the host: null, a String, a boxed primitive, or the JSON of the heap codec
+/// for objects and arrays
+fn dex_value_to_boxed<'local>(env: &mut JNIEnv<'local>, interpreter: &Interpreter, value: &DexValue) -> Result<JObject<'local>, InterpreterError> {
+    let box_value = |env: &mut JNIEnv<'local>, class_name: &str, descriptor: &str, value: JValue| {
+        env.call_static_method(class_name, "valueOf", format!("({})L{};", descriptor, class_name), &[value])
+            .and_then(|boxed| boxed.l())
+            .unwrap()
+    };
+    let object = match value {
+        DexValue::Null | DexValue::Void => JObject::null(),
+        DexValue::String(text) => env.new_string(text).unwrap().into(),
+        DexValue::Boolean(value) => box_value(env, "java/lang/Boolean", "Z", JValue::Bool(*value as jboolean)),
+        DexValue::Byte(value) => box_value(env, "java/lang/Byte", "B", JValue::Byte(*value)),
+        DexValue::Short(value) => box_value(env, "java/lang/Short", "S", JValue::Short(*value)),
+        DexValue::Char(value) => box_value(env, "java/lang/Character", "C", JValue::Char(*value)),
+        DexValue::Int(value) => box_value(env, "java/lang/Integer", "I", JValue::Int(*value)),
+        DexValue::Long(value) => box_value(env, "java/lang/Long", "J", JValue::Long(*value)),
+        DexValue::Float(value) => box_value(env, "java/lang/Float", "F", JValue::Float(*value)),
+        DexValue::Double(value) => box_value(env, "java/lang/Double", "D", JValue::Double(*value)),
+        _ => env.new_string(codec::encode(interpreter, value)?.to_string()).unwrap().into(),
+    };
+    Ok(object)
+}
+
+/// Runs a method of the extension's main instance without arguments
+fn call_main_method(method_name: &str) -> Result<Option<DexValue>, InterpreterError> {
+    let mut guard = get_or_init_interpreter().map_err(InterpreterError::Load)?;
+    let interpreter = guard.as_mut().unwrap();
+    interpreter.call_method(interpreter.main_idx, method_name, interpreter.main_object, Vec::new())
+}
+
+/// Calls a method of the main class returning a long (or a narrower integer), e.g. `versionId`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionCallLongMethod(
+    mut env: JNIEnv,
+    _this: JObject,
+    method_name: JString,
+) -> jlong {
+    let Some(method_name) = java_string(&mut env, &method_name) else {
+        return 0;
+    };
+    let value = match call_main_method(&method_name) {
+        Ok(value) => value,
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            return 0;
+        }
+    };
+
+    match value.as_ref().and_then(|value| value.as_long().or_else(|| value.as_int().map(i64::from))) {
+        Some(value) => value,
+        None => {
+            let _ = env.throw_new("java/lang/IllegalStateException", format!("Expected a long but got {:?}", value));
+            0
+        }
+    }
+}
+
+/// Calls a method of the main class returning a double (or a float)
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionCallDoubleMethod(
+    mut env: JNIEnv,
+    _this: JObject,
+    method_name: JString,
+) -> jdouble {
+    let Some(method_name) = java_string(&mut env, &method_name) else {
+        return 0.0;
+    };
+    let value = match call_main_method(&method_name) {
+        Ok(value) => value,
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            return 0.0;
+        }
+    };
+
+    match value.as_ref().and_then(|value| value.as_double().or_else(|| value.as_float().map(f64::from))) {
+        Some(value) => value,
+        None => {
+            let _ = env.throw_new("java/lang/IllegalStateException", format!("Expected a double but got {:?}", value));
+            0.0
+        }
+    }
+}
+
+/// Calls a paged listing method (e.g. `getPopularManga`) for up to `limit` pages starting at
+/// `start_page` and returns the pages as a JSON array, ending early once a page has no next page
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionFetchPages(
+    mut env: JNIEnv,
+    _this: JObject,
+    method_name: JString,
+    start_page: jint,
+    limit: jint,
+) -> jstring {
+    let Some(method_name) = java_string(&mut env, &method_name) else {
+        return JObject::null().into_raw();
+    };
+
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    match pagination::fetch_pages(interpreter, &method_name, start_page, limit) {
+        Ok(pages) => env.new_string(serde_json::to_string(&pages).unwrap()).unwrap().into_raw(),
+        Err(error) => {
+            throw_interpreter_error(&mut env, &error);
+            JObject::null().into_raw()
+        }
+    }
+}
+
+/// Returns the readable signatures of the extension's main class as a JSON array
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionListMethods(
+    mut env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    let mut methods: Vec<&DexMethod> = interpreter.parser.classes[interpreter.main_idx].methods.values().collect();
+    methods.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let signatures: Vec<String> = methods.iter().map(|method| method.signature()).collect();
+    env.new_string(serde_json::to_string(&signatures).unwrap()).unwrap().into_raw()
+}
+
+/// Returns the hiddenapi flags of the extension's members and the references to restricted ones
+/// as a JSON object, see `policy::HiddenApiReport`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionHiddenApiReport(
+    mut env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    let report = policy::hidden_api_report(&interpreter.parser);
+    env.new_string(serde_json::to_string(&report).unwrap()).unwrap().into_raw()
+}
+
+/// Returns what each class of the extension references and the classes it expects the runner
+/// to provide as a JSON object, see `dependencies::DependencyGraph`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionDependencyGraph(
+    mut env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    let graph = dependencies::DependencyGraph::build(&interpreter.parser);
+    env.new_string(serde_json::to_string(&graph).unwrap()).unwrap().into_raw()
+}
+
+/// Decodes the code of `class_name`, the extension's main class if null, and of every class it
+/// reaches ahead of running it. Returns the number of classes.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionPreloadClosure(
+    mut env: JNIEnv,
+    _this: JObject,
+    class_name: JString,
+) -> jint {
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return 0;
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    let class_name = if class_name.is_null() {
+        interpreter.parser.classes[interpreter.main_idx].name.clone()
+    } else {
+        let Some(class_name) = java_string(&mut env, &class_name) else {
+            return 0;
+        };
+        utils::class_descriptor(&class_name)
+    };
+    let closure = dependencies::preload_closure(&mut interpreter.parser, &class_name);
+    rust_log(&format!("Preloaded {} classes reached from {}.", closure.len(), class_name));
+    closure.len() as jint
+}
+
+/// Returns what the extension uses that the interpreter doesn't support as a JSON object, see
+/// `compatibility::UnsupportedReport`. Hosts check it before running the extension.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionUnsupportedReport(
+    mut env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    let report = compatibility::UnsupportedReport::scan(&interpreter.parser);
+    env.new_string(serde_json::to_string(&report).unwrap()).unwrap().into_raw()
+}
+
+/// Returns the methods the extension calls on classes it doesn't define that no shim provides
+/// as a JSON object, see `compatibility::MissingShimReport`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionMissingShims(
+    mut env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    let report = compatibility::MissingShimReport::scan(&interpreter.parser);
+    env.new_string(serde_json::to_string(&report).unwrap()).unwrap().into_raw()
+}
+
+/// Returns the headers and strings of the extension's dex files and every class with its decoded
+/// methods as one versioned JSON document, see `parser::export::DexExport`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionExportJson(
+    mut env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    env.new_string(interpreter.parser.to_json()).unwrap().into_raw()
+}
+
+/// Returns Kotlin stubs of the extension's classes, signatures without bodies, as a JSON array
+/// of `{path, source}` objects, see `stubs::kotlin_stubs`
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustExtensionKotlinStubs(
+    mut env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let Some(mut guard) = loaded_interpreter(&mut env) else {
+        return JObject::null().into_raw();
+    };
+    let interpreter = guard.as_mut().unwrap();
+
+    let stubs = stubs::kotlin_stubs(&interpreter.parser);
+    env.new_string(serde_json::to_string(&stubs).unwrap()).unwrap().into_raw()
+}
+
+/// Raises an uncaught interpreter error as a RuntimeException on the calling Java thread
+fn throw_interpreter_error(env: &mut JNIEnv, error: &InterpreterError) {
+    rust_log(&error.to_string());
+    let _ = env.throw_new("java/lang/RuntimeException", error.to_string());
+}
+
+/// Compares a repo's index.min.json with the installed extensions and returns the available
+/// updates as a JSON array. `fingerprint` is the repo's signing key fingerprint and may be null.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustCheckExtensionUpdates(
+    mut env: JNIEnv,
+    _this: JObject,
+    index: JString,
+    installed: JString,
+    fingerprint: JString,
+) -> jstring {
+    let Some(index) = java_string(&mut env, &index) else {
+        return JObject::null().into_raw();
+    };
+    let Some(installed) = java_string(&mut env, &installed) else {
+        return JObject::null().into_raw();
+    };
+    let fingerprint: Option<String> = if fingerprint.is_null() {
+        None
+    } else {
+        let Some(fingerprint) = java_string(&mut env, &fingerprint) else {
+            return JObject::null().into_raw();
+        };
+        Some(fingerprint)
+    };
+
+    match repo::check_updates(&index, &installed, fingerprint.as_deref()) {
+        Ok(updates) => env.new_string(updates).unwrap().into_raw(),
+        Err(error) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", error.to_string());
+            JObject::null().into_raw()
+        }
+    }
+}
+
+/// Runs the startup self test and returns its report as a JSON string
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustRunSelfTest(
+    mut env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let mut report = selftest::run_self_test(Path::new(cache::CACHE_ROOT));
+    let vm_registered = JVM.lock().unwrap().is_some();
+    report.push(selftest::check_jni(&mut env, vm_registered));
+    let report = serde_json::to_string(&report).unwrap();
+    env.new_string(report).unwrap().into_raw()
+}
+
+/// Sets the maximum size of the on-disk cache in bytes. A negative value removes the limit.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustSetMaxCacheSize(
+    _env: JNIEnv,
+    _this: JObject,
+    max_size: jlong,
+) {
+    let max_size = if max_size < 0 { None } else { Some(max_size as u64) };
+    cache::set_max_size(max_size);
+}
+
+/// Selects where log output goes: `stdout`, `logcat`, `callback` (RustBridge.logFromRust) or
+/// `file`, which writes to `path` and keeps `max_files` rotated files of up to `max_size` bytes
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustSetLogSink(
+    mut env: JNIEnv,
+    _this: JObject,
+    sink: JString,
+    path: JString,
+    max_size: jlong,
+    max_files: jint,
+) {
+    let Some(sink) = java_string(&mut env, &sink) else {
+        return;
+    };
+    let path: Option<String> = if path.is_null() {
+        None
+    } else {
+        let Some(path) = java_string(&mut env, &path) else {
+            return;
+        };
+        Some(path)
+    };
+
+    match logging::LogSink::from_name(&sink, path, max_size.max(0) as u64, max_files.max(0) as u32) {
+        Some(sink) => logging::set_sink(sink),
+        None => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unknown log sink {}", sink));
+        }
+    }
+}
+
+/// Sets the locale (language tag) and time zone id the date shims use for `source`, given as a
+/// class name or descriptor. A null `source` stands for the loaded extension.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustSetSourceDateContext(
+    mut env: JNIEnv,
+    _this: JObject,
+    source: JString,
+    locale: JString,
+    time_zone: JString,
+) {
+    let Some(locale) = java_string(&mut env, &locale) else {
+        return;
+    };
+    let Some(time_zone) = java_string(&mut env, &time_zone) else {
+        return;
+    };
+    if date::time_zone_offset(&time_zone).is_none() {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unknown time zone {}", time_zone));
+        return;
+    }
+
+    let source: String = if source.is_null() {
+        match get_or_init_interpreter().ok().and_then(|guard| guard.as_ref()?.source_name()) {
+            Some(source) => source,
+            None => {
+                let _ = env.throw_new("java/lang/IllegalStateException", "No extension loaded");
+                return;
+            }
+        }
+    } else {
+        let Some(source) = java_string(&mut env, &source) else {
+            return;
+        };
+        source
+    };
+
+    date::set_source_context(&source, date::DateContext { locale, time_zone });
+}
+
+/// Grants or revokes a capability, e.g. `scratchStorage`, for `source`, given as a class name or
+/// descriptor. A null `source` stands for the loaded extension.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustSetSourceCapability(
+    mut env: JNIEnv,
+    _this: JObject,
+    source: JString,
+    capability: JString,
+    granted: jboolean,
+) {
+    let Some(name) = java_string(&mut env, &capability) else {
+        return;
+    };
+    let Some(capability) = policy::Capability::from_name(&name) else {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Unknown capability {}", name));
+        return;
+    };
+
+    let source: String = if source.is_null() {
+        match get_or_init_interpreter().ok().and_then(|guard| guard.as_ref()?.source_name()) {
+            Some(source) => source,
+            None => {
+                let _ = env.throw_new("java/lang/IllegalStateException", "No extension loaded");
+                return;
+            }
+        }
+    } else {
+        let Some(source) = java_string(&mut env, &source) else {
+            return;
+        };
+        source
+    };
+
+    policy::set_granted(&source, capability, granted != 0);
+}
+
+/// Returns the cookies `source` holds as a JSON array, those sent with a request to `url` or all
+/// of them for a null `url`. A null `source` stands for the loaded extension.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustGetCookies(
+    mut env: JNIEnv,
+    _this: JObject,
+    source: JString,
+    url: JString,
+) -> jstring {
+    let source: String = if source.is_null() {
+        match get_or_init_interpreter().ok().and_then(|guard| guard.as_ref()?.source_name()) {
+            Some(source) => source,
+            None => {
+                let _ = env.throw_new("java/lang/IllegalStateException", "No extension loaded");
+                return JObject::null().into_raw();
+            }
+        }
+    } else {
+        let Some(source) = java_string(&mut env, &source) else {
+            return JObject::null().into_raw();
+        };
+        source
+    };
+
+    let cookies = if url.is_null() {
+        cookies::list(&source, None)
+    } else {
+        let Some(url) = java_string(&mut env, &url) else {
+            return JObject::null().into_raw();
+        };
+        let Some((host, path, https)) = http::url_parts(&url) else {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid url {}", url));
+            return JObject::null().into_raw();
+        };
+        cookies::load(&source, &host, &path, https)
+    };
+    env.new_string(serde_json::to_string(&cookies).unwrap()).unwrap().into_raw()
+}
+
+/// Removes the cookies of `source` for the host of `url` and its subdomains, or all of them for a
+/// null `url`. A null `source` stands for the loaded extension. Returns how many were removed.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustClearCookies(
+    mut env: JNIEnv,
+    _this: JObject,
+    source: JString,
+    url: JString,
+) -> jint {
+    let source: String = if source.is_null() {
+        match get_or_init_interpreter().ok().and_then(|guard| guard.as_ref()?.source_name()) {
+            Some(source) => source,
+            None => {
+                let _ = env.throw_new("java/lang/IllegalStateException", "No extension loaded");
+                return 0;
+            }
+        }
+    } else {
+        let Some(source) = java_string(&mut env, &source) else {
+            return 0;
+        };
+        source
+    };
+
+    if url.is_null() {
+        return cookies::clear(&source, None) as jint;
+    }
+    let Some(url) = java_string(&mut env, &url) else {
+        return 0;
+    };
+    match http::url_parts(&url) {
+        Some((host, _, _)) => cookies::clear(&source, Some(&host)) as jint,
+        None => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Invalid url {}", url));
+            0
+        }
+    }
+}
+
+/// Sets how many frames extension code may stack up before a StackOverflowError is thrown
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustSetMaxCallDepth(
+    mut env: JNIEnv,
+    _this: JObject,
+    depth: jint,
+) {
+    if depth < 1 {
+        let _ = env.throw_new("java/lang/IllegalArgumentException", format!("Call depth must be positive, got {}", depth));
+        return;
+    }
+    set_max_call_depth(depth as usize);
+}
+
+/// Fixes the time extensions see at `millis` since the epoch, so tests get the same output on
+/// every run. A negative value lets the clock run with the host's again.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustSetFixedClock(
+    _env: JNIEnv,
+    _this: JObject,
+    millis: jlong,
+) {
+    clock::set_fixed(if millis < 0 { None } else { Some(millis) });
+}
+
+/// Moves a fixed clock forward by `millis`, nothing happens while it runs with the host's
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustAdvanceClock(
+    _env: JNIEnv,
+    _this: JObject,
+    millis: jlong,
+) {
+    clock::advance(Duration::from_millis(millis.max(0) as u64));
+}
+
+/// Returns the current cache usage per category as a JSON string
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustGetCacheUsage(
+    env: JNIEnv,
+    _this: JObject,
+) -> jstring {
+    let usage = serde_json::to_string(&cache::usage()).unwrap();
+    env.new_string(usage).unwrap().into_raw()
+}
+
+/// Evicts least recently used cache entries until the cache fits into `target_size` bytes.
+/// Returns the amount of bytes freed.
+#[unsafe(no_mangle)]
+pub extern "system" fn Java_mihonx_runner_RustBridge_rustTrimCache(
+    _env: JNIEnv,
+    _this: JObject,
+    target_size: jlong,
+) -> jlong {
+    cache::trim(target_size.max(0) as u64) as jlong
+}
+
+/// Rust function you can call anywhere to log, the output goes to the configured log sink
 pub fn rust_log(msg: &str) {
-    let vm_mutex = JVM.lock();
-    let vm = vm_mutex.as_ref().unwrap().as_ref().unwrap();
+    logging::log(msg);
+}
+
+/// Hand a log line to `RustBridge.logFromRust`, returns false if the JVM isn't known yet
+pub(crate) fn log_to_jvm(msg: &str) -> bool {
+    if lifecycle::is_shutting_down() {
+        return false;
+    }
+    let vm_mutex = JVM.lock().unwrap();
+    let Some(vm) = vm_mutex.as_ref() else {
+        return false;
+    };
     let mut env = vm.attach_current_thread().expect("attach failed");
 
     let jmsg = env.new_string(msg).expect("Couldn't create java string");
@@ -287,4 +1466,18 @@ pub fn rust_log(msg: &str) {
         "(Ljava/lang/String;)V",
         &[arg],
     );
-}
\ No newline at end of file
+    true
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex, self_test_cases};
+
+    /// An extension without a Source is refused with an error rather than a panic, which would
+    /// abort the host when unwinding out of a JNI function
+    #[test]
+    fn refuses_extensions_without_a_main_class() {
+        let mut interpreter = Interpreter::new(parse_micro_dex(&self_test_cases()));
+        assert_eq!(init_extension(&mut interpreter), Err(InterpreterError::NoMainClass));
+    }
+}
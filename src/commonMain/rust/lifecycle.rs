@@ -0,0 +1,59 @@
+// Keeps track of the native worker threads so the library can be torn down while extensions are
+// still being installed. Workers register for as long as they run, shutdown stops new ones from
+// starting and waits for the running ones to finish and detach from the JVM.
+
+use lazy_static::lazy_static;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Condvar, Mutex};
+use std::time::Duration;
+
+lazy_static! {
+    /// Number of running workers, signalled whenever one finishes
+    static ref WORKERS: (Mutex<usize>, Condvar) = (Mutex::new(0), Condvar::new());
+}
+
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Held by a worker thread while it runs
+pub struct WorkerGuard {
+    _private: (),
+}
+
+impl Drop for WorkerGuard {
+    fn drop(&mut self) {
+        let (count, finished) = &*WORKERS;
+        *count.lock().unwrap() -= 1;
+        finished.notify_all();
+    }
+}
+
+/// Register the calling thread as a worker, `None` once shutdown has started
+pub fn register_worker() -> Option<WorkerGuard> {
+    let (count, _) = &*WORKERS;
+    let mut count = count.lock().unwrap();
+    // Checked under the lock so no worker slips in after draining started
+    if is_shutting_down() {
+        return None;
+    }
+    *count += 1;
+    Some(WorkerGuard { _private: () })
+}
+
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+/// Refuse new workers and wait up to `timeout` for the running ones, returns whether all finished
+pub fn begin_shutdown(timeout: Duration) -> bool {
+    let (count, finished) = &*WORKERS;
+    let count = count.lock().unwrap();
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+
+    let (count, _) = finished.wait_timeout_while(count, timeout, |count| *count > 0).unwrap();
+    *count == 0
+}
+
+/// Allow workers again, the host initialized the library anew
+pub fn reset() {
+    SHUTTING_DOWN.store(false, Ordering::SeqCst);
+}
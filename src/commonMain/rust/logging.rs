@@ -0,0 +1,109 @@
+// Logging facade. Everything the runner prints goes through `log`, which forwards it to the
+// configured sink: stdout, logcat, the JVM host or a rotating log file.
+
+use lazy_static::lazy_static;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+lazy_static! {
+    static ref SINK: Mutex<LogSink> = Mutex::new(LogSink::Callback);
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LogSink {
+    Stdout,
+    /// Android's logcat, stdout on other platforms
+    Logcat,
+    /// `RustBridge.logFromRust` on the host, stdout until the JVM is known
+    Callback,
+    /// Appends to `path`, rotating it to `path.1` .. `path.<max_files>` once it grows past `max_size` bytes
+    File { path: PathBuf, max_size: u64, max_files: u32 },
+}
+
+impl LogSink {
+    /// Sink from its host-facing name, `path`, `max_size` and `max_files` only matter for `file`
+    pub fn from_name(name: &str, path: Option<String>, max_size: u64, max_files: u32) -> Option<Self> {
+        match name {
+            "stdout" => Some(LogSink::Stdout),
+            "logcat" => Some(LogSink::Logcat),
+            "callback" => Some(LogSink::Callback),
+            "file" => path.map(|path| LogSink::File { path: PathBuf::from(path), max_size, max_files }),
+            _ => None,
+        }
+    }
+}
+
+pub fn set_sink(sink: LogSink) {
+    *SINK.lock().unwrap() = sink;
+}
+
+pub fn log(message: &str) {
+    let sink = SINK.lock().unwrap().clone();
+
+    match sink {
+        LogSink::Stdout => println!("{}", message),
+        LogSink::Logcat => log_to_logcat(message),
+        LogSink::Callback => {
+            if !crate::log_to_jvm(message) {
+                println!("{}", message);
+            }
+        }
+        LogSink::File { path, max_size, max_files } => {
+            if log_to_file(&path, max_size, max_files, message).is_err() {
+                println!("{}", message);
+            }
+        }
+    }
+}
+
+/// Push out anything still buffered, file sinks write every line through directly
+pub fn flush() {
+    let _ = std::io::stdout().flush();
+}
+
+#[cfg(target_os = "android")]
+fn log_to_logcat(message: &str) {
+    use std::ffi::{c_char, c_int, CString};
+
+    #[link(name = "log")]
+    unsafe extern "C" {
+        fn __android_log_write(prio: c_int, tag: *const c_char, text: *const c_char) -> c_int;
+    }
+
+    const ANDROID_LOG_DEBUG: c_int = 3;
+    let tag = CString::new("mihonx-runner").unwrap();
+    let text = CString::new(message.replace('\0', "")).unwrap();
+    unsafe {
+        __android_log_write(ANDROID_LOG_DEBUG, tag.as_ptr(), text.as_ptr());
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+fn log_to_logcat(message: &str) {
+    println!("{}", message);
+}
+
+fn log_to_file(path: &PathBuf, max_size: u64, max_files: u32, message: &str) -> std::io::Result<()> {
+    if fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0) >= max_size {
+        rotate(path, max_files)?;
+    }
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", message)
+}
+
+/// Shift `path.N` to `path.N+1`, dropping the oldest, and move `path` to `path.1`
+fn rotate(path: &PathBuf, max_files: u32) -> std::io::Result<()> {
+    let rotated = |index: u32| PathBuf::from(format!("{}.{}", path.display(), index));
+
+    if max_files == 0 {
+        return fs::remove_file(path);
+    }
+    let _ = fs::remove_file(rotated(max_files));
+    for index in (1..max_files).rev() {
+        let _ = fs::rename(rotated(index), rotated(index + 1));
+    }
+    fs::rename(path, rotated(1))
+}
@@ -0,0 +1,179 @@
+// Read-only traversal of a parsed extension for tools such as linters, stats or search. The
+// index operands of instructions come resolved, so tools never look at the dex tables, the
+// parser's internals or the layout of the class cache.
+
+use crate::parser::parser::Parser;
+
+pub use crate::parser::error::ParseError;
+pub use crate::types::{AccessFlags, DexAnnotation, DexClass, DexField, DexMethod, DexValue, Instruction, LocalVariable};
+
+/// What the index operand of an instruction points to
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+    String(String),
+    /// Type descriptor, e.g. `Ljava/lang/String;` or `[I`
+    Type(String),
+    Field { class_name: String, name: String, ty: String },
+    Method { class_name: String, name: String, descriptor: String },
+    /// invoke-custom call site, `method_type` is its descriptor
+    CallSite { method_name: String, method_type: String },
+}
+
+/// An instruction together with where it sits
+pub struct InstructionSite<'a> {
+    pub class: &'a DexClass,
+    pub method: &'a DexMethod,
+    /// Position of the instruction in `method.instructions`
+    pub pc: usize,
+    pub instruction: &'a Instruction,
+    /// Resolved index operand, `None` for instructions without one
+    pub reference: Option<Reference>,
+}
+
+pub struct Model {
+    parser: Parser,
+}
+
+impl Model {
+    /// Parse dex bytes, nothing is written to the cache
+    pub fn parse(bytes: Vec<u8>) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(bytes, false);
+        parser.persist = false;
+        parser.parse()?;
+        Ok(Self { parser })
+    }
+
+    /// The installed extension, as the interpreter loads it
+    pub fn installed() -> Result<Self, ParseError> {
+        let mut parser = Parser::initialize_from_files()?;
+        parser.debug_enabled = false;
+        Ok(Self { parser })
+    }
+
+    pub fn classes(&self) -> &[DexClass] {
+        &self.parser.classes
+    }
+
+    /// Problems the parser recovered from, e.g. malformed strings
+    pub fn warnings(&self) -> Vec<String> {
+        self.parser.errors.iter().map(|error| error.to_string()).collect()
+    }
+
+    /// Visit every class in dex order
+    pub fn for_each_class(&self, visit: impl FnMut(&DexClass)) {
+        self.parser.classes.iter().for_each(visit);
+    }
+
+    /// Visit every method, classes in dex order and their methods by key
+    pub fn for_each_method(&self, mut visit: impl FnMut(&DexClass, &DexMethod)) {
+        for class in &self.parser.classes {
+            let mut methods: Vec<(&String, &DexMethod)> = class.methods.iter().collect();
+            methods.sort_by_key(|(key, _)| *key);
+            for (_, method) in methods {
+                visit(class, method);
+            }
+        }
+    }
+
+    /// Visit every instruction of every method, in the order of `for_each_method`. Methods whose
+    /// code can't be decoded are skipped.
+    pub fn for_each_instruction(&self, mut visit: impl FnMut(InstructionSite)) {
+        self.for_each_method(|class, method| {
+            let Ok(instructions) = self.parser.instructions(class, method) else {
+                return;
+            };
+            for (pc, instruction) in instructions.iter().enumerate() {
+                visit(InstructionSite {
+                    class,
+                    method,
+                    pc,
+                    instruction,
+                    reference: self.resolve(class, instruction),
+                });
+            }
+        });
+    }
+
+    /// The whole extension as one versioned JSON document, for diffing two versions of it
+    pub fn to_json(&self) -> String {
+        self.parser.to_json()
+    }
+
+    /// Resolve the index operand of `instruction`, one of the instructions of `class`
+    pub fn resolve(&self, class: &DexClass, instruction: &Instruction) -> Option<Reference> {
+        resolve_reference(&self.parser, class.dex, instruction)
+    }
+}
+
+/// Resolve the index operand of `instruction` through the tables of dex file `dex` of `parser`
+pub(crate) fn resolve_reference(parser: &Parser, dex: usize, instruction: &Instruction) -> Option<Reference> {
+    let tables = parser.tables(dex)?;
+    // Range forms carry the same operand as their regular forms
+    let expanded = instruction.expand_range();
+
+    match expanded.as_ref().unwrap_or(instruction) {
+        Instruction::ConstString { string_idx, .. } => tables.strings.get(*string_idx as usize).map(|string| Reference::String(string.to_string())),
+        Instruction::ConstStringJumbo { string_idx, .. } => tables.strings.get(*string_idx as usize).map(|string| Reference::String(string.to_string())),
+
+        Instruction::ConstClass { type_idx, .. }
+        | Instruction::InstanceOf { type_idx, .. }
+        | Instruction::CheckCast { type_idx, .. }
+        | Instruction::NewInstance { type_idx, .. }
+        | Instruction::NewArray { type_idx, .. }
+        | Instruction::FilledNewArray { type_idx, .. } => tables.resolve_type(*type_idx as usize).map(|ty| Reference::Type(ty.to_string())),
+
+        Instruction::SGet { static_field_idx: field_idx, .. }
+        | Instruction::SGetWide { static_field_idx: field_idx, .. }
+        | Instruction::SGetObject { static_field_idx: field_idx, .. }
+        | Instruction::SGetBoolean { static_field_idx: field_idx, .. }
+        | Instruction::SGetByte { static_field_idx: field_idx, .. }
+        | Instruction::SGetChar { static_field_idx: field_idx, .. }
+        | Instruction::SGetShort { static_field_idx: field_idx, .. }
+        | Instruction::SPut { static_field_idx: field_idx, .. }
+        | Instruction::SPutWide { static_field_idx: field_idx, .. }
+        | Instruction::SPutObject { static_field_idx: field_idx, .. }
+        | Instruction::SPutBoolean { static_field_idx: field_idx, .. }
+        | Instruction::SPutByte { static_field_idx: field_idx, .. }
+        | Instruction::SPutChar { static_field_idx: field_idx, .. }
+        | Instruction::SPutShort { static_field_idx: field_idx, .. }
+        | Instruction::IGet { instance_field_idx: field_idx, .. }
+        | Instruction::IGetWide { instance_field_idx: field_idx, .. }
+        | Instruction::IGetObject { instance_field_idx: field_idx, .. }
+        | Instruction::IGetBoolean { instance_field_idx: field_idx, .. }
+        | Instruction::IGetByte { instance_field_idx: field_idx, .. }
+        | Instruction::IGetChar { instance_field_idx: field_idx, .. }
+        | Instruction::IGetShort { instance_field_idx: field_idx, .. }
+        | Instruction::IPut { instance_field_idx: field_idx, .. }
+        | Instruction::IPutWide { instance_field_idx: field_idx, .. }
+        | Instruction::IPutObject { instance_field_idx: field_idx, .. }
+        | Instruction::IPutBoolean { instance_field_idx: field_idx, .. }
+        | Instruction::IPutByte { instance_field_idx: field_idx, .. }
+        | Instruction::IPutChar { instance_field_idx: field_idx, .. }
+        | Instruction::IPutShort { instance_field_idx: field_idx, .. } => {
+            let (class_name, name) = tables.resolve_field(*field_idx)?;
+            let ty = tables.resolve_field_type(*field_idx)?;
+            Some(Reference::Field { class_name: class_name.to_string(), name: name.to_string(), ty: ty.to_string() })
+        }
+
+        Instruction::InvokeVirtual { method_idx, .. }
+        | Instruction::InvokeSuper { method_idx, .. }
+        | Instruction::InvokeDirect { method_idx, .. }
+        | Instruction::InvokeStatic { method_idx, .. }
+        | Instruction::InvokeInterface { method_idx, .. }
+        | Instruction::InvokePolymorphic { method_idx, .. } => {
+            let (class_name, name) = tables.resolve_method(*method_idx)?;
+            let descriptor = tables.resolve_method_descriptor(*method_idx)?;
+            Some(Reference::Method { class_name: class_name.to_string(), name: name.to_string(), descriptor })
+        }
+
+        Instruction::InvokeCustom { call_site_idx, .. } => {
+            let call_site = tables.container.call_site(*call_site_idx as usize)?;
+            Some(Reference::CallSite {
+                method_name: call_site.method_name.clone(),
+                method_type: call_site.method_type.clone(),
+            })
+        }
+
+        _ => None,
+    }
+}
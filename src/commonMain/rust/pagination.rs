@@ -0,0 +1,61 @@
+// Walks a source's paged listings (popular, latest, search) page by page, so the host can
+// prefetch several pages of a MangasPage-returning method in one call.
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::interpreter::codec;
+use crate::interpreter::error::InterpreterError;
+use crate::interpreter::interpreter::Interpreter;
+use crate::types::DexValue;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchedPage {
+    pub page: i32,
+    pub has_next_page: bool,
+    /// Every manga on the page, encoded by the heap codec
+    pub mangas: Vec<Value>,
+}
+
+/// Call `method_name(page)` on the extension's main instance for up to `limit` pages starting at
+/// `start_page`, stopping early at the first page without a next page
+pub fn fetch_pages(interpreter: &mut Interpreter, method_name: &str, start_page: i32, limit: i32) -> Result<Vec<FetchedPage>, InterpreterError> {
+    let mut pages = Vec::new();
+
+    for page in start_page..start_page.saturating_add(limit.max(0)) {
+        let value = interpreter.call_method(interpreter.main_idx, method_name, interpreter.main_object, vec![DexValue::Int(page)])?;
+        let fetched = read_mangas_page(interpreter, page, value)?;
+
+        let has_next_page = fetched.has_next_page;
+        pages.push(fetched);
+        if !has_next_page {
+            break;
+        }
+    }
+    Ok(pages)
+}
+
+/// Read a MangasPage through its getters, some sources compute hasNextPage lazily in a subclass
+fn read_mangas_page(interpreter: &mut Interpreter, page: i32, value: Option<DexValue>) -> Result<FetchedPage, InterpreterError> {
+    let Some(DexValue::Object(id)) = value else {
+        return Ok(FetchedPage { page, has_next_page: false, mangas: Vec::new() });
+    };
+
+    let has_next_page = interpreter.call_virtual(id, "getHasNextPage", Vec::new())?
+        .and_then(|value| value.as_int())
+        .is_some_and(|value| value != 0);
+
+    let mangas = match interpreter.call_virtual(id, "getMangas", Vec::new())? {
+        Some(DexValue::Object(list)) => interpreter.heap.get(&list)
+            .and_then(|list| list.array.clone())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    };
+
+    let mangas = mangas.iter()
+        .map(|manga| codec::encode(interpreter, manga))
+        .collect::<Result<_, _>>()?;
+
+    Ok(FetchedPage { page, has_next_page, mangas })
+}
@@ -0,0 +1,65 @@
+// Dex files are read from one shared buffer. The data section, the further dex files of a 041
+// container and the stored dex files of an APK are views into the buffer they were found in
+// rather than copies of it, and a file can be mapped instead of read, so installing a large
+// extension doesn't hold its bytes several times over.
+
+use memmap2::Mmap;
+use std::fmt;
+use std::fs::File;
+use std::ops::{Deref, Range};
+use std::path::Path;
+use std::sync::Arc;
+
+/// A range of a shared buffer, either owned or a mapped file. Cloning and slicing share the
+/// buffer, it is freed, or unmapped, with the last view into it.
+#[derive(Clone)]
+pub struct DexBytes {
+    buffer: Arc<dyn AsRef<[u8]> + Send + Sync>,
+    range: Range<usize>,
+}
+
+impl DexBytes {
+    /// Map the file at `path`. The file must not change while it is mapped, a file truncated
+    /// underneath the mapping faults on the next read of the lost pages.
+    pub fn map_file(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        // Safety: see above, the runner itself never writes the files it maps
+        let map = unsafe { Mmap::map(&file)? };
+        let len = map.len();
+        Ok(Self { buffer: Arc::new(map), range: 0..len })
+    }
+
+    /// The bytes of `range` of this view, sharing the buffer. Panics like slicing if `range`
+    /// isn't within the view.
+    pub fn slice(&self, range: Range<usize>) -> Self {
+        let _ = &self[range.clone()];
+        Self { buffer: self.buffer.clone(), range: self.range.start + range.start..self.range.start + range.end }
+    }
+}
+
+impl Deref for DexBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &(*self.buffer).as_ref()[self.range.clone()]
+    }
+}
+
+impl From<Vec<u8>> for DexBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        let len = bytes.len();
+        Self { buffer: Arc::new(bytes), range: 0..len }
+    }
+}
+
+impl Default for DexBytes {
+    fn default() -> Self {
+        Vec::new().into()
+    }
+}
+
+impl fmt::Debug for DexBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DexBytes({} bytes)", self.len())
+    }
+}
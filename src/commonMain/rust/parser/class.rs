@@ -1,117 +1,112 @@
-use super::uleb::read_uleb128;
+use super::opcodes::{instruction_format, Format};
+use super::endian::Endian;
+use super::error::ParseError;
+use super::uleb::{checked_sleb128, checked_uleb128, checked_uleb128p1, read_sleb128, read_uleb128};
 use crate::{
     parser::strings::parse_string_at_offset, types::{
-        Class_Def_Item, CodeItem, DexClass, DexContainer, DexField, DexMethod, DexValue,
-        Header_Item, Instruction, NO_INDEX,
-    }, utils::{parse_i16, parse_i32, parse_u16, parse_u32, parse_u64, get_lower_bits}
+        AccessFlags, CatchHandler, Class_Def_Item, CodeItem, DexAnnotation, DexClass, DexContainer, DexField, DexMethod, DexValue,
+        Header_Item, HiddenApiFlags, Instruction, LocalVariable, Payload, TryBlock, TryItem, NO_INDEX,
+    },
 };
-use std::{collections::HashMap, string};
+use std::collections::HashMap;
 
-fn parse_encoded_array(
+pub fn parse_encoded_array(
     data: &[u8],
     offset: usize,
     container: &DexContainer,
-) -> (Vec<DexValue>, usize) {
-    let mut cursor = (offset as usize)
-        .checked_sub(container.header_item.data_off as usize)
-        .expect("String offset is before data section");
-    let (size, c) = read_uleb128(data, cursor);
+) -> Result<(Vec<DexValue>, usize), ParseError> {
+    let mut cursor = data_cursor(data, offset as u32, "encoded_array_item", container)?;
+    let (size, c) = checked_uleb128(data, cursor).ok_or(ParseError::MalformedLeb128 { item: "encoded_array_item" })?;
     cursor = c;
+    check_count(data, cursor, size, 1, "encoded_array_item")?;
 
     let mut values = Vec::new();
     for _ in 0..size {
-        let (val, new_cursor) = parse_encoded_value(data, cursor, container);
+        let (val, new_cursor) = parse_encoded_value(data, cursor, container, 0)?;
         values.push(val);
         cursor = new_cursor;
     }
-    (values, cursor)
+    Ok((values, cursor))
+}
+
+/// Deepest nesting of arrays and annotations in an encoded_value. d8 never nests more than a few
+/// levels, a deeper value is hostile and would run out of stack.
+const MAX_VALUE_DEPTH: usize = 32;
+
+/// Error unless `count` items of at least `item_size` bytes each fit in `data` after `cursor`,
+/// so a forged count can't drive a huge allocation or a loop long past the end of the data
+fn check_count(data: &[u8], cursor: usize, count: u32, item_size: usize, item: &'static str) -> Result<(), ParseError> {
+    if (count as usize).saturating_mul(item_size) > data.len().saturating_sub(cursor) {
+        return Err(ParseError::UnexpectedEnd { item });
+    }
+    Ok(())
 }
 
-fn parse_encoded_value(data: &[u8], offset: usize, container: &DexContainer) -> (DexValue, usize) {
+/// The `size` little-endian bytes of an encoded_value at `cursor`, zero-extended to `N` bytes.
+/// Bytes past `N` are dropped, like the high bytes of an index too wide for its table.
+fn value_bytes<const N: usize>(data: &[u8], cursor: usize, size: usize) -> Result<[u8; N], ParseError> {
+    let bytes = data.get(cursor..cursor + size).ok_or(ParseError::UnexpectedEnd { item: "encoded_value" })?;
+    let mut buf = [0u8; N];
+    let len = size.min(N);
+    buf[..len].copy_from_slice(&bytes[..len]);
+    Ok(buf)
+}
+
+fn parse_encoded_value(data: &[u8], offset: usize, container: &DexContainer, depth: usize) -> Result<(DexValue, usize), ParseError> {
     let mut cursor = offset;
-    let byte = data[cursor];
+    let byte = *data.get(cursor).ok_or(ParseError::UnexpectedEnd { item: "encoded_value" })?;
     cursor += 1;
 
     let val_type = byte & 0x1f; // lower 5 bits
     let val_arg = (byte >> 5) & 0x07; // upper 3 bits
     let size = (val_arg as usize) + 1; // actual byte length
 
-    // println!("val_type = 0x{:x}, val_arg = 0x{:x}, size = {}", val_type, val_arg, size);
-
-    match val_type {
+    let value = match val_type {
         0x00 => {
             // VALUE_BYTE
-            let v = data[cursor] as i8 as i32;
+            let v = value_bytes::<1>(data, cursor, 1)?[0] as i8 as i32;
             cursor += 1;
-            (DexValue::Int(v), cursor)
+            DexValue::Int(v)
         }
         0x02 => {
             // VALUE_SHORT
-            let mut buf = [0u8; 2];
-            for i in 0..size {
-                buf[i] = data[cursor + i];
-            }
-            let v = i16::from_le_bytes(buf) as i32;
+            let v = i16::from_le_bytes(value_bytes(data, cursor, size)?) as i32;
             cursor += size;
-            (DexValue::Int(v), cursor)
+            DexValue::Int(v)
         }
         0x03 => {
             // VALUE_CHAR
-            let mut buf = [0u8; 2];
-            for i in 0..size {
-                // println!("size = {} i = {}", size, i);
-                buf[i] = data[cursor + i];
-            }
-            let v = u16::from_le_bytes(buf);
+            let v = u16::from_le_bytes(value_bytes(data, cursor, size)?);
             cursor += size;
-            (DexValue::Char(v), cursor)
+            DexValue::Char(v)
         }
         0x04 => {
             // VALUE_INT
-            let mut buf = [0u8; 4];
-            for i in 0..size {
-                buf[i] = data[cursor + i];
-            }
-            let v = i32::from_le_bytes(buf);
+            let v = i32::from_le_bytes(value_bytes(data, cursor, size)?);
             cursor += size;
-            (DexValue::Int(v), cursor)
+            DexValue::Int(v)
         }
         0x06 => {
             // VALUE_LONG
-            let mut buf = [0u8; 8];
-            for i in 0..size {
-                buf[i] = data[cursor + i];
-            }
-            let v = i64::from_le_bytes(buf);
+            let v = i64::from_le_bytes(value_bytes(data, cursor, size)?);
             cursor += size;
-            (DexValue::Long(v), cursor)
+            DexValue::Long(v)
         }
         0x10 => {
             // VALUE_FLOAT
-            let mut buf = [0u8; 4];
-            for i in 0..size {
-                buf[i] = data[cursor + i];
-            }
-            let v = f32::from_le_bytes(buf);
+            let v = f32::from_le_bytes(value_bytes(data, cursor, size)?);
             cursor += size;
-            (DexValue::Float(v), cursor)
+            DexValue::Float(v)
         }
         0x11 => {
             // VALUE_DOUBLE
-            let mut buf = [0u8; 8];
-            for i in 0..size {
-                buf[i] = data[cursor + i];
-            }
-            let v = f64::from_le_bytes(buf);
+            let v = f64::from_le_bytes(value_bytes(data, cursor, size)?);
             cursor += size;
-            (DexValue::Double(v), cursor)
+            DexValue::Double(v)
         }
         0x17 => {
             // VALUE_STRING
-            let mut val = 0u32;
-            for i in 0..size {
-                val |= (data[cursor + i] as u32) << (8 * i);
-            }
+            let val = u32::from_le_bytes(value_bytes(data, cursor, size)?);
             cursor += size;
             let (_, s) = container
                 .string_offset(val as usize)
@@ -119,14 +114,11 @@ fn parse_encoded_value(data: &[u8], offset: usize, container: &DexContainer) ->
                     super::strings::parse_string_at_offset(data, off, &container.header_item, 0)
                 })
                 .unwrap_or_else(|| (val as usize, "<unknown>".to_string()));
-            (DexValue::String(s), cursor)
+            DexValue::String(s)
         }
         0x18 => {
             // VALUE_TYPE
-            let mut val = 0u32;
-            for i in 0..size {
-                val |= (data[cursor + i] as u32) << (8 * i);
-            }
+            let val = u32::from_le_bytes(value_bytes(data, cursor, size)?);
             cursor += size;
             let (_, s) = container
                 .type_to_string_offset(val as usize)
@@ -134,47 +126,249 @@ fn parse_encoded_value(data: &[u8], offset: usize, container: &DexContainer) ->
                     super::strings::parse_string_at_offset(data, off, &container.header_item, 0)
                 })
                 .unwrap_or_else(|| (val as usize, "<unknown>".to_string()));
-            (DexValue::Type(s), cursor)
+            DexValue::Type(s)
+        }
+        0x1e => DexValue::Null, // VALUE_NULL
+        0x1f => DexValue::Boolean(val_arg != 0), // VALUE_BOOLEAN duplicate
+        0x15 => {
+            // VALUE_METHOD_TYPE
+            let val = u32::from_le_bytes(value_bytes(data, cursor, size)?);
+            cursor += size;
+            let descriptor = proto_descriptor(data, val as usize, container).unwrap_or_else(|| "<unknown>".to_string());
+            DexValue::MethodType(descriptor)
         }
-        0x1e => (DexValue::Null, cursor), // VALUE_NULL
-        0x1f => (DexValue::Boolean(val_arg != 0), cursor), // VALUE_BOOLEAN duplicate
-        _ => (DexValue::Null, cursor),    // fallback for unhandled types
+        0x16 => {
+            // VALUE_METHOD_HANDLE
+            let val = u32::from_le_bytes(value_bytes(data, cursor, size)?);
+            cursor += size;
+            DexValue::MethodHandle(val)
+        }
+        0x19..=0x1b => {
+            // VALUE_FIELD, VALUE_METHOD and VALUE_ENUM, whose constant is a field of the enum
+            let val = u32::from_le_bytes(value_bytes(data, cursor, size)?);
+            cursor += size;
+            let name_idx = if val_type == 0x1a {
+                container.method_id_items.get(val as usize).map(|method_id| method_id.name_idx)
+            } else {
+                container.field_id_items.get(val as usize).map(|field_id| field_id.name_idx)
+            };
+            let name = name_idx
+                .and_then(|name_idx| container.string_offset(name_idx as usize))
+                .map(|off| parse_string_at_offset(data, off, &container.header_item, 0).1)
+                .unwrap_or_else(|| "<unknown>".to_string());
+            match val_type {
+                0x19 => DexValue::Field(name),
+                0x1a => DexValue::Method(name),
+                _ => DexValue::Enum(name),
+            }
+        }
+        0x1c | 0x1d if depth >= MAX_VALUE_DEPTH => return Err(ParseError::ValueTooDeep { depth }),
+        0x1c => {
+            // VALUE_ARRAY, an encoded_array right after the header byte
+            let (size, c) = checked_uleb128(data, cursor).ok_or(ParseError::MalformedLeb128 { item: "encoded_array" })?;
+            cursor = c;
+            check_count(data, cursor, size, 1, "encoded_array")?;
+            let mut values = Vec::with_capacity(size as usize);
+            for _ in 0..size {
+                let (value, c) = parse_encoded_value(data, cursor, container, depth + 1)?;
+                values.push(value);
+                cursor = c;
+            }
+            DexValue::Array(values)
+        }
+        0x1d => {
+            // VALUE_ANNOTATION, nested annotations keep their elements only
+            let (_, elements, c) = parse_encoded_annotation(data, cursor, container, depth + 1)?;
+            cursor = c;
+            DexValue::Annotation(elements)
+        }
+        _ => DexValue::Null,    // fallback for unhandled types
+    };
+    Ok((value, cursor))
+}
+
+/// (name, value) elements of an encoded_annotation
+type AnnotationElements = Vec<(String, DexValue)>;
+
+/// Parse an encoded_annotation at `offset` into its type descriptor and (name, value) elements
+fn parse_encoded_annotation(
+    data: &[u8],
+    offset: usize,
+    container: &DexContainer,
+    depth: usize,
+) -> Result<(String, AnnotationElements, usize), ParseError> {
+    let malformed = ParseError::MalformedLeb128 { item: "encoded_annotation" };
+    let (type_idx, mut cursor) = checked_uleb128(data, offset).ok_or(malformed.clone())?;
+    let (size, c) = checked_uleb128(data, cursor).ok_or(malformed.clone())?;
+    cursor = c;
+    // Each element is at least a name_idx and a value header byte
+    check_count(data, cursor, size, 2, "encoded_annotation")?;
+    let ty = container
+        .type_to_string_offset(type_idx as usize)
+        .map(|off| parse_string_at_offset(data, off, &container.header_item, 0).1)
+        .unwrap_or_else(|| "<unknown>".to_string());
+
+    let mut elements = Vec::with_capacity(size as usize);
+    for _ in 0..size {
+        let (name_idx, c) = checked_uleb128(data, cursor).ok_or(malformed.clone())?;
+        let name = container
+            .string_offset(name_idx as usize)
+            .map(|off| parse_string_at_offset(data, off, &container.header_item, 0).1)
+            .unwrap_or_else(|| "<unknown>".to_string());
+        let (value, c) = parse_encoded_value(data, c, container, depth)?;
+        cursor = c;
+        elements.push((name, value));
     }
+    Ok((ty, elements, cursor))
 }
 
-pub fn parse_code_item(data: &[u8], offset: usize) -> CodeItem {
+/// Position in `data` of the `item` at file offset `offset`, which has to be in the data section
+fn data_cursor(data: &[u8], offset: u32, item: &'static str, container: &DexContainer) -> Result<usize, ParseError> {
+    (offset as usize)
+        .checked_sub(container.header_item.data_off as usize)
+        .filter(|cursor| *cursor < data.len())
+        .ok_or(ParseError::OffsetOutOfBounds { item, offset })
+}
+
+/// Position in `data` of the item at file offset `offset`, `None` for 0, which means no item
+fn data_position(offset: u32, container: &DexContainer) -> Option<usize> {
+    (offset != 0).then(|| (offset as usize).checked_sub(container.header_item.data_off as usize)).flatten()
+}
+
+/// Parse the annotation_set_item at file offset `offset`
+fn parse_annotation_set(data: &[u8], offset: u32, container: &DexContainer) -> Result<Vec<DexAnnotation>, ParseError> {
+    let Some(cursor) = data_position(offset, container) else {
+        return Ok(Vec::new());
+    };
+    let endian = container.header_item.endian();
+    let size = endian.read_u32(data, cursor);
+    check_count(data, cursor + 4, size, 4, "annotation_set_item")?;
+    (0..size as usize)
+        .filter_map(|i| data_position(endian.read_u32(data, cursor + 4 + i * 4), container))
+        .map(|item| {
+            let visibility = *data.get(item).ok_or(ParseError::UnexpectedEnd { item: "annotation_item" })?;
+            let (ty, elements, _) = parse_encoded_annotation(data, item + 1, container, 0)?;
+            Ok(DexAnnotation { ty, visibility, elements })
+        })
+        .collect()
+}
+
+/// Annotations of a class and its members, from its annotations_directory_item
+#[derive(Default)]
+struct AnnotationsDirectory {
+    class: Vec<DexAnnotation>,
+    /// By field_idx
+    fields: HashMap<u32, Vec<DexAnnotation>>,
+    /// By method_idx
+    methods: HashMap<u32, Vec<DexAnnotation>>,
+    /// Annotations of each parameter, by method_idx
+    parameters: HashMap<u32, Vec<Vec<DexAnnotation>>>,
+}
+
+fn parse_annotations_directory(data: &[u8], offset: u32, container: &DexContainer) -> Result<AnnotationsDirectory, ParseError> {
+    let Some(mut cursor) = data_position(offset, container) else {
+        return Ok(AnnotationsDirectory::default());
+    };
+    let endian = container.header_item.endian();
+    let class_annotations_off = endian.read_u32(data, cursor);
+    let fields_size = endian.read_u32(data, cursor + 4);
+    let methods_size = endian.read_u32(data, cursor + 8);
+    let parameters_size = endian.read_u32(data, cursor + 12);
+    cursor += 16;
+    let pairs_size = fields_size as u64 + methods_size as u64 + parameters_size as u64;
+    check_count(data, cursor, pairs_size.try_into().unwrap_or(u32::MAX), 8, "annotations_directory_item")?;
+
+    // field_annotation, method_annotation and parameter_annotation items are (idx, offset) pairs
+    let mut pairs = |count: u32| {
+        let pairs: Vec<(u32, u32)> = (0..count as usize)
+            .map(|i| (endian.read_u32(data, cursor + i * 8), endian.read_u32(data, cursor + i * 8 + 4)))
+            .collect();
+        cursor += count as usize * 8;
+        pairs
+    };
+    let fields = pairs(fields_size);
+    let methods = pairs(methods_size);
+    let parameters = pairs(parameters_size);
+
+    Ok(AnnotationsDirectory {
+        class: parse_annotation_set(data, class_annotations_off, container)?,
+        fields: fields.into_iter()
+            .map(|(field_idx, off)| Ok((field_idx, parse_annotation_set(data, off, container)?)))
+            .collect::<Result<_, ParseError>>()?,
+        methods: methods.into_iter()
+            .map(|(method_idx, off)| Ok((method_idx, parse_annotation_set(data, off, container)?)))
+            .collect::<Result<_, ParseError>>()?,
+        // An annotation_set_ref_list, with an annotation set per parameter
+        parameters: parameters.into_iter()
+            .filter_map(|(method_idx, off)| Some((method_idx, data_position(off, container)?)))
+            .map(|(method_idx, list)| {
+                let size = endian.read_u32(data, list);
+                check_count(data, list + 4, size, 4, "annotation_set_ref_list")?;
+                let sets = (0..size as usize)
+                    .map(|i| parse_annotation_set(data, endian.read_u32(data, list + 4 + i * 4), container))
+                    .collect::<Result<_, ParseError>>()?;
+                Ok((method_idx, sets))
+            })
+            .collect::<Result<_, ParseError>>()?,
+    })
+}
+
+pub fn parse_code_item(data: &[u8], offset: usize, endian: Endian) -> Result<CodeItem, ParseError> {
     let mut cursor = offset;
+    check_count(data, cursor, 1, 16, "code_item")?;
 
-    let registers_size = parse_u16(data, cursor);
+    let registers_size = endian.read_u16(data, cursor);
     cursor += 2;
-    let ins_size = parse_u16(data, cursor);
+    let ins_size = endian.read_u16(data, cursor);
     cursor += 2;
-    let outs_size = parse_u16(data, cursor);
+    let outs_size = endian.read_u16(data, cursor);
     cursor += 2;
-    let tries_size = parse_u16(data, cursor);
+    let tries_size = endian.read_u16(data, cursor);
     cursor += 2;
-    let debug_info_off = parse_u32(data, cursor);
+    let debug_info_off = endian.read_u32(data, cursor);
     cursor += 4;
-    let insns_size = parse_u32(data, cursor);
+    let insns_size = endian.read_u32(data, cursor);
     cursor += 4;
 
-    // Read raw instructions
-    let mut insns = Vec::with_capacity((insns_size as usize) * 2);
-    for _ in 0..insns_size * 2 {
-        insns.push(data[cursor]);
-        cursor += 1;
+    // Code units are in the byte order of the file, wider literals are made of consecutive
+    // code units, low-order unit first, either way
+    let insns_end = cursor + insns_size as usize * 2;
+    let insns: Vec<u16> = data
+        .get(cursor..insns_end)
+        .ok_or(ParseError::TruncatedCode { insns_size })?
+        .chunks_exact(2)
+        .map(|unit| endian.u16([unit[0], unit[1]]))
+        .collect();
+    cursor = insns_end;
+
+    // Padding is only present if there are tries and insns_size is odd
+    let mut padding = None;
+    if tries_size != 0 && insns_size % 2 != 0 {
+        padding = Some(endian.read_u16(data, cursor));
+        cursor += 2;
     }
 
-    // Optional padding if insns_size is odd
-    let padding = if insns_size % 2 != 0 {
-        Some(parse_u16(data, cursor))
-    } else {
-        None
-    };
+    let (instructions, addresses, payloads) = parse_instructions(&insns)?;
 
-    let instructions = parse_instructions(&insns);
+    check_count(data, cursor, tries_size as u32, 8, "try_item")?;
+    let mut tries = Vec::with_capacity(tries_size as usize);
+    for _ in 0..tries_size {
+        let start_addr = endian.read_u32(data, cursor);
+        cursor += 4;
+        let insn_count = endian.read_u16(data, cursor);
+        cursor += 2;
+        let handler_off = endian.read_u16(data, cursor);
+        cursor += 2;
 
-    CodeItem {
+        tries.push(TryItem { start_addr, insn_count, handler_off });
+    }
+
+    let mut handlers = Vec::new();
+    if tries_size != 0 {
+        handlers = parse_catch_handlers(data, cursor)?;
+    }
+
+    Ok(CodeItem {
         registers_size,
         ins_size,
         outs_size,
@@ -183,2197 +377,720 @@ pub fn parse_code_item(data: &[u8], offset: usize) -> CodeItem {
         insns_size,
         insns,
         instructions,
+        addresses,
+        payloads,
         padding,
-    }
+        tries,
+        handlers,
+    })
 }
 
-fn parse_instructions(insns: &[u8]) -> Vec<Instruction> {
-    let mut instructions = Vec::new();
-    let mut i: usize = 0;
+/// Parse the encoded_catch_handler_list starting at `offset`
+fn parse_catch_handlers(data: &[u8], offset: usize) -> Result<Vec<CatchHandler>, ParseError> {
+    let (size, mut cursor) = read_uleb128(data, offset);
+    // Each handler is at least its size and either a pair or a catch-all address
+    check_count(data, cursor, size, 2, "encoded_catch_handler_list")?;
 
-    // println!("{:X?}", insns);
-
-    while i < insns.len() {
-        let opcode = insns[i];
-
-        if i + 1 >= insns.len() {
-            break
+    let mut handlers = Vec::with_capacity(size as usize);
+    for _ in 0..size {
+        let handler_offset = (cursor - offset) as u16;
+        // A non-positive size means there is a catch-all handler after the typed ones
+        let (handler_count, c) = read_sleb128(data, cursor);
+        cursor = c;
+        check_count(data, cursor, handler_count.unsigned_abs(), 2, "encoded_catch_handler")?;
+
+        let mut pairs = Vec::with_capacity(handler_count.unsigned_abs() as usize);
+        for _ in 0..handler_count.unsigned_abs() {
+            let (type_idx, c) = read_uleb128(data, cursor);
+            cursor = c;
+            let (addr, c) = read_uleb128(data, cursor);
+            cursor = c;
+            pairs.push((type_idx, addr));
         }
 
-        match opcode {
-            0x00 => {
-                // noop
-                i += 1;
+        let mut catch_all_addr = None;
+        if handler_count <= 0 {
+            let (addr, c) = read_uleb128(data, cursor);
+            cursor = c;
+            catch_all_addr = Some(addr);
+        }
 
-                instructions.push(Instruction::Nop);
-            }
-            0x01 => {
-                // move
-                i += 1;
+        handlers.push(CatchHandler {
+            offset: handler_offset,
+            handlers: pairs,
+            catch_all_addr,
+        });
+    }
 
-                let destination = insns[i] >> 4;
-                let source = get_lower_bits(insns[i], 4);
-                i += 1;
+    Ok(handlers)
+}
 
-                instructions.push(Instruction::Move {
-                    dst: destination,
-                    src: source,
-                });
-            }
-            0x02 => {
-                // move-from 16
-                i += 1;
+/// Resolve the try items of a code item against its handlers and the type pool
+fn resolve_tries(data: &[u8], code_item: &CodeItem, container: &DexContainer) -> Vec<TryBlock> {
+    code_item.tries.iter().filter_map(|try_item| {
+        let handler = code_item.handlers.iter().find(|h| h.offset == try_item.handler_off)?;
 
-                let destination = insns[i];
-                i += 1;
+        let handlers = handler.handlers.iter().map(|(type_idx, addr)| {
+            let (_, type_name) = container
+                .type_to_string_offset(*type_idx as usize)
+                .map(|off| super::strings::parse_string_at_offset(data, off, &container.header_item, 0))
+                .unwrap_or_else(|| (0, "<unknown>".to_string()));
+            (type_name, *addr)
+        }).collect();
+
+        Some(TryBlock {
+            start_addr: try_item.start_addr,
+            end_addr: try_item.start_addr.saturating_add(try_item.insn_count as u32),
+            handlers,
+            catch_all_addr: handler.catch_all_addr,
+        })
+    }).collect()
+}
 
-                let source = parse_u16(insns, i);
-                i += 2;
+/// Decode the debug_info_item of `code_item` into the (address, line) of each position and the
+/// named locals. `this_type` is the declaring class of an instance method, `None` for a static
+/// one, so the parameters can be placed in the frame's last registers.
+fn parse_debug_info(
+    data: &[u8],
+    code_item: &CodeItem,
+    container: &DexContainer,
+    this_type: Option<&str>,
+    parameters: &[String],
+) -> (Vec<(u32, u32)>, Vec<LocalVariable>) {
+    let Some(mut cursor) = (code_item.debug_info_off as usize).checked_sub(container.header_item.data_off as usize)
+        .filter(|_| code_item.debug_info_off != 0) else {
+        return (Vec::new(), Vec::new());
+    };
+    let string_at = |idx: Option<u32>| idx
+        .and_then(|idx| container.string_offset(idx as usize))
+        .map(|off| parse_string_at_offset(data, off, &container.header_item, 0).1);
+    let type_at = |idx: Option<u32>| idx
+        .and_then(|idx| container.type_to_string_offset(idx as usize))
+        .map(|off| parse_string_at_offset(data, off, &container.header_item, 0).1);
+
+    // Malformed debug info is only missing positions and names, it is read up to the first
+    // malformed value rather than failing the method
+    let Some((line_start, c)) = checked_uleb128(data, cursor) else {
+        return (Vec::new(), Vec::new());
+    };
+    let Some((parameters_size, c)) = checked_uleb128(data, c) else {
+        return (Vec::new(), Vec::new());
+    };
+    cursor = c;
 
-                instructions.push(Instruction::MoveFrom16 {
-                    dst: destination,
-                    src: source,
-                });
-            }
-            0x03 => {
-                // move/16
-                i += 1;
+    // Open locals per register, and the last one ended in each for restart-local
+    let mut open: HashMap<u16, (String, String, u32)> = HashMap::new();
+    let mut ended: HashMap<u16, (String, String)> = HashMap::new();
+    let mut locals = Vec::new();
 
-                let destination = parse_u16(insns, i);
-                i += 1;
+    // Parameters are live from the start, in the last registers of the frame
+    let mut register = code_item.registers_size.saturating_sub(code_item.ins_size);
+    if let Some(this_type) = this_type {
+        open.insert(register, ("this".to_string(), this_type.to_string(), 0));
+        register = register.wrapping_add(1);
+    }
+    // Each name is at least a byte, a larger count can only be forged
+    for i in 0..(parameters_size as usize).min(data.len().saturating_sub(cursor)) {
+        let Some((name_idx, c)) = checked_uleb128p1(data, cursor) else {
+            break;
+        };
+        cursor = c;
+        let ty = parameters.get(i).cloned().unwrap_or_default();
+        if let Some(name) = string_at(name_idx) {
+            open.insert(register, (name, ty.clone(), 0));
+        }
+        register = register.wrapping_add(if ty == "J" || ty == "D" { 2 } else { 1 });
+    }
 
-                let source = parse_u16(insns, i);
-                i += 1;
+    let mut address = 0u32;
+    let mut line = line_start;
+    let mut lines = Vec::new();
+    let mut close = |open: &mut HashMap<u16, (String, String, u32)>, ended: &mut HashMap<u16, (String, String)>, register: u16, address: u32| {
+        if let Some((name, ty, start_addr)) = open.remove(&register) {
+            locals.push(LocalVariable { register, name: name.clone(), ty: ty.clone(), start_addr, end_addr: address });
+            ended.insert(register, (name, ty));
+        }
+    };
 
-                instructions.push(
-                    Instruction::Move16 { dst: destination, src: source }
-                );
+    while let Some(&opcode) = data.get(cursor) {
+        cursor += 1;
+        match opcode {
+            // DBG_END_SEQUENCE
+            0x00 => break,
+            // DBG_ADVANCE_PC
+            0x01 => {
+                let Some((diff, c)) = checked_uleb128(data, cursor) else {
+                    break;
+                };
+                cursor = c;
+                address = address.wrapping_add(diff);
             }
-            0x04 => {
-                // move-wide
-                i += 1;
-                let destination = insns[i] >> 4;
-                let source = get_lower_bits(insns[i], 4);
-                i += 1;
-
-                instructions.push(Instruction::MoveWide {
-                    dst: destination,
-                    src: source,
-                });
+            // DBG_ADVANCE_LINE
+            0x02 => {
+                let Some((diff, c)) = checked_sleb128(data, cursor) else {
+                    break;
+                };
+                cursor = c;
+                line = line.wrapping_add_signed(diff);
+            }
+            // DBG_START_LOCAL and DBG_START_LOCAL_EXTENDED, which adds a signature
+            0x03 | 0x04 => {
+                let Some((register, c)) = checked_uleb128(data, cursor) else {
+                    break;
+                };
+                let Some((name_idx, c)) = checked_uleb128p1(data, c) else {
+                    break;
+                };
+                let Some((type_idx, c)) = checked_uleb128p1(data, c) else {
+                    break;
+                };
+                let signature = if opcode == 0x04 { checked_uleb128p1(data, c) } else { Some((None, c)) };
+                let Some((_, c)) = signature else {
+                    break;
+                };
+                cursor = c;
+                close(&mut open, &mut ended, register as u16, address);
+                if let Some(name) = string_at(name_idx) {
+                    open.insert(register as u16, (name, type_at(type_idx).unwrap_or_default(), address));
+                }
             }
+            // DBG_END_LOCAL
             0x05 => {
-                // move-wide/from16
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let source = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(
-                    Instruction::MoveWideFrom16 { dst: destination, src: source }
-                );
+                let Some((register, c)) = checked_uleb128(data, cursor) else {
+                    break;
+                };
+                cursor = c;
+                close(&mut open, &mut ended, register as u16, address);
             }
+            // DBG_RESTART_LOCAL
             0x06 => {
-                // move-wide/16
-                i += 1;
-
-                let destination = parse_u16(insns, i);
-                i += 2;
-
-                let source = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(
-                    Instruction::MoveWide16 { dst: destination, src: source }
-                );
-            }
-            0x07 => {
-                // move-object
-                i += 1;
-
-                let registers_byte = insns[i];
-                let source = registers_byte >> 4;
-                let destination = get_lower_bits(registers_byte, 4);
-
-                i += 1;
-
-                instructions.push(Instruction::MoveObject {
-                    dst: destination,
-                    src: source,
-                });
-            }
-            0x08 => {
-                // move-object/from16
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let source = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::MoveObjectFrom16 {
-                    dst: destination,
-                    src: source,
-                });
+                let Some((register, c)) = checked_uleb128(data, cursor) else {
+                    break;
+                };
+                cursor = c;
+                close(&mut open, &mut ended, register as u16, address);
+                if let Some((name, ty)) = ended.get(&(register as u16)).cloned() {
+                    open.insert(register as u16, (name, ty, address));
+                }
             }
+            // DBG_SET_PROLOGUE_END and DBG_SET_EPILOGUE_BEGIN
+            0x07 | 0x08 => {}
+            // DBG_SET_FILE, positions in other files only come from inlining, which d8 doesn't mark
             0x09 => {
-                // move-object/16
-                i += 1;
-
-                let destination = parse_u16(insns, i);
-                i += 1;
-
-                let source = parse_u16(insns, i);
-                i += 1;
-
-                instructions.push(
-                    Instruction::MoveObject16 { dst: destination, src: source }
-                );
-            }
-            0x10 => {
-                // return wide
-                i += 1;
-
-                let ret_reg = insns[i];
-                i += 1;
-
-                instructions.push(Instruction::ReturnWide { reg: ret_reg });
-            }
-            0x0A => {
-                // move-result
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                instructions.push(Instruction::MoveResult { dst: destination });
-            }
-            0x0B => {
-                // move-result-wide
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                instructions.push(Instruction::MoveResultWide { dst: destination })
-            }
-            0x0C => {
-                // move-result-object
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                instructions.push(Instruction::MoveResultObject { dst: destination });
-            }
-            0x0D => {
-                // move-exception
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                instructions.push(Instruction::MoveException { dst: destination });
-            }
-            0x0E => {
-                // return-void
-                i += 2;
-
-                instructions.push(Instruction::ReturnVoid);
-            }
-            0x0F => {
-                // move-result-object
-                i += 1;
-                let return_value_register = insns[i];
-                i += 1;
-
-                instructions.push(Instruction::Return {
-                    reg: return_value_register,
-                });
-            }
-            0x11 => {
-                // return-object
-                // println!("return-object");
-                i += 1;
-                // next byte is register
-                let return_register = insns[i];
-                // println!("Return register -> {}", return_register);
-                i += 1;
-
-                instructions.push(Instruction::ReturnObject {
-                    src: return_register,
-                });
-            }
-            0x12 => {
-                // const/4
-                i += 1;
-                let destination = get_lower_bits(insns[i], 4);
-                let literal: i8 = i8::from_le_bytes([insns[i] >> 4]);
-
-                i += 1;
-                instructions.push(Instruction::Const4Bit {
-                    dst: destination,
-                    signed_int: literal,
-                });
-            }
-            0x13 => {
-                // const/16
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                let literal: i16 = parse_i16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::Const16Bit {
-                    dst: destination,
-                    signed_int: literal,
-                });
-            }
-            0x14 => {
-                // const 32 bit
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                let literal: u32 = parse_u32(insns, i);
-                i += 4;
-
-                instructions.push(Instruction::Const32Bit {
-                    dst: destination,
-                    literal,
-                });
-            }
-            0x15 => {
-                // const-high/16
-                i += 1;
-                
-                let destination = insns[i];
-                i += 1;
-
-                let literal = parse_i16(insns, i);
-                i += 2;
-
-                instructions.push(
-                    Instruction::ConstHigh16 { dst: destination, literal }
-                );
-            }
-            0x16 => {
-                // const-wide 16
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                let literal: i16 = parse_i16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::ConstWide16Bit {
-                    dst: destination,
-                    signed_int: literal,
-                });
-            }
-            0x17 => {
-                // const-wide/32
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let literal = parse_i32(insns, i);
-                i += 4;
-
-                instructions.push(
-                    Instruction::ConstWide32 { dst: destination, literal }
-                );
-            }
-            0x18 => {
-                // const-wide 64
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                let literal: u64 = parse_u64(insns, i);
-                i += 8;
-
-                instructions.push(Instruction::ConstWide64Bit {
-                    dst: destination,
-                    literal,
-                });
-            }
-            0x19 => {
-                // const-wide/high16
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let literal = parse_i16(insns, i);
-                i += 2;
-
-                instructions.push(
-                    Instruction::ConstWide16BitHigh { dst: destination, signed_int: literal }
-                );
+                let Some((_, c)) = checked_uleb128p1(data, cursor) else {
+                    break;
+                };
+                cursor = c;
             }
-            0x1A => {
-                // const-string
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                let string_idx = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::ConstString {
-                    dest: destination,
-                    string_idx,
-                });
-            }
-            0x1B => {
-                // const-string/jumbo
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let string_idx = parse_u32(insns, i);
-                i += 4;
-
-                instructions.push(
-                    Instruction::ConstStringJumbo { dest: destination, string_idx }
-                );
-            }
-            0x1C => {
-                // const-class
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-                let type_idx = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::ConstClass {
-                    dst: destination,
-                    type_idx,
-                });
-            }
-            0x1D => {
-                // monitor-enter
-                i += 1;
-
-                let ref_bearing_reg = insns[i];
-                i += 1;
-
-                instructions.push(
-                    Instruction::MonitorEnter { ref_bearing_reg }
-                );
-            }
-            0x1E => {
-                // monitor-enter
-                i += 1;
-
-                let ref_bearing_reg = insns[i];
-                i += 1;
-
-                instructions.push(
-                    Instruction::MonitorExit { ref_bearing_reg }
-                );
-            }
-            0x1F => {
-                // check-cast
-                i += 1;
-                let ref_bearing_reg = insns[i];
-                i += 1;
-                let type_idx = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::CheckCast {
-                    ref_bearing_reg,
-                    type_idx,
-                });
-            }
-            0x20 => {
-                // instance-of
-                i += 1;
-
-                let destination = insns[i] >> 4;
-                let ref_bearing_reg = get_lower_bits(insns[i], 4);
-                i += 1;
-
-                let type_idx = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::InstanceOf {
-                    dst: destination,
-                    ref_bearing_reg,
-                    type_idx,
-                });
-            }
-            0x21 => {
-                // array-length
-                i += 1;
-
-                let destination = insns[i] >> 4;
-                let array_ref_bearing_reg = get_lower_bits(insns[i], 4);
-                i += 1;
-
-                instructions.push(Instruction::ArrayLength {
-                    dst: destination,
-                    array_ref_bearing_reg,
-                });
-            }
-            0x22 => {
-                // new-instance
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-                let type_idx = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::NewInstance {
-                    dst: destination,
-                    type_idx,
-                });
-            }
-            0x23 => {
-                // new-array
-                i += 1;
-
-                let destination = insns[i] >> 4;
-                let size = get_lower_bits(insns[i], 4);
-                i += 1;
-
-                let type_idx = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::NewArray {
-                    dst: destination,
-                    size,
-                    type_idx,
-                })
-            }
-            0x24 => {
-                // filled-new-array
-                i += 1;
-
-                let argument_count: u8 = insns[i] >> 4;
-                i += 1;
-
-                let type_idx = parse_u16(insns, i);
-                i += 2;
-
-                let mut arg_registers: Vec<u8> = Vec::with_capacity(argument_count as usize);
-                let args_slice = &insns[i..i + 2];
-                i += 2;
-                if argument_count > 0 {
-                    for b in 0..(argument_count - 1).div_ceil(2) {
-                        let argument_register: u8 = args_slice[b as usize] >> 4;
-                        arg_registers.push(argument_register);
-                        if argument_count % 2 == 0 {
-                            let second_argument_register: u8 = get_lower_bits(args_slice[b as usize], 4);
-                            arg_registers.push(second_argument_register);
-                        }
-                    }
-                }
-
-                instructions.push(Instruction::FilledNewArray {
-                    argc: argument_count,
-                    args: arg_registers,
-                    type_idx,
-                });
+            // Special opcodes advance both and record a position
+            _ => {
+                let adjusted = (opcode - 0x0a) as i32;
+                line = line.wrapping_add_signed(-4 + adjusted % 15);
+                address = address.wrapping_add((adjusted / 15) as u32);
+                lines.push((address, line));
             }
-            0x25 => {
-                // filled-new-array/range
-                i += 1;
-
-                let count = insns[i];
-                i += 1;
-
-                let type_idx = parse_u16(insns, i);
-                i += 2;
-
-                let first_arg_reg = parse_u16(insns, i);
-                i += 2;
+        }
+    }
 
-                instructions.push(Instruction::FilledNewArrayRange {
-                    count,
-                    type_idx,
-                    first_arg_reg,
-                });
-            }
-            0x26 => {
-                // filled-array-data
-                i += 1;
+    let mut registers: Vec<u16> = open.keys().copied().collect();
+    registers.sort();
+    for register in registers {
+        close(&mut open, &mut ended, register, code_item.insns_size);
+    }
+    (lines, locals)
+}
 
-                let array_ref = insns[i];
-                i += 1;
+/// Instructions of a method's code, the address (in 16-bit code units) of each of them and the
+/// payloads in between by address
+type DecodedCode = (Vec<Instruction>, Vec<u32>, Vec<(u32, Payload)>);
 
-                let signed_fake_branch_off = parse_i32(insns, i);
-                i += 1;
+/// Decode the code units `insns` into instructions, alongside the address (in 16-bit code units)
+/// of each instruction. Unused opcodes take a code unit and decode to nothing, payloads are
+/// skipped over and returned separately.
+pub fn parse_instructions(insns: &[u16]) -> Result<DecodedCode, ParseError> {
+    let mut instructions = Vec::new();
+    let mut addresses = Vec::new();
+    let mut payloads = Vec::new();
+    let mut pc = 0;
+
+    while pc < insns.len() {
+        let opcode = (insns[pc] & 0xFF) as u8;
+        let address = pc as u32;
+        if let Some((payload, width)) = parse_payload(&insns[pc..], address)? {
+            payloads.push((address, payload));
+            pc += width;
+            continue;
+        }
+        let Some(format) = instruction_format(opcode) else {
+            pc += 1;
+            continue;
+        };
+        let units = insns
+            .get(pc..pc + format.width())
+            .ok_or(ParseError::TruncatedInstruction { opcode, address })?;
+        pc += format.width();
 
-                instructions.push(
-                    Instruction::FilledArrayData { array_ref, signed_fake_branch_off }
-                );
-            }
-            0x27 => {
-                // throw
-                i += 1;
+        instructions.push(decode_instruction(opcode, Operands::decode(format, units)));
+        addresses.push(address);
+    }
 
-                let reg = insns[i];
-                i += 1;
+    Ok((instructions, addresses, payloads))
+}
 
-                instructions.push(Instruction::Throw { reg });
-            }
-            0x28 => {
-                // goto
-                i += 1;
+/// Idents of the payload pseudo-instructions, a nop opcode with a non-zero high byte
+const PACKED_SWITCH_PAYLOAD: u16 = 0x0100;
+const SPARSE_SWITCH_PAYLOAD: u16 = 0x0200;
+const FILL_ARRAY_DATA_PAYLOAD: u16 = 0x0300;
+
+/// Decode the payload at the start of `insns`, alongside its width in code units. `None` if
+/// `insns` doesn't start with a payload ident.
+fn parse_payload(insns: &[u16], address: u32) -> Result<Option<(Payload, usize)>, ParseError> {
+    let ident = insns[0];
+    let unit = |i: usize| insns.get(i).copied().unwrap_or(0) as usize;
+    // The sizes are in the first units after the ident, a payload cut off before them is
+    // caught by the width check like one cut off in its entries
+    let size = unit(1);
+    let data_len = unit(1).saturating_mul(unit(2) | unit(3) << 16);
+    let width = match ident {
+        PACKED_SWITCH_PAYLOAD => 4 + size * 2,
+        SPARSE_SWITCH_PAYLOAD => 2 + size * 4,
+        FILL_ARRAY_DATA_PAYLOAD => 4 + data_len.div_ceil(2),
+        _ => return Ok(None),
+    };
+    let units = insns.get(..width).ok_or(ParseError::TruncatedPayload { ident, address })?;
+    let int = |i: usize| (units[i] as u32 | (units[i + 1] as u32) << 16) as i32;
+    let ints = |start: usize, count: usize| (0..count).map(|i| int(start + i * 2)).collect::<Vec<_>>();
+
+    let payload = match ident {
+        PACKED_SWITCH_PAYLOAD => Payload::PackedSwitch { first_key: int(2), targets: ints(4, size) },
+        SPARSE_SWITCH_PAYLOAD => Payload::SparseSwitch { keys: ints(2, size), targets: ints(2 + size * 2, size) },
+        _ => {
+            let data = units[4..].iter().flat_map(|unit| unit.to_le_bytes()).take(data_len).collect();
+            Payload::FillArrayData { element_width: units[1], data }
+        }
+    };
+    Ok(Some((payload, width)))
+}
 
-                let signed_branch_off: i8 = i8::from_le_bytes([insns[i]]);
-                i += 1;
+/// Operands of an instruction, read from its code units by its format. `a`, `b` and `c` are the
+/// spec's `A`, `B` and `C` of the format, registers, literals, branch offsets or indices in the
+/// order the format lists them.
+#[derive(Default)]
+struct Operands {
+    a: u32,
+    b: u32,
+    c: u32,
+    /// Literal of 51l
+    wide: u64,
+    /// Argument registers of 35c and 45cc, in argument order C, D, E, F, G
+    args: Vec<u16>,
+    /// proto_idx of 45cc and 4rcc
+    h: u16,
+}
 
-                instructions.push(Instruction::Goto { signed_branch_off });
-            }
-            0x29 => {
-                // goto/16
-                i += 1;
+impl Operands {
+    fn decode(format: Format, units: &[u16]) -> Self {
+        // The high byte of the first unit is either AA or B|A
+        let aa = (units[0] >> 8) as u32;
+        let (nibble_a, nibble_b) = (aa & 0xF, aa >> 4);
+        let unit = |i: usize| units[i] as u32;
+        let long = |i: usize| unit(i) | unit(i + 1) << 16;
+        // A|G|op BBBB F|E|D|C
+        let args = || {
+            let count = nibble_b.min(5) as usize;
+            [unit(2) & 0xF, unit(2) >> 4 & 0xF, unit(2) >> 8 & 0xF, unit(2) >> 12, nibble_a]
+                .into_iter()
+                .take(count)
+                .map(|register| register as u16)
+                .collect()
+        };
 
-                let signed_branch_off = parse_i16(insns, i);
-                i += 2;
+        match format {
+            Format::F10x => Operands::default(),
+            Format::F12x | Format::F11n => Operands { a: nibble_a, b: nibble_b, ..Default::default() },
+            Format::F11x | Format::F10t => Operands { a: aa, ..Default::default() },
+            Format::F20t => Operands { a: unit(1), ..Default::default() },
+            Format::F22x | Format::F21t | Format::F21s | Format::F21h | Format::F21c => {
+                Operands { a: aa, b: unit(1), ..Default::default() }
+            }
+            Format::F23x | Format::F22b => Operands { a: aa, b: unit(1) & 0xFF, c: unit(1) >> 8, ..Default::default() },
+            Format::F22t | Format::F22s | Format::F22c => Operands { a: nibble_a, b: nibble_b, c: unit(1), ..Default::default() },
+            Format::F30t => Operands { a: long(1), ..Default::default() },
+            Format::F32x => Operands { a: unit(1), b: unit(2), ..Default::default() },
+            Format::F31i | Format::F31t | Format::F31c => Operands { a: aa, b: long(1), ..Default::default() },
+            Format::F35c => Operands { a: nibble_b, b: unit(1), args: args(), ..Default::default() },
+            Format::F3rc => Operands { a: aa, b: unit(1), c: unit(2), ..Default::default() },
+            Format::F45cc => Operands { a: nibble_b, b: unit(1), args: args(), h: units[3], ..Default::default() },
+            Format::F4rcc => Operands { a: aa, b: unit(1), c: unit(2), h: units[3], ..Default::default() },
+            Format::F51l => Operands { a: aa, wide: long(1) as u64 | (long(3) as u64) << 32, ..Default::default() },
+        }
+    }
+}
 
-                instructions.push(Instruction::Goto16 { signed_branch_off });
-            }
-            0x2A => {
-                i += 1;
+/// The instruction with `opcode` and the operands its format was read into
+fn decode_instruction(opcode: u8, operands: Operands) -> Instruction {
+    let Operands { a, b, c, wide, args, h } = operands;
+    let (a8, b8, c8) = (a as u8, b as u8, c as u8);
+    let (a16, b16, c16) = (a as u16, b as u16, c as u16);
+
+    match opcode {
+        0x00 => Instruction::Nop,
+        0x01 => Instruction::Move { dst: a8, src: b8 },
+        0x02 => Instruction::MoveFrom16 { dst: a8, src: b16 },
+        0x03 => Instruction::Move16 { dst: a16, src: b16 },
+        0x04 => Instruction::MoveWide { dst: a8, src: b8 },
+        0x05 => Instruction::MoveWideFrom16 { dst: a8, src: b16 },
+        0x06 => Instruction::MoveWide16 { dst: a16, src: b16 },
+        0x07 => Instruction::MoveObject { dst: a8, src: b8 },
+        0x08 => Instruction::MoveObjectFrom16 { dst: a8, src: b16 },
+        0x09 => Instruction::MoveObject16 { dst: a16, src: b16 },
+        0x0A => Instruction::MoveResult { dst: a8 },
+        0x0B => Instruction::MoveResultWide { dst: a8 },
+        0x0C => Instruction::MoveResultObject { dst: a8 },
+        0x0D => Instruction::MoveException { dst: a8 },
+        0x0E => Instruction::ReturnVoid,
+        0x0F => Instruction::Return { reg: a8 },
+        0x10 => Instruction::ReturnWide { reg: a8 },
+        0x11 => Instruction::ReturnObject { src: a8 },
+        // The 4-bit literal is sign-extended
+        0x12 => Instruction::Const4Bit { dst: a8, signed_int: (b8 << 4) as i8 >> 4 },
+        0x13 => Instruction::Const16Bit { dst: a8, signed_int: b16 as i16 },
+        0x14 => Instruction::Const32Bit { dst: a8, literal: b },
+        0x15 => Instruction::ConstHigh16 { dst: a8, literal: b16 as i16 },
+        0x16 => Instruction::ConstWide16Bit { dst: a8, signed_int: b16 as i16 },
+        0x17 => Instruction::ConstWide32 { dst: a8, literal: b as i32 },
+        0x18 => Instruction::ConstWide64Bit { dst: a8, literal: wide },
+        0x19 => Instruction::ConstWide16BitHigh { dst: a8, signed_int: b16 as i16 },
+        0x1A => Instruction::ConstString { dest: a8, string_idx: b16 },
+        0x1B => Instruction::ConstStringJumbo { dest: a8, string_idx: b },
+        0x1C => Instruction::ConstClass { dst: a8, type_idx: b16 },
+        0x1D => Instruction::MonitorEnter { ref_bearing_reg: a8 },
+        0x1E => Instruction::MonitorExit { ref_bearing_reg: a8 },
+        0x1F => Instruction::CheckCast { ref_bearing_reg: a8, type_idx: b16 },
+        0x20 => Instruction::InstanceOf { dst: a8, ref_bearing_reg: b8, type_idx: c16 },
+        0x21 => Instruction::ArrayLength { dst: a8, array_ref_bearing_reg: b8 },
+        0x22 => Instruction::NewInstance { dst: a8, type_idx: b16 },
+        0x23 => Instruction::NewArray { dst: a8, size: b8, type_idx: c16 },
+        0x24 => Instruction::FilledNewArray { argc: a8, args, type_idx: b16 },
+        0x25 => Instruction::FilledNewArrayRange { count: a8, type_idx: b16, first_arg_reg: c16 },
+        0x26 => Instruction::FilledArrayData { array_ref: a8, signed_fake_branch_off: b as i32 },
+        0x27 => Instruction::Throw { reg: a8 },
+        0x28 => Instruction::Goto { signed_branch_off: a8 as i8 },
+        0x29 => Instruction::Goto16 { signed_branch_off: a16 as i16 },
+        0x2A => Instruction::Goto32 { signed_branch_off: a as i32 },
+        0x2B => Instruction::PackedSwitch { test_reg: a8, signed_fake_branch_off: b as i32 },
+        0x2C => Instruction::SparseSwitch { test_reg: a8, signed_fake_branch_off: b as i32 },
+
+        0x2D => Instruction::CmpLessFloat { dst: a8, first_reg: b8, second_reg: c8 },
+        0x2E => Instruction::CmpGreaterFloat { dst: a8, first_reg: b8, second_reg: c8 },
+        0x2F => Instruction::CmpLessDouble { dst: a8, first_reg: b8, second_reg: c8 },
+        0x30 => Instruction::CmpGreaterDouble { dst: a8, first_reg: b8, second_reg: c8 },
+        0x31 => Instruction::CmpLong { dst: a8, first_reg: b8, second_reg: c8 },
+
+        0x32..=0x37 => {
+            let (first_reg, second_reg, signed_branch_off) = (a8, b8, c16 as i16);
+            match opcode {
+                0x32 => Instruction::TestIfEqual { first_reg, second_reg, signed_branch_off },
+                0x33 => Instruction::TestIfNotEqual { first_reg, second_reg, signed_branch_off },
+                0x34 => Instruction::TestIfLessThan { first_reg, second_reg, signed_branch_off },
+                0x35 => Instruction::TestIfGreaterEqual { first_reg, second_reg, signed_branch_off },
+                0x36 => Instruction::TestIfGreaterThan { first_reg, second_reg, signed_branch_off },
+                _ => Instruction::TestIfLessEqual { first_reg, second_reg, signed_branch_off },
             }
-            0x2B => {
-                // packed-switch
-                i += 1;
-
-                let test_reg = insns[i];
-                i += 1;
-
-                let signed_fake_branch_off = parse_i32(insns, i);
-                i += 4;
-
-                instructions.push(
-                    Instruction::PackedSwitch { test_reg, signed_fake_branch_off }
-                );
+        }
+        0x38..=0x3D => {
+            let (test_reg, signed_branch_off) = (a8, b16 as i16);
+            match opcode {
+                0x38 => Instruction::BranchIfEqualZero { test_reg, signed_branch_off },
+                0x39 => Instruction::BranchIfNotEqualZero { test_reg, signed_branch_off },
+                0x3A => Instruction::BranchIfLessThanZero { test_reg, signed_branch_off },
+                0x3B => Instruction::BranchIfGreaterEqualZero { test_reg, signed_branch_off },
+                0x3C => Instruction::BranchIfGreaterThanZero { test_reg, signed_branch_off },
+                _ => Instruction::BranchIfLessEqualZero { test_reg, signed_branch_off },
             }
-            0x2C => {
-                // sparse-switch
-                i += 1;
-
-                let test_reg = insns[i];
-                i += 1;
-
-                let signed_fake_branch_off = parse_i32(insns, i);
-                i += 4;
+        }
 
-                instructions.push(Instruction::SparseSwitch {
-                    test_reg,
-                    signed_fake_branch_off,
-                });
-            }
-            0x2D..=0x31 => {
-                // 2d = cmpl-float (lt bias)
-                // 2e = cmpg-float (gt bias)
-                // 2f = cmpl-double (lt bias)
-                // 30 = cmpg-double (gt bias)
-                // 31 = cmp-long
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let first_reg = insns[i];
-                i += 1;
-                
-                let second_reg = insns[i];
-                i += 1;
-
-                match opcode {
-                    0x2D => {
-                        instructions.push(
-                            Instruction::CmpLessFloat { dst: destination, first_reg, second_reg }
-                        );
-                    }
-                    0x2E => {
-                        instructions.push(
-                            Instruction::CmpGreaterFloat { dst: destination, first_reg, second_reg }
-                        );
-                    }
-                    0x2F => {
-                        instructions.push(
-                            Instruction::CmpLessDouble { dst: destination, first_reg, second_reg }
-                        );
-                    }
-                    0x30 => {
-                        instructions.push(
-                            Instruction::CmpGreaterDouble { dst: destination, first_reg, second_reg }
-                        );
-                    }
-                    0x31 => {
-                        instructions.push(
-                            Instruction::CmpLong { dst: destination, first_reg, second_reg }
-                        );
-                    }
-                    _ => {
-                        assert!(false, "This should be unreachable. Opcode -> {:02X}", opcode);
-                    }
-                }
-            }
-            0x32..=0x37 => {
-                // 32 = if-eq
-                // 33 = if-ne
-                // 34 = if-lt
-                // 35 = if-ge
-                // 36 = if-gt
-                // 37 = if-le
-                i += 1;
-
-                let first_reg = insns[i] >> 4;
-                let second_reg = get_lower_bits(insns[i], 4);
-                i += 1;
-
-                let signed_branch_off = parse_i16(insns, i);
-                i += 2;
-
-                match opcode {
-                    0x32 => {
-                        instructions.push(Instruction::TestIfEqual {
-                            first_reg,
-                            second_reg,
-                            signed_branch_off,
-                        });
-                    }
-                    0x33 => {
-                        instructions.push(Instruction::TestIfNotEqual {
-                            first_reg,
-                            second_reg,
-                            signed_branch_off,
-                        });
-                    }
-                    0x34 => {
-                        instructions.push(Instruction::TestIfLessThan {
-                            first_reg,
-                            second_reg,
-                            signed_branch_off,
-                        });
-                    }
-                    0x35 => {
-                        instructions.push(Instruction::TestIfGreaterEqual {
-                            first_reg,
-                            second_reg,
-                            signed_branch_off,
-                        });
-                    }
-                    0x36 => {
-                        instructions.push(Instruction::TestIfGreaterThan {
-                            first_reg,
-                            second_reg,
-                            signed_branch_off,
-                        });
-                    }
-                    0x37 => {
-                        instructions.push(Instruction::TestIfLessEqual {
-                            first_reg,
-                            second_reg,
-                            signed_branch_off,
-                        });
-                    }
-                    _ => {
-                        assert!(
-                            false,
-                            "This should be unreachable. Opcode -> 0x{:X}",
-                            opcode
-                        );
-                    }
-                }
-            }
-            0x38..=0x3D => {
-                // 38 = if-eqz
-                // 39 = if-nez
-                // 3a = if-ltz
-                // 3b = if-gez
-                // 3c = if-gtz
-                // 3d = if-lez
-
-                i += 1;
-                let test_reg = insns[i];
-                i += 1;
-
-                let branch_off = parse_i16(insns, i);
-                i += 2;
-
-                match opcode {
-                    0x38 => {
-                        instructions.push(Instruction::BranchIfEqualZero {
-                            test_reg,
-                            signed_branch_off: branch_off,
-                        });
-                    }
-                    0x39 => {
-                        instructions.push(Instruction::BranchIfNotEqualZero {
-                            test_reg,
-                            signed_branch_off: branch_off,
-                        });
-                    }
-                    0x3A => {
-                        instructions.push(Instruction::BranchIfLessThanZero {
-                            test_reg,
-                            signed_branch_off: branch_off,
-                        });
-                    }
-                    0x3B => {
-                        instructions.push(Instruction::BranchIfGreaterEqualZero {
-                            test_reg,
-                            signed_branch_off: branch_off,
-                        });
-                    }
-                    0x3C => {
-                        instructions.push(Instruction::BranchIfGreaterThanZero {
-                            test_reg,
-                            signed_branch_off: branch_off,
-                        });
-                    }
-                    0x3D => {
-                        instructions.push(Instruction::BranchIfLessEqualZero {
-                            test_reg,
-                            signed_branch_off: branch_off,
-                        });
-                    }
-                    _ => {
-                        assert!(false, "This should not be reachable")
-                    }
-                }
+        0x44..=0x51 => {
+            let (src, array_reg, index_reg) = (a8, b8, c8);
+            match opcode {
+                0x44 => Instruction::AGet { src, array_reg, index_reg },
+                0x45 => Instruction::AGetWide { src, array_reg, index_reg },
+                0x46 => Instruction::AGetObject { src, array_reg, index_reg },
+                0x47 => Instruction::AGetBoolean { src, array_reg, index_reg },
+                0x48 => Instruction::AGetByte { src, array_reg, index_reg },
+                0x49 => Instruction::AGetChar { src, array_reg, index_reg },
+                0x4A => Instruction::AGetShort { src, array_reg, index_reg },
+                0x4B => Instruction::APut { src, array_reg, index_reg },
+                0x4C => Instruction::APutWide { src, array_reg, index_reg },
+                0x4D => Instruction::APutObject { src, array_reg, index_reg },
+                0x4E => Instruction::APutBoolean { src, array_reg, index_reg },
+                0x4F => Instruction::APutByte { src, array_reg, index_reg },
+                0x50 => Instruction::APutChar { src, array_reg, index_reg },
+                _ => Instruction::APutShort { src, array_reg, index_reg },
             }
-            0x3e..=0x43 => {
-                // unused
-                i += 1;
+        }
+        0x52..=0x5F => {
+            let (src, obj, instance_field_idx) = (a8, b8, c16);
+            match opcode {
+                0x52 => Instruction::IGet { src, obj, instance_field_idx },
+                0x53 => Instruction::IGetWide { src, obj, instance_field_idx },
+                0x54 => Instruction::IGetObject { src, obj, instance_field_idx },
+                0x55 => Instruction::IGetBoolean { src, obj, instance_field_idx },
+                0x56 => Instruction::IGetByte { src, obj, instance_field_idx },
+                0x57 => Instruction::IGetChar { src, obj, instance_field_idx },
+                0x58 => Instruction::IGetShort { src, obj, instance_field_idx },
+                0x59 => Instruction::IPut { src, obj, instance_field_idx },
+                0x5A => Instruction::IPutWide { src, obj, instance_field_idx },
+                0x5B => Instruction::IPutObject { src, obj, instance_field_idx },
+                0x5C => Instruction::IPutBoolean { src, obj, instance_field_idx },
+                0x5D => Instruction::IPutByte { src, obj, instance_field_idx },
+                0x5E => Instruction::IPutChar { src, obj, instance_field_idx },
+                _ => Instruction::IPutShort { src, obj, instance_field_idx },
             }
-            0x44..=0x51 => {
-                // 44 = aget
-                // 45 = aget-wide
-                // 46 = aget-object
-                // 47 = aget-boolean
-                // 48 = aget-byte
-                // 49 = aget-char
-                // 4a = aget-short
-                // 4b = aput
-                // 4c = aput-wide
-                // 4d = aput-object
-                // 4e = aput-boolean
-                // 4f = aput-byte
-                // 50 = aput-char
-                // 51 = aput-short
-
-                i += 1;
-                let source = insns[i];
-                i += 1;
-
-                let array_reg = insns[i];
-                i += 1;
-
-                let index_reg = insns[i];
-                i += 1;
-
-                match opcode {
-                    0x44 => {
-                        instructions.push(Instruction::AGet {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x45 => {
-                        instructions.push(Instruction::AGetWide {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x46 => {
-                        instructions.push(Instruction::AGetObject {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x47 => {
-                        instructions.push(Instruction::AGetBoolean {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x48 => {
-                        instructions.push(Instruction::AGetByte {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x49 => {
-                        instructions.push(Instruction::AGetChar {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x4A => {
-                        instructions.push(Instruction::AGetShort {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x4B => {
-                        instructions.push(Instruction::APut {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x4C => {
-                        instructions.push(Instruction::APutWide {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x4D => {
-                        instructions.push(Instruction::APutObject {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x4E => {
-                        instructions.push(Instruction::APutBoolean {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x4F => {
-                        instructions.push(Instruction::APutByte {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x50 => {
-                        instructions.push(Instruction::APutChar {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    0x51 => {
-                        instructions.push(Instruction::APutShort {
-                            src: source,
-                            array_reg,
-                            index_reg,
-                        });
-                    }
-                    _ => {
-                        assert!(
-                            false,
-                            "This should be unreachable. opcode -> 0x{:X}",
-                            opcode
-                        );
-                    }
-                }
+        }
+        0x60..=0x6D => {
+            let (src, static_field_idx) = (a8, b16);
+            match opcode {
+                0x60 => Instruction::SGet { src, static_field_idx },
+                0x61 => Instruction::SGetWide { src, static_field_idx },
+                0x62 => Instruction::SGetObject { src, static_field_idx },
+                0x63 => Instruction::SGetBoolean { src, static_field_idx },
+                0x64 => Instruction::SGetByte { src, static_field_idx },
+                0x65 => Instruction::SGetChar { src, static_field_idx },
+                0x66 => Instruction::SGetShort { src, static_field_idx },
+                0x67 => Instruction::SPut { src, static_field_idx },
+                0x68 => Instruction::SPutWide { src, static_field_idx },
+                0x69 => Instruction::SPutObject { src, static_field_idx },
+                0x6A => Instruction::SPutBoolean { src, static_field_idx },
+                0x6B => Instruction::SPutByte { src, static_field_idx },
+                0x6C => Instruction::SPutChar { src, static_field_idx },
+                _ => Instruction::SPutShort { src, static_field_idx },
             }
-            0x52..=0x5F => {
-                // 52 = iget
-                // 53 = iget-wide
-                // 54 = iget-object
-                // 55 = iget-boolean
-                // 56 = iget-byte
-                // 57 = iget-char
-                // 58 = iget-short
-                // 59 = iput
-                // 5a = iput-wide
-                // 5b = iput-object
-                // 5c = iput-boolean
-                // 5d = iput-byte
-                // 5e = iput-char
-                // 5f = iput-short
-                i += 1;
-                // next byte is source register and object register
-                let registers_byte = insns[i];
-                let source = get_lower_bits(registers_byte, 4);
-                let object = registers_byte >> 4;
-                // println!("Source -> {}, Object -> {}", source, object);
-                i += 1;
-
-                // next 2 bytes are instance field reference index
-                let reference_index = parse_u16(insns, i);
-                // println!("Instance field index -> {}", reference_index);
-                i += 2;
-
-                match opcode {
-                    0x52 => {
-                        instructions.push(Instruction::IGet {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x53 => {
-                        instructions.push(Instruction::IGetWide {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x54 => {
-                        instructions.push(Instruction::IGetObject {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x55 => {
-                        instructions.push(Instruction::IGetBoolean {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x56 => {
-                        instructions.push(Instruction::IGetByte {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x57 => {
-                        instructions.push(Instruction::IGetChar {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x58 => {
-                        instructions.push(Instruction::IGetShort {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x59 => {
-                        instructions.push(Instruction::IPut {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x5A => {
-                        instructions.push(Instruction::IPutWide {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x5B => {
-                        instructions.push(Instruction::IPutObject {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x5C => {
-                        instructions.push(Instruction::IPutBoolean {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x5D => {
-                        instructions.push(Instruction::IPutByte {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x5E => {
-                        instructions.push(Instruction::IPutChar {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    0x5F => {
-                        instructions.push(Instruction::IPutShort {
-                            src: source,
-                            obj: object,
-                            instance_field_idx: reference_index,
-                        });
-                    }
-                    _ => {
-                        assert!(false, "This should be unreachable. Opcode -> {:X}", opcode);
-                    }
-                }
+        }
+        0x6E..=0x72 => {
+            let (argc, method_idx) = (a8, b16);
+            match opcode {
+                0x6E => Instruction::InvokeVirtual { argc, args, method_idx },
+                0x6F => Instruction::InvokeSuper { argc, args, method_idx },
+                0x70 => Instruction::InvokeDirect { argc, args, method_idx },
+                0x71 => Instruction::InvokeStatic { argc, args, method_idx },
+                _ => Instruction::InvokeInterface { argc, args, method_idx },
             }
-            0x60..=0x6D => {
-                // 60 = sget
-                // 61 = sget-wide
-                // 62 = sget-object
-                // 63 = sget-boolean
-                // 64 = sget-byte
-                // 65 = sget-char
-                // 66 = sget-short
-                // 67 = sput
-                // 68 = sput-wide
-                // 69 = sput-object
-                // 6a = sput-boolean
-                // 6b = sput-byte
-                // 6c = sput-char
-                // 6d = sput-short
-
-                i += 1;
-                let source = insns[i];
-                i += 1;
-
-                let static_field_ref_idx = parse_u16(insns, i);
-                i += 2;
-
-                match opcode {
-                    0x60 => {
-                        instructions.push(Instruction::SGet {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x61 => {
-                        instructions.push(Instruction::SGetWide {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x62 => {
-                        instructions.push(Instruction::SGetObject {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x63 => {
-                        instructions.push(Instruction::SGetBoolean {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x64 => {
-                        instructions.push(Instruction::SGetByte {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x65 => {
-                        instructions.push(Instruction::SGetChar {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x66 => {
-                        instructions.push(Instruction::SGetShort {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x67 => {
-                        instructions.push(Instruction::SPut {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x68 => {
-                        instructions.push(Instruction::SPutWide {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x69 => {
-                        instructions.push(Instruction::SPutObject {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x6A => {
-                        instructions.push(Instruction::SPutBoolean {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x6B => {
-                        instructions.push(Instruction::SPutByte {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x6C => {
-                        instructions.push(Instruction::SPutChar {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    0x6D => {
-                        instructions.push(Instruction::SPutShort {
-                            src: source,
-                            static_field_idx: static_field_ref_idx,
-                        });
-                    }
-                    _ => {
-                        assert!(
-                            false,
-                            "This should be unreachable. opcode -> 0x{:X}",
-                            opcode
-                        );
-                    }
-                }
+        }
+        0x74..=0x78 => {
+            let (count, method_idx, first_arg_reg) = (a8, b16, c16);
+            match opcode {
+                0x74 => Instruction::InvokeVirtualRange { count, method_idx, first_arg_reg },
+                0x75 => Instruction::InvokeSuperRange { count, method_idx, first_arg_reg },
+                0x76 => Instruction::InvokeDirectRange { count, method_idx, first_arg_reg },
+                0x77 => Instruction::InvokeStaticRange { count, method_idx, first_arg_reg },
+                _ => Instruction::InvokeInterfaceRange { count, method_idx, first_arg_reg },
             }
-            0x6E..=0x72 => {
-                // 0x6e = invoke-virtual
-                // 0x6f = invoke-super
-                // 0x70 = invoke-direct
-                // 0x71 = invoke-static
-                // 0x72 = invoke-interface
-                i += 1;
-                let argument_count: u8 = insns[i] >> 4;
-                i += 1;
-
-                let method_ref_index = parse_u16(insns, i);
-                i += 2;
-
-                let mut arg_registers: Vec<u8> = Vec::with_capacity(argument_count as usize);
-                let args_slice = &insns[i..i + 2];
-                i += 2;
-                if argument_count > 0 {
-                    for b in 0..(argument_count - 1).div_ceil(2) {
-                        let argument_register: u8 = args_slice[b as usize] >> 4;
-                        arg_registers.push(argument_register);
-                        if argument_count % 2 == 0 {
-                            let second_argument_register: u8 = get_lower_bits(args_slice[b as usize], 4);
-                            arg_registers.push(second_argument_register);
-                        }
-                    }
-                }
+        }
 
-                match opcode {
-                    0x6e => {
-                        instructions.push(Instruction::InvokeVirtual {
-                            argc: argument_count,
-                            args: arg_registers,
-                            method_idx: method_ref_index,
-                        });
-                    }
-                    0x6f => {
-                        instructions.push(Instruction::InvokeSuper {
-                            argc: argument_count,
-                            args: arg_registers,
-                            method_idx: method_ref_index,
-                        });
-                    }
-                    0x70 => {
-                        instructions.push(Instruction::InvokeDirect {
-                            argc: argument_count,
-                            args: arg_registers,
-                            method_idx: method_ref_index,
-                        });
-                    }
-                    0x71 => {
-                        instructions.push(Instruction::InvokeStatic {
-                            argc: argument_count,
-                            args: arg_registers,
-                            method_idx: method_ref_index,
-                        });
-                    }
-                    0x72 => {
-                        instructions.push(Instruction::InvokeInterface {
-                            argc: argument_count,
-                            args: arg_registers,
-                            method_idx: method_ref_index,
-                        });
-                    }
-                    _ => {
-                        assert!(false, "This should be unreachable. Opcode -> {:X}", opcode);
-                    }
-                }
-            }
-            0x73 => {
-                // unused
-                i += 1;
-            }
-            0x74..=0x78 => {
-                // 74 = invoke-virtual/range
-                // 75 = invoke-super/range
-                // 76 = invoke-direct/range
-                // 77 = invoke-static/range
-                // 78 = invoke-interface/range
-                i += 1;
-
-                let count = insns[i];
-                i += 1;
-
-                let type_idx = parse_u16(insns, i);
-                i += 2;
-
-                let first_arg_reg = parse_u16(insns, i);
-                i += 2;
-
-                match opcode {
-                    0x74 => {
-                        instructions.push(Instruction::InvokeVirtualRange {
-                            count,
-                            type_idx,
-                            first_arg_reg,
-                        });
-                    }
-                    0x75 => {
-                        instructions.push(Instruction::InvokeSuperRange {
-                            count,
-                            type_idx,
-                            first_arg_reg,
-                        });
-                    }
-                    0x76 => {
-                        instructions.push(Instruction::InvokeDirectRange {
-                            count,
-                            type_idx,
-                            first_arg_reg,
-                        });
-                    }
-                    0x77 => {
-                        instructions.push(Instruction::InvokeStaticRange {
-                            count,
-                            type_idx,
-                            first_arg_reg,
-                        });
-                    }
-                    0x78 => {
-                        instructions.push(Instruction::InvokeInterfaceRange {
-                            count,
-                            type_idx,
-                            first_arg_reg,
-                        });
-                    }
-                    _ => {
-                        assert!(false, "This should be unreachable. Opcode -> {:X}", opcode);
-                    }
-                }
-            }
-            0x79..=0x7A => {
-                // unused
-                i += 1;
-            }
-            0x7B..=0x8F => {
-                // 7b = neg-int
-                // 7c = not-int
-                // 7d = neg-long
-                // 7e = not-long
-                // 7f = neg-float
-                // 80 = neg-double
-                // 81 = int-to-long
-                // 82 = int-to-float
-                // 83 = int-to-double
-                // 84 = long-to-int
-                // 85 = long-to-float
-                // 86 = long-to-double
-                // 87 = float-to-int
-                // 88 = float-to-long
-                // 89 = float-to-double
-                // 8a = double-to-int
-                // 8b = double-to-long
-                // 8c = double-to-float
-                // 8d = int-to-byte
-                // 8e = int-to-char
-                // 8f = int-to-short
-                i += 1;
-
-                let destination = insns[i] >> 4;
-                let source = get_lower_bits(insns[i], 4);
-
-                i += 1;
-
-                match opcode {
-                    0x7b => {
-                        instructions.push(Instruction::NegInt {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x7c => {
-                        instructions.push(Instruction::NotInt {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x7d => {
-                        instructions.push(Instruction::NegLong {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x7e => {
-                        instructions.push(Instruction::NotLong {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x7f => {
-                        instructions.push(Instruction::NegFloat {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x80 => {
-                        instructions.push(Instruction::NegDouble {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x81 => {
-                        instructions.push(Instruction::IntToLong {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x82 => {
-                        instructions.push(Instruction::IntToFloat {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x83 => {
-                        instructions.push(Instruction::IntToDouble {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x84 => {
-                        instructions.push(Instruction::LongToInt {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x85 => {
-                        instructions.push(Instruction::LongToFloat {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x86 => {
-                        instructions.push(Instruction::LongToDouble {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x87 => {
-                        instructions.push(Instruction::FloatToInt {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x88 => {
-                        instructions.push(Instruction::FloatToLong {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x89 => {
-                        instructions.push(Instruction::FloatToDouble {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x8a => {
-                        instructions.push(Instruction::DoubleToInt {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x8b => {
-                        instructions.push(Instruction::DoubleToLong {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x8c => {
-                        instructions.push(Instruction::DoubleToFloat {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x8d => {
-                        instructions.push(Instruction::IntToByte {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x8e => {
-                        instructions.push(Instruction::IntToChar {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    0x8f => {
-                        instructions.push(Instruction::IntToShort {
-                            dst: destination,
-                            src: source,
-                        });
-                    }
-                    _ => {
-                        assert!(
-                            false,
-                            "This code should not be reachable. Opcode -> 0x{:X}",
-                            opcode
-                        );
-                    }
-                }
-            }
-            0x90..=0xAF => {
-                // 90 = add-int
-                // 91 = sub-int
-                // 92 = mul-int
-                // 93 = div-int
-                // 94 = rem-int
-                // 95 = and-int
-                // 96 = or-int
-                // 97 = xor-int
-                // 98 = shl-int
-                // 99 = shr-int
-                // 9a = ushr-int
-                // 9b = add-long
-                // 9c = sub-long
-                // 9d = mul-long
-                // 9e = div-long
-                // 9f = rem-long
-                // a0 = and-long
-                // a1 = or-long
-                // a2 = xor-long
-                // a3 = shl-long
-                // a4 = shr-long
-                // a5 = ushr-long
-                // a6 = add-float
-                // a7 = sub-float
-                // a8 = mul-float
-                // a9 = div-float
-                // aa = rem-float
-                // ab = add-double
-                // ac = sub-double
-                // ad = mul-double
-                // ae = div-double
-                // af = rem-double
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let first_src = insns[i];
-                i += 1;
-
-                let second_src = insns[i];
-                i += 1;
-
-                match opcode {
-                    0x90 => {
-                        instructions.push(Instruction::AddInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x91 => {
-                        instructions.push(Instruction::SubInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x92 => {
-                        instructions.push(Instruction::MulInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x93 => {
-                        instructions.push(Instruction::DivInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x94 => {
-                        instructions.push(Instruction::RemInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x95 => {
-                        instructions.push(Instruction::AndInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x96 => {
-                        instructions.push(Instruction::OrInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x97 => {
-                        instructions.push(Instruction::XorInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x98 => {
-                        instructions.push(Instruction::ShLInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x99 => {
-                        instructions.push(Instruction::ShRInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x9a => {
-                        instructions.push(Instruction::UShRInt {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x9b => {
-                        instructions.push(Instruction::AddLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x9c => {
-                        instructions.push(Instruction::SubLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x9d => {
-                        instructions.push(Instruction::MulLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x9e => {
-                        instructions.push(Instruction::DivLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0x9f => {
-                        instructions.push(Instruction::RemLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa0 => {
-                        instructions.push(Instruction::AndLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa1 => {
-                        instructions.push(Instruction::OrLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa2 => {
-                        instructions.push(Instruction::XorLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa3 => {
-                        instructions.push(Instruction::ShLLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa4 => {
-                        instructions.push(Instruction::ShRLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa5 => {
-                        instructions.push(Instruction::UShRLong {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa6 => {
-                        instructions.push(Instruction::AddFloat {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa7 => {
-                        instructions.push(Instruction::SubFloat {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa8 => {
-                        instructions.push(Instruction::MulFloat {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xa9 => {
-                        instructions.push(Instruction::DivFloat {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xaa => {
-                        instructions.push(Instruction::RemFloat {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xab => {
-                        instructions.push(Instruction::AddDouble {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xac => {
-                        instructions.push(Instruction::SubDouble {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xad => {
-                        instructions.push(Instruction::MulDouble {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xae => {
-                        instructions.push(Instruction::DivDouble {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    0xaf => {
-                        instructions.push(Instruction::RemDouble {
-                            dst: destination,
-                            first_src,
-                            second_src,
-                        });
-                    }
-                    _ => {
-                        assert!(false, "This should be unreachable. Opcode -> {:X}", opcode);
-                    }
-                }
-            }
-            0xB0..=0xCF => {
-                // b0 = add-int/2addr
-                // b1 = sub-int/2addr
-                // b2 = mul-int/2addr
-                // b3 = div-int/2addr
-                // b4 = rem-int/2addr
-                // b5 = and-int/2addr
-                // b6 = or-int/2addr
-                // b7 = xor-int/2addr
-                // b8 = shl-int/2addr
-                // b9 = shr-int/2addr
-                // ba = ushr-int/2addr
-                // bb = add-long/2addr
-                // bc = sub-long/2addr
-                // bd = mul-long/2addr
-                // be = div-long/2addr
-                // bf = rem-long/2addr
-                // c0 = and-long/2addr
-                // c1 = or-long/2addr
-                // c2 = xor-long/2addr
-                // c3 = shl-long/2addr
-                // c4 = shr-long/2addr
-                // c5 = ushr-long/2addr
-                // c6 = add-float/2addr
-                // c7 = sub-float/2addr
-                // c8 = mul-float/2addr
-                // c9 = div-float/2addr
-                // ca = rem-float/2addr
-                // cb = add-double/2addr
-                // cc = sub-double/2addr
-                // cd = mul-double/2addr
-                // ce = div-double/2addr
-                // cf = rem-double/2addr
-                i += 1;
-
-                let dst_and_first_src = insns[i] >> 4;
-                let second_src = get_lower_bits(insns[i], 4);
-                i += 1;
-
-                match opcode {
-                    0xB0 => instructions.push(Instruction::AddInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB1 => instructions.push(Instruction::SubInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB2 => instructions.push(Instruction::MulInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB3 => instructions.push(Instruction::DivInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB4 => instructions.push(Instruction::RemInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB5 => instructions.push(Instruction::AndInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB6 => instructions.push(Instruction::OrInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB7 => instructions.push(Instruction::XorInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB8 => instructions.push(Instruction::ShlInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xB9 => instructions.push(Instruction::ShrInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xBA => instructions.push(Instruction::UshrInt2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-
-                    0xBB => instructions.push(Instruction::AddLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xBC => instructions.push(Instruction::SubLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xBD => instructions.push(Instruction::MulLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xBE => instructions.push(Instruction::DivLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xBF => instructions.push(Instruction::RemLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC0 => instructions.push(Instruction::AndLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC1 => instructions.push(Instruction::OrLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC2 => instructions.push(Instruction::XorLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC3 => instructions.push(Instruction::ShlLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC4 => instructions.push(Instruction::ShrLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC5 => instructions.push(Instruction::UshrLong2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-
-                    0xC6 => instructions.push(Instruction::AddFloat2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC7 => instructions.push(Instruction::SubFloat2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC8 => instructions.push(Instruction::MulFloat2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xC9 => instructions.push(Instruction::DivFloat2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xCA => instructions.push(Instruction::RemFloat2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-
-                    0xCB => instructions.push(Instruction::AddDouble2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xCC => instructions.push(Instruction::SubDouble2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xCD => instructions.push(Instruction::MulDouble2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xCE => instructions.push(Instruction::DivDouble2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-                    0xCF => instructions.push(Instruction::RemDouble2Addr {
-                        dst_and_first_src,
-                        second_src,
-                    }),
-
-                    _ => {
-                        panic!("This should be unreachable. Opcode -> 0x{:X}", opcode);
-                    }
-                }
-            }
-            0xD0..=0xD7 => {
-                // d0 = add-int/lit16
-                // d1 = rsub-int (reverse subtract)
-                // d2 = mul-int/lit16
-                // d3 = div-int/lit16
-                // d4 = rem-int/lit16
-                // d5 = and-int/lit16
-                // d6 = or-int/lit16
-                // d7 = xor-int/lit16
-                i += 1;
-
-                let destination = insns[i] >> 4;
-                let source = get_lower_bits(insns[i], 4);
-                i += 1;
-
-                let literal = parse_i16(insns, i);
-                i += 2;
-
-                match opcode {
-                    0xD0 => {
-                        instructions.push(
-                            Instruction::AddIntLit16 { dst: destination, src: source, literal }
-                        );
-                    }
-                    0xD1 => {
-                        instructions.push(
-                            Instruction::RSubIntLit16 { dst: destination, src: source, literal }
-                        );
-                    }
-                    0xD2 => {
-                        instructions.push(
-                            Instruction::MulIntLit16 { dst: destination, src: source, literal }
-                        );
-                    }
-                    0xD3 => {
-                        instructions.push(
-                            Instruction::DivIntLit16 { dst: destination, src: source, literal }
-                        );
-                    }
-                    0xD4 => {
-                        instructions.push(
-                            Instruction::RemIntLit16 { dst: destination, src: source, literal }
-                        );
-                    }
-                    0xD5 => {
-                        instructions.push(
-                            Instruction::AndIntLit16 { dst: destination, src: source, literal }
-                        );
-                    }
-                    0xD6 => {
-                        instructions.push(
-                            Instruction::OrIntLit16 { dst: destination, src: source, literal }
-                        );
-                    }
-                    0xD7 => {
-                        instructions.push(
-                            Instruction::XorIntLit16 { dst: destination, src: source, literal }
-                        );
-                    }
-                    _ => {
-                        assert!(false, "This should be unreachable. Opcode -> {:02X}", opcode);
-                    }
-                }
-            }
-            0xD8..=0xE2 => {
-                // d8 = add-int/lit8
-                // d9 = rsub-int/lit8
-                // da = mul-int/lit8
-                // db = div-int/lit8
-                // dc = rem-int/lit8
-                // dd = and-int/lit8
-                // de = or-int/lit8
-                // df = xor-int/lit8
-                // e0 = shl-int/lit8
-                // e1 = shr-int/lit8
-                // e2 = ushr-int/lit8
-
-                i += 1;
-                let destination = insns[i];
-                i += 1;
-
-                let source = insns[i];
-                i += 1;
-
-                let signed_int_const: i8 = i8::from_le_bytes([insns[i]]);
-                i += 1;
-
-                match opcode {
-                    0xD8 => {
-                        instructions.push(Instruction::AddInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xD9 => {
-                        instructions.push(Instruction::RSubInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xDA => {
-                        instructions.push(Instruction::MulInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xDB => {
-                        instructions.push(Instruction::DivInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xDC => {
-                        instructions.push(Instruction::RemInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xDD => {
-                        instructions.push(Instruction::AndInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xDE => {
-                        instructions.push(Instruction::OrInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xDF => {
-                        instructions.push(Instruction::XorInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xE0 => {
-                        instructions.push(Instruction::ShLInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xE1 => {
-                        instructions.push(Instruction::ShRInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    0xE2 => {
-                        instructions.push(Instruction::UShRInt8Lit8 {
-                            dst: destination,
-                            src: source,
-                            signed_int_const,
-                        });
-                    }
-                    _ => {
-                        assert!(false, "This should be unreachable.")
-                    }
-                }
-            }
-            0xE3..=0xF9 => {
-                // unused
-                i += 1;
-            }
-            0xFA => {
-                // TODO: invoke-polymorphic
-                i += 1;
+        0x7B..=0x8F => {
+            let (dst, src) = (a8, b8);
+            match opcode {
+                0x7B => Instruction::NegInt { dst, src },
+                0x7C => Instruction::NotInt { dst, src },
+                0x7D => Instruction::NegLong { dst, src },
+                0x7E => Instruction::NotLong { dst, src },
+                0x7F => Instruction::NegFloat { dst, src },
+                0x80 => Instruction::NegDouble { dst, src },
+                0x81 => Instruction::IntToLong { dst, src },
+                0x82 => Instruction::IntToFloat { dst, src },
+                0x83 => Instruction::IntToDouble { dst, src },
+                0x84 => Instruction::LongToInt { dst, src },
+                0x85 => Instruction::LongToFloat { dst, src },
+                0x86 => Instruction::LongToDouble { dst, src },
+                0x87 => Instruction::FloatToInt { dst, src },
+                0x88 => Instruction::FloatToLong { dst, src },
+                0x89 => Instruction::FloatToDouble { dst, src },
+                0x8A => Instruction::DoubleToInt { dst, src },
+                0x8B => Instruction::DoubleToLong { dst, src },
+                0x8C => Instruction::DoubleToFloat { dst, src },
+                0x8D => Instruction::IntToByte { dst, src },
+                0x8E => Instruction::IntToChar { dst, src },
+                _ => Instruction::IntToShort { dst, src },
             }
-            0xFB => {
-                // TODO: invoke-polymorphic/range
-                i += 1;
-            }
-            0xFC => {
-                // TODO: invoke-custom
-                i += 1;
-            }
-            0xFD => {
-                // invoke-custom/range
-                i += 1;
-
-                let count = insns[i];
-                i += 1;
-
-                let call_site_ref = parse_u16(insns, i);
-                i += 2;
-
-                let first_arg_reg = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::InvokeCustomRange {
-                    count,
-                    call_site_ref,
-                    first_arg_reg,
-                });
+        }
+        0x90..=0xAF => {
+            let (dst, first_src, second_src) = (a8, b8, c8);
+            match opcode {
+                0x90 => Instruction::AddInt { dst, first_src, second_src },
+                0x91 => Instruction::SubInt { dst, first_src, second_src },
+                0x92 => Instruction::MulInt { dst, first_src, second_src },
+                0x93 => Instruction::DivInt { dst, first_src, second_src },
+                0x94 => Instruction::RemInt { dst, first_src, second_src },
+                0x95 => Instruction::AndInt { dst, first_src, second_src },
+                0x96 => Instruction::OrInt { dst, first_src, second_src },
+                0x97 => Instruction::XorInt { dst, first_src, second_src },
+                0x98 => Instruction::ShLInt { dst, first_src, second_src },
+                0x99 => Instruction::ShRInt { dst, first_src, second_src },
+                0x9A => Instruction::UShRInt { dst, first_src, second_src },
+                0x9B => Instruction::AddLong { dst, first_src, second_src },
+                0x9C => Instruction::SubLong { dst, first_src, second_src },
+                0x9D => Instruction::MulLong { dst, first_src, second_src },
+                0x9E => Instruction::DivLong { dst, first_src, second_src },
+                0x9F => Instruction::RemLong { dst, first_src, second_src },
+                0xA0 => Instruction::AndLong { dst, first_src, second_src },
+                0xA1 => Instruction::OrLong { dst, first_src, second_src },
+                0xA2 => Instruction::XorLong { dst, first_src, second_src },
+                0xA3 => Instruction::ShLLong { dst, first_src, second_src },
+                0xA4 => Instruction::ShRLong { dst, first_src, second_src },
+                0xA5 => Instruction::UShRLong { dst, first_src, second_src },
+                0xA6 => Instruction::AddFloat { dst, first_src, second_src },
+                0xA7 => Instruction::SubFloat { dst, first_src, second_src },
+                0xA8 => Instruction::MulFloat { dst, first_src, second_src },
+                0xA9 => Instruction::DivFloat { dst, first_src, second_src },
+                0xAA => Instruction::RemFloat { dst, first_src, second_src },
+                0xAB => Instruction::AddDouble { dst, first_src, second_src },
+                0xAC => Instruction::SubDouble { dst, first_src, second_src },
+                0xAD => Instruction::MulDouble { dst, first_src, second_src },
+                0xAE => Instruction::DivDouble { dst, first_src, second_src },
+                _ => Instruction::RemDouble { dst, first_src, second_src },
             }
-            0xFE => {
-                // const-method-handle
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let method_handle_idx = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::ConstMethodHandle {
-                    dst: destination,
-                    method_handle_idx,
-                });
+        }
+        0xB0..=0xCF => {
+            let (dst_and_first_src, second_src) = (a8, b8);
+            match opcode {
+                0xB0 => Instruction::AddInt2Addr { dst_and_first_src, second_src },
+                0xB1 => Instruction::SubInt2Addr { dst_and_first_src, second_src },
+                0xB2 => Instruction::MulInt2Addr { dst_and_first_src, second_src },
+                0xB3 => Instruction::DivInt2Addr { dst_and_first_src, second_src },
+                0xB4 => Instruction::RemInt2Addr { dst_and_first_src, second_src },
+                0xB5 => Instruction::AndInt2Addr { dst_and_first_src, second_src },
+                0xB6 => Instruction::OrInt2Addr { dst_and_first_src, second_src },
+                0xB7 => Instruction::XorInt2Addr { dst_and_first_src, second_src },
+                0xB8 => Instruction::ShlInt2Addr { dst_and_first_src, second_src },
+                0xB9 => Instruction::ShrInt2Addr { dst_and_first_src, second_src },
+                0xBA => Instruction::UshrInt2Addr { dst_and_first_src, second_src },
+                0xBB => Instruction::AddLong2Addr { dst_and_first_src, second_src },
+                0xBC => Instruction::SubLong2Addr { dst_and_first_src, second_src },
+                0xBD => Instruction::MulLong2Addr { dst_and_first_src, second_src },
+                0xBE => Instruction::DivLong2Addr { dst_and_first_src, second_src },
+                0xBF => Instruction::RemLong2Addr { dst_and_first_src, second_src },
+                0xC0 => Instruction::AndLong2Addr { dst_and_first_src, second_src },
+                0xC1 => Instruction::OrLong2Addr { dst_and_first_src, second_src },
+                0xC2 => Instruction::XorLong2Addr { dst_and_first_src, second_src },
+                0xC3 => Instruction::ShlLong2Addr { dst_and_first_src, second_src },
+                0xC4 => Instruction::ShrLong2Addr { dst_and_first_src, second_src },
+                0xC5 => Instruction::UshrLong2Addr { dst_and_first_src, second_src },
+                0xC6 => Instruction::AddFloat2Addr { dst_and_first_src, second_src },
+                0xC7 => Instruction::SubFloat2Addr { dst_and_first_src, second_src },
+                0xC8 => Instruction::MulFloat2Addr { dst_and_first_src, second_src },
+                0xC9 => Instruction::DivFloat2Addr { dst_and_first_src, second_src },
+                0xCA => Instruction::RemFloat2Addr { dst_and_first_src, second_src },
+                0xCB => Instruction::AddDouble2Addr { dst_and_first_src, second_src },
+                0xCC => Instruction::SubDouble2Addr { dst_and_first_src, second_src },
+                0xCD => Instruction::MulDouble2Addr { dst_and_first_src, second_src },
+                0xCE => Instruction::DivDouble2Addr { dst_and_first_src, second_src },
+                _ => Instruction::RemDouble2Addr { dst_and_first_src, second_src },
             }
-            0xFF => {
-                // const-method-type
-                i += 1;
-
-                let destination = insns[i];
-                i += 1;
-
-                let method_proto_ref = parse_u16(insns, i);
-                i += 2;
-
-                instructions.push(Instruction::ConstMethodType {
-                    dst: destination,
-                    method_proto_ref,
-                });
+        }
+        0xD0..=0xD7 => {
+            let (dst, src, literal) = (a8, b8, c16 as i16);
+            match opcode {
+                0xD0 => Instruction::AddIntLit16 { dst, src, literal },
+                0xD1 => Instruction::RSubIntLit16 { dst, src, literal },
+                0xD2 => Instruction::MulIntLit16 { dst, src, literal },
+                0xD3 => Instruction::DivIntLit16 { dst, src, literal },
+                0xD4 => Instruction::RemIntLit16 { dst, src, literal },
+                0xD5 => Instruction::AndIntLit16 { dst, src, literal },
+                0xD6 => Instruction::OrIntLit16 { dst, src, literal },
+                _ => Instruction::XorIntLit16 { dst, src, literal },
             }
-            _ => {
-                assert!(
-                    false,
-                    "Unknown opcode: 0x{:02b} (0x{:X}) at i: {}",
-                    opcode, opcode, i
-                );
+        }
+        0xD8..=0xE2 => {
+            let (dst, src, signed_int_const) = (a8, b8, c8 as i8);
+            match opcode {
+                0xD8 => Instruction::AddInt8Lit8 { dst, src, signed_int_const },
+                0xD9 => Instruction::RSubInt8Lit8 { dst, src, signed_int_const },
+                0xDA => Instruction::MulInt8Lit8 { dst, src, signed_int_const },
+                0xDB => Instruction::DivInt8Lit8 { dst, src, signed_int_const },
+                0xDC => Instruction::RemInt8Lit8 { dst, src, signed_int_const },
+                0xDD => Instruction::AndInt8Lit8 { dst, src, signed_int_const },
+                0xDE => Instruction::OrInt8Lit8 { dst, src, signed_int_const },
+                0xDF => Instruction::XorInt8Lit8 { dst, src, signed_int_const },
+                0xE0 => Instruction::ShLInt8Lit8 { dst, src, signed_int_const },
+                0xE1 => Instruction::ShRInt8Lit8 { dst, src, signed_int_const },
+                _ => Instruction::UShRInt8Lit8 { dst, src, signed_int_const },
             }
         }
 
-        // i += 1
+        0xFA => Instruction::InvokePolymorphic { argc: a8, args, method_idx: b16, proto_idx: h },
+        0xFB => Instruction::InvokePolymorphicRange { count: a8, method_idx: b16, first_arg_reg: c16, proto_idx: h },
+        0xFC => Instruction::InvokeCustom { argc: a8, args, call_site_idx: b16 },
+        0xFD => Instruction::InvokeCustomRange { count: a8, call_site_ref: b16, first_arg_reg: c16 },
+        0xFE => Instruction::ConstMethodHandle { dst: a8, method_handle_idx: b16 },
+        0xFF => Instruction::ConstMethodType { dst: a8, method_proto_ref: b16 },
+        // Unused opcodes have no format and are never decoded
+        _ => Instruction::Nop,
     }
+}
 
-    instructions
+/// Descriptor of a proto_id, e.g. `(Ljava/lang/String;I)V`
+pub fn proto_descriptor(data: &[u8], proto_idx: usize, container: &DexContainer) -> Option<String> {
+    let proto = container.proto_id_items.get(proto_idx)?;
+    let return_type = parse_string_at_offset(data, container.type_to_string_offset(proto.return_type_idx as usize)?, &container.header_item, 0).1;
+    let parameters = if proto.parameters_off != 0 {
+        parse_parameters(data, proto.parameters_off, container).ok()?
+    } else {
+        Vec::new()
+    };
+
+    Some(format!("({}){}", parameters.concat(), return_type))
 }
 
-fn parse_parameters(data: &[u8], parameter_off: u32, container: &DexContainer) -> Vec<String> {
+pub fn parse_parameters(data: &[u8], parameter_off: u32, container: &DexContainer) -> Result<Vec<String>, ParseError> {
     let mut parameters: Vec<String> = Vec::new();
 
-    let mut offset = (parameter_off as usize)
-        .checked_sub(container.header_item.data_off as usize)
-        .expect("String offset is before data section");
+    let mut offset = data_cursor(data, parameter_off, "type_list", container)?;
 
+    let endian = container.header_item.endian();
     // size: u32
-    let size = parse_u32(data, offset);
+    let size = endian.read_u32(data, offset);
     offset += 4;
+    check_count(data, offset, size, 2, "type_list")?;
 
     // list: type_item[] -> type_item: u16
     for _ in 0..size {
-        let type_idx = parse_u16(data, offset);
+        let type_idx = endian.read_u16(data, offset);
         offset += 2;
         // parse string at type_id
-        let string_off = container.type_to_string_offset(type_idx as usize).expect("Type_item string offset failed.");
+        let string_off = container
+            .type_to_string_offset(type_idx as usize)
+            .ok_or(ParseError::IndexOutOfBounds { table: "type_id", index: type_idx as u32 })?;
         parameters.push(parse_string_at_offset(data, string_off, &container.header_item, type_idx as usize).1);
     }
 
-    parameters
+    Ok(parameters)
 }
 
+/// `hidden_api_off` is the file offset of the class's hiddenapi flags, 0 without
 pub fn parse_class_data(
     data: &[u8],
     class_def: &Class_Def_Item,
     container: &DexContainer,
-) -> DexClass {
+    hidden_api_off: u32,
+) -> Result<DexClass, ParseError> {
     if class_def.class_data_off == 0 {
-        return DexClass {
+        return Ok(DexClass {
             name: "<unknown>".to_string(),
             super_class: None,
+            access_flags: AccessFlags::from_bits_retain(class_def.access_flags),
+            interfaces: Vec::new(),
             static_fields: HashMap::new(),
             instance_fields: HashMap::new(),
             methods: HashMap::new(),
-        };
+            source_file: None,
+            annotations: Vec::new(),
+            dex: 0,
+            generation: 0,
+        });
     }
 
-    let mut cursor = (class_def.class_data_off as usize)
-        .checked_sub(container.header_item.data_off as usize)
-        .expect("String offset is before data section");
+    let mut cursor = data_cursor(data, class_def.class_data_off, "class_data_item", container)?;
 
     // 1️⃣ Read field and method counts
     let (static_fields_size, c) = read_uleb128(data, cursor);
@@ -2384,6 +1101,10 @@ pub fn parse_class_data(
     cursor = c;
     let (virtual_methods_size, c) = read_uleb128(data, cursor);
     cursor = c;
+    // An encoded_field is at least 2 bytes and an encoded_method at least 3
+    let fields_size = static_fields_size as u64 + instance_fields_size as u64;
+    let methods_size = direct_methods_size as u64 + virtual_methods_size as u64;
+    check_count(data, cursor, (fields_size * 2 + methods_size * 3).try_into().unwrap_or(u32::MAX), 1, "class_data_item")?;
 
     // 2️⃣ Resolve class name and superclass
     let (_, class_name) = container
@@ -2393,21 +1114,49 @@ pub fn parse_class_data(
 
     let mut super_class_name: Option<String> = None;
     if class_def.superclass_idx != NO_INDEX {
-        super_class_name = Some(container
+        let off = container
             .type_to_string_offset(class_def.superclass_idx as usize)
-            .map(|off| super::strings::parse_string_at_offset(data, off, &container.header_item, 0)).unwrap().1);
+            .ok_or(ParseError::IndexOutOfBounds { table: "type_id", index: class_def.superclass_idx })?;
+        super_class_name = Some(super::strings::parse_string_at_offset(data, off, &container.header_item, 0).1);
+    }
+
+    let mut interfaces = Vec::new();
+    if class_def.interfaces_off != 0 {
+        interfaces = parse_parameters(data, class_def.interfaces_off, container)?;
+    }
+
+    let source_file = (class_def.source_file_idx != NO_INDEX)
+        .then(|| container.string_offset(class_def.source_file_idx as usize))
+        .flatten()
+        .map(|off| super::strings::parse_string_at_offset(data, off, &container.header_item, 0).1);
+
+    let mut annotations = parse_annotations_directory(data, class_def.annotations_off, container)?;
+
+    // The hiddenapi flags are a uleb128 per field and method, in class_data order
+    let mut hidden_api_cursor = data_position(hidden_api_off, container);
+    let mut next_hidden_api = || {
+        let cursor = hidden_api_cursor.as_mut()?;
+        let (flags, next) = read_uleb128(data, *cursor);
+        *cursor = next;
+        Some(HiddenApiFlags(flags))
+    };
+
+    // 3️⃣ Parse static fields, initial values are listed in the same order as the fields
+    let mut static_values = Vec::new();
+    if class_def.static_values_off != 0 {
+        static_values = parse_encoded_array(data, class_def.static_values_off as usize, container)?.0;
     }
 
-    // 3️⃣ Parse static fields
     let mut static_fields: HashMap<String, DexField> = HashMap::new();
-    let mut prev_field_idx = 0;
-    for _ in 0..static_fields_size {
+    let mut prev_field_idx = 0u32;
+    for i in 0..static_fields_size as usize {
         let (field_idx_diff, c) = read_uleb128(data, cursor);
         cursor = c;
         let (access_flags, c) = read_uleb128(data, cursor);
         cursor = c;
-        let field_idx = prev_field_idx + field_idx_diff;
+        let field_idx = prev_field_idx.wrapping_add(field_idx_diff);
         prev_field_idx = field_idx;
+        let hidden_api = next_hidden_api();
 
         if let Some(field_id) = container.field_id_items.get(field_idx as usize) {
             let (_, field_name) = container
@@ -2425,20 +1174,13 @@ pub fn parse_class_data(
 
             let field = DexField {
                 ty: field_type,
-                value: None, // TODO: parse from class_def.static_values_off
+                value: static_values.get(i).cloned(),
                 is_static: true,
+                access_flags: AccessFlags::from_bits_retain(access_flags),
+                annotations: annotations.fields.remove(&field_idx).unwrap_or_default(),
+                hidden_api,
             };
 
-            if class_def.static_values_off != 0 {
-                let (static_values, _) =
-                    parse_encoded_array(data, class_def.static_values_off as usize, container);
-
-                for (i, field) in static_fields.values_mut().enumerate() {
-                    if let Some(val) = static_values.get(i) {
-                        field.value = Some(val.clone());
-                    }
-                }
-            }
             static_fields.insert(field_name.clone(), field);
         }
     }
@@ -2451,8 +1193,9 @@ pub fn parse_class_data(
         cursor = c;
         let (access_flags, c) = read_uleb128(data, cursor);
         cursor = c;
-        let field_idx = prev_field_idx + field_idx_diff;
+        let field_idx = prev_field_idx.wrapping_add(field_idx_diff);
         prev_field_idx = field_idx;
+        let hidden_api = next_hidden_api();
 
         if let Some(field_id) = container.field_id_items.get(field_idx as usize) {
             let (_, field_name) = container
@@ -2474,6 +1217,9 @@ pub fn parse_class_data(
                     ty: field_type,
                     value: None,
                     is_static: false,
+                    access_flags: AccessFlags::from_bits_retain(access_flags),
+                    annotations: annotations.fields.remove(&field_idx).unwrap_or_default(),
+                    hidden_api,
                 },
             );
         }
@@ -2487,10 +1233,12 @@ pub fn parse_class_data(
             *cursor = c;
             let (access_flags, c) = read_uleb128(data, *cursor);
             *cursor = c;
+            let access_flags = AccessFlags::from_bits_retain(access_flags);
             let (code_off, c) = read_uleb128(data, *cursor);
             *cursor = c;
-            let method_idx = *prev_method_idx + method_idx_diff;
+            let method_idx = prev_method_idx.wrapping_add(method_idx_diff);
             *prev_method_idx = method_idx;
+            let hidden_api = next_hidden_api();
 
             if let Some(method_id) = container.method_id_items.get(method_idx as usize) {
                 let (_, method_name) = container
@@ -2499,7 +1247,10 @@ pub fn parse_class_data(
                         super::strings::parse_string_at_offset(data, off, &container.header_item, 0)
                     })
                     .unwrap_or_else(|| (0, "<unknown>".to_string()));
-                let proto = &container.proto_id_items[method_id.proto_idx as usize];
+                let proto = container
+                    .proto_id_items
+                    .get(method_id.proto_idx as usize)
+                    .ok_or(ParseError::IndexOutOfBounds { table: "proto_id", index: method_id.proto_idx as u32 })?;
                 let (_, return_type) = container
                     .type_to_string_offset(proto.return_type_idx as usize)
                     .map(|off| {
@@ -2509,46 +1260,71 @@ pub fn parse_class_data(
 
                 let mut parameters: Vec<String> = Vec::new();
                 if proto.parameters_off != 0 {
-                    parameters = parse_parameters(data, proto.parameters_off, container);
+                    parameters = parse_parameters(data, proto.parameters_off, container)?;
                 }
 
-                let mut instructions: Vec<Instruction> = Vec::new();
-                let mut registers: u16 = 0;
-                if code_off != 0 {
-                    let code_item_off = (code_off as usize)
-                        .checked_sub(container.header_item.data_off as usize)
-                        .expect("String offset is before data section");
-                    let code_item = parse_code_item(data, code_item_off);
-                    instructions = code_item.instructions;
-                    registers = code_item.registers_size;
-                }
-
-                methods.insert(
-                    method_name.clone(),
-                    DexMethod {
-                        name: method_name,
-                        return_type,
-                        parameters,
-                        registers,
-                        instructions: instructions, // TODO: parse actual bytecode from code_off
-                    },
-                );
+                // The code is decoded the first time the method runs, see `decode_method_code`
+                let method = DexMethod {
+                    name: method_name,
+                    return_type,
+                    parameters,
+                    access_flags,
+                    registers: 0,
+                    ins_size: 0,
+                    instructions: Vec::new(),
+                    addresses: Vec::new(),
+                    payloads: Vec::new(),
+                    tries: Vec::new(),
+                    lines: Vec::new(),
+                    locals: Vec::new(),
+                    code_off,
+                    annotations: annotations.methods.remove(&method_idx).unwrap_or_default(),
+                    parameter_annotations: annotations.parameters.remove(&method_idx).unwrap_or_default(),
+                    hidden_api,
+                };
+                methods.insert(method.key(), method);
             }
         }
+        Ok(())
     };
 
     let mut prev_method_idx = 0;
-    parse_methods(direct_methods_size, &mut cursor, &mut prev_method_idx);
+    parse_methods(direct_methods_size, &mut cursor, &mut prev_method_idx)?;
     prev_method_idx = 0;
-    parse_methods(virtual_methods_size, &mut cursor, &mut prev_method_idx);
+    parse_methods(virtual_methods_size, &mut cursor, &mut prev_method_idx)?;
 
-    DexClass {
+    Ok(DexClass {
         name: class_name,
         super_class: super_class_name,
+        access_flags: AccessFlags::from_bits_retain(class_def.access_flags),
+        interfaces,
         static_fields,
         instance_fields,
         methods,
+        source_file,
+        annotations: annotations.class,
+        dex: 0,
+        generation: 0,
+    })
+}
+
+/// Decode the code_item of `method`, one of the methods of `class_name`, into its instructions,
+/// try blocks and debug info. Does nothing for methods without code or already decoded.
+pub fn decode_method_code(data: &[u8], container: &DexContainer, class_name: &str, method: &mut DexMethod) -> Result<(), ParseError> {
+    if !method.code_pending() {
+        return Ok(());
     }
+    let code_item_off = data_cursor(data, method.code_off, "code_item", container)?;
+    let code_item = parse_code_item(data, code_item_off, container.header_item.endian())?;
+    method.tries = resolve_tries(data, &code_item, container);
+    let this_type = (!method.access_flags.contains(AccessFlags::STATIC)).then_some(class_name);
+    (method.lines, method.locals) = parse_debug_info(data, &code_item, container, this_type, &method.parameters);
+    method.addresses = code_item.addresses;
+    method.payloads = code_item.payloads;
+    method.instructions = code_item.instructions;
+    method.registers = code_item.registers_size;
+    method.ins_size = code_item.ins_size;
+    Ok(())
 }
 
 pub fn get_name_of_class(
@@ -2558,9 +1334,13 @@ pub fn get_name_of_class(
     type_id_items: &Vec<u32>,
     string_id_items: &Vec<u32>,
 ) -> String {
-    let string_idx = type_id_items[id] as usize;
-    let string_offset = string_id_items[string_idx];
-    super::strings::parse_string_at_offset(data, string_offset, header_item, string_idx).1
+    let Some(string_idx) = type_id_items.get(id).map(|string_idx| *string_idx as usize) else {
+        return "<unknown>".to_string();
+    };
+    let Some(string_offset) = string_id_items.get(string_idx) else {
+        return "<unknown>".to_string();
+    };
+    super::strings::parse_string_at_offset(data, *string_offset, header_item, string_idx).1
 }
 
 /// Parse a simple const-string getter method into DexValue::String
@@ -2605,3 +1385,108 @@ pub fn parse_const_string_method(
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A payload of each kind in between the instructions is skipped and recorded by address
+    #[test]
+    fn skips_payloads() {
+        let code: Vec<u16> = vec![
+            // packed-switch v0, 0004 // +0004
+            0x002B, 0x0004, 0x0000,
+            // nop, aligning the payload to 4 bytes
+            0x0000,
+            // packed-switch-payload, keys 10 and 11
+            0x0100, 0x0002, 0x000A, 0x0000, 0x0005, 0x0000, 0x0007, 0x0000,
+            // fill-array-data-payload, 3 elements of 1 byte
+            0x0300, 0x0001, 0x0003, 0x0000, 0x0201, 0x0003,
+            // sparse-switch-payload, key -1
+            0x0200, 0x0001, 0xFFFF, 0xFFFF, 0x0003, 0x0000,
+            // return-void
+            0x000E,
+        ];
+        let expected_instructions = format!(
+            "{:?}",
+            [Instruction::PackedSwitch { test_reg: 0, signed_fake_branch_off: 4 }, Instruction::Nop, Instruction::ReturnVoid]
+        );
+        let expected_payloads = vec![
+            (4, Payload::PackedSwitch { first_key: 10, targets: vec![5, 7] }),
+            (12, Payload::FillArrayData { element_width: 1, data: vec![1, 2, 3] }),
+            (18, Payload::SparseSwitch { keys: vec![-1], targets: vec![3] }),
+        ];
+
+        let (instructions, addresses, payloads) = parse_instructions(&code).unwrap();
+        assert_eq!(format!("{:?}", instructions), expected_instructions);
+        assert_eq!(addresses, [0, 3, 24]);
+        assert_eq!(payloads, expected_payloads);
+    }
+
+    /// `dexdump -d` of a method using one instruction per format or operand layout: address,
+    /// the code units in dexdump's byte order and the disassembly
+    const DEXDUMP: &str = concat!(
+        "0000: 12f0                         |0000: const/4 v0, #int -1\n",
+        "0001: 28ff                         |0001: goto 0000 // -0001\n",
+        "0002: 2132                         |0002: array-length v2, v3\n",
+        "0003: b010                         |0003: add-int/2addr v0, v1\n",
+        "0004: 1a00 1000                    |0004: const-string v0, string@0010\n",
+        "0006: 2001 0200                    |0006: instance-of v1, v0, type@0002\n",
+        "0008: 5210 0300                    |0008: iget v0, v1, field@0003\n",
+        "000a: 3221 0300                    |000a: if-eq v1, v2, 000d // +0003\n",
+        "000c: d010 e803                    |000c: add-int/lit16 v0, v1, #int 1000\n",
+        "000e: d800 01fe                    |000e: add-int/lit8 v0, v1, #int -2\n",
+        "0010: 6e30 0400 2103               |0010: invoke-virtual {v1, v2, v3}, method@0004\n",
+        "0013: 7703 0700 0400               |0013: invoke-static/range {v4, v5, v6}, method@0007\n",
+        "0016: fa20 0500 2100 0600          |0016: invoke-polymorphic {v1, v2}, method@0005, proto@0006\n",
+        "001a: 1800 0000 0000 0000 f03f     |001a: const-wide v0, #double 1.00000\n",
+    );
+
+    /// The listing's code units decode to the instructions dexdump shows at its addresses,
+    /// catching decoders that read a format's nibbles or code units in the wrong order
+    #[test]
+    fn decodes_like_dexdump() {
+        let expected = [
+            Instruction::Const4Bit { dst: 0, signed_int: -1 },
+            Instruction::Goto { signed_branch_off: -1 },
+            Instruction::ArrayLength { dst: 2, array_ref_bearing_reg: 3 },
+            Instruction::AddInt2Addr { dst_and_first_src: 0, second_src: 1 },
+            Instruction::ConstString { dest: 0, string_idx: 0x10 },
+            Instruction::InstanceOf { dst: 1, ref_bearing_reg: 0, type_idx: 2 },
+            Instruction::IGet { src: 0, obj: 1, instance_field_idx: 3 },
+            Instruction::TestIfEqual { first_reg: 1, second_reg: 2, signed_branch_off: 3 },
+            Instruction::AddIntLit16 { dst: 0, src: 1, literal: 1000 },
+            Instruction::AddInt8Lit8 { dst: 0, src: 1, signed_int_const: -2 },
+            Instruction::InvokeVirtual { argc: 3, args: vec![1, 2, 3], method_idx: 4 },
+            Instruction::InvokeStaticRange { count: 3, method_idx: 7, first_arg_reg: 4 },
+            Instruction::InvokePolymorphic { argc: 2, args: vec![1, 2], method_idx: 5, proto_idx: 6 },
+            Instruction::ConstWide64Bit { dst: 0, literal: 1.0f64.to_bits() },
+        ];
+        let mut code = Vec::new();
+        let mut listed_addresses = Vec::new();
+        for line in DEXDUMP.lines() {
+            let (address, units) = line.split_once(": ").unwrap();
+            listed_addresses.push(u32::from_str_radix(address, 16).unwrap());
+            // dexdump prints each code unit's bytes in file order
+            for unit in units.split('|').next().unwrap().split_whitespace() {
+                code.push(u16::from_str_radix(unit, 16).unwrap().swap_bytes());
+            }
+        }
+
+        let (instructions, addresses, payloads) = parse_instructions(&code).unwrap();
+        for ((line, instruction), expected) in DEXDUMP.lines().zip(&instructions).zip(&expected) {
+            assert_eq!(format!("{:?}", instruction), format!("{:?}", expected), "{}", line);
+        }
+        assert_eq!(instructions.len(), expected.len());
+        assert_eq!(addresses, listed_addresses);
+        assert!(payloads.is_empty());
+    }
+
+    /// A payload cut off by the end of the code is reported rather than decoded as instructions
+    #[test]
+    fn refuses_truncated_payloads() {
+        // The packed-switch-payload of `skips_payloads` without its last target
+        let code = [0x0100, 0x0002, 0x000A, 0x0000, 0x0005, 0x0000, 0x0007];
+        assert!(matches!(parse_instructions(&code), Err(ParseError::TruncatedPayload { ident: 0x0100, address: 0 })));
+    }
+}
@@ -0,0 +1,119 @@
+/// endian_tag of a dex file in little-endian byte order, read as little-endian
+pub const ENDIAN_CONSTANT: u32 = 0x12345678;
+/// endian_tag of a byte-swapped dex file, read as little-endian
+pub const REVERSE_ENDIAN_CONSTANT: u32 = 0x78563412;
+
+/// Offset of the endian_tag in the header
+const ENDIAN_TAG_OFFSET: usize = 40;
+
+/// Byte order of the fixed-width fields of a dex file. Dex files are little-endian, but the
+/// format allows byte-swapped ones, which say so in their endian_tag. uleb128, MUTF-8 strings and
+/// encoded values are byte streams either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+}
+
+impl Endian {
+    /// Byte order of the dex file given its endian_tag read as little-endian
+    pub fn from_tag(endian_tag: u32) -> Self {
+        if endian_tag == REVERSE_ENDIAN_CONSTANT {
+            Endian::Big
+        } else {
+            Endian::Little
+        }
+    }
+
+    /// Byte order of the dex file starting at `bytes`, little-endian if it is too short to tell
+    pub fn of_dex(bytes: &[u8]) -> Self {
+        bytes
+            .get(ENDIAN_TAG_OFFSET..ENDIAN_TAG_OFFSET + 4)
+            .map_or(Endian::Little, |tag| Self::from_tag(u32::from_le_bytes(tag.try_into().unwrap())))
+    }
+
+    pub fn u16(self, bytes: [u8; 2]) -> u16 {
+        match self {
+            Endian::Little => u16::from_le_bytes(bytes),
+            Endian::Big => u16::from_be_bytes(bytes),
+        }
+    }
+
+    pub fn u32(self, bytes: [u8; 4]) -> u32 {
+        match self {
+            Endian::Little => u32::from_le_bytes(bytes),
+            Endian::Big => u32::from_be_bytes(bytes),
+        }
+    }
+
+    pub fn u16_bytes(self, value: u16) -> [u8; 2] {
+        match self {
+            Endian::Little => value.to_le_bytes(),
+            Endian::Big => value.to_be_bytes(),
+        }
+    }
+
+    pub fn u32_bytes(self, value: u32) -> [u8; 4] {
+        match self {
+            Endian::Little => value.to_le_bytes(),
+            Endian::Big => value.to_be_bytes(),
+        }
+    }
+
+    /// Bytes past the end of the data are read as zero, like `parse_u32`
+    pub fn read_u16(self, data: &[u8], offset: usize) -> u16 {
+        let mut bytes = [0; 2];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = data.get(offset + i).copied().unwrap_or(0);
+        }
+        self.u16(bytes)
+    }
+
+    /// Bytes past the end of the data are read as zero, like `parse_u32`
+    pub fn read_u32(self, data: &[u8], offset: usize) -> u32 {
+        let mut bytes = [0; 4];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = data.get(offset + i).copied().unwrap_or(0);
+        }
+        self.u32(bytes)
+    }
+
+    /// Swap the bytes of the 16-bit code units of `insns` if this is big-endian, to write code
+    /// in this byte order. Wider literals are made of consecutive code units, low-order unit
+    /// first, so swapping the units is enough.
+    pub fn swap_code_units(self, insns: &mut [u8]) {
+        if self == Endian::Big {
+            for unit in insns.chunks_exact_mut(2) {
+                unit.swap(0, 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parser::Parser;
+    use crate::selftest::{build_micro_dex, self_test_cases};
+
+    /// The micro dex byte-swapped has to parse into the same classes as the little-endian one
+    #[test]
+    fn parses_byte_swapped_dex() {
+        let [little, big] = [Endian::Little, Endian::Big].map(|endian| {
+            let mut parser = Parser::new(build_micro_dex(&self_test_cases(), endian), false);
+            parser.persist = false;
+            parser.verify_integrity = true;
+            parser.parse().unwrap();
+            for class in parser.classes.clone() {
+                for key in class.methods.keys() {
+                    parser.decoded_method(&class, key).unwrap();
+                }
+            }
+            assert!(parser.errors.is_empty(), "{:?}: {:?}", endian, parser.errors);
+            // Compared as values, the methods of a class are in a HashMap
+            serde_json::to_value(&parser.classes).unwrap()
+        });
+        assert_eq!(little, big);
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+
+/// Errors found while parsing a dex file
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseError {
+    /// The string_data_item of `string_id` points outside of the data section
+    StringOffsetOutOfBounds { string_id: usize, offset: u32 },
+    /// The decoded string doesn't have the utf16 length stated in its string_data_item
+    StringSizeMismatch { string_id: usize, expected: u32, actual: u32 },
+    /// The string data isn't terminated within the bounds implied by its utf16 length
+    UnterminatedString { string_id: usize },
+    /// The string data isn't valid (M)UTF-8
+    InvalidStringData { string_id: usize },
+    /// A later dex file of a multi-dex extension defines a class again, the first one is kept
+    DuplicateClass { name: String, dex: usize },
+    /// The file is a compact dex, the format ART optimizes dex files into on the device, which
+    /// only the ART of the same version can read
+    CompactDex { version: String },
+    /// The file doesn't start with the dex magic
+    NotADexFile,
+    /// The dex version is older or newer than the versions the parser knows the layout of
+    UnsupportedVersion { version: String },
+    /// The file is shorter than the `expected` size its header states
+    Truncated { expected: u32, actual: u32 },
+    /// The adler32 checksum of the file doesn't match the one in its header
+    ChecksumMismatch { expected: u32, actual: u32 },
+    /// The SHA-1 of the file doesn't match the signature in its header
+    SignatureMismatch,
+    /// A section the header lists doesn't fit into the file
+    SectionOutOfBounds { section: &'static str, offset: u32, size: u32 },
+    /// The file offset of an item doesn't point into the data section
+    OffsetOutOfBounds { item: &'static str, offset: u32 },
+    /// An index operand or field refers past the end of its table
+    IndexOutOfBounds { table: &'static str, index: u32 },
+    /// An item, or the number of entries it states, runs past the end of the data section
+    UnexpectedEnd { item: &'static str },
+    /// A uleb128 or sleb128 of the `item` is cut off by the end of the data section, takes more
+    /// than 5 bytes or doesn't fit into 32 bits
+    MalformedLeb128 { item: &'static str },
+    /// Arrays and annotations in an encoded_value are nested deeper than any real dex nests them
+    ValueTooDeep { depth: usize },
+    /// A code_item states more code units than the data section has left
+    TruncatedCode { insns_size: u32 },
+    /// The last instruction of a method's code is cut off by the end of the code
+    TruncatedInstruction { opcode: u8, address: u32 },
+    /// A switch or fill-array-data payload states more entries than the code has left
+    TruncatedPayload { ident: u16, address: u32 },
+    /// The class_def or class_data_item of `name` is malformed, the class is left out
+    MalformedClass { name: String, error: Box<ParseError> },
+    /// A file of the cache in `out/` couldn't be written or read, e.g. because the disk is full
+    Cache { file: String, error: String },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::StringOffsetOutOfBounds { string_id, offset } => {
+                write!(f, "string_id {} points outside of the data section (offset 0x{:X})", string_id, offset)
+            }
+            ParseError::StringSizeMismatch { string_id, expected, actual } => {
+                write!(f, "string_id {} has a utf16 size of {} but decodes to {} code units", string_id, expected, actual)
+            }
+            ParseError::UnterminatedString { string_id } => {
+                write!(f, "string_id {} is not terminated", string_id)
+            }
+            ParseError::InvalidStringData { string_id } => {
+                write!(f, "string_id {} contains invalid MUTF-8 data", string_id)
+            }
+            ParseError::DuplicateClass { name, dex } => {
+                write!(f, "{} of dex file {} is already defined in an earlier dex file", name, dex + 1)
+            }
+            ParseError::CompactDex { version } => {
+                write!(f, "compact dex files (cdex {}) are not supported, install the extension's APK or its original dex file", version)
+            }
+            ParseError::NotADexFile => write!(f, "not a dex file"),
+            ParseError::UnsupportedVersion { version } => write!(f, "dex version {} is not supported", version),
+            ParseError::Truncated { expected, actual } => {
+                write!(f, "the dex file is truncated, its header states {} bytes but there are {}", expected, actual)
+            }
+            ParseError::ChecksumMismatch { expected, actual } => {
+                write!(f, "the dex file is corrupt, its checksum is 0x{:08X} but its header states 0x{:08X}", actual, expected)
+            }
+            ParseError::SignatureMismatch => write!(f, "the dex file is corrupt, its SHA-1 doesn't match its signature"),
+            ParseError::SectionOutOfBounds { section, offset, size } => {
+                write!(f, "the {} section at 0x{:X} ({} bytes) lies outside of the dex file", section, offset, size)
+            }
+            ParseError::OffsetOutOfBounds { item, offset } => {
+                write!(f, "the {} at 0x{:X} lies outside of the data section", item, offset)
+            }
+            ParseError::IndexOutOfBounds { table, index } => write!(f, "{} {} doesn't exist", table, index),
+            ParseError::UnexpectedEnd { item } => write!(f, "the {} runs past the end of the data section", item),
+            ParseError::MalformedLeb128 { item } => write!(f, "the {} has a malformed LEB128 value", item),
+            ParseError::ValueTooDeep { depth } => write!(f, "an encoded_value nests more than {} levels deep", depth),
+            ParseError::TruncatedCode { insns_size } => {
+                write!(f, "the code_item states {} code units but the data section ends before them", insns_size)
+            }
+            ParseError::TruncatedInstruction { opcode, address } => {
+                write!(f, "the instruction 0x{:02X} at address {} runs past the end of the code", opcode, address)
+            }
+            ParseError::TruncatedPayload { ident, address } => {
+                write!(f, "the payload 0x{:04X} at address {} runs past the end of the code", ident, address)
+            }
+            ParseError::MalformedClass { name, error } => write!(f, "{} was left out: {}", name, error),
+            ParseError::Cache { file, error } => write!(f, "the cache file {} is unusable: {}", file, error),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl ParseError {
+    /// Error of the cache file `file`
+    pub fn cache(file: &str, error: impl fmt::Display) -> Self {
+        ParseError::Cache { file: file.to_string(), error: error.to_string() }
+    }
+}
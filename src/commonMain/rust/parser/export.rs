@@ -0,0 +1,157 @@
+// The whole parsed extension as one JSON document: the header and strings of each dex file and
+// every class with its fields and decoded methods. Maps are sorted and the code decoded, so two
+// versions of an extension export alike except where they differ, which tools can diff.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::parser::endian::Endian;
+use crate::parser::parser::{DexTables, Parser};
+use crate::types::{AccessFlags, DexAnnotation, DexClass, DexField, DexMethod, Header_Item};
+
+/// Version of the document's layout, bumped whenever a field changes meaning or goes away
+pub const EXPORT_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DexExport<'a> {
+    pub format_version: u32,
+    /// classes.dex first, then classes2.dex and on
+    pub dex_files: Vec<DexFileExport>,
+    /// By name
+    pub classes: Vec<ClassExport<'a>>,
+    /// Problems the parser recovered from
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DexFileExport {
+    /// e.g. `035`
+    pub version: String,
+    pub endian: &'static str,
+    pub checksum: u32,
+    /// SHA-1 of the file past the signature, in hex
+    pub signature: String,
+    pub file_size: u32,
+    pub type_ids: usize,
+    pub proto_ids: usize,
+    pub field_ids: usize,
+    pub method_ids: usize,
+    pub class_defs: usize,
+    pub method_handles: usize,
+    pub call_sites: usize,
+    /// Every string, by string_id
+    pub strings: Vec<String>,
+}
+
+impl DexFileExport {
+    fn new(tables: DexTables) -> Self {
+        let container = tables.container;
+        let header: &Header_Item = &container.header_item;
+        DexFileExport {
+            version: String::from_utf8_lossy(&header.magic[4..7]).into_owned(),
+            endian: if header.endian() == Endian::Little { "little" } else { "big" },
+            checksum: header.checksum,
+            signature: header.signature.iter().map(|byte| format!("{:02x}", byte)).collect(),
+            file_size: header.file_size,
+            type_ids: container.type_id_items.len(),
+            proto_ids: container.proto_id_items.len(),
+            field_ids: container.field_id_items.len(),
+            method_ids: container.method_id_items.len(),
+            class_defs: container.class_defs_items.len(),
+            method_handles: container.method_handle_items.len(),
+            call_sites: container.call_site_items.len(),
+            strings: tables.strings.iter().map(|string| string.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClassExport<'a> {
+    pub name: &'a str,
+    pub super_class: Option<&'a str>,
+    pub access_flags: AccessFlags,
+    pub interfaces: &'a [String],
+    pub source_file: Option<&'a str>,
+    /// Index of the dex file the class comes from
+    pub dex: usize,
+    pub annotations: &'a [DexAnnotation],
+    pub static_fields: BTreeMap<&'a str, &'a DexField>,
+    pub instance_fields: BTreeMap<&'a str, &'a DexField>,
+    /// By key, with their code decoded. Methods whose code can't be decoded keep it pending.
+    pub methods: BTreeMap<&'a str, DexMethod>,
+}
+
+impl<'a> ClassExport<'a> {
+    fn new(parser: &Parser, class: &'a DexClass) -> Self {
+        let methods = class.methods.iter()
+            .map(|(key, method)| {
+                let method = parser.decoded(class, method).map_or_else(|_| method.clone(), |method| method.into_owned());
+                (key.as_str(), method)
+            })
+            .collect();
+
+        ClassExport {
+            name: &class.name,
+            super_class: class.super_class.as_deref(),
+            access_flags: class.access_flags,
+            interfaces: &class.interfaces,
+            source_file: class.source_file.as_deref(),
+            dex: class.dex,
+            annotations: &class.annotations,
+            static_fields: class.static_fields.iter().map(|(name, field)| (name.as_str(), field)).collect(),
+            instance_fields: class.instance_fields.iter().map(|(name, field)| (name.as_str(), field)).collect(),
+            methods,
+        }
+    }
+}
+
+impl<'a> DexExport<'a> {
+    /// Export of the parsed extension, no dex files before parsing
+    pub fn new(parser: &'a Parser) -> Self {
+        let dex_files = (0..=parser.secondary_dexes.len())
+            .map_while(|dex| parser.tables(dex))
+            .map(DexFileExport::new)
+            .collect();
+        let mut classes: Vec<ClassExport> = parser.classes.iter().map(|class| ClassExport::new(parser, class)).collect();
+        classes.sort_by_key(|class| class.name);
+
+        DexExport {
+            format_version: EXPORT_FORMAT_VERSION,
+            dex_files,
+            classes,
+            errors: parser.errors.iter().map(|error| error.to_string()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex, self_test_cases, SELF_TEST_CLASS, SELF_TEST_STRING};
+
+    /// Exporting twice gives the same document, with the format version, the strings and every
+    /// method decoded
+    #[test]
+    fn exports_the_micro_dex() {
+        let cases = self_test_cases();
+        let parser = parse_micro_dex(&cases);
+        let json = parser.to_json();
+        assert_eq!(parser.to_json(), json);
+
+        let export: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(export["formatVersion"], EXPORT_FORMAT_VERSION);
+        assert_eq!(export["dexFiles"][0]["version"], "035");
+        assert!(export["dexFiles"][0]["strings"].as_array().unwrap().iter().any(|string| string == SELF_TEST_STRING));
+
+        let class = &export["classes"][0];
+        assert_eq!(class["name"], SELF_TEST_CLASS);
+        let methods = class["methods"].as_object().unwrap();
+        assert_eq!(methods.len(), cases.len());
+        for (key, method) in methods {
+            assert!(method["instructions"].as_array().is_some_and(|instructions| !instructions.is_empty()), "{}", key);
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Which classes of an extension an install parses. Extensions bundle the libraries they use,
+// kotlinx, okio, jsoup and the like, which often make up most of their classes, yet the
+// interpreter runs its own versions of them. Restricting an install to the extension's own
+// packages skips decoding and persisting the rest.
+
+use lazy_static::lazy_static;
+use std::sync::{Arc, Mutex};
+
+lazy_static! {
+    static ref CLASS_FILTER: Mutex<ClassFilter> = Mutex::new(ClassFilter::default());
+}
+
+/// Whether to parse a class given its descriptor
+type ClassPredicate = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Decides by descriptor which classes to parse, the default one parses every class
+#[derive(Clone, Default)]
+pub struct ClassFilter {
+    /// Descriptor prefixes of the included packages, e.g. `Leu/kanade/tachiyomi/extension/`
+    prefixes: Vec<String>,
+    predicate: Option<ClassPredicate>,
+}
+
+impl ClassFilter {
+    /// Include the classes of `packages`, Java package names like `eu.kanade.tachiyomi.extension`,
+    /// and of their subpackages. Blank names are ignored, no packages include every class.
+    pub fn packages<S: AsRef<str>>(packages: &[S]) -> Self {
+        let prefixes = packages
+            .iter()
+            .map(|package| package.as_ref().trim().trim_matches('.'))
+            .filter(|package| !package.is_empty())
+            .map(|package| format!("L{}/", package.replace('.', "/")))
+            .collect();
+        Self { prefixes, predicate: None }
+    }
+
+    /// Include the classes `predicate` accepts given their descriptor
+    #[cfg(test)]
+    pub fn predicate(predicate: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
+        Self { prefixes: Vec::new(), predicate: Some(Arc::new(predicate)) }
+    }
+
+    /// Whether every class is included
+    pub fn is_all(&self) -> bool {
+        self.prefixes.is_empty() && self.predicate.is_none()
+    }
+
+    /// Whether the class `descriptor` is parsed
+    pub fn includes(&self, descriptor: &str) -> bool {
+        self.is_all()
+            || self.prefixes.iter().any(|prefix| descriptor.starts_with(prefix.as_str()))
+            || self.predicate.as_ref().is_some_and(|predicate| predicate(descriptor))
+    }
+}
+
+/// Restrict the installs started from now on to the classes `filter` includes
+pub fn set_class_filter(filter: ClassFilter) {
+    *CLASS_FILTER.lock().unwrap() = filter;
+}
+
+/// Filter of the installs, see `set_class_filter`
+pub fn class_filter() -> ClassFilter {
+    CLASS_FILTER.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex_with, self_test_cases};
+
+    /// Filters that include and exclude the micro dex class, a package has to match as a whole,
+    /// `mihonx.self` doesn't include `mihonx.selftest`
+    #[test]
+    fn filters_classes_by_package() {
+        let filters = [
+            ("all", ClassFilter::default(), 1),
+            ("package", ClassFilter::packages(&["mihonx.selftest"]), 1),
+            ("parent package", ClassFilter::packages(&["mihonx"]), 1),
+            ("other package", ClassFilter::packages(&["mihonx.other", "  "]), 0),
+            ("package prefix", ClassFilter::packages(&["mihonx.self"]), 0),
+            ("predicate", ClassFilter::predicate(|descriptor| descriptor.ends_with("/SelfTest;")), 1),
+        ];
+        for (name, filter, expected) in filters {
+            let parser = parse_micro_dex_with(&self_test_cases(), |parser| parser.class_filter = filter);
+            assert_eq!(parser.classes.len(), expected, "{}", name);
+        }
+    }
+}
@@ -1,5 +1,84 @@
+use sha1::{Digest, Sha1};
+
+use crate::parser::endian::Endian;
+use crate::parser::error::ParseError;
 use crate::types::Header_Item;
 
+const DEX_MAGIC: &[u8] = b"dex\n";
+const COMPACT_DEX_MAGIC: &[u8] = b"cdex";
+
+/// Oldest and newest dex versions the parser reads
+pub const MIN_DEX_VERSION: u32 = 35;
+pub const MAX_DEX_VERSION: u32 = 41;
+/// Version that added method handles and call sites
+pub const METHOD_HANDLES_DEX_VERSION: u32 = 38;
+/// First version with a hiddenapi_class_data_item
+pub const HIDDENAPI_DEX_VERSION: u32 = 39;
+/// Version that put dex files into a container sharing one data area, see `Parser::parse`
+pub const CONTAINER_DEX_VERSION: u32 = 41;
+
+/// Size of the header_item, 041 appends the container's size and the header's offset
+pub const HEADER_SIZE: usize = 0x70;
+pub const CONTAINER_HEADER_SIZE: usize = 0x78;
+
+/// Check that `bytes` are a dex file the parser can read before anything else is read from them.
+/// Returns its version, e.g. 35 for `dex\n035\0`.
+pub fn check_magic(bytes: &[u8]) -> Result<u32, ParseError> {
+    // The magic is followed by a 3 digit version and a NUL
+    let version = bytes.get(4..7).map(|version| String::from_utf8_lossy(version).into_owned()).unwrap_or_default();
+    if bytes.starts_with(COMPACT_DEX_MAGIC) {
+        return Err(ParseError::CompactDex { version });
+    }
+    if !bytes.starts_with(DEX_MAGIC) {
+        return Err(ParseError::NotADexFile);
+    }
+
+    match version.parse() {
+        Ok(number) if (MIN_DEX_VERSION..=MAX_DEX_VERSION).contains(&number) => Ok(number),
+        _ => Err(ParseError::UnsupportedVersion { version }),
+    }
+}
+
+/// Offsets of the checksum and the signature in the header, each covers the file after itself
+const CHECKSUM_OFFSET: usize = 8;
+const SIGNATURE_OFFSET: usize = 12;
+const FILE_SIZE_OFFSET: usize = 32;
+
+/// Check the dex file starting at `bytes` against the adler32 checksum and the SHA-1 signature
+/// of its header. Both cover the file up to its `file_size`, which can be followed by the
+/// further dex files of a 041 container.
+pub fn verify_integrity(bytes: &[u8]) -> Result<(), ParseError> {
+    let endian = Endian::of_dex(bytes);
+    let file_size = bytes
+        .get(FILE_SIZE_OFFSET..FILE_SIZE_OFFSET + 4)
+        .map(|field| endian.u32(field.try_into().unwrap()))
+        .ok_or(ParseError::Truncated { expected: FILE_SIZE_OFFSET as u32 + 4, actual: bytes.len() as u32 })?;
+    let file = bytes
+        .get(..file_size as usize)
+        .filter(|file| file.len() > FILE_SIZE_OFFSET)
+        .ok_or(ParseError::Truncated { expected: file_size, actual: bytes.len() as u32 })?;
+
+    let expected = endian.u32(file[CHECKSUM_OFFSET..SIGNATURE_OFFSET].try_into().unwrap());
+    let actual = adler32(&file[SIGNATURE_OFFSET..]);
+    if expected != actual {
+        return Err(ParseError::ChecksumMismatch { expected, actual });
+    }
+
+    if Sha1::digest(&file[FILE_SIZE_OFFSET..])[..] != file[SIGNATURE_OFFSET..FILE_SIZE_OFFSET] {
+        return Err(ParseError::SignatureMismatch);
+    }
+    Ok(())
+}
+
+pub fn adler32(bytes: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
 pub fn parse_header_item(bytes: &mut Vec<u8>) -> Header_Item {
     Header_Item {
         magic: bytes.drain(0..8).collect::<Vec<u8>>().try_into().unwrap(),
@@ -25,5 +104,7 @@ pub fn parse_header_item(bytes: &mut Vec<u8>) -> Header_Item {
         class_defs_off: u32::from_le_bytes(bytes.drain(0..4).collect::<Vec<u8>>().try_into().unwrap()),
         data_size: u32::from_le_bytes(bytes.drain(0..4).collect::<Vec<u8>>().try_into().unwrap()),
         data_off: u32::from_le_bytes(bytes.drain(0..4).collect::<Vec<u8>>().try_into().unwrap()),
+        container_size: 0,
+        header_offset: 0,
     }
 }
\ No newline at end of file
@@ -1,9 +1,8 @@
 use crate::types::{Class_Def_Item, Field_Id_Item, Method_Id_Item, Proto_Id_Item};
-use crate::utils::convert_vec_u8_to_vec_u32;
 
 pub fn parse_ids_array(bytes: &mut Vec<u8>, length: u32) -> Vec<u32> {
-    let mut id_bytes: Vec<u8> = bytes.drain(0..(length as usize * 4)).collect();
-    convert_vec_u8_to_vec_u32(&mut id_bytes).expect("Vec<u8> to Vec<u32> conversion failed.")
+    let id_bytes: Vec<u8> = bytes.drain(0..(length as usize * 4)).collect();
+    id_bytes.chunks_exact(4).map(|c| u32::from_le_bytes(c.try_into().unwrap())).collect()
 }
 
 pub fn parse_proto_id_array(bytes: &mut Vec<u8>, length: u32) -> Vec<Proto_Id_Item> {
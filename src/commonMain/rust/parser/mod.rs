@@ -1,6 +1,13 @@
+pub mod bytes;
+pub mod endian;
+pub mod error;
+pub mod export;
+pub mod filter;
 pub mod header;
 pub mod ids;
+pub mod opcodes;
 pub mod class;
+pub mod signature;
 pub mod strings;
 pub mod uleb;
 pub mod parser;
\ No newline at end of file
@@ -0,0 +1,119 @@
+// Format of every Dalvik instruction, which says how its operands are laid out in its code
+// units and how many code units it takes, e.g. 0x03 move/16 is format 32x and takes 3 code
+// units. The decoder reads the operands by format, so every instruction of a format is read
+// alike and the decoder stays in sync with the instruction stream.
+
+/// Instruction formats as the Dalvik bytecode spec names them: the number of code units, the
+/// number of registers and the kind of the extra operand, e.g. 22c takes 2 code units, has 2
+/// registers and a constant pool index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    F10x,
+    F12x,
+    F11n,
+    F11x,
+    F10t,
+    F20t,
+    F22x,
+    F21t,
+    F21s,
+    F21h,
+    F21c,
+    F23x,
+    F22b,
+    F22t,
+    F22s,
+    F22c,
+    F30t,
+    F32x,
+    F31i,
+    F31t,
+    F31c,
+    F35c,
+    F3rc,
+    F45cc,
+    F4rcc,
+    F51l,
+}
+
+impl Format {
+    /// Width in 16-bit code units
+    pub fn width(self) -> usize {
+        match self {
+            Format::F10x | Format::F12x | Format::F11n | Format::F11x | Format::F10t => 1,
+            Format::F20t
+            | Format::F22x
+            | Format::F21t
+            | Format::F21s
+            | Format::F21h
+            | Format::F21c
+            | Format::F23x
+            | Format::F22b
+            | Format::F22t
+            | Format::F22s
+            | Format::F22c => 2,
+            Format::F30t | Format::F32x | Format::F31i | Format::F31t | Format::F31c | Format::F35c | Format::F3rc => 3,
+            Format::F45cc | Format::F4rcc => 4,
+            Format::F51l => 5,
+        }
+    }
+}
+
+/// Format of the instruction with `opcode`, `None` for unused opcodes
+pub fn instruction_format(opcode: u8) -> Option<Format> {
+    let format = match opcode {
+        0x00 | 0x0E => Format::F10x,
+        0x01 | 0x04 | 0x07 | 0x21 | 0x7B..=0x8F | 0xB0..=0xCF => Format::F12x,
+        0x12 => Format::F11n,
+        0x0A..=0x0D | 0x0F..=0x11 | 0x1D | 0x1E | 0x27 => Format::F11x,
+        0x28 => Format::F10t,
+        0x29 => Format::F20t,
+        0x02 | 0x05 | 0x08 => Format::F22x,
+        0x38..=0x3D => Format::F21t,
+        0x13 | 0x16 => Format::F21s,
+        0x15 | 0x19 => Format::F21h,
+        0x1A | 0x1C | 0x1F | 0x22 | 0x60..=0x6D | 0xFE | 0xFF => Format::F21c,
+        0x2D..=0x31 | 0x44..=0x51 | 0x90..=0xAF => Format::F23x,
+        0xD8..=0xE2 => Format::F22b,
+        0x32..=0x37 => Format::F22t,
+        0xD0..=0xD7 => Format::F22s,
+        0x20 | 0x23 | 0x52..=0x5F => Format::F22c,
+        0x2A => Format::F30t,
+        0x03 | 0x06 | 0x09 => Format::F32x,
+        0x14 | 0x17 => Format::F31i,
+        0x26 | 0x2B | 0x2C => Format::F31t,
+        0x1B => Format::F31c,
+        0x24 | 0x6E..=0x72 | 0xFC => Format::F35c,
+        0x25 | 0x74..=0x78 | 0xFD => Format::F3rc,
+        0xFA => Format::F45cc,
+        0xFB => Format::F4rcc,
+        0x18 => Format::F51l,
+        _ => return None,
+    };
+    Some(format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::class::parse_instructions;
+    use crate::types::Instruction;
+
+    /// Every opcode followed by a return-void has the return-void land right after the opcode's
+    /// width, catching decoders that consume too few or too many code units
+    #[test]
+    fn decodes_each_opcode_to_its_width() {
+        for opcode in 0..=u8::MAX {
+            let Some(width) = instruction_format(opcode).map(Format::width) else {
+                continue;
+            };
+            let mut code = vec![opcode as u16];
+            code.resize(width, 0);
+            code.push(0x000E);
+
+            let (instructions, addresses, _) = parse_instructions(&code).unwrap_or_else(|error| panic!("0x{:02X} ({})", opcode, error));
+            assert!(matches!(instructions.last(), Some(Instruction::ReturnVoid)), "0x{:02X} decoded as {:?}", opcode, instructions);
+            assert_eq!(addresses.last(), Some(&(width as u32)), "0x{:02X}", opcode);
+        }
+    }
+}
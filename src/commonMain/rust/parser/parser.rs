@@ -1,128 +1,584 @@
-use crate::types::{Class_Def_Item, DexClass, DexContainer, Field_Id_Item, Header_Item, Method_Id_Item, Proto_Id_Item};
-use crate::utils::{convert_vec_u8_to_vec_u32, save_container_to_file, save_data_to_file, save_strings_to_file};
-use crate::parser::uleb::read_uleb128;
+use crate::types::{CallSiteItem, Class_Def_Item, DexClass, DexContainer, DexMethod, DexValue, Field_Id_Item, Header_Item, Instruction, MethodHandleItem, Method_Id_Item, Proto_Id_Item};
+use std::collections::HashSet;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::utils::{save_container_to_file, save_data_to_file, save_strings_to_file};
+use crate::parser::bytes::DexBytes;
+use crate::parser::endian::Endian;
+use crate::parser::error::ParseError;
+use crate::parser::export::DexExport;
+use crate::parser::filter::{class_filter, ClassFilter};
+use crate::parser::header::{check_magic, verify_integrity, CONTAINER_DEX_VERSION, CONTAINER_HEADER_SIZE, HEADER_SIZE, HIDDENAPI_DEX_VERSION, METHOD_HANDLES_DEX_VERSION};
+use crate::parser::strings::{try_parse_string_at_offset, StringPool};
 use crate::parser::class::get_name_of_class;
 use crate::parser::class::parse_class_data;
+use crate::parser::class::decode_method_code;
+use crate::parser::class::parse_encoded_array;
 use crate::utils::save_class_to_file;
 use crate::utils::load_classes_from_file;
 use crate::utils::load_container_from_file;
 use crate::utils::load_data_from_file;
 use crate::utils::load_strings_from_file;
+use crate::utils::{load_generation, save_generation};
+use crate::cache::CACHE_ROOT;
+
+/// Names of the classes of the cached extension, to tell when one of their files went missing
+const CLASSES_FILE: &str = "extension_classes.txt";
+
+const TYPE_CALL_SITE_ID_ITEM: u16 = 0x0007;
+const TYPE_METHOD_HANDLE_ITEM: u16 = 0x0008;
+const TYPE_HIDDENAPI_CLASS_DATA_ITEM: u16 = 0xF000;
+
+/// classes2.dex and on of a multi-dex extension, the tables the instructions of its classes
+/// index into. classes.dex keeps its tables in the parser itself.
+#[derive(Debug, Clone)]
+pub struct DexFile {
+    pub data: DexBytes,
+    pub container: DexContainer,
+    pub strings: Vec<Arc<str>>,
+}
+
+/// The tables of one dex file, which resolve the indices its instructions carry. Names come
+/// from the interned strings, resolving them doesn't allocate.
+#[derive(Clone, Copy)]
+pub struct DexTables<'a> {
+    pub data: &'a [u8],
+    pub container: &'a DexContainer,
+    pub strings: &'a [Arc<str>],
+}
+
+impl DexTables<'_> {
+    /// Descriptor of a type_id, e.g. `Ljava/lang/String;`
+    pub fn resolve_type(&self, type_idx: usize) -> Option<Arc<str>> {
+        self.strings.get(self.container.type_to_string_id(type_idx)?).cloned()
+    }
+
+    /// Resolve a method_id into the descriptor of the class it is referenced on and its name
+    pub fn resolve_method(&self, method_idx: u16) -> Option<(Arc<str>, Arc<str>)> {
+        let method_id = self.container.method_id_items.get(method_idx as usize)?;
+        let class_name = self.resolve_type(method_id.class_idx as usize)?;
+        let method_name = self.strings.get(method_id.name_idx as usize)?;
+
+        Some((class_name, method_name.clone()))
+    }
+
+    /// Descriptor of a method_id, e.g. `(Ljava/lang/String;I)V`
+    pub fn resolve_method_descriptor(&self, method_idx: u16) -> Option<String> {
+        let method_id = self.container.method_id_items.get(method_idx as usize)?;
+        let proto = self.container.proto_id_items.get(method_id.proto_idx as usize)?;
+        let return_type = self.resolve_type(proto.return_type_idx as usize)?;
+
+        let mut descriptor = String::from("(");
+        if proto.parameters_off != 0 {
+            // type_list: size u32, then a u16 type_idx per parameter
+            let endian = self.container.header_item.endian();
+            let offset = (proto.parameters_off as usize).checked_sub(self.container.header_item.data_off as usize)?;
+            let size = (endian.read_u32(self.data, offset) as usize).min(self.data.len() / 2);
+            for i in 0..size {
+                descriptor.push_str(&self.resolve_type(endian.read_u16(self.data, offset + 4 + i * 2) as usize)?);
+            }
+        }
+        descriptor.push(')');
+        descriptor.push_str(&return_type);
+        Some(descriptor)
+    }
+
+    /// Resolve a field_id into its declaring class descriptor and field name
+    pub fn resolve_field(&self, field_idx: u16) -> Option<(Arc<str>, Arc<str>)> {
+        let field_id = self.container.field_id_items.get(field_idx as usize)?;
+        let class_name = self.resolve_type(field_id.class_idx as usize)?;
+        let field_name = self.strings.get(field_id.name_idx as usize)?;
+
+        Some((class_name, field_name.clone()))
+    }
+
+    /// Declared type of a field_id
+    pub fn resolve_field_type(&self, field_idx: u16) -> Option<Arc<str>> {
+        let field_id = self.container.field_id_items.get(field_idx as usize)?;
+        self.resolve_type(field_id.type_idx as usize)
+    }
+}
 
 pub struct Parser {
-    pub bytes: Vec<u8>,
+    pub bytes: DexBytes,
     pub debug_enabled: bool,
-    pub data: Vec<u8>,
+    /// The data section, a view into `bytes`
+    pub data: DexBytes,
     pub container: Option<DexContainer>,
-    pub strings: Vec<String>,
+    /// Every string of classes.dex, interned once while parsing and resolved by string_id
+    pub strings: Vec<Arc<str>>,
+    /// Version of the dex file, e.g. 35, known once parsing started
+    pub version: u32,
+    /// Offset of the header of the dex file to parse in `bytes`, other than 0 for the further
+    /// dex files of a 041 container
+    pub header_offset: usize,
+    /// Byte order of the dex file, known once parsing started
+    pub endian: Endian,
+    /// Bytes of classes2.dex and on, parsed after `bytes`
+    pub secondary_bytes: Vec<DexBytes>,
+    pub secondary_dexes: Vec<DexFile>,
+    /// Classes of every dex file, the first definition of a class wins like on Android
     pub classes: Vec<DexClass>,
     pub cursor: usize,
+    /// Write the parsed extension to the on-disk cache
+    pub persist: bool,
+    /// Directory of the on-disk cache, `out/` of the working directory unless set otherwise
+    pub cache_dir: PathBuf,
+    /// Check the checksum and signature of every dex file before parsing it, set when installing
+    pub verify_integrity: bool,
+    /// Classes to parse, every class unless an install was restricted with `set_class_filter`
+    pub class_filter: ClassFilter,
+    /// Pool the strings are interned into, shared with the other parsers of a batch install
+    pub string_pool: Option<StringPool>,
+    /// Recoverable errors found during the last parse
+    pub errors: Vec<ParseError>,
+    /// Install generation of the classes, see `DexClass.generation`
+    pub generation: u64,
 }
 
 #[macro_export]
 macro_rules! parser_log {
     ($parser:expr, $($arg:tt)*) => {
         if $parser.debug_enabled {
-            println!($($arg)*);
+            $crate::logging::log(&format!($($arg)*));
         }
     };
 }
 
 impl Parser {
-    pub fn new(bytes: Vec<u8>, debug_enabled: bool) -> Self {
+    pub fn new(bytes: impl Into<DexBytes>, debug_enabled: bool) -> Self {
         Self {
-            bytes,
+            bytes: bytes.into(),
             debug_enabled,
-            data: Vec::new(),
+            data: DexBytes::default(),
             container: None,
             strings: Vec::new(),
+            version: 0,
+            header_offset: 0,
+            endian: Endian::Little,
+            secondary_bytes: Vec::new(),
+            secondary_dexes: Vec::new(),
             classes: Vec::new(),
-            cursor: 0
+            cursor: 0,
+            persist: true,
+            cache_dir: PathBuf::from(CACHE_ROOT),
+            verify_integrity: false,
+            class_filter: ClassFilter::default(),
+            string_pool: None,
+            errors: Vec::new(),
+            generation: 0,
         }
     }
 
-    pub fn initialize_from_files() -> Self {
-        let data = load_data_from_file("extension.data").expect("Extension.data not found.");
-        let container = Some(load_container_from_file("extension_container.json").expect("Extension_container.json not found."));
-        let strings = load_strings_from_file("extension.txt").expect("Extension.txt not found.");
-        let classes = load_classes_from_file().expect("ELoading classes failed.");
-        Self {
-            bytes: Vec::new(),
+    /// Parser for the dex files of a multi-dex extension, classes.dex first
+    pub fn new_multi_dex(dexes: Vec<impl Into<DexBytes>>, debug_enabled: bool) -> Self {
+        let mut dexes = dexes.into_iter().map(Into::into);
+        let mut parser = Self::new(dexes.next().unwrap_or_default(), debug_enabled);
+        parser.secondary_bytes = dexes.collect();
+        parser
+    }
+
+    /// Parser of the installed extension from its cache in `out/`, see `initialize_from_dir`
+    pub fn initialize_from_files() -> Result<Self, ParseError> {
+        Self::initialize_from_dir(Path::new(CACHE_ROOT))
+    }
+
+    /// Parser of the extension cached in `dir`. A cache that is incomplete or corrupt is rebuilt
+    /// from the extension's dex files, which `save` keeps next to it.
+    pub fn initialize_from_dir(dir: &Path) -> Result<Self, ParseError> {
+        Self::load_cache(dir).or_else(|error| {
+            crate::logging::log(&format!("Loading the extension cache failed, parsing the extension again: {}", error));
+            Self::reparse_cache(dir).map_err(|_| error)
+        })
+    }
+
+    fn load_cache(dir: &Path) -> Result<Self, ParseError> {
+        let data = load_data_from_file(dir, "extension.data").map_err(|error| ParseError::cache("extension.data", error))?.into();
+        let container = load_container_from_file(dir, "extension_container.json").map_err(|error| ParseError::cache("extension_container.json", error))?;
+        let strings = load_strings_from_file(dir.join("extension.txt")).map_err(|error| ParseError::cache("extension.txt", error))?;
+        // Classes another install left behind, e.g. of a previously installed extension, stay out
+        let generation = load_generation(dir);
+        let classes: Vec<DexClass> = load_classes_from_file(dir).map_err(|error| ParseError::cache(&dir.to_string_lossy(), error))?
+            .into_iter()
+            .filter(|class| class.generation == generation)
+            .collect();
+        // A class file the cache budget evicted leaves the extension incomplete
+        let class_names = load_strings_from_file(dir.join(CLASSES_FILE)).map_err(|error| ParseError::cache(CLASSES_FILE, error))?;
+        let loaded: HashSet<&str> = classes.iter().map(|class| class.name.as_str()).collect();
+        if let Some(missing) = class_names.iter().map(|name| &**name).find(|name| !loaded.contains(name)) {
+            return Err(ParseError::cache(missing, std::io::Error::new(std::io::ErrorKind::NotFound, "the class file is missing")));
+        }
+        // Only the dex files the classes come from, a previous install may have had more
+        let dex_count = classes.iter().map(|class| class.dex).max().unwrap_or(0);
+        let secondary_dexes = (1..=dex_count)
+            .map(|dex| {
+                let (container_file, data_file, strings_file) = secondary_dex_files(dex);
+                Ok(DexFile {
+                    data: load_data_from_file(dir, &data_file).map_err(|error| ParseError::cache(&data_file, error))?.into(),
+                    container: load_container_from_file(dir, &container_file).map_err(|error| ParseError::cache(&container_file, error))?,
+                    strings: load_strings_from_file(dir.join(&strings_file)).map_err(|error| ParseError::cache(&strings_file, error))?,
+                })
+            })
+            .collect::<Result<_, ParseError>>()?;
+        Ok(Self {
+            bytes: DexBytes::default(),
             debug_enabled: true,
             data,
-            container,
+            container: Some(container),
             strings,
+            version: 0,
+            header_offset: 0,
+            endian: Endian::Little,
+            secondary_bytes: Vec::new(),
+            secondary_dexes,
             classes,
-            cursor: 0
+            cursor: 0,
+            persist: true,
+            cache_dir: dir.to_path_buf(),
+            verify_integrity: false,
+            class_filter: ClassFilter::default(),
+            string_pool: None,
+            errors: Vec::new(),
+            generation,
+        })
+    }
+
+    /// Parse the dex files `save` kept and write the cache anew
+    fn reparse_cache(dir: &Path) -> Result<Self, ParseError> {
+        let mut dexes = Vec::new();
+        for dex in 0.. {
+            match load_data_from_file(dir, &dex_file(dex)) {
+                Ok(bytes) => dexes.push(bytes),
+                Err(error) if dex == 0 => return Err(ParseError::cache(&dex_file(dex), error)),
+                Err(_) => break,
+            }
         }
+        let mut parser = Self::new_multi_dex(dexes, true);
+        parser.persist = false;
+        parser.cache_dir = dir.to_path_buf();
+        parser.class_filter = class_filter();
+        parser.parse()?;
+        parser.save()?;
+        Ok(parser)
     }
 
-    pub fn parse(&mut self) {
-        parser_log!(self, "Parsing Header item.");
-        let header_item: Header_Item = self.parse_header();
+    /// Parse the dex file at `header_offset` of `bytes`, then the further dex files of its 041
+    /// container and the ones of `secondary_bytes`.
+    ///
+    /// Versions 035 to 040 share one layout, 038 added the method handles and call sites listed
+    /// in the map_list. 041 puts several dex files into a container that shares their data, the
+    /// header grows by the container's size and the header's offset, and every offset is from
+    /// the start of the container. The data section then is the whole container.
+    ///
+    /// Fails on files that can't be read at all, the errors of single strings or classes are
+    /// collected in `errors` instead and only leave those out.
+    pub fn parse(&mut self) -> Result<(), ParseError> {
+        self.errors.clear();
+        self.secondary_dexes.clear();
+
+        if let Err(error) = self.parse_dex() {
+            parser_log!(self, "Refusing to parse: {}", error);
+            return Err(error);
+        }
 
+        if self.persist {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn parse_dex(&mut self) -> Result<(), ParseError> {
+        self.version = check_magic(self.bytes.get(self.header_offset..).unwrap_or_default())?;
+        self.endian = Endian::of_dex(&self.bytes[self.header_offset..]);
+        if self.verify_integrity {
+            verify_integrity(&self.bytes[self.header_offset..])?;
+        }
+
+        parser_log!(self, "Parsing Header item of {:?}-endian dex version {:03}.", self.endian, self.version);
+        let header_size = if self.version >= CONTAINER_DEX_VERSION { CONTAINER_HEADER_SIZE } else { HEADER_SIZE };
+        self.seek_section("header", self.header_offset as u32, 1, header_size)?;
+        let mut header_item: Header_Item = self.parse_header();
+        if self.version >= CONTAINER_DEX_VERSION {
+            header_item.data_off = 0;
+            header_item.data_size = self.bytes.len() as u32;
+        }
+
+        // The sections usually follow each other, but only their offsets say where they are
         parser_log!(self, "Parsing string_id_items.");
+        self.seek_section("string_ids", header_item.string_ids_off, header_item.string_ids_size, 4)?;
         let string_id_items = self.parse_ids_array(header_item.string_ids_size as usize * 4);
 
         parser_log!(self, "Parsing type_id_items.");
+        self.seek_section("type_ids", header_item.type_ids_off, header_item.type_ids_size, 4)?;
         let type_id_items = self.parse_ids_array(header_item.type_ids_size as usize * 4);
 
         parser_log!(self, "Parsing proto_id_items.");
+        self.seek_section("proto_ids", header_item.proto_ids_off, header_item.proto_ids_size, 12)?;
         let proto_id_items = self.parse_proto_id_array(header_item.proto_ids_size);
 
         parser_log!(self, "Parsing field_id_items.");
+        self.seek_section("field_ids", header_item.field_ids_off, header_item.field_ids_size, 8)?;
         let field_id_items = self.parse_field_id_array(header_item.field_ids_size);
 
         parser_log!(self, "Parsing method_id_items.");
+        self.seek_section("method_ids", header_item.method_ids_off, header_item.method_ids_size, 8)?;
         let method_id_items = self.parse_method_id_array(header_item.method_ids_size);
 
         parser_log!(self, "Parsing class_defs.");
+        self.seek_section("class_defs", header_item.class_defs_off, header_item.class_defs_size, 32)?;
         let class_defs = self.parse_class_defs_array(header_item.class_defs_size);
 
+        let mut method_handle_items = Vec::new();
+        if self.version >= METHOD_HANDLES_DEX_VERSION {
+            parser_log!(self, "Parsing method_handle_items.");
+            method_handle_items = self.parse_method_handle_items(&header_item);
+        }
+
         self.container = Some(
             DexContainer {
                 header_item: header_item.clone(),
                 string_id_items: string_id_items.clone(),
-                type_id_items: type_id_items.clone(),
-                proto_id_items: proto_id_items.clone(),
-                field_id_items: field_id_items.clone(),
-                method_id_items: method_id_items.clone(),
-                class_defs_items: class_defs.clone(),
+                type_id_items,
+                proto_id_items,
+                field_id_items,
+                method_id_items,
+                class_defs_items: class_defs,
+                method_handle_items,
+                // Filled in once the data section is available
+                call_site_items: Vec::new(),
             }
         );
 
         parser_log!(self, "Parsing Data section.");
-        self.data = self.bytes[(header_item.data_off as usize)..(header_item.data_off + header_item.data_size) as usize].try_into().unwrap();
+        self.seek_section("data", header_item.data_off, header_item.data_size, 1)?;
+        self.data = self.bytes.slice(self.cursor..self.cursor + header_item.data_size as usize);
+
+        if self.version >= METHOD_HANDLES_DEX_VERSION {
+            parser_log!(self, "Parsing call_site_ids.");
+            let call_site_items = self.parse_call_site_items(&header_item)?;
+            if let Some(container) = self.container.as_mut() {
+                container.call_site_items = call_site_items;
+            }
+        }
 
-        self.strings = string_id_items.iter()
+        let strings: Vec<String> = string_id_items.iter()
             .enumerate()
             .map(|(i, off)| {
-                let string = self.parse_string_at_offset(*off, &header_item);
+                let string = self.parse_string_at_offset(*off, &header_item, i).unwrap_or_else(|error| {
+                    parser_log!(self, "Invalid string: {}", error);
+                    self.errors.push(error);
+                    format!("<invalid string {}>", i)
+                });
                 parser_log!(self, "Index of string: {}, string -> {}", i, string);
                 string
             })
             .collect();
+        self.strings = match &self.string_pool {
+            Some(pool) => pool.intern(strings),
+            None => strings.into_iter().map(Arc::from).collect(),
+        };
 
         self.parse_class_items();
+        if self.version >= CONTAINER_DEX_VERSION && self.header_offset == 0 {
+            self.parse_container_dexes(&header_item)?;
+        }
+        self.parse_secondary_dexes()
+    }
+
+    /// Point the cursor at the section of `count` items of `item_size` bytes at `offset`, if the
+    /// file holds all of it
+    fn seek_section(&mut self, section: &'static str, offset: u32, count: u32, item_size: usize) -> Result<(), ParseError> {
+        // A forged count can overflow usize on 32-bit ABIs
+        let end = (count as usize).checked_mul(item_size).and_then(|size| (offset as usize).checked_add(size));
+        if end.is_none_or(|end| end > self.bytes.len()) {
+            let size = count.saturating_mul(item_size as u32);
+            return Err(ParseError::SectionOutOfBounds { section, offset, size });
+        }
+        self.cursor = offset as usize;
+        Ok(())
+    }
+
+    /// Write the parsed classes and the data needed to recreate the parser to `cache_dir` as the
+    /// next install generation. The generation is written last, so an interrupted or failed save
+    /// leaves the previous generation current and its half-written classes are never mixed in.
+    pub fn save(&mut self) -> Result<(), ParseError> {
+        let dir = self.cache_dir.as_path();
+        self.generation = load_generation(dir) + 1;
+        for class in &mut self.classes {
+            class.generation = self.generation;
+        }
+
+        for class in &self.classes {
+            parser_log!(self, "Saving class -> {}", class.name);
+            save_class_to_file(dir, class, &class.name).map_err(|error| ParseError::cache(&class.name, error))?;
+        }
 
         // store other parser data to create a parser on demand from on disk data
-        let _ = save_container_to_file(&self.container.clone().unwrap(), "extension_container.json");
-        let _ = save_data_to_file(self.data.clone(), "extension.data");
-        let _ = save_strings_to_file(self.strings.clone(), "extension.txt");
+        if let Some(container) = &self.container {
+            save_container_to_file(dir, container, "extension_container.json").map_err(|error| ParseError::cache("extension_container.json", error))?;
+        }
+        save_data_to_file(dir, &self.data, "extension.data").map_err(|error| ParseError::cache("extension.data", error))?;
+        save_strings_to_file(&self.strings, dir.join("extension.txt")).map_err(|error| ParseError::cache("extension.txt", error))?;
+        let class_names: Vec<&str> = self.classes.iter().map(|class| class.name.as_str()).collect();
+        save_strings_to_file(&class_names, dir.join(CLASSES_FILE)).map_err(|error| ParseError::cache(CLASSES_FILE, error))?;
+        for (i, dex_file) in self.secondary_dexes.iter().enumerate() {
+            let (container_file, data_file, strings_file) = secondary_dex_files(i + 1);
+            save_container_to_file(dir, &dex_file.container, &container_file).map_err(|error| ParseError::cache(&container_file, error))?;
+            save_data_to_file(dir, &dex_file.data, &data_file).map_err(|error| ParseError::cache(&data_file, error))?;
+            save_strings_to_file(&dex_file.strings, dir.join(&strings_file)).map_err(|error| ParseError::cache(&strings_file, error))?;
+        }
+        // The dex files themselves, to parse the extension again should the cache get corrupted
+        let dexes = std::iter::once(&self.bytes).chain(&self.secondary_bytes).filter(|bytes| !bytes.is_empty());
+        let mut dex_count = 0;
+        for (dex, bytes) in dexes.enumerate() {
+            save_data_to_file(dir, bytes, &dex_file(dex)).map_err(|error| ParseError::cache(&dex_file(dex), error))?;
+            dex_count = dex + 1;
+        }
+        // The dex files are read up to the first missing one, so a stale one of a previous
+        // install must not follow
+        if dex_count > 0 {
+            let _ = std::fs::remove_file(dir.join(dex_file(dex_count)));
+        }
+        save_generation(dir, self.generation).map_err(|error| ParseError::cache("generation", error))
+    }
+
+    /// Index of the extension's main class in `classes`, the first one extending a Source
+    pub fn main_class(&self) -> Option<usize> {
+        self.classes.iter().position(|class| class.super_class.as_ref().is_some_and(|super_class| super_class.contains("Source")))
+    }
+
+    /// The parsed extension as one versioned JSON document, see `DexExport`
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&DexExport::new(self)).unwrap()
+    }
+
+    /// Tables of dex file `dex`, see `DexClass.dex`. `None` before parsing.
+    pub fn tables(&self, dex: usize) -> Option<DexTables<'_>> {
+        if dex == 0 {
+            return Some(DexTables { data: &self.data, container: self.container.as_ref()?, strings: &self.strings });
+        }
+        let dex_file = self.secondary_dexes.get(dex - 1)?;
+        Some(DexTables { data: &dex_file.data, container: &dex_file.container, strings: &dex_file.strings })
+    }
+
+    /// Method `method_key` of `class` with its code decoded, `None` if the class has no such
+    /// method. The decoded code is kept with the parsed classes, so each method is only decoded
+    /// the first time it runs.
+    pub fn decoded_method(&mut self, class: &DexClass, method_key: &str) -> Result<Option<DexMethod>, ParseError> {
+        let Some(mut method) = class.methods.get(method_key).cloned() else {
+            return Ok(None);
+        };
+        if !method.code_pending() {
+            return Ok(Some(method));
+        }
+        let Some(tables) = self.tables(class.dex) else {
+            return Ok(Some(method));
+        };
+        decode_method_code(tables.data, tables.container, &class.name, &mut method)?;
+
+        if let Some(parsed) = self.classes.iter_mut().find(|parsed| parsed.name == class.name) {
+            parsed.methods.insert(method_key.to_string(), method.clone());
+        }
+        Ok(Some(method))
+    }
+
+    /// Instructions of `method` of `class`, decoded for the occasion if it hasn't run yet
+    pub fn instructions<'a>(&self, class: &DexClass, method: &'a DexMethod) -> Result<Cow<'a, [Instruction]>, ParseError> {
+        Ok(match self.decoded(class, method)? {
+            Cow::Borrowed(method) => Cow::Borrowed(&method.instructions),
+            Cow::Owned(method) => Cow::Owned(method.instructions),
+        })
+    }
+
+    /// `method` of `class` with its code and debug info, decoded for the occasion if it hasn't
+    /// run yet. The parsed classes are left as they are, unlike `decoded_method`.
+    pub fn decoded<'a>(&self, class: &DexClass, method: &'a DexMethod) -> Result<Cow<'a, DexMethod>, ParseError> {
+        if !method.code_pending() {
+            return Ok(Cow::Borrowed(method));
+        }
+        let Some(tables) = self.tables(class.dex) else {
+            return Ok(Cow::Borrowed(method));
+        };
+        let mut decoded = method.clone();
+        decode_method_code(tables.data, tables.container, &class.name, &mut decoded)?;
+        Ok(Cow::Owned(decoded))
+    }
+
+    /// Parse the dex files following the first one in its 041 container. Each one's header
+    /// follows the end of the one before.
+    fn parse_container_dexes(&mut self, header_item: &Header_Item) -> Result<(), ParseError> {
+        let container_size = (header_item.container_size as usize).min(self.bytes.len());
+        let mut offset = header_item.file_size as usize;
+        while offset < container_size {
+            parser_log!(self, "Parsing the dex file at 0x{:X} of the container.", offset);
+            let mut secondary = Parser::new(self.bytes.clone(), self.debug_enabled);
+            secondary.header_offset = offset;
+            secondary.persist = false;
+            secondary.verify_integrity = self.verify_integrity;
+            secondary.class_filter = self.class_filter.clone();
+            secondary.string_pool = self.string_pool.clone();
+            secondary.parse()?;
+
+            let file_size = secondary.container.as_ref().map_or(0, |container| container.header_item.file_size as usize);
+            self.add_secondary(secondary);
+            if file_size == 0 {
+                break;
+            }
+            offset += file_size;
+        }
+        Ok(())
+    }
+
+    /// Parse classes2.dex and on, keeping their tables and adding their classes after the ones
+    /// of classes.dex
+    fn parse_secondary_dexes(&mut self) -> Result<(), ParseError> {
+        for bytes in self.secondary_bytes.clone() {
+            parser_log!(self, "Parsing dex file {}.", self.secondary_dexes.len() + 2);
+            let mut secondary = Parser::new(bytes, self.debug_enabled);
+            secondary.persist = false;
+            secondary.verify_integrity = self.verify_integrity;
+            secondary.class_filter = self.class_filter.clone();
+            secondary.string_pool = self.string_pool.clone();
+            secondary.parse()?;
+            self.add_secondary(secondary);
+        }
+        Ok(())
+    }
+
+    /// Add the classes and tables of the parsed `secondary`, and of the further dex files of its
+    /// container, after the dex files already added
+    fn add_secondary(&mut self, mut secondary: Parser) {
+        self.errors.append(&mut secondary.errors);
+        let Some(container) = secondary.container.take() else {
+            return;
+        };
+
+        let first = self.secondary_dexes.len() + 1;
+        for mut class in secondary.classes {
+            let dex = first + class.dex;
+            if self.classes.iter().any(|existing| existing.name == class.name) {
+                self.errors.push(ParseError::DuplicateClass { name: class.name, dex });
+                continue;
+            }
+            class.dex = dex;
+            self.classes.push(class);
+        }
+        self.secondary_dexes.push(DexFile { data: secondary.data, container, strings: secondary.strings });
+        self.secondary_dexes.append(&mut secondary.secondary_dexes);
     }
 
     pub fn parse_ids_array(&mut self, size: usize) -> Vec<u32> {
-        let mut id_bytes = self.get_bytes_vec(size);
-        convert_vec_u8_to_vec_u32(&mut id_bytes).expect("Vec<u8> to Vec<u32> conversion failed.")
+        (0..size / 4).map(|_| self.read_u32()).collect()
     }
 
     pub fn parse_proto_id_array(&mut self, length: u32) -> Vec<Proto_Id_Item> {
         let mut return_vec: Vec<Proto_Id_Item> = Vec::new();
         for _ in 0..(length as usize) {
             let proto_id_item = Proto_Id_Item {
-                shorty_idx: u32::from_le_bytes(self.get_bytes::<4>()),
-                return_type_idx: u32::from_le_bytes(self.get_bytes::<4>()),
-                parameters_off: u32::from_le_bytes(self.get_bytes::<4>()),
+                shorty_idx: self.read_u32(),
+                return_type_idx: self.read_u32(),
+                parameters_off: self.read_u32(),
             };
             return_vec.push(proto_id_item);
         }
@@ -133,9 +589,9 @@ impl Parser {
         let mut return_vec: Vec<Field_Id_Item> = Vec::new();
         for _ in 0..(length as usize) {
             let field_id_item = Field_Id_Item {
-                class_idx: u16::from_le_bytes(self.get_bytes::<2>()),
-                type_idx: u16::from_le_bytes(self.get_bytes::<2>()),
-                name_idx: u32::from_le_bytes(self.get_bytes::<4>()),
+                class_idx: self.read_u16(),
+                type_idx: self.read_u16(),
+                name_idx: self.read_u32(),
             };
             return_vec.push(field_id_item);
         }
@@ -146,64 +602,136 @@ impl Parser {
         let mut return_vec: Vec<Method_Id_Item> = Vec::new();
         for _ in 0..(length as usize) {
             let method_id_item = Method_Id_Item {
-                class_idx: u16::from_le_bytes(self.get_bytes::<2>()),
-                proto_idx: u16::from_le_bytes(self.get_bytes::<2>()),
-                name_idx: u32::from_le_bytes(self.get_bytes::<4>()),
+                class_idx: self.read_u16(),
+                proto_idx: self.read_u16(),
+                name_idx: self.read_u32(),
             };
             return_vec.push(method_id_item);
         }
         return return_vec;
     }
 
+    /// Size and offset of the map_list entry of `item_type`, if the dex has one
+    pub fn find_map_item(&self, map_off: u32, item_type: u16) -> Option<(u32, u32)> {
+        let map_off = map_off as usize;
+        if map_off == 0 || map_off + 4 > self.bytes.len() {
+            return None;
+        }
+
+        // map_item: type u16, unused u16, size u32, offset u32
+        let size = self.endian.read_u32(&self.bytes, map_off) as usize;
+        (0..size)
+            .map(|i| map_off + 4 + i * 12)
+            .take_while(|offset| offset + 12 <= self.bytes.len())
+            .find(|offset| self.endian.read_u16(&self.bytes, *offset) == item_type)
+            .map(|offset| (self.endian.read_u32(&self.bytes, offset + 4), self.endian.read_u32(&self.bytes, offset + 8)))
+    }
+
+    /// Method handles only exist from dex 038 on and are found through the map_list
+    pub fn parse_method_handle_items(&self, header_item: &Header_Item) -> Vec<MethodHandleItem> {
+        let Some((size, offset)) = self.find_map_item(header_item.map_off, TYPE_METHOD_HANDLE_ITEM) else {
+            return Vec::new();
+        };
+
+        (0..size as usize)
+            .map(|i| offset as usize + i * 8)
+            .take_while(|offset| offset + 8 <= self.bytes.len())
+            .map(|offset| MethodHandleItem {
+                method_handle_type: self.endian.read_u16(&self.bytes, offset),
+                field_or_method_id: self.endian.read_u16(&self.bytes, offset + 4),
+            })
+            .collect()
+    }
+
+    /// Call sites are only found through the map_list as well, each call_site_id points to an
+    /// encoded_array holding the bootstrap method handle, the method name, the method type and
+    /// the extra bootstrap arguments
+    pub fn parse_call_site_items(&self, header_item: &Header_Item) -> Result<Vec<CallSiteItem>, ParseError> {
+        let Some(container) = &self.container else {
+            return Ok(Vec::new());
+        };
+        let Some((size, offset)) = self.find_map_item(header_item.map_off, TYPE_CALL_SITE_ID_ITEM) else {
+            return Ok(Vec::new());
+        };
+
+        (0..size as usize)
+            .map(|i| offset as usize + i * 4)
+            .take_while(|offset| offset + 4 <= self.bytes.len())
+            .map(|offset| {
+                let (values, _) = parse_encoded_array(&self.data, self.endian.read_u32(&self.bytes, offset) as usize, container)?;
+                let mut values = values.into_iter();
+
+                let bootstrap_method_handle = match values.next() {
+                    Some(DexValue::MethodHandle(handle)) => handle,
+                    _ => u32::MAX,
+                };
+                let method_name = match values.next() {
+                    Some(DexValue::String(name)) => name,
+                    _ => String::new(),
+                };
+                let method_type = match values.next() {
+                    Some(DexValue::MethodType(descriptor)) => descriptor,
+                    _ => String::new(),
+                };
+
+                Ok(CallSiteItem { bootstrap_method_handle, method_name, method_type, arguments: values.collect() })
+            })
+            .collect()
+    }
+
     pub fn parse_class_defs_array(&mut self, length: u32) -> Vec<Class_Def_Item> {
         let mut return_vec: Vec<Class_Def_Item> = Vec::new();
         for _ in 0..(length as usize) {
             let class_def_item = Class_Def_Item {
-                class_idx: u32::from_le_bytes(self.get_bytes::<4>()),
-                access_flags: u32::from_le_bytes(self.get_bytes::<4>()),
-                superclass_idx: u32::from_le_bytes(self.get_bytes::<4>()),
-                interfaces_off: u32::from_le_bytes(self.get_bytes::<4>()),
-                source_file_idx: u32::from_le_bytes(self.get_bytes::<4>()),
-                annotations_off: u32::from_le_bytes(self.get_bytes::<4>()),
-                class_data_off: u32::from_le_bytes(self.get_bytes::<4>()),
-                static_values_off: u32::from_le_bytes(self.get_bytes::<4>()),
+                class_idx: self.read_u32(),
+                access_flags: self.read_u32(),
+                superclass_idx: self.read_u32(),
+                interfaces_off: self.read_u32(),
+                source_file_idx: self.read_u32(),
+                annotations_off: self.read_u32(),
+                class_data_off: self.read_u32(),
+                static_values_off: self.read_u32(),
             };
             return_vec.push(class_def_item);
         }
         return return_vec;
     }
 
-    pub fn parse_string_at_offset(&self, string_offset: u32, header_item: &Header_Item) -> String {
-        let offset = (string_offset as usize).checked_sub(header_item.data_off as usize).expect("String offset before data section");
-        let (_utf16_size, mut cursor) = read_uleb128(&self.data, offset);
-
-        let mut string_bytes: Vec<u8> = Vec::new();
-        while cursor < self.data.len() && self.data[cursor] != 0 {
-            let mut byte = self.data[cursor];
-            if byte == 0xC0 && self.data.get(cursor + 1) == Some(&0x80) {
-                byte = 0;
-                cursor += 1;
-            }
-            string_bytes.push(byte);
-            cursor += 1;
-        }
-
-        String::from_utf8(string_bytes).unwrap_or_else(|_| "UTF-8 decode failed".to_string())
+    pub fn parse_string_at_offset(&self, string_offset: u32, header_item: &Header_Item, string_id: usize) -> Result<String, ParseError> {
+        try_parse_string_at_offset(&self.data, string_offset, header_item, string_id)
     }
 
-    pub fn parse_strings(&self, string_id_items: &Vec<u32>, header_item: &Header_Item) -> Vec<String> {
-        let all_strings: Vec<String> = string_id_items.iter()
-            .map(|off| self.parse_string_at_offset(*off, header_item))
+    pub fn parse_strings(&self, string_id_items: &Vec<u32>, header_item: &Header_Item) -> Result<Vec<String>, ParseError> {
+        let all_strings = string_id_items.iter()
+            .enumerate()
+            .map(|(i, off)| self.parse_string_at_offset(*off, header_item, i))
             .collect();
 
         // save_strings_to_file(&all_strings, "dex_strings.txt").expect("Failed to write strings");
         return all_strings
     }
 
+    /// File offset of the hiddenapi flags of the `class_def_idx`th class, 0 if the class has none.
+    /// The hiddenapi_class_data_item starts with its size, then an offset per class_def relative
+    /// to the start of the item.
+    fn hidden_api_offset(&self, header_item: &Header_Item, class_def_idx: usize) -> u32 {
+        if self.version < HIDDENAPI_DEX_VERSION {
+            return 0;
+        }
+        let Some((_, section)) = self.find_map_item(header_item.map_off, TYPE_HIDDENAPI_CLASS_DATA_ITEM) else {
+            return 0;
+        };
+        match self.endian.read_u32(&self.bytes, section as usize + 4 + class_def_idx * 4) {
+            0 => 0,
+            // A sum past 4GiB is no offset in the file either
+            offset => section.checked_add(offset).unwrap_or(0),
+        }
+    }
+
     pub fn parse_class_items(&mut self) {
         match &self.container {
             Some(container) => {
-                for class_def in &container.class_defs_items {
+                for (class_def_idx, class_def) in container.class_defs_items.iter().enumerate() {
                     // Safety check
                     if class_def.class_data_off == 0 {
                         continue;
@@ -216,6 +744,10 @@ impl Parser {
                         &container.type_id_items,
                         &container.string_id_items,
                     );
+                    if !self.class_filter.includes(&name) {
+                        parser_log!(self, "Skipping class -> {}", name);
+                        continue;
+                    }
                     let super_class = get_name_of_class(
                         class_def.superclass_idx as usize,
                         &self.data,
@@ -224,13 +756,16 @@ impl Parser {
                         &container.string_id_items,
                     );
 
-                    // TODO: Support filter
                     parser_log!(self, "Parsing class -> {}", name);
-                    let dex_class = parse_class_data(&self.data, class_def, &container);
-                    self.classes.push(dex_class.clone());
-
-                    parser_log!(self, "Saving class -> {}", name);
-                    save_class_to_file(&dex_class, &dex_class.name).expect("Saving class to file failed.");
+                    let hidden_api_off = self.hidden_api_offset(&container.header_item, class_def_idx);
+                    match parse_class_data(&self.data, class_def, container, hidden_api_off) {
+                        Ok(dex_class) => self.classes.push(dex_class),
+                        Err(error) => {
+                            let error = ParseError::MalformedClass { name, error: Box::new(error) };
+                            parser_log!(self, "{}", error);
+                            self.errors.push(error);
+                        }
+                    }
                 }
             
             },
@@ -238,44 +773,169 @@ impl Parser {
         }
     }
 
-    // CONST is screwing me over, so im using this workaround now 
-    pub fn get_bytes_vec(&mut self, size: usize) -> Vec<u8> {
-        let old_cursor = self.cursor;
-        self.cursor += size;
-        self.bytes[old_cursor..self.cursor].try_into().unwrap()
-    }
-
     pub fn get_bytes<const count: usize>(&mut self) -> [u8; count] {
         let old_cursor = self.cursor;
         self.cursor += count;
         self.bytes[old_cursor..self.cursor].try_into().unwrap()
     }
 
+    pub fn read_u16(&mut self) -> u16 {
+        let bytes = self.get_bytes::<2>();
+        self.endian.u16(bytes)
+    }
+
+    pub fn read_u32(&mut self) -> u32 {
+        let bytes = self.get_bytes::<4>();
+        self.endian.u32(bytes)
+    }
+
     pub fn parse_header(&mut self) -> Header_Item {
         Header_Item {
             magic: self.get_bytes::<8>(),
-            checksum: u32::from_le_bytes(self.get_bytes::<4>()),
+            checksum: self.read_u32(),
             signature: self.get_bytes::<20>(),
-            file_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            header_size: u32::from_le_bytes(self.get_bytes::<4>()),
+            file_size: self.read_u32(),
+            header_size: self.read_u32(),
+            // Read as is, see REVERSE_ENDIAN_CONSTANT
             endian_tag: u32::from_le_bytes(self.get_bytes::<4>()),
-            link_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            link_off: u32::from_le_bytes(self.get_bytes::<4>()),
-            map_off: u32::from_le_bytes(self.get_bytes::<4>()),
-            string_ids_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            string_ids_off: u32::from_le_bytes(self.get_bytes::<4>()),
-            type_ids_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            type_ids_off: u32::from_le_bytes(self.get_bytes::<4>()),
-            proto_ids_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            proto_ids_off: u32::from_le_bytes(self.get_bytes::<4>()),
-            field_ids_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            field_ids_off: u32::from_le_bytes(self.get_bytes::<4>()),
-            method_ids_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            method_ids_off: u32::from_le_bytes(self.get_bytes::<4>()),
-            class_defs_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            class_defs_off: u32::from_le_bytes(self.get_bytes::<4>()),
-            data_size: u32::from_le_bytes(self.get_bytes::<4>()),
-            data_off: u32::from_le_bytes(self.get_bytes::<4>()),
-        }
-    }
-}
\ No newline at end of file
+            link_size: self.read_u32(),
+            link_off: self.read_u32(),
+            map_off: self.read_u32(),
+            string_ids_size: self.read_u32(),
+            string_ids_off: self.read_u32(),
+            type_ids_size: self.read_u32(),
+            type_ids_off: self.read_u32(),
+            proto_ids_size: self.read_u32(),
+            proto_ids_off: self.read_u32(),
+            field_ids_size: self.read_u32(),
+            field_ids_off: self.read_u32(),
+            method_ids_size: self.read_u32(),
+            method_ids_off: self.read_u32(),
+            class_defs_size: self.read_u32(),
+            class_defs_off: self.read_u32(),
+            data_size: self.read_u32(),
+            data_off: self.read_u32(),
+            container_size: if self.version >= CONTAINER_DEX_VERSION { self.read_u32() } else { 0 },
+            header_offset: if self.version >= CONTAINER_DEX_VERSION { self.read_u32() } else { 0 },
+        }
+    }
+}
+
+/// Cache file of the bytes of dex file `dex`, see `DexClass.dex`
+fn dex_file(dex: usize) -> String {
+    if dex == 0 { "extension.dex".to_string() } else { format!("extension{}.dex", dex + 1) }
+}
+
+/// Cache files of the container, data and strings of dex file `dex`, e.g.
+/// `extension_container2.json` for classes2.dex
+fn secondary_dex_files(dex: usize) -> (String, String, String) {
+    (format!("extension_container{}.json", dex + 1), format!("extension{}.data", dex + 1), format!("extension{}.txt", dex + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::CacheManager;
+    use crate::selftest::{build_micro_dex, self_test_cases, TestCacheDir, SELF_TEST_CLASS};
+    use crate::utils::panic_message;
+    use std::panic;
+
+    /// Seed of the byte flips of `survives_malformed_input`, fixed so a failure reproduces
+    const MALFORMED_SEED: u64 = 0x9E37_79B9_7F4A_7C15;
+    /// Number of mutants with flipped bytes
+    const MALFORMED_FLIPS: usize = 512;
+
+    /// A cache missing its data section is rebuilt from the dex file kept next to it
+    #[test]
+    fn reparses_a_corrupted_cache() {
+        let dir = TestCacheDir::new("reparse");
+        let mut parser = Parser::new(build_micro_dex(&self_test_cases(), Endian::Little), false);
+        parser.cache_dir = dir.0.clone();
+        parser.parse().unwrap();
+        std::fs::remove_file(dir.0.join("extension.data")).unwrap();
+
+        let loaded = Parser::initialize_from_dir(&dir.0).unwrap();
+        assert_eq!(loaded.classes.len(), parser.classes.len());
+        assert_eq!(loaded.generation, parser.generation + 1);
+        assert!(Parser::load_cache(&dir.0).is_ok());
+    }
+
+    /// A class file the cache budget evicted is parsed again from the dex file kept in the cache
+    #[test]
+    fn reparses_evicted_classes() {
+        let dir = TestCacheDir::new("evict");
+        let mut parser = Parser::new(build_micro_dex(&self_test_cases(), Endian::Little), false);
+        parser.cache_dir = dir.0.clone();
+        parser.parse().unwrap();
+
+        let class_file = dir.0.join(SELF_TEST_CLASS.trim_end_matches(';'));
+        let mut cache = CacheManager::new(dir.0.clone());
+        let total = cache.usage().total;
+        // The class file is the only one the budget may evict
+        assert!(cache.trim(total - 1) > 0);
+        assert!(!class_file.exists());
+
+        let loaded = Parser::initialize_from_dir(&dir.0).unwrap();
+        assert_eq!(loaded.classes.iter().map(|class| class.name.as_str()).collect::<Vec<_>>(), [SELF_TEST_CLASS]);
+        assert!(class_file.exists());
+    }
+
+    /// Truncated and corrupted copies of the micro dex have to be refused or parsed, never panic.
+    /// The flipped copies are the ones a fuzzer turned up, they are derived from a fixed seed so
+    /// any of them can be reproduced from its number.
+    #[test]
+    fn survives_malformed_input() {
+        let dex = build_micro_dex(&self_test_cases(), Endian::Little);
+        let mut mutants: Vec<(String, Vec<u8>)> = Vec::new();
+
+        for len in (0..dex.len()).step_by(7) {
+            mutants.push((format!("truncated to {}", len), dex[..len].to_vec()));
+        }
+        // xorshift64, 1 to 4 random bytes of the dex set to random values per mutant
+        let mut state = MALFORMED_SEED;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for i in 0..MALFORMED_FLIPS {
+            let mut bytes = dex.clone();
+            for _ in 0..=next() % 4 {
+                let offset = next() as usize % bytes.len();
+                bytes[offset] = next() as u8;
+            }
+            mutants.push((format!("flip {}", i), bytes));
+        }
+
+        let mut failures = Vec::new();
+        for (name, bytes) in &mutants {
+            let parsed = panic::catch_unwind(|| {
+                let mut parser = Parser::new(bytes.clone(), false);
+                parser.persist = false;
+                if parser.parse().is_ok() {
+                    for class in parser.classes.clone() {
+                        for key in class.methods.keys() {
+                            let _ = parser.decoded_method(&class, key);
+                        }
+                    }
+                }
+            });
+            if let Err(error) = parsed {
+                failures.push(format!("{} ({})", name, panic_message(error)));
+            }
+        }
+        assert!(failures.is_empty(), "Panicked on {} of {} mutants: {}", failures.len(), mutants.len(), failures.join(", "));
+    }
+
+    /// Section sizes are computed without overflowing, whatever count the header states
+    #[test]
+    fn refuses_sections_past_the_end() {
+        let mut parser = Parser::new(vec![0; 0x70], false);
+        assert_eq!(
+            parser.seek_section("type_ids", 0x40, u32::MAX, 4),
+            Err(ParseError::SectionOutOfBounds { section: "type_ids", offset: 0x40, size: u32::MAX })
+        );
+        assert_eq!(parser.seek_section("header", 0, 1, 0x70), Ok(()));
+    }
+}
@@ -0,0 +1,61 @@
+// Renders dex type descriptors and method protos the way they would read in Kotlin source,
+// e.g. `fun searchManga(page: Int, query: String): MangasPage`.
+
+/// Readable name of a single type descriptor (`I` -> `Int`, `[Ljava/lang/String;` -> `Array<String>`)
+pub fn readable_type(descriptor: &str) -> String {
+    if let Some(component) = descriptor.strip_prefix('[') {
+        return match component {
+            "Z" => "BooleanArray".to_string(),
+            "B" => "ByteArray".to_string(),
+            "S" => "ShortArray".to_string(),
+            "C" => "CharArray".to_string(),
+            "I" => "IntArray".to_string(),
+            "J" => "LongArray".to_string(),
+            "F" => "FloatArray".to_string(),
+            "D" => "DoubleArray".to_string(),
+            _ => format!("Array<{}>", readable_type(component)),
+        };
+    }
+
+    match descriptor {
+        "V" => "Unit".to_string(),
+        "Z" => "Boolean".to_string(),
+        "B" => "Byte".to_string(),
+        "S" => "Short".to_string(),
+        "C" => "Char".to_string(),
+        "I" => "Int".to_string(),
+        "J" => "Long".to_string(),
+        "F" => "Float".to_string(),
+        "D" => "Double".to_string(),
+        "Ljava/lang/Object;" => "Any".to_string(),
+        _ => match descriptor.strip_prefix('L').and_then(|d| d.strip_suffix(';')) {
+            Some(class_name) => class_name
+                .rsplit('/')
+                .next()
+                .unwrap_or(class_name)
+                .replace('$', "."),
+            // Not a descriptor we know, show it untouched
+            None => descriptor.to_string(),
+        },
+    }
+}
+
+/// Readable signature of a method. Parameters without a known name are called `p0`, `p1`, ...
+pub fn render_signature(name: &str, parameters: &[String], parameter_names: &[String], return_type: &str) -> String {
+    let parameters = parameters.iter()
+        .enumerate()
+        .map(|(i, parameter)| {
+            let parameter_name = parameter_names.get(i)
+                .cloned()
+                .unwrap_or_else(|| format!("p{}", i));
+            format!("{}: {}", parameter_name, readable_type(parameter))
+        })
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    if return_type == "V" {
+        format!("fun {}({})", name, parameters)
+    } else {
+        format!("fun {}({}): {}", name, parameters, readable_type(return_type))
+    }
+}
@@ -1,23 +1,117 @@
+use crate::parser::error::ParseError;
 use crate::parser::uleb::read_uleb128;
 use crate::types::Header_Item;
-use crate::utils::{save_strings_to_file, convert_vec_u8_to_vec_u16};
+use crate::utils::save_strings_to_file;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 
 pub fn parse_string_at_offset(data: &[u8], string_offset: u32, header_item: &Header_Item, i: usize) -> (usize, String) {
-    let offset = (string_offset as usize).checked_sub(header_item.data_off as usize).expect("String offset before data section");
-    let (_utf16_size, mut cursor) = read_uleb128(data, offset);
-
-    let mut string_bytes: Vec<u8> = Vec::new();
-    while cursor < data.len() && data[cursor] != 0 {
-        let mut byte = data[cursor];
-        if byte == 0xC0 && data.get(cursor + 1) == Some(&0x80) {
-            byte = 0;
-            cursor += 1;
+    let string = try_parse_string_at_offset(data, string_offset, header_item, i)
+        .unwrap_or_else(|_| "UTF-8 decode failed".to_string());
+
+    (i, string)
+}
+
+/// Decode the string_data_item of `string_id`, validating it against its utf16 size
+pub fn try_parse_string_at_offset(data: &[u8], string_offset: u32, header_item: &Header_Item, string_id: usize) -> Result<String, ParseError> {
+    let offset = (string_offset as usize)
+        .checked_sub(header_item.data_off as usize)
+        .filter(|offset| *offset < data.len())
+        .ok_or(ParseError::StringOffsetOutOfBounds { string_id, offset: string_offset })?;
+    let (utf16_size, cursor) = read_uleb128(data, offset);
+
+    // Every utf16 code unit takes at most 3 bytes in MUTF-8
+    let limit = (cursor + utf16_size as usize * 3 + 1).min(data.len());
+    let length = data
+        .get(cursor..limit)
+        .unwrap_or_default()
+        .iter()
+        .position(|byte| *byte == 0)
+        .ok_or(ParseError::UnterminatedString { string_id })?;
+
+    let decoded = decode_mutf8(&data[cursor..cursor + length]).ok_or(ParseError::InvalidStringData { string_id })?;
+    if decoded.utf16_len != utf16_size {
+        return Err(ParseError::StringSizeMismatch { string_id, expected: utf16_size, actual: decoded.utf16_len });
+    }
+
+    Ok(decoded.value)
+}
+
+/// A string decoded from MUTF-8 with the number of utf16 code units Java sees in it
+pub struct DecodedString {
+    pub value: String,
+    pub utf16_len: u32,
+}
+
+/// Decode MUTF-8, the UTF-8 variant of dex files, without the terminating NUL. NUL is written
+/// as `C0 80` and characters outside the BMP as two 3 byte surrogates instead of one 4 byte
+/// sequence. A surrogate without its pair is valid in Java but not in a Rust String, it becomes
+/// U+FFFD, which keeps the utf16 length. `None` for byte sequences no encoder writes, such as
+/// overlong encodings other than `C0 80`.
+pub fn decode_mutf8(bytes: &[u8]) -> Option<DecodedString> {
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut cursor = 0;
+    while cursor < bytes.len() {
+        let byte = bytes[cursor] as u16;
+        let continuation = |index: usize| bytes.get(cursor + index).filter(|byte| *byte & 0xC0 == 0x80).map(|byte| (*byte & 0x3F) as u16);
+
+        match byte {
+            0x01..=0x7F => {
+                units.push(byte);
+                cursor += 1;
+            }
+            0xC0..=0xDF => {
+                let unit = (byte & 0x1F) << 6 | continuation(1)?;
+                // Overlong, only NUL is written in 2 bytes though 1 would do
+                if unit != 0 && unit < 0x80 {
+                    return None;
+                }
+                units.push(unit);
+                cursor += 2;
+            }
+            0xE0..=0xEF => {
+                let unit = (byte & 0x0F) << 12 | continuation(1)? << 6 | continuation(2)?;
+                if unit < 0x800 {
+                    return None;
+                }
+                units.push(unit);
+                cursor += 3;
+            }
+            _ => return None,
         }
-        string_bytes.push(byte);
-        cursor += 1;
     }
 
-    (i, String::from_utf8(string_bytes).unwrap_or_else(|_| "UTF-8 decode failed".to_string()))
+    let value = char::decode_utf16(units.iter().copied())
+        .map(|unit| unit.unwrap_or(char::REPLACEMENT_CHARACTER))
+        .collect();
+    Some(DecodedString { value, utf16_len: units.len() as u32 })
+}
+
+/// Strings shared by several parsers, so the ones many extensions have in common, the names of
+/// Kotlin's and OkHttp's classes and the like, are held once while a batch is installed
+#[derive(Clone, Default)]
+pub struct StringPool(Arc<Mutex<HashSet<Arc<str>>>>);
+
+impl StringPool {
+    /// `strings` as the pool's copies, added to it where it has none yet
+    pub fn intern(&self, strings: Vec<String>) -> Vec<Arc<str>> {
+        let mut pool = self.0.lock().unwrap();
+        strings.into_iter()
+            .map(|string| match pool.get(string.as_str()) {
+                Some(interned) => interned.clone(),
+                None => {
+                    let interned: Arc<str> = Arc::from(string);
+                    pool.insert(interned.clone());
+                    interned
+                }
+            })
+            .collect()
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
 }
 
 pub fn parse_strings(data: &[u8], string_id_items: &Vec<u32>, header_item: &Header_Item) {
@@ -26,5 +120,36 @@ pub fn parse_strings(data: &[u8], string_id_items: &Vec<u32>, header_item: &Head
         .map(|(i, off)| parse_string_at_offset(data, *off, header_item, i).1)
         .collect();
 
-    save_strings_to_file(all_strings, "dex_strings.txt").expect("Failed to write strings");
+    save_strings_to_file(&all_strings, "dex_strings.txt").expect("Failed to write strings");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_nul_and_surrogate_pairs() {
+        // "a", NUL, U+00E9, U+1F600 as its two surrogates
+        let decoded = decode_mutf8(&[0x61, 0xC0, 0x80, 0xC3, 0xA9, 0xED, 0xA0, 0xBD, 0xED, 0xB8, 0x80]).unwrap();
+        assert_eq!(decoded.value, "a\0\u{E9}\u{1F600}");
+        assert_eq!(decoded.utf16_len, 5);
+    }
+
+    /// Only NUL may take more bytes than it needs
+    #[test]
+    fn refuses_overlong_encodings() {
+        for bytes in [&[0xC1, 0xBF][..], &[0xC0, 0x81], &[0xE0, 0x80, 0x80], &[0xE0, 0x9F, 0xBF], &[0x00], &[0xF0, 0x9F, 0x98, 0x80]] {
+            assert!(decode_mutf8(bytes).is_none(), "{:02X?}", bytes);
+        }
+        assert!(decode_mutf8(&[0xE0, 0xA0, 0x80]).is_some());
+    }
+
+    #[test]
+    fn interns_strings_once_across_parsers() {
+        let pool = StringPool::default();
+        let first = pool.intern(vec!["Lkotlin/Unit;".to_string(), "first".to_string()]);
+        let second = pool.clone().intern(vec!["Lkotlin/Unit;".to_string(), "second".to_string()]);
+        assert!(Arc::ptr_eq(&first[0], &second[0]));
+        assert_eq!(pool.len(), 3);
+    }
 }
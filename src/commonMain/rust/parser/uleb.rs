@@ -1,14 +1,131 @@
-pub fn read_uleb128(data: &[u8], mut offset: usize) -> (u32, usize) {
-    let mut result = 0u32;
-    let mut shift = 0;
-    loop {
-        let byte = data[offset];
-        offset += 1;
-        result |= ((byte & 0x7F) as u32) << shift;
-        if byte & 0x80 == 0 {
-            break;
+/// A uleb128 or sleb128 of a dex file takes at most 5 bytes
+const MAX_LEB128_BYTES: usize = 5;
+
+/// Bits of the 5th byte past the 32 bits of the value, 0 in a well-formed uleb128 and copies of
+/// the sign bit in a well-formed sleb128
+const EXCESS_BITS: u8 = 0x70;
+
+/// The 7-bit groups of the LEB128 at `offset`, how many bytes it took and whether it is
+/// well-formed: ended within the data and the 5 bytes, without bits beyond 32
+struct Leb128 {
+    bits: u32,
+    len: usize,
+    last: u8,
+    ended: bool,
+}
+
+impl Leb128 {
+    fn read(data: &[u8], offset: usize) -> Self {
+        let mut leb = Leb128 { bits: 0, len: 0, last: 0, ended: false };
+        while leb.len < MAX_LEB128_BYTES {
+            let byte = data.get(offset + leb.len).copied();
+            let value = byte.unwrap_or(0);
+            leb.bits |= ((value & 0x7F) as u32) << (7 * leb.len);
+            leb.len += 1;
+            leb.last = value;
+            if value & 0x80 == 0 {
+                leb.ended = byte.is_some();
+                break;
+            }
         }
-        shift += 7;
+        leb
+    }
+
+    fn signed(&self) -> i32 {
+        // sign extend from the last read bit
+        let unused = 32usize.saturating_sub(7 * self.len) as u32;
+        ((self.bits << unused) as i32) >> unused
+    }
+
+    fn fits_unsigned(&self) -> bool {
+        self.ended && (self.len < MAX_LEB128_BYTES || self.last & EXCESS_BITS == 0)
+    }
+
+    fn fits_signed(&self) -> bool {
+        let sign = if self.last & 0x08 != 0 { EXCESS_BITS } else { 0 };
+        self.ended && (self.len < MAX_LEB128_BYTES || self.last & EXCESS_BITS == sign)
+    }
+}
+
+/// Bytes past the end of the data are read as zero, which ends the value, and a value ends after
+/// 5 bytes, so malformed data can neither run past the end nor overflow
+pub fn read_uleb128(data: &[u8], offset: usize) -> (u32, usize) {
+    let leb = Leb128::read(data, offset);
+    (leb.bits, offset + leb.len)
+}
+
+/// Bounded like `read_uleb128`
+pub fn read_sleb128(data: &[u8], offset: usize) -> (i32, usize) {
+    let leb = Leb128::read(data, offset);
+    (leb.signed(), offset + leb.len)
+}
+
+/// Like `read_uleb128`, but `None` if the value runs past the end of the data, takes more than
+/// 5 bytes or has bits beyond 32
+pub fn checked_uleb128(data: &[u8], offset: usize) -> Option<(u32, usize)> {
+    let leb = Leb128::read(data, offset);
+    leb.fits_unsigned().then_some((leb.bits, offset + leb.len))
+}
+
+/// Like `read_sleb128`, but `None` if the value is malformed, see `checked_uleb128`
+pub fn checked_sleb128(data: &[u8], offset: usize) -> Option<(i32, usize)> {
+    let leb = Leb128::read(data, offset);
+    leb.fits_signed().then(|| (leb.signed(), offset + leb.len))
+}
+
+/// Read a uleb128p1, where 0 stands for no index. `None` if the value is malformed, see
+/// `checked_uleb128`
+pub fn checked_uleb128p1(data: &[u8], offset: usize) -> Option<(Option<u32>, usize)> {
+    checked_uleb128(data, offset).map(|(value, offset)| (value.checked_sub(1), offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The examples of the dex format spec: bytes, sleb128, uleb128, uleb128p1
+    #[test]
+    fn reads_spec_examples() {
+        let examples: &[(&[u8], i32, u32, Option<u32>)] = &[
+            (&[0x00], 0, 0, None),
+            (&[0x01], 1, 1, Some(0)),
+            (&[0x7F], -1, 127, Some(126)),
+            (&[0x80, 0x7F], -128, 16256, Some(16255)),
+        ];
+        for (bytes, sleb, uleb, uleb_p1) in examples {
+            let end = bytes.len();
+            assert_eq!(checked_sleb128(bytes, 0), Some((*sleb, end)), "{:02X?}", bytes);
+            assert_eq!(checked_uleb128(bytes, 0), Some((*uleb, end)), "{:02X?}", bytes);
+            assert_eq!(checked_uleb128p1(bytes, 0), Some((*uleb_p1, end)), "{:02X?}", bytes);
+        }
+    }
+
+    /// Values at the edges of 32 bits, the checked readers refuse ones that are cut off,
+    /// overlong or too large
+    #[test]
+    fn checks_the_edges_of_32_bits() {
+        let edges: &[(&[u8], Option<u32>, Option<i32>)] = &[
+            (&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F], Some(u32::MAX), None),
+            (&[0xFF, 0xFF, 0xFF, 0xFF, 0x7F], None, Some(-1)),
+            (&[0x80, 0x80, 0x80, 0x80, 0x78], None, Some(i32::MIN)),
+            (&[0xFF, 0xFF, 0xFF, 0xFF, 0x07], Some(i32::MAX as u32), Some(i32::MAX)),
+            // Bits beyond 32
+            (&[0xFF, 0xFF, 0xFF, 0xFF, 0x1F], None, None),
+            (&[0x80, 0x80, 0x80, 0x80, 0x08], Some(1 << 31), None),
+            // Overlong and cut off
+            (&[0xFF; 6], None, None),
+            (&[0x80], None, None),
+        ];
+        for (bytes, uleb, sleb) in edges {
+            assert_eq!(checked_uleb128(bytes, 0).map(|(value, _)| value), *uleb, "{:02X?}", bytes);
+            assert_eq!(checked_sleb128(bytes, 0).map(|(value, _)| value), *sleb, "{:02X?}", bytes);
+        }
+    }
+
+    /// The lenient readers end a value of continuation bytes only after 5 of them
+    #[test]
+    fn ends_overlong_values_after_5_bytes() {
+        assert_eq!(read_uleb128(&[0xFF; 8], 0).1, 5);
+        assert_eq!(read_sleb128(&[0xFF; 8], 0).1, 5);
     }
-    (result, offset)
 }
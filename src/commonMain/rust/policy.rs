@@ -0,0 +1,309 @@
+// Capabilities sources need beyond plain computation, granted per source by the host. Sources
+// the host didn't configure get each capability's default. Hardened hosts can additionally
+// forbid opcodes and platform namespaces outright, extensions using them are rejected at install.
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::model::{resolve_reference, Reference};
+use crate::parser::parser::Parser;
+use crate::types::{method_key, DexClass, DexMethod, HiddenApiFlags, Instruction};
+use crate::utils::class_descriptor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// Reading and writing files inside the source's own scratch directory
+    ScratchStorage,
+}
+
+impl Capability {
+    /// Capability from its host-facing name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "scratchStorage" => Some(Capability::ScratchStorage),
+            _ => None,
+        }
+    }
+
+    fn granted_by_default(self) -> bool {
+        match self {
+            // Confined to a directory of its own, sources caching tokens shouldn't need setup
+            Capability::ScratchStorage => true,
+        }
+    }
+}
+
+lazy_static! {
+    /// Decisions of the host, keyed by the descriptor of the source's main class
+    static ref GRANTS: Mutex<HashMap<(String, Capability), bool>> = Mutex::new(HashMap::new());
+    static ref INSTALL_POLICY: Mutex<InstallPolicy> = Mutex::new(InstallPolicy::default());
+}
+
+/// Grant or revoke `capability` for `source`, given as a class name or descriptor
+pub fn set_granted(source: &str, capability: Capability, granted: bool) {
+    GRANTS.lock().unwrap().insert((class_descriptor(source), capability), granted);
+}
+
+pub fn is_granted(source: &str, capability: Capability) -> bool {
+    GRANTS.lock().unwrap()
+        .get(&(class_descriptor(source), capability))
+        .copied()
+        .unwrap_or_else(|| capability.granted_by_default())
+}
+
+/// What extensions may contain, given by the host as JSON at init. Opcodes are named like
+/// `invoke-custom` or `invoke-custom/range`, namespaces like `java.lang.reflect`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InstallPolicy {
+    /// Only these opcodes may be used, if given
+    pub allowed_opcodes: Option<Vec<String>>,
+    pub denied_opcodes: Vec<String>,
+    /// Packages whose classes, fields and methods extensions may not reference
+    pub denied_namespaces: Vec<String>,
+}
+
+/// A use of something the install policy forbids
+#[derive(Debug, Clone, Serialize)]
+pub struct PolicyViolation {
+    pub class_name: String,
+    /// Key of the method, see `DexClass.methods`
+    pub method: String,
+    /// Position of the instruction in the method
+    pub pc: usize,
+    pub detail: String,
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{} at {}: {}", self.class_name, self.method, self.pc, self.detail)
+    }
+}
+
+pub fn set_install_policy(policy: InstallPolicy) {
+    *INSTALL_POLICY.lock().unwrap() = policy;
+}
+
+/// The install policy set with `set_install_policy`
+pub fn install_policy() -> InstallPolicy {
+    INSTALL_POLICY.lock().unwrap().clone()
+}
+
+/// Everything in the parsed extension the install policy forbids, in class and method order
+pub fn check_install(parser: &Parser) -> Vec<PolicyViolation> {
+    check_install_with(&install_policy(), parser)
+}
+
+/// Like `check_install` with `policy`, e.g. one taken once for a whole batch of installs
+pub fn check_install_with(policy: &InstallPolicy, parser: &Parser) -> Vec<PolicyViolation> {
+    if policy.allowed_opcodes.is_none() && policy.denied_opcodes.is_empty() && policy.denied_namespaces.is_empty() {
+        return Vec::new();
+    }
+
+    let mut violations = Vec::new();
+    for class in &parser.classes {
+        let mut methods: Vec<(&String, &DexMethod)> = class.methods.iter().collect();
+        methods.sort_by_key(|(key, _)| *key);
+
+        for (key, method) in methods {
+            // Code the interpreter couldn't run can't be vetted either
+            let instructions = match parser.instructions(class, method) {
+                Ok(instructions) => instructions,
+                Err(error) => {
+                    let detail = format!("code can't be decoded: {}", error);
+                    violations.push(PolicyViolation { class_name: class.name.clone(), method: key.clone(), pc: 0, detail });
+                    continue;
+                }
+            };
+            for (pc, instruction) in instructions.iter().enumerate() {
+                let detail = policy.opcode_violation(instruction)
+                    .or_else(|| policy.namespace_violation(resolve_reference(parser, class.dex, instruction)?));
+                if let Some(detail) = detail {
+                    violations.push(PolicyViolation { class_name: class.name.clone(), method: key.clone(), pc, detail });
+                }
+            }
+        }
+    }
+    violations
+}
+
+/// Human readable report of a rejected install
+pub fn rejection_report(violations: &[PolicyViolation]) -> String {
+    let mut report = format!("Rejected by the install policy, {} violations:", violations.len());
+    for violation in violations {
+        report.push_str(&format!("\n  {}", violation));
+    }
+    report
+}
+
+/// A field or method the extension's hiddenapi data puts on an API list
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HiddenApiMember {
+    pub class_name: String,
+    /// Name of a field or key of a method, see `DexClass.methods`
+    pub member: String,
+    /// API list, e.g. `sdk` or `blocked`, see `HiddenApiFlags::list`
+    pub list: &'static str,
+    pub core_platform_api: bool,
+    pub test_api: bool,
+}
+
+impl HiddenApiMember {
+    fn new(class_name: &str, member: &str, flags: HiddenApiFlags) -> Self {
+        HiddenApiMember {
+            class_name: class_name.to_string(),
+            member: member.to_string(),
+            list: flags.list(),
+            core_platform_api: flags.is_core_platform_api(),
+            test_api: flags.is_test_api(),
+        }
+    }
+}
+
+/// A reference to a member on a restricted API list
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HiddenApiUse {
+    pub class_name: String,
+    /// Key of the method the reference is in
+    pub method: String,
+    pub pc: usize,
+    pub target: HiddenApiMember,
+}
+
+/// What the hiddenapi data of an extension says, for hosts deciding whether to trust it. Only
+/// members of the extension's own classes carry flags, references to the framework aren't covered.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct HiddenApiReport {
+    /// Every flagged member, in class and member order
+    pub marked: Vec<HiddenApiMember>,
+    /// References to members whose list isn't `sdk`, in class, method and pc order
+    pub used: Vec<HiddenApiUse>,
+}
+
+/// Hiddenapi flags of the parsed extension and the references to its restricted members. Empty
+/// for dex files before 039 or without a hiddenapi section.
+pub fn hidden_api_report(parser: &Parser) -> HiddenApiReport {
+    let mut report = HiddenApiReport::default();
+    let find_class = |name: &str| parser.classes.iter().find(|class| class.name == name);
+
+    for class in &parser.classes {
+        let mut fields: Vec<(&String, HiddenApiFlags)> = class.static_fields.iter()
+            .chain(&class.instance_fields)
+            .filter_map(|(name, field)| Some((name, field.hidden_api?)))
+            .collect();
+        fields.sort_by_key(|(name, _)| *name);
+        let mut methods: Vec<(&String, HiddenApiFlags)> = class.methods.iter()
+            .filter_map(|(key, method)| Some((key, method.hidden_api?)))
+            .collect();
+        methods.sort_by_key(|(key, _)| *key);
+
+        report.marked.extend(
+            fields.into_iter().chain(methods).map(|(member, flags)| HiddenApiMember::new(&class.name, member, flags)),
+        );
+    }
+
+    for class in &parser.classes {
+        let mut methods: Vec<(&String, &DexMethod)> = class.methods.iter().collect();
+        methods.sort_by_key(|(key, _)| *key);
+
+        for (key, method) in methods {
+            let Ok(instructions) = parser.instructions(class, method) else {
+                continue;
+            };
+            for (pc, instruction) in instructions.iter().enumerate() {
+                let target = match resolve_reference(parser, class.dex, instruction) {
+                    Some(Reference::Field { class_name, name, .. }) => find_class(&class_name)
+                        .and_then(|target| field_flags(target, &name))
+                        .map(|flags| (class_name, name, flags)),
+                    Some(Reference::Method { class_name, name, descriptor }) => {
+                        let member = method_key(&name, &descriptor);
+                        find_class(&class_name)
+                            .and_then(|target| target.methods.get(&member)?.hidden_api)
+                            .map(|flags| (class_name, member, flags))
+                    }
+                    _ => None,
+                };
+                if let Some((class_name, member, flags)) = target && flags.is_restricted() {
+                    report.used.push(HiddenApiUse {
+                        class_name: class.name.clone(),
+                        method: key.clone(),
+                        pc,
+                        target: HiddenApiMember::new(&class_name, &member, flags),
+                    });
+                }
+            }
+        }
+    }
+    report
+}
+
+fn field_flags(class: &DexClass, name: &str) -> Option<HiddenApiFlags> {
+    class.static_fields.get(name).or_else(|| class.instance_fields.get(name))?.hidden_api
+}
+
+impl InstallPolicy {
+    fn opcode_violation(&self, instruction: &Instruction) -> Option<String> {
+        let name = opcode_name(instruction);
+        // Denying an opcode covers its range form
+        let names: Vec<String> = std::iter::once(name.clone())
+            .chain(instruction.expand_range().map(|expanded| opcode_name(&expanded)))
+            .collect();
+        let listed = |opcodes: &[String]| opcodes.iter()
+            .any(|opcode| names.iter().any(|name| normalize_opcode(opcode) == normalize_opcode(name)));
+
+        if let Some(allowed) = &self.allowed_opcodes && !listed(allowed) {
+            return Some(format!("opcode {} is not in the allowlist", name));
+        }
+        if listed(&self.denied_opcodes) {
+            return Some(format!("opcode {} is denied", name));
+        }
+        None
+    }
+
+    fn namespace_violation(&self, reference: Reference) -> Option<String> {
+        let java_name = |descriptor: &str| descriptor
+            .trim_start_matches('[')
+            .trim_start_matches('L')
+            .trim_end_matches(';')
+            .replace('/', ".");
+        let (class_name, subject) = match reference {
+            Reference::Type(descriptor) => (java_name(&descriptor), format!("type {}", java_name(&descriptor))),
+            Reference::Field { class_name, name, .. } => (java_name(&class_name), format!("field {}.{}", java_name(&class_name), name)),
+            Reference::Method { class_name, name, .. } => (java_name(&class_name), format!("method {}.{}", java_name(&class_name), name)),
+            Reference::String(_) | Reference::CallSite { .. } => return None,
+        };
+
+        self.denied_namespaces.iter()
+            .map(|namespace| namespace.trim_end_matches(".*"))
+            .find(|namespace| class_name == *namespace || class_name.starts_with(&format!("{}.", namespace)))
+            .map(|namespace| format!("{} is in the denied namespace {}", subject, namespace))
+    }
+}
+
+/// Kebab case name of the instruction's variant, e.g. `invoke-custom-range`
+fn opcode_name(instruction: &Instruction) -> String {
+    let debug = format!("{:?}", instruction);
+    let variant = debug.split([' ', '{', '(']).next().unwrap_or_default();
+
+    let mut name = String::new();
+    for (i, c) in variant.char_indices() {
+        if i > 0 && c.is_ascii_uppercase() {
+            name.push('-');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+/// `invoke-custom/range`, `invoke-custom-range` and `InvokeCustomRange` all name the same opcode
+fn normalize_opcode(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
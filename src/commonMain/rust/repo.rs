@@ -0,0 +1,79 @@
+// Extension repository index handling. A repo publishes `index.min.json`, a list of every
+// extension it hosts, which is compared against what the host has installed to find updates.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// One entry of a repo's `index.min.json`
+#[derive(Debug, Clone, Deserialize)]
+pub struct RepoExtension {
+    pub name: String,
+    pub pkg: String,
+    pub apk: String,
+    pub code: i64,
+    pub version: String,
+}
+
+/// An extension the host has installed
+#[derive(Debug, Clone, Deserialize)]
+pub struct InstalledExtension {
+    pub pkg: String,
+    pub code: i64,
+    /// SHA-256 fingerprint of the certificate the installed apk was signed with
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtensionUpdate {
+    pub pkg: String,
+    pub name: String,
+    pub installed_code: i64,
+    pub available_code: i64,
+    pub version: String,
+    pub apk: String,
+    /// The repo signs with a different key than the installed apk, so the update can't be
+    /// installed over it without removing the old extension first
+    pub signature_mismatch: bool,
+}
+
+/// Installed extensions that have a newer version code in `index`. `fingerprint` is the repo's
+/// signing key fingerprint from its `repo.json`, when known.
+pub fn find_updates(index: &[RepoExtension], installed: &[InstalledExtension], fingerprint: Option<&str>) -> Vec<ExtensionUpdate> {
+    let available: HashMap<&str, &RepoExtension> = index.iter()
+        .map(|extension| (extension.pkg.as_str(), extension))
+        .collect();
+
+    installed.iter()
+        .filter_map(|installed| {
+            let extension = available.get(installed.pkg.as_str())?;
+            if extension.code <= installed.code {
+                return None;
+            }
+
+            let signature_mismatch = match (installed.signature.as_deref(), fingerprint) {
+                (Some(signature), Some(fingerprint)) => !signature.eq_ignore_ascii_case(fingerprint),
+                _ => false,
+            };
+
+            Some(ExtensionUpdate {
+                pkg: installed.pkg.clone(),
+                name: extension.name.clone(),
+                installed_code: installed.code,
+                available_code: extension.code,
+                version: extension.version.clone(),
+                apk: extension.apk.clone(),
+                signature_mismatch,
+            })
+        })
+        .collect()
+}
+
+/// JSON entry point: parses both lists and returns the updates as a JSON array
+pub fn check_updates(index_json: &str, installed_json: &str, fingerprint: Option<&str>) -> Result<String, serde_json::Error> {
+    let index: Vec<RepoExtension> = serde_json::from_str(index_json)?;
+    let installed: Vec<InstalledExtension> = serde_json::from_str(installed_json)?;
+
+    serde_json::to_string(&find_updates(&index, &installed, fingerprint))
+}
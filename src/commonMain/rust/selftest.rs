@@ -0,0 +1,537 @@
+// Startup self test. Checks that the library fits the environment it was loaded into: the JNI
+// bridge, the sizes of the types it converts, both dex byte orders, the on-disk cache, and a
+// micro dex parsed and run through the interpreter. Development fixtures live in the modules'
+// cargo tests, which share the micro dex built here.
+
+use jni::JNIEnv;
+use jni::sys::{jboolean, jbyte, jchar, jdouble, jfloat, jint, jlong, jshort, JNI_VERSION_1_6};
+use serde::Serialize;
+use sha1::{Digest, Sha1};
+use std::mem::size_of;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use crate::interpreter::interpreter::Interpreter;
+use crate::parser::endian::{Endian, ENDIAN_CONSTANT};
+use crate::parser::header::adler32;
+use crate::parser::parser::Parser;
+use crate::types::DexValue;
+use crate::utils::panic_message;
+
+pub(crate) const SELF_TEST_CLASS: &str = "Lmihonx/selftest/SelfTest;";
+pub(crate) const OBJECT_CLASS: &str = "Ljava/lang/Object;";
+pub(crate) const SELF_TEST_STRING: &str = "selftest";
+/// Static int field of the micro dex, seeded through static_values and bumped by `<clinit>`
+const STATIC_FIELD: &str = "counter";
+const STATIC_FIELD_VALUE: u8 = 40;
+const CLASS_INITIALIZER: &str = "<clinit>";
+/// Class of the host's side of the bridge, looked up to check the JNI wiring
+const BRIDGE_CLASS: &str = "mihonx/runner/RustBridge";
+
+#[derive(Debug, Serialize)]
+pub struct SelfTestCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SelfTestReport {
+    pub passed: bool,
+    pub checks: Vec<SelfTestCheck>,
+}
+
+impl SelfTestReport {
+    /// Add a check made outside of `run_self_test`, e.g. one that needs the JNI environment
+    pub fn push(&mut self, check: SelfTestCheck) {
+        self.passed &= check.passed;
+        self.checks.push(check);
+    }
+}
+
+/// A public static method of the micro dex, executed without arguments
+pub(crate) struct SelfTestCase {
+    pub name: &'static str,
+    pub return_type: &'static str,
+    pub registers: u16,
+    /// Builds the raw instructions, string constants are resolved through the string pool
+    pub insns: fn(&[String]) -> Vec<u8>,
+    pub expected: fn() -> DexValue,
+}
+
+pub(crate) fn self_test_cases() -> Vec<SelfTestCase> {
+    vec![
+        SelfTestCase {
+            name: "constInt",
+            return_type: "I",
+            registers: 2,
+            // const/4 v0, #7; return v0
+            insns: |_| vec![0x12, 0x70, 0x0F, 0x00],
+            expected: || DexValue::Int(7),
+        },
+        SelfTestCase {
+            name: "constString",
+            return_type: "Ljava/lang/String;",
+            registers: 2,
+            // const-string v0, "selftest"; return-object v0
+            insns: |strings| {
+                let [lo, hi] = string_idx(strings, SELF_TEST_STRING).to_le_bytes();
+                vec![0x1A, 0x00, lo, hi, 0x11, 0x00]
+            },
+            expected: || DexValue::String(SELF_TEST_STRING.to_string()),
+        },
+        SelfTestCase {
+            name: "addLong",
+            return_type: "J",
+            registers: 4,
+            // const-wide/16 v0, #40; const-wide/16 v2, #2; add-long v0, v0, v2; return-wide v0
+            insns: |_| vec![0x16, 0x00, 40, 0x00, 0x16, 0x02, 2, 0x00, 0x9B, 0x00, 0x00, 0x02, 0x10, 0x00],
+            expected: || DexValue::Long(42),
+        },
+        SelfTestCase {
+            name: "cmpLong",
+            return_type: "I",
+            registers: 4,
+            // const-wide/16 v0, #5; const-wide/16 v2, #7; cmp-long v0, v0, v2; return v0
+            insns: |_| vec![0x16, 0x00, 5, 0x00, 0x16, 0x02, 7, 0x00, 0x31, 0x00, 0x00, 0x02, 0x0F, 0x00],
+            expected: || DexValue::Int(-1),
+        },
+        SelfTestCase {
+            name: "cmplDouble",
+            return_type: "I",
+            registers: 4,
+            // const-wide/high16 v0, #2.0; const-wide/high16 v2, #1.0; cmpl-double v0, v0, v2; return v0
+            insns: |_| vec![0x19, 0x00, 0x00, 0x40, 0x19, 0x02, 0xF0, 0x3F, 0x2F, 0x00, 0x00, 0x02, 0x0F, 0x00],
+            expected: || DexValue::Int(1),
+        },
+        SelfTestCase {
+            name: "move16",
+            return_type: "I",
+            registers: 300,
+            // const/4 v0, #7; move/16 v299, v0; move/from16 v1, v299; return v1
+            insns: |_| vec![0x12, 0x70, 0x03, 0x00, 0x2B, 0x01, 0x00, 0x00, 0x02, 0x01, 0x2B, 0x01, 0x0F, 0x01],
+            expected: || DexValue::Int(7),
+        },
+        SelfTestCase {
+            name: CLASS_INITIALIZER,
+            return_type: "V",
+            registers: 1,
+            // sget v0, counter; add-int/lit8 v0, v0, #1; sput v0, counter; return-void
+            insns: |_| vec![0x60, 0x00, 0x00, 0x00, 0xD8, 0x00, 0x00, 0x01, 0x67, 0x00, 0x00, 0x00, 0x0E, 0x00],
+            expected: || DexValue::Void,
+        },
+        SelfTestCase {
+            name: "staticField",
+            return_type: "I",
+            registers: 1,
+            // sget v0, counter; return v0
+            insns: |_| vec![0x60, 0x00, 0x00, 0x00, 0x0F, 0x00],
+            expected: || DexValue::Int(STATIC_FIELD_VALUE as i32 + 1),
+        },
+    ]
+}
+
+/// Run the self test with the cache in `cache_dir`
+pub fn run_self_test(cache_dir: &Path) -> SelfTestReport {
+    let mut checks = Vec::new();
+    let cases = self_test_cases();
+
+    checks.push(check_platform());
+    checks.push(check_cache_access(cache_dir));
+
+    let parsed = panic::catch_unwind(|| {
+        let mut parser = Parser::new(build_micro_dex(&cases, Endian::Little), false);
+        parser.persist = false;
+        parser.parse().map(|_| parser)
+    });
+
+    match parsed {
+        Ok(Ok(parser)) if parser.classes.len() == 1 && parser.classes[0].name == SELF_TEST_CLASS => {
+            checks.push(SelfTestCheck {
+                name: "parse".to_string(),
+                passed: true,
+                detail: format!("{} methods", parser.classes[0].methods.len()),
+            });
+
+            let mut interpreter = Interpreter::new(parser);
+            // <clinit> is run by the interpreter itself on the first static field access
+            for case in cases.iter().filter(|case| case.name != CLASS_INITIALIZER) {
+                checks.push(run_case(&mut interpreter, case));
+            }
+        }
+        Ok(Ok(parser)) => checks.push(SelfTestCheck {
+            name: "parse".to_string(),
+            passed: false,
+            detail: format!("Unexpected classes: {:?}", parser.classes.iter().map(|c| &c.name).collect::<Vec<_>>()),
+        }),
+        Ok(Err(error)) => checks.push(SelfTestCheck {
+            name: "parse".to_string(),
+            passed: false,
+            detail: error.to_string(),
+        }),
+        Err(error) => checks.push(SelfTestCheck {
+            name: "parse".to_string(),
+            passed: false,
+            detail: panic_message(error),
+        }),
+    }
+
+    SelfTestReport {
+        passed: checks.iter().all(|check| check.passed),
+        checks,
+    }
+}
+
+fn run_case(interpreter: &mut Interpreter, case: &SelfTestCase) -> SelfTestCheck {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| interpreter.call_method(0, case.name, None, Vec::new())));
+    interpreter.frames.clear();
+
+    let expected = (case.expected)();
+    match result {
+        Ok(Ok(Some(value))) if value == expected => SelfTestCheck {
+            name: case.name.to_string(),
+            passed: true,
+            detail: format!("{:?}", value),
+        },
+        Ok(Ok(value)) => SelfTestCheck {
+            name: case.name.to_string(),
+            passed: false,
+            detail: format!("Expected {:?} but got {:?}", expected, value),
+        },
+        Ok(Err(error)) => SelfTestCheck {
+            name: case.name.to_string(),
+            passed: false,
+            detail: error.to_string(),
+        },
+        Err(error) => SelfTestCheck {
+            name: case.name.to_string(),
+            passed: false,
+            detail: panic_message(error),
+        },
+    }
+}
+
+/// The JNI types have the sizes the bridge converts them with, dex offsets fit a usize, and
+/// both byte orders of a dex read back on this target
+fn check_platform() -> SelfTestCheck {
+    let sizes = [
+        ("jboolean", size_of::<jboolean>(), 1),
+        ("jbyte", size_of::<jbyte>(), 1),
+        ("jchar", size_of::<jchar>(), 2),
+        ("jshort", size_of::<jshort>(), 2),
+        ("jint", size_of::<jint>(), 4),
+        ("jlong", size_of::<jlong>(), 8),
+        ("jfloat", size_of::<jfloat>(), 4),
+        ("jdouble", size_of::<jdouble>(), 8),
+        ("usize", size_of::<usize>().min(4), 4),
+    ];
+    let mut failures: Vec<String> = sizes.iter()
+        .filter(|(_, size, expected)| size != expected)
+        .map(|(name, size, _)| format!("{} is {} bytes", name, size))
+        .collect();
+    for endian in [Endian::Little, Endian::Big] {
+        if endian.u32(endian.u32_bytes(ENDIAN_CONSTANT)) != ENDIAN_CONSTANT {
+            failures.push(format!("{:?}-endian values don't read back", endian));
+        }
+    }
+    if Endian::Little.u32(ENDIAN_CONSTANT.to_le_bytes()) != ENDIAN_CONSTANT {
+        failures.push("little-endian dex values are misread".to_string());
+    }
+
+    SelfTestCheck {
+        name: "platform".to_string(),
+        passed: failures.is_empty(),
+        detail: if failures.is_empty() {
+            format!("{} {}-bit {}-endian", std::env::consts::ARCH, usize::BITS, if cfg!(target_endian = "big") { "big" } else { "little" })
+        } else {
+            failures.join(", ")
+        },
+    }
+}
+
+/// The host registered its JVM through nativeInit, the JNI version is at least 1.6 and the
+/// bridge class resolves from native code
+pub fn check_jni(env: &mut JNIEnv, vm_registered: bool) -> SelfTestCheck {
+    let mut failures = Vec::new();
+    if !vm_registered {
+        failures.push("nativeInit wasn't called".to_string());
+    }
+    let version = env.get_version().map(jint::from);
+    if !version.as_ref().is_ok_and(|version| *version >= JNI_VERSION_1_6) {
+        failures.push(format!("JNI version {:?}", version));
+    }
+    if env.find_class(BRIDGE_CLASS).is_err() {
+        let _ = env.exception_clear();
+        failures.push(format!("{} not found", BRIDGE_CLASS));
+    }
+
+    SelfTestCheck {
+        name: "jni".to_string(),
+        passed: failures.is_empty(),
+        detail: if failures.is_empty() { format!("{} resolved", BRIDGE_CLASS) } else { failures.join(", ") },
+    }
+}
+
+fn check_cache_access(dir: &Path) -> SelfTestCheck {
+    let probe = dir.join(".selftest");
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&probe, SELF_TEST_STRING))
+        .and_then(|_| std::fs::read(&probe))
+        .and_then(|content| {
+            std::fs::remove_file(&probe)?;
+            if content == SELF_TEST_STRING.as_bytes() {
+                Ok(())
+            } else {
+                Err(std::io::Error::other("Probe file content mismatch"))
+            }
+        });
+
+    SelfTestCheck {
+        name: "cache".to_string(),
+        passed: result.is_ok(),
+        detail: match result {
+            Ok(_) => format!("{} is writable", dir.display()),
+            Err(error) => error.to_string(),
+        },
+    }
+}
+
+
+fn string_idx(strings: &[String], value: &str) -> u16 {
+    strings.iter().position(|s| s == value).expect("String missing from micro dex pool") as u16
+}
+
+fn shorty_of(descriptor: &str) -> &str {
+    if descriptor.starts_with('L') || descriptor.starts_with('[') {
+        "L"
+    } else {
+        descriptor
+    }
+}
+
+fn push_uleb128(bytes: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            break;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn align4(bytes: &mut Vec<u8>) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(0);
+    }
+}
+
+/// The micro dex of `cases` parsed, without writing the cache
+#[cfg(test)]
+pub(crate) fn parse_micro_dex(cases: &[SelfTestCase]) -> Parser {
+    parse_micro_dex_with(cases, |_| {})
+}
+
+/// Cache directory of a test under the system temp directory, removed again when dropped
+#[cfg(test)]
+pub(crate) struct TestCacheDir(pub std::path::PathBuf);
+
+#[cfg(test)]
+impl TestCacheDir {
+    /// An empty directory named after the test `name`, unique to this test process
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("mihon-runner-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        TestCacheDir(dir)
+    }
+}
+
+#[cfg(test)]
+impl Drop for TestCacheDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Like `parse_micro_dex`, with `configure` applied to the parser ahead of parsing
+#[cfg(test)]
+pub(crate) fn parse_micro_dex_with(cases: &[SelfTestCase], configure: impl FnOnce(&mut Parser)) -> Parser {
+    let mut parser = Parser::new(build_micro_dex(cases, Endian::Little), false);
+    parser.persist = false;
+    configure(&mut parser);
+    parser.parse().unwrap();
+    parser
+}
+
+/// Assembles a single class dex holding `cases` as public static methods next to one static int field,
+/// with its fixed-width fields in `endian` byte order
+pub(crate) fn build_micro_dex(cases: &[SelfTestCase], endian: Endian) -> Vec<u8> {
+    build_micro_dex_extending(cases, endian, OBJECT_CLASS)
+}
+
+/// `build_micro_dex` with the class extending `superclass` instead of Object
+pub(crate) fn build_micro_dex_extending(cases: &[SelfTestCase], endian: Endian, superclass: &str) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 0x70;
+    let push_u16 = |bytes: &mut Vec<u8>, value: u16| bytes.extend_from_slice(&endian.u16_bytes(value));
+    let push_u32 = |bytes: &mut Vec<u8>, value: u32| bytes.extend_from_slice(&endian.u32_bytes(value));
+
+    let mut strings: Vec<String> = vec![
+        SELF_TEST_CLASS.to_string(),
+        superclass.to_string(),
+        SELF_TEST_STRING.to_string(),
+        STATIC_FIELD.to_string(),
+        "I".to_string(),
+    ];
+    for case in cases {
+        strings.push(case.name.to_string());
+        strings.push(case.return_type.to_string());
+        strings.push(shorty_of(case.return_type).to_string());
+    }
+    strings.sort();
+    strings.dedup();
+
+    let mut types: Vec<u32> = vec![
+        string_idx(&strings, SELF_TEST_CLASS) as u32,
+        string_idx(&strings, superclass) as u32,
+        string_idx(&strings, "I") as u32,
+    ];
+    types.extend(cases.iter().map(|case| string_idx(&strings, case.return_type) as u32));
+    types.sort();
+    types.dedup();
+    let type_idx = |descriptor: &str| types.iter().position(|t| *t == string_idx(&strings, descriptor) as u32).unwrap() as u32;
+
+    let mut protos: Vec<&str> = cases.iter().map(|case| case.return_type).collect();
+    protos.sort_by_key(|descriptor| type_idx(descriptor));
+    protos.dedup();
+
+    let mut methods: Vec<&SelfTestCase> = cases.iter().collect();
+    methods.sort_by_key(|case| string_idx(&strings, case.name));
+
+    let string_ids_off = HEADER_SIZE;
+    let type_ids_off = string_ids_off + strings.len() as u32 * 4;
+    let proto_ids_off = type_ids_off + types.len() as u32 * 4;
+    let field_ids_off = proto_ids_off + protos.len() as u32 * 12;
+    let method_ids_off = field_ids_off + 8;
+    let class_defs_off = method_ids_off + methods.len() as u32 * 8;
+    let data_off = class_defs_off + 32;
+
+    // Data section, offsets inside of it are absolute file offsets
+    let mut data: Vec<u8> = Vec::new();
+    let mut string_offsets = Vec::new();
+    for string in &strings {
+        string_offsets.push(data_off + data.len() as u32);
+        push_uleb128(&mut data, string.encode_utf16().count() as u32);
+        data.extend_from_slice(string.as_bytes());
+        data.push(0);
+    }
+
+    let mut code_offsets = Vec::new();
+    for case in &methods {
+        align4(&mut data);
+        code_offsets.push(data_off + data.len() as u32);
+        let mut insns = (case.insns)(&strings);
+        endian.swap_code_units(&mut insns);
+        push_u16(&mut data, case.registers);
+        push_u16(&mut data, 0); // ins_size
+        push_u16(&mut data, 0); // outs_size
+        push_u16(&mut data, 0); // tries_size
+        push_u32(&mut data, 0); // debug_info_off
+        push_u32(&mut data, insns.len() as u32 / 2);
+        data.extend_from_slice(&insns);
+    }
+
+    let static_values_off = data_off + data.len() as u32;
+    push_uleb128(&mut data, 1); // size
+    data.extend_from_slice(&[0x04, STATIC_FIELD_VALUE]); // VALUE_INT, one byte wide
+
+    let class_data_off = data_off + data.len() as u32;
+    push_uleb128(&mut data, 1); // static_fields_size
+    push_uleb128(&mut data, 0); // instance_fields_size
+    push_uleb128(&mut data, methods.len() as u32); // direct_methods_size
+    push_uleb128(&mut data, 0); // virtual_methods_size
+    push_uleb128(&mut data, 0); // field_idx_diff
+    push_uleb128(&mut data, 0x0008); // ACC_STATIC
+    for (i, code_off) in code_offsets.iter().enumerate() {
+        push_uleb128(&mut data, if i == 0 { 0 } else { 1 }); // method_idx_diff
+        push_uleb128(&mut data, 0x0009); // ACC_PUBLIC | ACC_STATIC
+        push_uleb128(&mut data, *code_off);
+    }
+    align4(&mut data);
+
+    let file_size = data_off + data.len() as u32;
+
+    let mut bytes: Vec<u8> = Vec::with_capacity(file_size as usize);
+    bytes.extend_from_slice(b"dex\n035\0");
+    push_u32(&mut bytes, 0); // checksum, patched below
+    bytes.extend_from_slice(&[0; 20]); // signature, patched below
+    push_u32(&mut bytes, file_size);
+    push_u32(&mut bytes, HEADER_SIZE);
+    push_u32(&mut bytes, ENDIAN_CONSTANT); // endian_tag, reversed when written big-endian
+    push_u32(&mut bytes, 0); // link_size
+    push_u32(&mut bytes, 0); // link_off
+    push_u32(&mut bytes, 0); // map_off
+    push_u32(&mut bytes, strings.len() as u32);
+    push_u32(&mut bytes, string_ids_off);
+    push_u32(&mut bytes, types.len() as u32);
+    push_u32(&mut bytes, type_ids_off);
+    push_u32(&mut bytes, protos.len() as u32);
+    push_u32(&mut bytes, proto_ids_off);
+    push_u32(&mut bytes, 1); // field_ids_size
+    push_u32(&mut bytes, field_ids_off);
+    push_u32(&mut bytes, methods.len() as u32);
+    push_u32(&mut bytes, method_ids_off);
+    push_u32(&mut bytes, 1); // class_defs_size
+    push_u32(&mut bytes, class_defs_off);
+    push_u32(&mut bytes, data.len() as u32);
+    push_u32(&mut bytes, data_off);
+
+    for offset in &string_offsets {
+        push_u32(&mut bytes, *offset);
+    }
+    for string_id in &types {
+        push_u32(&mut bytes, *string_id);
+    }
+    for return_type in &protos {
+        push_u32(&mut bytes, string_idx(&strings, shorty_of(return_type)) as u32);
+        push_u32(&mut bytes, type_idx(return_type));
+        push_u32(&mut bytes, 0); // parameters_off
+    }
+    push_u16(&mut bytes, type_idx(SELF_TEST_CLASS) as u16);
+    push_u16(&mut bytes, type_idx("I") as u16);
+    push_u32(&mut bytes, string_idx(&strings, STATIC_FIELD) as u32);
+    for case in &methods {
+        push_u16(&mut bytes, type_idx(SELF_TEST_CLASS) as u16);
+        push_u16(&mut bytes, protos.iter().position(|p| *p == case.return_type).unwrap() as u16);
+        push_u32(&mut bytes, string_idx(&strings, case.name) as u32);
+    }
+
+    push_u32(&mut bytes, type_idx(SELF_TEST_CLASS));
+    push_u32(&mut bytes, 0x0001); // ACC_PUBLIC
+    push_u32(&mut bytes, type_idx(superclass));
+    push_u32(&mut bytes, 0); // interfaces_off
+    push_u32(&mut bytes, 0xFFFFFFFF); // source_file_idx
+    push_u32(&mut bytes, 0); // annotations_off
+    push_u32(&mut bytes, class_data_off);
+    push_u32(&mut bytes, static_values_off);
+
+    bytes.extend_from_slice(&data);
+
+    let signature = Sha1::digest(&bytes[32..]);
+    bytes[12..32].copy_from_slice(&signature);
+    let checksum = adler32(&bytes[12..]);
+    bytes[8..12].copy_from_slice(&endian.u32_bytes(checksum));
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passes_on_the_build_host() {
+        let dir = TestCacheDir::new("selftest");
+        let report = run_self_test(&dir.0);
+        assert!(report.passed, "{:?}", report.checks.iter().filter(|check| !check.passed).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,126 @@
+// Soak harness, runs the installed source's popular, search and chapter list flows over and over
+// for hours and writes one CSV row per call with its latency next to the heap and cache size, so
+// leaks and a cache outgrowing its budget show up as trends in the report.
+
+use std::io::{self, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cache;
+use crate::interpreter::error::InterpreterError;
+use crate::interpreter::interpreter::Interpreter;
+use crate::parser::parser::Parser;
+use crate::types::DexValue;
+
+pub const CSV_HEADER: &str = "elapsed_ms,round,flow,latency_ms,ok,heap_objects,cache_bytes,error";
+
+pub struct SoakConfig {
+    /// How long to keep starting new rounds
+    pub duration: Duration,
+    /// Pause after each round
+    pub interval: Duration,
+    pub query: String,
+}
+
+#[derive(Debug, Default)]
+pub struct SoakSummary {
+    pub rounds: u64,
+    pub calls: u64,
+    pub failures: u64,
+    pub max_heap_objects: usize,
+    pub max_latency: Duration,
+}
+
+/// Soak the installed extension with `config`, writing the CSV report to `report`
+pub fn run(config: &SoakConfig, report: &mut impl Write) -> io::Result<SoakSummary> {
+    let mut parser = Parser::initialize_from_files().map_err(io::Error::other)?;
+    parser.debug_enabled = false;
+    let mut interpreter = Interpreter::new(parser);
+    let extension = crate::init_extension(&mut interpreter).map_err(io::Error::other)?;
+
+    writeln!(report, "{}", CSV_HEADER)?;
+    let started = Instant::now();
+    let mut summary = SoakSummary::default();
+
+    while started.elapsed() < config.duration {
+        summary.rounds += 1;
+        let mut manga = None;
+
+        let popular = timed(|| interpreter.call_method(interpreter.main_idx, "getPopularManga", Some(extension), vec![DexValue::Int(1)]));
+        if let Ok(page) = &popular.1 {
+            manga = first_manga(&mut interpreter, page.clone());
+        }
+        sample(report, &mut summary, &interpreter, started, "popular", popular)?;
+
+        let filters = interpreter.call_method(interpreter.main_idx, "getFilterList", Some(extension), Vec::new())
+            .ok()
+            .flatten()
+            .unwrap_or(DexValue::Null);
+        let args = vec![DexValue::Int(1), DexValue::String(config.query.clone()), filters];
+        let search = timed(|| interpreter.call_method(interpreter.main_idx, "getSearchManga", Some(extension), args));
+        sample(report, &mut summary, &interpreter, started, "search", search)?;
+
+        // Chapters are listed for the first popular manga, rounds without one skip the flow
+        if let Some(manga) = manga {
+            let chapters = timed(|| interpreter.call_method(interpreter.main_idx, "getChapterList", Some(extension), vec![manga]));
+            sample(report, &mut summary, &interpreter, started, "chapters", chapters)?;
+        }
+
+        report.flush()?;
+        thread::sleep(config.interval);
+    }
+    Ok(summary)
+}
+
+fn timed<T>(call: impl FnOnce() -> T) -> (Duration, T) {
+    let started = Instant::now();
+    let value = call();
+    (started.elapsed(), value)
+}
+
+/// First manga of a MangasPage
+fn first_manga(interpreter: &mut Interpreter, page: Option<DexValue>) -> Option<DexValue> {
+    let Some(DexValue::Object(page)) = page else {
+        return None;
+    };
+    let Ok(Some(DexValue::Object(list))) = interpreter.call_virtual(page, "getMangas", Vec::new()) else {
+        return None;
+    };
+    interpreter.heap.get(&list)?.array.as_ref()?.first().cloned()
+}
+
+fn sample(
+    report: &mut impl Write,
+    summary: &mut SoakSummary,
+    interpreter: &Interpreter,
+    started: Instant,
+    flow: &str,
+    (latency, result): (Duration, Result<Option<DexValue>, InterpreterError>),
+) -> io::Result<()> {
+    let heap_objects = interpreter.heap.len();
+    summary.calls += 1;
+    summary.max_heap_objects = summary.max_heap_objects.max(heap_objects);
+    summary.max_latency = summary.max_latency.max(latency);
+
+    let error = match &result {
+        Ok(_) => String::new(),
+        Err(error) => {
+            summary.failures += 1;
+            // Quoted for CSV, stack traces span several lines
+            format!("\"{}\"", error.to_string().replace('"', "\"\""))
+        }
+    };
+
+    writeln!(
+        report,
+        "{},{},{},{},{},{},{},{}",
+        started.elapsed().as_millis(),
+        summary.rounds,
+        flow,
+        latency.as_millis(),
+        result.is_ok(),
+        heap_objects,
+        cache::usage().total,
+        error,
+    )
+}
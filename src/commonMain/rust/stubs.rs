@@ -0,0 +1,260 @@
+// Kotlin stubs of the parsed classes: their declarations and member signatures without bodies,
+// so integrators see the API surface of an installed extension without decompiling it. Nested
+// classes go inside their outer class, synthetic members and anonymous classes are left out.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::parser::parser::Parser;
+use crate::parser::signature::readable_type;
+use crate::types::{AccessFlags, DexClass, DexField, DexMethod};
+
+const INDENT: &str = "    ";
+const INNER_CLASS_ANNOTATION: &str = "Ldalvik/annotation/InnerClass;";
+
+/// Source of one top-level class and the classes nested in it
+#[derive(Debug, Clone, Serialize)]
+pub struct KotlinStub {
+    /// Relative path, e.g. `eu/kanade/tachiyomi/extension/en/Source.kt`
+    pub path: String,
+    pub source: String,
+}
+
+/// Stubs of every parsed class, by path
+pub fn kotlin_stubs(parser: &Parser) -> Vec<KotlinStub> {
+    let mut top_level: BTreeMap<&str, &DexClass> = BTreeMap::new();
+    let mut nested: BTreeMap<&str, Vec<&DexClass>> = BTreeMap::new();
+    for class in &parser.classes {
+        let name = class_path(&class.name);
+        match name.rsplit_once('$') {
+            // Anonymous classes and lambdas aren't part of the API
+            Some((_, simple_name)) if simple_name.starts_with(|c: char| c.is_ascii_digit()) => {}
+            Some((outer, _)) if parser.classes.iter().any(|class| class_path(&class.name) == outer) => {
+                nested.entry(outer).or_default().push(class);
+            }
+            _ => {
+                top_level.insert(name, class);
+            }
+        }
+    }
+
+    top_level.into_iter()
+        .map(|(name, class)| {
+            let (package, _) = name.rsplit_once('/').unwrap_or(("", name));
+            let mut source = format!("// Stub of {}, bodies left out\n", class.name);
+            if !package.is_empty() {
+                source.push_str(&format!("package {}\n", package.replace('/', ".")));
+            }
+            source.push('\n');
+            render_class(parser, class, &nested, 0, &mut source);
+            KotlinStub { path: format!("{}.kt", name.replace('$', "_")), source }
+        })
+        .collect()
+}
+
+/// `a/b/Outer$Inner` for `La/b/Outer$Inner;`
+fn class_path(descriptor: &str) -> &str {
+    descriptor.strip_prefix('L').and_then(|name| name.strip_suffix(';')).unwrap_or(descriptor)
+}
+
+/// Kotlin name of a type descriptor: primitives, String and Any as Kotlin has them, classes
+/// qualified, e.g. `okhttp3.Request.Builder`
+fn kotlin_type(descriptor: &str) -> String {
+    if let Some(component) = descriptor.strip_prefix('[')
+        && component.starts_with(['L', '['])
+    {
+        return format!("Array<{}>", kotlin_type(component));
+    }
+    match descriptor {
+        "Ljava/lang/String;" => "String".to_string(),
+        _ if descriptor.starts_with('L') => class_path(descriptor).replace(['/', '$'], "."),
+        _ => readable_type(descriptor),
+    }
+}
+
+fn visibility(flags: AccessFlags) -> &'static str {
+    if flags.contains(AccessFlags::PRIVATE) {
+        "private "
+    } else if flags.contains(AccessFlags::PROTECTED) {
+        "protected "
+    } else {
+        ""
+    }
+}
+
+fn render_class(parser: &Parser, class: &DexClass, nested: &BTreeMap<&str, Vec<&DexClass>>, depth: usize, out: &mut String) {
+    let indent = INDENT.repeat(depth);
+    // Whether a nested class is static or private is only in its InnerClass annotation
+    let flags = class.annotation(INNER_CLASS_ANNOTATION)
+        .and_then(|annotation| annotation.value("accessFlags")?.as_int())
+        .map_or(class.access_flags, |flags| AccessFlags::from_bits_retain(flags as u32));
+    let simple_name = class_path(&class.name).rsplit(['/', '$']).next().unwrap_or_default();
+    let kind = if flags.contains(AccessFlags::ANNOTATION) {
+        "annotation class"
+    } else if flags.contains(AccessFlags::INTERFACE) {
+        "interface"
+    } else if flags.contains(AccessFlags::ENUM) {
+        "enum class"
+    } else if flags.contains(AccessFlags::ABSTRACT) {
+        "abstract class"
+    } else if !flags.contains(AccessFlags::FINAL) {
+        "open class"
+    } else {
+        "class"
+    };
+    // Nested classes that aren't static are inner classes, they hold their outer instance
+    let inner = depth > 0 && !flags.contains(AccessFlags::STATIC) && !flags.intersects(AccessFlags::INTERFACE | AccessFlags::ENUM);
+
+    let mut supertypes: Vec<String> = class.super_class.iter()
+        .filter(|super_class| !matches!(super_class.as_str(), "Ljava/lang/Object;" | "Ljava/lang/Enum;"))
+        .map(|super_class| format!("{}()", kotlin_type(super_class)))
+        .collect();
+    supertypes.extend(
+        class.interfaces.iter()
+            .filter(|interface| *interface != "Ljava/lang/annotation/Annotation;")
+            .map(|interface| kotlin_type(interface)),
+    );
+    out.push_str(&format!(
+        "{}{}{}{} {}{}",
+        indent,
+        visibility(flags),
+        if inner { "inner " } else { "" },
+        kind,
+        simple_name,
+        if supertypes.is_empty() { String::new() } else { format!(" : {}", supertypes.join(", ")) },
+    ));
+
+    let mut members = Vec::new();
+    let mut statics = Vec::new();
+    let mut fields: Vec<(&String, &DexField)> = class.instance_fields.iter().chain(&class.static_fields).collect();
+    fields.sort_by_key(|(name, _)| *name);
+    let entries: Vec<&str> = fields.iter()
+        .filter(|(_, field)| field.access_flags.contains(AccessFlags::ENUM))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    for (name, field) in &fields {
+        if field.access_flags.intersects(AccessFlags::SYNTHETIC | AccessFlags::ENUM) {
+            continue;
+        }
+        let keyword = if field.access_flags.contains(AccessFlags::FINAL) { "val" } else { "var" };
+        let line = format!("{}{} {}: {} = TODO()", visibility(field.access_flags), keyword, name, kotlin_type(&field.ty));
+        if field.is_static { statics.push(line) } else { members.push(line) }
+    }
+
+    let mut methods: Vec<(&String, &DexMethod)> = class.methods.iter().collect();
+    methods.sort_by_key(|(key, _)| *key);
+    for (_, method) in methods {
+        let method_flags = method.access_flags;
+        if method_flags.intersects(AccessFlags::SYNTHETIC | AccessFlags::BRIDGE) || method.name == "<clinit>" {
+            continue;
+        }
+        let names = parameter_names(parser, class, method);
+        let parameters: Vec<String> = method.parameters.iter()
+            .enumerate()
+            .map(|(i, ty)| format!("{}: {}", names.get(i).cloned().flatten().unwrap_or_else(|| format!("p{}", i)), kotlin_type(ty)))
+            .collect();
+        if method.name == "<init>" {
+            members.push(format!("{}constructor({})", visibility(method_flags), parameters.join(", ")));
+            continue;
+        }
+
+        let abstract_method = method_flags.contains(AccessFlags::ABSTRACT);
+        let mut line = visibility(method_flags).to_string();
+        if abstract_method && !flags.contains(AccessFlags::INTERFACE) {
+            line.push_str("abstract ");
+        } else if !abstract_method && !method_flags.intersects(AccessFlags::FINAL | AccessFlags::PRIVATE | AccessFlags::STATIC)
+            && !flags.intersects(AccessFlags::FINAL | AccessFlags::INTERFACE)
+        {
+            line.push_str("open ");
+        }
+        if method_flags.contains(AccessFlags::NATIVE) {
+            line.push_str("external ");
+        }
+        line.push_str(&format!("fun {}({})", method.name, parameters.join(", ")));
+        if method.return_type != "V" {
+            line.push_str(&format!(": {}", kotlin_type(&method.return_type)));
+        }
+        if !abstract_method && !method_flags.contains(AccessFlags::NATIVE) {
+            line.push_str(" = TODO()");
+        }
+        if method_flags.contains(AccessFlags::STATIC) { statics.push(line) } else { members.push(line) }
+    }
+
+    let children = nested.get(class_path(&class.name)).map(Vec::as_slice).unwrap_or_default();
+    if entries.is_empty() && members.is_empty() && statics.is_empty() && children.is_empty() {
+        out.push('\n');
+        return;
+    }
+    out.push_str(" {\n");
+    let member_indent = INDENT.repeat(depth + 1);
+    if !entries.is_empty() {
+        out.push_str(&format!("{}{};\n", member_indent, entries.join(", ")));
+    }
+    for member in &members {
+        out.push_str(&format!("{}{}\n", member_indent, member));
+    }
+    if !statics.is_empty() {
+        if !entries.is_empty() || !members.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&format!("{}companion object {{\n", member_indent));
+        for member in &statics {
+            out.push_str(&format!("{}{}@JvmStatic {}\n", member_indent, INDENT, member));
+        }
+        out.push_str(&format!("{}}}\n", member_indent));
+    }
+    for child in children {
+        out.push('\n');
+        render_class(parser, child, nested, depth + 1, out);
+    }
+    out.push_str(&format!("{}}}\n", indent));
+}
+
+/// Names of the parameters of `method` from its debug info, `None` for the ones without.
+/// Arguments take the last registers of the frame, after `this` for instance methods.
+fn parameter_names(parser: &Parser, class: &DexClass, method: &DexMethod) -> Vec<Option<String>> {
+    let Ok(method) = parser.decoded(class, method) else {
+        return Vec::new();
+    };
+    let mut register = method.registers.saturating_sub(method.ins_size);
+    if !method.access_flags.contains(AccessFlags::STATIC) {
+        register += 1;
+    }
+    method.parameters.iter()
+        .map(|ty| {
+            let name = method.locals.iter()
+                .find(|local| local.register == register && local.start_addr == 0)
+                .map(|local| local.name.clone());
+            register += if matches!(ty.as_str(), "J" | "D") { 2 } else { 1 };
+            name
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::selftest::{parse_micro_dex, self_test_cases};
+
+    /// The stub of the micro dex class has its package, declaration and the signatures of its
+    /// static methods and field, but no `<clinit>`
+    #[test]
+    fn renders_static_members() {
+        let stubs = kotlin_stubs(&parse_micro_dex(&self_test_cases()));
+        let [stub] = stubs.as_slice() else {
+            panic!("{} stubs", stubs.len());
+        };
+        assert_eq!(stub.path, "mihonx/selftest/SelfTest.kt");
+        for line in [
+            "package mihonx.selftest\n",
+            "class SelfTest {",
+            "companion object {",
+            "@JvmStatic var counter: Int = TODO()",
+            "@JvmStatic fun constInt(): Int = TODO()",
+            "@JvmStatic fun addLong(): Long = TODO()",
+        ] {
+            assert!(stub.source.contains(line), "no {:?} in {}", line, stub.source);
+        }
+        assert!(!stub.source.contains("<clinit>"));
+    }
+}
@@ -1,7 +1,12 @@
 
 // Custom Representation
+use bitflags::bitflags;
 use serde::{Serialize, Deserialize};
 use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::parser::endian::Endian;
+use crate::parser::signature::render_signature;
 
 
 /// Object identity in the heap
@@ -15,16 +20,154 @@ pub struct Object {
     pub class_name: String,
     pub fields: HashMap<String, DexValue>, // instance fields
     pub methods: HashMap<String, Option<NativeMethod>>, // e.g. "getUserAgent:()Ljava/lang/String;" -> fn
+    /// Elements, when the object is an array
+    pub array: Option<Vec<DexValue>>,
+}
+
+/// Instance of a functional interface created through LambdaMetafactory
+#[derive(Debug, Clone)]
+pub struct Lambda {
+    /// Name of the interface method the lambda implements
+    pub method_name: String,
+    /// Index into method_handles of the method the lambda forwards to
+    pub implementation: u32,
+    /// Dex file whose method_handles `implementation` indexes, see `DexClass.dex`
+    pub dex: usize,
+    /// Values captured at the call site, passed before the interface method's arguments
+    pub captured: Vec<DexValue>,
 }
 
 /// One method’s execution context
 pub struct Frame {
     pub registers: Vec<DexValue>,
     pub temp: Option<DexValue>,
-    /// Instead of borrowing a DexMethod, store an owned key to find it:
-    pub class_idx: usize,
-    pub method_name: String,
+    /// Descriptor of the class declaring the running method, the starting point of invoke-super
+    pub class_name: String,
+    /// The class itself, shared with the other frames running its methods
+    pub class: Arc<DexClass>,
+    /// Key of the running method in `DexClass.methods`
+    pub method_key: String,
     pub pc: usize, // program counter (index into instructions)
+    /// Exception caught by the handler currently executing, read by move-exception
+    pub exception: Option<DexValue>,
+}
+
+impl Frame {
+    /// Read a long/double from the register pair starting at `reg`
+    pub fn get_wide(&self, reg: usize) -> DexValue {
+        self.registers[reg].clone()
+    }
+
+    /// Store a long/double into the register pair `reg`, `reg + 1`
+    pub fn set_wide(&mut self, reg: usize, value: DexValue) {
+        self.registers[reg] = value;
+        if reg + 1 < self.registers.len() {
+            self.registers[reg + 1] = DexValue::WideHigh;
+        }
+    }
+
+    /// Copy a register pair, the source pair may overlap the destination pair
+    pub fn move_wide(&mut self, dst: usize, src: usize) {
+        let value = self.get_wide(src);
+        self.set_wide(dst, value);
+    }
+
+    pub fn binary_int(&mut self, dst: usize, first: usize, second: usize, op: impl Fn(i32, i32) -> i32) -> bool {
+        let (Some(a), Some(b)) = (self.registers[first].as_int(), self.registers[second].as_int()) else {
+            return false;
+        };
+        self.registers[dst] = DexValue::Int(op(a, b));
+        true
+    }
+
+    /// Same as `binary_int` with the second operand encoded in the instruction
+    pub fn binary_int_lit(&mut self, dst: usize, src: usize, literal: i32, op: impl Fn(i32, i32) -> i32) -> bool {
+        let Some(a) = self.registers[src].as_int() else {
+            return false;
+        };
+        self.registers[dst] = DexValue::Int(op(a, literal));
+        true
+    }
+
+    pub fn binary_long(&mut self, dst: usize, first: usize, second: usize, op: impl Fn(i64, i64) -> Option<i64>) -> bool {
+        let (Some(a), Some(b)) = (self.registers[first].as_long(), self.registers[second].as_long()) else {
+            return false;
+        };
+        match op(a, b) {
+            Some(result) => {
+                self.set_wide(dst, DexValue::Long(result));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Shifts take a long from a register pair and the shift distance from a single int register
+    pub fn shift_long(&mut self, dst: usize, first: usize, second: usize, op: impl Fn(i64, u32) -> i64) -> bool {
+        let (Some(a), Some(b)) = (self.registers[first].as_long(), self.registers[second].as_int()) else {
+            return false;
+        };
+        self.set_wide(dst, DexValue::Long(op(a, (b & 0x3f) as u32)));
+        true
+    }
+
+    /// Writes -1, 0 or 1 into `dst`. `nan_bias` is the result when the operands are unordered.
+    pub fn compare<T: PartialOrd>(&mut self, dst: usize, a: T, b: T, nan_bias: i32) {
+        let result = match a.partial_cmp(&b) {
+            Some(std::cmp::Ordering::Less) => -1,
+            Some(std::cmp::Ordering::Equal) => 0,
+            Some(std::cmp::Ordering::Greater) => 1,
+            None => nan_bias,
+        };
+        self.registers[dst] = DexValue::Int(result);
+    }
+
+    /// Address `offset` code units from the running instruction
+    fn address_from(&self, method: &DexMethod, offset: i32) -> Option<u32> {
+        // `pc` has already moved past the running instruction
+        method.address_of(self.pc.saturating_sub(1))?.checked_add_signed(offset)
+    }
+
+    /// Move to the instruction `offset` code units from the running one, false if no instruction
+    /// starts there
+    pub fn branch(&mut self, offset: i32) -> bool {
+        let Some(method) = self.class.methods.get(&self.method_key) else {
+            return false;
+        };
+        let target = self.address_from(method, offset).and_then(|address| method.pc_for_address(address));
+        match target {
+            Some(pc) => {
+                self.pc = pc;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The payload `offset` code units from the running switch or fill-array-data
+    pub fn payload(&self, offset: i32) -> Option<Payload> {
+        let method = self.class.methods.get(&self.method_key)?;
+        let address = self.address_from(method, offset)?;
+        method.payloads.iter()
+            .find(|(payload_address, _)| *payload_address == address)
+            .map(|(_, payload)| payload.clone())
+    }
+
+    pub fn binary_double(&mut self, dst: usize, first: usize, second: usize, op: impl Fn(f64, f64) -> f64) -> bool {
+        let (Some(a), Some(b)) = (self.registers[first].as_double(), self.registers[second].as_double()) else {
+            return false;
+        };
+        self.set_wide(dst, DexValue::Double(op(a, b)));
+        true
+    }
+
+    pub fn binary_float(&mut self, dst: usize, first: usize, second: usize, op: impl Fn(f32, f32) -> f32) -> bool {
+        let (Some(a), Some(b)) = (self.registers[first].as_float(), self.registers[second].as_float()) else {
+            return false;
+        };
+        self.registers[dst] = DexValue::Float(op(a, b));
+        true
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,13 +212,14 @@ pub enum Instruction {
     ArrayLength { dst: u8, array_ref_bearing_reg: u8 },
     NewInstance { dst: u8, type_idx: u16 },
     NewArray { dst: u8, size: u8, type_idx: u16 },
-    FilledNewArray { argc: u8, args: Vec<u8>, type_idx: u16 },
+    FilledNewArray { argc: u8, args: Vec<u16>, type_idx: u16 },
     FilledNewArrayRange { count: u8, type_idx: u16, first_arg_reg: u16 },
     FilledArrayData { array_ref: u8, signed_fake_branch_off: i32 },
 
     Throw { reg: u8 },
     Goto { signed_branch_off: i8 },
     Goto16 { signed_branch_off: i16 },
+    Goto32 { signed_branch_off: i32 },
 
     TestIfEqual { first_reg: u8, second_reg: u8, signed_branch_off: i16 },
     TestIfNotEqual { first_reg: u8, second_reg: u8, signed_branch_off: i16 },
@@ -257,23 +401,91 @@ pub enum Instruction {
     IntToChar { dst: u8, src: u8 },
     IntToShort { dst: u8, src: u8 },
     
-    InvokeVirtualRange { count: u8, type_idx: u16, first_arg_reg: u16 },
-    InvokeSuperRange { count: u8, type_idx: u16, first_arg_reg: u16 },
-    InvokeDirectRange { count: u8, type_idx: u16, first_arg_reg: u16 },
-    InvokeStaticRange { count: u8, type_idx: u16, first_arg_reg: u16 },
-    InvokeInterfaceRange { count: u8, type_idx: u16, first_arg_reg: u16 },
-
-    InvokeVirtual { argc: u8, args: Vec<u8>, method_idx: u16 },
-    InvokeSuper { argc: u8, args: Vec<u8>, method_idx: u16 },
-    InvokeDirect { argc: u8, args: Vec<u8>, method_idx: u16 },
-    InvokeStatic { argc: u8, args: Vec<u8>, method_idx: u16 },
-    InvokeInterface { argc: u8, args: Vec<u8>, method_idx: u16 },
+    InvokeVirtualRange { count: u8, method_idx: u16, first_arg_reg: u16 },
+    InvokeSuperRange { count: u8, method_idx: u16, first_arg_reg: u16 },
+    InvokeDirectRange { count: u8, method_idx: u16, first_arg_reg: u16 },
+    InvokeStaticRange { count: u8, method_idx: u16, first_arg_reg: u16 },
+    InvokeInterfaceRange { count: u8, method_idx: u16, first_arg_reg: u16 },
+
+    InvokeVirtual { argc: u8, args: Vec<u16>, method_idx: u16 },
+    InvokeSuper { argc: u8, args: Vec<u16>, method_idx: u16 },
+    InvokeDirect { argc: u8, args: Vec<u16>, method_idx: u16 },
+    InvokeStatic { argc: u8, args: Vec<u16>, method_idx: u16 },
+    InvokeInterface { argc: u8, args: Vec<u16>, method_idx: u16 },
     
+    InvokePolymorphic { argc: u8, args: Vec<u16>, method_idx: u16, proto_idx: u16 },
+    InvokePolymorphicRange { count: u8, method_idx: u16, first_arg_reg: u16, proto_idx: u16 },
+    InvokeCustom { argc: u8, args: Vec<u16>, call_site_idx: u16 },
     InvokeCustomRange { count: u8, call_site_ref: u16, first_arg_reg: u16 },
     ConstMethodHandle { dst: u8, method_handle_idx: u16 },
     ConstMethodType { dst: u8, method_proto_ref: u16 },
 }
 
+impl Instruction {
+    /// The regular form of an invoke-*/range instruction, with its consecutive registers listed out
+    pub fn expand_range(&self) -> Option<Instruction> {
+        let registers = |count: &u8, first_arg_reg: &u16| (0..*count as u16).map(|i| first_arg_reg + i).collect();
+
+        let expanded = match self {
+            Instruction::InvokeVirtualRange { count, method_idx, first_arg_reg } => Instruction::InvokeVirtual {
+                argc: *count,
+                args: registers(count, first_arg_reg),
+                method_idx: *method_idx,
+            },
+            Instruction::InvokeSuperRange { count, method_idx, first_arg_reg } => Instruction::InvokeSuper {
+                argc: *count,
+                args: registers(count, first_arg_reg),
+                method_idx: *method_idx,
+            },
+            Instruction::InvokeDirectRange { count, method_idx, first_arg_reg } => Instruction::InvokeDirect {
+                argc: *count,
+                args: registers(count, first_arg_reg),
+                method_idx: *method_idx,
+            },
+            Instruction::InvokeStaticRange { count, method_idx, first_arg_reg } => Instruction::InvokeStatic {
+                argc: *count,
+                args: registers(count, first_arg_reg),
+                method_idx: *method_idx,
+            },
+            Instruction::InvokeInterfaceRange { count, method_idx, first_arg_reg } => Instruction::InvokeInterface {
+                argc: *count,
+                args: registers(count, first_arg_reg),
+                method_idx: *method_idx,
+            },
+            Instruction::InvokePolymorphicRange { count, method_idx, first_arg_reg, proto_idx } => Instruction::InvokePolymorphic {
+                argc: *count,
+                args: registers(count, first_arg_reg),
+                method_idx: *method_idx,
+                proto_idx: *proto_idx,
+            },
+            Instruction::InvokeCustomRange { count, call_site_ref, first_arg_reg } => Instruction::InvokeCustom {
+                argc: *count,
+                args: registers(count, first_arg_reg),
+                call_site_idx: *call_site_ref,
+            },
+            Instruction::FilledNewArrayRange { count, type_idx, first_arg_reg } => Instruction::FilledNewArray {
+                argc: *count,
+                args: registers(count, first_arg_reg),
+                type_idx: *type_idx,
+            },
+            _ => return None,
+        };
+        Some(expanded)
+    }
+}
+
+/// Data of a switch or fill-array-data instruction. Payloads are pseudo-instructions inline in
+/// the code, only read through the instruction whose branch offset points at them.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum Payload {
+    /// Branch offsets of the consecutive keys starting at `first_key`
+    PackedSwitch { first_key: i32, targets: Vec<i32> },
+    /// Branch offsets of `keys`, which are sorted low to high
+    SparseSwitch { keys: Vec<i32>, targets: Vec<i32> },
+    /// Elements of `element_width` bytes each, little-endian
+    FillArrayData { element_width: u16, data: Vec<u8> },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum DexValue {
     Byte(i8),
@@ -296,16 +508,145 @@ pub enum DexValue {
     Annotation(Vec<(String, DexValue)>), // encoded_annotation as (name, value)
     Object(usize),
     KotlinObject(),
-    Void
+    Void,
+    /// Upper half of a long/double held in the register directly below
+    WideHigh,
 }
 
 impl DexValue {
+    /// Zero value of a field or array element with type `descriptor`
+    pub fn default_for(descriptor: &str) -> DexValue {
+        match descriptor {
+            "Z" => DexValue::Boolean(false),
+            "B" => DexValue::Byte(0),
+            "S" => DexValue::Short(0),
+            "C" => DexValue::Char(0),
+            "I" => DexValue::Int(0),
+            "J" => DexValue::Long(0),
+            "F" => DexValue::Float(0.0),
+            "D" => DexValue::Double(0.0),
+            _ => DexValue::Null,
+        }
+    }
+
     pub fn to_boolean(&self) -> Option<DexValue> {
         match self {
             DexValue::Int(v) => Some(DexValue::Boolean(*v != 0)),
             _ => None, // unsupported conversion
         }
     }
+
+    pub fn is_wide(&self) -> bool {
+        matches!(self, DexValue::Long(_) | DexValue::Double(_))
+    }
+
+    pub fn as_int(&self) -> Option<i32> {
+        match self {
+            DexValue::Int(v) => Some(*v),
+            DexValue::Byte(v) => Some(*v as i32),
+            DexValue::Short(v) => Some(*v as i32),
+            DexValue::Char(v) => Some(*v as i32),
+            DexValue::Boolean(v) => Some(*v as i32),
+            // Registers are untyped, a float might be read as its raw bits
+            DexValue::Float(v) => Some(v.to_bits() as i32),
+            _ => None,
+        }
+    }
+
+    pub fn as_long(&self) -> Option<i64> {
+        match self {
+            DexValue::Long(v) => Some(*v),
+            DexValue::Double(v) => Some(v.to_bits() as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(&self) -> Option<f32> {
+        match self {
+            DexValue::Float(v) => Some(*v),
+            // const stores floats as their raw bits
+            DexValue::Int(v) => Some(f32::from_bits(*v as u32)),
+            _ => None,
+        }
+    }
+
+    pub fn as_double(&self) -> Option<f64> {
+        match self {
+            DexValue::Double(v) => Some(*v),
+            // const-wide stores doubles as their raw bits
+            DexValue::Long(v) => Some(f64::from_bits(*v as u64)),
+            _ => None,
+        }
+    }
+}
+
+/// access_flags of a class, field or method. Some bits mean different things on fields and
+/// methods, e.g. 0x40 is VOLATILE on a field and BRIDGE on a method.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AccessFlags(u32);
+
+bitflags! {
+    impl AccessFlags: u32 {
+        const PUBLIC = 0x1;
+        const PRIVATE = 0x2;
+        const PROTECTED = 0x4;
+        const STATIC = 0x8;
+        const FINAL = 0x10;
+        const SYNCHRONIZED = 0x20;
+        const VOLATILE = 0x40;
+        const BRIDGE = 0x40;
+        const TRANSIENT = 0x80;
+        const VARARGS = 0x80;
+        const NATIVE = 0x100;
+        const INTERFACE = 0x200;
+        const ABSTRACT = 0x400;
+        const STRICT = 0x800;
+        const SYNTHETIC = 0x1000;
+        const ANNOTATION = 0x2000;
+        const ENUM = 0x4000;
+        const CONSTRUCTOR = 0x10000;
+        const DECLARED_SYNCHRONIZED = 0x20000;
+    }
+}
+
+/// Restriction of a member from the hiddenapi_class_data_item of dex 039 on. The low bits are the
+/// API list the member is on, the bits above the domains it is also API of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct HiddenApiFlags(pub u32);
+
+impl HiddenApiFlags {
+    const LIST_MASK: u32 = 0x7;
+    const CORE_PLATFORM_API: u32 = 0x8;
+    const TEST_API: u32 = 0x10;
+
+    /// Name of the API list, as hiddenapi calls them
+    pub fn list(self) -> &'static str {
+        match self.0 & Self::LIST_MASK {
+            0 => "sdk",
+            1 => "unsupported",
+            2 => "blocked",
+            3 => "max-target-o",
+            4 => "max-target-p",
+            5 => "max-target-q",
+            6 => "max-target-r",
+            _ => "max-target-s",
+        }
+    }
+
+    /// Whether apps may be denied access, everything but the public SDK
+    pub fn is_restricted(self) -> bool {
+        self.0 & Self::LIST_MASK != 0
+    }
+
+    pub fn is_core_platform_api(self) -> bool {
+        self.0 & Self::CORE_PLATFORM_API != 0
+    }
+
+    pub fn is_test_api(self) -> bool {
+        self.0 & Self::TEST_API != 0
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -313,6 +654,41 @@ pub struct DexField {
     pub ty: String,
     pub value: Option<DexValue>,
     pub is_static: bool,
+    /// Empty for classes cached before access flags were kept
+    #[serde(default)]
+    pub access_flags: AccessFlags,
+    #[serde(default)]
+    pub annotations: Vec<DexAnnotation>,
+    /// `None` unless the dex file has hiddenapi data
+    #[serde(default)]
+    pub hidden_api: Option<HiddenApiFlags>,
+}
+
+/// An annotation on a class, member or parameter, e.g. Mihon's `@Nsfw` on a source or
+/// kotlinx.serialization's `@SerialName` on a model's property
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DexAnnotation {
+    /// Type descriptor, e.g. `Leu/kanade/tachiyomi/annotations/Nsfw;`
+    pub ty: String,
+    /// 0 for build, 1 for runtime and 2 for system annotations the compiler adds, such as
+    /// `dalvik.annotation.Signature`
+    pub visibility: u8,
+    /// (name, value) of each element the annotation sets, defaults aren't included
+    pub elements: Vec<(String, DexValue)>,
+}
+
+impl DexAnnotation {
+    /// Value of the element `name`, `None` if the annotation leaves it at its default
+    pub fn value(&self, name: &str) -> Option<&DexValue> {
+        self.elements.iter().find(|(element, _)| element == name).map(|(_, value)| value)
+    }
+}
+
+impl DexField {
+    /// The field's annotation of type `ty`
+    pub fn annotation(&self, ty: &str) -> Option<&DexAnnotation> {
+        self.annotations.iter().find(|annotation| annotation.ty == ty)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -320,17 +696,160 @@ pub struct DexMethod {
     pub name: String,
     pub return_type: String,
     pub parameters: Vec<String>,
+    #[serde(default)]
+    pub access_flags: AccessFlags,
     pub registers: u16,
+    /// Number of registers taken by `this` and the arguments, they occupy the last registers of the frame
+    #[serde(default)]
+    pub ins_size: u16,
     pub instructions: Vec<Instruction>,
+    /// Address of each instruction in 16-bit code units, used to resolve branches and try blocks
+    #[serde(default)]
+    pub addresses: Vec<u32>,
+    /// Payloads of the switches and fill-array-datas by address, they are not in `instructions`
+    #[serde(default)]
+    pub payloads: Vec<(u32, Payload)>,
+    #[serde(default)]
+    pub tries: Vec<TryBlock>,
+    /// (address, line) of each position in the debug info, by address. Empty without debug info.
+    #[serde(default)]
+    pub lines: Vec<(u32, u32)>,
+    /// Named parameters and locals from the debug info
+    #[serde(default)]
+    pub locals: Vec<LocalVariable>,
+    /// File offset of the code_item, 0 for abstract and native methods. The code is only decoded
+    /// the first time the method runs, until then the fields above are empty.
+    #[serde(default)]
+    pub code_off: u32,
+    #[serde(default)]
+    pub annotations: Vec<DexAnnotation>,
+    /// Annotations of each parameter, empty when none of them has any
+    #[serde(default)]
+    pub parameter_annotations: Vec<Vec<DexAnnotation>>,
+    /// `None` unless the dex file has hiddenapi data
+    #[serde(default)]
+    pub hidden_api: Option<HiddenApiFlags>,
+}
+
+impl DexMethod {
+    /// Whether the method has code, decoded or not
+    pub fn has_code(&self) -> bool {
+        self.code_off != 0 || !self.instructions.is_empty()
+    }
+
+    /// Whether the method's code still has to be decoded, see `decode_method_code`
+    pub fn code_pending(&self) -> bool {
+        self.code_off != 0 && self.instructions.is_empty()
+    }
+
+    /// Descriptor of the method, e.g. `(ILjava/lang/String;)Lrx/Observable;`
+    pub fn descriptor(&self) -> String {
+        format!("({}){}", self.parameters.concat(), self.return_type)
+    }
+
+    pub fn key(&self) -> String {
+        method_key(&self.name, &self.descriptor())
+    }
+
+    /// The method's annotation of type `ty`
+    pub fn annotation(&self, ty: &str) -> Option<&DexAnnotation> {
+        self.annotations.iter().find(|annotation| annotation.ty == ty)
+    }
+
+    /// Source line of the instruction at `address`, from the last position at or before it
+    pub fn line_at(&self, address: u32) -> Option<u32> {
+        let index = self.lines.partition_point(|(start, _)| *start <= address);
+        index.checked_sub(1).map(|index| self.lines[index].1)
+    }
+
+    /// Human-readable signature, e.g. `fun searchManga(p0: Int, p1: String): MangasPage`
+    pub fn signature(&self) -> String {
+        render_signature(&self.name, &self.parameters, &[], &self.return_type)
+    }
+
+    /// Address (in 16-bit code units) of the instruction at `pc`
+    pub fn address_of(&self, pc: usize) -> Option<u32> {
+        self.addresses.get(pc).copied()
+    }
+
+    /// Index into `instructions` of the instruction starting at `address`
+    pub fn pc_for_address(&self, address: u32) -> Option<usize> {
+        self.addresses.binary_search(&address).ok()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DexClass {
     pub name: String,
     pub super_class: Option<String>,
+    #[serde(default)]
+    pub access_flags: AccessFlags,
+    /// Interfaces the class declares it implements, not the ones it inherits
+    #[serde(default)]
+    pub interfaces: Vec<String>,
     pub static_fields: HashMap<String, DexField>,
     pub instance_fields: HashMap<String, DexField>,
+    /// Keyed by `name:descriptor` so overloads don't replace each other
+    #[serde(deserialize_with = "deserialize_methods")]
     pub methods: HashMap<String, DexMethod>,
+    /// Name of the file the class was compiled from, e.g. `MangaSource.kt`
+    #[serde(default)]
+    pub source_file: Option<String>,
+    #[serde(default)]
+    pub annotations: Vec<DexAnnotation>,
+    /// Index of the dex file of a multi-dex extension the class comes from, 0 for
+    /// classes.dex. Its instructions index into that file's tables.
+    #[serde(default)]
+    pub dex: usize,
+    /// Install generation the class was cached with, 0 for caches older than generations
+    #[serde(default)]
+    pub generation: u64,
+}
+
+impl DexClass {
+    /// The class's annotation of type `ty`, e.g. `Leu/kanade/tachiyomi/annotations/Nsfw;`
+    pub fn annotation(&self, ty: &str) -> Option<&DexAnnotation> {
+        self.annotations.iter().find(|annotation| annotation.ty == ty)
+    }
+
+    /// Key in `methods` of `name`, which may already be a full key. A bare name picks the
+    /// overload taking `argc` arguments if given, the one with the fewest parameters otherwise.
+    pub fn resolve_method_key(&self, name: &str, argc: Option<usize>) -> Option<String> {
+        if self.methods.contains_key(name) {
+            return Some(name.to_string());
+        }
+
+        let mut overloads: Vec<(&String, &DexMethod)> = self.methods.iter()
+            .filter(|(_, method)| method.name == name)
+            .collect();
+        // Sorted so the choice doesn't depend on the map's order
+        overloads.sort_by_key(|(key, method)| (method.parameters.len(), *key));
+
+        overloads.iter()
+            .find(|(_, method)| argc.is_none_or(|argc| method.parameters.len() == argc))
+            .or(overloads.first())
+            .map(|(key, _)| key.to_string())
+    }
+}
+
+/// `name:descriptor`, the key of a method in `DexClass.methods`
+pub fn method_key(name: &str, descriptor: &str) -> String {
+    format!("{}:{}", name, descriptor)
+}
+
+/// Name part of a method key
+pub fn method_name_of(key: &str) -> &str {
+    key.split_once(':').map_or(key, |(name, _)| name)
+}
+
+/// Classes cached before methods were keyed by descriptor still use bare names, the keys are
+/// rebuilt from the methods themselves
+fn deserialize_methods<'de, D>(deserializer: D) -> Result<HashMap<String, DexMethod>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let methods: HashMap<String, DexMethod> = HashMap::deserialize(deserializer)?;
+    Ok(methods.into_values().map(|method| (method.key(), method)).collect())
 }
 
 
@@ -349,6 +868,10 @@ pub struct DexContainer {
     pub field_id_items: Vec<Field_Id_Item>,
     pub method_id_items: Vec<Method_Id_Item>,
     pub class_defs_items: Vec<Class_Def_Item>,
+    #[serde(default)]
+    pub method_handle_items: Vec<MethodHandleItem>,
+    #[serde(default)]
+    pub call_site_items: Vec<CallSiteItem>,
 }
 
 impl DexContainer {
@@ -370,6 +893,8 @@ impl DexContainer {
             field_id_items,
             method_id_items,
             class_defs_items,
+            method_handle_items: Vec::new(),
+            call_site_items: Vec::new(),
         }
     }
 
@@ -403,11 +928,14 @@ impl DexContainer {
             .and_then(|sid| self.string_offset(sid))
     }
 
-    /// Convenience: method_id -> string data offset
-    pub fn method_id_to_string_offset(&self, method_id: usize) -> Option<u32> {
-        self.string_offset(
-            self.method_id_items.get(method_id).unwrap().name_idx as usize
-        )
+    /// Safe lookup: method_handle index → method_handle_item, empty before dex 038.
+    pub fn method_handle(&self, handle_idx: usize) -> Option<&MethodHandleItem> {
+        self.method_handle_items.get(handle_idx)
+    }
+
+    /// Safe lookup: call_site_id → decoded call_site_item, empty before dex 038.
+    pub fn call_site(&self, call_site_idx: usize) -> Option<&CallSiteItem> {
+        self.call_site_items.get(call_site_idx)
     }
 
     /// Convenience: method_id -> class_id -> string data offset
@@ -428,19 +956,32 @@ pub struct Header_Item {
     pub link_off: u32,
     pub map_off: u32,
     pub string_ids_size: u32,
-    pub string_ids_off: u32, // Useless as we drain the bytes
+    pub string_ids_off: u32,
     pub type_ids_size: u32,
-    pub type_ids_off: u32, // Useless as we drain the bytes
+    pub type_ids_off: u32,
     pub proto_ids_size: u32,
-    pub proto_ids_off: u32, // Useless as we drain the bytes
+    pub proto_ids_off: u32,
     pub field_ids_size: u32,
-    pub field_ids_off: u32, // Useless as we drain the bytes
+    pub field_ids_off: u32,
     pub method_ids_size: u32,
-    pub method_ids_off: u32, // Useless as we drain the bytes
+    pub method_ids_off: u32,
     pub class_defs_size: u32,
-    pub class_defs_off: u32, // Useless as we drain the bytes
+    pub class_defs_off: u32,
     pub data_size: u32,
-    pub data_off: u32, // Useless as we drain the bytes
+    pub data_off: u32,
+    /// Size of the container holding the dex file, from version 041 on
+    #[serde(default)]
+    pub container_size: u32,
+    /// Offset of this header in the container, from version 041 on
+    #[serde(default)]
+    pub header_offset: u32,
+}
+
+impl Header_Item {
+    /// Byte order of the fixed-width fields of the dex file
+    pub fn endian(&self) -> Endian {
+        Endian::from_tag(self.endian_tag)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -450,6 +991,50 @@ pub struct Proto_Id_Item {
     pub parameters_off: u32 // Probably useless in Rust
 }
 
+/// method_handle_item, found through the map_list from dex 038 on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MethodHandleItem {
+    pub method_handle_type: u16,
+    pub field_or_method_id: u16, // Index into field_ids or method_ids, depending on the type
+}
+
+impl MethodHandleItem {
+    pub const STATIC_PUT: u16 = 0x00;
+    pub const STATIC_GET: u16 = 0x01;
+    pub const INSTANCE_PUT: u16 = 0x02;
+    pub const INSTANCE_GET: u16 = 0x03;
+    pub const INVOKE_STATIC: u16 = 0x04;
+    pub const INVOKE_INSTANCE: u16 = 0x05;
+    pub const INVOKE_CONSTRUCTOR: u16 = 0x06;
+    pub const INVOKE_DIRECT: u16 = 0x07;
+    pub const INVOKE_INTERFACE: u16 = 0x08;
+
+    /// Whether the handle reads or writes a field instead of invoking a method
+    pub fn is_field_accessor(&self) -> bool {
+        matches!(
+            self.method_handle_type,
+            Self::STATIC_PUT | Self::STATIC_GET | Self::INSTANCE_PUT | Self::INSTANCE_GET
+        )
+    }
+
+    /// Index into method_ids, `None` for field accessors whose id indexes field_ids
+    pub fn method_id(&self) -> Option<u16> {
+        (!self.is_field_accessor()).then_some(self.field_or_method_id)
+    }
+}
+
+/// call_site_item, the decoded encoded_array a call_site_id points to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallSiteItem {
+    /// Index into method_handles of the bootstrap method linking the call site
+    pub bootstrap_method_handle: u32,
+    pub method_name: String,
+    /// Descriptor of the call site, e.g. `(Ljava/lang/String;)Lkotlin/jvm/functions/Function0;`
+    pub method_type: String,
+    /// Extra arguments passed to the bootstrap method
+    pub arguments: Vec<DexValue>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Field_Id_Item {
     pub class_idx: u16,
@@ -494,7 +1079,53 @@ pub struct CodeItem {
     pub tries_size: u16,
     pub debug_info_off: u32,
     pub insns_size: u32,
-    pub insns: Vec<u8>,
+    pub insns: Vec<u16>,
     pub instructions: Vec<Instruction>,
+    pub addresses: Vec<u32>,
+    pub payloads: Vec<(u32, Payload)>,
     pub padding: Option<u16>,
+    pub tries: Vec<TryItem>,
+    pub handlers: Vec<CatchHandler>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TryItem {
+    // Start address of the covered block, in 16-bit code units
+    pub start_addr: u32,
+    // Number of 16-bit code units covered
+    pub insn_count: u16,
+    // Byte offset of the handler from the start of the encoded_catch_handler_list
+    pub handler_off: u16,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CatchHandler {
+    // Byte offset from the start of the encoded_catch_handler_list
+    pub offset: u16,
+    // (type_idx, handler address) pairs, checked in order
+    pub handlers: Vec<(u32, u32)>,
+    pub catch_all_addr: Option<u32>,
+}
+
+/// A try block with its handler resolved, as stored on a `DexMethod`
+/// A parameter or local variable the debug info names, and where its register holds it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LocalVariable {
+    pub register: u16,
+    pub name: String,
+    /// Type descriptor, the generic signature isn't kept
+    pub ty: String,
+    pub start_addr: u32,
+    /// Exclusive end address, in 16-bit code units
+    pub end_addr: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TryBlock {
+    pub start_addr: u32,
+    /// Exclusive end address, in 16-bit code units
+    pub end_addr: u32,
+    /// (exception type descriptor, handler address), checked in order
+    pub handlers: Vec<(String, u32)>,
+    pub catch_all_addr: Option<u32>,
 }
\ No newline at end of file
@@ -1,44 +1,86 @@
 use std::fs::{self, File};
 use std::io::{BufRead, BufReader, BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
+use crate::cache;
 use crate::types::{DexClass, DexContainer};
 
-pub fn save_class_to_file(class: &DexClass, path: &str) -> std::io::Result<()> {
-    let full_path = "out/".to_owned() + path.rsplit_once(";").expect("Couldnt remove ;").0;
+/// Descriptor of a class given as `com.example.Source` or `Lcom/example/Source;`
+pub fn class_descriptor(name: &str) -> String {
+    if name.starts_with('L') && name.ends_with(';') {
+        name.to_string()
+    } else {
+        format!("L{};", name.replace('.', "/"))
+    }
+}
+
+/// Holds the generation of the last completed install, written after everything else
+pub const GENERATION_FILE: &str = "generation";
+
+/// Generation of the extension cached in `dir`, 0 if nothing was installed with generations yet
+pub fn load_generation(dir: &Path) -> u64 {
+    fs::read_to_string(dir.join(GENERATION_FILE))
+        .ok()
+        .and_then(|generation| generation.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn save_generation(dir: &Path, generation: u64) -> std::io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join(GENERATION_FILE), generation.to_string())
+}
+
+/// File of the class `descriptor` in `dir`, e.g. `Lcom/example/Source;` is `dir/Lcom/example/Source`
+fn class_file(dir: &Path, descriptor: &str) -> PathBuf {
+    dir.join(descriptor.strip_suffix(';').unwrap_or(descriptor))
+}
+
+pub fn save_class_to_file(dir: &Path, class: &DexClass, path: &str) -> std::io::Result<()> {
+    let full_path = class_file(dir, path);
     let json_string = serde_json::to_string(class).expect("Failed to serialize to JSON");
-    let dirs = full_path.rsplit_once('/').expect("Filepath is corrupted.");
-    std::fs::create_dir_all(dirs.0).expect("Creating directories failed.");
-    return std::fs::write(full_path, json_string);
+    if let Some(parent) = full_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&full_path, json_string)?;
+    cache::record(&full_path);
+    Ok(())
 }
 
-pub fn save_container_to_file(container: &DexContainer, path: &str) -> std::io::Result<()> {
-    let full_path = "out/".to_owned() + path;
+pub fn save_container_to_file(dir: &Path, container: &DexContainer, path: &str) -> std::io::Result<()> {
+    let full_path = dir.join(path);
     let json_string = serde_json::to_string(container).expect("Failed to serialize to JSON");
-    return std::fs::write(full_path, json_string);
+    fs::create_dir_all(dir)?;
+    fs::write(&full_path, json_string)?;
+    cache::record(&full_path);
+    Ok(())
 }
 
-pub fn save_data_to_file(data: Vec<u8>, path: &str) -> std::io::Result<()> {
-    let full_path = "out/".to_owned() + path;
-    return std::fs::write(full_path, data);
+pub fn save_data_to_file(dir: &Path, data: &[u8], path: &str) -> std::io::Result<()> {
+    let full_path = dir.join(path);
+    fs::create_dir_all(dir)?;
+    fs::write(&full_path, data)?;
+    cache::record(&full_path);
+    Ok(())
 }
 
-pub fn class_file_to_class(path: &str) -> Option<DexClass> {
-    let full_path = "out/".to_owned() + path.rsplit_once(";").expect("Couldnt remove ;").0;
+pub fn class_file_to_class(dir: &Path, path: &str) -> Option<DexClass> {
+    let full_path = class_file(dir, path);
 
-    let file = File::open(full_path).expect("Opening file failed");
+    // The class might have been evicted from the cache, or its file be corrupted
+    let file = File::open(&full_path).ok()?;
+    cache::touch(&full_path);
     let reader = BufReader::new(file);
 
-    // Read the JSON contents of the file as an instance of `User`.
-    let class: DexClass = serde_json::from_reader(reader).expect("Parsing content to class failed.");
-    return Some(class)
+    let class: DexClass = serde_json::from_reader(reader).ok()?;
+    Some(class)
 }
 
-pub fn save_strings_to_file(strings: Vec<String>, path: &str) -> std::io::Result<()> {
+pub fn save_strings_to_file(strings: &[impl AsRef<str>], path: impl AsRef<Path>) -> std::io::Result<()> {
     let file = File::create(path)?;
     let mut writer = BufWriter::new(file);
     for s in strings {
-        writeln!(writer, "{}", s.replace("\n", "\\n"))?;
+        writeln!(writer, "{}", s.as_ref().replace("\n", "\\n"))?;
     }
     Ok(())
 }
@@ -51,44 +93,62 @@ fn load_classes_from_dir(dir: &Path, classes: &mut Vec<DexClass>) -> std::io::Re
         if path.is_dir() {
             // Recurse into subdirectory
             load_classes_from_dir(&path, classes)?;
-        } else if path.is_file() {
-            // Try to open and parse as DexClass JSON
-            if let Ok(file) = File::open(&path) {
-                let reader = BufReader::new(file);
-                if let Ok(class) = serde_json::from_reader::<_, DexClass>(reader) {
-                    classes.push(class);
-                }
-            }
+        } else if let Some(class) = read_class_file(&path) {
+            classes.push(class);
         }
     }
     Ok(())
 }
 
-pub fn load_classes_from_file() -> std::io::Result<Vec<DexClass>> {
+/// The class in the file at `path`, `None` if it isn't a class file
+fn read_class_file(path: &Path) -> Option<DexClass> {
+    let reader = BufReader::new(File::open(path).ok()?);
+    serde_json::from_reader(reader).ok()
+}
+
+/// Classes cached in `dir`. Only the class files and their directories are looked into, named
+/// after the first part of a descriptor like `Lcom`, as other caches sit next to them.
+pub fn load_classes_from_file(dir: &Path) -> std::io::Result<Vec<DexClass>> {
     let mut classes = Vec::new();
-    load_classes_from_dir(Path::new("out"), &mut classes)?;
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_class_path = path.file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('L') || name.starts_with('['));
+        if !is_class_path {
+            continue;
+        }
+        if path.is_dir() {
+            load_classes_from_dir(&path, &mut classes)?;
+        } else if let Some(class) = read_class_file(&path) {
+            classes.push(class);
+        }
+    }
     Ok(classes)
 }
 
-pub fn load_container_from_file(path: &str) -> std::io::Result<DexContainer> {
-    let full_path = "out/".to_owned() + path;
-    let file = File::open(full_path)?;
+pub fn load_container_from_file(dir: &Path, path: &str) -> std::io::Result<DexContainer> {
+    let full_path = dir.join(path);
+    let file = File::open(&full_path)?;
+    cache::touch(&full_path);
     let reader = BufReader::new(file);
-    let container = serde_json::from_reader(reader).expect("Failed to deserialize DexContainer JSON");
+    let container = serde_json::from_reader(reader).map_err(std::io::Error::other)?;
     Ok(container)
 }
 
-pub fn load_data_from_file(path: &str) -> std::io::Result<Vec<u8>> {
-    let full_path = "out/".to_owned() + path;
-    std::fs::read(full_path)
+pub fn load_data_from_file(dir: &Path, path: &str) -> std::io::Result<Vec<u8>> {
+    let full_path = dir.join(path);
+    let data = fs::read(&full_path)?;
+    cache::touch(&full_path);
+    Ok(data)
 }
 
-pub fn load_strings_from_file(path: &str) -> std::io::Result<Vec<String>> {
+pub fn load_strings_from_file(path: impl AsRef<Path>) -> std::io::Result<Vec<Arc<str>>> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
     let mut strings = Vec::new();
     for line in reader.lines() {
-        strings.push(line?);
+        strings.push(Arc::from(line?));
     }
     Ok(strings)
 }
@@ -97,54 +157,13 @@ pub fn convert_vec_u8_to_vec_u16(data: &mut Vec<u8>) -> Result<Vec<u16>, &'stati
     Ok(data.chunks_exact(2).map(|c| u16::from_le_bytes(c.try_into().unwrap())).collect())
 }
 
-pub fn convert_vec_u8_to_vec_u32(data: &mut Vec<u8>) -> Result<Vec<u32>, &'static str> {
-    if data.len() % 4 != 0 {
-        return Err("Input Vec<u8> length must be a multiple of 4 for u32 conversion.");
+/// Message of a panic payload caught with `catch_unwind`
+pub fn panic_message(error: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(message) = error.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = error.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_string()
     }
-    Ok(data.chunks_exact(4).map(|c| u32::from_le_bytes(c.try_into().unwrap())).collect())
-}
-
-pub fn parse_u16(data: &[u8], offset: usize) -> u16 {
-    u16::from_le_bytes([data[offset], data[offset + 1]])
-}
-
-pub fn parse_i16(data: &[u8], offset: usize) -> i16 {
-    i16::from_le_bytes([data[offset], data[offset + 1]])
 }
-
-pub fn parse_u32(data: &[u8], offset: usize) -> u32 {
-    let available = (data.len() - 1) - offset;
-    let mut slice: [u8; 4] = [0; 4];
-    for i in 0..4 {
-        if offset + i <= available {
-            slice[i] = data[offset + i];
-        } else {
-            slice[i] = 0x00000000
-        }
-    }
-
-    u32::from_le_bytes(slice)
-}
-
-pub fn parse_i32(data: &[u8], offset: usize) -> i32 {
-    let available = (data.len() - 1) - offset;
-    let mut slice: [u8; 4] = [0; 4];
-    for i in 0..4 {
-        if offset + i <= available {
-            slice[i] = data[offset + i];
-        } else {
-            slice[i] = 0x00000000
-        }
-    }
-    i32::from_le_bytes(slice)
-}
-
-pub fn parse_u64(data: &[u8], offset: usize) -> u64 {
-    u64::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3], data[offset + 4], data[offset + 5], data[offset + 6], data[offset + 7]])
-}
-
-pub fn get_lower_bits(value: u8, num_bits: u8) -> u8 {
-    let mask = (1 << num_bits) - 1; 
-
-    value & mask
-}
\ No newline at end of file
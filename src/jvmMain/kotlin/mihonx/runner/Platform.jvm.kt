@@ -5,9 +5,12 @@ actual fun platform(): String {
 }
 
 actual object RustBridge {
+    /** System property holding the install policy JSON, read when the library is loaded */
+    const val INSTALL_POLICY_PROPERTY = "mihonx.runner.installPolicy"
+
     init {
         System.loadLibrary("mihon_runner")
-        nativeInit()
+        nativeInit(System.getProperty(INSTALL_POLICY_PROPERTY))
     }
 
     actual fun callUserAgent(ctx: ExtensionContext): String {
@@ -22,6 +25,22 @@ actual object RustBridge {
         rustInstallExtension(bytes)
     }
 
+    actual fun installExtensionFile(path: String) {
+        rustInstallExtensionFile(path)
+    }
+
+    actual fun installMultiDexExtension(dexes: Array<ByteArray>) {
+        rustInstallMultiDexExtension(dexes)
+    }
+
+    actual fun installExtensions(batch: Array<ByteArray>): String {
+        return rustInstallExtensions(batch)
+    }
+
+    actual fun setClassFilter(packages: Array<String>) {
+        rustSetClassFilter(packages)
+    }
+
     actual fun getName(ctx: ExtensionContext): String {
         return rustExtensionGetName(ctx)
     }
@@ -32,21 +51,169 @@ actual object RustBridge {
         return "Fake data"
     }
 
+    actual fun callLongMethod(method_name: String): Long {
+        return rustExtensionCallLongMethod(method_name)
+    }
+
+    actual fun callDoubleMethod(method_name: String): Double {
+        return rustExtensionCallDoubleMethod(method_name)
+    }
+
     actual fun isUserAgentEqual(): Boolean {
         return rustExtensionIsUserAgentEqual()
     }
 
+    actual fun listMethods(): String {
+        return rustExtensionListMethods()
+    }
+
+    actual fun hiddenApiReport(): String {
+        return rustExtensionHiddenApiReport()
+    }
+
+    actual fun dependencyGraph(): String {
+        return rustExtensionDependencyGraph()
+    }
+
+    actual fun preloadClosure(className: String?): Int {
+        return rustExtensionPreloadClosure(className)
+    }
+
+    actual fun unsupportedReport(): String {
+        return rustExtensionUnsupportedReport()
+    }
+
+    actual fun missingShims(): String {
+        return rustExtensionMissingShims()
+    }
+
+    actual fun exportJson(): String {
+        return rustExtensionExportJson()
+    }
+
+    actual fun kotlinStubs(): String {
+        return rustExtensionKotlinStubs()
+    }
+
+    actual fun callMethodJson(method_name: String, argsJson: String): String {
+        return rustExtensionCallMethodJson(method_name, argsJson)
+    }
+
+    actual fun callMethodGraph(method_name: String, argsJson: String): String {
+        return rustExtensionCallMethodGraph(method_name, argsJson)
+    }
+
+    actual fun callMethodWithArgs(method_name: String, signature: String?, args: Array<Any?>): Any? {
+        return rustExtensionCallMethodWithArgs(method_name, signature, args)
+    }
+
+    actual fun fetchPages(method_name: String, startPage: Int, limit: Int): String {
+        return rustExtensionFetchPages(method_name, startPage, limit)
+    }
+
+    actual fun checkExtensionUpdates(index: String, installed: String, fingerprint: String?): String {
+        return rustCheckExtensionUpdates(index, installed, fingerprint)
+    }
+
+    actual fun runSelfTest(): String {
+        return rustRunSelfTest()
+    }
+
+    actual fun setLogSink(sink: String, path: String?, maxBytes: Long, maxFiles: Int) {
+        rustSetLogSink(sink, path, maxBytes, maxFiles)
+    }
+
+    actual fun setSourceDateContext(source: String?, locale: String, timeZone: String) {
+        rustSetSourceDateContext(source, locale, timeZone)
+    }
+
+    actual fun setSourceCapability(source: String?, capability: String, granted: Boolean) {
+        rustSetSourceCapability(source, capability, granted)
+    }
+
+    actual fun setMaxCallDepth(depth: Int) {
+        rustSetMaxCallDepth(depth)
+    }
+
+    actual fun setFixedClock(millis: Long) {
+        rustSetFixedClock(millis)
+    }
+
+    actual fun advanceClock(millis: Long) {
+        rustAdvanceClock(millis)
+    }
+
+    actual fun getCookies(source: String?, url: String?): String {
+        return rustGetCookies(source, url)
+    }
+
+    actual fun clearCookies(source: String?, url: String?): Int {
+        return rustClearCookies(source, url)
+    }
+
+    actual fun setMaxCacheSize(bytes: Long) {
+        rustSetMaxCacheSize(bytes)
+    }
+
+    actual fun getCacheUsage(): String {
+        return rustGetCacheUsage()
+    }
+
+    actual fun trimCache(targetBytes: Long): Long {
+        return rustTrimCache(targetBytes)
+    }
+
+    actual fun shutdown(timeoutMillis: Long): Boolean {
+        return nativeShutdown(timeoutMillis)
+    }
+
     @JvmStatic
     fun logFromRust(message: String) {
         println("Rust logged: $message")
     }
 
-    external fun nativeInit()
+    external fun nativeInit(installPolicy: String?)
+    external fun nativeShutdown(timeoutMillis: Long): Boolean
     external fun rustUseExtensionContext(ctx: ExtensionContext): String
     external fun rustInstallExtension(bytes: ByteArray)
+    external fun rustInstallExtensionFile(path: String)
+    external fun rustInstallMultiDexExtension(dexes: Array<ByteArray>)
+    external fun rustInstallExtensions(batch: Array<ByteArray>): String
+    external fun rustSetClassFilter(packages: Array<String>)
     external fun rustExtensionGetName(ctx: ExtensionContext): String
     external fun rustExtensionCallMethod(method_name: String)
+    external fun rustExtensionCallLongMethod(method_name: String): Long
+    external fun rustExtensionCallDoubleMethod(method_name: String): Double
     external fun rustExtensionIsUserAgentEqual(): Boolean
+    external fun rustExtensionListMethods(): String
+    external fun rustExtensionHiddenApiReport(): String
+    external fun rustExtensionDependencyGraph(): String
+    external fun rustExtensionPreloadClosure(className: String?): Int
+    external fun rustExtensionUnsupportedReport(): String
+    external fun rustExtensionMissingShims(): String
+    external fun rustExtensionExportJson(): String
+    external fun rustExtensionKotlinStubs(): String
+    external fun rustExtensionCallMethodJson(method_name: String, argsJson: String): String
+    external fun rustExtensionCallMethodGraph(method_name: String, argsJson: String): String
+    external fun rustExtensionCallMethodWithArgs(method_name: String, signature: String?, args: Array<Any?>): Any?
+    external fun rustExtensionFetchPages(method_name: String, startPage: Int, limit: Int): String
 
     external fun rustGetDexVersion(): String
+
+    external fun rustCheckExtensionUpdates(index: String, installed: String, fingerprint: String?): String
+
+    external fun rustRunSelfTest(): String
+
+    external fun rustSetLogSink(sink: String, path: String?, maxBytes: Long, maxFiles: Int)
+    external fun rustSetSourceDateContext(source: String?, locale: String, timeZone: String)
+    external fun rustSetSourceCapability(source: String?, capability: String, granted: Boolean)
+    external fun rustSetMaxCallDepth(depth: Int)
+    external fun rustSetFixedClock(millis: Long)
+    external fun rustAdvanceClock(millis: Long)
+    external fun rustGetCookies(source: String?, url: String?): String
+    external fun rustClearCookies(source: String?, url: String?): Int
+
+    external fun rustSetMaxCacheSize(bytes: Long)
+    external fun rustGetCacheUsage(): String
+    external fun rustTrimCache(targetBytes: Long): Long
 }
\ No newline at end of file